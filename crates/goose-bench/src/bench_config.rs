@@ -34,6 +34,8 @@ pub struct BenchRunConfig {
     pub eval_result_filename: String,
     pub run_summary_filename: String,
     pub env_file: Option<PathBuf>,
+    #[serde(default)]
+    pub workers: Option<usize>,
 }
 
 impl Default for BenchRunConfig {
@@ -68,6 +70,7 @@ impl Default for BenchRunConfig {
             eval_result_filename: "eval-results.json".to_string(),
             run_summary_filename: "run-results-summary.json".to_string(),
             env_file: None,
+            workers: None,
         }
     }
 }
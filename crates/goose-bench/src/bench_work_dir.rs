@@ -53,7 +53,8 @@ impl BenchmarkWorkDir {
         }
     }
 
-    pub fn init_experiment(output_dir: PathBuf) -> anyhow::Result<()> {
+    /// Create a new benchmark directory under `output_dir` and cd into it, returning its path
+    pub fn init_experiment(output_dir: PathBuf) -> anyhow::Result<PathBuf> {
         if !output_dir.is_absolute() {
             anyhow::bail!(
                 "Internal Error: init_experiment received a non-absolute path: {}",
@@ -79,7 +80,7 @@ impl BenchmarkWorkDir {
                 base_path.display()
             )
         })?;
-        Ok(())
+        Ok(base_path)
     }
 
     pub fn canonical_dirs(include_dirs: Vec<PathBuf>) -> Vec<PathBuf> {
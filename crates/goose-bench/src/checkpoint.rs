@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing;
+
+/// Append-only log of finished cases in the benchmark directory, read by `--resume`
+pub const CHECKPOINT_FILENAME: &str = "bench-checkpoint.jsonl";
+
+/// One evaluation of one model in one repeat, recorded once its results are written
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CaseCheckpoint {
+    pub provider: String,
+    pub model: String,
+    pub run_id: String,
+    pub selector: String,
+    /// Results file, relative to the benchmark directory
+    pub result_path: PathBuf,
+    pub completed_at: String,
+}
+
+impl CaseCheckpoint {
+    pub fn new(
+        provider: String,
+        model: String,
+        run_id: String,
+        selector: String,
+        result_path: PathBuf,
+    ) -> Self {
+        Self {
+            provider,
+            model,
+            run_id,
+            selector,
+            result_path,
+            completed_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+
+    pub fn key(&self) -> String {
+        Self::case_key(&self.provider, &self.model, &self.run_id, &self.selector)
+    }
+
+    pub fn case_key(provider: &str, model: &str, run_id: &str, selector: &str) -> String {
+        format!("{}/{}/run-{}/{}", provider, model, run_id, selector)
+    }
+
+    /// Append this checkpoint to the log in `bench_dir`, in one write so workers don't interleave
+    pub fn record(&self, bench_dir: &Path) -> Result<()> {
+        let mut checkpoint = self.clone();
+        if let Ok(relative) = checkpoint.result_path.strip_prefix(bench_dir) {
+            checkpoint.result_path = relative.to_path_buf();
+        }
+        let mut line = serde_json::to_string(&checkpoint)?;
+        line.push('\n');
+
+        let path = bench_dir.join(CHECKPOINT_FILENAME);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open checkpoint log {}", path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write checkpoint log {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Keys of the cases in `bench_dir` that finished and still have their results file
+pub fn completed_cases(bench_dir: &Path) -> HashSet<String> {
+    let path = bench_dir.join(CHECKPOINT_FILENAME);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return HashSet::new();
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<CaseCheckpoint>(line) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                // truncated last line of a killed run, that case runs again
+                tracing::warn!(
+                    "Skipping unreadable checkpoint in {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        })
+        .filter(|checkpoint| bench_dir.join(&checkpoint.result_path).exists())
+        .map(|checkpoint| checkpoint.key())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completed_cases() {
+        let bench_dir =
+            std::env::temp_dir().join(format!("goose-bench-checkpoint-{}", std::process::id()));
+        fs::create_dir_all(&bench_dir).unwrap();
+        let case = |selector: &str| {
+            CaseCheckpoint::new(
+                "openai".to_string(),
+                "gpt-4o".to_string(),
+                "0".to_string(),
+                selector.to_string(),
+                bench_dir.join(format!("{}.json", selector.replace(':', "-"))),
+            )
+        };
+        let finished = case("core:developer");
+        fs::write(&finished.result_path, "{}").unwrap();
+        finished.record(&bench_dir).unwrap();
+        // Recorded, but its results file is gone since
+        case("core:memory").record(&bench_dir).unwrap();
+        // The last line of a run killed while writing it
+        let log_path = bench_dir.join(CHECKPOINT_FILENAME);
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(br#"{"provider": "openai", "mod"#).unwrap();
+
+        assert_eq!(completed_cases(&bench_dir), HashSet::from([finished.key()]));
+        // Results are logged relative to the benchmark directory, so a moved run resumes
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(
+            log.contains(r#""result_path":"core-developer.json""#),
+            "{}",
+            log
+        );
+
+        fs::remove_dir_all(&bench_dir).unwrap();
+    }
+}
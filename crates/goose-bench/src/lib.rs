@@ -1,6 +1,7 @@
 pub mod bench_config;
 pub mod bench_session;
 pub mod bench_work_dir;
+pub mod checkpoint;
 pub mod error_capture;
 pub mod eval_suites;
 pub mod reporting;
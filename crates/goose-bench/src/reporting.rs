@@ -27,6 +27,30 @@ pub struct BenchmarkResults {
     pub suites: Vec<SuiteResult>,
 }
 
+/// Filename of the report merging every repeat of a model's run
+pub const MERGED_REPORT_FILENAME: &str = "merged-results-summary.json";
+
+/// All repeats of a model's benchmark, merged once every worker has finished
+#[derive(Default, Deserialize, Serialize)]
+pub struct MergedBenchmarkReport {
+    pub provider: String,
+    pub model: String,
+    pub runs: Vec<BenchmarkResults>,
+    /// Cases without results, as `run-{id}/{selector}`
+    pub missing: Vec<String>,
+}
+
+impl MergedBenchmarkReport {
+    pub fn new(provider: String, model: String) -> Self {
+        Self {
+            provider,
+            model,
+            runs: Vec::new(),
+            missing: Vec::new(),
+        }
+    }
+}
+
 impl EvaluationResult {
     pub fn new(name: String) -> Self {
         Self {
@@ -31,12 +31,65 @@ impl BenchRunner {
             )?,
         };
 
-        BenchmarkWorkDir::init_experiment(resolved_output_dir)?;
+        let bench_dir = BenchmarkWorkDir::init_experiment(resolved_output_dir)?;
+        if let Some(run_id) = bench_dir.file_name() {
+            println!(
+                "Benchmark run {} (continue it with `goose bench run --resume {}` if interrupted)",
+                run_id.to_string_lossy(),
+                run_id.to_string_lossy()
+            );
+        }
 
         config.save("config.cfg".to_string());
         Ok(BenchRunner { config })
     }
 
+    /// Continue the interrupted run in benchmark directory `run_id`, with its saved configuration
+    pub fn resume(run_id: &str, config_path: Option<PathBuf>) -> anyhow::Result<BenchRunner> {
+        let output_dir = match config_path {
+            Some(path) => BenchRunConfig::from(path)?.output_dir,
+            None => None,
+        };
+        let output_dir = match output_dir {
+            Some(dir) => dir,
+            None => std::env::current_dir().context("Failed to get current working directory")?,
+        };
+
+        let candidate = PathBuf::from(run_id);
+        let bench_dir = if candidate.is_dir() {
+            candidate
+        } else {
+            output_dir.join(run_id)
+        };
+        let saved_config = bench_dir.join("config.cfg");
+        if !saved_config.exists() {
+            anyhow::bail!(
+                "No benchmark run '{}' found: {} does not exist",
+                run_id,
+                saved_config.display()
+            );
+        }
+
+        std::env::set_current_dir(&bench_dir).with_context(|| {
+            format!(
+                "Failed to change working directory to: {}",
+                bench_dir.display()
+            )
+        })?;
+        let config = BenchRunConfig::from(saved_config)?;
+        println!("Resuming benchmark run {}", bench_dir.display());
+        Ok(BenchRunner { config })
+    }
+
+    /// Shard parallel-safe evaluations of each model across `workers` processes
+    pub fn with_workers(mut self, workers: Option<usize>) -> Self {
+        if workers.is_some() {
+            self.config.workers = workers;
+            self.config.save("config.cfg".to_string());
+        }
+        self
+    }
+
     pub fn from(config: String) -> anyhow::Result<BenchRunner> {
         let config = BenchRunConfig::from_string(config)?;
         Ok(BenchRunner { config })
@@ -1,6 +1,7 @@
 use crate::bench_config::{BenchEval, BenchModel, BenchRunConfig};
 use crate::bench_session::BenchAgent;
 use crate::bench_work_dir::BenchmarkWorkDir;
+use crate::checkpoint::CaseCheckpoint;
 use crate::eval_suites::{EvaluationSuite, ExtensionRequirements};
 use crate::reporting::EvaluationResult;
 use crate::utilities::await_process_exits;
@@ -78,6 +79,25 @@ impl EvalRunner {
             .unwrap_or_else(|| "run-0".to_string());
         let run_id = format!("run-{}", run_id.clone());
 
+        // clear what an interrupted attempt at this case left behind
+        let eval_dir = work_dir.base_path.join(&run_id).join(
+            bench_eval
+                .selector
+                .replace(":", std::path::MAIN_SEPARATOR_STR),
+        );
+        if eval_dir.exists() {
+            tracing::info!(
+                "Clearing partial results of interrupted evaluation in {}",
+                eval_dir.display()
+            );
+            fs::remove_dir_all(&eval_dir).with_context(|| {
+                format!(
+                    "Failed to clear evaluation directory {}",
+                    eval_dir.display()
+                )
+            })?;
+        }
+
         // create entire dir subtree for eval and cd into dir for running eval
         work_dir.set_eval(&bench_eval.selector, run_id);
         tracing::info!("Set evaluation directory for {}", bench_eval.selector);
@@ -165,6 +185,24 @@ impl EvalRunner {
             fs::write(here.join("session.json"), session_json)
                 .context("Failed to write session JSON to evaluation directory")?;
 
+            let model = self
+                .config
+                .models
+                .first()
+                .context("No model specified in configuration")?;
+            CaseCheckpoint::new(
+                model.provider.clone(),
+                model.name.clone(),
+                self.config
+                    .run_id
+                    .clone()
+                    .unwrap_or_else(|| "0".to_string()),
+                bench_eval.selector.clone(),
+                eval_results_file.clone(),
+            )
+            .record(&work_dir.run_dir)
+            .context("Failed to record evaluation checkpoint")?;
+
             tracing::info!("Evaluation completed successfully");
         } else {
             tracing::error!("No evaluation found for selector: {}", bench_eval.selector);
@@ -1,14 +1,17 @@
 use crate::bench_config::{BenchEval, BenchModel, BenchRunConfig};
+use crate::checkpoint::{self, CaseCheckpoint};
 use crate::eval_suites::EvaluationSuite;
-use crate::reporting::{BenchmarkResults, SuiteResult};
+use crate::reporting::{
+    BenchmarkResults, EvaluationResult, MergedBenchmarkReport, SuiteResult, MERGED_REPORT_FILENAME,
+};
 use crate::runners::eval_runner::EvalRunner;
 use crate::utilities::{await_process_exits, parallel_bench_cmd};
 use anyhow::{Context, Result};
 use dotenvy::from_path_iter;
 use std::collections::HashMap;
+use std::env;
 use std::fs::read_to_string;
 use std::path::PathBuf;
-use std::process::Child;
 use std::thread;
 use tracing;
 
@@ -31,41 +34,85 @@ impl ModelRunner {
             .first()
             .context("No model specified in config")?;
         let suites = self.collect_evals_for_run();
+        let repeat = self.config.repeat.unwrap_or(1);
+
+        // skip cases a previous, interrupted attempt at this run already finished
+        let bench_dir = env::current_dir().context("Failed to get benchmark directory")?;
+        let completed = checkpoint::completed_cases(&bench_dir);
+
+        let mut parallel_cases = Vec::new();
+        let mut sequential_cases = Vec::new();
+        let mut skipped = 0;
+        for i in 0..repeat {
+            let run_id = i.to_string();
+            for evals in suites.values() {
+                for eval in evals {
+                    let key = CaseCheckpoint::case_key(
+                        &model.provider,
+                        &model.name,
+                        &run_id,
+                        &eval.selector,
+                    );
+                    if completed.contains(&key) {
+                        skipped += 1;
+                        continue;
+                    }
+                    // Only run in parallel if the model is parallel_safe
+                    if eval.parallel_safe && model.parallel_safe {
+                        parallel_cases.push((run_id.clone(), eval.clone()));
+                    } else {
+                        sequential_cases.push((run_id.clone(), eval.clone()));
+                    }
+                }
+            }
+        }
+        if skipped > 0 {
+            tracing::info!(
+                "Resuming {}: {} cases already complete, {} left",
+                model.name,
+                skipped,
+                parallel_cases.len() + sequential_cases.len()
+            );
+        }
 
-        let mut handles = vec![];
+        let envs = self.case_envs(model)?;
 
-        for i in 0..self.config.repeat.unwrap_or(1) {
-            let self_copy = self.clone();
-            let model_clone = model.clone();
-            let suites_clone = suites.clone();
-            let handle = thread::spawn(move || -> Result<()> {
-                self_copy.run_benchmark(&model_clone, suites_clone, i.to_string())
-            });
-            handles.push(handle);
+        // shard parallel-safe cases round-robin across the workers
+        let workers = self
+            .config
+            .workers
+            .unwrap_or(parallel_cases.len())
+            .clamp(1, parallel_cases.len().max(1));
+        let mut shards = vec![Vec::new(); workers];
+        for (i, case) in parallel_cases.into_iter().enumerate() {
+            shards[i % workers].push(case);
         }
+
+        let handles = shards
+            .into_iter()
+            .filter(|shard| !shard.is_empty())
+            .map(|shard| {
+                let self_copy = self.clone();
+                let envs = envs.clone();
+                thread::spawn(move || -> Result<()> { self_copy.run_cases(shard, &envs) })
+            })
+            .collect();
+
+        // Run non-parallel-safe evaluations one at a time alongside the workers
+        self.run_cases(sequential_cases, &envs)?;
         await_process_exits(&mut Vec::new(), handles);
 
-        let mut all_runs_results: Vec<BenchmarkResults> = Vec::new();
-        for i in 0..self.config.repeat.unwrap_or(1) {
-            match self.collect_run_results(model.clone(), suites.clone(), i.to_string()) {
-                Ok(run_results) => all_runs_results.push(run_results),
-                Err(e) => {
-                    tracing::error!("Failed to collect results for run {}: {}", i, e)
-                }
+        for i in 0..repeat {
+            if let Err(e) = self.collect_run_results(model.clone(), suites.clone(), i.to_string()) {
+                tracing::error!("Failed to collect results for run {}: {}", i, e)
             }
         }
+        self.write_merged_report(model, &suites, repeat)?;
 
         Ok(())
     }
 
-    fn run_benchmark(
-        &self,
-        model: &BenchModel,
-        suites: HashMap<String, Vec<BenchEval>>,
-        run_id: String,
-    ) -> Result<()> {
-        let mut results_handles = HashMap::<String, Vec<Child>>::new();
-
+    fn case_envs(&self, model: &BenchModel) -> Result<Vec<(String, String)>> {
         // Load environment variables from file if specified
         let mut envs = self.toolshim_envs();
         if let Some(env_file) = &self.config.env_file {
@@ -77,62 +124,83 @@ impl ModelRunner {
         }
         envs.push(("GOOSE_MODEL".to_string(), model.clone().name));
         envs.push(("GOOSE_PROVIDER".to_string(), model.clone().provider));
+        Ok(envs)
+    }
 
-        // Only run in parallel if the model is parallel_safe
-        let run_parallel = model.parallel_safe;
-
-        for (suite, evals) in suites.iter() {
-            results_handles.insert((*suite).clone(), Vec::new());
-
-            // Group evaluations by parallel_safe
-            let mut parallel_evals = Vec::new();
-            let mut sequential_evals = Vec::new();
+    /// Run each case in its own `exec-eval` process, one after another
+    fn run_cases(&self, cases: Vec<(String, BenchEval)>, envs: &[(String, String)]) -> Result<()> {
+        for (run_id, eval) in cases {
+            let mut config_copy = self.config.clone();
+            config_copy.run_id = Some(run_id);
+            config_copy.evals = vec![eval];
+            let cfg = config_copy
+                .to_string()
+                .context("Failed to serialize configuration")?;
+
+            let handle = parallel_bench_cmd("exec-eval".to_string(), cfg, envs.to_vec());
+            await_process_exits(&mut [handle], Vec::new());
+        }
+        Ok(())
+    }
 
-            for eval in evals {
-                if eval.parallel_safe && run_parallel {
-                    parallel_evals.push(eval);
-                } else {
-                    sequential_evals.push(eval);
-                }
-            }
+    fn load_eval_result(
+        &self,
+        model: &BenchModel,
+        eval: &BenchEval,
+        run_id: String,
+    ) -> Result<(EvaluationResult, PathBuf)> {
+        let mut eval_path = EvalRunner::path_for_eval(model, eval, run_id);
+        eval_path.push(self.config.eval_result_filename.clone());
+
+        let content = read_to_string(&eval_path).with_context(|| {
+            format!(
+                "Failed to read evaluation results from {}",
+                eval_path.display()
+            )
+        })?;
+
+        let eval_result =
+            serde_json::from_str(&content).context("Failed to parse evaluation results JSON")?;
+        Ok((eval_result, eval_path))
+    }
 
-            // Run parallel-safe evaluations in parallel
-            if !parallel_evals.is_empty() {
-                for eval_selector in &parallel_evals {
-                    let mut config_copy = self.config.clone();
-                    config_copy.run_id = Some(run_id.clone());
-                    config_copy.evals = vec![(*eval_selector).clone()];
-                    let cfg = config_copy
-                        .to_string()
-                        .context("Failed to serialize configuration")?;
-
-                    let handle = parallel_bench_cmd("exec-eval".to_string(), cfg, envs.clone());
-                    results_handles.get_mut(suite).unwrap().push(handle);
+    /// Merge every repeat into one report, listing the cases without results
+    fn write_merged_report(
+        &self,
+        model: &BenchModel,
+        suites: &HashMap<String, Vec<BenchEval>>,
+        repeat: usize,
+    ) -> Result<()> {
+        let mut report = MergedBenchmarkReport::new(model.provider.clone(), model.name.clone());
+        for i in 0..repeat {
+            let mut results = BenchmarkResults::new(model.provider.clone());
+            for (suite, evals) in suites.iter() {
+                let mut suite_result = SuiteResult::new(suite.clone());
+                for eval in evals {
+                    match self.load_eval_result(model, eval, i.to_string()) {
+                        Ok((eval_result, _)) => suite_result.add_evaluation(eval_result),
+                        Err(_) => report.missing.push(format!("run-{}/{}", i, eval.selector)),
+                    }
                 }
+                results.add_suite(suite_result);
             }
-
-            // Run non-parallel-safe evaluations sequentially
-            for eval_selector in &sequential_evals {
-                let mut config_copy = self.config.clone();
-                config_copy.run_id = Some(run_id.clone());
-                config_copy.evals = vec![(*eval_selector).clone()];
-                let cfg = config_copy
-                    .to_string()
-                    .context("Failed to serialize configuration")?;
-
-                let handle = parallel_bench_cmd("exec-eval".to_string(), cfg, envs.clone());
-
-                // Wait for this process to complete before starting the next one
-                let mut child_procs = vec![handle];
-                await_process_exits(&mut child_procs, Vec::new());
-            }
+            report.runs.push(results);
         }
 
-        // Wait for any remaining parallel processes to complete
-        for (_, child_procs) in results_handles.iter_mut() {
-            await_process_exits(child_procs, Vec::new());
+        let report_path = PathBuf::from(format!("{}-{}", model.provider, model.name))
+            .join(MERGED_REPORT_FILENAME);
+        let output_str = serde_json::to_string_pretty(&report)
+            .context("Failed to serialize merged benchmark report to JSON")?;
+        std::fs::write(&report_path, &output_str).with_context(|| {
+            format!("Failed to write merged report to {}", report_path.display())
+        })?;
+        if !report.missing.is_empty() {
+            tracing::warn!(
+                "{} cases have no results, see {}",
+                report.missing.len(),
+                report_path.display()
+            );
         }
-
         Ok(())
     }
 
@@ -149,19 +217,8 @@ impl ModelRunner {
         for (suite, evals) in suites.iter() {
             let mut suite_result = SuiteResult::new(suite.clone());
             for eval_selector in evals {
-                let mut eval_path =
-                    EvalRunner::path_for_eval(&model, eval_selector, run_id.clone());
-                eval_path.push(self.config.eval_result_filename.clone());
-
-                let content = read_to_string(&eval_path).with_context(|| {
-                    format!(
-                        "Failed to read evaluation results from {}",
-                        eval_path.display()
-                    )
-                })?;
-
-                let eval_result = serde_json::from_str(&content)
-                    .context("Failed to parse evaluation results JSON")?;
+                let (eval_result, eval_path) =
+                    self.load_eval_result(&model, eval_selector, run_id.clone())?;
 
                 suite_result.add_evaluation(eval_result);
 
@@ -1,21 +1,37 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
 
 use goose::config::{Config, ExtensionConfig};
 
 use crate::commands::acp::run_acp_agent;
+use crate::commands::auth::{
+    handle_auth_export, handle_auth_import, handle_auth_list, handle_auth_login, handle_auth_switch,
+};
 use crate::commands::bench::agent_generator;
+use crate::commands::config::{
+    handle_prompt_add_section, handle_prompt_list_sections, handle_prompt_remove_section,
+    handle_prompt_show,
+};
 use crate::commands::configure::handle_configure;
+use crate::commands::extension::handle_extension_test;
 use crate::commands::info::handle_info;
+use crate::commands::onboarding::handle_onboarding;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
-use crate::commands::recipe::{handle_deeplink, handle_list, handle_validate};
+use crate::commands::recipe::{handle_deeplink, handle_examples, handle_list, handle_validate};
+use crate::commands::redact::{handle_session_redact, RedactionOptions};
+use crate::commands::run::handle_run_diff;
+use crate::commands::runs::{handle_runs_list, handle_runs_show};
 // Import the new handlers from commands::schedule
 use crate::commands::schedule::{
     handle_schedule_add, handle_schedule_cron_help, handle_schedule_list, handle_schedule_remove,
     handle_schedule_run_now, handle_schedule_services_status, handle_schedule_services_stop,
     handle_schedule_sessions,
 };
-use crate::commands::session::{handle_session_list, handle_session_remove};
+use crate::commands::session::{
+    handle_session_compress, handle_session_gc, handle_session_list, handle_session_remove,
+    handle_session_tasks,
+};
+use crate::exit_code::{ExitCode, RunFailed, EXIT_CODES_HELP};
 use crate::recipes::extract_from_cli::extract_recipe_info_from_cli;
 use crate::recipes::recipe::{explain_recipe, render_recipe_as_yaml};
 use crate::session::{build_session, SessionBuilderConfig, SessionSettings};
@@ -33,6 +49,29 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+
+    /// Skip the guided first-run setup
+    #[arg(
+        long = "no-onboarding",
+        help = "Skip the guided setup on first run and only configure a provider"
+    )]
+    no_onboarding: bool,
+
+    /// Only use local models and tools
+    #[arg(
+        long = "offline",
+        global = true,
+        help = "Work without the network: only local providers and extensions, and network actions fail with an error"
+    )]
+    offline: bool,
+
+    /// Paste OAuth codes instead of receiving them on localhost
+    #[arg(
+        long = "oauth-manual",
+        global = true,
+        help = "Sign in to OAuth services by pasting the authorization code or redirect URL into the terminal, for when the browser can't reach localhost"
+    )]
+    oauth_manual: bool,
 }
 
 #[derive(Args, Debug)]
@@ -135,6 +174,54 @@ enum SessionCommand {
         #[arg(short, long, help = "Regex for removing matched sessions (optional)")]
         regex: Option<String>,
     },
+    #[command(
+        about = "Remove unreferenced attachments and tool output, and old task logs, runs and cached results",
+        long_about = "Large attachments and tool output are stored once, named by their content, and shared by every session that uses them. This removes the ones whose sessions have all been deleted. Blobs written in the last hour are kept. It also removes the logs and resumable runs of subagent tasks nothing was written to in the last 7 days, and the cached task results older than GOOSE_TASK_CACHE_TTL (a day unless set)."
+    )]
+    Gc {
+        #[arg(
+            long = "dry-run",
+            help = "Report what would be removed without deleting"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            long = "clear-task-cache",
+            help = "Remove every cached task result, not only the expired ones"
+        )]
+        clear_task_cache: bool,
+    },
+    #[command(
+        about = "Compress the messages of sessions stored before compression was on",
+        long_about = "New messages are stored zstd-compressed when they are large (set GOOSE_SESSION_COMPRESSION to false to turn this off). This compresses the large messages stored earlier and shrinks the session database to match."
+    )]
+    Compress {},
+    #[command(
+        about = "List the logs of subagent tasks, follow one as the task runs, or pause task runs",
+        long_about = "Everything a subagent task prints is logged to tasks/<task_id>.log in the sessions directory. Without options this lists the logs, newest first; with --tail it prints one and keeps printing what the task adds to it. --pause keeps parallel task runs from starting more tasks, letting the running ones finish, until --resume."
+    )]
+    Tasks {
+        #[arg(
+            long,
+            value_name = "TASK_ID",
+            help = "Follow the log of this task (its id, or the start of it)"
+        )]
+        tail: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["tail", "resume"],
+            help = "Start no more tasks in parallel task runs until --resume"
+        )]
+        pause: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "tail",
+            help = "Let paused task runs start tasks again"
+        )]
+        resume: bool,
+    },
     #[command(about = "Export a session to Markdown format")]
     Export {
         #[command(flatten)]
@@ -156,6 +243,65 @@ enum SessionCommand {
         )]
         format: String,
     },
+    #[command(
+        about = "Copy a session with messages, tool outputs, paths or secrets removed, for sharing",
+        long_about = "Copy a session with selected messages removed and tool outputs, paths, environment values or custom patterns blanked. Without any rule options the messages and rules are picked interactively. The original session is not changed; export the copy to share it."
+    )]
+    Redact {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        #[arg(
+            long = "drop",
+            value_name = "INDEX",
+            value_delimiter = ',',
+            help = "Indexes of messages to remove, counting from 0 (e.g. 3,4)"
+        )]
+        drop: Vec<usize>,
+
+        #[arg(long = "tool-outputs", help = "Blank every tool output")]
+        tool_outputs: bool,
+
+        #[arg(long = "paths", help = "Blank absolute and home-relative file paths")]
+        paths: bool,
+
+        #[arg(
+            long = "env",
+            help = "Replace values of your environment variables with $NAME"
+        )]
+        env: bool,
+
+        #[arg(
+            long = "pattern",
+            value_name = "REGEX",
+            help = "Blank text matching this regex (can be specified multiple times)",
+            action = clap::ArgAction::Append
+        )]
+        patterns: Vec<String>,
+    },
+    #[command(about = "Replay a stored session in the terminal")]
+    Replay {
+        /// Session name or ID
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+
+        #[arg(
+            long,
+            help = "Pause between messages as long as they took in the original session"
+        )]
+        realtime: bool,
+
+        #[arg(
+            long,
+            value_name = "SPEED",
+            value_parser = crate::session::parse_speed,
+            help = "Replay with original timing sped up by this factor (e.g. 4x)"
+        )]
+        speed: Option<f64>,
+
+        #[arg(long, help = "Show full tool arguments and output")]
+        debug: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -222,9 +368,25 @@ pub enum BenchCommand {
         #[arg(
             short,
             long,
-            help = "A config file generated by the config-init command"
+            help = "A config file generated by the config-init command",
+            required_unless_present = "resume"
+        )]
+        config: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "RUN_ID",
+            help = "Continue an interrupted run, skipping the evaluations it already finished",
+            long_help = "Continue an interrupted run. RUN_ID is the benchmark directory name printed when the run started (e.g. benchmark-2025-01-31-12:00:00) or a path to it. The configuration saved with the run is used."
         )]
-        config: PathBuf,
+        resume: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Shard parallel-safe evaluations of each model across N worker processes"
+        )]
+        workers: Option<usize>,
     },
 
     #[command(about = "List all available selectors")]
@@ -263,6 +425,318 @@ pub enum BenchCommand {
     },
 }
 
+#[derive(Subcommand)]
+enum DaemonCommand {
+    /// Start goosed in the background on a unix socket
+    #[command(about = "Start a background goosed daemon listening on a unix socket")]
+    Start {
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Socket path (defaults to the goose state dir)"
+        )]
+        socket: Option<PathBuf>,
+    },
+
+    /// Check whether the daemon is running
+    #[command(about = "Check whether the goosed daemon is running")]
+    Status {
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Socket path (defaults to the goose state dir)"
+        )]
+        socket: Option<PathBuf>,
+    },
+
+    /// Stop the daemon
+    #[command(about = "Stop the goosed daemon")]
+    Stop {},
+
+    /// Chat in a session of the running daemon
+    #[command(
+        about = "Attach this terminal to a session of the running goosed daemon",
+        long_about = "Starts a session in the working directory on the running daemon, or resumes --session-id, and chats in it from this terminal. The daemon keeps the session's provider and extensions warm, so detaching with Ctrl+D and attaching again is instant."
+    )]
+    Attach {
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Socket path (defaults to the goose state dir)"
+        )]
+        socket: Option<PathBuf>,
+
+        #[arg(
+            long = "session-id",
+            value_name = "ID",
+            help = "Resume this session instead of starting one"
+        )]
+        session_id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DoctorCommand {
+    /// Explain a code from the warnings shown when a session starts
+    #[command(about = "Explain a startup warning code, or list all codes")]
+    Explain {
+        #[arg(value_name = "CODE", help = "The code to explain, e.g. E101")]
+        code: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StatsCommand {
+    /// Summarize /good and /bad ratings across sessions
+    #[command(about = "Summarize feedback recorded with /good and /bad")]
+    Feedback {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write every feedback entry to a JSON file instead of printing a summary"
+        )]
+        export: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExtensionCommand {
+    #[command(
+        about = "Check that an extension starts and answers",
+        long_about = "Start a configured extension on its own, list its tools, check their \
+                      schemas and call the one given with --tool; without it no tool is called \
+                      and one the server marks read-only is suggested. Prints a pass/fail line \
+                      per check with how long it took."
+    )]
+    Test {
+        #[arg(help = "Name of the extension")]
+        name: String,
+        #[arg(long, help = "Tool to call; none is called without it")]
+        tool: Option<String>,
+        #[arg(
+            long = "args",
+            value_name = "JSON",
+            help = "Arguments for the tool, as a JSON object",
+            requires = "tool"
+        )]
+        arguments: Option<String>,
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            default_value = "30",
+            help = "How long connecting, listing the tools and the tool call may each take"
+        )]
+        timeout: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommand {
+    #[command(
+        about = "List OAuth extensions and the accounts they are signed in as",
+        long_about = "List the extensions that sign in with OAuth, the profile each uses and the \
+                      account it is signed in as, when the server says who that is."
+    )]
+    List {},
+    #[command(
+        about = "Sign an OAuth extension in again",
+        long_about = "Sign an OAuth extension in again in the browser, replacing its cached \
+                      tokens, e.g. when the session header says its sign-in has expired."
+    )]
+    Login {
+        #[arg(help = "Extension name")]
+        name: String,
+    },
+    #[command(
+        about = "Change which account profile an OAuth server signs in with",
+        long_about = "Change the profile extensions of an OAuth server sign in with when they \
+                      don't pick one with oauth_profile. Each profile keeps its own tokens, so \
+                      switching back doesn't need another sign-in."
+    )]
+    Switch {
+        #[arg(help = "Extension name, server URL or OAuth host")]
+        target: String,
+        #[arg(help = "Profile to sign in with, e.g. work or personal; default for the usual one")]
+        profile: String,
+    },
+    #[command(
+        about = "Save an OAuth or bearer token obtained elsewhere",
+        long_about = "Save a token for machines that can't sign in in a browser: a file written \
+                      by goose auth export, an OAuth token response, or a bare access token. \
+                      It goes to the same token store as a sign-in."
+    )]
+    Import {
+        #[arg(long, help = "Extension name, server URL or OAuth host")]
+        host: String,
+        #[arg(long, help = "File holding the token")]
+        token_file: PathBuf,
+        #[arg(
+            long,
+            help = "Profile to save the token under; the usual one by default"
+        )]
+        profile: Option<String>,
+        #[arg(
+            long,
+            help = "OAuth client the token was issued to, needed to use its refresh token"
+        )]
+        client_id: Option<String>,
+    },
+    #[command(
+        about = "Write a cached OAuth token to a file for goose auth import",
+        long_about = "Write the cached OAuth token of an extension to a file, readable only by \
+                      you, or print it. Anyone with the file can use the token, so move it \
+                      carefully and delete it once imported."
+    )]
+    Export {
+        #[arg(long, help = "Extension name, server URL or OAuth host")]
+        host: String,
+        #[arg(long, help = "File to write; printed when not given")]
+        token_file: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Profile whose token is exported; the usual one by default"
+        )]
+        profile: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Manage the sections added to the system prompt
+    #[command(about = "Manage your own system prompt sections")]
+    Prompt {
+        #[command(subcommand)]
+        command: PromptCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum PromptCommand {
+    #[command(
+        about = "Add a section to the system prompt, or replace the one of the same name",
+        long_about = "Add a section to the system prompt, or replace the one of the same name. \
+                      With --mode it is only added in those goose modes, and with --extension \
+                      only while one of those extensions is enabled."
+    )]
+    AddSection {
+        #[arg(help = "Name of the section")]
+        name: String,
+        #[arg(long, help = "Text of the section", conflicts_with = "file")]
+        text: Option<String>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Read the section's text from a file"
+        )]
+        file: Option<PathBuf>,
+        #[arg(
+            long = "mode",
+            value_name = "MODE",
+            help = "Only add it in this goose mode (auto, approve, smart_approve, chat); repeatable"
+        )]
+        modes: Vec<String>,
+        #[arg(
+            long = "extension",
+            value_name = "NAME",
+            help = "Only add it while this extension is enabled; repeatable"
+        )]
+        extensions: Vec<String>,
+    },
+    #[command(about = "Remove a system prompt section")]
+    RemoveSection {
+        #[arg(help = "Name of the section")]
+        name: String,
+    },
+    #[command(about = "List the system prompt sections")]
+    ListSections {},
+    #[command(
+        about = "Show the sections that apply, in the order they are added",
+        long_about = "Show the sections added to the system prompt in a goose mode with some extensions \
+                      enabled, in the order they are added. Defaults to the configured mode and the \
+                      enabled extensions."
+    )]
+    Show {
+        #[arg(
+            long,
+            value_name = "MODE",
+            help = "goose mode to show the sections for"
+        )]
+        mode: Option<String>,
+        #[arg(
+            long = "extension",
+            value_name = "NAME",
+            help = "Extension to take as enabled; repeatable"
+        )]
+        extensions: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunCommand {
+    /// Compare two runs
+    #[command(
+        about = "Compare two runs, such as two runs of the same recipe",
+        long_about = "Compare two runs: tasks and tool calls that succeeded and failed, files changed, tokens, cost, duration and the final output. Runs are sessions, named by ID or name; list them with `goose session list`."
+    )]
+    Diff {
+        #[arg(value_name = "RUN_A", help = "Session ID or name of the earlier run")]
+        run_a: String,
+
+        #[arg(value_name = "RUN_B", help = "Session ID or name of the later run")]
+        run_b: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunsCommand {
+    /// List the parallel task runs recorded
+    #[command(
+        about = "List recorded parallel task runs",
+        long_about = "List the parallel runs of sub-recipe and dynamic tasks recorded in the run history, the latest first, with how many of their tasks completed and failed and what they spent."
+    )]
+    List {
+        #[arg(short, long, help = "Number of runs to list", default_value = "20")]
+        limit: usize,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+
+    /// Show the tasks of a recorded run
+    #[command(
+        about = "Show the tasks of a recorded parallel task run",
+        long_about = "Show each task of a recorded parallel run: its parameters, status, duration, error and what it spent. Tasks that didn't complete can be run again with `goose run --resume-tasks RUN_ID`."
+    )]
+    Show {
+        #[arg(
+            value_name = "RUN_ID",
+            help = "ID of the run, as `goose runs list` shows it"
+        )]
+        run_id: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum RecipeCommand {
     /// Validate a recipe file
@@ -303,6 +777,43 @@ enum RecipeCommand {
         )]
         verbose: bool,
     },
+
+    /// Browse the example recipes bundled with goose
+    #[command(
+        about = "Show example recipes, or copy one into the project",
+        long_about = "List the example recipes bundled with goose (code review, dependency upgrade, changelog), show one, or copy one into the current directory as a starting point with the project's name filled in."
+    )]
+    Examples {
+        /// Example to show or copy
+        #[arg(help = "Example to show or copy; lists them all when left out")]
+        name: Option<String>,
+
+        /// Copy the example instead of showing it
+        #[arg(long, requires = "name", help = "Copy the example into the project")]
+        copy: bool,
+
+        /// Project name to fill in
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Project name to fill in (default: the current directory's name)"
+        )]
+        project: Option<String>,
+
+        /// Where to copy the example
+        #[arg(
+            short,
+            long,
+            value_name = "FILE",
+            requires = "copy",
+            help = "Where to copy the example (default: <name>.yaml here)"
+        )]
+        output: Option<PathBuf>,
+
+        /// Overwrite an existing file
+        #[arg(long, requires = "copy", help = "Overwrite the file if it exists")]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -311,6 +822,27 @@ enum Command {
     #[command(about = "Configure goose settings")]
     Configure {},
 
+    /// Work with configured extensions
+    #[command(about = "Test configured extensions")]
+    Extension {
+        #[command(subcommand)]
+        command: ExtensionCommand,
+    },
+
+    /// Manage OAuth sign-ins
+    #[command(about = "Manage OAuth account profiles")]
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
+
+    /// Manage settings that `configure` doesn't cover
+    #[command(about = "Manage system prompt sections and other settings")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
     /// Display goose configuration information
     #[command(about = "Display goose information")]
     Info {
@@ -382,6 +914,14 @@ enum Command {
         )]
         max_turns: Option<u32>,
 
+        /// Start stdio extensions with the full environment
+        #[arg(
+            long = "inherit-env",
+            help = "Pass the full environment to stdio extensions",
+            long_help = "By default stdio extensions only get a small set of safe variables (PATH, HOME, locale, proxies), the variables configured for them and any listed under GOOSE_EXTENSION_ENV_ALLOWLIST. This passes on everything instead, including unrelated secrets."
+        )]
+        inherit_env: bool,
+
         /// Add stdio extensions with environment variables and commands
         #[arg(
             long = "with-extension",
@@ -432,8 +972,14 @@ enum Command {
     Projects,
 
     /// Execute commands from an instruction file
-    #[command(about = "Execute commands from an instruction file or stdin")]
+    #[command(
+        about = "Execute commands from an instruction file or stdin",
+        after_help = EXIT_CODES_HELP
+    )]
     Run {
+        #[command(subcommand)]
+        command: Option<RunCommand>,
+
         /// Path to instruction file containing commands
         #[arg(
             short,
@@ -552,6 +1098,24 @@ enum Command {
         )]
         resume: bool,
 
+        /// Run again the tasks of a parallel run that didn't complete
+        #[arg(
+            long = "resume-tasks",
+            value_name = "RUN_ID",
+            help = "Run again the tasks of a parallel run that didn't complete",
+            long_help = "Pick up a parallel run of sub-recipe or dynamic tasks where it stopped, after a crash, an interrupt or failures. RUN_ID is shown when a run ends with tasks that didn't complete. Only the pending, failed, cancelled and skipped tasks run again; the completed ones keep their results.",
+            conflicts_with_all = ["instructions", "input_text", "recipe", "interactive"]
+        )]
+        resume_tasks: Option<String>,
+
+        /// Run every task instead of reusing cached results
+        #[arg(
+            long = "no-cache",
+            help = "Run every sub-recipe and dynamic task instead of reusing cached results",
+            long_help = "Tasks that ask to be cached (sub-recipes with cache: true), and whose recipe, parameters, input files and model match a task that completed before, normally get its cached result instead of running again. This runs every task, as GOOSE_TASK_CACHE=false does for all runs."
+        )]
+        no_cache: bool,
+
         /// Enable debug output mode
         #[arg(
             long,
@@ -560,6 +1124,14 @@ enum Command {
         )]
         debug: bool,
 
+        /// Start stdio extensions with the full environment
+        #[arg(
+            long = "inherit-env",
+            help = "Pass the full environment to stdio extensions",
+            long_help = "By default stdio extensions only get a small set of safe variables (PATH, HOME, locale, proxies), the variables configured for them and any listed under GOOSE_EXTENSION_ENV_ALLOWLIST. This passes on everything instead, including unrelated secrets."
+        )]
+        inherit_env: bool,
+
         /// Add stdio extensions with environment variables and commands
         #[arg(
             long = "with-extension",
@@ -608,6 +1180,14 @@ enum Command {
         )]
         quiet: bool,
 
+        /// Porcelain mode - keep decorative output off stdout
+        #[arg(
+            long = "porcelain",
+            help = "Print only assistant content and tool results to stdout, everything else to stderr",
+            long_help = "Route decorative output (session info, tool call boxes, status messages, progress) to stderr and keep only assistant content and tool results on stdout, so `goose run ... > out.md` captures clean content."
+        )]
+        porcelain: bool,
+
         /// Scheduled job ID (used internally for scheduled executions)
         #[arg(
             long = "scheduled-job-id",
@@ -685,6 +1265,36 @@ enum Command {
         cmd: BenchCommand,
     },
 
+    /// Manage the background goosed daemon
+    #[command(
+        about = "Manage a background goosed daemon that keeps providers and extensions warm"
+    )]
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommand,
+    },
+
+    /// Diagnose problems reported at startup
+    #[command(about = "Diagnose problems reported when a session starts")]
+    Doctor {
+        #[command(subcommand)]
+        command: DoctorCommand,
+    },
+
+    /// Past parallel runs of tasks
+    #[command(about = "List and show recorded parallel task runs")]
+    Runs {
+        #[command(subcommand)]
+        command: RunsCommand,
+    },
+
+    /// Aggregate statistics across saved sessions
+    #[command(about = "Show statistics aggregated across sessions")]
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommand,
+    },
+
     /// Start a web server with a chat interface
     #[command(about = "Experimental: Start a web server with a chat interface")]
     Web {
@@ -739,6 +1349,12 @@ pub struct RecipeInfo {
 
 pub async fn cli() -> Result<()> {
     let cli = Cli::parse();
+    if cli.offline {
+        goose::offline::set_offline();
+    }
+    if cli.oauth_manual {
+        goose::oauth::manual::set_manual();
+    }
 
     // Track the current directory in projects.json
     if let Err(e) = crate::project_tracker::update_project_tracker(None, None) {
@@ -747,6 +1363,9 @@ pub async fn cli() -> Result<()> {
 
     let command_name = match &cli.command {
         Some(Command::Configure {}) => "configure",
+        Some(Command::Extension { .. }) => "extension",
+        Some(Command::Auth { .. }) => "auth",
+        Some(Command::Config { .. }) => "config",
         Some(Command::Info { .. }) => "info",
         Some(Command::Mcp { .. }) => "mcp",
         Some(Command::Acp {}) => "acp",
@@ -758,6 +1377,10 @@ pub async fn cli() -> Result<()> {
         Some(Command::Update { .. }) => "update",
         Some(Command::Bench { .. }) => "bench",
         Some(Command::Recipe { .. }) => "recipe",
+        Some(Command::Daemon { .. }) => "daemon",
+        Some(Command::Doctor { .. }) => "doctor",
+        Some(Command::Runs { .. }) => "runs",
+        Some(Command::Stats { .. }) => "stats",
         Some(Command::Web { .. }) => "web",
         None => "default_session",
     };
@@ -773,6 +1396,55 @@ pub async fn cli() -> Result<()> {
             let _ = handle_configure().await;
             return Ok(());
         }
+        Some(Command::Extension { command }) => {
+            match command {
+                ExtensionCommand::Test {
+                    name,
+                    tool,
+                    arguments,
+                    timeout,
+                } => handle_extension_test(name, tool, arguments, timeout).await?,
+            }
+            return Ok(());
+        }
+        Some(Command::Auth { command }) => {
+            match command {
+                AuthCommand::List {} => handle_auth_list()?,
+                AuthCommand::Login { name } => handle_auth_login(name).await?,
+                AuthCommand::Switch { target, profile } => handle_auth_switch(target, profile)?,
+                AuthCommand::Import {
+                    host,
+                    token_file,
+                    profile,
+                    client_id,
+                } => handle_auth_import(host, token_file, profile, client_id)?,
+                AuthCommand::Export {
+                    host,
+                    token_file,
+                    profile,
+                } => handle_auth_export(host, token_file, profile)?,
+            }
+            return Ok(());
+        }
+        Some(Command::Config { command }) => {
+            match command {
+                ConfigCommand::Prompt { command } => match command {
+                    PromptCommand::AddSection {
+                        name,
+                        text,
+                        file,
+                        modes,
+                        extensions,
+                    } => handle_prompt_add_section(name, text, file, modes, extensions)?,
+                    PromptCommand::RemoveSection { name } => handle_prompt_remove_section(name)?,
+                    PromptCommand::ListSections {} => handle_prompt_list_sections()?,
+                    PromptCommand::Show { mode, extensions } => {
+                        handle_prompt_show(mode, extensions)?
+                    }
+                },
+            }
+            return Ok(());
+        }
         Some(Command::Info { verbose }) => {
             handle_info(verbose)?;
             return Ok(());
@@ -793,6 +1465,7 @@ pub async fn cli() -> Result<()> {
             debug,
             max_tool_repetitions,
             max_turns,
+            inherit_env,
             extensions,
             remote_extensions,
             streamable_http_extensions,
@@ -812,6 +1485,25 @@ pub async fn cli() -> Result<()> {
                     handle_session_remove(id, regex).await?;
                     return Ok(());
                 }
+                Some(SessionCommand::Gc {
+                    dry_run,
+                    clear_task_cache,
+                }) => {
+                    handle_session_gc(dry_run, clear_task_cache).await?;
+                    return Ok(());
+                }
+                Some(SessionCommand::Compress {}) => {
+                    handle_session_compress().await?;
+                    return Ok(());
+                }
+                Some(SessionCommand::Tasks {
+                    tail,
+                    pause,
+                    resume,
+                }) => {
+                    handle_session_tasks(tail, pause, resume).await?;
+                    return Ok(());
+                }
                 Some(SessionCommand::Export {
                     identifier,
                     output,
@@ -839,6 +1531,77 @@ pub async fn cli() -> Result<()> {
                     .await?;
                     Ok(())
                 }
+                Some(SessionCommand::Redact {
+                    identifier,
+                    drop,
+                    tool_outputs,
+                    paths,
+                    env,
+                    patterns,
+                }) => {
+                    let session_id = if let Some(id) = identifier {
+                        get_session_id(id).await?
+                    } else {
+                        match crate::commands::session::prompt_interactive_session_selection().await
+                        {
+                            Ok(id) => id,
+                            Err(e) => {
+                                eprintln!("Error: {}", e);
+                                return Ok(());
+                            }
+                        }
+                    };
+                    let interactive =
+                        drop.is_empty() && !tool_outputs && !paths && !env && patterns.is_empty();
+                    let options = RedactionOptions {
+                        drop_messages: drop.into_iter().collect(),
+                        tool_outputs,
+                        paths,
+                        env_values: env,
+                        patterns: patterns
+                            .iter()
+                            .map(|pattern| {
+                                regex::Regex::new(pattern)
+                                    .with_context(|| format!("Invalid pattern '{}'", pattern))
+                            })
+                            .collect::<Result<_>>()?,
+                    };
+                    handle_session_redact(session_id, options, interactive).await?;
+                    Ok(())
+                }
+                Some(SessionCommand::Replay {
+                    name,
+                    realtime,
+                    speed,
+                    debug,
+                }) => {
+                    let session_id = match name {
+                        Some(name) => {
+                            get_session_id(Identifier {
+                                name: Some(name),
+                                session_id: None,
+                                path: None,
+                            })
+                            .await?
+                        }
+                        None => {
+                            match crate::commands::session::prompt_interactive_session_selection()
+                                .await
+                            {
+                                Ok(id) => id,
+                                Err(e) => {
+                                    eprintln!("Error: {}", e);
+                                    return Ok(());
+                                }
+                            }
+                        }
+                    };
+                    crate::commands::session::handle_session_replay(
+                        session_id, speed, realtime, debug,
+                    )
+                    .await?;
+                    Ok(())
+                }
                 None => {
                     let session_start = std::time::Instant::now();
                     let session_type = if resume { "resumed" } else { "new" };
@@ -873,9 +1636,12 @@ pub async fn cli() -> Result<()> {
                         debug,
                         max_tool_repetitions,
                         max_turns,
+                        inherit_env,
+                        no_cache: false,
                         scheduled_job_id: None,
                         interactive: true,
                         quiet: false,
+                        porcelain: false,
                         sub_recipes: None,
                         final_output_response: None,
                         retry_config: None,
@@ -938,6 +1704,26 @@ pub async fn cli() -> Result<()> {
         }
 
         Some(Command::Run {
+            command:
+                Some(RunCommand::Diff {
+                    run_a,
+                    run_b,
+                    format,
+                }),
+            ..
+        }) => {
+            let identifier = |name| Identifier {
+                name: Some(name),
+                session_id: None,
+                path: None,
+            };
+            let run_a = get_session_id(identifier(run_a)).await?;
+            let run_b = get_session_id(identifier(run_b)).await?;
+            handle_run_diff(run_a, run_b, format).await?;
+            return Ok(());
+        }
+        Some(Command::Run {
+            command: None,
             instructions,
             input_text,
             recipe,
@@ -945,10 +1731,13 @@ pub async fn cli() -> Result<()> {
             interactive,
             identifier,
             resume,
+            resume_tasks,
+            no_cache,
             no_session,
             debug,
             max_tool_repetitions,
             max_turns,
+            inherit_env,
             extensions,
             remote_extensions,
             streamable_http_extensions,
@@ -958,10 +1747,44 @@ pub async fn cli() -> Result<()> {
             render_recipe,
             scheduled_job_id,
             quiet,
+            porcelain,
             additional_sub_recipes,
             provider,
             model,
         }) => {
+            if let Some(run_id) = resume_tasks {
+                let session_id = if let Some(id) = identifier {
+                    Some(get_session_id(id).await?)
+                } else {
+                    None
+                };
+                let mut session = build_session(SessionBuilderConfig {
+                    session_id,
+                    resume,
+                    no_session,
+                    extensions,
+                    remote_extensions,
+                    streamable_http_extensions,
+                    builtins,
+                    provider,
+                    model,
+                    debug,
+                    max_tool_repetitions,
+                    max_turns,
+                    inherit_env,
+                    no_cache,
+                    scheduled_job_id,
+                    quiet,
+                    porcelain,
+                    ..Default::default()
+                })
+                .await;
+                let exit_code = session.resume_tasks(&run_id).await?;
+                if exit_code != ExitCode::Success {
+                    return Err(RunFailed(exit_code).into());
+                }
+                return Ok(());
+            }
             let (input_config, recipe_info) = match (instructions, input_text, recipe) {
                 (Some(file), _, _) if file == "-" => {
                     let mut input = String::new();
@@ -982,7 +1805,7 @@ pub async fn cli() -> Result<()> {
                             "Instruction file not found — did you mean to use goose run --text?\n{}",
                             err
                         );
-                        std::process::exit(1);
+                        ExitCode::Usage.exit();
                     });
                     let input_config = InputConfig {
                         contents: Some(contents),
@@ -1045,7 +1868,7 @@ pub async fn cli() -> Result<()> {
                 }
                 (None, None, None) => {
                     eprintln!("Error: Must provide either --instructions (-i), --text (-t), or --recipe. Use -i - for stdin.");
-                    std::process::exit(1);
+                    ExitCode::Usage.exit();
                 }
             };
             let session_id = if let Some(id) = identifier {
@@ -1072,9 +1895,12 @@ pub async fn cli() -> Result<()> {
                 debug,
                 max_tool_repetitions,
                 max_turns,
+                inherit_env,
+                no_cache,
                 scheduled_job_id,
                 interactive, // Use the interactive flag from the Run command
                 quiet,
+                porcelain,
                 sub_recipes: recipe_info.as_ref().and_then(|r| r.sub_recipes.clone()),
                 final_output_response: recipe_info
                     .as_ref()
@@ -1103,7 +1929,10 @@ pub async fn cli() -> Result<()> {
                 let result = session.headless(contents).await;
 
                 let session_duration = session_start.elapsed();
-                let exit_type = if result.is_ok() { "normal" } else { "error" };
+                let exit_type = match &result {
+                    Ok(exit_code) => exit_code.label(),
+                    Err(e) => ExitCode::of_error(e).label(),
+                };
 
                 let (total_tokens, message_count) = session
                     .get_metadata()
@@ -1136,10 +1965,13 @@ pub async fn cli() -> Result<()> {
                     );
                 }
 
-                result?;
+                let exit_code = result?;
+                if exit_code != ExitCode::Success {
+                    return Err(RunFailed(exit_code).into());
+                }
             } else {
                 eprintln!("Error: no text provided for prompt in headless mode");
-                std::process::exit(1);
+                ExitCode::Usage.exit();
             }
 
             return Ok(());
@@ -1196,7 +2028,19 @@ pub async fn cli() -> Result<()> {
                     config.output_dir = Some(cwd);
                     config.save(name);
                 }
-                BenchCommand::Run { config } => BenchRunner::new(config)?.run()?,
+                BenchCommand::Run {
+                    config,
+                    resume,
+                    workers,
+                } => {
+                    let runner = match resume {
+                        Some(run_id) => BenchRunner::resume(&run_id, config)?,
+                        None => BenchRunner::new(config.ok_or_else(|| {
+                            anyhow::anyhow!("--config is required unless resuming a run")
+                        })?)?,
+                    };
+                    runner.with_workers(workers).run()?
+                }
                 BenchCommand::EvalModel { config } => ModelRunner::from(config)?.run()?,
                 BenchCommand::ExecEval { config } => {
                     EvalRunner::from(config)?.run(agent_generator).await?
@@ -1218,6 +2062,53 @@ pub async fn cli() -> Result<()> {
                 RecipeCommand::List { format, verbose } => {
                     handle_list(&format, verbose)?;
                 }
+                RecipeCommand::Examples {
+                    name,
+                    copy,
+                    project,
+                    output,
+                    force,
+                } => {
+                    handle_examples(name, copy, project, output, force)?;
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Daemon { command }) => {
+            match command {
+                DaemonCommand::Start { socket } => {
+                    crate::commands::daemon::handle_daemon_start(socket).await?
+                }
+                DaemonCommand::Status { socket } => {
+                    crate::commands::daemon::handle_daemon_status(socket).await?
+                }
+                DaemonCommand::Stop {} => crate::commands::daemon::handle_daemon_stop().await?,
+                DaemonCommand::Attach { socket, session_id } => {
+                    crate::commands::daemon::handle_daemon_attach(socket, session_id).await?
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Doctor { command }) => {
+            match command {
+                DoctorCommand::Explain { code } => {
+                    crate::commands::doctor::handle_doctor_explain(code)?
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Runs { command }) => {
+            match command {
+                RunsCommand::List { limit, format } => handle_runs_list(limit, format).await?,
+                RunsCommand::Show { run_id, format } => handle_runs_show(run_id, format).await?,
+            }
+            return Ok(());
+        }
+        Some(Command::Stats { command }) => {
+            match command {
+                StatsCommand::Feedback { export } => {
+                    crate::commands::stats::handle_stats_feedback(export).await?
+                }
             }
             return Ok(());
         }
@@ -1232,7 +2123,11 @@ pub async fn cli() -> Result<()> {
         }
         None => {
             return if !Config::global().exists() {
-                let _ = handle_configure().await;
+                if cli.no_onboarding {
+                    let _ = handle_configure().await;
+                } else {
+                    let _ = handle_onboarding().await;
+                }
                 Ok(())
             } else {
                 // Run session command by default
@@ -1252,9 +2147,12 @@ pub async fn cli() -> Result<()> {
                     debug: false,
                     max_tool_repetitions: None,
                     max_turns: None,
+                    inherit_env: false,
+                    no_cache: false,
                     scheduled_job_id: None,
                     interactive: true,
                     quiet: false,
+                    porcelain: false,
                     sub_recipes: None,
                     final_output_response: None,
                     retry_config: None,
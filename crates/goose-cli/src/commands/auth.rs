@@ -0,0 +1,336 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use console::style;
+use goose::agents::extension::McpAuthType;
+use goose::config::extensions::name_to_key;
+use goose::config::{ExtensionConfig, ExtensionConfigManager};
+use goose::oauth::http::OAuthHttpSettings;
+use goose::oauth::registration::ClientRegistration;
+use goose::oauth::token_store::{
+    set_default_profile, token_store, write_private, StoredToken, TokenKey, DEFAULT_PROFILE,
+};
+use goose::oauth::wait::OAUTH_TIMEOUT_KEY;
+use goose::oauth::{sign_in_again, sign_in_hint, AuthError};
+
+/// The OAuth host `target` names: a configured extension, a server URL, or the host itself
+fn oauth_host(target: &str) -> Result<(String, Option<String>)> {
+    let target = target.trim();
+    for entry in ExtensionConfigManager::get_all()? {
+        if let ExtensionConfig::StreamableHttp {
+            name,
+            uri,
+            oauth_profile,
+            ..
+        } = entry.config
+        {
+            if name_to_key(&name) == name_to_key(target) {
+                let host = TokenKey::for_resource(&uri)?.oauth_host;
+                let pinned =
+                    oauth_profile.map(|profile| format!("{} always signs in as {}", name, profile));
+                return Ok((host, pinned));
+            }
+        }
+    }
+    if target.contains("://") {
+        return Ok((TokenKey::for_resource(target)?.oauth_host, None));
+    }
+    Ok((target.to_string(), None))
+}
+
+pub fn handle_auth_switch(target: String, profile: String) -> Result<()> {
+    let (host, pinned) = oauth_host(&target)?;
+    set_default_profile(&host, &profile)
+        .with_context(|| format!("Failed to switch the profile for {}", host))?;
+    let profile = profile.trim();
+    if profile.eq_ignore_ascii_case(DEFAULT_PROFILE) {
+        println!("{} is back to the default profile", host);
+    } else {
+        println!("{} now signs in as {}", host, profile);
+    }
+    if let Some(pinned) = pinned {
+        println!(
+            "Its extension picks a profile of its own, though: {}",
+            pinned
+        );
+    }
+    println!("Sessions already running keep their accounts until the extension is restarted.");
+    Ok(())
+}
+
+/// Each OAuth extension with the account it is signed in as
+pub fn handle_auth_list() -> Result<()> {
+    let store = token_store();
+    let mut listed = false;
+    for entry in ExtensionConfigManager::get_all()? {
+        let ExtensionConfig::StreamableHttp {
+            name,
+            uri,
+            envs,
+            env_keys,
+            auth_type,
+            auth: None,
+            oauth_profile,
+            ..
+        } = entry.config
+        else {
+            continue;
+        };
+        listed = true;
+        if auth_type != McpAuthType::AuthorizationCode {
+            println!(
+                "{} {}",
+                style(&name).bold(),
+                style("(signs in as its service account on start)").dim()
+            );
+            continue;
+        }
+        let registration = ClientRegistration::from_extension(&name, &envs, &env_keys);
+        let key = TokenKey::for_profile(&uri, oauth_profile.as_deref())?
+            .with_client(registration.as_ref().map(|r| r.client_id.as_str()));
+        let account = match store.load(&key) {
+            Ok(Some(token)) => {
+                let who = match &token.identity {
+                    Some(identity) => identity.to_string(),
+                    None => "signed in, account unknown".to_string(),
+                };
+                match token.expiry() {
+                    Some(expiry) => format!(
+                        "{} {}",
+                        who,
+                        style(format!("({}, {})", expiry, sign_in_hint(&name))).yellow()
+                    ),
+                    None => who,
+                }
+            }
+            Ok(None) => style("not signed in").dim().to_string(),
+            Err(e) => style(format!("unreadable token: {}", e)).red().to_string(),
+        };
+        println!(
+            "{} [{}] {}",
+            style(&name).bold(),
+            key.profile_name(),
+            account
+        );
+    }
+    if !listed {
+        println!("No extensions use OAuth");
+    }
+    Ok(())
+}
+
+/// Sign the extension `name` in again in the browser, replacing its cached tokens
+pub async fn handle_auth_login(name: String) -> Result<()> {
+    let extension = ExtensionConfigManager::get_all()?
+        .into_iter()
+        .map(|entry| entry.config)
+        .find(|config| name_to_key(&config.name()) == name_to_key(name.trim()))
+        .with_context(|| format!("No extension named {}", name))?;
+    let ExtensionConfig::StreamableHttp {
+        name,
+        uri,
+        envs,
+        env_keys,
+        auth_type,
+        auth,
+        scopes,
+        oauth_http,
+        oauth_profile,
+        ..
+    } = extension
+    else {
+        bail!("{} doesn't sign in with OAuth", name);
+    };
+    if let Some(auth) = auth {
+        bail!(
+            "{} sends the secret {} instead of signing in; change it with `goose configure`",
+            name,
+            auth.secret
+        );
+    }
+    if auth_type != McpAuthType::AuthorizationCode {
+        bail!(
+            "{} signs in as its service account on start, there is nothing to log in to",
+            name
+        );
+    }
+
+    let registration = ClientRegistration::from_extension(&name, &envs, &env_keys);
+    let http = oauth_http
+        .or(OAuthHttpSettings::from_config(&name))
+        .client()?;
+    let key = TokenKey::for_profile(&uri, oauth_profile.as_deref())?
+        .with_client(registration.as_ref().map(|r| r.client_id.as_str()));
+
+    // The old sign-in stays until the new one replaces it, so giving up leaves it working
+    if let Err(e) = sign_in_again(&uri, &name, &key, registration.as_ref(), &scopes, &http).await {
+        let advice = match &e {
+            AuthError::Timeout(_) => format!(
+                "; give the browser longer with {}_OAUTH_TIMEOUT or {}",
+                name.to_uppercase().replace(['-', ' '], "_"),
+                OAUTH_TIMEOUT_KEY
+            ),
+            e if e.needs_configuration() => format!("; {}", e.hint(&name).unwrap_or_default()),
+            e if e.is_retryable() => "; try again in a moment".to_string(),
+            _ => String::new(),
+        };
+        bail!("Failed to sign in to {}: {}{}", name, e, advice);
+    }
+
+    match token_store()
+        .load(&key)
+        .ok()
+        .flatten()
+        .and_then(|token| token.identity)
+    {
+        Some(identity) => println!("Signed in to {} as {}", name, identity),
+        None => println!("Signed in to {}", name),
+    }
+    println!("Sessions already running keep their old sign-in until the extension is restarted.");
+    Ok(())
+}
+
+/// The token store entries `target` names, with the extension each belongs to: the extension's
+/// own, the server URL's, or those of every OAuth extension on the host
+fn token_keys(target: &str, profile: Option<&str>) -> Result<Vec<(String, TokenKey)>> {
+    let target = target.trim();
+    if target.contains("://") {
+        return Ok(vec![(
+            target.to_string(),
+            TokenKey::for_profile(target, profile)?,
+        )]);
+    }
+    let mut keys = Vec::new();
+    for entry in ExtensionConfigManager::get_all()? {
+        // SSE extensions sign in without a profile, so their tokens are kept under none
+        let (name, envs, env_keys, auth, key) = match entry.config {
+            ExtensionConfig::StreamableHttp {
+                name,
+                uri,
+                envs,
+                env_keys,
+                auth,
+                oauth_profile,
+                ..
+            } => {
+                let key = TokenKey::for_profile(&uri, profile.or(oauth_profile.as_deref()))?;
+                (name, envs, env_keys, auth, key)
+            }
+            ExtensionConfig::Sse {
+                name,
+                uri,
+                envs,
+                env_keys,
+                auth,
+                ..
+            } => {
+                let key = TokenKey::for_resource(&uri)?;
+                (name, envs, env_keys, auth, key)
+            }
+            _ => continue,
+        };
+        let named = name_to_key(&name) == name_to_key(target);
+        let registration = ClientRegistration::from_extension(&name, &envs, &env_keys);
+        let key = key.with_client(registration.as_ref().map(|r| r.client_id.as_str()));
+        if !named && key.oauth_host != target {
+            continue;
+        }
+        if let Some(auth) = auth {
+            if named {
+                bail!(
+                    "{} sends the secret {} instead of OAuth tokens",
+                    name,
+                    auth.secret
+                );
+            }
+            continue;
+        }
+        if named {
+            return Ok(vec![(name, key)]);
+        }
+        // Extensions on the host with the same client share one entry
+        if !keys.iter().any(|(_, listed)| *listed == key) {
+            keys.push((name, key));
+        }
+    }
+    if keys.is_empty() {
+        bail!(
+            "No OAuth extension is named {} or uses that host; pass the server URL instead",
+            target
+        );
+    }
+    Ok(keys)
+}
+
+/// Save a token obtained elsewhere for `target`, as if it had signed in here
+pub fn handle_auth_import(
+    target: String,
+    token_file: PathBuf,
+    profile: Option<String>,
+    client_id: Option<String>,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(&token_file)
+        .with_context(|| format!("Failed to read {}", token_file.display()))?;
+    let token = StoredToken::import(&contents, client_id.as_deref())?;
+    let store = token_store();
+    for (name, key) in token_keys(&target, profile.as_deref())? {
+        store
+            .save(&key, &token)
+            .with_context(|| format!("Failed to save the token for {}", name))?;
+        println!("Imported the token for {} [{}]", name, key.profile_name());
+    }
+    match token.expiry() {
+        Some(expiry) => println!("{}", style(format!("The token {}", expiry)).yellow()),
+        None if !token.has_refresh_token() => {
+            println!("Without a refresh token, import a new token before this one runs out.")
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Write the cached token of `target` to `token_file`, or print it, for `handle_auth_import` on
+/// another machine
+pub fn handle_auth_export(
+    target: String,
+    token_file: Option<PathBuf>,
+    profile: Option<String>,
+) -> Result<()> {
+    let store = token_store();
+    let mut tokens = Vec::new();
+    for (name, key) in token_keys(&target, profile.as_deref())? {
+        if let Some(token) = store
+            .load(&key)
+            .with_context(|| format!("Failed to read the token for {}", name))?
+        {
+            tokens.push((name, token));
+        }
+    }
+    let (name, token) = match tokens.len() {
+        0 => bail!("{} is not signed in", target),
+        1 => tokens.remove(0),
+        _ => bail!(
+            "Several extensions are signed in at {}: {}; export one by name",
+            target,
+            tokens
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let exported = serde_json::to_string_pretty(&token)?;
+    match token_file {
+        Some(token_file) => {
+            write_private(&token_file, exported.as_bytes())
+                .with_context(|| format!("Failed to write {}", token_file.display()))?;
+            println!(
+                "Exported the token for {} to {}; it signs anyone who has it in",
+                name,
+                token_file.display()
+            );
+        }
+        None => println!("{}", exported),
+    }
+    Ok(())
+}
@@ -12,7 +12,8 @@ use tokio::sync::Mutex;
 #[async_trait]
 impl BenchBaseSession for CliSession {
     async fn headless(&mut self, message: String) -> anyhow::Result<()> {
-        self.headless(message).await
+        // Benchmarks score the conversation, not how the run ended
+        CliSession::headless(self, message).await.map(|_| ())
     }
     fn message_history(&self) -> Conversation {
         self.message_history()
@@ -53,7 +54,10 @@ pub async fn agent_generator(
         interactive: false, // Benchmarking is non-interactive
         scheduled_job_id: None,
         max_turns: None,
+        inherit_env: false,
+        no_cache: false,
         quiet: false,
+        porcelain: false,
         sub_recipes: None,
         final_output_response: None,
         retry_config: None,
@@ -0,0 +1,140 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use goose::config::prompt_sections::applicable_sections;
+use goose::config::{Config, ExtensionConfigManager, PromptSection, PromptSectionManager};
+use std::path::PathBuf;
+
+const GOOSE_MODES: [&str; 4] = ["auto", "approve", "smart_approve", "chat"];
+
+fn describe(section: &PromptSection) -> String {
+    let modes = if section.modes.is_empty() {
+        "all modes".to_string()
+    } else {
+        format!("modes: {}", section.modes.join(", "))
+    };
+    if section.extensions.is_empty() {
+        modes
+    } else {
+        format!("{}; extensions: {}", modes, section.extensions.join(", "))
+    }
+}
+
+pub fn handle_prompt_add_section(
+    name: String,
+    text: Option<String>,
+    file: Option<PathBuf>,
+    modes: Vec<String>,
+    extensions: Vec<String>,
+) -> Result<()> {
+    let text = match (text, file) {
+        (Some(text), None) => text,
+        (None, Some(path)) => std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+        _ => bail!("Give the section's text with either --text or --file"),
+    };
+    let modes: Vec<String> = modes.iter().map(|mode| mode.to_lowercase()).collect();
+    if let Some(unknown) = modes
+        .iter()
+        .find(|mode| !GOOSE_MODES.contains(&mode.as_str()))
+    {
+        bail!(
+            "Unknown goose mode '{}'; expected one of {}",
+            unknown,
+            GOOSE_MODES.join(", ")
+        );
+    }
+
+    let replaced = PromptSectionManager::get_all()
+        .iter()
+        .any(|section| section.name == name);
+    let section = PromptSection {
+        name,
+        text: text.trim().to_string(),
+        modes,
+        extensions,
+    };
+    let description = describe(&section);
+    let name = section.name.clone();
+    PromptSectionManager::set(section)?;
+    println!(
+        "{} prompt section '{}' ({})",
+        if replaced { "Replaced" } else { "Added" },
+        name,
+        description
+    );
+    Ok(())
+}
+
+pub fn handle_prompt_remove_section(name: String) -> Result<()> {
+    if !PromptSectionManager::remove(&name)? {
+        bail!("There is no prompt section called '{}'", name);
+    }
+    println!("Removed prompt section '{}'", name);
+    Ok(())
+}
+
+pub fn handle_prompt_list_sections() -> Result<()> {
+    let mut sections = PromptSectionManager::get_all();
+    if sections.is_empty() {
+        println!("No prompt sections. Add one with `goose config prompt add-section`.");
+        return Ok(());
+    }
+    sections.sort_by(|a, b| a.name.cmp(&b.name));
+    for section in sections {
+        println!("{} ({})", style(&section.name).bold(), describe(&section));
+        for line in section.text.lines() {
+            println!("    {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// The prompt sections added in `mode` with `extensions` on, in the order they go into the
+/// system prompt. Both default to the configured mode and enabled extensions.
+pub fn handle_prompt_show(mode: Option<String>, extensions: Vec<String>) -> Result<()> {
+    let mode = mode.unwrap_or_else(|| {
+        Config::global()
+            .get_param("GOOSE_MODE")
+            .unwrap_or("auto".to_string())
+    });
+    let extensions = if extensions.is_empty() {
+        ExtensionConfigManager::get_all()?
+            .into_iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.config.name())
+            .collect()
+    } else {
+        extensions
+    };
+
+    let sections = PromptSectionManager::get_all();
+    let applicable = applicable_sections(&sections, &mode, &extensions);
+    println!(
+        "{}",
+        style(format!(
+            "Prompt sections in {} mode with {}:",
+            mode,
+            if extensions.is_empty() {
+                "no extensions".to_string()
+            } else {
+                extensions.join(", ")
+            }
+        ))
+        .dim()
+    );
+    if applicable.is_empty() {
+        println!("None apply.");
+        return Ok(());
+    }
+    for section in applicable {
+        println!();
+        println!("{} ({})", style(&section.name).bold(), describe(section));
+        println!("{}", section.text);
+    }
+    println!();
+    println!(
+        "{}",
+        style("They follow goose's own additional instructions; /system in a session shows the whole prompt.").dim()
+    );
+    Ok(())
+}
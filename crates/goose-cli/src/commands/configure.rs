@@ -1,15 +1,16 @@
 use crate::recipes::github_recipe::GOOSE_RECIPE_GITHUB_REPO_CONFIG_KEY;
 use cliclack::spinner;
 use console::style;
-use goose::agents::extension::ToolInfo;
+use goose::agents::extension::{McpAuthType, ToolInfo};
 use goose::agents::extension_manager::get_parameter_names;
 use goose::agents::platform_tools::{
     PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_READ_RESOURCE_TOOL_NAME,
 };
+use goose::agents::remote_auth::{AuthScheme, RemoteAuth};
 use goose::agents::Agent;
 use goose::agents::{extension::Envs, ExtensionConfig};
 use goose::config::custom_providers::CustomProviderConfig;
-use goose::config::extensions::name_to_key;
+use goose::config::extensions::{extension_secret_key, name_to_key};
 use goose::config::permission::PermissionLevel;
 use goose::config::{
     Config, ConfigError, ExperimentManager, ExtensionConfigManager, ExtensionEntry,
@@ -17,6 +18,8 @@ use goose::config::{
 };
 use goose::conversation::message::Message;
 use goose::model::ModelConfig;
+use goose::oauth::client_credentials::{CLIENT_ID_ENV, CLIENT_SECRET_ENV};
+use goose::oauth::jwt_bearer::KEY_FILE_ENV;
 use goose::providers::{create, providers};
 use rmcp::model::{Tool, ToolAnnotations};
 use rmcp::object;
@@ -416,6 +419,13 @@ fn select_model_from_list(
 
 /// Dialog for configuring the A provider and model
 pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
+    configure_provider_dialog_with_default(None).await
+}
+
+/// The provider dialog with `preferred` selected to start with instead of the configured provider
+pub async fn configure_provider_dialog_with_default(
+    preferred: Option<&str>,
+) -> Result<bool, Box<dyn Error>> {
     // Get global config instance
     let config = Config::global();
 
@@ -430,7 +440,10 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
 
     // Get current default provider if it exists
     let current_provider: Option<String> = config.get_param("GOOSE_PROVIDER").ok();
-    let default_provider = current_provider.unwrap_or_default();
+    let default_provider = preferred
+        .map(str::to_string)
+        .or(current_provider)
+        .unwrap_or_default();
 
     // Select provider
     let provider_name = cliclack::select("Which model provider should we use?")
@@ -746,6 +759,7 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     "Tutorial",
                     "Access interactive tutorials and guides",
                 ),
+                ("web", "Web", "Search the web and read pages as markdown"),
             ];
 
             let mut select = cliclack::select("Which built-in extension would you like to enable?");
@@ -926,6 +940,8 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     Err(_) => Err("Please enter a valid description"),
                 })
                 .interact()?;
+            let auth = remote_auth_dialog(&name)?;
+
             let add_env =
                 cliclack::confirm("Would you like to add environment variables?").interact()?;
 
@@ -969,6 +985,7 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     uri,
                     envs: Envs::new(envs),
                     env_keys,
+                    auth,
                     description,
                     timeout: Some(timeout),
                     bundled: None,
@@ -1053,6 +1070,67 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
             let mut env_keys = Vec::new();
             let config = Config::global();
 
+            let auth = remote_auth_dialog(&name)?;
+
+            let auth_type = if auth.is_some() {
+                McpAuthType::default()
+            } else if cliclack::confirm(
+                "Does this server sign in machines with OAuth client credentials (no browser)?",
+            )
+            .initial_value(false)
+            .interact()?
+            {
+                let client_id: String = cliclack::input("OAuth client ID:").interact()?;
+                let client_secret: String = cliclack::password("OAuth client secret:")
+                    .mask('▪')
+                    .interact()?;
+                envs.insert(CLIENT_ID_ENV.to_string(), client_id);
+                save_client_secret(&name, client_secret, &mut envs, &mut env_keys);
+                McpAuthType::ClientCredentials
+            } else if cliclack::confirm(
+                "Does it sign in a service account with a key file instead (JWT bearer, no browser)?",
+            )
+            .initial_value(false)
+            .interact()?
+            {
+                let key_file: String = cliclack::input("Path to the service account key file:")
+                    .placeholder("/etc/goose/service-account.json")
+                    .interact()?;
+                envs.insert(KEY_FILE_ENV.to_string(), key_file);
+                McpAuthType::JwtBearer
+            } else {
+                if cliclack::confirm(
+                    "Sign in as an OAuth client registered with the server ahead of time? (for servers without dynamic client registration)",
+                )
+                .initial_value(false)
+                .interact()?
+                {
+                    let client_id: String = cliclack::input("OAuth client ID:").interact()?;
+                    envs.insert(CLIENT_ID_ENV.to_string(), client_id);
+                    if cliclack::confirm("Does the client have a secret?")
+                        .initial_value(false)
+                        .interact()?
+                    {
+                        let client_secret: String = cliclack::password("OAuth client secret:")
+                            .mask('▪')
+                            .interact()?;
+                        save_client_secret(&name, client_secret, &mut envs, &mut env_keys);
+                    }
+                }
+                McpAuthType::default()
+            };
+
+            let scopes: Vec<String> = if auth.is_some() {
+                Vec::new()
+            } else {
+                let scopes: String = cliclack::input(
+                    "OAuth scopes to ask for (space separated, empty for the server's default):",
+                )
+                .required(false)
+                .interact()?;
+                scopes.split_whitespace().map(str::to_string).collect()
+            };
+
             if add_env {
                 loop {
                     let key: String = cliclack::input("Environment variable name:")
@@ -1090,6 +1168,11 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     envs: Envs::new(envs),
                     env_keys,
                     headers,
+                    auth_type,
+                    auth,
+                    scopes,
+                    oauth_http: Default::default(),
+                    oauth_profile: None,
                     description,
                     timeout: Some(timeout),
                     bundled: None,
@@ -1105,6 +1188,68 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Keep the OAuth client secret of the extension `name` in the secrets store, under a key of
+/// its own so another extension's secret doesn't replace it, or in its envs if that fails
+fn save_client_secret(
+    name: &str,
+    client_secret: String,
+    envs: &mut HashMap<String, String>,
+    env_keys: &mut Vec<String>,
+) {
+    let key = extension_secret_key(name, CLIENT_SECRET_ENV);
+    match Config::global().set_secret(&key, Value::String(client_secret.clone())) {
+        Ok(_) => env_keys.push(CLIENT_SECRET_ENV.to_string()),
+        Err(_) => {
+            envs.insert(CLIENT_SECRET_ENV.to_string(), client_secret);
+        }
+    }
+}
+
+/// Ask whether a remote extension sends a static token or API key, and keep it in the secrets
+/// store under `<NAME>_AUTH`
+fn remote_auth_dialog(name: &str) -> Result<Option<RemoteAuth>, Box<dyn Error>> {
+    let scheme = cliclack::select("How does this server authenticate goose?")
+        .item(
+            "oauth",
+            "OAuth",
+            "Sign in in the browser or as a machine client",
+        )
+        .item("bearer", "Bearer token", "Authorization: Bearer <token>")
+        .item(
+            "header",
+            "API key header",
+            "The key in a header such as X-API-Key",
+        )
+        .item("basic", "User name and password", "HTTP basic auth")
+        .interact()?;
+    let scheme = match scheme {
+        "bearer" => AuthScheme::Bearer,
+        "basic" => AuthScheme::Basic,
+        "header" => {
+            let header: String = cliclack::input("Header name:")
+                .placeholder("X-API-Key")
+                .validate(|input: &String| {
+                    format!("header:{}", input)
+                        .parse::<AuthScheme>()
+                        .map(|_| ())
+                        .map_err(|_| "Please enter a valid header name")
+                })
+                .interact()?;
+            AuthScheme::Header(header.trim().to_string())
+        }
+        _ => return Ok(None),
+    };
+    let prompt = if scheme == AuthScheme::Basic {
+        "User name and password (user:password):"
+    } else {
+        "Token or API key:"
+    };
+    let value: String = cliclack::password(prompt).mask('▪').interact()?;
+    let secret = format!("{}_AUTH", name_to_key(name).to_uppercase());
+    Config::global().set_secret(&secret, Value::String(value))?;
+    Ok(Some(RemoteAuth { scheme, secret }))
+}
+
 pub fn remove_extension_dialog() -> Result<(), Box<dyn Error>> {
     let extensions = ExtensionConfigManager::get_all()?;
 
@@ -0,0 +1,670 @@
+use anyhow::{Context, Result};
+#[cfg(unix)]
+use console::style;
+use etcetera::{choose_app_strategy, AppStrategy};
+use std::path::PathBuf;
+
+const SOCKET_FILE: &str = "goosed.sock";
+/// The version of goosed's events the attach client reads
+#[cfg(unix)]
+const EVENT_VERSION_HEADER: &str = "X-Goose-Event-Version";
+#[cfg(unix)]
+const EVENT_VERSION: u32 = 2;
+#[cfg(unix)]
+const PID_FILE: &str = "goosed.pid";
+#[cfg(unix)]
+const KEY_FILE: &str = "goosed.key";
+
+/// Directory holding the daemon socket, pid and secret key files, only open to the user
+fn daemon_dir() -> Result<PathBuf> {
+    let strategy = choose_app_strategy(crate::APP_STRATEGY.clone())?;
+    let dir = strategy
+        .in_state_dir("daemon")
+        .unwrap_or_else(|| strategy.in_data_dir("daemon"));
+    std::fs::create_dir_all(&dir).context("Failed to create daemon directory")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+            .context("Failed to restrict the daemon directory")?;
+    }
+    Ok(dir)
+}
+
+pub fn default_socket_path() -> Result<PathBuf> {
+    Ok(daemon_dir()?.join(SOCKET_FILE))
+}
+
+/// The goosed binary shipped next to the goose binary, falling back to PATH lookup
+#[cfg(unix)]
+fn goosed_binary() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("goosed")))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from("goosed"))
+}
+
+/// Secret key the daemon expects in X-Secret-Key, created on first start
+#[cfg(unix)]
+fn daemon_secret() -> Result<String> {
+    read_or_create_secret(&daemon_dir()?.join(KEY_FILE))
+}
+
+/// The key in `path`, written there first if there is none. The file is only ever readable by
+/// the user, from the moment it's created.
+#[cfg(unix)]
+fn read_or_create_secret(path: &std::path::Path) -> Result<String> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let key = uuid::Uuid::new_v4().to_string();
+    let created = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path);
+    match created {
+        Ok(mut file) => {
+            file.write_all(key.as_bytes())
+                .context("Failed to write daemon key")?;
+            Ok(key)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            // Keys written before this kept whatever mode the umask gave them
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                .context("Failed to restrict the daemon key")?;
+            let key = std::fs::read_to_string(path).context("Failed to read daemon key")?;
+            Ok(key.trim().to_string())
+        }
+        Err(e) => Err(e).context("Failed to write daemon key"),
+    }
+}
+
+#[cfg(unix)]
+async fn ping(socket_path: &std::path::Path, secret: &str) -> bool {
+    let Ok(response) = send_request(socket_path, secret, "GET", "/status", None).await else {
+        return false;
+    };
+    response.status == 200
+}
+
+#[cfg(unix)]
+pub async fn handle_daemon_start(socket: Option<PathBuf>) -> Result<()> {
+    let socket_path = match socket {
+        Some(path) => path,
+        None => default_socket_path()?,
+    };
+    let secret = daemon_secret()?;
+
+    if ping(&socket_path, &secret).await {
+        println!(
+            "goosed is already running on {}",
+            style(socket_path.display()).cyan()
+        );
+        return Ok(());
+    }
+
+    let child = std::process::Command::new(goosed_binary())
+        .arg("agent")
+        .env("GOOSE_SOCKET_PATH", &socket_path)
+        .env("GOOSE_SERVER__SECRET_KEY", &secret)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .context("Failed to start goosed - is it installed next to goose?")?;
+    std::fs::write(daemon_dir()?.join(PID_FILE), child.id().to_string())?;
+
+    for _ in 0..50 {
+        if ping(&socket_path, &secret).await {
+            println!(
+                "goosed started (pid {}) on {}",
+                child.id(),
+                style(socket_path.display()).cyan()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    Err(anyhow::anyhow!(
+        "goosed did not start listening on {} in time",
+        socket_path.display()
+    ))
+}
+
+#[cfg(unix)]
+pub async fn handle_daemon_status(socket: Option<PathBuf>) -> Result<()> {
+    let socket_path = match socket {
+        Some(path) => path,
+        None => default_socket_path()?,
+    };
+    if ping(&socket_path, &daemon_secret()?).await {
+        println!(
+            "goosed is {} on {}",
+            style("running").green(),
+            style(socket_path.display()).cyan()
+        );
+    } else {
+        println!("goosed is {}", style("not running").yellow());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub async fn handle_daemon_stop() -> Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    let pid_path = daemon_dir()?.join(PID_FILE);
+    let Ok(pid) = std::fs::read_to_string(&pid_path) else {
+        println!("goosed is {}", style("not running").yellow());
+        return Ok(());
+    };
+    let pid: i32 = pid.trim().parse().context("Invalid goosed pid file")?;
+
+    match kill(Pid::from_raw(pid), Signal::SIGTERM) {
+        Ok(()) => println!("Stopped goosed (pid {})", pid),
+        Err(nix::errno::Errno::ESRCH) => {
+            println!("goosed is {}", style("not running").yellow())
+        }
+        Err(e) => return Err(anyhow::anyhow!("Failed to stop goosed: {}", e)),
+    }
+    let _ = std::fs::remove_file(pid_path);
+    Ok(())
+}
+
+/// A response of the daemon, read up to the start of its body
+#[cfg(unix)]
+struct DaemonResponse {
+    status: u16,
+    chunked: bool,
+    done: bool,
+    reader: tokio::io::BufReader<tokio::net::UnixStream>,
+}
+
+#[cfg(unix)]
+impl DaemonResponse {
+    /// Read the status line and headers of the response coming in on `stream`
+    async fn read(stream: tokio::net::UnixStream) -> Result<Self> {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut reader = tokio::io::BufReader::new(stream);
+        let mut status = String::new();
+        reader.read_line(&mut status).await?;
+        let status = status
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .context("Malformed response from goosed")?;
+        let mut chunked = false;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                chunked |= name.trim().eq_ignore_ascii_case("transfer-encoding")
+                    && value.trim().eq_ignore_ascii_case("chunked");
+            }
+        }
+        Ok(Self {
+            status,
+            chunked,
+            done: false,
+            reader,
+        })
+    }
+
+    /// The next piece of the body, or `None` once all of it was read
+    async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+        if self.done {
+            return Ok(None);
+        }
+        if !self.chunked {
+            self.done = true;
+            let mut body = Vec::new();
+            self.reader.read_to_end(&mut body).await?;
+            return Ok(Some(body));
+        }
+
+        let mut size = String::new();
+        self.reader.read_line(&mut size).await?;
+        let size = size.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16).context("Malformed chunk from goosed")?;
+        if size == 0 {
+            self.done = true;
+            return Ok(None);
+        }
+        let mut chunk = vec![0; size + 2];
+        self.reader.read_exact(&mut chunk).await?;
+        chunk.truncate(size);
+        Ok(Some(chunk))
+    }
+
+    async fn json(mut self, path: &str) -> Result<serde_json::Value> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.next_chunk().await? {
+            body.extend(chunk);
+        }
+        if self.status != 200 {
+            anyhow::bail!("goosed answered {} with status {}", path, self.status);
+        }
+        if body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(serde_json::Value::Null);
+        }
+        serde_json::from_slice(&body).with_context(|| format!("Malformed answer to {}", path))
+    }
+}
+
+#[cfg(unix)]
+async fn send_request(
+    socket_path: &std::path::Path,
+    secret: &str,
+    method: &str,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<DaemonResponse> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut stream = tokio::net::UnixStream::connect(socket_path)
+        .await
+        .with_context(|| format!("Failed to connect to goosed on {}", socket_path.display()))?;
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nX-Secret-Key: {}\r\n{}: {}\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        secret,
+        EVENT_VERSION_HEADER,
+        EVENT_VERSION,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await?;
+    DaemonResponse::read(stream).await
+}
+
+#[cfg(unix)]
+async fn post_json(
+    socket_path: &std::path::Path,
+    secret: &str,
+    path: &str,
+    body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    send_request(socket_path, secret, "POST", path, Some(&body))
+        .await?
+        .json(path)
+        .await
+}
+
+/// Takes the events complete in `buffer` off its front, as the values of their `data:` lines
+#[cfg(unix)]
+fn take_events(buffer: &mut String) -> Vec<String> {
+    let mut events = Vec::new();
+    while let Some(end) = buffer.find("\n\n") {
+        let event: String = buffer.drain(..end + 2).collect();
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim_start)
+            .collect();
+        if !data.is_empty() {
+            events.push(data.join("\n"));
+        }
+    }
+    events
+}
+
+/// Gives the daemon's agent for `session_id` the configured provider, and the enabled
+/// extensions unless it has tools already from an earlier attach
+#[cfg(unix)]
+async fn prepare_agent(
+    socket_path: &std::path::Path,
+    secret: &str,
+    session_id: &str,
+) -> Result<()> {
+    use goose::config::{Config, ExtensionConfigManager};
+    use serde_json::json;
+
+    let config = Config::global();
+    let provider: String = config
+        .get_param("GOOSE_PROVIDER")
+        .context("No provider configured. Run 'goose configure' first")?;
+    post_json(
+        socket_path,
+        secret,
+        "/agent/update_provider",
+        json!({
+            "provider": provider,
+            "model": config.get_param::<String>("GOOSE_MODEL").ok(),
+            "session_id": session_id,
+        }),
+    )
+    .await?;
+
+    let tools_path = format!("/agent/tools?session_id={}", session_id);
+    let tools = send_request(socket_path, secret, "GET", &tools_path, None)
+        .await?
+        .json(&tools_path)
+        .await?;
+    if tools.as_array().is_some_and(|tools| !tools.is_empty()) {
+        return Ok(());
+    }
+
+    for extension in ExtensionConfigManager::get_all()?
+        .into_iter()
+        .filter(|extension| extension.enabled)
+    {
+        let name = extension.config.name();
+        let mut request = serde_json::to_value(&extension.config)?;
+        request["session_id"] = json!(session_id);
+        let response = post_json(socket_path, secret, "/extensions/add", request).await;
+        let error = match response {
+            Ok(response) if response["error"].as_bool() == Some(true) => Some(
+                response["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string(),
+            ),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(error) = error {
+            eprintln!(
+                "{}",
+                style(format!("Failed to start extension {}: {}", name, error)).yellow()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Asks whether the tool call of `confirmation` may go ahead, and tells the daemon
+#[cfg(unix)]
+async fn confirm_tool_call(
+    socket_path: &std::path::Path,
+    secret: &str,
+    session_id: &str,
+    confirmation: &goose::conversation::message::ToolConfirmationRequest,
+) -> Result<()> {
+    if let Some(prompt) = &confirmation.prompt {
+        println!("\n{}", prompt);
+    }
+    let allowed = cliclack::confirm(format!(
+        "Goose would like to call {}, do you allow?",
+        confirmation.tool_name
+    ))
+    .interact()
+    .unwrap_or(false);
+    post_json(
+        socket_path,
+        secret,
+        "/confirm",
+        serde_json::json!({
+            "id": confirmation.id,
+            "action": if allowed { "allow_once" } else { "deny" },
+            "session_id": session_id,
+        }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Sends the conversation in `messages` to the daemon and renders its reply as it streams in,
+/// adding it to `messages`
+#[cfg(unix)]
+async fn reply(
+    socket_path: &std::path::Path,
+    secret: &str,
+    session_id: &str,
+    messages: &mut Vec<goose::conversation::message::Message>,
+) -> Result<()> {
+    use goose::conversation::message::{Message, MessageContent};
+    use serde_json::json;
+
+    let request = json!({"messages": messages, "session_id": session_id});
+    let mut response = send_request(socket_path, secret, "POST", "/reply", Some(&request)).await?;
+    if response.status != 200 {
+        return response.json("/reply").await.map(|_| ());
+    }
+
+    let mut buffer = String::new();
+    while let Some(chunk) = response.next_chunk().await? {
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        for event in take_events(&mut buffer) {
+            let event: serde_json::Value = match serde_json::from_str(&event) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("Ignoring malformed event from goosed: {}", e);
+                    continue;
+                }
+            };
+            match event["type"].as_str() {
+                Some("Message") => {
+                    let message: Message = serde_json::from_value(event["message"].clone())?;
+                    if let Some(MessageContent::ToolConfirmationRequest(confirmation)) =
+                        message.content.first()
+                    {
+                        confirm_tool_call(socket_path, secret, session_id, confirmation).await?;
+                        continue;
+                    }
+                    crate::session::render_message(&message, false);
+                    messages.push(message);
+                }
+                Some("HistoryReplaced") => {
+                    *messages = serde_json::from_value(event["messages"].clone())?;
+                }
+                Some("Error") => {
+                    eprintln!(
+                        "{}",
+                        style(event["error"].as_str().unwrap_or("unknown error")).red()
+                    );
+                }
+                Some("Finish") => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Opens a session on the running daemon, a new one in the working directory or `session_id`,
+/// and chats in it from this terminal
+#[cfg(unix)]
+pub async fn handle_daemon_attach(
+    socket: Option<PathBuf>,
+    session_id: Option<String>,
+) -> Result<()> {
+    use goose::conversation::message::Message;
+    use rustyline::error::ReadlineError;
+    use serde_json::json;
+
+    let socket_path = match socket {
+        Some(path) => path,
+        None => default_socket_path()?,
+    };
+    let secret = daemon_secret()?;
+    if !ping(&socket_path, &secret).await {
+        anyhow::bail!(
+            "goosed is not running on {}. Start it with 'goose daemon start'",
+            socket_path.display()
+        );
+    }
+
+    let session = match session_id {
+        Some(session_id) => {
+            post_json(
+                &socket_path,
+                &secret,
+                "/agent/resume",
+                json!({ "session_id": session_id }),
+            )
+            .await?
+        }
+        None => {
+            let working_dir = std::env::current_dir()?;
+            post_json(
+                &socket_path,
+                &secret,
+                "/agent/start",
+                json!({ "working_dir": working_dir.to_string_lossy() }),
+            )
+            .await?
+        }
+    };
+    let session_id = session["id"]
+        .as_str()
+        .context("goosed returned a session without an id")?
+        .to_string();
+    let mut messages: Vec<Message> = match &session["conversation"] {
+        serde_json::Value::Null => Vec::new(),
+        conversation => serde_json::from_value(conversation.clone())?,
+    };
+    prepare_agent(&socket_path, &secret, &session_id).await?;
+
+    println!(
+        "Attached to session {} on {}. Ctrl+D to detach.",
+        style(&session_id).cyan(),
+        style(socket_path.display()).cyan()
+    );
+    let mut editor = rustyline::DefaultEditor::new()?;
+    loop {
+        let line = match editor.readline("( O)> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        messages.push(Message::user().with_text(line));
+        if let Err(e) = reply(&socket_path, &secret, &session_id, &mut messages).await {
+            eprintln!("{}", style(format!("{:#}", e)).red());
+        }
+    }
+    println!("Detached; the session keeps running in goosed");
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn handle_daemon_start(_socket: Option<PathBuf>) -> Result<()> {
+    Err(anyhow::anyhow!("goose daemon requires unix domain sockets"))
+}
+
+#[cfg(not(unix))]
+pub async fn handle_daemon_status(_socket: Option<PathBuf>) -> Result<()> {
+    Err(anyhow::anyhow!("goose daemon requires unix domain sockets"))
+}
+
+#[cfg(not(unix))]
+pub async fn handle_daemon_stop() -> Result<()> {
+    Err(anyhow::anyhow!("goose daemon requires unix domain sockets"))
+}
+
+#[cfg(not(unix))]
+pub async fn handle_daemon_attach(
+    _socket: Option<PathBuf>,
+    _session_id: Option<String>,
+) -> Result<()> {
+    Err(anyhow::anyhow!("goose daemon requires unix domain sockets"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_take_events() {
+        let mut buffer = "data: {\"type\":\"Ping\"}\n\ndata: {\"type\":".to_string();
+        assert_eq!(take_events(&mut buffer), vec![r#"{"type":"Ping"}"#]);
+        assert_eq!(buffer, "data: {\"type\":");
+
+        buffer.push_str("\"Finish\"}\n\n: comment\n\n");
+        assert_eq!(take_events(&mut buffer), vec![r#"{"type":"Finish"}"#]);
+        assert!(buffer.is_empty());
+    }
+
+    /// The response `pieces` make up, written one at a time, so each read gets only part of it
+    #[cfg(unix)]
+    async fn response(pieces: &'static [&'static str]) -> DaemonResponse {
+        use tokio::io::AsyncWriteExt;
+
+        let (client, mut server) = tokio::net::UnixStream::pair().unwrap();
+        tokio::spawn(async move {
+            for piece in pieces {
+                server.write_all(piece.as_bytes()).await.unwrap();
+                server.flush().await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        });
+        DaemonResponse::read(client).await.unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_chunked_response_in_short_reads() {
+        let mut response = response(&[
+            "HTTP/1.1 200 OK\r\nTransfer-",
+            "Encoding: chunked\r\n\r\n",
+            "5\r\nda",
+            "ta:\r\n",
+            "a;ext=1\r\n {\"a\":1}\n\n\r\n0\r\n",
+            "\r\n",
+        ])
+        .await;
+        assert_eq!(response.status, 200);
+        assert!(response.chunked);
+        assert_eq!(response.next_chunk().await.unwrap().unwrap(), b"data:");
+        assert_eq!(
+            response.next_chunk().await.unwrap().unwrap(),
+            b" {\"a\":1}\n\n"
+        );
+        assert_eq!(response.next_chunk().await.unwrap(), None);
+        assert_eq!(response.next_chunk().await.unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plain_response_in_short_reads() {
+        let response = response(&[
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n",
+            "\r\n{\"id\":",
+            "\"s1\"}",
+        ])
+        .await;
+        assert!(!response.chunked);
+        let body = response.json("/agent/start").await.unwrap();
+        assert_eq!(body, serde_json::json!({"id": "s1"}));
+
+        let refused = response(&["HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n"]).await;
+        assert_eq!(refused.status, 401);
+        assert!(refused.json("/agent/start").await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_secret_is_private_from_the_start() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(KEY_FILE);
+        let key = read_or_create_secret(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(read_or_create_secret(&path).unwrap(), key);
+
+        // An older key readable by others is kept, but made private
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(read_or_create_secret(&path).unwrap(), key);
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
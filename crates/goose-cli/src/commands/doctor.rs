@@ -0,0 +1,34 @@
+use anyhow::{bail, Result};
+use console::style;
+
+use crate::session::startup_warnings::WarningCode;
+
+fn describe(code: WarningCode) -> String {
+    format!(
+        "{} {}\n\n{}",
+        style(code.code()).bold(),
+        style(code.title()).bold(),
+        code.explanation()
+    )
+}
+
+/// Explain a startup warning code, or list them all when no code is given
+pub fn handle_doctor_explain(code: Option<String>) -> Result<()> {
+    let Some(code) = code else {
+        for known in WarningCode::ALL {
+            println!("{}  {}", style(known.code()).bold(), known.title());
+        }
+        return Ok(());
+    };
+
+    match WarningCode::from_code(&code) {
+        Some(known) => {
+            println!("{}", describe(known));
+            Ok(())
+        }
+        None => bail!(
+            "Unknown code '{}'. Run `goose doctor explain` to list the known codes.",
+            code
+        ),
+    }
+}
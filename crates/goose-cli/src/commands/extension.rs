@@ -0,0 +1,284 @@
+//! `goose extension test`: check that an extension works before relying on it.
+
+use anyhow::{bail, Context, Result};
+use console::style;
+use goose::agents::ExtensionManager;
+use goose::config::ExtensionConfigManager;
+use rmcp::model::{CallToolRequestParam, CallToolResult, JsonObject, Tool};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+enum Outcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+struct Check {
+    name: String,
+    outcome: Outcome,
+    elapsed: Option<Duration>,
+}
+
+impl Check {
+    fn print(&self) {
+        let (label, detail) = match &self.outcome {
+            Outcome::Pass(detail) => (style("PASS").green().bold(), detail),
+            Outcome::Fail(detail) => (style("FAIL").red().bold(), detail),
+            Outcome::Skip(detail) => (style("SKIP").yellow().bold(), detail),
+        };
+        let elapsed = self
+            .elapsed
+            .map(|elapsed| format!("{}ms", elapsed.as_millis()))
+            .unwrap_or_default();
+        println!("  {}  {:<24} {:>8}  {}", label, self.name, elapsed, detail);
+    }
+}
+
+fn required_arguments(tool: &Tool) -> usize {
+    tool.input_schema
+        .get("required")
+        .and_then(|required| required.as_array())
+        .map_or(0, Vec::len)
+}
+
+/// The tool to suggest calling: one the server says is read-only, not destructive and without
+/// required arguments. Tools that stay away from the outside world go first, then idempotent
+/// ones, then by name.
+fn suggested_tool(tools: &[Tool]) -> Option<&Tool> {
+    tools
+        .iter()
+        .filter(|tool| {
+            tool.annotations.as_ref().is_some_and(|annotations| {
+                annotations.read_only_hint == Some(true)
+                    && annotations.destructive_hint != Some(true)
+            }) && required_arguments(tool) == 0
+        })
+        .min_by_key(|tool| {
+            let annotations = tool.annotations.as_ref();
+            (
+                annotations.and_then(|a| a.open_world_hint) != Some(false),
+                annotations.and_then(|a| a.idempotent_hint) != Some(true),
+                tool.name.clone(),
+            )
+        })
+}
+
+/// Tools whose input schema a model couldn't fill in
+fn schema_problems(tools: &[Tool]) -> Vec<String> {
+    tools
+        .iter()
+        .filter(|tool| {
+            tool.input_schema.get("type").and_then(|kind| kind.as_str()) != Some("object")
+        })
+        .map(|tool| format!("{}: input schema is not an object", tool.name))
+        .collect()
+}
+
+pub async fn handle_extension_test(
+    name: String,
+    tool: Option<String>,
+    arguments: Option<String>,
+    timeout: u64,
+) -> Result<()> {
+    let config = ExtensionConfigManager::get_config_by_name(&name)?
+        .with_context(|| format!("No extension called '{}'; `goose configure` adds one", name))?;
+    let arguments: Option<JsonObject> = arguments
+        .map(|arguments| serde_json::from_str(&arguments).context("--args must be a JSON object"))
+        .transpose()?;
+    let timeout = Duration::from_secs(timeout);
+    let key = config.key();
+
+    println!("Testing {}\n", style(&name).bold());
+    let manager = ExtensionManager::new();
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    let connected = tokio::time::timeout(timeout, manager.add_extension(config)).await;
+    checks.push(Check {
+        name: "connect".to_string(),
+        elapsed: Some(started.elapsed()),
+        outcome: match &connected {
+            Ok(Ok(())) => Outcome::Pass(String::new()),
+            Ok(Err(e)) => Outcome::Fail(e.to_string()),
+            Err(_) => Outcome::Fail(too_slow(timeout)),
+        },
+    });
+
+    if let Ok(Ok(())) = connected {
+        let started = Instant::now();
+        let listed = tokio::time::timeout(timeout, manager.get_prefixed_tools(None)).await;
+        let elapsed = started.elapsed();
+        match listed {
+            Ok(Ok(tools)) => {
+                checks.push(Check {
+                    name: "list tools".to_string(),
+                    elapsed: Some(elapsed),
+                    outcome: Outcome::Pass(format!("{} tools", tools.len())),
+                });
+                let problems = schema_problems(&tools);
+                checks.push(Check {
+                    name: "tool schemas".to_string(),
+                    elapsed: None,
+                    outcome: if problems.is_empty() {
+                        Outcome::Pass(String::new())
+                    } else {
+                        Outcome::Fail(problems.join("; "))
+                    },
+                });
+                checks.push(call_check(&manager, &tools, tool, arguments, timeout).await);
+            }
+            Ok(Err(e)) => checks.push(Check {
+                name: "list tools".to_string(),
+                elapsed: Some(elapsed),
+                outcome: Outcome::Fail(e.to_string()),
+            }),
+            Err(_) => checks.push(Check {
+                name: "list tools".to_string(),
+                elapsed: Some(elapsed),
+                outcome: Outcome::Fail(too_slow(timeout)),
+            }),
+        }
+    }
+    // A connection that timed out may have come up since
+    if let Err(e) = manager.remove_extension(&key).await {
+        tracing::debug!("Failed to stop {}: {}", name, e);
+    }
+
+    for check in &checks {
+        check.print();
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| matches!(check.outcome, Outcome::Fail(_)))
+        .count();
+    println!();
+    if failed > 0 {
+        bail!("{} of {} checks failed", failed, checks.len());
+    }
+    println!("{} works", name);
+    Ok(())
+}
+
+fn too_slow(timeout: Duration) -> String {
+    format!("no answer within {}s", timeout.as_secs())
+}
+
+/// The text of a tool's result, for showing why it failed
+fn result_text(result: &CallToolResult) -> String {
+    let text: Vec<&str> = result
+        .content
+        .iter()
+        .filter_map(|content| content.as_text())
+        .map(|text| text.text.as_str())
+        .collect();
+    match text.join(" ").trim() {
+        "" => "the tool returned an error".to_string(),
+        text => text.to_string(),
+    }
+}
+
+/// Call the tool named with `--tool`, or suggest one when there is none
+async fn call_check(
+    manager: &ExtensionManager,
+    tools: &[Tool],
+    requested: Option<String>,
+    arguments: Option<JsonObject>,
+    timeout: Duration,
+) -> Check {
+    // Tools are listed as `<extension>__<tool>`
+    let short_name = |tool: &Tool| {
+        tool.name
+            .split_once("__")
+            .map_or(tool.name.to_string(), |(_, name)| name.to_string())
+    };
+    let Some(requested) = requested else {
+        return Check {
+            name: "call tool".to_string(),
+            elapsed: None,
+            outcome: Outcome::Skip(match suggested_tool(tools) {
+                Some(tool) => format!(
+                    "pick one with --tool, e.g. --tool {}, which says it only reads",
+                    short_name(tool)
+                ),
+                None => "pick one with --tool".to_string(),
+            }),
+        };
+    };
+    let Some(tool) = tools
+        .iter()
+        .find(|tool| tool.name == requested || short_name(tool) == requested)
+    else {
+        return Check {
+            name: "call tool".to_string(),
+            elapsed: None,
+            outcome: Outcome::Fail(format!("it has no tool called {}", requested)),
+        };
+    };
+
+    let name = format!("call {}", short_name(tool));
+    let started = Instant::now();
+    let call = CallToolRequestParam {
+        name: tool.name.clone(),
+        arguments: arguments.or_else(|| Some(JsonObject::new())),
+    };
+    let cancel = CancellationToken::new();
+    let outcome = match tokio::time::timeout(timeout, manager.call_tool(call, cancel.clone())).await
+    {
+        Ok(Ok(result)) if result.is_error == Some(true) => Outcome::Fail(result_text(&result)),
+        Ok(Ok(result)) => Outcome::Pass(format!("{} content items", result.content.len())),
+        Ok(Err(e)) => Outcome::Fail(e.to_string()),
+        Err(_) => {
+            cancel.cancel();
+            Outcome::Fail(too_slow(timeout))
+        }
+    };
+    Check {
+        name,
+        elapsed: Some(started.elapsed()),
+        outcome,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ToolAnnotations;
+    use rmcp::object;
+
+    fn tool(name: &str, read_only: bool, open_world: bool, required: &[&str]) -> Tool {
+        Tool::new(
+            name.to_string(),
+            String::new(),
+            object!({"type": "object", "required": required}),
+        )
+        .annotate(ToolAnnotations {
+            title: None,
+            read_only_hint: Some(read_only),
+            destructive_hint: Some(!read_only),
+            idempotent_hint: None,
+            open_world_hint: Some(open_world),
+        })
+    }
+
+    #[test]
+    fn test_suggested_tool() {
+        let tools = vec![
+            tool("ext__write", false, false, &[]),
+            tool("ext__fetch", true, true, &[]),
+            tool("ext__read", true, false, &["path"]),
+            tool("ext__status", true, false, &[]),
+        ];
+        assert_eq!(suggested_tool(&tools).unwrap().name, "ext__status");
+        assert_eq!(suggested_tool(&tools[..3]).unwrap().name, "ext__fetch");
+        assert!(suggested_tool(&tools[..1]).is_none());
+    }
+
+    #[test]
+    fn test_schema_problems() {
+        let mut tools = vec![tool("ext__status", true, false, &[])];
+        assert!(schema_problems(&tools).is_empty());
+        tools.push(Tool::new("ext__bad", "", object!({"type": "string"})));
+        assert_eq!(schema_problems(&tools).len(), 1);
+    }
+}
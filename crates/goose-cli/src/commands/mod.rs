@@ -1,10 +1,20 @@
 pub mod acp;
+pub mod auth;
 pub mod bench;
+pub mod config;
 pub mod configure;
+pub mod daemon;
+pub mod doctor;
+pub mod extension;
 pub mod info;
+pub mod onboarding;
 pub mod project;
 pub mod recipe;
+pub mod redact;
+pub mod run;
+pub mod runs;
 pub mod schedule;
 pub mod session;
+pub mod stats;
 pub mod update;
 pub mod web;
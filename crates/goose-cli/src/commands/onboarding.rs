@@ -0,0 +1,229 @@
+//! The guided setup shown the first time goose runs without a config.
+
+use cliclack::spinner;
+use console::style;
+use goose::agents::ExtensionConfig;
+use goose::config::{Config, ExtensionConfigManager, ExtensionEntry};
+use goose::providers::base::ProviderMetadata;
+use goose::providers::providers;
+use std::error::Error;
+use std::path::PathBuf;
+
+use super::configure::configure_provider_dialog_with_default;
+use crate::session::{build_session, SessionBuilderConfig};
+
+const SAMPLE_PROMPT: &str = "In two or three sentences, say hello and describe what you can help me with in this directory.";
+
+const EXAMPLE_RECIPE_FILE: &str = "example-recipe.yaml";
+
+const EXAMPLE_RECIPE: &str = r#"version: 1.0.0
+title: Summarize a directory
+description: Give an overview of what a directory contains and how it fits together
+instructions: |
+  You explain projects to people who are new to them. Read the files you need, then give a
+  short, well organised overview.
+prompt: |
+  Look at the files in {{ directory }} and summarize what they contain and how they fit together.
+parameters:
+  - key: directory
+    input_type: string
+    requirement: optional
+    default: "."
+    description: The directory to summarize
+extensions:
+  - type: builtin
+    name: developer
+    description: Read and edit files and run shell commands
+"#;
+
+/// Providers whose required credentials are all set in the environment. Providers that need
+/// nothing without a default (like a local ollama) don't count, there is nothing to detect.
+fn providers_with_env_keys<'a>(
+    providers: &'a [ProviderMetadata],
+    is_set: impl Fn(&str) -> bool,
+) -> Vec<&'a ProviderMetadata> {
+    providers
+        .iter()
+        .filter(|provider| {
+            let mut needed = provider
+                .config_keys
+                .iter()
+                .filter(|key| key.required && key.default.is_none())
+                .peekable();
+            needed.peek().is_some() && needed.all(|key| is_set(&key.name))
+        })
+        .collect()
+}
+
+pub async fn handle_onboarding() -> Result<(), Box<dyn Error>> {
+    let config = Config::global();
+
+    println!();
+    println!(
+        "{}",
+        style("Welcome to goose! This will take a minute: we'll connect a model provider, then try it out.")
+            .dim()
+    );
+    println!(
+        "{}",
+        style("  run 'goose configure' later to change anything you pick here").dim()
+    );
+    println!();
+    cliclack::intro(style(" goose-onboarding ").on_cyan().black())?;
+
+    let available = providers();
+    let detected = providers_with_env_keys(&available, |name| std::env::var(name).is_ok());
+    if !detected.is_empty() {
+        let names: Vec<&str> = detected.iter().map(|p| p.display_name.as_str()).collect();
+        let _ = cliclack::log::info(format!(
+            "Found credentials in your environment for {}",
+            names.join(", ")
+        ));
+    }
+
+    match configure_provider_dialog_with_default(detected.first().map(|p| p.name.as_str())).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = config.clear();
+            cliclack::outro(format!(
+                "We did not save your config, inspect your credentials and run '{}' again",
+                style("goose").cyan()
+            ))?;
+            return Ok(());
+        }
+        Err(e) => {
+            let _ = config.clear();
+            cliclack::outro(format!(
+                "{} {}\n   Run '{}' again to retry",
+                style("Setup failed:").red(),
+                e,
+                style("goose").cyan()
+            ))?;
+            return Ok(());
+        }
+    }
+
+    let developer = cliclack::confirm(
+        "Enable the developer extension, so goose can read and edit files and run commands?",
+    )
+    .initial_value(true)
+    .interact()?;
+    if developer {
+        ExtensionConfigManager::set(ExtensionEntry {
+            enabled: true,
+            config: ExtensionConfig::default(),
+        })?;
+    }
+
+    cliclack::note(
+        "Try a command",
+        format!(
+            "{}\n{}",
+            style(format!("goose run --text \"{}\"", SAMPLE_PROMPT)).cyan(),
+            style("runs one instruction and exits; plain 'goose' starts a chat").dim()
+        ),
+    )?;
+    if cliclack::confirm("Run it now?")
+        .initial_value(true)
+        .interact()?
+    {
+        let mut session = build_session(SessionBuilderConfig {
+            no_session: true,
+            quiet: true,
+            ..Default::default()
+        })
+        .await;
+        if let Err(e) = session.headless(SAMPLE_PROMPT.to_string()).await {
+            let _ = cliclack::log::warning(format!("The sample command failed: {}", e));
+        }
+    }
+
+    if cliclack::confirm("Create an example recipe to show how goose automates a task?")
+        .initial_value(true)
+        .interact()?
+    {
+        let path: String = cliclack::input("Where should it go?")
+            .default_input(EXAMPLE_RECIPE_FILE)
+            .interact()?;
+        let path = PathBuf::from(path);
+        if path.exists() {
+            let _ = cliclack::log::warning(format!(
+                "{} already exists, leaving it as it is",
+                path.display()
+            ));
+        } else {
+            let spin = spinner();
+            spin.start("Writing the example recipe...");
+            std::fs::write(&path, EXAMPLE_RECIPE)?;
+            spin.stop(format!("Wrote {}", path.display()));
+            let _ = cliclack::log::info(format!(
+                "Run it with {}",
+                style(format!(
+                    "goose run --recipe {} --params directory=.",
+                    path.display()
+                ))
+                .cyan()
+            ));
+        }
+    }
+
+    cliclack::outro(format!(
+        "You're all set. Run '{}' to start a session",
+        style("goose").cyan()
+    ))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goose::providers::base::ConfigKey;
+    use goose::recipe::Recipe;
+
+    fn provider(name: &str, keys: Vec<ConfigKey>) -> ProviderMetadata {
+        ProviderMetadata::new(name, name, "", "model", vec![], "", keys)
+    }
+
+    #[test]
+    fn test_providers_with_env_keys() {
+        let providers = vec![
+            provider(
+                "openai",
+                vec![
+                    ConfigKey::new("OPENAI_API_KEY", true, true, None),
+                    ConfigKey::new("OPENAI_HOST", true, false, Some("https://api.openai.com")),
+                ],
+            ),
+            provider(
+                "azure",
+                vec![
+                    ConfigKey::new("AZURE_OPENAI_API_KEY", true, true, None),
+                    ConfigKey::new("AZURE_OPENAI_ENDPOINT", true, false, None),
+                ],
+            ),
+            provider(
+                "ollama",
+                vec![ConfigKey::new(
+                    "OLLAMA_HOST",
+                    true,
+                    false,
+                    Some("localhost"),
+                )],
+            ),
+        ];
+
+        let set = ["OPENAI_API_KEY", "AZURE_OPENAI_API_KEY", "OLLAMA_HOST"];
+        let detected = providers_with_env_keys(&providers, |name| set.contains(&name));
+        assert_eq!(
+            detected.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+            vec!["openai"]
+        );
+    }
+
+    #[test]
+    fn test_example_recipe_is_valid() {
+        let recipe = Recipe::from_content(EXAMPLE_RECIPE).unwrap();
+        assert_eq!(recipe.title, "Summarize a directory");
+        assert_eq!(recipe.parameters.unwrap()[0].key, "directory");
+    }
+}
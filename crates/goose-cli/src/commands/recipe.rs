@@ -1,6 +1,8 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use console::style;
+use std::path::PathBuf;
 
+use crate::recipes::examples::{find_example, project_name, EXAMPLE_RECIPES};
 use crate::recipes::github_recipe::RecipeSource;
 use crate::recipes::recipe::load_recipe_for_validation;
 use crate::recipes::search_recipe::list_available_recipes;
@@ -129,6 +131,85 @@ pub fn handle_list(format: &str, verbose: bool) -> Result<()> {
     Ok(())
 }
 
+/// Lists the bundled example recipes, shows one, or copies one into the current directory
+///
+/// # Arguments
+///
+/// * `name` - Example to show or copy; all are listed without one
+/// * `copy` - Write the example to `output` instead of showing it
+/// * `project` - Project name to fill in; the current directory's name by default
+/// * `output` - Where to copy the example; `<name>.yaml` in the current directory by default
+/// * `force` - Overwrite `output` if it exists
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub fn handle_examples(
+    name: Option<String>,
+    copy: bool,
+    project: Option<String>,
+    output: Option<PathBuf>,
+    force: bool,
+) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let project = project.unwrap_or_else(|| project_name(&cwd));
+
+    let Some(name) = name else {
+        println!("Example recipes:");
+        for example in EXAMPLE_RECIPES {
+            let description = example
+                .recipe(&project)
+                .map(|recipe| recipe.description)
+                .unwrap_or_default();
+            println!("  {:<20} {}", style(example.name).bold(), description);
+        }
+        println!();
+        println!(
+            "Show one with `goose recipe examples <name>`, or start from it with `goose recipe examples <name> --copy`."
+        );
+        return Ok(());
+    };
+    let Some(example) = find_example(&name) else {
+        let names: Vec<&str> = EXAMPLE_RECIPES.iter().map(|example| example.name).collect();
+        bail!(
+            "No example recipe called '{}'; there are {}",
+            name,
+            names.join(", ")
+        );
+    };
+
+    let content = example.render(&project);
+    if !copy {
+        crate::session::print_code(&content, "YAML");
+        return Ok(());
+    }
+
+    let output = output.unwrap_or_else(|| cwd.join(format!("{}.yaml", example.name)));
+    if output.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite it",
+            output.display()
+        );
+    }
+    std::fs::write(&output, content)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    println!(
+        "{} Copied the {} example to {}",
+        style("✓").green().bold(),
+        example.name,
+        output.display()
+    );
+    if output.parent() == Some(cwd.as_path()) {
+        if let Some(stem) = output.file_stem() {
+            println!(
+                "Edit it to fit, then run it with `goose run --recipe {}`",
+                stem.to_string_lossy()
+            );
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
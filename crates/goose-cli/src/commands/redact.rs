@@ -0,0 +1,329 @@
+//! `goose session redact`: copy a session with what shouldn't be shared removed.
+
+use anyhow::{Context, Result};
+use cliclack::multiselect;
+use goose::conversation::message::{Message, MessageContent};
+use goose::conversation::Conversation;
+use goose::session::SessionManager;
+use goose::utils::safe_truncate;
+use regex::Regex;
+use rmcp::model::{Content, RawContent, Role};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+const REDACTED: &str = "[redacted]";
+const REDACTED_PATH: &str = "[redacted path]";
+const REDACTED_TOOL_OUTPUT: &str = "[tool output redacted]";
+const PREVIEW_LENGTH: usize = 60;
+/// Shorter environment values are too likely to match ordinary words
+const MIN_ENV_VALUE_LENGTH: usize = 6;
+
+/// What to take out of a session
+#[derive(Debug, Default)]
+pub struct RedactionOptions {
+    /// Indexes of messages to drop entirely
+    pub drop_messages: HashSet<usize>,
+    pub tool_outputs: bool,
+    pub paths: bool,
+    pub env_values: bool,
+    pub patterns: Vec<Regex>,
+}
+
+struct Redactor {
+    /// Literal values and what replaces them, longest first so overlapping values go whole
+    literals: Vec<(String, String)>,
+    rules: Vec<(Regex, String)>,
+    tool_outputs: bool,
+}
+
+impl Redactor {
+    fn new(options: &RedactionOptions, env: &[(String, String)]) -> Self {
+        let mut literals: Vec<(String, String)> = if options.env_values {
+            env.iter()
+                .filter(|(_, value)| value.chars().count() >= MIN_ENV_VALUE_LENGTH)
+                .map(|(name, value)| (value.clone(), format!("${}", name)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        literals.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+        let mut rules: Vec<(Regex, String)> = options
+            .patterns
+            .iter()
+            .map(|pattern| (pattern.clone(), REDACTED.to_string()))
+            .collect();
+        if options.paths {
+            // Unix paths with at least two components, Windows drive paths and ~/ paths
+            let paths = Regex::new(r"(?:[A-Za-z]:\\|~/|/)[\w.\-@]+(?:[/\\][\w.\-@]+)+[/\\]?")
+                .expect("valid path pattern");
+            rules.push((paths, REDACTED_PATH.to_string()));
+        }
+
+        Self {
+            literals,
+            rules,
+            tool_outputs: options.tool_outputs,
+        }
+    }
+
+    fn text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (value, replacement) in &self.literals {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), replacement);
+            }
+        }
+        for (rule, replacement) in &self.rules {
+            text = rule.replace_all(&text, replacement.as_str()).into_owned();
+        }
+        text
+    }
+
+    fn value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.text(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.value(item)),
+            _ => {}
+        }
+    }
+
+    fn tool_output(&self, content: &mut Content) {
+        if self.tool_outputs {
+            *content = Content::text(REDACTED_TOOL_OUTPUT);
+            return;
+        }
+        match &mut content.raw {
+            RawContent::Text(text) => text.text = self.text(&text.text),
+            // Images and embedded resources can't be scrubbed piecemeal
+            _ => *content = Content::text(REDACTED_TOOL_OUTPUT),
+        }
+    }
+
+    fn message(&self, mut message: Message) -> Message {
+        for content in &mut message.content {
+            match content {
+                MessageContent::Text(text) => text.raw.text = self.text(&text.raw.text),
+                MessageContent::Thinking(thinking) => {
+                    thinking.thinking = self.text(&thinking.thinking)
+                }
+                MessageContent::ToolRequest(request) => {
+                    if let Ok(call) = &mut request.tool_call {
+                        if let Some(arguments) = &mut call.arguments {
+                            arguments.values_mut().for_each(|value| self.value(value));
+                        }
+                    }
+                }
+                MessageContent::ToolResponse(response) => {
+                    if let Ok(contents) = &mut response.tool_result {
+                        contents
+                            .iter_mut()
+                            .for_each(|content| self.tool_output(content));
+                    }
+                }
+                MessageContent::ToolConfirmationRequest(request) => {
+                    request
+                        .arguments
+                        .values_mut()
+                        .for_each(|value| self.value(value));
+                }
+                _ => {}
+            }
+        }
+        message
+    }
+}
+
+/// The messages of a session with `options` applied; `env` is the environment whose values to
+/// blank
+pub fn redact_messages(
+    messages: &[Message],
+    options: &RedactionOptions,
+    env: &[(String, String)],
+) -> Vec<Message> {
+    let redactor = Redactor::new(options, env);
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !options.drop_messages.contains(index))
+        .map(|(_, message)| redactor.message(message.clone()))
+        .collect()
+}
+
+fn preview(message: &Message) -> String {
+    let role = match message.role {
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+    };
+    let text = message
+        .content
+        .iter()
+        .map(|content| content.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{}: {}", role, safe_truncate(&text, PREVIEW_LENGTH))
+}
+
+/// Ask which messages to drop and what to blank
+fn prompt_options(messages: &[Message], mut options: RedactionOptions) -> Result<RedactionOptions> {
+    let mut select_messages =
+        multiselect("Select messages to remove (space to toggle, enter to continue)")
+            .required(false);
+    for (index, message) in messages.iter().enumerate() {
+        select_messages = select_messages.item(index, preview(message), format!("#{}", index));
+    }
+    options
+        .drop_messages
+        .extend(select_messages.interact()?.into_iter());
+
+    let blanks = multiselect("What else should be blanked?")
+        .required(false)
+        .item("tool_outputs", "Tool outputs", "replace every tool result")
+        .item("paths", "Paths", "absolute and home-relative file paths")
+        .item(
+            "env_values",
+            "Environment values",
+            "values of your environment variables, shown as $NAME",
+        )
+        .initial_values(
+            [
+                (options.tool_outputs, "tool_outputs"),
+                (options.paths, "paths"),
+                (options.env_values, "env_values"),
+            ]
+            .into_iter()
+            .filter_map(|(selected, name)| selected.then_some(name))
+            .collect(),
+        )
+        .interact()?;
+    options.tool_outputs = blanks.contains(&"tool_outputs");
+    options.paths = blanks.contains(&"paths");
+    options.env_values = blanks.contains(&"env_values");
+    Ok(options)
+}
+
+pub async fn handle_session_redact(
+    session_id: String,
+    options: RedactionOptions,
+    interactive: bool,
+) -> Result<()> {
+    let session = SessionManager::get_session(&session_id, true)
+        .await
+        .with_context(|| format!("Session '{}' not found or failed to read", session_id))?;
+    let conversation = session
+        .conversation
+        .ok_or_else(|| anyhow::anyhow!("Session has no messages"))?;
+    let messages = conversation.messages();
+
+    let options = if interactive {
+        prompt_options(messages, options)?
+    } else {
+        options
+    };
+    let env: Vec<(String, String)> = std::env::vars().collect();
+    let redacted = redact_messages(messages, &options, &env);
+
+    let working_dir = if options.paths {
+        PathBuf::from(REDACTED_PATH)
+    } else {
+        session.working_dir.clone()
+    };
+    let copy =
+        SessionManager::create_session(working_dir, format!("{} (redacted)", session.description))
+            .await?;
+    SessionManager::replace_conversation(&copy.id, &Conversation::new_unvalidated(redacted))
+        .await?;
+
+    println!(
+        "Redacted copy of {} saved as session {} ({} of {} messages kept)",
+        session_id,
+        copy.id,
+        messages.len() - options.drop_messages.len().min(messages.len()),
+        messages.len()
+    );
+    println!(
+        "Export it with: goose session export --session-id {}",
+        copy.id
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::CallToolRequestParam;
+    use serde_json::json;
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::user().with_text("Deploy with token sk-live-1234567890 from /home/alice/acme/deploy.sh"),
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(CallToolRequestParam {
+                    name: "developer__shell".into(),
+                    arguments: json!({"command": "cat /home/alice/acme/.env", "env": ["TOKEN=sk-live-1234567890"]})
+                        .as_object()
+                        .cloned(),
+                }),
+            ),
+            Message::user().with_tool_response(
+                "call_1",
+                Ok(vec![Content::text("DATABASE_URL=postgres://internal-db:5432")]),
+            ),
+            Message::assistant().with_text("Done"),
+        ]
+    }
+
+    fn texts(messages: &[Message]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|m| serde_json::to_string(&m.content).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_drop_messages_and_patterns() {
+        let options = RedactionOptions {
+            drop_messages: HashSet::from([3]),
+            patterns: vec![Regex::new(r"sk-live-\w+").unwrap()],
+            ..Default::default()
+        };
+        let redacted = redact_messages(&messages(), &options, &[]);
+        assert_eq!(redacted.len(), 3);
+        let texts = texts(&redacted);
+        assert!(texts.iter().all(|t| !t.contains("sk-live")));
+        assert!(texts[0].contains("[redacted]"));
+        assert!(texts[1].contains("TOKEN=[redacted]"));
+        // Paths and tool output were not selected
+        assert!(texts[0].contains("/home/alice/acme/deploy.sh"));
+        assert!(texts[2].contains("internal-db"));
+    }
+
+    #[test]
+    fn test_paths_env_and_tool_outputs() {
+        let options = RedactionOptions {
+            tool_outputs: true,
+            paths: true,
+            env_values: true,
+            ..Default::default()
+        };
+        let env = vec![
+            ("DEPLOY_TOKEN".to_string(), "sk-live-1234567890".to_string()),
+            ("SHORT".to_string(), "sk".to_string()),
+        ];
+        let redacted = redact_messages(&messages(), &options, &env);
+        let texts = texts(&redacted);
+        assert!(texts[0].contains("$DEPLOY_TOKEN"));
+        assert!(texts[0].contains("[redacted path]"));
+        assert!(!texts[0].contains("alice"));
+        assert!(!texts[1].contains("alice"));
+        assert!(texts[2].contains("[tool output redacted]"));
+        assert!(!texts[2].contains("internal-db"));
+        assert!(texts[3].contains("Done"));
+    }
+}
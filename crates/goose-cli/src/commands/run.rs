@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use console::style;
+use goose::config::Config;
+use goose::providers::pricing::initialize_pricing_cache;
+use goose::session::run_diff::{MetricChange, RunDiff, RunSummary};
+use goose::session::SessionManager;
+
+use crate::session::{estimate_cost_usd, TokenCounts};
+
+async fn summarize(session_id: &str) -> Result<RunSummary> {
+    let session = SessionManager::get_session(session_id, true)
+        .await
+        .with_context(|| format!("Run '{}' not found or failed to read", session_id))?;
+    let mut summary = RunSummary::from_session(&session);
+
+    // Sessions don't record their model, so cost is estimated at the configured model's price
+    let config = Config::global();
+    if let (Ok(provider), Ok(model)) = (
+        config.get_param::<String>("GOOSE_PROVIDER"),
+        config.get_param::<String>("GOOSE_MODEL"),
+    ) {
+        let tokens = TokenCounts {
+            input: summary.input_tokens.max(0) as usize,
+            cached_input: session
+                .accumulated_cached_input_tokens
+                .or(session.cached_input_tokens)
+                .unwrap_or(0) as usize,
+            output: summary.output_tokens.max(0) as usize,
+            reasoning: 0,
+        };
+        summary.cost_usd = estimate_cost_usd(&provider, &model, &tokens).await;
+    }
+    Ok(summary)
+}
+
+fn format_metric(metric: &MetricChange) -> String {
+    let value = |value: f64| {
+        if metric.metric == "cost_usd" {
+            format!("${:.4}", value)
+        } else {
+            format!("{}", value)
+        }
+    };
+    let change = if metric.change == 0.0 {
+        style("=".to_string()).dim()
+    } else if metric.change > 0.0 {
+        style(format!("+{}", value(metric.change))).yellow()
+    } else {
+        style(value(metric.change)).green()
+    };
+    format!(
+        "  {:<18} {:>12} {:>12}  {}",
+        metric.metric,
+        value(metric.a),
+        value(metric.b),
+        change
+    )
+}
+
+fn print_diff(diff: &RunDiff) {
+    for (label, run) in [("a", &diff.a), ("b", &diff.b)] {
+        println!(
+            "{} {} {} ({})",
+            style(format!("run {}:", label)).bold(),
+            run.session_id,
+            run.recipe.as_deref().unwrap_or(&run.description),
+            run.started_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    println!();
+    println!("  {:<18} {:>12} {:>12}", "", "a", "b");
+    for metric in &diff.metrics {
+        println!("{}", format_metric(metric));
+    }
+
+    if !diff.files_only_in_a.is_empty() || !diff.files_only_in_b.is_empty() {
+        println!("\n{}", style("Files changed").bold());
+        for file in &diff.files_only_in_a {
+            println!("  {} {} (only in a)", style("-").red(), file);
+        }
+        for file in &diff.files_only_in_b {
+            println!("  {} {} (only in b)", style("+").green(), file);
+        }
+    }
+
+    if !diff.new_task_errors.is_empty() {
+        println!("\n{}", style("New task errors in b").bold());
+        for error in &diff.new_task_errors {
+            println!("  {}", style(error).red());
+        }
+    }
+
+    if diff.final_output_changed {
+        println!("\n{}", style("Final output").bold());
+        for (label, run) in [("a", &diff.a), ("b", &diff.b)] {
+            println!("--- {}", label);
+            println!("{}", run.final_output.as_deref().unwrap_or("(none)"));
+        }
+    }
+
+    println!();
+    if diff.regressed() {
+        println!("{}", style("Run b did worse than run a").red().bold());
+    } else {
+        println!("{}", style("No new failures in run b").green());
+    }
+}
+
+/// Compare two runs, `a` the earlier one as a rule
+pub async fn handle_run_diff(run_a: String, run_b: String, format: String) -> Result<()> {
+    if let Err(e) = initialize_pricing_cache().await {
+        tracing::warn!("Failed to initialize pricing cache: {e}");
+    }
+    let diff = RunDiff::new(summarize(&run_a).await?, summarize(&run_b).await?);
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&diff)?),
+        "text" => print_diff(&diff),
+        _ => return Err(anyhow::anyhow!("Unsupported format: {}", format)),
+    }
+    Ok(())
+}
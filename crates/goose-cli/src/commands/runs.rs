@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use console::{style, StyledObject};
+use goose::agents::subagent_execution_tool::run_history::{RunHistory, TaskRecord, HISTORY_FILE};
+use goose::agents::subagent_execution_tool::task_types::TaskStatus;
+use goose::agents::subagent_execution_tool::task_usage::TaskUsage;
+use goose::session::session_manager::ensure_session_dir;
+
+async fn open_history() -> Result<RunHistory> {
+    RunHistory::open(&ensure_session_dir()?.join(HISTORY_FILE)).await
+}
+
+fn format_usage(usage: &TaskUsage) -> String {
+    let tokens = usage.input_tokens + usage.output_tokens;
+    match usage.cost_usd {
+        Some(cost) if usage.cost_partial => format!("{} tokens, ${:.4} (partial)", tokens, cost),
+        Some(cost) => format!("{} tokens, ${:.4}", tokens, cost),
+        None => format!("{} tokens", tokens),
+    }
+}
+
+fn format_status(status: &TaskStatus) -> StyledObject<String> {
+    let name = style(status.to_string().to_lowercase());
+    match status {
+        TaskStatus::Completed => name.green(),
+        TaskStatus::Failed => name.red(),
+        _ => name.yellow(),
+    }
+}
+
+fn print_task(task: &TaskRecord) {
+    let duration = task
+        .duration_secs
+        .map_or_else(|| "-".to_string(), |secs| format!("{:.1}s", secs));
+    println!(
+        "  {:<24} {:<10} {:>8}  {}",
+        task.name,
+        format_status(&task.status),
+        duration,
+        format_usage(&task.usage)
+    );
+    if let Some(parameters) = task.parameters.as_ref().and_then(|p| p.as_object()) {
+        let parameters: Vec<String> = parameters
+            .iter()
+            .map(|(key, value)| match value.as_str() {
+                Some(text) => format!("{}={}", key, text),
+                None => format!("{}={}", key, value),
+            })
+            .collect();
+        println!("    {}", style(parameters.join(", ")).dim());
+    }
+    if let Some(error) = &task.error {
+        println!("    {}", style(error.lines().next().unwrap_or(error)).red());
+    }
+}
+
+/// List the parallel task runs recorded, the latest first
+pub async fn handle_runs_list(limit: usize, format: String) -> Result<()> {
+    let runs = open_history().await?.list(limit).await?;
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&runs)?),
+        "text" => {
+            if runs.is_empty() {
+                println!("No task runs recorded yet");
+                return Ok(());
+            }
+            println!(
+                "{}",
+                style(format!(
+                    "{:<24} {:<17} {:>9} {:>6} {:>6} {:>6}  {}",
+                    "RUN", "STARTED", "DURATION", "TASKS", "OK", "FAILED", "USAGE"
+                ))
+                .bold()
+            );
+            for run in &runs {
+                println!(
+                    "{:<24} {:<17} {:>8.1}s {:>6} {:>6} {:>6}  {}",
+                    run.run_id,
+                    run.started_at
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M"),
+                    run.duration_secs,
+                    run.total,
+                    run.completed,
+                    run.failed,
+                    format_usage(&run.usage)
+                );
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}", format)),
+    }
+    Ok(())
+}
+
+/// Show the tasks of the recorded run `run_id`
+pub async fn handle_runs_show(run_id: String, format: String) -> Result<()> {
+    let run = open_history().await?.get(&run_id).await?.ok_or_else(|| {
+        anyhow!(
+            "Task run '{}' not found; list runs with `goose runs list`",
+            run_id
+        )
+    })?;
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&run)?),
+        "text" => {
+            println!(
+                "{} {} ({}, {:.1}s)",
+                style("run:").bold(),
+                run.run_id,
+                run.started_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M"),
+                run.duration_secs
+            );
+            println!();
+            for task in &run.tasks {
+                print_task(task);
+            }
+            let usage = TaskUsage::total(run.tasks.iter().map(|task| &task.usage));
+            println!("\n  {}", format_usage(&usage));
+            if run
+                .tasks
+                .iter()
+                .any(|task| task.status != TaskStatus::Completed)
+            {
+                println!(
+                    "\nRun the tasks that didn't complete again with `goose run --resume-tasks {}`",
+                    run.run_id
+                );
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}", format)),
+    }
+    Ok(())
+}
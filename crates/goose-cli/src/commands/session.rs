@@ -1,12 +1,26 @@
 use crate::session::message_to_markdown;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
+use chrono::{DateTime, Local};
 use cliclack::{confirm, multiselect, select};
+use goose::agents::subagent_execution_tool::pause::pause_file;
+use goose::agents::subagent_execution_tool::result_cache::{self, ResultCache};
+use goose::agents::subagent_execution_tool::run_manifest::{
+    self, manifest_dir, DEFAULT_RUN_RETENTION,
+};
+use goose::agents::subagent_execution_tool::task_logs::{
+    self, log_dir, log_path, DEFAULT_LOG_RETENTION,
+};
+use goose::session::blob_store::{BlobStore, DEFAULT_GC_GRACE};
+use goose::session::compression::SessionSize;
 use goose::session::{Session, SessionManager};
 use goose::utils::safe_truncate;
 use regex::Regex;
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 const TRUNCATED_DESC_LENGTH: usize = 60;
 
@@ -114,6 +128,232 @@ pub async fn handle_session_remove(id: Option<String>, regex_string: Option<Stri
     remove_sessions(matched_sessions).await
 }
 
+/// Remove stored attachments and tool output that no remaining session references, and task
+/// logs and runs nothing was written to for a while
+pub async fn handle_session_gc(dry_run: bool, clear_task_cache: bool) -> Result<()> {
+    let live_sessions: HashSet<String> = SessionManager::list_sessions()
+        .await
+        .context("Failed to retrieve sessions")?
+        .into_iter()
+        .map(|session| session.id)
+        .collect();
+    let report = BlobStore::open()?.gc(&live_sessions, DEFAULT_GC_GRACE, dry_run)?;
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!(
+        "{} {} unreferenced blob(s), {:.1} MB; {} blob(s) still in use",
+        verb,
+        report.removed_blobs,
+        report.freed_bytes as f64 / (1024.0 * 1024.0),
+        report.kept_blobs
+    );
+    if report.removed_refs > 0 {
+        println!(
+            "{} the references of {} deleted session(s)",
+            if dry_run { "Would forget" } else { "Forgot" },
+            report.removed_refs
+        );
+    }
+
+    let logs = task_logs::prune(&log_dir()?, DEFAULT_LOG_RETENTION, dry_run)?;
+    println!(
+        "{} {} task log(s) older than {} days, {:.1} MB; {} log(s) kept",
+        verb,
+        logs.removed,
+        DEFAULT_LOG_RETENTION.as_secs() / (24 * 60 * 60),
+        logs.freed_bytes as f64 / (1024.0 * 1024.0),
+        logs.kept
+    );
+
+    let runs = run_manifest::prune(&manifest_dir()?, DEFAULT_RUN_RETENTION, dry_run)?;
+    println!(
+        "{} {} task run file(s) older than {} days; {} kept for --resume-tasks",
+        verb,
+        runs.removed,
+        DEFAULT_RUN_RETENTION.as_secs() / (24 * 60 * 60),
+        runs.kept
+    );
+
+    let ttl = if clear_task_cache {
+        Duration::ZERO
+    } else {
+        ResultCache::ttl_from_config()
+    };
+    let cached = result_cache::prune(&ResultCache::dir()?, ttl, dry_run)?;
+    println!(
+        "{} {} cached task result(s){}; {} kept",
+        verb,
+        cached.removed,
+        if clear_task_cache {
+            String::new()
+        } else {
+            format!(" older than {} hour(s)", ttl.as_secs() / (60 * 60))
+        },
+        cached.kept
+    );
+    Ok(())
+}
+
+/// Compress the messages stored before session compression was turned on
+pub async fn handle_session_compress() -> Result<()> {
+    let report = SessionManager::compress_stored_messages()
+        .await
+        .context("Failed to compress stored sessions")?;
+    if report.messages_compressed == 0 {
+        println!("Nothing to compress; every large message is already compressed");
+        return Ok(());
+    }
+    println!(
+        "Compressed {} message(s) from {} to {}",
+        report.messages_compressed,
+        format_size(report.bytes_before),
+        format_size(report.bytes_after)
+    );
+    Ok(())
+}
+
+/// How often a followed task log is checked for new output
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// List the logs of recent tasks, follow the one of `tail` as it is written, or `pause` or
+/// `resume` the scheduling of task runs
+pub async fn handle_session_tasks(tail: Option<String>, pause: bool, resume: bool) -> Result<()> {
+    if pause || resume {
+        return set_task_runs_paused(pause);
+    }
+    let dir = log_dir()?;
+    if let Some(task_id) = tail {
+        return follow_task_log(&find_task_log(&dir, &task_id)?).await;
+    }
+
+    let logs = task_logs(&dir)?;
+    if logs.is_empty() {
+        println!("No task logs in {}", dir.display());
+        return Ok(());
+    }
+    println!("Task logs in {}:", dir.display());
+    for log in &logs {
+        println!(
+            "{} - {} - {}",
+            log.task_id,
+            DateTime::<Local>::from(log.modified).format("%Y-%m-%d %H:%M:%S"),
+            format_size(log.bytes)
+        );
+    }
+    Ok(())
+}
+
+/// Pause every parallel task run, or let them go on, through the pause file they check
+fn set_task_runs_paused(paused: bool) -> Result<()> {
+    let file = pause_file()?;
+    if paused {
+        fs::write(&file, "").with_context(|| format!("Failed to write {}", file.display()))?;
+        println!(
+            "Task runs are paused: running tasks finish, but no more start until `goose session tasks --resume`"
+        );
+    } else if file.exists() {
+        fs::remove_file(&file).with_context(|| format!("Failed to remove {}", file.display()))?;
+        println!("Task runs are resumed");
+    } else {
+        println!("Task runs aren't paused");
+    }
+    Ok(())
+}
+
+struct TaskLog {
+    task_id: String,
+    path: PathBuf,
+    modified: SystemTime,
+    bytes: u64,
+}
+
+/// The task logs in `dir`, most recently written first
+fn task_logs(dir: &Path) -> Result<Vec<TaskLog>> {
+    let mut logs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "log") {
+            continue;
+        }
+        let Some(task_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let metadata = fs::metadata(&path)?;
+        logs.push(TaskLog {
+            task_id: task_id.to_string(),
+            modified: metadata.modified()?,
+            bytes: metadata.len(),
+            path,
+        });
+    }
+    logs.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(logs)
+}
+
+/// The log of the task whose id is, or starts with, `task_id`
+fn find_task_log(dir: &Path, task_id: &str) -> Result<PathBuf> {
+    let exact = log_path(dir, task_id);
+    if exact.exists() {
+        return Ok(exact);
+    }
+    let mut matches: Vec<PathBuf> = task_logs(dir)?
+        .into_iter()
+        .filter(|log| log.task_id.starts_with(task_id))
+        .map(|log| log.path)
+        .collect();
+    match matches.len() {
+        0 => bail!("No task {} has a log in {}", task_id, dir.display()),
+        1 => Ok(matches.remove(0)),
+        n => bail!(
+            "{} tasks have ids starting with {}; give more of it",
+            n,
+            task_id
+        ),
+    }
+}
+
+/// Print the log at `path`, then what is added to it, until interrupted
+async fn follow_task_log(path: &Path) -> Result<()> {
+    eprintln!("Following {} (Ctrl-C to stop)", path.display());
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut stdout = tokio::io::stdout();
+    let mut buffer = vec![0; 8192];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            tokio::time::sleep(TAIL_POLL_INTERVAL).await;
+            continue;
+        }
+        stdout.write_all(&buffer[..read]).await?;
+        stdout.flush().await?;
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes < KB * KB {
+        format!("{:.1} KB", bytes / KB)
+    } else {
+        format!("{:.1} MB", bytes / (KB * KB))
+    }
+}
+
+/// The stored size, and the uncompressed one when compression saved something
+fn describe_size(size: &SessionSize) -> String {
+    if size.stored_bytes < size.content_bytes {
+        format!(
+            "{} ({} uncompressed)",
+            format_size(size.stored_bytes),
+            format_size(size.content_bytes)
+        )
+    } else {
+        format_size(size.stored_bytes)
+    }
+}
+
 pub async fn handle_session_list(
     format: String,
     ascending: bool,
@@ -142,9 +382,20 @@ pub async fn handle_session_list(
         sessions.truncate(n);
     }
 
+    let sizes = SessionManager::session_sizes().await.unwrap_or_default();
     match format.as_str() {
         "json" => {
-            println!("{}", serde_json::to_string(&sessions)?);
+            let mut listed = Vec::new();
+            for session in &sessions {
+                let size = sizes.get(&session.id).copied().unwrap_or_default();
+                let mut value = serde_json::to_value(session)?;
+                if let Some(fields) = value.as_object_mut() {
+                    fields.insert("stored_bytes".to_string(), size.stored_bytes.into());
+                    fields.insert("content_bytes".to_string(), size.content_bytes.into());
+                }
+                listed.push(value);
+            }
+            println!("{}", serde_json::to_string(&listed)?);
         }
         _ => {
             if sessions.is_empty() {
@@ -153,13 +404,21 @@ pub async fn handle_session_list(
             }
 
             println!("Available sessions:");
-            for session in sessions {
+            let mut total = SessionSize::default();
+            for session in &sessions {
+                let size = sizes.get(&session.id).copied().unwrap_or_default();
+                total.stored_bytes += size.stored_bytes;
+                total.content_bytes += size.content_bytes;
                 let output = format!(
-                    "{} - {} - {}",
-                    session.id, session.description, session.updated_at
+                    "{} - {} - {} - {}",
+                    session.id,
+                    session.description,
+                    session.updated_at,
+                    describe_size(&size)
                 );
                 println!("{}", output);
             }
+            println!("{} session(s), {}", sessions.len(), describe_size(&total));
         }
     }
     Ok(())
@@ -204,6 +463,25 @@ pub async fn handle_session_export(
 
     Ok(())
 }
+pub async fn handle_session_replay(
+    session_id: String,
+    speed: Option<f64>,
+    realtime: bool,
+    debug: bool,
+) -> Result<()> {
+    let session = SessionManager::get_session(&session_id, true)
+        .await
+        .with_context(|| format!("Session '{}' not found or failed to read", session_id))?;
+    let conversation = session
+        .conversation
+        .ok_or_else(|| anyhow::anyhow!("Session has no messages"))?;
+
+    let speed = speed.or(realtime.then_some(1.0));
+    crate::session::replay_messages(conversation.messages(), &session.description, speed, debug)
+        .await;
+    Ok(())
+}
+
 /// Convert a list of messages to markdown format for session export
 ///
 /// This function handles the formatting of a complete session including headers,
@@ -328,3 +606,28 @@ pub async fn prompt_interactive_session_selection() -> Result<String> {
         Err(anyhow::anyhow!("Invalid selection"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_task_log() {
+        let dir = tempfile::tempdir().unwrap();
+        for task_id in ["3f2a91c0", "3f7b0d44", "a81c5e02"] {
+            fs::write(log_path(dir.path(), task_id), "output\n").unwrap();
+        }
+
+        assert_eq!(
+            find_task_log(dir.path(), "a81c5e02").unwrap(),
+            dir.path().join("a81c5e02.log")
+        );
+        assert_eq!(
+            find_task_log(dir.path(), "3f7").unwrap(),
+            dir.path().join("3f7b0d44.log")
+        );
+        assert!(find_task_log(dir.path(), "3f").is_err());
+        assert!(find_task_log(dir.path(), "ffff").is_err());
+        assert_eq!(task_logs(dir.path()).unwrap().len(), 3);
+    }
+}
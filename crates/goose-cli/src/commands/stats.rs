@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use console::style;
+use goose::session::extension_data::{
+    ExtensionState, FeedbackEntry, FeedbackRating, FeedbackState,
+};
+use goose::session::SessionManager;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+struct SessionFeedback {
+    session_id: String,
+    description: String,
+    #[serde(flatten)]
+    entry: FeedbackEntry,
+}
+
+async fn collect_feedback() -> Result<Vec<SessionFeedback>> {
+    let sessions = SessionManager::list_sessions()
+        .await
+        .context("Failed to retrieve sessions")?;
+
+    let mut all = Vec::new();
+    for session in sessions {
+        let Some(feedback) = FeedbackState::from_extension_data(&session.extension_data) else {
+            continue;
+        };
+        all.extend(feedback.entries.into_iter().map(|entry| SessionFeedback {
+            session_id: session.id.clone(),
+            description: session.description.clone(),
+            entry,
+        }));
+    }
+    all.sort_by(|a, b| a.entry.created_at.cmp(&b.entry.created_at));
+    Ok(all)
+}
+
+pub async fn handle_stats_feedback(export: Option<PathBuf>) -> Result<()> {
+    let feedback = collect_feedback().await?;
+
+    if let Some(path) = export {
+        let json = serde_json::to_string_pretty(&feedback)?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!(
+            "Exported {} feedback entries to {}",
+            feedback.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if feedback.is_empty() {
+        println!("No feedback recorded yet. Use /good or /bad [reason] in a session.");
+        return Ok(());
+    }
+
+    let good = feedback
+        .iter()
+        .filter(|f| f.entry.rating == FeedbackRating::Good)
+        .count();
+    let bad = feedback.len() - good;
+    let sessions = feedback
+        .iter()
+        .map(|f| f.session_id.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+
+    println!(
+        "Feedback: {} rated responses across {} sessions",
+        feedback.len(),
+        sessions
+    );
+    println!(
+        "  {} {} ({:.0}%)",
+        style("good").green(),
+        good,
+        good as f64 * 100.0 / feedback.len() as f64
+    );
+    println!(
+        "  {} {} ({:.0}%)",
+        style("bad").red(),
+        bad,
+        bad as f64 * 100.0 / feedback.len() as f64
+    );
+
+    let mut reasons: HashMap<String, usize> = HashMap::new();
+    for f in &feedback {
+        if let Some(reason) = &f.entry.reason {
+            *reasons.entry(reason.to_lowercase()).or_default() += 1;
+        }
+    }
+    if !reasons.is_empty() {
+        let mut reasons: Vec<_> = reasons.into_iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        println!("\nMost common reasons:");
+        for (reason, count) in reasons.into_iter().take(10) {
+            println!("  {:>3}  {}", count, reason);
+        }
+    }
+
+    Ok(())
+}
@@ -559,6 +559,7 @@ async fn process_message_streaming(
                                         goose::permission::PermissionConfirmation {
                                             principal_type: goose::permission::permission_confirmation::PrincipalType::Tool,
                                             permission: goose::permission::Permission::AllowOnce,
+                                            arguments: None,
                                         }
                                     ).await;
                                 }
@@ -0,0 +1,162 @@
+//! The exit codes of goose, so scripts can tell why a headless run failed.
+
+use goose::agents::StopReason;
+use goose::config::ConfigError;
+use goose::providers::errors::ProviderError;
+
+/// Shown under `goose run --help`
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    success
+  1    the task failed
+  2    invalid arguments
+  3    the recipe's success checks still failed after the last retry
+  4    the turn limit (--max-turns) was reached
+  5    the provider rejected the credentials
+  6    goose or the provider is not configured correctly
+  7    goose asked a question no configured answer covers (see GOOSE_ASK_USER_DEFAULTS)
+  130  interrupted";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success,
+    TaskFailure,
+    Usage,
+    ChecksFailed,
+    MaxTurnsReached,
+    ProviderAuth,
+    Config,
+    NeedsInput,
+    Interrupted,
+}
+
+impl ExitCode {
+    pub fn code(&self) -> i32 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::TaskFailure => 1,
+            ExitCode::Usage => 2,
+            ExitCode::ChecksFailed => 3,
+            ExitCode::MaxTurnsReached => 4,
+            ExitCode::ProviderAuth => 5,
+            ExitCode::Config => 6,
+            ExitCode::NeedsInput => 7,
+            ExitCode::Interrupted => 130,
+        }
+    }
+
+    /// Short label for telemetry
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExitCode::Success => "normal",
+            ExitCode::TaskFailure => "error",
+            ExitCode::Usage => "usage_error",
+            ExitCode::ChecksFailed => "checks_failed",
+            ExitCode::MaxTurnsReached => "max_turns",
+            ExitCode::ProviderAuth => "auth_error",
+            ExitCode::Config => "config_error",
+            ExitCode::NeedsInput => "needs_input",
+            ExitCode::Interrupted => "interrupted",
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ExitCode::Success => "finished",
+            ExitCode::TaskFailure => "the task failed",
+            ExitCode::Usage => "invalid arguments",
+            ExitCode::ChecksFailed => "the success checks still failed after the last retry",
+            ExitCode::MaxTurnsReached => "stopped at the turn limit before finishing",
+            ExitCode::ProviderAuth => "the provider rejected the credentials",
+            ExitCode::Config => "goose is not configured correctly",
+            ExitCode::NeedsInput => "a question needed an answer nobody could give",
+            ExitCode::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn exit(&self) -> ! {
+        std::process::exit(self.code())
+    }
+
+    pub fn from_stop_reason(reason: StopReason) -> Self {
+        match reason {
+            StopReason::Completed => ExitCode::Success,
+            StopReason::MaxTurnsReached => ExitCode::MaxTurnsReached,
+            StopReason::ChecksFailed => ExitCode::ChecksFailed,
+        }
+    }
+
+    /// The failure class of an error, looking through its whole chain of causes
+    pub fn of_error(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(RunFailed(code)) = cause.downcast_ref::<RunFailed>() {
+                return *code;
+            }
+            if let Some(ProviderError::Authentication(_)) = cause.downcast_ref::<ProviderError>() {
+                return ExitCode::ProviderAuth;
+            }
+            if cause.downcast_ref::<ConfigError>().is_some() {
+                return ExitCode::Config;
+            }
+        }
+        ExitCode::TaskFailure
+    }
+}
+
+/// A run that ended with something other than success, for callers that return errors rather
+/// than exit themselves
+#[derive(Debug)]
+pub struct RunFailed(pub ExitCode);
+
+impl std::fmt::Display for RunFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (exit code {})", self.0.describe(), self.0.code())
+    }
+}
+
+impl std::error::Error for RunFailed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_of_error() {
+        let auth: anyhow::Error = ProviderError::Authentication("bad key".to_string()).into();
+        assert_eq!(ExitCode::of_error(&auth), ExitCode::ProviderAuth);
+
+        let config = Err::<(), _>(ConfigError::NotFound("GOOSE_PROVIDER".to_string()))
+            .context("Failed to start")
+            .unwrap_err();
+        assert_eq!(ExitCode::of_error(&config), ExitCode::Config);
+
+        let failed: anyhow::Error = RunFailed(ExitCode::MaxTurnsReached).into();
+        assert_eq!(ExitCode::of_error(&failed), ExitCode::MaxTurnsReached);
+        assert_eq!(
+            failed.to_string(),
+            "stopped at the turn limit before finishing (exit code 4)"
+        );
+
+        let other = anyhow::anyhow!("tool crashed");
+        assert_eq!(ExitCode::of_error(&other), ExitCode::TaskFailure);
+    }
+
+    #[test]
+    fn test_help_lists_every_code() {
+        for code in [
+            ExitCode::Success,
+            ExitCode::TaskFailure,
+            ExitCode::Usage,
+            ExitCode::ChecksFailed,
+            ExitCode::MaxTurnsReached,
+            ExitCode::ProviderAuth,
+            ExitCode::Config,
+            ExitCode::NeedsInput,
+            ExitCode::Interrupted,
+        ] {
+            let line = format!("\n  {:<5}", code.code());
+            assert!(EXIT_CODES_HELP.contains(&line), "missing {}", code.code());
+        }
+    }
+}
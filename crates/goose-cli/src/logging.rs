@@ -1,33 +1,370 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::{Lazy, OnceCell};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Once;
 use tokio::sync::Mutex;
-use tracing_appender::rolling::Rotation;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
-    filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
-    Registry,
+    filter::LevelFilter, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter,
+    Layer, Registry,
 };
 
+use goose::config::Config;
 use goose::tracing::{langfuse_layer, otlp_layer};
 use goose_bench::bench_session::BenchAgentError;
 use goose_bench::error_capture::ErrorCaptureLayer;
 
+pub const LOG_SINKS_KEY: &str = "GOOSE_LOG_SINKS";
+pub const LOG_FILTER_KEY: &str = "GOOSE_LOG_FILTER";
+pub const LOG_ROTATION_KEY: &str = "GOOSE_LOG_ROTATION";
+pub const LOG_MAX_FILES_KEY: &str = "GOOSE_LOG_MAX_FILES";
+
+/// mcp-client and goose at DEBUG, goose-cli at INFO, everything else at WARN
+const DEFAULT_FILTER: &str = "warn,mcp_client=debug,goose=debug,goose_cli=info";
+
 // Used to ensure we only set up tracing once
 static INIT: Once = Once::new();
 
+/// Changes the filter of the sinks at runtime
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+/// The directives the filter was last built from
+static FILTER_DIRECTIVES: Lazy<std::sync::Mutex<String>> =
+    Lazy::new(|| std::sync::Mutex::new(String::new()));
+/// The session a panic is logged with
+static PANIC_SESSION: Lazy<std::sync::Mutex<Option<String>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sink {
+    File,
+    Stderr,
+    Journald,
+}
+
+fn parse_sinks(value: &str) -> Vec<Sink> {
+    let mut sinks = Vec::new();
+    for name in value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let sink = match name.to_lowercase().as_str() {
+            "file" => Sink::File,
+            "stderr" => Sink::Stderr,
+            "journald" => Sink::Journald,
+            _ => {
+                eprintln!("Unknown log sink '{}' in {}", name, LOG_SINKS_KEY);
+                continue;
+            }
+        };
+        if !sinks.contains(&sink) {
+            sinks.push(sink);
+        }
+    }
+    if sinks.is_empty() {
+        sinks.push(Sink::File);
+    }
+    sinks
+}
+
+fn parse_rotation(value: &str) -> Rotation {
+    match value.trim().to_lowercase().as_str() {
+        "minutely" => Rotation::MINUTELY,
+        "hourly" => Rotation::HOURLY,
+        "daily" => Rotation::DAILY,
+        _ => Rotation::NEVER,
+    }
+}
+
+/// The module a filter directive is for; empty for the default level
+fn directive_target(directive: &str) -> &str {
+    directive
+        .rsplit_once('=')
+        .map(|(target, _)| target)
+        .unwrap_or("")
+}
+
+/// `current` with the directives in `changes` replacing those for the same modules
+fn merge_directives(current: &str, changes: &str) -> String {
+    let mut directives: Vec<&str> = current
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .collect();
+    for change in changes
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+    {
+        directives.retain(|directive| directive_target(directive) != directive_target(change));
+        directives.push(change);
+    }
+    directives.join(",")
+}
+
+/// The filter in effect
+pub fn log_filter() -> String {
+    FILTER_DIRECTIVES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Change the levels of this process's logs, e.g. `mcp_client=debug`; modules not named keep
+/// their level. Returns the filter now in effect.
+pub fn set_log_filter(changes: &str) -> Result<String> {
+    let handle = FILTER
+        .get()
+        .ok_or_else(|| anyhow!("Logging is not set up"))?;
+    let mut directives = FILTER_DIRECTIVES.lock().unwrap_or_else(|e| e.into_inner());
+    let merged = merge_directives(&directives, changes);
+    let filter =
+        EnvFilter::try_new(&merged).with_context(|| format!("Invalid log filter '{}'", changes))?;
+    handle
+        .reload(filter)
+        .context("Failed to change the log filter")?;
+    *directives = merged.clone();
+    Ok(merged)
+}
+
+/// The session to mention when this process panics
+pub fn set_panic_session(session_id: &str) {
+    *PANIC_SESSION.lock().unwrap_or_else(|e| e.into_inner()) = Some(session_id.to_string());
+}
+
+fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "(no message)".to_string());
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_default();
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        // The panic may have happened while the lock was held
+        let session = PANIC_SESSION
+            .try_lock()
+            .ok()
+            .and_then(|session| session.clone())
+            .unwrap_or_default();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        tracing::error!(
+            target: "goose_cli::panic",
+            %location,
+            %thread,
+            %session,
+            %backtrace,
+            "panic: {}",
+            payload
+        );
+        previous(info);
+    }));
+}
+
+#[cfg(unix)]
+mod journald {
+    use std::io::Write;
+    use std::os::unix::net::UnixDatagram;
+    use std::sync::Arc;
+
+    use tracing::{Level, Metadata};
+    use tracing_subscriber::fmt::MakeWriter;
+
+    const SOCKET: &str = "/run/systemd/journal/socket";
+
+    /// Sends each event to journald in its native protocol
+    #[derive(Clone)]
+    pub struct Journald {
+        socket: Arc<UnixDatagram>,
+    }
+
+    impl Journald {
+        pub fn connect() -> std::io::Result<Self> {
+            let socket = UnixDatagram::unbound()?;
+            socket.connect(SOCKET)?;
+            Ok(Self {
+                socket: Arc::new(socket),
+            })
+        }
+    }
+
+    /// One event, sent when the formatter is done with it
+    pub struct Entry {
+        socket: Arc<UnixDatagram>,
+        priority: u8,
+        buffer: Vec<u8>,
+    }
+
+    impl Write for Entry {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.buffer.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Drop for Entry {
+        fn drop(&mut self) {
+            if !self.buffer.is_empty() {
+                let _ = self.socket.send(&payload(self.priority, &self.buffer));
+            }
+        }
+    }
+
+    fn priority(level: &Level) -> u8 {
+        match *level {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            _ => 7,
+        }
+    }
+
+    /// The fields of a journal entry; the message length-prefixed, since it may span lines
+    pub(super) fn payload(priority: u8, message: &[u8]) -> Vec<u8> {
+        let message = message.strip_suffix(b"\n").unwrap_or(message);
+        let mut payload =
+            format!("PRIORITY={}\nSYSLOG_IDENTIFIER=goose\nMESSAGE\n", priority).into_bytes();
+        payload.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        payload.extend_from_slice(message);
+        payload.push(b'\n');
+        payload
+    }
+
+    impl<'a> MakeWriter<'a> for Journald {
+        type Writer = Entry;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            Entry {
+                socket: self.socket.clone(),
+                priority: 6,
+                buffer: Vec::new(),
+            }
+        }
+
+        fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+            Entry {
+                socket: self.socket.clone(),
+                priority: priority(meta.level()),
+                buffer: Vec::new(),
+            }
+        }
+    }
+}
+
 /// Returns the directory where log files should be stored.
 /// Creates the directory structure if it doesn't exist.
 fn get_log_directory() -> Result<PathBuf> {
     goose::logging::get_log_directory("cli", true)
 }
 
+fn file_layer(name: Option<&str>, config: &Config) -> Result<BoxedLayer> {
+    let log_dir = get_log_directory()?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+
+    // Create log file name by prefixing with timestamp
+    let prefix = match name {
+        Some(name) => format!("{}-{}", timestamp, name),
+        None => timestamp,
+    };
+    let rotation = parse_rotation(
+        &config
+            .get_param::<String>(LOG_ROTATION_KEY)
+            .unwrap_or_default(),
+    );
+    let mut builder = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(prefix)
+        .filename_suffix("log");
+    if let Ok(max_files) = config.get_param::<usize>(LOG_MAX_FILES_KEY) {
+        builder = builder.max_log_files(max_files.max(1));
+    }
+    let file_appender = builder
+        .build(log_dir)
+        .context("Failed to create the log file")?;
+
+    // JSON, with all logs the filter lets through
+    Ok(fmt::layer()
+        .with_target(true)
+        .with_level(true)
+        .with_writer(file_appender)
+        .with_ansi(false)
+        .json()
+        .boxed())
+}
+
+fn sink_layers(name: Option<&str>, config: &Config) -> Result<Vec<BoxedLayer>> {
+    let sinks = parse_sinks(
+        &config
+            .get_param::<String>(LOG_SINKS_KEY)
+            .unwrap_or_default(),
+    );
+    let mut layers = Vec::new();
+    for sink in sinks {
+        match sink {
+            Sink::File => layers.push(file_layer(name, config)?),
+            Sink::Stderr => layers.push(
+                fmt::layer()
+                    .with_target(true)
+                    .with_writer(std::io::stderr)
+                    .with_ansi(false)
+                    .boxed(),
+            ),
+            #[cfg(unix)]
+            Sink::Journald => match journald::Journald::connect() {
+                Ok(journald) => layers.push(
+                    fmt::layer()
+                        .with_target(true)
+                        .with_level(false)
+                        .without_time()
+                        .with_writer(journald)
+                        .with_ansi(false)
+                        .boxed(),
+                ),
+                Err(e) => eprintln!("Not logging to journald: {}", e),
+            },
+            #[cfg(not(unix))]
+            Sink::Journald => eprintln!("Not logging to journald: only available on Linux"),
+        }
+    }
+    Ok(layers)
+}
+
+/// `RUST_LOG`, or else the configured filter, or else the default one
+fn initial_filter(config: &Config) -> (String, EnvFilter) {
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        return (std::env::var("RUST_LOG").unwrap_or_default(), filter);
+    }
+    if let Ok(directives) = config.get_param::<String>(LOG_FILTER_KEY) {
+        let directives = merge_directives(DEFAULT_FILTER, &directives);
+        match EnvFilter::try_new(&directives) {
+            Ok(filter) => return (directives, filter),
+            Err(e) => eprintln!("Ignoring {}: {}", LOG_FILTER_KEY, e),
+        }
+    }
+    (DEFAULT_FILTER.to_string(), EnvFilter::new(DEFAULT_FILTER))
+}
+
 /// Sets up the logging infrastructure for the application.
 /// This includes:
-/// - File-based logging with JSON formatting (DEBUG level)
-/// - No console output (all logs go to files only)
+/// - The configured sinks, by default only a JSON log file
 /// - Optional Langfuse integration (DEBUG level)
 /// - Optional error capture layer for benchmarking
+/// - Logging panics
 pub fn setup_logging(
     name: Option<&str>,
     error_capture: Option<Arc<Mutex<Vec<BenchAgentError>>>>,
@@ -50,58 +387,22 @@ fn setup_logging_internal(
 
     let mut setup = || {
         result = (|| {
-            // Set up file appender for goose module logs
-            let log_dir = get_log_directory()?;
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
-
-            // Create log file name by prefixing with timestamp
-            let log_filename = if name.is_some() {
-                format!("{}-{}.log", timestamp, name.unwrap())
-            } else {
-                format!("{}.log", timestamp)
-            };
-
-            // Create non-rolling file appender for detailed logs
-            let file_appender = tracing_appender::rolling::RollingFileAppender::new(
-                Rotation::NEVER,
-                log_dir,
-                log_filename,
-            );
-
-            // Create JSON file logging layer with all logs (DEBUG and above)
-            let file_layer = fmt::layer()
-                .with_target(true)
-                .with_level(true)
-                .with_writer(file_appender)
-                .with_ansi(false)
-                .json();
-
-            // Base filter
-            let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                // Set default levels for different modules
-                EnvFilter::new("")
-                    // Set mcp-client to DEBUG
-                    .add_directive("mcp_client=debug".parse().unwrap())
-                    // Set goose module to DEBUG
-                    .add_directive("goose=debug".parse().unwrap())
-                    // Set goose-cli to INFO
-                    .add_directive("goose_cli=info".parse().unwrap())
-                    // Set everything else to WARN
-                    .add_directive(LevelFilter::WARN.into())
-            });
+            let config = Config::global();
+            let (directives, env_filter) = initial_filter(config);
+            let (filter, handle) = reload::Layer::new(env_filter);
 
             // Start building the subscriber
-            let mut layers = vec![
-                file_layer.with_filter(env_filter).boxed(),
-                // Console logging disabled for CLI - all logs go to files only
-            ];
+            let mut layers = vec![sink_layers(name, config)?.with_filter(filter).boxed()];
 
             // Only add ErrorCaptureLayer if not in test mode
             if !force {
                 layers.push(ErrorCaptureLayer::new().boxed());
             }
 
-            if !force {
+            // Exporting telemetry needs the network
+            let offline = goose::offline::is_offline();
+
+            if !force && !offline {
                 if let Ok((otlp_tracing_layer, otlp_metrics_layer)) = otlp_layer::init_otlp() {
                     layers.push(
                         otlp_tracing_layer
@@ -116,7 +417,8 @@ fn setup_logging_internal(
                 }
             }
 
-            if let Some(langfuse) = langfuse_layer::create_langfuse_observer() {
+            if let Some(langfuse) = langfuse_layer::create_langfuse_observer().filter(|_| !offline)
+            {
                 layers.push(langfuse.with_filter(LevelFilter::DEBUG).boxed());
             }
 
@@ -137,6 +439,9 @@ fn setup_logging_internal(
                 subscriber
                     .try_init()
                     .context("Failed to set global subscriber")?;
+                let _ = FILTER.set(handle);
+                *FILTER_DIRECTIVES.lock().unwrap_or_else(|e| e.into_inner()) = directives;
+                install_panic_hook();
                 Ok(())
             }
         })();
@@ -181,6 +486,43 @@ mod tests {
         assert!(path_components.iter().any(|c| c.as_os_str() == "cli"));
     }
 
+    #[test]
+    fn test_parse_sinks() {
+        assert_eq!(parse_sinks(""), vec![Sink::File]);
+        assert_eq!(
+            parse_sinks("stderr, journald,stderr"),
+            vec![Sink::Stderr, Sink::Journald]
+        );
+        assert_eq!(parse_sinks("syslog"), vec![Sink::File]);
+        assert_eq!(parse_rotation("Daily"), Rotation::DAILY);
+        assert_eq!(parse_rotation(""), Rotation::NEVER);
+    }
+
+    #[test]
+    fn test_merge_directives() {
+        assert_eq!(
+            merge_directives(DEFAULT_FILTER, "mcp_client=info"),
+            "warn,goose=debug,goose_cli=info,mcp_client=info"
+        );
+        assert_eq!(
+            merge_directives("warn,goose=debug", "error, goose::agents=trace"),
+            "goose=debug,error,goose::agents=trace"
+        );
+        assert!(EnvFilter::try_new(merge_directives(DEFAULT_FILTER, "goose=trace")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_journald_payload() {
+        let payload = journald::payload(4, b"slow response\nretrying\n");
+        let header = b"PRIORITY=4\nSYSLOG_IDENTIFIER=goose\nMESSAGE\n";
+        assert!(payload.starts_with(header));
+        let length =
+            u64::from_le_bytes(payload[header.len()..header.len() + 8].try_into().unwrap());
+        assert_eq!(length, "slow response\nretrying".len() as u64);
+        assert!(payload.ends_with(b"retrying\n"));
+    }
+
     #[tokio::test]
     async fn test_langfuse_layer_creation() {
         let _temp_dir = setup_temp_home();
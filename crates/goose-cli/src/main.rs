@@ -1,8 +1,13 @@
 use anyhow::Result;
 use goose_cli::cli::cli;
+use goose_cli::exit_code::ExitCode;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Telemetry starts before arguments are parsed, so it has to see --offline already
+    if std::env::args().skip(1).any(|arg| arg == "--offline") {
+        goose::offline::set_offline();
+    }
     if let Err(e) = goose_cli::logging::setup_logging(None, None) {
         eprintln!("Warning: Failed to initialize telemetry: {}", e);
     }
@@ -34,5 +39,10 @@ async fn main() -> Result<()> {
         goose::tracing::shutdown_otlp();
     }
 
-    result
+    // Exit with the failure class of the error
+    if let Err(e) = result {
+        eprintln!("Error: {:?}", e);
+        ExitCode::of_error(&e).exit();
+    }
+    Ok(())
 }
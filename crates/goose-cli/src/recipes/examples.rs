@@ -0,0 +1,85 @@
+//! Example recipes bundled with goose, shown by `goose recipe examples`.
+
+use std::path::Path;
+
+use anyhow::Result;
+use goose::recipe::Recipe;
+
+pub const PROJECT_NAME_PLACEHOLDER: &str = "__PROJECT_NAME__";
+const FALLBACK_PROJECT_NAME: &str = "my-project";
+
+pub struct ExampleRecipe {
+    pub name: &'static str,
+    content: &'static str,
+}
+
+pub const EXAMPLE_RECIPES: &[ExampleRecipe] = &[
+    ExampleRecipe {
+        name: "code-review",
+        content: include_str!("examples/code-review.yaml"),
+    },
+    ExampleRecipe {
+        name: "dependency-upgrade",
+        content: include_str!("examples/dependency-upgrade.yaml"),
+    },
+    ExampleRecipe {
+        name: "changelog",
+        content: include_str!("examples/changelog.yaml"),
+    },
+];
+
+impl ExampleRecipe {
+    /// The recipe for `project_name`
+    pub fn render(&self, project_name: &str) -> String {
+        self.content.replace(PROJECT_NAME_PLACEHOLDER, project_name)
+    }
+
+    pub fn recipe(&self, project_name: &str) -> Result<Recipe> {
+        Recipe::from_content(&self.render(project_name))
+    }
+}
+
+/// The example called `name`, with or without its `.yaml` extension
+pub fn find_example(name: &str) -> Option<&'static ExampleRecipe> {
+    let name = name.trim().trim_end_matches(".yaml");
+    EXAMPLE_RECIPES
+        .iter()
+        .find(|example| example.name.eq_ignore_ascii_case(name))
+}
+
+/// The name of the project in `dir`: the directory's own name
+pub fn project_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| FALLBACK_PROJECT_NAME.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples_are_valid_recipes() {
+        for example in EXAMPLE_RECIPES {
+            let rendered = example.render("acme-api");
+            assert!(!rendered.contains(PROJECT_NAME_PLACEHOLDER));
+            let recipe = example.recipe("acme-api").unwrap();
+            assert!(recipe.title.contains("acme-api"), "{}", example.name);
+            assert!(recipe.instructions.is_some(), "{}", example.name);
+        }
+    }
+
+    #[test]
+    fn test_find_example() {
+        assert_eq!(find_example("changelog.yaml").unwrap().name, "changelog");
+        assert_eq!(find_example("Code-Review").unwrap().name, "code-review");
+        assert!(find_example("deploy").is_none());
+    }
+
+    #[test]
+    fn test_project_name() {
+        assert_eq!(project_name(Path::new("/home/me/acme-api")), "acme-api");
+        assert_eq!(project_name(Path::new("/")), FALLBACK_PROJECT_NAME);
+    }
+}
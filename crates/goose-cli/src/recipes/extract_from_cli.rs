@@ -34,6 +34,13 @@ pub fn extract_recipe_info_from_cli(
                         values: None,
                         sequential_when_repeated: true,
                         description: None,
+                        resources: None,
+                        retry: None,
+                        timeout_seconds: None,
+                        priority: None,
+                        artifacts: Vec::new(),
+                        depends_on: Vec::new(),
+                        cache: false,
                     };
                     all_sub_recipes.push(additional_sub_recipe);
                 }
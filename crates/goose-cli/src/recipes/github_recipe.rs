@@ -37,6 +37,7 @@ pub fn retrieve_recipe_from_github(
         "📦 Looking for recipe \"{}\" in github repo: {}",
         recipe_name, recipe_repo_full_name
     );
+    goose::offline::require_network("Fetching recipes from GitHub")?;
     ensure_gh_authenticated()?;
     let max_attempts = 2;
     let mut last_err = None;
@@ -217,6 +218,7 @@ fn list_files(dir: &Path) -> Result<()> {
 
 /// Lists all available recipes from a GitHub repository
 pub fn list_github_recipes(repo: &str) -> Result<Vec<RecipeInfo>> {
+    goose::offline::require_network("Listing recipes on GitHub")?;
     discover_github_recipes(repo)
 }
 
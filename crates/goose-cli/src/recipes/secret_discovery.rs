@@ -50,17 +50,28 @@ fn extract_secrets_from_extensions(
     let mut secrets = Vec::new();
 
     for ext in extensions {
-        let (extension_name, env_keys) = match ext {
-            ExtensionConfig::Sse { name, env_keys, .. } => (name, env_keys),
-            ExtensionConfig::Stdio { name, env_keys, .. } => (name, env_keys),
-            ExtensionConfig::StreamableHttp { name, env_keys, .. } => (name, env_keys),
-            ExtensionConfig::Builtin { name, .. } => (name, &Vec::new()),
-            ExtensionConfig::Platform { name, .. } => (name, &Vec::new()),
-            ExtensionConfig::Frontend { name, .. } => (name, &Vec::new()),
-            ExtensionConfig::InlinePython { name, .. } => (name, &Vec::new()),
+        let (extension_name, env_keys, auth) = match ext {
+            ExtensionConfig::Sse {
+                name,
+                env_keys,
+                auth,
+                ..
+            } => (name, env_keys, auth.as_ref()),
+            ExtensionConfig::Stdio { name, env_keys, .. } => (name, env_keys, None),
+            ExtensionConfig::StreamableHttp {
+                name,
+                env_keys,
+                auth,
+                ..
+            } => (name, env_keys, auth.as_ref()),
+            ExtensionConfig::Builtin { name, .. } => (name, &Vec::new(), None),
+            ExtensionConfig::Platform { name, .. } => (name, &Vec::new(), None),
+            ExtensionConfig::Frontend { name, .. } => (name, &Vec::new(), None),
+            ExtensionConfig::InlinePython { name, .. } => (name, &Vec::new(), None),
         };
 
-        for key in env_keys {
+        // The token or API key of a remote extension is a secret like any other
+        for key in env_keys.iter().chain(auth.map(|auth| &auth.secret)) {
             if seen_keys.insert(key.clone()) {
                 let secret_req = SecretRequirement::new(extension_name.clone(), key.clone());
                 secrets.push(secret_req);
@@ -125,6 +136,7 @@ fn load_sub_recipe(recipe_path: &str) -> Result<Recipe, Box<dyn std::error::Erro
 mod tests {
     use super::*;
     use goose::agents::extension::{Envs, ExtensionConfig};
+    use goose::agents::remote_auth::{AuthScheme, RemoteAuth};
     use goose::recipe::Recipe;
     use std::collections::HashMap;
 
@@ -141,6 +153,7 @@ mod tests {
                     uri: "sse://example.com".to_string(),
                     envs: Envs::new(HashMap::new()),
                     env_keys: vec!["GITHUB_TOKEN".to_string(), "GITHUB_API_URL".to_string()],
+                    auth: None,
                     description: "github-mcp".to_string(),
                     timeout: None,
                     bundled: None,
@@ -201,6 +214,26 @@ mod tests {
         assert_eq!(slack_token.extension_name, "slack-mcp");
     }
 
+    #[test]
+    fn test_discover_remote_auth_secret() {
+        let mut recipe = create_test_recipe_with_extensions();
+        if let Some(ExtensionConfig::Sse { auth, .. }) = recipe
+            .extensions
+            .as_mut()
+            .and_then(|extensions| extensions.first_mut())
+        {
+            *auth = Some(RemoteAuth {
+                scheme: AuthScheme::Bearer,
+                secret: "GITHUB_MCP_AUTH".to_string(),
+            });
+        }
+        let secrets = discover_recipe_secrets(&recipe);
+
+        assert_eq!(secrets.len(), 4);
+        let auth = secrets.iter().find(|s| s.key == "GITHUB_MCP_AUTH").unwrap();
+        assert_eq!(auth.extension_name, "github-mcp");
+    }
+
     #[test]
     fn test_discover_recipe_secrets_empty_recipe() {
         let recipe = Recipe {
@@ -238,6 +271,7 @@ mod tests {
                     uri: "sse://example.com".to_string(),
                     envs: Envs::new(HashMap::new()),
                     env_keys: vec!["API_KEY".to_string()],
+                    auth: None,
                     description: "service-a".to_string(),
                     timeout: None,
                     bundled: None,
@@ -297,6 +331,7 @@ mod tests {
                 uri: "sse://parent.com".to_string(),
                 envs: Envs::new(HashMap::new()),
                 env_keys: vec!["PARENT_TOKEN".to_string()],
+                auth: None,
                 description: "parent-ext".to_string(),
                 timeout: None,
                 bundled: None,
@@ -308,6 +343,13 @@ mod tests {
                 values: None,
                 sequential_when_repeated: false,
                 description: None,
+                resources: None,
+                retry: None,
+                timeout_seconds: None,
+                priority: None,
+                artifacts: Vec::new(),
+                depends_on: Vec::new(),
+                cache: false,
             }]),
             context: None,
             settings: None,
@@ -0,0 +1,104 @@
+//! The prompt shown under a tool call box when goose mode asks for approval.
+
+use anyhow::{anyhow, Context, Result};
+use goose::permission::Permission;
+use rmcp::model::JsonObject;
+use std::io::Write;
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalChoice {
+    Decide(Permission),
+    EditArguments,
+}
+
+/// Ask what to do with a tool call. `Always Allow` is left out when `allow_always` is false,
+/// e.g. when a security finding explains why the call needs a look.
+pub fn prompt_approval(prompt: &str, allow_always: bool) -> std::io::Result<ApprovalChoice> {
+    let mut select = cliclack::select(prompt).item(
+        ApprovalChoice::Decide(Permission::AllowOnce),
+        "Allow",
+        "Allow the tool call once",
+    );
+    if allow_always {
+        select = select.item(
+            ApprovalChoice::Decide(Permission::AlwaysAllow),
+            "Always Allow",
+            "Always allow this tool",
+        );
+    }
+    select
+        .item(
+            ApprovalChoice::EditArguments,
+            "Edit Arguments",
+            "Edit the arguments in $EDITOR, then decide",
+        )
+        .item(
+            ApprovalChoice::Decide(Permission::DenyOnce),
+            "Deny",
+            "Deny the tool call",
+        )
+        .item(
+            ApprovalChoice::Decide(Permission::Cancel),
+            "Cancel",
+            "Cancel the AI response and tool call",
+        )
+        .interact()
+}
+
+/// The editor to open, with its arguments: `$VISUAL`, then `$EDITOR`, then vi
+fn editor_command() -> Vec<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .and_then(|value| shlex::split(&value))
+        .filter(|parts| !parts.is_empty())
+        .unwrap_or_else(|| vec!["vi".to_string()])
+}
+
+/// Parse what came back from the editor; it has to stay a JSON object
+fn parse_edited(text: &str) -> Result<JsonObject> {
+    match serde_json::from_str(text).context("The edited arguments are not valid JSON")? {
+        serde_json::Value::Object(arguments) => Ok(arguments),
+        _ => Err(anyhow!("The edited arguments must be a JSON object")),
+    }
+}
+
+/// Open `arguments` in the editor and return the edited version, or `None` if they were left as
+/// they were
+pub fn edit_arguments(arguments: &JsonObject) -> Result<Option<JsonObject>> {
+    let mut file = tempfile::Builder::new()
+        .prefix("goose-tool-arguments-")
+        .suffix(".json")
+        .tempfile()?;
+    writeln!(file, "{}", serde_json::to_string_pretty(arguments)?)?;
+    file.flush()?;
+
+    let command = editor_command();
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .arg(file.path())
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", command[0]))?;
+    if !status.success() {
+        return Err(anyhow!("The editor exited with {}", status));
+    }
+
+    let edited = parse_edited(&std::fs::read_to_string(file.path())?)?;
+    Ok((&edited != arguments).then_some(edited))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_edited() {
+        let arguments = parse_edited("{\"command\": \"ls -la\"}\n").unwrap();
+        assert_eq!(arguments.get("command"), Some(&json!("ls -la")));
+        assert!(parse_edited("[\"ls\"]").is_err());
+        assert!(parse_edited("{\"command\": ").is_err());
+    }
+}
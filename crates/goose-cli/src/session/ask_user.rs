@@ -0,0 +1,65 @@
+//! Answering the `ask_user` tool in the terminal.
+
+use goose::agents::ask_user_tool::{
+    resolve_file, Answer, Question, QuestionKind, ASK_USER_DEFAULTS_KEY,
+};
+use goose::config::Config;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Ask `question` and wait for the answer. An `Interrupted` error means the user cancelled.
+pub fn ask(question: &Question) -> std::io::Result<Answer> {
+    let default = question
+        .default
+        .as_ref()
+        .and_then(|value| question.answer_from(value).ok());
+    match question.kind {
+        QuestionKind::Choice if question.multiple => {
+            let mut select = cliclack::multiselect(&question.question).required(true);
+            for option in &question.options {
+                select = select.item(option.clone(), option, "");
+            }
+            if let Some(Answer::Choice(picked)) = default {
+                select = select.initial_values(picked);
+            }
+            Ok(Answer::Choice(select.interact()?))
+        }
+        QuestionKind::Choice => {
+            let mut select = cliclack::select(&question.question);
+            for option in &question.options {
+                select = select.item(option.clone(), option, "");
+            }
+            if let Some(Answer::Choice(picked)) = default {
+                select = select.initial_value(picked[0].clone());
+            }
+            Ok(Answer::Choice(vec![select.interact()?]))
+        }
+        QuestionKind::Text => {
+            let mut input = cliclack::input(&question.question);
+            if let Some(Answer::Text(text)) = default {
+                input = input.default_input(&text);
+            }
+            Ok(Answer::Text(input.interact()?))
+        }
+        QuestionKind::File => {
+            let working_dir = std::env::current_dir().unwrap_or_default();
+            let mut input = cliclack::input(&question.question).placeholder("path to a file");
+            if let Some(Answer::File(path)) = default {
+                input = input.default_input(&path.to_string_lossy());
+            }
+            let dir = working_dir.clone();
+            let path: String = input
+                .validate(move |path: &String| resolve_file(path, &dir).map(|_| ()))
+                .interact()?;
+            Ok(Answer::File(working_dir.join(path.trim())))
+        }
+    }
+}
+
+/// The answer for a headless run, from `GOOSE_ASK_USER_DEFAULTS` or the question's default
+pub fn unattended(question: &Question) -> Result<Answer, String> {
+    let defaults = Config::global()
+        .get_param::<HashMap<String, Value>>(ASK_USER_DEFAULTS_KEY)
+        .unwrap_or_default();
+    question.unattended_answer(&defaults)
+}
@@ -1,5 +1,7 @@
 use super::output;
+use super::startup_warnings::{missing_secrets, sign_in_expiring, StartupWarnings, WarningCode};
 use super::CliSession;
+use crate::exit_code::ExitCode;
 use console::style;
 use goose::agents::types::RetryConfig;
 use goose::agents::Agent;
@@ -11,7 +13,6 @@ use goose::agents::extension::PlatformExtensionContext;
 use goose::session::SessionManager;
 use rustyline::EditMode;
 use std::collections::HashSet;
-use std::process;
 use std::sync::Arc;
 use tokio::task::JoinSet;
 
@@ -51,12 +52,18 @@ pub struct SessionBuilderConfig {
     pub max_tool_repetitions: Option<u32>,
     /// Maximum number of turns (iterations) allowed without user input
     pub max_turns: Option<u32>,
+    /// Start stdio extensions with the full environment instead of the allowlisted variables
+    pub inherit_env: bool,
+    /// Run every sub-recipe and dynamic task instead of reusing cached results
+    pub no_cache: bool,
     /// ID of the scheduled job that triggered this session (if any)
     pub scheduled_job_id: Option<String>,
     /// Whether this session will be used interactively (affects debugging prompts)
     pub interactive: bool,
     /// Quiet mode - suppress non-response output
     pub quiet: bool,
+    /// Porcelain mode - decorative output goes to stderr, leaving stdout to the response
+    pub porcelain: bool,
     /// Sub-recipes to add to the session
     pub sub_recipes: Option<Vec<SubRecipe>>,
     /// Final output expected response
@@ -159,6 +166,8 @@ pub struct SessionSettings {
 }
 
 pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
+    output::set_porcelain(session_config.porcelain);
+
     // Load config and get provider/model
     let config = Config::global();
 
@@ -171,7 +180,10 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 .and_then(|s| s.goose_provider.clone())
         })
         .or_else(|| config.get_param("GOOSE_PROVIDER").ok())
-        .expect("No provider configured. Run 'goose configure' first");
+        .unwrap_or_else(|| {
+            output::render_error("No provider configured. Run 'goose configure' first");
+            ExitCode::Config.exit();
+        });
 
     let model_name = session_config
         .model
@@ -182,19 +194,26 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 .and_then(|s| s.goose_model.clone())
         })
         .or_else(|| config.get_param("GOOSE_MODEL").ok())
-        .expect("No model configured. Run 'goose configure' first");
+        .unwrap_or_else(|| {
+            output::render_error("No model configured. Run 'goose configure' first");
+            ExitCode::Config.exit();
+        });
 
     let temperature = session_config.settings.as_ref().and_then(|s| s.temperature);
 
     let model_config = goose::model::ModelConfig::new(&model_name)
         .unwrap_or_else(|e| {
             output::render_error(&format!("Failed to create model configuration: {}", e));
-            process::exit(1);
+            ExitCode::Config.exit();
         })
         .with_temperature(temperature);
 
     // Create the agent
     let agent: Agent = Agent::new();
+    agent
+        .extension_manager
+        .set_inherit_env(session_config.inherit_env);
+    agent.set_cache_task_results(!session_config.no_cache);
 
     if let Some(sub_recipes) = session_config.sub_recipes {
         agent.add_sub_recipes(sub_recipes).await;
@@ -203,6 +222,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
     if let Some(final_output_response) = session_config.final_output_response {
         agent.add_final_output_tool(final_output_response).await;
     }
+    agent.enable_ask_user().await;
 
     let new_provider = match create(&provider_name, model_config) {
         Ok(provider) => provider,
@@ -214,7 +234,11 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 For more info, see: https://block.github.io/goose/docs/troubleshooting/#keychainkeyring-errors",
                 e
             ));
-            process::exit(1);
+            // Only a rejected credential is an auth failure
+            match ExitCode::of_error(&e) {
+                ExitCode::ProviderAuth => ExitCode::ProviderAuth.exit(),
+                _ => ExitCode::Config.exit(),
+            }
         }
     };
     // Keep a reference to the provider for display_session_info
@@ -237,7 +261,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         .await
         .unwrap_or_else(|e| {
             output::render_error(&format!("Failed to initialize agent: {}", e));
-            process::exit(1);
+            ExitCode::of_error(&e).exit();
         });
 
     // Handle session file resolution and resuming
@@ -252,7 +276,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                         "Cannot resume session {} - no such session exists",
                         style(&session_id).cyan()
                     ));
-                    process::exit(1);
+                    ExitCode::Usage.exit();
                 }
             }
         } else {
@@ -260,13 +284,13 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 Ok(sessions) => {
                     if sessions.is_empty() {
                         output::render_error("Cannot resume - no previous sessions found");
-                        process::exit(1);
+                        ExitCode::Usage.exit();
                     }
                     Some(sessions[0].id.clone())
                 }
                 Err(_) => {
                     output::render_error("Cannot resume - no previous sessions found");
-                    process::exit(1);
+                    ExitCode::Usage.exit();
                 }
             }
         }
@@ -281,6 +305,9 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         .unwrap();
         Some(session.id)
     };
+    if let Some(session_id) = &session_id {
+        crate::logging::set_panic_session(session_id);
+    }
 
     agent
         .extension_manager
@@ -296,7 +323,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 .await
                 .unwrap_or_else(|e| {
                     output::render_error(&format!("Failed to read session metadata: {}", e));
-                    process::exit(1);
+                    ExitCode::TaskFailure.exit();
                 });
 
             let current_workdir =
@@ -342,8 +369,22 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
     let mut set = JoinSet::new();
     let agent_ptr = Arc::new(agent);
 
+    let mut warnings = StartupWarnings::default();
     let mut waiting_on = HashSet::new();
     for extension in extensions_to_run {
+        if let Some(message) = sign_in_expiring(&extension) {
+            warnings.push(WarningCode::SignInExpiring, message);
+        }
+        for key in missing_secrets(&extension) {
+            warnings.push(
+                WarningCode::MissingSecret,
+                format!(
+                    "Extension '{}' needs {}, which is not set",
+                    extension.name(),
+                    key
+                ),
+            );
+        }
         waiting_on.insert(extension.name());
         let agent_ptr = agent_ptr.clone();
         set.spawn(async move {
@@ -363,6 +404,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
     let spinner = cliclack::spinner();
     spinner.start(get_message(&waiting_on));
 
+    // Extensions that failed to start, offered for debugging once the warnings are shown
     let mut offer_debug = Vec::new();
     while let Some(result) = set.join_next().await {
         match result {
@@ -370,26 +412,19 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 waiting_on.remove(&name);
                 spinner.set_message(get_message(&waiting_on));
             }
-            Ok((name, Err(e))) => offer_debug.push((name, e)),
+            Ok((name, Err(e))) => {
+                warnings.push(
+                    WarningCode::ExtensionFailed,
+                    format!("Extension '{}' failed to start: {}", name, e),
+                );
+                offer_debug.push((name, e.to_string()));
+            }
             Err(e) => tracing::error!("failed to add extension: {}", e),
         }
     }
 
     spinner.clear();
 
-    for (name, err) in offer_debug {
-        if let Err(debug_err) = offer_extension_debugging_help(
-            &name,
-            &err.to_string(),
-            Arc::clone(&provider_for_display),
-            session_config.interactive,
-        )
-        .await
-        {
-            eprintln!("Note: Could not start debugging session: {}", debug_err);
-        }
-    }
-
     // Determine editor mode
     let edit_mode = config
         .get_param::<String>("EDIT_MODE")
@@ -397,8 +432,11 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         .and_then(|edit_mode| match edit_mode.to_lowercase().as_str() {
             "emacs" => Some(EditMode::Emacs),
             "vi" => Some(EditMode::Vi),
-            _ => {
-                eprintln!("Invalid EDIT_MODE specified, defaulting to Emacs");
+            other => {
+                warnings.push(
+                    WarningCode::InvalidEditMode,
+                    format!("EDIT_MODE '{}' is not emacs or vi, using emacs", other),
+                );
                 None
             }
         });
@@ -419,64 +457,25 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
     // Add extensions if provided
     for extension_str in session_config.extensions {
         if let Err(e) = session.add_extension(extension_str.clone()).await {
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Warning: Failed to start extension '{}': {}",
-                    extension_str, e
-                ))
-                .yellow()
+            warnings.push(
+                WarningCode::ExtensionFailed,
+                format!("Extension '{}' failed to start: {}", extension_str, e),
             );
-            eprintln!(
-                "{}",
-                style(format!("Continuing without extension '{}'", extension_str)).yellow()
-            );
-
-            // Offer debugging help
-            if let Err(debug_err) = offer_extension_debugging_help(
-                &extension_str,
-                &e.to_string(),
-                Arc::clone(&provider_for_display),
-                session_config.interactive,
-            )
-            .await
-            {
-                eprintln!("Note: Could not start debugging session: {}", debug_err);
-            }
+            offer_debug.push((extension_str, e.to_string()));
         }
     }
 
     // Add remote extensions if provided
     for extension_str in session_config.remote_extensions {
         if let Err(e) = session.add_remote_extension(extension_str.clone()).await {
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Warning: Failed to start remote extension '{}': {}",
+            warnings.push(
+                WarningCode::ExtensionFailed,
+                format!(
+                    "Remote extension '{}' failed to start: {}",
                     extension_str, e
-                ))
-                .yellow()
+                ),
             );
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Continuing without remote extension '{}'",
-                    extension_str
-                ))
-                .yellow()
-            );
-
-            // Offer debugging help
-            if let Err(debug_err) = offer_extension_debugging_help(
-                &extension_str,
-                &e.to_string(),
-                Arc::clone(&provider_for_display),
-                session_config.interactive,
-            )
-            .await
-            {
-                eprintln!("Note: Could not start debugging session: {}", debug_err);
-            }
+            offer_debug.push((extension_str, e.to_string()));
         }
     }
 
@@ -486,71 +485,53 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
             .add_streamable_http_extension(extension_str.clone())
             .await
         {
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Warning: Failed to start streamable HTTP extension '{}': {}",
+            warnings.push(
+                WarningCode::ExtensionFailed,
+                format!(
+                    "Streamable HTTP extension '{}' failed to start: {}",
                     extension_str, e
-                ))
-                .yellow()
-            );
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Continuing without streamable HTTP extension '{}'",
-                    extension_str
-                ))
-                .yellow()
+                ),
             );
-
-            // Offer debugging help
-            if let Err(debug_err) = offer_extension_debugging_help(
-                &extension_str,
-                &e.to_string(),
-                Arc::clone(&provider_for_display),
-                session_config.interactive,
-            )
-            .await
-            {
-                eprintln!("Note: Could not start debugging session: {}", debug_err);
-            }
+            offer_debug.push((extension_str, e.to_string()));
         }
     }
 
     // Add builtin extensions
     for builtin in session_config.builtins {
         if let Err(e) = session.add_builtin(builtin.clone()).await {
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Warning: Failed to start builtin extension '{}': {}",
-                    builtin, e
-                ))
-                .yellow()
-            );
-            eprintln!(
-                "{}",
-                style(format!(
-                    "Continuing without builtin extension '{}'",
-                    builtin
-                ))
-                .yellow()
+            warnings.push(
+                WarningCode::ExtensionFailed,
+                format!("Builtin extension '{}' failed to start: {}", builtin, e),
             );
-
-            // Offer debugging help
-            if let Err(debug_err) = offer_extension_debugging_help(
-                &builtin,
-                &e.to_string(),
-                Arc::clone(&provider_for_display),
-                session_config.interactive,
-            )
-            .await
-            {
-                eprintln!("Note: Could not start debugging session: {}", debug_err);
-            }
+            offer_debug.push((builtin, e.to_string()));
         }
     }
 
+    // Tell the user once what each stdio extension can see of their environment
+    for notice in session.agent.extension_manager.take_env_notices().await {
+        let (code, message) = if notice.inherit {
+            let message = format!(
+                "Extension '{}' was started with your full environment{}",
+                notice.extension,
+                if notice.variables.is_empty() {
+                    String::new()
+                } else {
+                    format!(", plus {}", notice.variables.join(", "))
+                }
+            );
+            (WarningCode::EnvInherited, message)
+        } else {
+            let message = format!(
+                "Extension '{}' was started with only these environment variables: {}. \
+                 List more under GOOSE_EXTENSION_ENV_ALLOWLIST or use --inherit-env.",
+                notice.extension,
+                notice.variables.join(", ")
+            );
+            (WarningCode::EnvRestricted, message)
+        };
+        warnings.push(code, message);
+    }
+
     // Add CLI-specific system prompt extension
     session
         .agent
@@ -567,6 +548,10 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         let override_prompt =
             std::fs::read_to_string(path).expect("Failed to read system prompt file");
         session.agent.override_system_prompt(override_prompt).await;
+        warnings.push(
+            WarningCode::SystemPromptOverride,
+            format!("Using the system prompt from {}", path),
+        );
     }
 
     // Display session information unless in quiet mode
@@ -579,6 +564,20 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
             Some(&provider_for_display),
         );
     }
+    warnings.render();
+
+    for (name, err) in offer_debug {
+        if let Err(debug_err) = offer_extension_debugging_help(
+            &name,
+            &err,
+            Arc::clone(&provider_for_display),
+            session_config.interactive,
+        )
+        .await
+        {
+            eprintln!("Note: Could not start debugging session: {}", debug_err);
+        }
+    }
     session
 }
 
@@ -604,9 +603,12 @@ mod tests {
             debug: true,
             max_tool_repetitions: Some(5),
             max_turns: None,
+            inherit_env: false,
+            no_cache: false,
             scheduled_job_id: None,
             interactive: true,
             quiet: false,
+            porcelain: false,
             sub_recipes: None,
             final_output_response: None,
             retry_config: None,
@@ -643,6 +645,7 @@ mod tests {
         assert!(config.scheduled_job_id.is_none());
         assert!(!config.interactive);
         assert!(!config.quiet);
+        assert!(!config.porcelain);
         assert!(config.final_output_response.is_none());
     }
 
@@ -1,26 +1,31 @@
-use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::completion::{Completer, Pair};
 use rustyline::highlight::{CmdKind, Highlighter};
 use rustyline::hint::Hinter;
 use rustyline::validate::Validator;
 use rustyline::{Context, Helper, Result};
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::Arc;
 
 use super::CompletionCache;
 
+/// Most path candidates offered at once, so the completion menu stays small
+const MAX_PATH_CANDIDATES: usize = 40;
+
+/// Entries of one directory looked at before giving up, to keep huge directories responsive
+const MAX_DIR_ENTRIES: usize = 2000;
+
 /// Completer for goose CLI commands
 pub struct GooseCompleter {
     completion_cache: Arc<std::sync::RwLock<CompletionCache>>,
-    filename_completer: FilenameCompleter,
 }
 
 impl GooseCompleter {
     /// Create a new GooseCompleter with a reference to the Session's completion cache
     pub fn new(completion_cache: Arc<std::sync::RwLock<CompletionCache>>) -> Self {
-        Self {
-            completion_cache,
-            filename_completer: FilenameCompleter::new(),
-        }
+        Self { completion_cache }
     }
 
     /// Complete prompt names for the /prompt command
@@ -260,32 +265,132 @@ impl GooseCompleter {
     }
 
     /// Complete file paths
-    fn complete_file_path(&self, line: &str, ctx: &Context) -> Result<(usize, Vec<Pair>)> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
+    fn complete_file_path(&self, line: &str) -> Result<(usize, Vec<Pair>)> {
+        let (start, word) = last_word(line);
 
-        if let Some(last_part) = parts.last() {
-            // Skip filename completion for words starting with special characters
-            if last_part.starts_with('/') && last_part.len() == 1 {
-                // Just a slash - no completion
-                return Ok((line.len(), vec![]));
-            }
+        // @-mentions complete the path after the @
+        if let Some(path) = word.strip_prefix('@') {
+            return Ok((start + 1, complete_path(path)));
+        }
 
-            if last_part.starts_with('-') || last_part.contains('=') {
-                // Skip flag or key-value pairs
-                return Ok((line.len(), vec![]));
-            }
+        // Skip filename completion for words starting with special characters
+        if word.is_empty() || word == "/" || word.starts_with('-') || word.contains('=') {
+            return Ok((line.len(), vec![]));
+        }
 
-            // Complete the partial path
-            let pos = line.len() - last_part.len();
-            let (start, candidates) =
-                self.filename_completer
-                    .complete(last_part, last_part.len(), ctx)?;
+        Ok((start, complete_path(word)))
+    }
 
-            // Return the completion results, with adjusted position
-            return Ok((pos + start, candidates));
+    /// Complete a path given as the value of a `key=value` prompt argument
+    fn complete_argument_value(&self, line: &str) -> Option<(usize, Vec<Pair>)> {
+        let (start, word) = last_word(line);
+        let (_, value) = word.split_once('=')?;
+        if value.is_empty() {
+            return None;
         }
+        Some((start + word.len() - value.len(), complete_path(value)))
+    }
+}
 
-        Ok((line.len(), vec![]))
+/// Start and text of the word the cursor is at, when the cursor is at the end of `line`
+fn last_word(line: &str) -> (usize, &str) {
+    let start = line
+        .rfind(char::is_whitespace)
+        .map(|i| i + line[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    (start, &line[start..])
+}
+
+/// Complete a partially typed path relative to the current directory
+fn complete_path(partial: &str) -> Vec<Pair> {
+    match std::env::current_dir() {
+        Ok(cwd) => path_candidates(partial, &cwd),
+        Err(_) => vec![],
+    }
+}
+
+/// Resolve the directory part of a partial path, e.g. "src/" or "~/code/"
+fn resolve_dir(dir_part: &str, base_dir: &Path) -> Option<PathBuf> {
+    if dir_part.is_empty() {
+        return Some(base_dir.to_path_buf());
+    }
+    if let Some(rest) = dir_part.strip_prefix("~/") {
+        return etcetera::home_dir().ok().map(|home| home.join(rest));
+    }
+    Some(base_dir.join(dir_part))
+}
+
+/// Files and directories matching `partial`, directories first, skipping hidden entries (unless
+/// asked for) and anything git ignores
+fn path_candidates(partial: &str, base_dir: &Path) -> Vec<Pair> {
+    let (dir_part, file_prefix) = match partial.rfind('/') {
+        Some(i) => partial.split_at(i + 1),
+        None => ("", partial),
+    };
+    let Some(dir) = resolve_dir(dir_part, base_dir) else {
+        return vec![];
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return vec![];
+    };
+
+    let mut matches: Vec<(String, bool)> = entries
+        .take(MAX_DIR_ENTRIES)
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            let hidden = name.starts_with('.') && !file_prefix.starts_with('.');
+            if !name.starts_with(file_prefix) || hidden || name == ".git" {
+                return None;
+            }
+            // follow symlinks so linked directories complete like directories
+            Some((name, entry.path().is_dir()))
+        })
+        .collect();
+
+    let ignored = git_ignored(&dir, &matches);
+    matches.retain(|(name, _)| !ignored.contains(name));
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    matches.truncate(MAX_PATH_CANDIDATES);
+
+    matches
+        .into_iter()
+        .map(|(name, is_dir)| {
+            let suffix = if is_dir { "/" } else { "" };
+            Pair {
+                display: format!("{}{}", name, suffix),
+                replacement: format!("{}{}{}", dir_part, name, suffix),
+            }
+        })
+        .collect()
+}
+
+/// Which of `entries` in `dir` git ignores; empty outside a repository or without git
+fn git_ignored(dir: &Path, entries: &[(String, bool)]) -> HashSet<String> {
+    if entries.is_empty() {
+        return HashSet::new();
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["check-ignore", "--"])
+        .args(entries.iter().map(|(name, is_dir)| {
+            if *is_dir {
+                format!("{}/", name)
+            } else {
+                name.clone()
+            }
+        }))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim_end_matches('/').to_string())
+            .collect(),
+        Err(_) => HashSet::new(),
     }
 }
 
@@ -296,7 +401,7 @@ impl Completer for GooseCompleter {
         &self,
         line: &str,
         pos: usize,
-        ctx: &Context<'_>,
+        _ctx: &Context<'_>,
     ) -> Result<(usize, Vec<Self::Candidate>)> {
         // If the cursor is not at the end of the line, don't try to complete
         if pos < line.len() {
@@ -310,6 +415,12 @@ impl Completer for GooseCompleter {
                 return self.complete_slash_commands(line);
             }
 
+            // Recipe files
+            if line.starts_with("/recipe ") {
+                let (start, word) = last_word(line);
+                return Ok((start, complete_path(word)));
+            }
+
             // Handle /prompt command
             if line.starts_with("/prompt") {
                 // If we're just after "/prompt" with or without a space
@@ -332,6 +443,11 @@ impl Completer for GooseCompleter {
                     }
                 }
 
+                // Argument values are often paths
+                if let Some(completion) = self.complete_argument_value(line) {
+                    return Ok(completion);
+                }
+
                 // If we have a prompt name and need argument completion
                 if parts.len() >= 2 {
                     return self.complete_argument_keys(line);
@@ -376,7 +492,7 @@ impl Completer for GooseCompleter {
         }
 
         // For normal text (not slash commands), try file path completion
-        self.complete_file_path(line, ctx)
+        self.complete_file_path(line)
     }
 }
 
@@ -592,6 +708,87 @@ mod tests {
         assert_eq!(candidates.len(), 0);
     }
 
+    fn create_test_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/session")).unwrap();
+        std::fs::create_dir_all(dir.path().join("scripts")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        std::fs::write(dir.path().join("setup.py"), "").unwrap();
+        std::fs::write(dir.path().join(".env"), "").unwrap();
+        dir
+    }
+
+    fn replacements(candidates: &[Pair]) -> Vec<&str> {
+        candidates.iter().map(|c| c.replacement.as_str()).collect()
+    }
+
+    #[test]
+    fn test_path_candidates() {
+        let dir = create_test_tree();
+
+        // directories first, with a trailing slash, and hidden files left out
+        let candidates = path_candidates("s", dir.path());
+        assert_eq!(
+            replacements(&candidates),
+            vec!["scripts/", "src/", "setup.py"]
+        );
+
+        let candidates = path_candidates("src/", dir.path());
+        assert_eq!(
+            replacements(&candidates),
+            vec!["src/session/", "src/main.rs"]
+        );
+        assert_eq!(candidates[1].display, "main.rs");
+
+        assert_eq!(
+            replacements(&path_candidates(".e", dir.path())),
+            vec![".env"]
+        );
+        assert!(path_candidates("missing/", dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_path_candidates_respect_gitignore() {
+        let dir = create_test_tree();
+        std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        std::fs::write(dir.path().join("server.log"), "").unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+        let git_init = Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(dir.path())
+            .status();
+        if !git_init.is_ok_and(|status| status.success()) {
+            // git isn't available, nothing to check
+            return;
+        }
+
+        let candidates = path_candidates("", dir.path());
+        assert_eq!(
+            replacements(&candidates),
+            vec!["scripts/", "src/", "setup.py"]
+        );
+    }
+
+    #[test]
+    fn test_last_word() {
+        assert_eq!(last_word("look at @src/ma"), (8, "@src/ma"));
+        assert_eq!(last_word("trailing "), (9, ""));
+        assert_eq!(last_word("single"), (0, "single"));
+    }
+
+    #[test]
+    fn test_complete_argument_value() {
+        let completer = GooseCompleter::new(create_test_cache());
+        let (pos, _) = completer
+            .complete_argument_value("/prompt test_prompt1 required_arg=src/")
+            .unwrap();
+        assert_eq!(pos, "/prompt test_prompt1 required_arg=".len());
+        assert!(completer
+            .complete_argument_value("/prompt test_prompt1 required_arg=")
+            .is_none());
+    }
+
     #[test]
     fn test_complete_argument_keys() {
         let cache = create_test_cache();
@@ -0,0 +1,307 @@
+//! Pick the syntax bat highlights fenced code blocks and tool output with.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+#[derive(Debug, PartialEq)]
+pub enum Segment<'a> {
+    /// Prose, including the fence lines around code blocks
+    Markdown(&'a str),
+    /// The body of a fenced code block and the language from its info string
+    Code {
+        language: Option<&'a str>,
+        code: &'a str,
+    },
+}
+
+struct Fence<'a> {
+    marker: char,
+    len: usize,
+    info: &'a str,
+}
+
+/// Recognise a fence line such as "```rust" or "~~~~", allowing up to three spaces of indent
+fn parse_fence(line: &str) -> Option<Fence<'_>> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == marker).count();
+    if len < 3 {
+        return None;
+    }
+    let info = trimmed[len..].trim();
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    Some(Fence { marker, len, info })
+}
+
+/// Split markdown into prose and fenced code blocks; an unclosed block runs to the end, which is
+/// what a partially streamed answer looks like
+pub fn split_fences(content: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut markdown_start = 0;
+    let mut open: Option<(Fence, usize)> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_end = offset + line.len();
+        match (&open, parse_fence(line)) {
+            (None, Some(fence)) => {
+                segments.push(Segment::Markdown(&content[markdown_start..line_end]));
+                open = Some((fence, line_end));
+            }
+            (Some((opening, code_start)), Some(fence))
+                if fence.marker == opening.marker
+                    && fence.len >= opening.len
+                    && fence.info.is_empty() =>
+            {
+                segments.push(Segment::Code {
+                    language: fence_language(opening.info),
+                    code: &content[*code_start..offset],
+                });
+                markdown_start = offset;
+                open = None;
+            }
+            _ => {}
+        }
+        offset = line_end;
+    }
+
+    match open {
+        Some((opening, code_start)) => segments.push(Segment::Code {
+            language: fence_language(opening.info),
+            code: &content[code_start..],
+        }),
+        None if markdown_start < content.len() => {
+            segments.push(Segment::Markdown(&content[markdown_start..]))
+        }
+        None => {}
+    }
+    segments.retain(|segment| !matches!(segment, Segment::Code { code: "", .. }));
+    segments
+}
+
+/// The language named by a fence info string, e.g. "rust" for "rust,ignore" or "{.python}"
+fn fence_language(info: &str) -> Option<&str> {
+    let token = info
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .next()?
+        .trim_matches(|c| c == '{' || c == '}' || c == '.');
+    (!token.is_empty()).then_some(token)
+}
+
+/// Map common fence labels that aren't bat syntax names or extensions onto ones that are
+fn normalize_alias(language: &str) -> String {
+    let language = language.to_lowercase();
+    let alias = match language.as_str() {
+        "shell" | "console" | "shell-session" | "zsh" => "sh",
+        "golang" => "go",
+        "c++" => "cpp",
+        "c#" | "csharp" => "cs",
+        "jsonc" | "json5" => "json",
+        "text" | "plain" | "plaintext" => "txt",
+        _ => return language,
+    };
+    alias.to_string()
+}
+
+/// Lower-cased syntax names and file extensions known to bat, mapped to the syntax name
+fn known_syntaxes() -> &'static HashMap<String, String> {
+    static SYNTAXES: OnceLock<HashMap<String, String>> = OnceLock::new();
+    SYNTAXES.get_or_init(|| {
+        let mut known = HashMap::new();
+        for syntax in bat::PrettyPrinter::new().syntaxes() {
+            for extension in &syntax.file_extensions {
+                known
+                    .entry(extension.to_lowercase())
+                    .or_insert_with(|| syntax.name.clone());
+            }
+            known.insert(syntax.name.to_lowercase(), syntax.name.clone());
+        }
+        known
+    })
+}
+
+/// The bat syntax name for a language label, if bat knows it
+pub fn resolve_syntax(language: &str) -> Option<&'static str> {
+    known_syntaxes()
+        .get(&normalize_alias(language).to_lowercase())
+        .map(String::as_str)
+}
+
+/// Guess the language of unlabelled code from a few unambiguous markers
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    let trimmed = code.trim();
+    let first_line = trimmed.lines().next()?;
+
+    if let Some(shebang) = first_line.strip_prefix("#!") {
+        return if shebang.contains("python") {
+            Some("py")
+        } else if shebang.contains("node") {
+            Some("js")
+        } else if shebang.contains("sh") {
+            Some("sh")
+        } else {
+            None
+        };
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+    if trimmed.starts_with("<?xml") {
+        return Some("xml");
+    }
+    let lower_first = first_line.to_lowercase();
+    if lower_first.starts_with("<!doctype html") || lower_first.starts_with("<html") {
+        return Some("html");
+    }
+    if first_line.starts_with("diff --git")
+        || (first_line.starts_with("--- ")
+            && trimmed.contains("\n+++ ")
+            && trimmed.contains("\n@@"))
+    {
+        return Some("diff");
+    }
+    if first_line.starts_with("package ") && trimmed.contains("\nfunc ") {
+        return Some("go");
+    }
+
+    let starts_any = |prefixes: &[&str]| {
+        trimmed.lines().any(|line| {
+            let line = line.trim_start();
+            prefixes.iter().any(|prefix| line.starts_with(prefix))
+        })
+    };
+    if starts_any(&[
+        "fn ",
+        "pub fn ",
+        "impl ",
+        "use std::",
+        "pub struct ",
+        "#[derive(",
+    ]) {
+        return Some("rs");
+    }
+    if (starts_any(&["def ", "async def "]) && trimmed.contains("):"))
+        || (starts_any(&["from "]) && trimmed.contains(" import "))
+    {
+        return Some("py");
+    }
+    if starts_any(&["#include <", "#include \""]) {
+        return Some("cpp");
+    }
+    if starts_any(&["const ", "let ", "function ", "export "]) && trimmed.contains(';') {
+        return Some("js");
+    }
+    let upper_first = first_line.to_uppercase();
+    if [
+        "SELECT ",
+        "INSERT INTO ",
+        "UPDATE ",
+        "DELETE FROM ",
+        "CREATE TABLE ",
+    ]
+    .iter()
+    .any(|keyword| upper_first.starts_with(keyword))
+    {
+        return Some("sql");
+    }
+    None
+}
+
+/// The bat syntax for a code block: its declared language if bat knows it, otherwise a guess
+pub fn code_syntax(language: Option<&str>, code: &str) -> Option<&'static str> {
+    language
+        .and_then(resolve_syntax)
+        .or_else(|| detect_language(code).and_then(resolve_syntax))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_fences() {
+        let content = "Here:\n```rust\nfn main() {}\n```\nand\n~~~\nplain\n~~~\n";
+        assert_eq!(
+            split_fences(content),
+            vec![
+                Segment::Markdown("Here:\n```rust\n"),
+                Segment::Code {
+                    language: Some("rust"),
+                    code: "fn main() {}\n"
+                },
+                Segment::Markdown("```\nand\n~~~\n"),
+                Segment::Code {
+                    language: None,
+                    code: "plain\n"
+                },
+                Segment::Markdown("~~~\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_fences_edge_cases() {
+        assert_eq!(
+            split_fences("no code here"),
+            vec![Segment::Markdown("no code here")]
+        );
+
+        // A shorter or different fence doesn't close the block, and an unclosed block runs to
+        // the end of the text
+        assert_eq!(
+            split_fences("````md\n```\nnested\n~~~\n"),
+            vec![
+                Segment::Markdown("````md\n"),
+                Segment::Code {
+                    language: Some("md"),
+                    code: "```\nnested\n~~~\n"
+                },
+            ]
+        );
+
+        assert_eq!(fence_language("rust,ignore"), Some("rust"));
+        assert_eq!(fence_language("{.python}"), Some("python"));
+        assert_eq!(fence_language(""), None);
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("#!/usr/bin/env python3\nprint(1)"),
+            Some("py")
+        );
+        assert_eq!(detect_language("{\"a\": [1, 2]}"), Some("json"));
+        assert_eq!(
+            detect_language("use std::fmt;\n\nfn main() {\n}\n"),
+            Some("rs")
+        );
+        assert_eq!(
+            detect_language("def add(a, b):\n    return a + b"),
+            Some("py")
+        );
+        assert_eq!(
+            detect_language("select * from users where id = 1"),
+            Some("sql")
+        );
+        assert_eq!(detect_language("Just some text."), None);
+        assert_eq!(detect_language("{not json"), None);
+    }
+
+    #[test]
+    fn test_resolve_syntax() {
+        assert_eq!(resolve_syntax("rust"), Some("Rust"));
+        assert_eq!(resolve_syntax("py"), Some("Python"));
+        assert_eq!(resolve_syntax("JSONC"), resolve_syntax("json"));
+        assert_eq!(resolve_syntax("not-a-language"), None);
+        assert_eq!(code_syntax(None, "{\"a\": 1}"), Some("JSON"));
+        assert_eq!(code_syntax(Some("nonsense"), "fn main() {}"), Some("Rust"));
+    }
+}
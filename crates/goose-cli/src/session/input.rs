@@ -1,5 +1,7 @@
 use super::completion::GooseCompleter;
 use anyhow::Result;
+use goose::agents::model_selector::task_router::TaskCategory;
+use goose::session::extension_data::FeedbackRating;
 use rustyline::Editor;
 use shlex;
 use std::collections::HashMap;
@@ -12,8 +14,10 @@ pub enum InputResult {
     AddBuiltin(String),
     ToggleTheme,
     SelectTheme(String),
+    ThemePicker,
     Retry,
     ListPrompts(Option<String>),
+    PickPrompt(Option<String>),
     PromptCommand(PromptCommandOptions),
     GooseMode(String),
     Plan(PlanCommandOptions),
@@ -21,6 +25,13 @@ pub enum InputResult {
     Clear,
     Recipe(Option<String>),
     Summarize,
+    ContextBreakdown,
+    ShowSystemPrompt,
+    Feedback(FeedbackRating, Option<String>),
+    RouteOverride(TaskCategory),
+    ShowLogs(String),
+    /// Change the log levels, or show them when empty
+    LogLevel(String),
 }
 
 #[derive(Debug)]
@@ -54,8 +65,10 @@ impl rustyline::ConditionalEventHandler for CtrlCHandler {
     }
 }
 
+/// Reads the next input, with the line pre-filled with `initial` if given.
 pub fn get_input(
     editor: &mut Editor<GooseCompleter, rustyline::history::DefaultHistory>,
+    initial: Option<&str>,
 ) -> Result<InputResult> {
     // Ensure Ctrl-J binding is set for newlines
     editor.bind_sequence(
@@ -70,7 +83,11 @@ pub fn get_input(
 
     let prompt = get_input_prompt_string();
 
-    let input = match editor.readline(&prompt) {
+    let read = match initial {
+        Some(initial) => editor.readline_with_initial(&prompt, (initial, "")),
+        None => editor.readline(&prompt),
+    };
+    let input = match read {
         Ok(text) => text,
         Err(e) => match e {
             rustyline::error::ReadlineError::Interrupted => return Ok(InputResult::Exit),
@@ -121,6 +138,13 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_CLEAR: &str = "/clear";
     const CMD_RECIPE: &str = "/recipe";
     const CMD_SUMMARIZE: &str = "/summarize";
+    const CMD_CONTEXT: &str = "/context";
+    const CMD_SYSTEM: &str = "/system";
+    const CMD_GOOD: &str = "/good";
+    const CMD_BAD: &str = "/bad";
+    const CMD_ROUTE: &str = "/route ";
+    const CMD_LOGS: &str = "/logs";
+    const CMD_LOG_LEVEL: &str = "/log level";
 
     match input {
         "/exit" | "/quit" => Some(InputResult::Exit),
@@ -129,6 +153,7 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
             Some(InputResult::Retry)
         }
         "/t" => Some(InputResult::ToggleTheme),
+        "/theme" => Some(InputResult::ThemePicker),
         s if s.starts_with("/t ") => {
             let t = s
                 .strip_prefix("/t ")
@@ -181,6 +206,34 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
         s if s == CMD_CLEAR => Some(InputResult::Clear),
         s if s.starts_with(CMD_RECIPE) => parse_recipe_command(s),
         s if s == CMD_SUMMARIZE => Some(InputResult::Summarize),
+        s if s == CMD_CONTEXT => Some(InputResult::ContextBreakdown),
+        s if s == CMD_SYSTEM => Some(InputResult::ShowSystemPrompt),
+        s if s == CMD_GOOD => Some(InputResult::Feedback(FeedbackRating::Good, None)),
+        s if s == CMD_BAD || s.starts_with("/bad ") => {
+            let reason = s[CMD_BAD.len()..].trim();
+            Some(InputResult::Feedback(
+                FeedbackRating::Bad,
+                (!reason.is_empty()).then(|| reason.to_string()),
+            ))
+        }
+        s if s == CMD_LOGS || s.starts_with("/logs ") => Some(InputResult::ShowLogs(
+            s[CMD_LOGS.len()..].trim().to_string(),
+        )),
+        s if s == "/log" || s == CMD_LOG_LEVEL || s.starts_with("/log level ") => {
+            Some(InputResult::LogLevel(
+                s.get(CMD_LOG_LEVEL.len()..)
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+            ))
+        }
+        s if s.starts_with(CMD_ROUTE) => match s[CMD_ROUTE.len()..].parse::<TaskCategory>() {
+            Ok(category) => Some(InputResult::RouteOverride(category)),
+            Err(e) => {
+                println!("{}", e);
+                Some(InputResult::Retry)
+            }
+        },
         _ => None,
     }
 }
@@ -213,6 +266,14 @@ fn parse_recipe_command(s: &str) -> Option<InputResult> {
 fn parse_prompts_command(args: &str) -> Option<InputResult> {
     let parts: Vec<String> = shlex::split(args).unwrap_or_default();
 
+    // Look for --pick flag, followed by an optional query
+    if let Some(pick) = parts.iter().position(|part| part == "--pick") {
+        let query = parts[pick + 1..].join(" ");
+        return Some(InputResult::PickPrompt(
+            (!query.is_empty()).then_some(query),
+        ));
+    }
+
     // Look for --extension flag
     for i in 0..parts.len() {
         if parts[i] == "--extension" && i + 1 < parts.len() {
@@ -292,9 +353,11 @@ fn print_help() {
 /exit or /quit - Exit the session
 /t - Toggle Light/Dark/Ansi theme
 /t <name> - Set theme directly (light, dark, ansi)
+/theme - Preview the available themes and pick one
 /extension <command> - Add a stdio extension (format: ENV1=val1 command args...)
 /builtin <names> - Add builtin extensions by name (comma-separated)
 /prompts [--extension <name>] - List all available prompts, optionally filtered by extension
+/prompts --pick [query] - Pick a prompt interactively and fill in the command for it
 /prompt <n> [--info] [key=value...] - Get prompt info or execute a prompt
 /mode <name> - Set the goose mode to use ('auto', 'approve', 'chat', 'smart_approve')
 /plan <message_text> -  Enters 'plan' mode with optional message. Create a plan based on the current messages and asks user if they want to act on it.
@@ -306,6 +369,13 @@ fn print_help() {
 /recipe [filepath] - Generate a recipe from the current conversation and save it to the specified filepath (must end with .yaml).
                        If no filepath is provided, it will be saved to ./recipe.yaml.
 /summarize - Summarize the current conversation to reduce context length while preserving key information.
+/context - Show what is using the context window (system prompt, tools, conversation, tool results)
+/system - Show the system prompt as it is sent to the model, prompt sections included
+/good - Mark the previous response as helpful
+/bad [reason] - Mark the previous response as unhelpful, optionally saying why
+/route <category> - Send the next message to the model routed for a category (code_edit, question, planning, data_transform)
+/logs [info|warn|error] [extension] - Show the log messages extensions sent this session
+/log level [module=level,...] - Change goose's own log levels (e.g. mcp_client=debug), or show them
 /? or /help - Display this help message
 /clear - Clears the current chat history
 
@@ -348,6 +418,71 @@ mod tests {
             Some(InputResult::ToggleTheme)
         ));
 
+        // Test theme picker
+        assert!(matches!(
+            handle_slash_command("/theme"),
+            Some(InputResult::ThemePicker)
+        ));
+
+        // Test context breakdown
+        assert!(matches!(
+            handle_slash_command("/context"),
+            Some(InputResult::ContextBreakdown)
+        ));
+
+        // Test system prompt dump
+        assert!(matches!(
+            handle_slash_command("/system"),
+            Some(InputResult::ShowSystemPrompt)
+        ));
+
+        // Test feedback commands
+        assert!(matches!(
+            handle_slash_command("/good"),
+            Some(InputResult::Feedback(FeedbackRating::Good, None))
+        ));
+        assert!(matches!(
+            handle_slash_command("/bad"),
+            Some(InputResult::Feedback(FeedbackRating::Bad, None))
+        ));
+        if let Some(InputResult::Feedback(rating, reason)) =
+            handle_slash_command("/bad  edited the wrong file ")
+        {
+            assert_eq!(rating, FeedbackRating::Bad);
+            assert_eq!(reason.as_deref(), Some("edited the wrong file"));
+        } else {
+            panic!("Expected Feedback");
+        }
+
+        assert!(matches!(
+            handle_slash_command("/route planning"),
+            Some(InputResult::RouteOverride(TaskCategory::Planning))
+        ));
+        assert!(matches!(
+            handle_slash_command("/route nonsense"),
+            Some(InputResult::Retry)
+        ));
+        assert!(handle_slash_command("/badge").is_none());
+
+        if let Some(InputResult::ShowLogs(args)) = handle_slash_command("/logs warn developer") {
+            assert_eq!(args, "warn developer");
+        } else {
+            panic!("Expected ShowLogs");
+        }
+        assert!(matches!(
+            handle_slash_command("/logs"),
+            Some(InputResult::ShowLogs(args)) if args.is_empty()
+        ));
+        assert!(handle_slash_command("/logsx").is_none());
+        assert!(matches!(
+            handle_slash_command("/log level mcp_client=debug"),
+            Some(InputResult::LogLevel(filter)) if filter == "mcp_client=debug"
+        ));
+        assert!(matches!(
+            handle_slash_command("/log"),
+            Some(InputResult::LogLevel(filter)) if filter.is_empty()
+        ));
+
         // Test extension command
         if let Some(InputResult::AddExtension(cmd)) = handle_slash_command("/extension foo bar") {
             assert_eq!(cmd, "foo bar");
@@ -383,6 +518,19 @@ mod tests {
         } else {
             panic!("Expected ListPrompts with extension");
         }
+
+        // Test the picker, with and without a query
+        assert!(matches!(
+            handle_slash_command("/prompts --pick"),
+            Some(InputResult::PickPrompt(None))
+        ));
+        if let Some(InputResult::PickPrompt(query)) =
+            handle_slash_command("/prompts --pick gh issue")
+        {
+            assert_eq!(query, Some("gh issue".to_string()));
+        } else {
+            panic!("Expected PickPrompt with query");
+        }
     }
 
     #[test]
@@ -0,0 +1,262 @@
+//! Log notifications from MCP extensions, kept for the whole session.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Local};
+use console::style;
+use goose::config::Config;
+use rmcp::model::LoggingLevel;
+
+pub const MCP_LOG_MODE_KEY: &str = "GOOSE_CLI_MCP_LOGS";
+
+/// Entries kept for `/logs`; older ones are still in the session log file
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpLogMode {
+    /// Show the latest notification in a spinner, as before
+    Spinner,
+    /// Collect notifications into the log panel
+    Panel,
+}
+
+pub fn mcp_log_mode() -> McpLogMode {
+    let value = std::env::var(MCP_LOG_MODE_KEY)
+        .ok()
+        .or_else(|| Config::global().get_param::<String>(MCP_LOG_MODE_KEY).ok());
+    match value.as_deref().map(str::to_lowercase).as_deref() {
+        Some("panel") => McpLogMode::Panel,
+        _ => McpLogMode::Spinner,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Fold the eight syslog-style MCP levels into three
+    pub fn from_mcp(level: LoggingLevel) -> Self {
+        match level {
+            LoggingLevel::Debug | LoggingLevel::Info | LoggingLevel::Notice => LogLevel::Info,
+            LoggingLevel::Warning => LogLevel::Warn,
+            LoggingLevel::Error
+            | LoggingLevel::Critical
+            | LoggingLevel::Alert
+            | LoggingLevel::Emergency => LogLevel::Error,
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "info" => Some(LogLevel::Info),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    fn styled_label(self) -> String {
+        let label = format!("{:<5}", self.label().to_uppercase());
+        match self {
+            LogLevel::Info => style(label).dim().to_string(),
+            LogLevel::Warn => style(label).yellow().to_string(),
+            LogLevel::Error => style(label).red().bold().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Local>,
+    pub extension: String,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn render(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            style(self.timestamp.format("%H:%M:%S")).dim(),
+            self.level.styled_label(),
+            style(format!("[{}]", self.extension)).cyan(),
+            self.message
+        )
+    }
+}
+
+/// Which entries `/logs` shows: `/logs [info|warn|error] [extension]`
+#[derive(Debug, Default, PartialEq)]
+pub struct LogFilter {
+    pub min_level: Option<LogLevel>,
+    pub extension: Option<String>,
+}
+
+impl LogFilter {
+    pub fn parse(args: &str) -> Self {
+        let mut filter = Self::default();
+        for arg in args.split_whitespace() {
+            match LogLevel::parse(arg) {
+                Some(level) => filter.min_level = Some(level),
+                None => filter.extension = Some(arg.to_string()),
+            }
+        }
+        filter
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        self.min_level.is_none_or(|level| entry.level >= level)
+            && self
+                .extension
+                .as_ref()
+                .is_none_or(|extension| &entry.extension == extension)
+    }
+}
+
+#[derive(Default)]
+pub struct McpLogPanel {
+    entries: VecDeque<LogEntry>,
+    /// Entries recorded over the session, including ones dropped from `entries`
+    recorded: usize,
+    turn_start: usize,
+}
+
+impl McpLogPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep a notification and write it to the session log
+    pub fn record(&mut self, extension: &str, level: LogLevel, message: &str) -> &LogEntry {
+        match level {
+            LogLevel::Info => tracing::info!(extension, "MCP log: {}", message),
+            LogLevel::Warn => tracing::warn!(extension, "MCP log: {}", message),
+            LogLevel::Error => tracing::error!(extension, "MCP log: {}", message),
+        }
+
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            timestamp: Local::now(),
+            extension: extension.to_string(),
+            level,
+            message: message.to_string(),
+        });
+        self.recorded += 1;
+        self.entries.back().expect("just pushed")
+    }
+
+    pub fn begin_turn(&mut self) {
+        self.turn_start = self.recorded;
+    }
+
+    fn turn_entries(&self) -> impl Iterator<Item = &LogEntry> {
+        let in_turn = (self.recorded - self.turn_start).min(self.entries.len());
+        self.entries.iter().skip(self.entries.len() - in_turn)
+    }
+
+    /// The collapsed panel for the turn: counts per extension and level, or `None` if no
+    /// extension logged anything
+    pub fn turn_summary(&self) -> Option<String> {
+        let mut counts: BTreeMap<&str, [usize; 3]> = BTreeMap::new();
+        for entry in self.turn_entries() {
+            counts.entry(&entry.extension).or_default()[entry.level as usize] += 1;
+        }
+        if counts.is_empty() {
+            return None;
+        }
+
+        let extensions: Vec<String> = counts
+            .iter()
+            .map(|(extension, levels)| {
+                let levels: Vec<String> = [LogLevel::Info, LogLevel::Warn, LogLevel::Error]
+                    .iter()
+                    .filter(|level| levels[**level as usize] > 0)
+                    .map(|level| format!("{} {}", levels[*level as usize], level.label()))
+                    .collect();
+                format!("{} {}", extension, levels.join(", "))
+            })
+            .collect();
+        Some(format!(
+            "▸ MCP logs: {} (/logs to expand)",
+            extensions.join(" · ")
+        ))
+    }
+
+    pub fn filtered(&self, filter: &LogFilter) -> Vec<&LogEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter_parse() {
+        assert_eq!(LogFilter::parse(""), LogFilter::default());
+        assert_eq!(
+            LogFilter::parse("warn developer"),
+            LogFilter {
+                min_level: Some(LogLevel::Warn),
+                extension: Some("developer".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_panel_collects_per_turn() {
+        let mut panel = McpLogPanel::new();
+        panel.record("developer", LogLevel::Info, "indexing");
+        panel.begin_turn();
+        assert_eq!(panel.turn_summary(), None);
+
+        panel.record("developer", LogLevel::Info, "reading files");
+        panel.record("developer", LogLevel::Warn, "slow disk");
+        panel.record("github", LogLevel::Error, "rate limited");
+        assert_eq!(
+            panel.turn_summary().unwrap(),
+            "▸ MCP logs: developer 1 info, 1 warn · github 1 error (/logs to expand)"
+        );
+
+        assert_eq!(panel.filtered(&LogFilter::default()).len(), 4);
+        let warnings = panel.filtered(&LogFilter::parse("warn"));
+        assert_eq!(warnings.len(), 2);
+        let developer = panel.filtered(&LogFilter::parse("developer"));
+        assert_eq!(developer.len(), 3);
+    }
+
+    #[test]
+    fn test_panel_drops_oldest_entries() {
+        let mut panel = McpLogPanel::new();
+        for i in 0..MAX_ENTRIES + 5 {
+            panel.record("developer", LogLevel::Info, &i.to_string());
+        }
+        let entries = panel.filtered(&LogFilter::default());
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].message, "5");
+
+        panel.begin_turn();
+        panel.record("developer", LogLevel::Error, "boom");
+        assert_eq!(
+            panel.turn_summary().unwrap(),
+            "▸ MCP logs: developer 1 error (/logs to expand)"
+        );
+    }
+}
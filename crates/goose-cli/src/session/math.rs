@@ -0,0 +1,523 @@
+//! Approximate LaTeX math with Unicode so formulas are readable in a terminal.
+
+use goose::config::Config;
+
+pub fn math_rendering_enabled() -> bool {
+    std::env::var("GOOSE_CLI_RENDER_MATH")
+        .ok()
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .or_else(|| {
+            Config::global()
+                .get_param::<bool>("GOOSE_CLI_RENDER_MATH")
+                .ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Replace the math in a markdown document with Unicode approximations
+pub fn render_math(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut prose = String::new();
+    // Some(is_math) while inside a fence
+    let mut fence: Option<bool> = None;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            match fence {
+                None => {
+                    out.push_str(&render_inline_math(&std::mem::take(&mut prose)));
+                    let is_math = matches!(info.trim(), "math" | "latex" | "tex");
+                    if !is_math {
+                        out.push_str(line);
+                    }
+                    fence = Some(is_math);
+                }
+                Some(is_math) => {
+                    if !is_math {
+                        out.push_str(line);
+                    }
+                    fence = None;
+                }
+            }
+            continue;
+        }
+        match fence {
+            Some(true) => {
+                out.push_str(&convert_latex(line.trim_end_matches('\n')));
+                if line.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            Some(false) => out.push_str(line),
+            None => prose.push_str(line),
+        }
+    }
+    out.push_str(&render_inline_math(&prose));
+    out
+}
+
+/// Convert math delimited inside prose, skipping inline code spans
+fn render_inline_math(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            let end = rest[1..].find('`').map(|i| i + 2).unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if let Some((math, consumed)) = delimited_math(rest) {
+            out.push_str(&convert_latex(math));
+            rest = &rest[consumed..];
+            continue;
+        }
+        if rest.starts_with("\\$") {
+            out.push('$');
+            rest = &rest[2..];
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// If `text` starts with delimited math, return the math and how many bytes the whole span took
+fn delimited_math(text: &str) -> Option<(&str, usize)> {
+    for (open, close) in [("$$", "$$"), ("\\[", "\\]"), ("\\(", "\\)")] {
+        if let Some(body) = text.strip_prefix(open) {
+            let end = body.find(close)?;
+            return Some((&body[..end], open.len() + end + close.len()));
+        }
+    }
+
+    // Inline $...$ follows pandoc's rules so prices like "$5 and $10" are left alone: the opening
+    // $ must be followed by a non-space, the closing one preceded by a non-space and not followed
+    // by a digit, and both must be on the same line
+    let body = text.strip_prefix('$')?;
+    if body.starts_with(char::is_whitespace) || body.is_empty() {
+        return None;
+    }
+    let line_end = body.find('\n').unwrap_or(body.len());
+    let mut search = 0;
+    while let Some(i) = body[search..line_end].find('$') {
+        let end = search + i;
+        let before = body[..end].chars().next_back();
+        let after = body[end + 1..].chars().next();
+        let escaped = before == Some('\\');
+        if end > 0
+            && !escaped
+            && !before.is_some_and(char::is_whitespace)
+            && !after.is_some_and(|c| c.is_ascii_digit())
+        {
+            return Some((&body[..end], end + 2));
+        }
+        search = end + 1;
+    }
+    None
+}
+
+/// Convert a LaTeX math expression to a Unicode approximation
+pub fn convert_latex(tex: &str) -> String {
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        pos: 0,
+    };
+    parser.parse_until(None)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse_until(&mut self, end: Option<char>) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if Some(c) == end {
+                self.pos += 1;
+                break;
+            }
+            self.pos += 1;
+            match c {
+                '\\' => out.push_str(&self.parse_command()),
+                '{' => out.push_str(&self.parse_until(Some('}'))),
+                '^' => {
+                    let arg = self.parse_arg();
+                    out.push_str(&script(&arg, superscript_char, '^'));
+                }
+                '_' => {
+                    let arg = self.parse_arg();
+                    out.push_str(&script(&arg, subscript_char, '_'));
+                }
+                '~' => out.push(' '),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// A single argument: a braced group, a command, or one character
+    fn parse_arg(&mut self) -> String {
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.parse_until(Some('}'))
+            }
+            Some('\\') => {
+                self.pos += 1;
+                self.parse_command()
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Parse the command after a backslash
+    fn parse_command(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // Control symbols like \{ \, \\
+            let Some(c) = self.peek() else {
+                return String::new();
+            };
+            self.pos += 1;
+            return match c {
+                ',' | ';' | ':' | ' ' => " ".to_string(),
+                '!' => String::new(),
+                '\\' => "\n".to_string(),
+                c => c.to_string(),
+            };
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.parse_arg();
+                let denominator = self.parse_arg();
+                fraction(&numerator, &denominator)
+            }
+            "sqrt" => {
+                let root = if self.peek() == Some('[') {
+                    self.pos += 1;
+                    Some(self.parse_until(Some(']')))
+                } else {
+                    None
+                };
+                let radicand = wrap(&self.parse_arg());
+                match root.as_deref() {
+                    Some("3") => format!("∛{}", radicand),
+                    Some("4") => format!("∜{}", radicand),
+                    Some(n) => format!("{}√{}", script(n, superscript_char, '^'), radicand),
+                    None => format!("√{}", radicand),
+                }
+            }
+            "text" | "mathrm" | "mathbf" | "mathit" | "mathsf" | "mathtt" | "operatorname"
+            | "mbox" | "textrm" | "textbf" | "boldsymbol" | "displaystyle" => self.parse_arg(),
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" => String::new(),
+            "quad" | "qquad" => " ".to_string(),
+            _ => match symbol(&name) {
+                Some(symbol) => symbol.to_string(),
+                // Function names like \sin and \log read fine as plain words
+                None => name,
+            },
+        }
+    }
+}
+
+fn needs_parens(s: &str) -> bool {
+    s.chars().count() > 1 && s.contains([' ', '+', '-', '−', '*', '/', '·', '×', '='])
+}
+
+fn wrap(s: &str) -> String {
+    if needs_parens(s) {
+        format!("({})", s)
+    } else {
+        s.to_string()
+    }
+}
+
+fn fraction(numerator: &str, denominator: &str) -> String {
+    let vulgar = match (numerator.trim(), denominator.trim()) {
+        ("1", "2") => Some("½"),
+        ("1", "3") => Some("⅓"),
+        ("2", "3") => Some("⅔"),
+        ("1", "4") => Some("¼"),
+        ("3", "4") => Some("¾"),
+        ("1", "5") => Some("⅕"),
+        ("1", "6") => Some("⅙"),
+        ("1", "8") => Some("⅛"),
+        _ => None,
+    };
+    match vulgar {
+        Some(v) => v.to_string(),
+        None => format!("{}/{}", wrap(numerator), wrap(denominator)),
+    }
+}
+
+/// Render a super- or subscript with Unicode script characters, falling back to `^(...)`
+fn script(arg: &str, map: fn(char) -> Option<char>, marker: char) -> String {
+    if let Some(mapped) = arg.chars().map(map).collect::<Option<String>>() {
+        return mapped;
+    }
+    if arg.chars().count() == 1 {
+        format!("{}{}", marker, arg)
+    } else {
+        format!("{}({})", marker, arg)
+    }
+}
+
+fn superscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'T' => 'ᵀ',
+        '′' => '′',
+        _ => return None,
+    })
+}
+
+fn subscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "infty" => "∞",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "circ" => "∘",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "degree" => "°",
+        "prime" => "′",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "mid" => "|",
+        "parallel" => "∥",
+        "perp" => "⊥",
+        "angle" => "∠",
+        "therefore" => "∴",
+        "because" => "∵",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_latex() {
+        assert_eq!(convert_latex(r"x^2 + y^2 = z^2"), "x² + y² = z²");
+        assert_eq!(convert_latex(r"\alpha \leq \beta"), "α ≤ β");
+        assert_eq!(convert_latex(r"\frac{1}{2}"), "½");
+        assert_eq!(convert_latex(r"\frac{a+b}{c}"), "(a+b)/c");
+        assert_eq!(convert_latex(r"\sqrt{x+1}"), "√(x+1)");
+        assert_eq!(convert_latex(r"a_{ij} x_n"), "aᵢⱼ xₙ");
+        assert_eq!(convert_latex(r"e^{i\pi}"), "e^(iπ)");
+        assert_eq!(convert_latex(r"\sum_{i=1}^{n} i"), "∑ᵢ₌₁ⁿ i");
+        assert_eq!(convert_latex(r"\sin\theta"), "sinθ");
+        assert_eq!(convert_latex(r"\text{if } x > 0"), "if  x > 0");
+    }
+
+    #[test]
+    fn test_render_math_in_markdown() {
+        assert_eq!(
+            render_math("Euler: $e^{i\\pi} + 1 = 0$ holds."),
+            "Euler: e^(iπ) + 1 = 0 holds."
+        );
+        assert_eq!(render_math("$$\\frac{1}{4}$$\n"), "¼\n");
+        assert_eq!(render_math("\\(\\alpha\\) and \\[\\beta\\]"), "α and β");
+    }
+
+    #[test]
+    fn test_leaves_prices_and_code_alone() {
+        let prices = "It costs $5 and $10 total";
+        assert_eq!(render_math(prices), prices);
+        let code = "Use `$x^2$` or\n```bash\necho $HOME$x^2$\n```\n";
+        assert_eq!(render_math(code), code);
+        assert_eq!(render_math("a \\$ sign"), "a $ sign");
+    }
+
+    #[test]
+    fn test_math_fence() {
+        assert_eq!(
+            render_math("before\n```math\nx^2 \\geq 0\n```\nafter\n"),
+            "before\nx² ≥ 0\nafter\n"
+        );
+    }
+}
@@ -0,0 +1,496 @@
+//! `@` mentions in user messages, which attach a file or the definition of a symbol.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use goose::session::blob_store::BlobStore;
+use regex::Regex;
+
+const MAX_MENTIONS: usize = 10;
+const MAX_FILE_BYTES: usize = 256 * 1024;
+const MAX_SYMBOL_LINES: usize = 120;
+const MAX_SYMBOL_MATCHES: usize = 3;
+const MAX_SEARCH_FILES: usize = 5000;
+
+#[derive(Debug, PartialEq)]
+pub enum Mention {
+    File(PathBuf),
+    Symbol(String),
+    /// A path to a file outside the working directory, which isn't attached
+    Outside(String),
+}
+
+/// A mention that was resolved and attached, for telling the user what was added
+#[derive(Debug, PartialEq)]
+pub struct Attachment {
+    pub mention: String,
+    pub summary: String,
+}
+
+/// The `@` tokens in `text`, skipping emails, inline code and fenced code blocks
+fn mention_tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut previous = ' ';
+        for (index, c) in line.char_indices() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '@' && !in_code && (previous.is_whitespace() || previous == '(') {
+                let rest = &line[index + 1..];
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let token = rest[..end]
+                    .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\'']);
+                if !token.is_empty() {
+                    tokens.push(token);
+                }
+            }
+            previous = c;
+        }
+    }
+    tokens
+}
+
+fn is_identifier(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The file `token` names, as a path inside `root`, or `Err` for a file outside of it
+fn resolve_path(token: &str, root: &Path) -> Option<Result<PathBuf, ()>> {
+    let relative = Path::new(token);
+    let escapes = token.starts_with('~')
+        || relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    let path = if escapes {
+        match token.strip_prefix("~/") {
+            Some(rest) => etcetera::home_dir().ok()?.join(rest),
+            None => root.join(relative),
+        }
+    } else {
+        root.join(relative)
+    };
+    if !path.is_file() {
+        return None;
+    }
+    let inside = !escapes
+        && fs::canonicalize(&path)
+            .ok()
+            .zip(fs::canonicalize(root).ok())
+            .is_some_and(|(path, root)| path.starts_with(root));
+    Some(if inside { Ok(path) } else { Err(()) })
+}
+
+/// Work out what each `@` token in `text` refers to: an existing file, or otherwise a symbol name
+/// (`Config::load` mentions `load`). Anything else, like `@someone`, is left alone by the caller
+/// if no definition is found.
+pub fn parse_mentions(text: &str, root: &Path) -> Vec<Mention> {
+    let mut seen = HashSet::new();
+    let mut mentions = Vec::new();
+
+    for token in mention_tokens(text) {
+        if !seen.insert(token) {
+            continue;
+        }
+        if let Some(path) = resolve_path(token, root) {
+            mentions.push(match path {
+                Ok(path) => Mention::File(path),
+                Err(()) => Mention::Outside(token.to_string()),
+            });
+        } else if let Some(symbol) = token.rsplit("::").next().filter(|s| is_identifier(s)) {
+            mentions.push(Mention::Symbol(symbol.to_string()));
+        }
+        if mentions.len() == MAX_MENTIONS {
+            break;
+        }
+    }
+    mentions
+}
+
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// The text of `path`, truncated when it is large. The whole of a truncated file is kept in the
+/// session's blob store, when there is one, so the model can still read all of it.
+fn read_text(path: &Path, blobs: Option<(&BlobStore, &str)>) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return None;
+    }
+    if bytes.len() <= MAX_FILE_BYTES {
+        return Some(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    let mut text = String::from_utf8_lossy(&bytes[..MAX_FILE_BYTES]).into_owned();
+    if text.ends_with('\u{FFFD}') {
+        text.pop();
+    }
+    let full_copy = blobs.and_then(|(store, session_id)| store.put(session_id, &bytes).ok());
+    match full_copy {
+        Some(copy) => text.push_str(&format!(
+            "\n[... truncated, {} of {} bytes shown; the full file is stored at {}]",
+            MAX_FILE_BYTES,
+            bytes.len(),
+            copy.display()
+        )),
+        None => text.push_str(&format!(
+            "\n[... truncated, {} of {} bytes shown]",
+            MAX_FILE_BYTES,
+            bytes.len()
+        )),
+    }
+    Some(text)
+}
+
+fn definition_regex(symbol: &str) -> Regex {
+    Regex::new(&format!(
+        r"^\s*(?:(?:pub(?:\([^)]*\))?|export|default|async|unsafe|static|abstract|public|private|protected|final|extern)\s+)*(?:fn|struct|enum|trait|type|mod|const|static|union|macro_rules!|class|def|func|function|interface|object|record)\s+{}\b",
+        regex::escape(symbol)
+    ))
+    .expect("escaped symbol makes a valid regex")
+}
+
+/// Candidate files containing `symbol`, from `git grep` so ignored files are skipped, or from a
+/// walk of the directory when it isn't a repository
+fn candidate_files(symbol: &str, root: &Path) -> Vec<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["grep", "--untracked", "-l", "-I", "-w", "-F", "-e", symbol])
+        .output();
+    match output {
+        // git grep exits with 1 when nothing matches, which is still an answer
+        Ok(output) if output.status.code().is_some_and(|code| code <= 1) => {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| root.join(line))
+                .collect()
+        }
+        _ => {
+            let mut files = Vec::new();
+            walk_files(root, &mut files);
+            files
+        }
+    }
+}
+
+fn walk_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if files.len() >= MAX_SEARCH_FILES {
+            return;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name == "target" || name == "node_modules" {
+            continue;
+        }
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => walk_files(&path, files),
+            Ok(file_type) if file_type.is_file() => files.push(path),
+            _ => {}
+        }
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// The lines of the definition starting at `start`, with the comments and attributes above it.
+/// Brace-delimited definitions end where their braces balance, others (Python) where the
+/// indentation drops back.
+fn definition_range(lines: &[&str], start: usize) -> (usize, usize) {
+    let mut first = start;
+    while first > 0 {
+        let above = lines[first - 1].trim_start();
+        if above.starts_with("//")
+            || above.starts_with("#[")
+            || above.starts_with('@')
+            || above.starts_with("/*")
+            || above.starts_with("* ")
+            || above.starts_with("*/")
+        {
+            first -= 1;
+        } else {
+            break;
+        }
+    }
+
+    let limit = (start + MAX_SYMBOL_LINES).min(lines.len());
+    let mut end = limit;
+    if lines[start].trim_end().ends_with(':') {
+        let indent = indent_of(lines[start]);
+        end = (start + 1..limit)
+            .find(|&index| !lines[index].trim().is_empty() && indent_of(lines[index]) <= indent)
+            .unwrap_or(limit);
+    } else {
+        let mut depth = 0i32;
+        let mut opened = false;
+        for (index, line) in lines.iter().enumerate().take(limit).skip(start) {
+            for c in line.chars() {
+                match c {
+                    '{' => {
+                        depth += 1;
+                        opened = true;
+                    }
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+            // Either the braces balanced or it's a one-line definition such as `type Id = u64;`
+            if (opened && depth <= 0) || (!opened && line.trim_end().ends_with(';')) {
+                end = index + 1;
+                break;
+            }
+        }
+    }
+
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    (first, end)
+}
+
+/// Definitions of `symbol` under `root`, as (file, first line, last line, source)
+pub fn find_symbol(symbol: &str, root: &Path) -> Vec<(PathBuf, usize, usize, String)> {
+    let definition = definition_regex(symbol);
+    let mut found = Vec::new();
+
+    for path in candidate_files(symbol, root) {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            if !definition.is_match(line) {
+                continue;
+            }
+            let (first, end) = definition_range(&lines, index);
+            found.push((path.clone(), first + 1, end, lines[first..end].join("\n")));
+            if found.len() == MAX_SYMBOL_MATCHES {
+                return found;
+            }
+        }
+    }
+    found
+}
+
+/// Append the files and symbol definitions mentioned in `text`, each between clear begin and end
+/// markers. Returns the text to send and what was attached.
+pub fn expand_mentions(
+    text: &str,
+    root: &Path,
+    blobs: Option<(&BlobStore, &str)>,
+) -> (String, Vec<Attachment>) {
+    let mut blocks = Vec::new();
+    let mut attachments = Vec::new();
+
+    for mention in parse_mentions(text, root) {
+        match mention {
+            Mention::File(path) => {
+                let shown = display_path(&path, root);
+                let Some(content) = read_text(&path, blobs) else {
+                    attachments.push(Attachment {
+                        mention: format!("@{}", shown),
+                        summary: "skipped, binary file".to_string(),
+                    });
+                    continue;
+                };
+                attachments.push(Attachment {
+                    mention: format!("@{}", shown),
+                    summary: format!("{} lines", content.lines().count()),
+                });
+                blocks.push(format!(
+                    "<attached-file path=\"{}\">\n{}\n</attached-file>",
+                    shown,
+                    content.trim_end()
+                ));
+            }
+            Mention::Outside(token) => attachments.push(Attachment {
+                mention: format!("@{}", token),
+                summary: "skipped, outside the working directory".to_string(),
+            }),
+            Mention::Symbol(symbol) => {
+                for (path, first, last, source) in find_symbol(&symbol, root) {
+                    let shown = display_path(&path, root);
+                    attachments.push(Attachment {
+                        mention: format!("@{}", symbol),
+                        summary: format!("{}:{}-{}", shown, first, last),
+                    });
+                    blocks.push(format!(
+                        "<attached-symbol name=\"{}\" path=\"{}\" lines=\"{}-{}\">\n{}\n</attached-symbol>",
+                        symbol, shown, first, last, source
+                    ));
+                }
+            }
+        }
+    }
+
+    if blocks.is_empty() {
+        return (text.to_string(), attachments);
+    }
+    (
+        format!(
+            "{}\n\nReferenced in this message:\n\n{}",
+            text,
+            blocks.join("\n\n")
+        ),
+        attachments,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mention_tokens() {
+        assert_eq!(
+            mention_tokens("look at @src/main.rs, and (@Config::load)."),
+            vec!["src/main.rs", "Config::load"]
+        );
+        assert!(mention_tokens("mail me at me@example.com").is_empty());
+        assert!(mention_tokens("the `@decorator` syntax").is_empty());
+        assert!(mention_tokens("```\n@skip_me\n```\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_mentions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.md"), "hello").unwrap();
+
+        let mentions = parse_mentions(
+            "see @notes.md and @Config::load, @notes.md again, @missing.txt",
+            dir.path(),
+        );
+        assert_eq!(
+            mentions,
+            vec![
+                Mention::File(dir.path().join("notes.md")),
+                Mention::Symbol("load".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mentions_stay_in_the_working_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("notes.md"), "hello").unwrap();
+        fs::write(dir.path().join("secret.txt"), "hunter2").unwrap();
+        let outside = dir.path().join("secret.txt");
+
+        let text = format!(
+            "@../secret.txt @{} @./notes.md @notes.md",
+            outside.display()
+        );
+        assert_eq!(
+            parse_mentions(&text, &root),
+            vec![
+                Mention::Outside("../secret.txt".to_string()),
+                Mention::Outside(outside.display().to_string()),
+                Mention::File(root.join("./notes.md")),
+                Mention::File(root.join("notes.md")),
+            ]
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("link.txt")).unwrap();
+            let (text, attachments) = expand_mentions("read @link.txt", &root, None);
+            assert_eq!(text, "read @link.txt");
+            assert_eq!(
+                attachments[0].summary,
+                "skipped, outside the working directory"
+            );
+        }
+    }
+
+    #[test]
+    fn test_definition_range() {
+        let rust =
+            "/// Adds\n#[inline]\npub fn add(a: i32) -> i32 {\n    a + 1\n}\n\nfn other() {}";
+        let lines: Vec<&str> = rust.lines().collect();
+        assert_eq!(definition_range(&lines, 2), (0, 5));
+
+        let python = "@cache\ndef area(r):\n    return r * r\n\nclass Next:\n    pass";
+        let lines: Vec<&str> = python.lines().collect();
+        assert_eq!(definition_range(&lines, 1), (0, 3));
+
+        let lines = vec!["pub type Id = u64;", "fn next() {}"];
+        assert_eq!(definition_range(&lines, 0), (0, 1));
+    }
+
+    #[test]
+    fn test_expand_mentions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "pub struct Widget {\n    size: u32,\n}\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("data.bin"), [0u8, 1, 2]).unwrap();
+
+        let (text, attachments) =
+            expand_mentions("explain @src/lib.rs and @Widget to @bob", dir.path(), None);
+        assert!(text.starts_with("explain @src/lib.rs and @Widget to @bob\n\n"));
+        assert!(text.contains("<attached-file path=\"src/lib.rs\">\npub struct Widget {"));
+        assert!(text.contains(
+            "<attached-symbol name=\"Widget\" path=\"src/lib.rs\" lines=\"1-3\">\npub struct Widget {\n    size: u32,\n}\n</attached-symbol>"
+        ));
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[1].summary, "src/lib.rs:1-3");
+
+        let (text, attachments) = expand_mentions("what is @data.bin", dir.path(), None);
+        assert_eq!(text, "what is @data.bin");
+        assert_eq!(attachments[0].summary, "skipped, binary file");
+
+        let (text, attachments) = expand_mentions("thanks @bob", dir.path(), None);
+        assert_eq!(text, "thanks @bob");
+        assert!(attachments.is_empty());
+    }
+
+    #[test]
+    fn test_large_file_kept_in_blob_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let big = "line\n".repeat(MAX_FILE_BYTES / 4);
+        fs::write(dir.path().join("big.log"), &big).unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+
+        let (text, _) = expand_mentions("look at @big.log", dir.path(), Some((&store, "s1")));
+        let copy = text
+            .split("the full file is stored at ")
+            .nth(1)
+            .and_then(|rest| rest.split(']').next())
+            .unwrap();
+        assert_eq!(fs::read_to_string(copy).unwrap(), big);
+
+        let (again, _) = expand_mentions("and @big.log", dir.path(), Some((&store, "s2")));
+        assert!(again.contains(copy));
+    }
+}
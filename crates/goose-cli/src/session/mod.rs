@@ -1,29 +1,46 @@
+mod approval;
+mod ask_user;
 mod builder;
 mod completion;
 mod export;
+mod highlight;
 mod input;
+mod log_panel;
+mod math;
+mod mentions;
 mod output;
 mod prompt;
+mod prompt_picker;
+mod replay;
+pub mod startup_warnings;
+mod status_line;
 mod task_execution_display;
 mod thinking;
 
+use crate::exit_code::ExitCode;
 use crate::session::task_execution_display::{
-    format_task_execution_notification, TASK_EXECUTION_NOTIFICATION_TYPE,
+    format_task_execution_notification, DashboardKey, DashboardKeys, TaskAction, TaskDashboard,
+    TASK_EXECUTION_NOTIFICATION_TYPE,
 };
 use goose::conversation::Conversation;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 
 pub use self::export::message_to_markdown;
 pub use builder::{build_session, SessionBuilderConfig, SessionSettings};
 use console::Color;
+use goose::agents::ask_user_tool::{Question, ASK_USER_TOOL_NAME};
+use goose::agents::timer_extension;
 use goose::agents::AgentEvent;
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::Permission;
 use goose::permission::PermissionConfirmation;
 use goose::providers::base::Provider;
 use goose::utils::safe_truncate;
+pub use output::{estimate_cost_usd, print_code, render_message, TokenCounts};
+pub use replay::{parse_speed, replay_messages};
 
 use anyhow::{Context, Result};
+use approval::ApprovalChoice;
 use completion::GooseCompleter;
 use etcetera::{choose_app_strategy, AppStrategy};
 use goose::agents::extension::{Envs, ExtensionConfig};
@@ -33,11 +50,16 @@ use goose::config::Config;
 use goose::providers::pricing::initialize_pricing_cache;
 use goose::session;
 use input::InputResult;
+use rmcp::model::CallToolRequestParam;
+use rmcp::model::Content;
 use rmcp::model::PromptMessage;
 use rmcp::model::ServerNotification;
 use rmcp::model::{ErrorCode, ErrorData};
+use status_line::{StatusInfo, StatusLine};
 
-use goose::conversation::message::{Message, MessageContent};
+use goose::conversation::message::{FrontendToolRequest, Message, MessageContent};
+use goose::session::blob_store::BlobStore;
+use goose::session::extension_data::{ExtensionState, FeedbackRating, FeedbackState};
 use goose::session::SessionManager;
 use rand::{distributions::Alphanumeric, Rng};
 use rustyline::EditMode;
@@ -65,6 +87,12 @@ pub struct CliSession {
     max_turns: Option<u32>,
     edit_mode: Option<EditMode>,
     retry_config: Option<RetryConfig>,
+    mcp_logs: log_panel::McpLogPanel,
+    task_dashboard: TaskDashboard,
+    /// Set when a reply ends early on an error or Ctrl+C
+    failure: Option<ExitCode>,
+    /// Set for `goose run`, where nobody is there to answer questions
+    headless: bool,
 }
 
 // Cache structure for completion data
@@ -152,6 +180,10 @@ impl CliSession {
             max_turns,
             edit_mode,
             retry_config,
+            mcp_logs: log_panel::McpLogPanel::new(),
+            task_dashboard: TaskDashboard::new(),
+            failure: None,
+            headless: false,
         }
     }
 
@@ -241,6 +273,7 @@ impl CliSession {
             uri: extension_url,
             envs: Envs::new(HashMap::new()),
             env_keys: Vec::new(),
+            auth: None,
             description: goose::config::DEFAULT_EXTENSION_DESCRIPTION.to_string(),
             // TODO: should set timeout
             timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
@@ -276,6 +309,11 @@ impl CliSession {
             envs: Envs::new(HashMap::new()),
             env_keys: Vec::new(),
             headers: HashMap::new(),
+            auth_type: Default::default(),
+            auth: None,
+            scopes: Vec::new(),
+            oauth_http: Default::default(),
+            oauth_profile: None,
             description: goose::config::DEFAULT_EXTENSION_DESCRIPTION.to_string(),
             // TODO: should set timeout
             timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
@@ -345,6 +383,54 @@ impl CliSession {
             .collect())
     }
 
+    /// Pick a prompt interactively and return the `/prompt` command for it
+    async fn pick_prompt(
+        &mut self,
+        extension: Option<String>,
+        query: Option<String>,
+    ) -> Result<Option<String>> {
+        let prompts = self.agent.list_extension_prompts().await;
+        if let Some(filter) = &extension {
+            if !prompts.contains_key(filter) {
+                return Err(anyhow::anyhow!("Extension '{}' not found", filter));
+            }
+        }
+
+        let items: Vec<prompt_picker::PromptItem> = prompts
+            .into_iter()
+            .filter(|(ext, _)| extension.as_ref().is_none_or(|f| f == ext))
+            .flat_map(|(extension, prompt_list)| {
+                prompt_list
+                    .into_iter()
+                    .map(move |prompt| prompt_picker::PromptItem {
+                        extension: extension.clone(),
+                        name: prompt.name,
+                        description: prompt.description,
+                    })
+            })
+            .collect();
+        if items.is_empty() {
+            println!("{}", console::style("No prompts available").yellow());
+            return Ok(None);
+        }
+
+        let query = query.unwrap_or_default();
+        let Some(item) = prompt_picker::pick_prompt(&items, &query)? else {
+            if !query.is_empty() && prompt_picker::rank(&items, &query).is_empty() {
+                println!(
+                    "{}",
+                    console::style(format!("No prompts match '{}'", query)).yellow()
+                );
+            }
+            return Ok(None);
+        };
+
+        Ok(self
+            .get_prompt_info(&item.name)
+            .await?
+            .map(|info| output::prompt_examples(&info).0))
+    }
+
     pub async fn get_prompt_info(&mut self, name: &str) -> Result<Option<output::PromptInfo>> {
         let prompts = self.agent.list_extension_prompts().await;
 
@@ -395,8 +481,7 @@ impl CliSession {
         self.update_completion_cache().await?;
 
         // Create a new editor with our custom completer
-        let builder =
-            rustyline::Config::builder().completion_type(rustyline::CompletionType::Circular);
+        let builder = rustyline::Config::builder().completion_type(rustyline::CompletionType::List);
         let builder = if let Some(edit_mode) = self.edit_mode {
             builder.edit_mode(edit_mode)
         } else {
@@ -444,17 +529,27 @@ impl CliSession {
             };
 
         output::display_greeting();
+        let mut status_line = StatusLine::start();
+        // Command to pre-fill the next input with, set by the prompt picker
+        let mut pending_input: Option<String> = None;
         loop {
             // Display context usage before each prompt
-            self.display_context_usage().await?;
+            match status_line.as_mut() {
+                Some(status_line) => status_line.draw(&self.status_info().await?),
+                None => self.display_context_usage().await?,
+            }
 
-            match input::get_input(&mut editor)? {
+            match input::get_input(&mut editor, pending_input.take().as_deref())? {
                 InputResult::Message(content) => {
+                    let message_text = attach_fired_timers(
+                        attach_mentions(&content, self.session_id.as_deref()),
+                        self.session_id.as_deref(),
+                    );
                     match self.run_mode {
                         RunMode::Normal => {
                             save_history(&mut editor);
 
-                            self.push_message(Message::user().with_text(&content));
+                            self.push_message(Message::user().with_text(&message_text));
 
                             // Track the current directory and last instruction in projects.json
                             if let Err(e) = crate::project_tracker::update_project_tracker(
@@ -482,7 +577,7 @@ impl CliSession {
                         }
                         RunMode::Plan => {
                             let mut plan_messages = self.messages.clone();
-                            plan_messages.push(Message::user().with_text(&content));
+                            plan_messages.push(Message::user().with_text(&message_text));
                             let reasoner = get_reasoner()?;
                             self.plan_with_reasoner_model(plan_messages, reasoner)
                                 .await?;
@@ -549,15 +644,116 @@ impl CliSession {
                     output::set_theme(new_theme);
                     continue;
                 }
+                input::InputResult::ThemePicker => {
+                    save_history(&mut editor);
+
+                    match output::select_theme_interactive() {
+                        Ok(Some(theme)) => {
+                            println!("Switching to {} theme", theme.display_name())
+                        }
+                        Ok(None) => {}
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
+                input::InputResult::ContextBreakdown => {
+                    save_history(&mut editor);
+
+                    if let Err(e) = self.display_context_breakdown().await {
+                        output::render_error(&format!("Failed to compute context usage: {}", e));
+                    }
+                    continue;
+                }
+                input::InputResult::ShowSystemPrompt => {
+                    save_history(&mut editor);
+
+                    match self.agent.prepare_tools_and_prompt().await {
+                        Ok((_, _, system_prompt)) => println!("{}", system_prompt),
+                        Err(e) => output::render_error(&format!(
+                            "Failed to build the system prompt: {}",
+                            e
+                        )),
+                    }
+                    continue;
+                }
+                input::InputResult::Feedback(rating, reason) => {
+                    save_history(&mut editor);
+
+                    if let Err(e) = self.record_feedback(rating, reason).await {
+                        output::render_error(&format!("Failed to record feedback: {}", e));
+                    }
+                    continue;
+                }
+                input::InputResult::ShowLogs(args) => {
+                    save_history(&mut editor);
+
+                    let entries = self.mcp_logs.filtered(&log_panel::LogFilter::parse(&args));
+                    if entries.is_empty() {
+                        println!("{}", console::style("No extension logs to show").dim());
+                    }
+                    for entry in entries {
+                        println!("{}", entry.render());
+                    }
+                    continue;
+                }
+                input::InputResult::LogLevel(changes) => {
+                    save_history(&mut editor);
+
+                    if changes.is_empty() {
+                        println!("Log filter: {}", crate::logging::log_filter());
+                    } else {
+                        match crate::logging::set_log_filter(&changes) {
+                            Ok(filter) => println!("Log filter: {}", filter),
+                            Err(e) => output::render_error(&format!("{:#}", e)),
+                        }
+                    }
+                    continue;
+                }
+                input::InputResult::RouteOverride(category) => {
+                    save_history(&mut editor);
+
+                    if self.agent.is_task_routing_enabled().await {
+                        self.agent.set_route_override(Some(category)).await;
+                        println!(
+                            "{}",
+                            console::style(format!(
+                                "Your next message will be routed as {}",
+                                category
+                            ))
+                            .dim()
+                        );
+                    } else {
+                        output::render_error(
+                            "Task routing is not configured. Add routes under GOOSE_TASK_ROUTER in your config.",
+                        );
+                    }
+                    continue;
+                }
                 input::InputResult::Retry => continue,
                 input::InputResult::ListPrompts(extension) => {
                     save_history(&mut editor);
 
+                    if prompt_picker::picker_by_default() && std::io::stdout().is_terminal() {
+                        match self.pick_prompt(extension, None).await {
+                            Ok(command) => pending_input = command,
+                            Err(e) => output::render_error(&e.to_string()),
+                        }
+                        continue;
+                    }
                     match self.list_prompts(extension).await {
                         Ok(prompts) => output::render_prompts(&prompts),
                         Err(e) => output::render_error(&e.to_string()),
                     }
                 }
+                input::InputResult::PickPrompt(query) => {
+                    save_history(&mut editor);
+
+                    match self.pick_prompt(None, query).await {
+                        Ok(command) => pending_input = command,
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
                 input::InputResult::GooseMode(mode) => {
                     save_history(&mut editor);
 
@@ -726,6 +922,14 @@ impl CliSession {
                                     session.accumulated_output_tokens,
                                     usage.usage.output_tokens,
                                 );
+                                let accumulated_cached_input = accumulate(
+                                    session.accumulated_cached_input_tokens,
+                                    usage.usage.cached_input_tokens,
+                                );
+                                let accumulated_reasoning = accumulate(
+                                    session.accumulated_reasoning_tokens,
+                                    usage.usage.reasoning_tokens,
+                                );
 
                                 SessionManager::update_session(session_id)
                                     .total_tokens(Some(summary_tokens))
@@ -734,6 +938,8 @@ impl CliSession {
                                     .accumulated_total_tokens(accumulated_total)
                                     .accumulated_input_tokens(accumulated_input)
                                     .accumulated_output_tokens(accumulated_output)
+                                    .accumulated_cached_input_tokens(accumulated_cached_input)
+                                    .accumulated_reasoning_tokens(accumulated_reasoning)
                                     .apply()
                                     .await?;
                             }
@@ -846,12 +1052,82 @@ impl CliSession {
         Ok(())
     }
 
-    /// Process a single message and exit
-    pub async fn headless(&mut self, prompt: String) -> Result<()> {
-        let message = Message::user().with_text(&prompt);
-        self.process_message(message, CancellationToken::default())
-            .await?;
-        Ok(())
+    /// Run again the tasks of the parallel run `run_id` that didn't complete, showing their
+    /// progress as it comes. Ctrl-C cancels the tasks still running.
+    pub async fn resume_tasks(&mut self, run_id: &str) -> Result<ExitCode> {
+        let (notifier, mut notifications) = tokio::sync::mpsc::channel(100);
+        let cancel = CancellationToken::new();
+        let run = self
+            .agent
+            .resume_tasks(run_id, notifier, Some(cancel.clone()));
+        tokio::pin!(run);
+        let mut interrupted = false;
+        let result = loop {
+            tokio::select! {
+                result = &mut run => break result,
+                Some(notification) = notifications.recv() => print_task_notification(&notification),
+                _ = tokio::signal::ctrl_c(), if !interrupted => {
+                    interrupted = true;
+                    cancel.cancel();
+                }
+            }
+        };
+        while let Ok(notification) = notifications.try_recv() {
+            print_task_notification(&notification);
+        }
+
+        match result {
+            Ok(_) if interrupted => Ok(ExitCode::Interrupted),
+            Ok(_) => Ok(ExitCode::Success),
+            Err(e) => {
+                eprintln!("{}: {}", console::style("Error").red().bold(), e);
+                Ok(if interrupted {
+                    ExitCode::Interrupted
+                } else {
+                    ExitCode::TaskFailure
+                })
+            }
+        }
+    }
+
+    /// Process a single message and exit, reporting how the run ended. Timers the agent set
+    /// are waited for, each batch that fires starting another turn.
+    pub async fn headless(&mut self, prompt: String) -> Result<ExitCode> {
+        self.headless = true;
+        let mut message =
+            Message::user().with_text(attach_mentions(&prompt, self.session_id.as_deref()));
+        loop {
+            self.failure = None;
+            self.process_message(message, CancellationToken::default())
+                .await?;
+            let outcome = match self.failure.take() {
+                Some(failure) => failure,
+                None => ExitCode::from_stop_reason(self.agent.stop_reason().await),
+            };
+            if outcome != ExitCode::Success {
+                return Ok(outcome);
+            }
+
+            let session_id = self.session_id.as_deref();
+            let note = loop {
+                let Some(due) = timer_extension::next_timer_due(session_id) else {
+                    return Ok(outcome);
+                };
+                let wait = (due - chrono::Utc::now()).to_std().unwrap_or_default();
+                output::print_decoration(
+                    console::style(format!("⏰ Waiting {}s for the next timer", wait.as_secs()))
+                        .dim(),
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = tokio::signal::ctrl_c() => return Ok(ExitCode::Interrupted),
+                }
+                if let Some(note) = timer_extension::fired_timers_note(session_id) {
+                    break note;
+                }
+            };
+            message = Message::user().with_text(note);
+        }
     }
 
     async fn process_agent_response(
@@ -879,13 +1155,56 @@ impl CliSession {
             .await?;
 
         let mut progress_bars = output::McpSpinners::new();
+        let log_mode = log_panel::mcp_log_mode();
+        self.mcp_logs.begin_turn();
+        let mut deadline_check = tokio::time::interval(std::time::Duration::from_secs(1));
+        // Read while the task dashboard is up
+        let mut dashboard_keys: Option<DashboardKeys> = None;
 
         use futures::StreamExt;
         loop {
             tokio::select! {
+                _ = deadline_check.tick() => {
+                    if let Some(remaining) = self.agent.approaching_deadline() {
+                        output::set_thinking_message(&format!(
+                            "Still waiting for the model, giving up in {}s...",
+                            remaining.as_secs()
+                        ));
+                    }
+                }
+                Some(key) = next_dashboard_key(&mut dashboard_keys) => {
+                    if let Some(action) = self.task_dashboard.press(key) {
+                        let outcome = match &action {
+                            TaskAction::Cancel(task_id) => self.agent.cancel_task(task_id).await,
+                            TaskAction::Restart(task_id) => self.agent.restart_task(task_id).await,
+                            TaskAction::Pause => {
+                                self.agent.pause_task_scheduling();
+                                Ok(())
+                            }
+                            TaskAction::Resume => self.agent.resume_task_scheduling(),
+                        };
+                        self.task_dashboard.report(&action, outcome);
+                    }
+                    print!("{}", self.task_dashboard.refresh());
+                    std::io::stdout().flush().unwrap();
+                }
                 result = stream.next() => {
+                    // Anything else printed while tasks run takes the dashboard's place, and the
+                    // dashboard is drawn again under it
+                    if self.task_dashboard.is_drawn() && !is_task_execution(&result) {
+                        if matches!(result, Some(Ok(AgentEvent::Message(_)))) {
+                            // A message may ask the user something, who needs the keyboard back
+                            dashboard_keys = None;
+                        }
+                        print!("{}", self.task_dashboard.take_down());
+                        std::io::stdout().flush().unwrap();
+                    }
                     match result {
                         Some(Ok(AgentEvent::Message(message))) => {
+                            if message.content.iter().any(|c| matches!(c, MessageContent::ToolRequest(_))) {
+                                output::set_destructive_tools(self.agent.destructive_tools().await);
+                            }
+
                             // If it's a confirmation request, get approval but otherwise do not render/persist
                             if let Some(MessageContent::ToolConfirmationRequest(confirmation)) = message.content.first() {
                                 output::hide_thinking();
@@ -898,33 +1217,27 @@ impl CliSession {
                                     "Goose would like to call the above tool, do you allow?".to_string()
                                 };
 
-                                // Get confirmation from user
-                                let permission_result = if confirmation.prompt.is_none() {
-                                    // No security message - show all options including "Always Allow"
-                                    cliclack::select(prompt)
-                                        .item(Permission::AllowOnce, "Allow", "Allow the tool call once")
-                                        .item(Permission::AlwaysAllow, "Always Allow", "Always allow the tool call")
-                                        .item(Permission::DenyOnce, "Deny", "Deny the tool call")
-                                        .item(Permission::Cancel, "Cancel", "Cancel the AI response and tool call")
-                                        .interact()
-                                } else {
-                                    // Security message present - don't show "Always Allow"
-                                    cliclack::select(prompt)
-                                        .item(Permission::AllowOnce, "Allow", "Allow the tool call once")
-                                        .item(Permission::DenyOnce, "Deny", "Deny the tool call")
-                                        .item(Permission::Cancel, "Cancel", "Cancel the AI response and tool call")
-                                        .interact()
-                                };
-
-                                let permission = match permission_result {
-                                    Ok(p) => p, // If Ok, use the selected permission
-                                    Err(e) => {
-                                        // Check if the error is an interruption (Ctrl+C/Cmd+C, Escape)
-                                        if e.kind() == std::io::ErrorKind::Interrupted {
-                                            Permission::Cancel // If interrupted, set permission to Cancel
-                                        } else {
-                                            return Err(e.into()); // Otherwise, convert and propagate the original error
-                                        }
+                                // Get confirmation from user, showing the box again whenever the arguments are edited.
+                                // "Always Allow" is only offered when there is no security message.
+                                let mut arguments = confirmation.arguments.clone();
+                                let permission = loop {
+                                    match approval::prompt_approval(&prompt, confirmation.prompt.is_none()) {
+                                        Ok(ApprovalChoice::Decide(permission)) => break permission,
+                                        Ok(ApprovalChoice::EditArguments) => match approval::edit_arguments(&arguments) {
+                                            Ok(Some(edited)) => {
+                                                output::render_tool_call(&CallToolRequestParam {
+                                                    name: confirmation.tool_name.clone().into(),
+                                                    arguments: Some(edited.clone()),
+                                                }, self.debug);
+                                                output::render_argument_changes(&confirmation.arguments, &edited);
+                                                arguments = edited;
+                                            }
+                                            Ok(None) => output::render_text("Arguments unchanged", Some(Color::Yellow), true),
+                                            Err(e) => output::render_error(&e.to_string()),
+                                        },
+                                        // Ctrl+C/Cmd+C or Escape cancels
+                                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => break Permission::Cancel,
+                                        Err(e) => return Err(e.into()),
                                     }
                                 };
 
@@ -941,11 +1254,67 @@ impl CliSession {
                                     drop(stream);
                                     break;
                                 } else {
+                                    let edited = arguments != confirmation.arguments;
+                                    if edited && permission != Permission::DenyOnce {
+                                        self.messages = Conversation::new_unvalidated(
+                                            self.messages.iter().cloned().map(|message| {
+                                                message.with_tool_call_arguments(&confirmation.id, &arguments)
+                                            }),
+                                        );
+                                    }
                                     self.agent.handle_confirmation(confirmation.id.clone(), PermissionConfirmation {
                                         principal_type: PrincipalType::Tool,
                                         permission,
+                                        arguments: edited.then_some(arguments),
                                     },).await;
                                 }
+                            } else if let Some((request_id, question)) = ask_user_request(&message) {
+                                output::hide_thinking();
+                                let _ = progress_bars.hide();
+                                self.messages.push(message.clone());
+
+                                let question = match question {
+                                    Ok(question) => question,
+                                    Err(e) => {
+                                        self.agent.handle_tool_result(request_id, Err(ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))).await;
+                                        continue;
+                                    }
+                                };
+                                let answer = if self.headless {
+                                    ask_user::unattended(&question).map_err(|e| (ExitCode::NeedsInput, e))
+                                } else {
+                                    match ask_user::ask(&question) {
+                                        Ok(answer) => Ok(answer),
+                                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                                            Err((ExitCode::Interrupted, "The user didn't answer".to_string()))
+                                        }
+                                        Err(e) => return Err(e.into()),
+                                    }
+                                };
+                                match answer {
+                                    Ok(answer) => {
+                                        let answer = answer.to_value(&question).to_string();
+                                        self.agent.handle_tool_result(request_id, Ok(vec![Content::text(answer)])).await;
+                                    }
+                                    Err((code, reason)) => {
+                                        if code == ExitCode::NeedsInput {
+                                            output::render_error(&reason);
+                                        } else {
+                                            output::render_text("Question cancelled. Returning to chat...", Some(Color::Yellow), true);
+                                        }
+                                        self.failure = Some(code);
+
+                                        let mut response_message = Message::user();
+                                        response_message.content.push(MessageContent::tool_response(
+                                            request_id,
+                                            Err(ErrorData::new(ErrorCode::INVALID_REQUEST, reason, None))
+                                        ));
+                                        self.messages.push(response_message);
+                                        cancel_token_clone.cancel();
+                                        drop(stream);
+                                        break;
+                                    }
+                                }
                             } else if let Some(MessageContent::ContextLengthExceeded(_)) = message.content.first() {
                                 output::hide_thinking();
 
@@ -1074,10 +1443,11 @@ impl CliSession {
                                 output::render_message(&message, self.debug);
                             }
                         }
-                        Some(Ok(AgentEvent::McpNotification((_id, message)))) => {
+                        Some(Ok(AgentEvent::McpNotification((request_id, message)))) => {
                             match &message {
                                 ServerNotification::LoggingMessageNotification(notification) => {
                                     let data = &notification.params.data;
+                                    let log_level = log_panel::LogLevel::from_mcp(notification.params.level);
                                     let (formatted_message, subagent_id, message_notification_type) = match data {
                                         Value::String(s) => (s.clone(), None, None),
                                         Value::Object(o) => {
@@ -1139,16 +1509,38 @@ impl CliSession {
                                     };
 
                                     // Handle subagent notifications - show immediately
-                                    if let Some(_id) = subagent_id {
-                                        // TODO: proper display for subagent notifications
-                                        if interactive {
+                                    if let Some(id) = subagent_id {
+                                        let tagged = format!("{} {}", output::agent_tag(&id), formatted_message);
+                                        if interactive && message_notification_type.as_deref() == Some("response_generated") {
                                             let _ = progress_bars.hide();
-                                            println!("{}", console::style(&formatted_message).green().dim());
+                                            let response = formatted_message.trim_start_matches("🤖 ");
+                                            output::render_message_from(&Message::assistant().with_text(response), Some(&id), self.debug);
+                                        } else if interactive {
+                                            let _ = progress_bars.hide();
+                                            output::print_decoration(tagged);
                                         } else {
-                                            progress_bars.log(&formatted_message);
+                                            progress_bars.log(&tagged);
                                         }
                                     } else if let Some(ref notification_type) = message_notification_type {
-                                        if notification_type == TASK_EXECUTION_NOTIFICATION_TYPE {
+                                        if notification_type == TASK_EXECUTION_NOTIFICATION_TYPE && interactive && TaskDashboard::is_supported() {
+                                            if let Some(frame) = self.task_dashboard.handle(data) {
+                                                let _ = progress_bars.hide();
+                                                print!("{}", frame);
+                                                std::io::stdout().flush().unwrap();
+                                            }
+                                            if self.task_dashboard.is_drawn() {
+                                                dashboard_keys.get_or_insert_with(DashboardKeys::start);
+                                            } else {
+                                                dashboard_keys = None;
+                                            }
+                                        } else if notification_type == TASK_EXECUTION_NOTIFICATION_TYPE {
+                                            // Parallel tasks interleave their output, so tag each line with its task
+                                            let formatted_message = match data.get("task_id").and_then(Value::as_str) {
+                                                Some(task_id) if data.get("subtype").and_then(Value::as_str) == Some("line_output") => {
+                                                    output::prefix_lines(task_id, &formatted_message)
+                                                }
+                                                _ => formatted_message,
+                                            };
                                             if interactive {
                                                 let _ = progress_bars.hide();
                                                 print!("{}", formatted_message);
@@ -1159,10 +1551,22 @@ impl CliSession {
                                             }
                                         }
                                     }
-                                    else if output::is_showing_thinking() {
-                                        output::set_thinking_message(&formatted_message);
-                                    } else {
-                                        progress_bars.log(&formatted_message);
+                                    else {
+                                        let extension = self
+                                            .extension_for_request(&request_id)
+                                            .or_else(|| notification.params.logger.clone())
+                                            .unwrap_or_else(|| "unknown".to_string());
+                                        let entry = self.mcp_logs.record(&extension, log_level, &formatted_message);
+                                        if log_mode == log_panel::McpLogMode::Panel {
+                                            if entry.level > log_panel::LogLevel::Info {
+                                                let _ = progress_bars.hide();
+                                                output::print_decoration(entry.render());
+                                            }
+                                        } else if output::is_showing_thinking() {
+                                            output::set_thinking_message(&formatted_message);
+                                        } else {
+                                            progress_bars.log(&formatted_message);
+                                        }
                                     }
                                 },
                                 ServerNotification::ProgressNotification(notification) => {
@@ -1170,8 +1574,17 @@ impl CliSession {
                                     let text = notification.params.message.as_deref();
                                     let total = notification.params.total;
                                     let token = &notification.params.progress_token;
+                                    let source = output::ProgressSource {
+                                        tool: self.tool_for_request(&request_id).and_then(|tool| {
+                                            tool.split_once("__").map(|(extension, operation)| {
+                                                (extension.to_string(), operation.to_string())
+                                            })
+                                        }),
+                                        request_id: request_id.clone(),
+                                    };
                                     progress_bars.update(
                                         &token.0.to_string(),
+                                        &source,
                                         progress,
                                         total,
                                         text,
@@ -1184,8 +1597,10 @@ impl CliSession {
                 self.messages = Conversation::new_unvalidated(new_messages.clone());
             }
             Some(Ok(AgentEvent::ModelChange { model, mode })) => {
-                            // Log model change if in debug mode
-                            if self.debug {
+                            // Show routing decisions, and log other model changes if in debug mode
+                            if let Some(category) = mode.strip_prefix("route:").or_else(|| mode.strip_prefix("route-override:")) {
+                                output::print_decoration(console::style(format!("↳ {} → {}", category, model)).dim());
+                            } else if self.debug {
                                 eprintln!("Model changed to {} in {} mode", model, mode);
                             }
                         }
@@ -1229,6 +1644,7 @@ impl CliSession {
                                 // Auto-compaction failed, fall through to common error handling below
                             }
                             eprintln!("Error: {}", e);
+                            self.failure = Some(ExitCode::of_error(&e));
                             cancel_token_clone.cancel();
                             drop(stream);
                             if let Err(e) = self.handle_interrupted_messages(false).await {
@@ -1293,6 +1709,7 @@ impl CliSession {
                     }
                 }
                 _ = tokio::signal::ctrl_c() => {
+                    self.failure = Some(ExitCode::Interrupted);
                     cancel_token_clone.cancel();
                     drop(stream);
                     if let Err(e) = self.handle_interrupted_messages(true).await {
@@ -1304,6 +1721,12 @@ impl CliSession {
         }
         println!();
 
+        if log_mode == log_panel::McpLogMode::Panel {
+            if let Some(summary) = self.mcp_logs.turn_summary() {
+                output::print_decoration(console::style(summary).dim());
+            }
+        }
+
         Ok(())
     }
 
@@ -1477,6 +1900,46 @@ impl CliSession {
         Ok(metadata.total_tokens)
     }
 
+    /// Attach a rating to the most recent assistant message in the session metadata
+    async fn record_feedback(&self, rating: FeedbackRating, reason: Option<String>) -> Result<()> {
+        let session_id = self
+            .session_id
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Feedback needs a saved session"))?;
+        let message_index = self
+            .messages
+            .messages()
+            .iter()
+            .rposition(|m| matches!(m.role, rmcp::model::Role::Assistant))
+            .ok_or_else(|| anyhow::anyhow!("There is no response to rate yet"))?;
+
+        let mut session = SessionManager::get_session(session_id, false).await?;
+        let mut feedback =
+            FeedbackState::from_extension_data(&session.extension_data).unwrap_or_default();
+        feedback.record(message_index, rating, reason);
+        feedback.to_extension_data(&mut session.extension_data)?;
+        SessionManager::update_session(session_id)
+            .extension_data(session.extension_data)
+            .apply()
+            .await?;
+
+        println!("{}", console::style("Thanks, feedback recorded.").dim());
+        Ok(())
+    }
+
+    /// Display how the context window is split across prompt, tools and history
+    pub async fn display_context_breakdown(&self) -> Result<()> {
+        let provider = self.agent.provider().await?;
+        let context_limit = provider.get_model_config().context_limit();
+        let breakdown = self
+            .agent
+            .context_breakdown(self.messages.messages())
+            .await?;
+
+        output::display_context_breakdown(&breakdown, context_limit);
+        Ok(())
+    }
+
     /// Display enhanced context usage with session totals
     pub async fn display_context_usage(&self) -> Result<()> {
         let provider = self.agent.provider().await?;
@@ -1511,16 +1974,17 @@ impl CliSession {
 
                 output::display_context_usage(total_tokens, context_limit);
 
+                let tokens = output::TokenCounts {
+                    input: metadata.input_tokens.unwrap_or(0) as usize,
+                    cached_input: metadata.cached_input_tokens.unwrap_or(0) as usize,
+                    output: metadata.output_tokens.unwrap_or(0) as usize,
+                    reasoning: metadata.reasoning_tokens.unwrap_or(0) as usize,
+                };
                 if show_cost {
-                    let input_tokens = metadata.input_tokens.unwrap_or(0) as usize;
-                    let output_tokens = metadata.output_tokens.unwrap_or(0) as usize;
-                    output::display_cost_usage(
-                        &provider_name,
-                        &model_config.model_name,
-                        input_tokens,
-                        output_tokens,
-                    )
-                    .await;
+                    output::display_cost_usage(&provider_name, &model_config.model_name, &tokens)
+                        .await;
+                } else {
+                    output::display_token_split(&tokens);
                 }
             }
             Err(_) => {
@@ -1531,6 +1995,50 @@ impl CliSession {
         Ok(())
     }
 
+    /// What the status line shows: model, mode, context usage and, if enabled, cost
+    async fn status_info(&self) -> Result<StatusInfo> {
+        let provider = self.agent.provider().await?;
+        let model_config = provider.get_model_config();
+
+        let config = Config::global();
+        let mode = config
+            .get_param::<String>("GOOSE_MODE")
+            .unwrap_or_else(|_| "auto".to_string());
+        let show_cost = config
+            .get_param::<bool>("GOOSE_CLI_SHOW_COST")
+            .unwrap_or(false);
+        let provider_name = config
+            .get_param::<String>("GOOSE_PROVIDER")
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        let metadata = self.get_metadata().await.ok();
+        let total_tokens = metadata.as_ref().and_then(|m| m.total_tokens).unwrap_or(0) as usize;
+
+        let cost = match (&metadata, show_cost) {
+            (Some(metadata), true) => {
+                if let Err(e) = initialize_pricing_cache().await {
+                    tracing::warn!("Failed to initialize pricing cache: {e}");
+                }
+                let tokens = output::TokenCounts {
+                    input: metadata.input_tokens.unwrap_or(0) as usize,
+                    cached_input: metadata.cached_input_tokens.unwrap_or(0) as usize,
+                    output: metadata.output_tokens.unwrap_or(0) as usize,
+                    reasoning: metadata.reasoning_tokens.unwrap_or(0) as usize,
+                };
+                output::estimate_cost_usd(&provider_name, &model_config.model_name, &tokens).await
+            }
+            _ => None,
+        };
+
+        Ok(StatusInfo {
+            model: model_config.model_name.clone(),
+            mode,
+            total_tokens,
+            context_limit: model_config.context_limit(),
+            cost,
+        })
+    }
+
     /// Handle prompt command execution
     async fn handle_prompt_command(&mut self, opts: input::PromptCommandOptions) -> Result<()> {
         // name is required
@@ -1636,11 +2144,117 @@ impl CliSession {
         Ok(path)
     }
 
+    /// The name of the tool the request `request_id` called
+    fn tool_for_request(&self, request_id: &str) -> Option<String> {
+        self.messages.iter().rev().find_map(|message| {
+            message.content.iter().find_map(|content| match content {
+                MessageContent::ToolRequest(request) if request.id == request_id => request
+                    .tool_call
+                    .as_ref()
+                    .ok()
+                    .map(|tool_call| tool_call.name.to_string()),
+                _ => None,
+            })
+        })
+    }
+
+    /// The extension whose tool made the request `request_id`, from the tool name's prefix
+    fn extension_for_request(&self, request_id: &str) -> Option<String> {
+        let tool = self.tool_for_request(request_id)?;
+        tool.split_once("__")
+            .map(|(extension, _)| extension.to_string())
+    }
+
     fn push_message(&mut self, message: Message) {
         self.messages.push(message);
     }
 }
 
+/// Attach the files and symbols `@`-mentioned in a user message, noting each one for the user
+fn attach_mentions(text: &str, session_id: Option<&str>) -> String {
+    let Ok(cwd) = std::env::current_dir() else {
+        return text.to_string();
+    };
+    let store = session_id.and_then(|_| BlobStore::open().ok());
+    let blobs = store.as_ref().zip(session_id);
+    let (expanded, attachments) = mentions::expand_mentions(text, &cwd, blobs);
+    for attachment in attachments {
+        output::print_decoration(
+            console::style(format!(
+                "📎 {} ({})",
+                attachment.mention, attachment.summary
+            ))
+            .dim(),
+        );
+    }
+    expanded
+}
+
+/// The `ask_user` call in a frontend tool request, with the question or why it couldn't be read
+fn ask_user_request(message: &Message) -> Option<(String, Result<Question, String>)> {
+    message.content.iter().find_map(|content| match content {
+        MessageContent::FrontendToolRequest(FrontendToolRequest {
+            id,
+            tool_call: Ok(call),
+        }) if call.name == ASK_USER_TOOL_NAME => Some((
+            id.clone(),
+            Question::from_arguments(&call.arguments.clone().unwrap_or_default()),
+        )),
+        _ => None,
+    })
+}
+
+/// Add a note about the timers that fired since the last message, so the model can follow up
+fn attach_fired_timers(text: String, session_id: Option<&str>) -> String {
+    match timer_extension::fired_timers_note(session_id) {
+        Some(note) => {
+            output::print_decoration(console::style("⏰ Timers fired").dim());
+            format!("{}\n\n{}", text, note)
+        }
+        None => text,
+    }
+}
+
+/// Whether `event` is a task run's progress, which the task dashboard draws
+fn is_task_execution<E>(event: &Option<Result<AgentEvent, E>>) -> bool {
+    let Some(Ok(AgentEvent::McpNotification((
+        _,
+        ServerNotification::LoggingMessageNotification(notification),
+    )))) = event
+    else {
+        return false;
+    };
+    notification.params.data.get("type").and_then(Value::as_str)
+        == Some(TASK_EXECUTION_NOTIFICATION_TYPE)
+}
+
+/// The next key pressed under the task dashboard; never, while it isn't up
+async fn next_dashboard_key(keys: &mut Option<DashboardKeys>) -> Option<DashboardKey> {
+    match keys {
+        Some(keys) => keys.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Print a task progress notification the way non-interactive runs show them
+fn print_task_notification(notification: &ServerNotification) {
+    let ServerNotification::LoggingMessageNotification(notification) = notification else {
+        return;
+    };
+    let data = &notification.params.data;
+    let Some((formatted, _, _)) = format_task_execution_notification(data) else {
+        return;
+    };
+    let formatted = match data.get("task_id").and_then(Value::as_str) {
+        Some(task_id) if data.get("subtype").and_then(Value::as_str) == Some("line_output") => {
+            output::prefix_lines(task_id, &formatted)
+        }
+        _ => formatted,
+    };
+    print!("{}", formatted);
+    std::io::stdout().flush().unwrap();
+}
+
 fn get_reasoner() -> Result<Arc<dyn Provider>, anyhow::Error> {
     use goose::model::ModelConfig;
     use goose::providers::create;
@@ -3,22 +3,74 @@ use bat::WrappingMode;
 use console::{measure_text_width, style, Color, Term};
 use goose::config::Config;
 use goose::conversation::message::{Message, MessageContent, ToolRequest, ToolResponse};
-use goose::providers::pricing::get_model_pricing;
-use goose::providers::pricing::parse_model_id;
+use goose::providers::pricing::{find_model_pricing, PricingInfo};
+use goose::token_counter::ContextBreakdown;
 use goose::utils::safe_truncate;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use regex::Regex;
 use rmcp::model::{CallToolRequestParam, JsonObject, PromptArgument};
 use serde_json::Value;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{Error, IsTerminal, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+use super::highlight::{self, Segment};
+
+/// With `--porcelain`, decorative output (greetings, session info, tool call boxes, status
+/// messages) goes to stderr and stdout carries only assistant content and tool results, so
+/// `goose run ... > out.md` captures clean content
+static PORCELAIN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_porcelain(porcelain: bool) {
+    PORCELAIN.store(porcelain, Ordering::Relaxed);
+}
+
+pub fn is_porcelain() -> bool {
+    PORCELAIN.load(Ordering::Relaxed)
+}
+
+/// Whether decorative output ends up on a terminal
+fn decor_is_terminal() -> bool {
+    if is_porcelain() {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    }
+}
+
+/// `println!` for decorative output, which goes to stderr in porcelain mode
+macro_rules! decor_println {
+    ($($arg:tt)*) => {
+        if is_porcelain() {
+            anstream::eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// `print!` for decorative output, which goes to stderr in porcelain mode
+macro_rules! decor_print {
+    ($($arg:tt)*) => {
+        if is_porcelain() {
+            anstream::eprint!($($arg)*)
+        } else {
+            print!($($arg)*)
+        }
+    };
+}
+
+/// Print a line of decorative output, to stderr in porcelain mode
+pub fn print_decoration(line: impl std::fmt::Display) {
+    decor_println!("{}", line);
+}
+
 // Re-export theme for use in main
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Theme {
     Light,
     Dark,
@@ -26,6 +78,16 @@ pub enum Theme {
 }
 
 impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::Ansi];
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+            Theme::Ansi => "Ansi",
+        }
+    }
+
     fn as_str(&self) -> &'static str {
         match self {
             Theme::Light => "GitHub",
@@ -88,6 +150,186 @@ pub fn get_theme() -> Theme {
     CURRENT_THEME.with(|t| *t.borrow())
 }
 
+const THEME_PREVIEW_MARKDOWN: &str = "Here is a **sample** response with `inline code`:
+
+- a list item
+- another item
+
+```rust
+fn main() {
+    println!(\"hello, goose\");
+}
+```
+";
+
+/// Preview every theme with a sample message and tool box, then persist the selection.
+/// Returns `None` if the user cancelled the picker.
+pub fn select_theme_interactive() -> std::io::Result<Option<Theme>> {
+    let sample_call = CallToolRequestParam {
+        name: "developer__shell".into(),
+        arguments: serde_json::json!({ "command": "cargo test --workspace" })
+            .as_object()
+            .cloned(),
+    };
+
+    for theme in Theme::ALL {
+        println!(
+            "\n{}",
+            style(format!("{} theme", theme.display_name())).bold()
+        );
+        print_markdown(THEME_PREVIEW_MARKDOWN, theme);
+        render_shell_request(&sample_call, false);
+    }
+
+    let mut picker = cliclack::select("Which theme would you like to use?");
+    for theme in Theme::ALL {
+        picker = picker.item(theme, theme.display_name(), "");
+    }
+
+    match picker.initial_value(get_theme()).interact() {
+        Ok(theme) => {
+            set_theme(theme);
+            Ok(Some(theme))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Characters used to draw boxes and rules around CLI output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoxStyle {
+    Rounded,
+    Square,
+    Double,
+    Ascii,
+    None,
+}
+
+struct BoxChars {
+    top_left: &'static str,
+    top_right: &'static str,
+    bottom_left: &'static str,
+    bottom_right: &'static str,
+    horizontal: &'static str,
+    vertical: &'static str,
+}
+
+impl BoxStyle {
+    fn from_config_str(val: &str) -> Self {
+        match val.to_lowercase().as_str() {
+            "square" => BoxStyle::Square,
+            "double" => BoxStyle::Double,
+            "ascii" => BoxStyle::Ascii,
+            "none" => BoxStyle::None,
+            _ => BoxStyle::Rounded,
+        }
+    }
+
+    fn chars(&self) -> Option<BoxChars> {
+        match self {
+            BoxStyle::Rounded => Some(BoxChars {
+                top_left: "╭",
+                top_right: "╮",
+                bottom_left: "╰",
+                bottom_right: "╯",
+                horizontal: "─",
+                vertical: "│",
+            }),
+            BoxStyle::Square => Some(BoxChars {
+                top_left: "┌",
+                top_right: "┐",
+                bottom_left: "└",
+                bottom_right: "┘",
+                horizontal: "─",
+                vertical: "│",
+            }),
+            BoxStyle::Double => Some(BoxChars {
+                top_left: "╔",
+                top_right: "╗",
+                bottom_left: "╚",
+                bottom_right: "╝",
+                horizontal: "═",
+                vertical: "║",
+            }),
+            BoxStyle::Ascii => Some(BoxChars {
+                top_left: "+",
+                top_right: "+",
+                bottom_left: "+",
+                bottom_right: "+",
+                horizontal: "-",
+                vertical: "|",
+            }),
+            BoxStyle::None => None,
+        }
+    }
+
+    /// Horizontal rule of the given width, empty for `BoxStyle::None`
+    fn rule(&self, width: usize) -> String {
+        self.chars()
+            .map(|c| c.horizontal.repeat(width))
+            .unwrap_or_default()
+    }
+
+    /// Filled and empty glyphs for the context usage meter
+    fn meter_glyphs(&self) -> (&'static str, &'static str) {
+        match self {
+            BoxStyle::Ascii => ("#", "-"),
+            _ => ("●", "○"),
+        }
+    }
+
+    /// Surround the given lines with a box, padding each line to the widest one
+    fn frame(&self, lines: &[String]) -> Vec<String> {
+        let Some(chars) = self.chars() else {
+            return lines.to_vec();
+        };
+
+        let width = lines
+            .iter()
+            .map(|line| measure_text_width(line))
+            .max()
+            .unwrap_or(0);
+
+        let mut framed = Vec::with_capacity(lines.len() + 2);
+        framed.push(format!(
+            "{}{}{}",
+            chars.top_left,
+            chars.horizontal.repeat(width + 2),
+            chars.top_right
+        ));
+        for line in lines {
+            let padding = " ".repeat(width - measure_text_width(line));
+            framed.push(format!(
+                "{} {}{} {}",
+                chars.vertical, line, padding, chars.vertical
+            ));
+        }
+        framed.push(format!(
+            "{}{}{}",
+            chars.bottom_left,
+            chars.horizontal.repeat(width + 2),
+            chars.bottom_right
+        ));
+        framed
+    }
+}
+
+thread_local! {
+    static CURRENT_BOX_STYLE: BoxStyle =
+        std::env::var("GOOSE_CLI_BOX_STYLE").ok()
+            .map(|val| BoxStyle::from_config_str(&val))
+            .unwrap_or_else(||
+                Config::global().get_param::<String>("GOOSE_CLI_BOX_STYLE").ok()
+                    .map(|val| BoxStyle::from_config_str(&val))
+                    .unwrap_or(BoxStyle::Rounded)
+            );
+}
+
+pub fn get_box_style() -> BoxStyle {
+    CURRENT_BOX_STYLE.with(|s| *s)
+}
+
 // Simple wrapper around spinner to manage its state
 #[derive(Default)]
 pub struct ThinkingIndicator {
@@ -161,12 +403,93 @@ pub fn set_thinking_message(s: &String) {
     }
 }
 
+// Agent whose message is being rendered, so tool headers can carry its tag
+thread_local! {
+    static RENDER_SOURCE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Tools annotated as destructive by their extension, highlighted in their headers
+thread_local! {
+    static DESTRUCTIVE_TOOLS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+pub fn set_destructive_tools(tools: HashSet<String>) {
+    DESTRUCTIVE_TOOLS.with(|t| *t.borrow_mut() = tools);
+}
+
+fn is_destructive_tool(name: &str) -> bool {
+    DESTRUCTIVE_TOOLS.with(|t| t.borrow().contains(name))
+}
+
+/// Tag colors, picked by hashing the agent name so each agent keeps one color for the whole run
+const AGENT_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Blue,
+    Color::Green,
+    Color::Red,
+];
+
+/// Longest agent name shown in a tag; generated ids are cut down to something readable
+const MAX_AGENT_TAG_LEN: usize = 12;
+
+fn agent_color(source: &str) -> Color {
+    // FNV-1a, stable across runs unlike the std hasher
+    let hash = source.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    AGENT_COLORS[(hash % AGENT_COLORS.len() as u64) as usize]
+}
+
+/// A colored `[name]` tag identifying which agent produced some output
+pub fn agent_tag(source: &str) -> String {
+    let name: String = source.chars().take(MAX_AGENT_TAG_LEN).collect();
+    style(format!("[{}]", name))
+        .fg(agent_color(source))
+        .bold()
+        .to_string()
+}
+
+/// Prefix every line of `text` with the agent's tag, for output that interleaves with other agents
+pub fn prefix_lines(source: &str, text: &str) -> String {
+    let tag = agent_tag(source);
+    text.split_inclusive('\n')
+        .map(|line| format!("{} {}", tag, line))
+        .collect()
+}
+
+fn current_source_tag() -> Option<String> {
+    RENDER_SOURCE.with(|s| s.borrow().as_deref().map(agent_tag))
+}
+
 pub fn render_message(message: &Message, debug: bool) {
+    render_message_from(message, None, debug);
+}
+
+/// Render a message produced by `source` (a sub-agent or parallel task), tagging its text and
+/// tool boxes so interleaved transcripts from several agents stay readable
+pub fn render_message_from(message: &Message, source: Option<&str>, debug: bool) {
+    RENDER_SOURCE.with(|s| *s.borrow_mut() = source.map(str::to_string));
+    render_message_contents(message, debug);
+    RENDER_SOURCE.with(|s| *s.borrow_mut() = None);
+}
+
+fn render_message_contents(message: &Message, debug: bool) {
     let theme = get_theme();
 
     for content in &message.content {
         match content {
-            MessageContent::Text(text) => print_markdown(&text.text, theme),
+            MessageContent::Text(text) => {
+                if let Some(tag) = current_source_tag() {
+                    decor_println!("{}", tag);
+                }
+                if super::math::math_rendering_enabled() {
+                    print_markdown(&super::math::render_math(&text.text), theme)
+                } else {
+                    print_markdown(&text.text, theme)
+                }
+            }
             MessageContent::ToolRequest(req) => render_tool_request(req, theme, debug),
             MessageContent::ToolResponse(resp) => render_tool_response(resp, theme, debug),
             MessageContent::Image(image) => {
@@ -182,14 +505,18 @@ pub fn render_message(message: &Message, debug: bool) {
             }
             MessageContent::RedactedThinking(_) => {
                 // For redacted thinking, print thinking was redacted
-                println!("\n{}", style("Thinking:").dim().italic());
-                print_markdown("Thinking was redacted", theme);
+                if is_porcelain() {
+                    decor_println!("\n{}", style("Thinking was redacted").dim().italic());
+                } else {
+                    println!("\n{}", style("Thinking:").dim().italic());
+                    print_markdown("Thinking was redacted", theme);
+                }
             }
             MessageContent::SummarizationRequested(summarization) => {
-                println!("\n{}", style(&summarization.msg).yellow());
+                decor_println!("\n{}", style(&summarization.msg).yellow());
             }
             _ => {
-                println!("WARNING: Message content type could not be rendered");
+                decor_println!("WARNING: Message content type could not be rendered");
             }
         }
     }
@@ -202,8 +529,8 @@ pub fn render_text(text: &str, color: Option<Color>, dim: bool) {
 }
 
 pub fn render_text_no_newlines(text: &str, color: Option<Color>, dim: bool) {
-    if !std::io::stdout().is_terminal() {
-        println!("{}", text);
+    if !decor_is_terminal() {
+        decor_println!("{}", text);
         return;
     }
     let mut styled_text = style(text);
@@ -215,11 +542,11 @@ pub fn render_text_no_newlines(text: &str, color: Option<Color>, dim: bool) {
     } else {
         styled_text = styled_text.green();
     }
-    print!("{}", styled_text);
+    decor_print!("{}", styled_text);
 }
 
 pub fn render_enter_plan_mode() {
-    println!(
+    decor_println!(
         "\n{} {}\n",
         style("Entering plan mode.").green().bold(),
         style("You can provide instructions to create a plan and then act on it. To exit early, type /endplan")
@@ -229,7 +556,7 @@ pub fn render_enter_plan_mode() {
 }
 
 pub fn render_act_on_plan() {
-    println!(
+    decor_println!(
         "\n{}\n",
         style("Exiting plan mode and acting on the above plan")
             .green()
@@ -238,11 +565,64 @@ pub fn render_act_on_plan() {
 }
 
 pub fn render_exit_plan_mode() {
-    println!("\n{}\n", style("Exiting plan mode.").green().bold());
+    decor_println!("\n{}\n", style("Exiting plan mode.").green().bold());
 }
 
 pub fn goose_mode_message(text: &str) {
-    println!("\n{}", style(text).yellow(),);
+    decor_println!("\n{}", style(text).yellow(),);
+}
+
+/// Draw the box for a tool call again, e.g. after its arguments were edited during approval
+pub fn render_tool_call(call: &CallToolRequestParam, debug: bool) {
+    let request = ToolRequest {
+        id: String::new(),
+        tool_call: Ok(call.clone()),
+    };
+    render_tool_request(&request, get_theme(), debug);
+}
+
+const MAX_CHANGE_WIDTH: usize = 200;
+
+/// What editing changed in a tool call's arguments, as `(marker, key, value)` lines in key order:
+/// `-` with the old value of a changed or removed key, `+` with the new value of a changed or
+/// added one
+fn argument_changes(before: &JsonObject, after: &JsonObject) -> Vec<(char, String, String)> {
+    let describe = |value: &Value| match value {
+        Value::String(s) => safe_truncate(s, MAX_CHANGE_WIDTH),
+        other => safe_truncate(&other.to_string(), MAX_CHANGE_WIDTH),
+    };
+    let keys: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = Vec::new();
+    for key in keys {
+        let (old, new) = (before.get(key), after.get(key));
+        if old == new {
+            continue;
+        }
+        if let Some(old) = old {
+            changes.push(('-', key.clone(), describe(old)));
+        }
+        if let Some(new) = new {
+            changes.push(('+', key.clone(), describe(new)));
+        }
+    }
+    changes
+}
+
+pub fn render_argument_changes(before: &JsonObject, after: &JsonObject) {
+    let changes = argument_changes(before, after);
+    if changes.is_empty() {
+        return;
+    }
+    decor_println!("{}", style("edited arguments:").dim());
+    for (marker, key, value) in changes {
+        let line = format!("{} {}: {}", marker, key, value);
+        if marker == '-' {
+            decor_println!("{}", style(line).red());
+        } else {
+            decor_println!("{}", style(line).green());
+        }
+    }
+    decor_println!();
 }
 
 fn render_tool_request(req: &ToolRequest, theme: Theme, debug: bool) {
@@ -286,7 +666,7 @@ fn render_tool_response(resp: &ToolResponse, theme: Theme, debug: bool) {
                 if debug {
                     println!("{:#?}", content);
                 } else if let Some(text) = content.as_text() {
-                    print_markdown(&text.text, theme);
+                    print_tool_output(&text.text, theme);
                 }
             }
         }
@@ -295,7 +675,7 @@ fn render_tool_response(resp: &ToolResponse, theme: Theme, debug: bool) {
 }
 
 pub fn render_error(message: &str) {
-    println!("\n  {} {}\n", style("error:").red().bold(), message);
+    decor_println!("\n  {} {}\n", style("error:").red().bold(), message);
 }
 
 pub fn render_prompts(prompts: &HashMap<String, Vec<String>>) {
@@ -319,6 +699,13 @@ pub fn render_prompt_info(info: &PromptInfo) {
         println!("\n {}", desc);
     }
     render_arguments(info);
+
+    let (required, full) = prompt_examples(info);
+    println!("\n Example:");
+    println!("  {}", style(&required).cyan());
+    if let Some(full) = full {
+        println!("  {}", style(&full).cyan());
+    }
     println!();
 }
 
@@ -339,54 +726,122 @@ fn render_arguments(info: &PromptInfo) {
                 req_str,
                 arg.description.as_deref().unwrap_or("")
             );
+            let choices = argument_choices(arg);
+            if !choices.is_empty() {
+                println!("    {} {}", style("one of:").dim(), choices.join(", "));
+            }
+        }
+    }
+}
+
+/// The values an argument accepts, when its description lists them. MCP prompt arguments have
+/// no schema beyond a name and description, so this picks up the usual ways of writing a list:
+/// "one of: a, b or c", "Format (json|yaml)" and similar.
+fn argument_choices(arg: &PromptArgument) -> Vec<String> {
+    let Some(description) = arg.description.as_deref() else {
+        return Vec::new();
+    };
+    let is_choice = |choice: &str| {
+        !choice.is_empty()
+            && choice.len() <= 32
+            && choice
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+
+    let listed =
+        Regex::new(r"(?i)\b(?:one of|choices|options|values|either)\b\s*:?\s*([^.;)\n]+)").unwrap();
+    for captures in listed.captures_iter(description) {
+        let choices: Vec<String> = captures[1]
+            .split([',', '|', '/'])
+            .flat_map(|part| part.split(" or "))
+            .map(|choice| {
+                choice
+                    .trim()
+                    .trim_matches(|c| matches!(c, '`' | '"' | '\'' | '(' | '['))
+                    .to_string()
+            })
+            .filter(|choice| !choice.is_empty())
+            .collect();
+        if choices.len() > 1 && choices.iter().all(|choice| is_choice(choice)) {
+            return choices;
         }
     }
+
+    let piped = Regex::new(r"[\w.-]+(?:\|[\w.-]+)+").unwrap();
+    piped
+        .find(description)
+        .map(|found| found.as_str().split('|').map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// A `/prompt` command using `info`'s required arguments, and one with every argument if some
+/// are optional. Placeholders stand in for values, or the first choice when there are some.
+pub fn prompt_examples(info: &PromptInfo) -> (String, Option<String>) {
+    let args = info.arguments.as_deref().unwrap_or_default();
+    let command = |include_optional: bool| {
+        let mut command = format!("/prompt {}", info.name);
+        for arg in args {
+            if !arg.required.unwrap_or(false) && !include_optional {
+                continue;
+            }
+            let value = argument_choices(arg)
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| format!("<{}>", arg.name));
+            command.push_str(&format!(" {}={}", arg.name, value));
+        }
+        command
+    };
+
+    let has_optional = args.iter().any(|arg| !arg.required.unwrap_or(false));
+    (command(false), has_optional.then(|| command(true)))
 }
 
 pub fn render_extension_success(name: &str) {
-    println!();
-    println!(
+    decor_println!();
+    decor_println!(
         "  {} extension `{}`",
         style("added").green(),
         style(name).cyan(),
     );
-    println!();
+    decor_println!();
 }
 
 pub fn render_extension_error(name: &str, error: &str) {
-    println!();
-    println!(
+    decor_println!();
+    decor_println!(
         "  {} to add extension {}",
         style("failed").red(),
         style(name).red()
     );
-    println!();
-    println!("{}", style(error).dim());
-    println!();
+    decor_println!();
+    decor_println!("{}", style(error).dim());
+    decor_println!();
 }
 
 pub fn render_builtin_success(names: &str) {
-    println!();
-    println!(
+    decor_println!();
+    decor_println!(
         "  {} builtin{}: {}",
         style("added").green(),
         if names.contains(',') { "s" } else { "" },
         style(names).cyan()
     );
-    println!();
+    decor_println!();
 }
 
 pub fn render_builtin_error(names: &str, error: &str) {
-    println!();
-    println!(
+    decor_println!();
+    decor_println!(
         "  {} to add builtin{}: {}",
         style("failed").red(),
         if names.contains(',') { "s" } else { "" },
         style(names).red()
     );
-    println!();
-    println!("{}", style(error).dim());
-    println!();
+    decor_println!();
+    decor_println!("{}", style(error).dim());
+    decor_println!();
 }
 
 fn render_text_editor_request(call: &CallToolRequestParam, debug: bool) {
@@ -395,7 +850,7 @@ fn render_text_editor_request(call: &CallToolRequestParam, debug: bool) {
     // Print path first with special formatting
     if let Some(args) = &call.arguments {
         if let Some(Value::String(path)) = args.get("path") {
-            println!(
+            decor_println!(
                 "{}: {}",
                 style("path").dim(),
                 style(shorten_path(path, debug)).green()
@@ -415,13 +870,86 @@ fn render_text_editor_request(call: &CallToolRequestParam, debug: bool) {
             }
         }
     }
-    println!();
+    decor_println!();
+}
+
+/// Built-in rules for shell commands that deserve a second look before approval
+const DEFAULT_SHELL_RISK_PATTERNS: &[(&str, &str)] = &[
+    (
+        "recursive delete",
+        r"(?i)\brm\s+(-\w*r\w*f\w*|-\w*f\w*r\w*|-r\s+-f|-f\s+-r|--recursive)",
+    ),
+    (
+        "pipe to shell",
+        r"\b(curl|wget)\b[^|]*\|\s*(sudo\s+)?(ba|z|k|da)?sh\b",
+    ),
+    (
+        "system path write",
+        r"(>|\btee\b(\s+-a)?|\bdd\b.*\bof=)\s*/(etc|usr|bin|sbin|boot|lib|dev|System)/",
+    ),
+    ("force push", r"\bgit\s+push\b.*\s(--force|-f)\b"),
+];
+
+/// Names of the risk rules a shell command matches, built in or from a map of name to regex
+/// under `GOOSE_CLI_SHELL_RISK_PATTERNS`
+fn shell_command_risks(command: &str) -> Vec<String> {
+    let mut rules: Vec<(String, String)> = DEFAULT_SHELL_RISK_PATTERNS
+        .iter()
+        .map(|(name, pattern)| (name.to_string(), pattern.to_string()))
+        .collect();
+    if let Ok(custom) =
+        Config::global().get_param::<HashMap<String, String>>("GOOSE_CLI_SHELL_RISK_PATTERNS")
+    {
+        let mut custom: Vec<_> = custom.into_iter().collect();
+        custom.sort();
+        rules.extend(custom);
+    }
+
+    rules
+        .into_iter()
+        .filter(|(name, pattern)| match Regex::new(pattern) {
+            Ok(re) => re.is_match(command),
+            Err(e) => {
+                tracing::warn!("Invalid shell risk pattern '{}': {}", name, e);
+                false
+            }
+        })
+        .map(|(name, _)| name)
+        .collect()
 }
 
 fn render_shell_request(call: &CallToolRequestParam, debug: bool) {
-    print_tool_header(call);
-    print_params(&call.arguments, 0, debug);
-    println!();
+    let command = call
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("command"))
+        .and_then(Value::as_str);
+    let risks = command.map(shell_command_risks).unwrap_or_default();
+
+    if risks.is_empty() {
+        print_tool_header(call);
+        print_params(&call.arguments, 0, debug);
+        decor_println!();
+        return;
+    }
+
+    let badge = style(format!(" ⚠ {} ", risks.join(", ")))
+        .red()
+        .bold()
+        .reverse();
+    print_tool_header_with_badge(call, Some(badge.to_string()));
+    decor_println!(
+        "{}: {}",
+        style("command").dim(),
+        style(command.unwrap_or_default()).red().bold()
+    );
+    let rest = call.arguments.as_ref().map(|args| {
+        let mut args = args.clone();
+        args.remove("command");
+        args
+    });
+    print_params(&rest, 0, debug);
+    decor_println!();
 }
 
 fn render_dynamic_task_request(call: &CallToolRequestParam, debug: bool) {
@@ -437,31 +965,31 @@ fn render_dynamic_task_request(call: &CallToolRequestParam, debug: bool) {
             _ => None,
         })
     {
-        println!("{}:", style("task_parameters").dim());
+        decor_println!("{}:", style("task_parameters").dim());
         for task_param in task_parameters.iter() {
-            println!("    -");
+            decor_println!("    -");
 
             if let Some(param_obj) = task_param.as_object() {
                 for (key, value) in param_obj {
                     match value {
                         Value::String(s) => {
                             // For strings, print the full content without truncation
-                            println!("        {}: {}", style(key).dim(), style(s).green());
+                            decor_println!("        {}: {}", style(key).dim(), style(s).green());
                         }
                         Value::Array(arr) => {
                             // For arrays, print each item on its own line
-                            println!("        {}:", style(key).dim());
+                            decor_println!("        {}:", style(key).dim());
                             for item in arr {
                                 if let Value::String(s) = item {
-                                    println!("            - {}", style(s).green());
+                                    decor_println!("            - {}", style(s).green());
                                 } else if let Value::Object(_) = item {
                                     // For objects in arrays, print them with indentation
-                                    print!("            - ");
+                                    decor_print!("            - ");
                                     if let Value::Object(obj) = item {
                                         print_params(&Some(obj.clone()), 3, debug);
                                     }
                                 } else {
-                                    println!(
+                                    decor_println!(
                                         "            - {}",
                                         style(format!("{}", item)).green()
                                     );
@@ -470,14 +998,14 @@ fn render_dynamic_task_request(call: &CallToolRequestParam, debug: bool) {
                         }
                         Value::Object(_) => {
                             // For objects, print them with proper indentation
-                            println!("        {}:", style(key).dim());
+                            decor_println!("        {}:", style(key).dim());
                             if let Value::Object(obj) = value {
                                 print_params(&Some(obj.clone()), 2, debug);
                             }
                         }
                         _ => {
                             // For other types (numbers, booleans, null)
-                            println!(
+                            decor_println!(
                                 "        {}: {}",
                                 style(key).dim(),
                                 style(format!("{}", value)).green()
@@ -489,7 +1017,7 @@ fn render_dynamic_task_request(call: &CallToolRequestParam, debug: bool) {
         }
     }
 
-    println!();
+    decor_println!();
 }
 
 fn render_todo_request(call: &CallToolRequestParam, _debug: bool) {
@@ -498,28 +1026,39 @@ fn render_todo_request(call: &CallToolRequestParam, _debug: bool) {
     // For todo tools, always show the full content without redaction
     if let Some(args) = &call.arguments {
         if let Some(Value::String(content)) = args.get("content") {
-            println!("{}: {}", style("content").dim(), style(content).green());
+            decor_println!("{}: {}", style("content").dim(), style(content).green());
         } else {
             // For todo__read, there are no arguments
             // Just print an empty line for consistency
         }
     }
-    println!();
+    decor_println!();
 }
 
 fn render_default_request(call: &CallToolRequestParam, debug: bool) {
     print_tool_header(call);
     print_params(&call.arguments, 0, debug);
-    println!();
+    decor_println!();
 }
 
 // Helper functions
 
 fn print_tool_header(call: &CallToolRequestParam) {
+    print_tool_header_with_badge(call, None);
+}
+
+fn print_tool_header_with_badge(call: &CallToolRequestParam, badge: Option<String>) {
     let parts: Vec<_> = call.name.rsplit("__").collect();
-    let tool_header = format!(
-        "─── {} | {} ──────────────────────────",
-        style(parts.first().unwrap_or(&"unknown")),
+    let box_style = get_box_style();
+    let destructive = is_destructive_tool(&call.name);
+    let tool_name = style(parts.first().unwrap_or(&"unknown"));
+    let label = format!(
+        "{} | {}",
+        if destructive {
+            tool_name.yellow().bold()
+        } else {
+            tool_name
+        },
         style(
             parts
                 .split_first()
@@ -529,8 +1068,30 @@ fn print_tool_header(call: &CallToolRequestParam) {
         .magenta()
         .dim(),
     );
-    println!();
-    println!("{}", tool_header);
+    let badge = badge.or_else(|| {
+        destructive.then(|| {
+            style(" ⚠ destructive ")
+                .yellow()
+                .bold()
+                .reverse()
+                .to_string()
+        })
+    });
+    let label = match badge {
+        Some(badge) => format!("{} {}", label, badge),
+        None => label,
+    };
+    let label = match current_source_tag() {
+        Some(tag) => format!("{} {}", tag, label),
+        None => label,
+    };
+    let tool_header = if box_style == BoxStyle::None {
+        label
+    } else {
+        format!("{} {} {}", box_style.rule(3), label, box_style.rule(26))
+    };
+    decor_println!();
+    decor_println!("{}", tool_header);
 }
 
 // Respect NO_COLOR, as https://crates.io/crates/console already does
@@ -540,25 +1101,57 @@ pub fn env_no_color() -> bool {
 }
 
 fn print_markdown(content: &str, theme: Theme) {
+    if !std::io::stdout().is_terminal() {
+        print!("{}", content);
+        return;
+    }
+    for segment in highlight::split_fences(content) {
+        match segment {
+            Segment::Markdown(text) => print_highlighted(text, "Markdown", theme),
+            Segment::Code { language, code } => print_highlighted(
+                code,
+                highlight::code_syntax(language, code).unwrap_or("Markdown"),
+                theme,
+            ),
+        }
+    }
+}
+
+/// Print tool output, highlighting it as code when it is obviously JSON, a diff, a script, etc.
+fn print_tool_output(content: &str, theme: Theme) {
+    match highlight::detect_language(content).and_then(highlight::resolve_syntax) {
+        Some(syntax) if std::io::stdout().is_terminal() => {
+            print_highlighted(content, syntax, theme)
+        }
+        _ => print_markdown(content, theme),
+    }
+}
+
+/// Print `content` highlighted as `language`, a bat syntax name, when stdout is a terminal
+pub fn print_code(content: &str, language: &str) {
     if std::io::stdout().is_terminal() {
-        bat::PrettyPrinter::new()
-            .input(bat::Input::from_bytes(content.as_bytes()))
-            .theme(theme.as_str())
-            .colored_output(env_no_color())
-            .language("Markdown")
-            .wrapping_mode(WrappingMode::NoWrapping(true))
-            .print()
-            .unwrap();
+        print_highlighted(content, language, get_theme());
     } else {
         print!("{}", content);
     }
 }
 
+fn print_highlighted(content: &str, language: &str, theme: Theme) {
+    bat::PrettyPrinter::new()
+        .input(bat::Input::from_bytes(content.as_bytes()))
+        .theme(theme.as_str())
+        .colored_output(env_no_color())
+        .language(language)
+        .wrapping_mode(WrappingMode::NoWrapping(true))
+        .print()
+        .unwrap();
+}
+
 const INDENT: &str = "    ";
 
 fn print_value_with_prefix(prefix: &String, value: &Value, debug: bool) {
     let prefix_width = measure_text_width(prefix.as_str());
-    print!("{}", prefix);
+    decor_print!("{}", prefix);
     print_value(value, debug, prefix_width)
 }
 
@@ -577,7 +1170,7 @@ fn print_value(value: &Value, debug: bool, reserve_width: usize) {
         Value::Null => style("null".to_string()).dim(),
         _ => unreachable!(),
     };
-    println!("{}", formatted);
+    decor_println!("{}", formatted);
 }
 
 fn print_params(value: &Option<JsonObject>, depth: usize, debug: bool) {
@@ -587,7 +1180,7 @@ fn print_params(value: &Option<JsonObject>, depth: usize, debug: bool) {
         for (key, val) in json_object.iter() {
             match val {
                 Value::Object(obj) => {
-                    println!("{}{}:", indent, style(key).dim());
+                    decor_println!("{}{}:", indent, style(key).dim());
                     print_params(&Some(obj.clone()), depth + 1, debug);
                 }
                 Value::Array(arr) => {
@@ -619,13 +1212,13 @@ fn print_params(value: &Option<JsonObject>, depth: usize, debug: bool) {
                         );
                     } else {
                         // Use the original multi-line format for complex arrays
-                        println!("{}{}:", indent, style(key).dim());
+                        decor_println!("{}{}:", indent, style(key).dim());
                         for item in arr.iter() {
                             if let Value::Object(obj) = item {
-                                println!("{}{}- ", indent, INDENT);
+                                decor_println!("{}{}- ", indent, INDENT);
                                 print_params(&Some(obj.clone()), depth + 2, debug);
                             } else {
-                                println!("{}{}- {}", indent, INDENT, item);
+                                decor_println!("{}{}- {}", indent, INDENT, item);
                             }
                         }
                     }
@@ -642,14 +1235,81 @@ fn print_params(value: &Option<JsonObject>, depth: usize, debug: bool) {
     }
 }
 
+/// How long paths in tool requests are shortened for display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Collapse middle components to their first letter
+    Initials,
+    /// Replace middle components with a single ellipsis, keeping as much of the end as fits
+    MiddleEllipsis,
+    /// Show paths inside the working directory relative to it, ellipsizing anything still too long
+    ProjectRelative,
+    /// Never shorten
+    Full,
+}
+
+const DEFAULT_PATH_MAX_LEN: usize = 60;
+
+impl PathStyle {
+    fn from_config_str(val: &str) -> Self {
+        match val.to_lowercase().as_str() {
+            "ellipsis" | "middle-ellipsis" => PathStyle::MiddleEllipsis,
+            "relative" | "project-relative" => PathStyle::ProjectRelative,
+            "full" | "none" => PathStyle::Full,
+            _ => PathStyle::Initials,
+        }
+    }
+}
+
+fn config_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .or_else(|| Config::global().get_param::<String>(key).ok())
+}
+
+thread_local! {
+    static CURRENT_PATH_STYLE: (PathStyle, usize) = (
+        config_value("GOOSE_CLI_PATH_STYLE")
+            .map(|val| PathStyle::from_config_str(&val))
+            .unwrap_or(PathStyle::Initials),
+        config_value("GOOSE_CLI_PATH_MAX_LEN")
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(DEFAULT_PATH_MAX_LEN),
+    );
+}
+
 fn shorten_path(path: &str, debug: bool) -> String {
     // In debug mode, return the full path
     if debug {
         return path.to_string();
     }
 
+    let (style, max_len) = CURRENT_PATH_STYLE.with(|s| *s);
+    let cwd = std::env::current_dir().ok();
+    shorten_path_with(path, style, max_len, cwd.as_deref())
+}
+
+fn shorten_path_with(
+    path: &str,
+    style: PathStyle,
+    max_len: usize,
+    project_root: Option<&Path>,
+) -> String {
+    if style == PathStyle::Full {
+        return path.to_string();
+    }
+
     let path = Path::new(path);
 
+    if style == PathStyle::ProjectRelative {
+        if let Some(relative) = project_root
+            .and_then(|root| path.strip_prefix(root).ok())
+            .filter(|relative| !relative.as_os_str().is_empty())
+        {
+            return ellipsize_middle(&relative.display().to_string(), max_len);
+        }
+    }
+
     // First try to convert to ~ if it's in home directory
     let home = etcetera::home_dir().ok();
     let path_str = if let Some(home) = home {
@@ -662,16 +1322,23 @@ fn shorten_path(path: &str, debug: bool) -> String {
         path.display().to_string()
     };
 
+    match style {
+        PathStyle::Initials => shorten_to_initials(&path_str, max_len),
+        _ => ellipsize_middle(&path_str, max_len),
+    }
+}
+
+fn shorten_to_initials(path_str: &str, max_len: usize) -> String {
     // If path is already short enough, return as is
-    if path_str.len() <= 60 {
-        return path_str;
+    if path_str.chars().count() <= max_len {
+        return path_str.to_string();
     }
 
     let parts: Vec<_> = path_str.split('/').collect();
 
     // If we have 3 or fewer parts, return as is
     if parts.len() <= 3 {
-        return path_str;
+        return path_str.to_string();
     }
 
     // Keep the first component (empty string before root / or ~) and last two components intact
@@ -691,6 +1358,32 @@ fn shorten_path(path: &str, debug: bool) -> String {
     shortened.join("/")
 }
 
+fn ellipsize_middle(path_str: &str, max_len: usize) -> String {
+    if path_str.chars().count() <= max_len {
+        return path_str.to_string();
+    }
+
+    let parts: Vec<_> = path_str.split('/').collect();
+    if parts.len() <= 3 {
+        return path_str.to_string();
+    }
+
+    // Keep the first component and the file name, then as many parents of the file as still fit
+    let head = parts[0];
+    let mut tail = vec![parts[parts.len() - 1]];
+    let mut len = head.chars().count() + "/…/".chars().count() + tail[0].chars().count();
+    for component in parts[1..parts.len() - 1].iter().rev() {
+        len += component.chars().count() + 1;
+        if len > max_len {
+            break;
+        }
+        tail.push(component);
+    }
+    tail.reverse();
+
+    format!("{}/…/{}", head, tail.join("/"))
+}
+
 // Session display functions
 pub fn display_session_info(
     resume: bool,
@@ -707,61 +1400,57 @@ pub fn display_session_info(
         "starting session |"
     };
 
+    let mut lines = Vec::new();
+
     // Check if we have lead/worker mode
-    if let Some(provider_inst) = provider_instance {
-        if let Some(lead_worker) = provider_inst.as_lead_worker() {
-            let (lead_model, worker_model) = lead_worker.get_model_info();
-            println!(
-                "{} {} {} {} {} {} {}",
-                style(start_session_msg).dim(),
-                style("provider:").dim(),
-                style(provider).cyan().dim(),
-                style("lead model:").dim(),
-                style(&lead_model).cyan().dim(),
-                style("worker model:").dim(),
-                style(&worker_model).cyan().dim(),
-            );
-        } else {
-            println!(
-                "{} {} {} {} {}",
-                style(start_session_msg).dim(),
-                style("provider:").dim(),
-                style(provider).cyan().dim(),
-                style("model:").dim(),
-                style(model).cyan().dim(),
-            );
-        }
+    if let Some(lead_worker) = provider_instance.and_then(|p| p.as_lead_worker()) {
+        let (lead_model, worker_model) = lead_worker.get_model_info();
+        lines.push(format!(
+            "{} {} {} {} {} {} {}",
+            style(start_session_msg).dim(),
+            style("provider:").dim(),
+            style(provider).cyan().dim(),
+            style("lead model:").dim(),
+            style(&lead_model).cyan().dim(),
+            style("worker model:").dim(),
+            style(&worker_model).cyan().dim(),
+        ));
     } else {
-        // Fallback to original behavior if no provider instance
-        println!(
+        lines.push(format!(
             "{} {} {} {} {}",
             style(start_session_msg).dim(),
             style("provider:").dim(),
             style(provider).cyan().dim(),
             style("model:").dim(),
             style(model).cyan().dim(),
-        );
+        ));
     }
 
     if let Some(id) = session_id {
-        println!(
+        lines.push(format!(
             "    {} {}",
             style("session id:").dim(),
             style(id).cyan().dim()
-        );
+        ));
     }
 
-    println!(
+    lines.push(format!(
         "    {} {}",
         style("working directory:").dim(),
         style(std::env::current_dir().unwrap().display())
             .cyan()
             .dim()
-    );
+    ));
+
+    for line in get_box_style().frame(&lines) {
+        decor_println!("{}", line);
+    }
 }
 
 pub fn display_greeting() {
-    println!("\ngoose is running! Enter your instructions, or try asking what goose can do.\n");
+    decor_println!(
+        "\ngoose is running! Enter your instructions, or try asking what goose can do.\n"
+    );
 }
 
 /// Display context window usage with both current and session totals
@@ -769,7 +1458,7 @@ pub fn display_context_usage(total_tokens: usize, context_limit: usize) {
     use console::style;
 
     if context_limit == 0 {
-        println!("Context: Error - context limit is zero");
+        decor_println!("Context: Error - context limit is zero");
         return;
     }
 
@@ -783,8 +1472,9 @@ pub fn display_context_usage(total_tokens: usize, context_limit: usize) {
         (((percentage as f64 / 100.0) * dot_count as f64).round() as usize).min(dot_count);
     let empty_dots = dot_count - filled_dots;
 
-    let filled = "●".repeat(filled_dots);
-    let empty = "○".repeat(empty_dots);
+    let (filled_glyph, empty_glyph) = get_box_style().meter_glyphs();
+    let filled = filled_glyph.repeat(filled_dots);
+    let empty = empty_glyph.repeat(empty_dots);
 
     // Combine dots and apply color
     let dots = format!("{}{}", filled, empty);
@@ -797,99 +1487,169 @@ pub fn display_context_usage(total_tokens: usize, context_limit: usize) {
     };
 
     // Print the status line
-    println!(
+    decor_println!(
         "Context: {} {}% ({}/{} tokens)",
-        colored_dots, percentage, total_tokens, context_limit
+        colored_dots,
+        percentage,
+        total_tokens,
+        context_limit
     );
 }
 
-fn normalize_model_name(model: &str) -> String {
-    let mut result = model.to_string();
+pub fn display_context_breakdown(breakdown: &ContextBreakdown, context_limit: usize) {
+    use console::style;
 
-    // Remove "-latest" suffix
-    if result.ends_with("-latest") {
-        result = result.strip_suffix("-latest").unwrap().to_string();
+    let total = breakdown.total();
+    display_context_usage(total, context_limit);
+
+    let rows = [
+        ("System prompt", breakdown.system_prompt),
+        ("Tool schemas", breakdown.tool_schemas),
+        ("Conversation", breakdown.conversation),
+        ("Tool results", breakdown.tool_results),
+    ];
+    let (filled_glyph, _) = get_box_style().meter_glyphs();
+    for (label, tokens) in rows {
+        let share = if total == 0 {
+            0.0
+        } else {
+            tokens as f64 / total as f64
+        };
+        let bar = filled_glyph.repeat((share * 20.0).round() as usize);
+        decor_println!(
+            "  {:<14} {:>8} tokens {:>4.0}% {}",
+            label,
+            tokens,
+            share * 100.0,
+            style(bar).dim()
+        );
     }
+}
 
-    // Remove date-like suffixes: -YYYYMMDD
-    let re_date = Regex::new(r"-\d{8}$").unwrap();
-    if re_date.is_match(&result) {
-        result = re_date.replace(&result, "").to_string();
-    }
+/// Token counts for a request, with the cached and reasoning parts broken out
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenCounts {
+    /// All prompt tokens, including the cached ones
+    pub input: usize,
+    pub cached_input: usize,
+    /// All completion tokens, including the reasoning ones
+    pub output: usize,
+    pub reasoning: usize,
+}
 
-    // Convert version numbers like -3-7- to -3.7- (e.g., claude-3-7-sonnet -> claude-3.7-sonnet)
-    let re_version = Regex::new(r"-(\d+)-(\d+)-").unwrap();
-    if re_version.is_match(&result) {
-        result = re_version.replace(&result, "-$1.$2-").to_string();
+impl TokenCounts {
+    fn describe(&self) -> String {
+        let mut input = format!("in {}", self.input);
+        if self.cached_input > 0 {
+            input.push_str(&format!(" ({} cached)", self.cached_input));
+        }
+        let mut output = format!("out {}", self.output);
+        if self.reasoning > 0 {
+            output.push_str(&format!(" ({} reasoning)", self.reasoning));
+        }
+        format!("{}, {}", input, output)
     }
-
-    result
 }
 
-async fn estimate_cost_usd(
-    provider: &str,
-    model: &str,
-    input_tokens: usize,
-    output_tokens: usize,
-) -> Option<f64> {
-    // For OpenRouter, parse the model name to extract real provider/model
-    let openrouter_data = if provider == "openrouter" {
-        parse_model_id(model)
-    } else {
-        None
-    };
-
-    let (provider_to_use, model_to_use) = match &openrouter_data {
-        Some((real_provider, real_model)) => (real_provider.as_str(), real_model.as_str()),
-        None => (provider, model),
-    };
-
-    // Use the pricing module's get_model_pricing which handles model name mapping internally
-    let cleaned_model = normalize_model_name(model_to_use);
-    let pricing_info = get_model_pricing(provider_to_use, &cleaned_model).await;
+fn cost_for_tokens(pricing: &PricingInfo, tokens: &TokenCounts) -> f64 {
+    pricing.cost(tokens.input, tokens.cached_input, tokens.output)
+}
 
-    match pricing_info {
-        Some(pricing) => {
-            let input_cost = pricing.input_cost * input_tokens as f64;
-            let output_cost = pricing.output_cost * output_tokens as f64;
-            Some(input_cost + output_cost)
-        }
-        None => None,
-    }
+pub async fn estimate_cost_usd(provider: &str, model: &str, tokens: &TokenCounts) -> Option<f64> {
+    find_model_pricing(provider, model)
+        .await
+        .map(|pricing| cost_for_tokens(&pricing, tokens))
 }
 
 /// Display cost information, if price data is available.
-pub async fn display_cost_usage(
-    provider: &str,
-    model: &str,
-    input_tokens: usize,
-    output_tokens: usize,
-) {
-    if let Some(cost) = estimate_cost_usd(provider, model, input_tokens, output_tokens).await {
+pub async fn display_cost_usage(provider: &str, model: &str, tokens: &TokenCounts) {
+    if let Some(cost) = estimate_cost_usd(provider, model, tokens).await {
         use console::style;
         eprintln!(
-            "Cost: {} USD ({} tokens: in {}, out {})",
+            "Cost: {} USD ({} tokens: {})",
             style(format!("${:.4}", cost)).cyan(),
-            input_tokens + output_tokens,
-            input_tokens,
-            output_tokens
+            tokens.input + tokens.output,
+            tokens.describe()
         );
     }
 }
 
+/// Show how the last request's tokens split between cached/fresh input and reasoning/answer output
+pub fn display_token_split(tokens: &TokenCounts) {
+    if tokens.cached_input == 0 && tokens.reasoning == 0 {
+        return;
+    }
+    decor_println!(
+        "{}",
+        console::style(format!("Tokens: {}", tokens.describe())).dim()
+    );
+}
+
+/// Where a progress notification came from, for labelling its bar
+#[derive(Debug, Clone, Default)]
+pub struct ProgressSource {
+    /// The tool request that is reporting progress
+    pub request_id: String,
+    /// The extension and tool name the request called, e.g. ("developer", "shell")
+    pub tool: Option<(String, String)>,
+}
+
+struct ProgressEntry {
+    bar: ProgressBar,
+    determinate: bool,
+    parent: Option<String>,
+    depth: usize,
+    /// The last bar shown in this bar's subtree, which the next child goes after
+    tail: ProgressBar,
+}
+
 pub struct McpSpinners {
-    bars: HashMap<String, ProgressBar>,
+    bars: HashMap<String, ProgressEntry>,
+    /// The first progress token of each tool request; later tokens of that request nest under it
+    request_roots: HashMap<String, String>,
     log_spinner: Option<ProgressBar>,
 
     multi_bar: MultiProgress,
 }
 
+fn determinate_style() -> ProgressStyle {
+    ProgressStyle::with_template("{prefix}[{elapsed}] {bar:40} {percent:>3}% {msg}").unwrap()
+}
+
+fn indeterminate_style() -> ProgressStyle {
+    ProgressStyle::with_template("{prefix}{spinner:.green} [{elapsed}] {msg}")
+        .unwrap()
+        .tick_chars("⠋⠙⠚⠛⠓⠒⠊⠉")
+}
+
+/// The text before a bar: tree indentation for nested bars and the tool it belongs to
+fn progress_prefix(source: &ProgressSource, depth: usize) -> String {
+    let indent = if depth == 0 {
+        String::new()
+    } else {
+        format!("{}└ ", "  ".repeat(depth - 1))
+    };
+    match (&source.tool, depth) {
+        (Some((extension, operation)), 0) => format!(
+            "{}{} ",
+            indent,
+            style(format!("{} · {}", extension, operation)).dim()
+        ),
+        _ => indent,
+    }
+}
+
 impl McpSpinners {
     pub fn new() -> Self {
+        Self::with_multi_progress(MultiProgress::new())
+    }
+
+    fn with_multi_progress(multi_bar: MultiProgress) -> Self {
         McpSpinners {
             bars: HashMap::new(),
+            request_roots: HashMap::new(),
             log_spinner: None,
-            multi_bar: MultiProgress::new(),
+            multi_bar,
         }
     }
 
@@ -911,28 +1671,103 @@ impl McpSpinners {
         spinner.set_message(message.to_string());
     }
 
-    pub fn update(&mut self, token: &str, value: f64, total: Option<f64>, message: Option<&str>) {
-        let bar = self.bars.entry(token.to_string()).or_insert_with(|| {
-            if let Some(total) = total {
-                self.multi_bar.add(
-                    ProgressBar::new((total * 100_f64) as u64).with_style(
-                        ProgressStyle::with_template("[{elapsed}] {bar:40} {pos:>3}/{len:3} {msg}")
-                            .unwrap(),
-                    ),
-                )
-            } else {
-                self.multi_bar.add(ProgressBar::new_spinner())
+    /// The bar a new token nests under: an existing token it extends with a `/`-separated
+    /// segment (`index/embed` under `index`), or else the first bar of the same tool request
+    fn parent_for(&self, token: &str, source: &ProgressSource) -> Option<String> {
+        token
+            .rsplit_once('/')
+            .map(|(parent, _)| parent.to_string())
+            .filter(|parent| self.bars.contains_key(parent))
+            .or_else(|| {
+                self.request_roots
+                    .get(&source.request_id)
+                    .filter(|root| root.as_str() != token)
+                    .cloned()
+            })
+    }
+
+    fn add_bar(&mut self, token: &str, source: &ProgressSource, total: Option<f64>) {
+        let parent = self.parent_for(token, source);
+        let depth = parent
+            .as_ref()
+            .and_then(|parent| self.bars.get(parent))
+            .map(|entry| entry.depth + 1)
+            .unwrap_or(0);
+
+        let bar = match total {
+            Some(total) => {
+                ProgressBar::new((total * 100_f64) as u64).with_style(determinate_style())
             }
-        });
-        bar.set_position((value * 100_f64) as u64);
+            None => ProgressBar::new_spinner().with_style(indeterminate_style()),
+        };
+        bar.set_prefix(progress_prefix(source, depth));
+        let bar = match parent.as_ref().and_then(|parent| self.bars.get(parent)) {
+            Some(parent_entry) => self.multi_bar.insert_after(&parent_entry.tail, bar),
+            None => self.multi_bar.add(bar),
+        };
+        if total.is_none() {
+            bar.enable_steady_tick(Duration::from_millis(100));
+        }
+
+        // The new bar is now the last one in the subtree of each of its ancestors
+        let mut ancestor = parent.clone();
+        while let Some(name) = ancestor {
+            let Some(entry) = self.bars.get_mut(&name) else {
+                break;
+            };
+            entry.tail = bar.clone();
+            ancestor = entry.parent.clone();
+        }
+
+        self.request_roots
+            .entry(source.request_id.clone())
+            .or_insert_with(|| token.to_string());
+        self.bars.insert(
+            token.to_string(),
+            ProgressEntry {
+                tail: bar.clone(),
+                bar,
+                determinate: total.is_some(),
+                parent,
+                depth,
+            },
+        );
+    }
+
+    pub fn update(
+        &mut self,
+        token: &str,
+        source: &ProgressSource,
+        value: f64,
+        total: Option<f64>,
+        message: Option<&str>,
+    ) {
+        if !self.bars.contains_key(token) {
+            self.add_bar(token, source, total);
+        }
+        let entry = self.bars.get_mut(token).expect("bar was just added");
+
+        if let Some(total) = total {
+            let length = (total * 100_f64) as u64;
+            if !entry.determinate {
+                // The total arrived after the work started, so the spinner becomes a bar
+                entry.bar.disable_steady_tick();
+                entry.bar.set_style(determinate_style());
+                entry.determinate = true;
+            }
+            if entry.bar.length() != Some(length) {
+                entry.bar.set_length(length);
+            }
+        }
+        entry.bar.set_position((value * 100_f64) as u64);
         if let Some(msg) = message {
-            bar.set_message(msg.to_string());
+            entry.bar.set_message(msg.to_string());
         }
     }
 
     pub fn hide(&mut self) -> Result<(), Error> {
-        self.bars.iter_mut().for_each(|(_, bar)| {
-            bar.disable_steady_tick();
+        self.bars.iter_mut().for_each(|(_, entry)| {
+            entry.bar.disable_steady_tick();
         });
         if let Some(spinner) = self.log_spinner.as_mut() {
             spinner.disable_steady_tick();
@@ -946,6 +1781,172 @@ mod tests {
     use super::*;
     use std::env;
 
+    fn prompt_argument(name: &str, description: &str, required: bool) -> PromptArgument {
+        PromptArgument {
+            name: name.to_string(),
+            title: None,
+            description: Some(description.to_string()),
+            required: Some(required),
+        }
+    }
+
+    #[test]
+    fn test_argument_choices() {
+        let choices =
+            |description: &str| argument_choices(&prompt_argument("a", description, true));
+        assert_eq!(
+            choices("Output format, one of: `json`, `yaml` or `toml`."),
+            vec!["json", "yaml", "toml"]
+        );
+        assert_eq!(
+            choices("Severity (low|medium|high)"),
+            vec!["low", "medium", "high"]
+        );
+        assert_eq!(choices("Either fast or thorough"), vec!["fast", "thorough"]);
+        assert!(choices("The options you want to pass, as free text").is_empty());
+        assert!(choices("A file path").is_empty());
+    }
+
+    #[test]
+    fn test_prompt_examples() {
+        let info = PromptInfo {
+            name: "review".to_string(),
+            description: None,
+            arguments: Some(vec![
+                prompt_argument("file", "The file to review", true),
+                prompt_argument("depth", "How deep to go (quick|full)", false),
+            ]),
+            extension: None,
+        };
+        assert_eq!(
+            prompt_examples(&info),
+            (
+                "/prompt review file=<file>".to_string(),
+                Some("/prompt review file=<file> depth=quick".to_string())
+            )
+        );
+
+        let info = PromptInfo {
+            arguments: None,
+            ..info
+        };
+        assert_eq!(prompt_examples(&info), ("/prompt review".to_string(), None));
+    }
+
+    #[test]
+    fn test_mcp_progress_nesting() {
+        let mut spinners = McpSpinners::with_multi_progress(MultiProgress::with_draw_target(
+            indicatif::ProgressDrawTarget::hidden(),
+        ));
+        let source = ProgressSource {
+            request_id: "req_1".to_string(),
+            tool: Some(("developer".to_string(), "index".to_string())),
+        };
+
+        // Starts without a total, which arrives later
+        spinners.update("index", &source, 1.0, None, Some("scanning"));
+        assert!(!spinners.bars["index"].determinate);
+        spinners.update("index", &source, 2.0, Some(10.0), None);
+        let index = &spinners.bars["index"];
+        assert!(index.determinate);
+        assert_eq!(index.bar.length(), Some(1000));
+        assert_eq!(index.bar.position(), 200);
+        assert_eq!(
+            console::strip_ansi_codes(&index.bar.prefix()),
+            "developer · index "
+        );
+
+        // Another token of the same request and a `/` child nest under the first bar
+        spinners.update("files", &source, 0.0, Some(5.0), None);
+        spinners.update("files/embed", &source, 0.0, None, None);
+        assert_eq!(spinners.bars["files"].parent.as_deref(), Some("index"));
+        assert_eq!(
+            spinners.bars["files/embed"].parent.as_deref(),
+            Some("files")
+        );
+        assert_eq!(spinners.bars["files/embed"].depth, 2);
+        assert_eq!(spinners.bars["files/embed"].bar.prefix(), "  └ ");
+
+        // A different request gets its own top-level bar
+        let other = ProgressSource {
+            request_id: "req_2".to_string(),
+            tool: None,
+        };
+        spinners.update("download", &other, 0.5, Some(1.0), None);
+        assert_eq!(spinners.bars["download"].parent, None);
+        assert_eq!(spinners.bars["download"].depth, 0);
+    }
+
+    #[test]
+    fn test_agent_tags() {
+        assert_eq!(agent_color("researcher"), agent_color("researcher"));
+        let tag = console::strip_ansi_codes(&agent_tag("a1b2c3d4-e5f6-7890-abcd-ef1234567890"))
+            .to_string();
+        assert_eq!(tag, "[a1b2c3d4-e5f]");
+
+        let prefixed = console::strip_ansi_codes(&prefix_lines("w1", "one\ntwo\n")).to_string();
+        assert_eq!(prefixed, "[w1] one\n[w1] two\n");
+    }
+
+    #[test]
+    fn test_cost_for_tokens_discounts_cached_input() {
+        let pricing = PricingInfo {
+            input_cost: 0.000003,
+            output_cost: 0.000015,
+            cached_input_cost: Some(0.0000003),
+            context_length: None,
+        };
+        let tokens = TokenCounts {
+            input: 1000,
+            cached_input: 800,
+            output: 100,
+            reasoning: 40,
+        };
+        let expected = 200.0 * 0.000003 + 800.0 * 0.0000003 + 100.0 * 0.000015;
+        assert!((cost_for_tokens(&pricing, &tokens) - expected).abs() < 1e-12);
+
+        let no_cache_price = PricingInfo {
+            cached_input_cost: None,
+            ..pricing
+        };
+        let expected = 1000.0 * 0.000003 + 100.0 * 0.000015;
+        assert!((cost_for_tokens(&no_cache_price, &tokens) - expected).abs() < 1e-12);
+        assert_eq!(
+            tokens.describe(),
+            "in 1000 (800 cached), out 100 (40 reasoning)"
+        );
+    }
+
+    #[test]
+    fn test_shell_command_risks() {
+        assert_eq!(
+            shell_command_risks("rm -rf target"),
+            vec!["recursive delete"]
+        );
+        assert_eq!(
+            shell_command_risks("rm -fr /tmp/x"),
+            vec!["recursive delete"]
+        );
+        assert_eq!(
+            shell_command_risks("curl -fsSL https://example.com/install.sh | sh"),
+            vec!["pipe to shell"]
+        );
+        assert_eq!(
+            shell_command_risks("echo 1 > /etc/hosts"),
+            vec!["system path write"]
+        );
+        assert_eq!(
+            shell_command_risks("git push --force origin main"),
+            vec!["force push"]
+        );
+        assert_eq!(shell_command_risks("git push -f"), vec!["force push"]);
+
+        assert!(shell_command_risks("rm file.txt").is_empty());
+        assert!(shell_command_risks("curl https://example.com -o out.html").is_empty());
+        assert!(shell_command_risks("git push origin feature-force").is_empty());
+        assert!(shell_command_risks("ls / > out.txt").is_empty());
+    }
+
     #[test]
     fn test_short_paths_unchanged() {
         assert_eq!(shorten_path("/usr/bin", false), "/usr/bin");
@@ -998,4 +1999,103 @@ mod tests {
             "/v/l/p/w/m/components/file.txt"
         );
     }
+
+    #[test]
+    fn test_path_styles() {
+        let long =
+            "/vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/long/path/with/many/components/file.txt";
+        assert_eq!(
+            shorten_path_with(long, PathStyle::Initials, 60, None),
+            "/v/l/p/w/m/components/file.txt"
+        );
+        assert_eq!(
+            shorten_path_with(long, PathStyle::MiddleEllipsis, 40, None),
+            "/…/path/with/many/components/file.txt"
+        );
+        assert_eq!(shorten_path_with(long, PathStyle::Full, 10, None), long);
+        assert_eq!(
+            shorten_path_with("/a/b/c/d", PathStyle::Initials, 100, None),
+            "/a/b/c/d"
+        );
+
+        let root = Path::new("/work/monorepo");
+        assert_eq!(
+            shorten_path_with(
+                "/work/monorepo/services/billing/src/lib.rs",
+                PathStyle::ProjectRelative,
+                60,
+                Some(root)
+            ),
+            "services/billing/src/lib.rs"
+        );
+        assert_eq!(
+            shorten_path_with(
+                "/work/monorepo/services/billing/src/handlers/invoices/lib.rs",
+                PathStyle::ProjectRelative,
+                30,
+                Some(root)
+            ),
+            "services/…/invoices/lib.rs"
+        );
+        assert_eq!(
+            shorten_path_with("/etc/hosts", PathStyle::ProjectRelative, 60, Some(root)),
+            "/etc/hosts"
+        );
+    }
+
+    #[test]
+    fn test_path_style_from_config_str() {
+        assert_eq!(
+            PathStyle::from_config_str("ellipsis"),
+            PathStyle::MiddleEllipsis
+        );
+        assert_eq!(
+            PathStyle::from_config_str("Relative"),
+            PathStyle::ProjectRelative
+        );
+        assert_eq!(PathStyle::from_config_str("none"), PathStyle::Full);
+        assert_eq!(PathStyle::from_config_str("unknown"), PathStyle::Initials);
+    }
+
+    #[test]
+    fn test_box_style_from_config_str() {
+        assert_eq!(BoxStyle::from_config_str("ascii"), BoxStyle::Ascii);
+        assert_eq!(BoxStyle::from_config_str("DOUBLE"), BoxStyle::Double);
+        assert_eq!(BoxStyle::from_config_str("square"), BoxStyle::Square);
+        assert_eq!(BoxStyle::from_config_str("none"), BoxStyle::None);
+        assert_eq!(BoxStyle::from_config_str("unknown"), BoxStyle::Rounded);
+    }
+
+    #[test]
+    fn test_box_style_frame() {
+        let lines = vec!["ab".to_string(), "abcd".to_string()];
+        assert_eq!(
+            BoxStyle::Ascii.frame(&lines),
+            vec!["+------+", "| ab   |", "| abcd |", "+------+"]
+        );
+        assert_eq!(BoxStyle::None.frame(&lines), lines);
+        assert_eq!(BoxStyle::None.rule(3), "");
+        assert_eq!(BoxStyle::Double.rule(3), "═══");
+    }
+
+    #[test]
+    fn test_argument_changes() {
+        let object = |value: Value| value.as_object().unwrap().clone();
+        let before =
+            object(serde_json::json!({"command": "rm -rf build", "timeout": 30, "cwd": "/tmp"}));
+        let after = object(
+            serde_json::json!({"command": "rm -rf build/cache", "cwd": "/tmp", "dry_run": true}),
+        );
+
+        assert_eq!(
+            argument_changes(&before, &after),
+            vec![
+                ('-', "command".to_string(), "rm -rf build".to_string()),
+                ('+', "command".to_string(), "rm -rf build/cache".to_string()),
+                ('+', "dry_run".to_string(), "true".to_string()),
+                ('-', "timeout".to_string(), "30".to_string()),
+            ]
+        );
+        assert!(argument_changes(&before, &before).is_empty());
+    }
 }
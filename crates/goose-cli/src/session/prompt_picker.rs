@@ -0,0 +1,144 @@
+//! An interactive picker for extension prompts, opened with `/prompts --pick [query]`.
+
+use goose::config::Config;
+use goose::utils::safe_truncate;
+
+pub const PROMPT_PICKER_KEY: &str = "GOOSE_CLI_PROMPT_PICKER";
+
+/// Room for the description next to the prompt name in the picker
+const MAX_HINT_CHARS: usize = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptItem {
+    pub extension: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl PromptItem {
+    fn haystack(&self) -> String {
+        format!("{}/{}", self.extension, self.name)
+    }
+}
+
+/// Whether plain `/prompts` opens the picker instead of printing the list
+pub fn picker_by_default() -> bool {
+    Config::global()
+        .get_param::<bool>(PROMPT_PICKER_KEY)
+        .unwrap_or(false)
+}
+
+fn is_word_start(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| matches!(c, '/' | '-' | '_' | ' ' | '.' | ':'))
+}
+
+/// Score `candidate` against `query` the way skim-like finders do: the query's characters must
+/// appear in order, and matches that are consecutive or start a word score higher. `None` means
+/// no match.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last_match: Option<usize> = None;
+
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..candidate.len()).find(|&i| candidate[i] == wanted)?;
+        score += 16;
+        if last_match.is_some_and(|last| last + 1 == found) {
+            score += 12;
+        } else if is_word_start(found.checked_sub(1).map(|i| candidate[i])) {
+            score += 10;
+        }
+        // Characters skipped since the previous match
+        score -= (found - last_match.map_or(0, |last| last + 1)) as i64;
+        last_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// The prompts matching `query`, best first; an empty query keeps them all in name order
+pub fn rank(items: &[PromptItem], query: &str) -> Vec<PromptItem> {
+    let mut ranked: Vec<(i64, &PromptItem)> = items
+        .iter()
+        .filter_map(|item| fuzzy_score(query, &item.haystack()).map(|score| (score, item)))
+        .collect();
+    ranked.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    ranked.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+/// Let the user choose one of `items` matching `query`; `None` if nothing matched or they
+/// cancelled
+pub fn pick_prompt(items: &[PromptItem], query: &str) -> std::io::Result<Option<PromptItem>> {
+    let ranked = rank(items, query);
+    if ranked.is_empty() {
+        return Ok(None);
+    }
+
+    let mut picker = cliclack::select("Pick a prompt (type to filter)").filter_mode();
+    for (index, item) in ranked.iter().enumerate() {
+        let hint = item
+            .description
+            .as_deref()
+            .map(|description| safe_truncate(description, MAX_HINT_CHARS))
+            .unwrap_or_default();
+        picker = picker.item(index, format!("{}  {}", item.name, item.extension), hint);
+    }
+
+    match picker.interact() {
+        Ok(index) => Ok(ranked.into_iter().nth(index)),
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(extension: &str, name: &str) -> PromptItem {
+        PromptItem {
+            extension: extension.to_string(),
+            name: name.to_string(),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("xyz", "developer/review").is_none());
+        assert!(fuzzy_score("wer", "developer/review").is_none());
+
+        // Consecutive and word-start matches beat scattered ones
+        let prefix = fuzzy_score("rev", "developer/review").unwrap();
+        let scattered = fuzzy_score("rev", "developer/refactor-everything").unwrap();
+        assert!(prefix > scattered);
+        assert!(fuzzy_score("REV", "developer/review").is_some());
+    }
+
+    #[test]
+    fn test_rank() {
+        let items = vec![
+            item("github", "create-issue"),
+            item("developer", "review"),
+            item("developer", "refactor"),
+        ];
+
+        let names = |ranked: Vec<PromptItem>| -> Vec<String> {
+            ranked.into_iter().map(|item| item.name).collect()
+        };
+        assert_eq!(
+            names(rank(&items, "")),
+            vec!["create-issue", "refactor", "review"]
+        );
+        assert_eq!(names(rank(&items, "rev")), vec!["review"]);
+        assert_eq!(names(rank(&items, "gh issue")), vec!["create-issue"]);
+        assert_eq!(names(rank(&items, "dev/re")), vec!["refactor", "review"]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use console::style;
+use goose::conversation::message::{Message, MessageContent};
+use rmcp::model::Role;
+
+use super::output;
+
+/// Longest pause between two replayed messages, so idle time in the original session
+/// (lunch breaks, waiting on approvals) doesn't stall the replay
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(10);
+
+/// Parse a replay speed such as "4x", "0.5x" or "2"
+pub fn parse_speed(s: &str) -> Result<f64, String> {
+    let trimmed = s.trim();
+    let number = trimmed.strip_suffix(['x', 'X']).unwrap_or(trimmed).trim();
+    match number.parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => Err(format!(
+            "invalid speed '{}', expected a positive multiplier like 2x",
+            s
+        )),
+    }
+}
+
+/// How long to wait before showing `next`, given when `previous` was created
+fn replay_delay(previous: &Message, next: &Message, speed: f64) -> Duration {
+    let gap = (next.created - previous.created).max(0) as f64;
+    Duration::from_secs_f64(gap / speed).min(MAX_REPLAY_GAP)
+}
+
+fn render_user_message(message: &Message, debug: bool) {
+    for content in &message.content {
+        match content {
+            MessageContent::Text(text) => {
+                println!("\n{} {}", style("( O)>").cyan().bold(), text.text)
+            }
+            _ => output::render_message(
+                &Message::new(message.role.clone(), message.created, vec![content.clone()]),
+                debug,
+            ),
+        }
+    }
+}
+
+/// Re-render a stored conversation, optionally paced at `speed` times its original timing
+pub async fn replay_messages(
+    messages: &[Message],
+    description: &str,
+    speed: Option<f64>,
+    debug: bool,
+) {
+    println!(
+        "{} {} ({} messages)",
+        style("Replaying session:").green().bold(),
+        description,
+        messages.len()
+    );
+
+    let mut previous: Option<&Message> = None;
+    for message in messages.iter().filter(|m| m.metadata.user_visible) {
+        if let (Some(speed), Some(previous)) = (speed, previous) {
+            let delay = replay_delay(previous, message, speed);
+            if !delay.is_zero() {
+                let waiting_on_agent = matches!(message.role, Role::Assistant);
+                if waiting_on_agent {
+                    output::show_thinking();
+                }
+                tokio::time::sleep(delay).await;
+                if waiting_on_agent {
+                    output::hide_thinking();
+                }
+            }
+        }
+
+        match message.role {
+            Role::User => render_user_message(message, debug),
+            Role::Assistant => output::render_message(message, debug),
+        }
+        previous = Some(message);
+    }
+
+    println!("\n{}", style("──────── End of replay ────────").dim());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("4x").unwrap(), 4.0);
+        assert_eq!(parse_speed("0.5X").unwrap(), 0.5);
+        assert_eq!(parse_speed("2").unwrap(), 2.0);
+        assert!(parse_speed("0x").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
+
+    #[test]
+    fn test_replay_delay() {
+        let first = Message::user().with_text("hi");
+        let mut second = Message::assistant().with_text("hello");
+
+        second.created = first.created + 4;
+        assert_eq!(replay_delay(&first, &second, 2.0), Duration::from_secs(2));
+
+        second.created = first.created + 3600;
+        assert_eq!(replay_delay(&first, &second, 1.0), MAX_REPLAY_GAP);
+
+        second.created = first.created - 5;
+        assert_eq!(replay_delay(&first, &second, 1.0), Duration::ZERO);
+    }
+}
@@ -0,0 +1,361 @@
+//! Problems noticed while a session starts, shown together once it has.
+
+use console::{style, StyledObject};
+use goose::agents::extension::McpAuthType;
+use goose::config::{Config, ExtensionConfig};
+use goose::oauth::registration::ClientRegistration;
+use goose::oauth::token_store::TokenKey;
+use goose::oauth::{sign_in_expiry, sign_in_hint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn heading(&self) -> &'static str {
+        match self {
+            Severity::Error => "errors",
+            Severity::Warning => "warnings",
+            Severity::Info => "notes",
+        }
+    }
+
+    fn paint<D>(&self, value: D) -> StyledObject<D> {
+        match self {
+            Severity::Error => style(value).red(),
+            Severity::Warning => style(value).yellow(),
+            Severity::Info => style(value).dim(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningCode {
+    ExtensionFailed,
+    MissingSecret,
+    EnvRestricted,
+    EnvInherited,
+    SignInExpiring,
+    InvalidEditMode,
+    SystemPromptOverride,
+}
+
+impl WarningCode {
+    pub const ALL: &'static [WarningCode] = &[
+        WarningCode::ExtensionFailed,
+        WarningCode::MissingSecret,
+        WarningCode::EnvRestricted,
+        WarningCode::EnvInherited,
+        WarningCode::SignInExpiring,
+        WarningCode::InvalidEditMode,
+        WarningCode::SystemPromptOverride,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningCode::ExtensionFailed => "E101",
+            WarningCode::MissingSecret => "W102",
+            WarningCode::EnvRestricted => "W103",
+            WarningCode::EnvInherited => "I104",
+            WarningCode::SignInExpiring => "W105",
+            WarningCode::InvalidEditMode => "W201",
+            WarningCode::SystemPromptOverride => "I202",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            WarningCode::ExtensionFailed => Severity::Error,
+            WarningCode::MissingSecret
+            | WarningCode::EnvRestricted
+            | WarningCode::SignInExpiring
+            | WarningCode::InvalidEditMode => Severity::Warning,
+            WarningCode::EnvInherited | WarningCode::SystemPromptOverride => Severity::Info,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            WarningCode::ExtensionFailed => "Extension failed to start",
+            WarningCode::MissingSecret => "Extension secret is not set",
+            WarningCode::EnvRestricted => "Extension started with a restricted environment",
+            WarningCode::EnvInherited => "Extension started with your full environment",
+            WarningCode::SignInExpiring => "Extension sign-in expired or about to expire",
+            WarningCode::InvalidEditMode => "Invalid EDIT_MODE",
+            WarningCode::SystemPromptOverride => "System prompt replaced from a file",
+        }
+    }
+
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            WarningCode::ExtensionFailed => {
+                "goose could not start the extension, so the session continues without its tools. \
+                 Common causes are a command that is not installed or not on PATH, a remote \
+                 server that is down or unreachable, a secret the extension needs that is not \
+                 set, or a timeout that is too short for a slow first start. Run the extension's \
+                 command yourself to see its output, check the URI of remote extensions, and \
+                 adjust or disable the extension with `goose configure`."
+            }
+            WarningCode::MissingSecret => {
+                "The extension lists a secret under env_keys, but it is neither in the \
+                 extension's envs nor stored in the keychain or config, so the extension starts \
+                 without it and will likely fail to authenticate. Set it with `goose configure` \
+                 (edit the extension) or export it as an environment variable before starting \
+                 goose."
+            }
+            WarningCode::EnvRestricted => {
+                "Stdio extensions only see an allowlist of your environment variables, plus the \
+                 ones configured for them, so secrets in your shell don't leak to every \
+                 extension. If an extension needs more, add the variable names to \
+                 GOOSE_EXTENSION_ENV_ALLOWLIST, set them in the extension's envs, or start goose \
+                 with --inherit-env to pass the whole environment."
+            }
+            WarningCode::EnvInherited => {
+                "The extension was started with every variable of your environment because \
+                 goose was started with --inherit-env. This is reported once so you know which \
+                 extensions can see your credentials."
+            }
+            WarningCode::SignInExpiring => {
+                "The extension signed in with OAuth, but its access token has run out or will \
+                 soon, and the server gave no refresh token to renew it with. Once it runs out \
+                 the server turns the extension's requests down. Run `goose auth login <name>` \
+                 to sign in again in the browser; sessions pick the new sign-in up when the \
+                 extension restarts."
+            }
+            WarningCode::InvalidEditMode => {
+                "EDIT_MODE selects the line editor key bindings and must be `emacs` or `vi`. \
+                 Any other value falls back to emacs. Fix it in config.yaml or unset the \
+                 EDIT_MODE environment variable."
+            }
+            WarningCode::SystemPromptOverride => {
+                "GOOSE_SYSTEM_PROMPT_FILE_PATH is set, so goose's built-in system prompt is \
+                 replaced with the contents of that file for this session. Unset it to go back \
+                 to the default prompt."
+            }
+        }
+    }
+
+    /// Look a code up case-insensitively, as typed on the command line
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|known| known.code().eq_ignore_ascii_case(code))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StartupWarning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct StartupWarnings {
+    warnings: Vec<StartupWarning>,
+}
+
+impl StartupWarnings {
+    pub fn push(&mut self, code: WarningCode, message: impl Into<String>) {
+        self.warnings.push(StartupWarning {
+            code,
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Warnings by severity, most severe first, in the order they were noticed
+    fn grouped(&self) -> Vec<(Severity, Vec<&StartupWarning>)> {
+        let mut groups: Vec<(Severity, Vec<&StartupWarning>)> = Vec::new();
+        let mut warnings: Vec<&StartupWarning> = self.warnings.iter().collect();
+        warnings.sort_by_key(|warning| warning.code.severity());
+        for warning in warnings {
+            match groups.last_mut() {
+                Some((severity, group)) if *severity == warning.code.severity() => {
+                    group.push(warning)
+                }
+                _ => groups.push((warning.code.severity(), vec![warning])),
+            }
+        }
+        groups
+    }
+
+    pub fn render(&self) {
+        if self.is_empty() {
+            return;
+        }
+        for (severity, group) in self.grouped() {
+            let heading = format!("{} {}", group.len(), severity.heading());
+            eprintln!("{}", severity.paint(heading).bold());
+            for warning in group {
+                let code = severity.paint(warning.code.code());
+                eprintln!("  {} {}", code, warning.message);
+            }
+        }
+        eprintln!(
+            "{}",
+            style("Run `goose doctor explain <code>` for details\n")
+                .dim()
+                .italic()
+        );
+    }
+}
+
+/// Secrets named in an extension's `env_keys` that neither its `envs` nor the config provide,
+/// and the secret of its `auth` if the secrets store doesn't have it
+pub fn missing_secrets(extension: &ExtensionConfig) -> Vec<String> {
+    let (envs, env_keys, auth) = match extension {
+        ExtensionConfig::Sse {
+            envs,
+            env_keys,
+            auth,
+            ..
+        }
+        | ExtensionConfig::StreamableHttp {
+            envs,
+            env_keys,
+            auth,
+            ..
+        } => (envs.get_env(), env_keys, auth.as_ref()),
+        ExtensionConfig::Stdio { envs, env_keys, .. } => (envs.get_env(), env_keys, None),
+        _ => return Vec::new(),
+    };
+    let config = Config::global();
+    env_keys
+        .iter()
+        .filter(|key| !envs.contains_key(*key))
+        .chain(auth.map(|auth| &auth.secret))
+        .filter(|key| {
+            config
+                .get(key, true)
+                .map(|value| value.is_null())
+                .unwrap_or(true)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Why an OAuth extension will need the user to sign in again soon, if it will
+pub fn sign_in_expiring(extension: &ExtensionConfig) -> Option<String> {
+    let ExtensionConfig::StreamableHttp {
+        name,
+        uri,
+        envs,
+        env_keys,
+        auth_type,
+        auth,
+        oauth_profile,
+        ..
+    } = extension
+    else {
+        return None;
+    };
+    if auth.is_some() || *auth_type != McpAuthType::AuthorizationCode {
+        return None;
+    }
+    let registration = ClientRegistration::from_extension(name, envs, env_keys);
+    let key = TokenKey::for_profile(uri, oauth_profile.as_deref())
+        .ok()?
+        .with_client(registration.as_ref().map(|r| r.client_id.as_str()));
+    let expiry = sign_in_expiry(&key)?;
+    Some(format!(
+        "The sign-in of extension '{}' {}; {}",
+        name,
+        expiry,
+        sign_in_hint(name)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goose::oauth::token_store::{set_token_store, MemoryStore, StoredToken, TokenStore};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_codes_are_unique_and_resolvable() {
+        let codes: HashSet<_> = WarningCode::ALL.iter().map(|code| code.code()).collect();
+        assert_eq!(codes.len(), WarningCode::ALL.len());
+        for code in WarningCode::ALL {
+            assert_eq!(WarningCode::from_code(code.code()), Some(*code));
+        }
+        assert_eq!(
+            WarningCode::from_code(" e101 "),
+            Some(WarningCode::ExtensionFailed)
+        );
+        assert_eq!(WarningCode::from_code("X999"), None);
+    }
+
+    #[test]
+    fn test_grouped_by_severity() {
+        let mut warnings = StartupWarnings::default();
+        warnings.push(WarningCode::EnvInherited, "inherit");
+        warnings.push(WarningCode::InvalidEditMode, "edit mode");
+        warnings.push(WarningCode::ExtensionFailed, "first");
+        warnings.push(WarningCode::ExtensionFailed, "second");
+
+        let groups = warnings.grouped();
+        let summary: Vec<(Severity, Vec<&str>)> = groups
+            .iter()
+            .map(|(severity, group)| {
+                (
+                    *severity,
+                    group.iter().map(|w| w.message.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Severity::Error, vec!["first", "second"]),
+                (Severity::Warning, vec!["edit mode"]),
+                (Severity::Info, vec!["inherit"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sign_in_expiring() {
+        let store = Arc::new(MemoryStore::default());
+        set_token_store(store.clone());
+        let uri = "https://docs.example.com/mcp";
+        let mut extension = ExtensionConfig::streamable_http("docs", uri, "Docs", 300u64);
+        assert_eq!(sign_in_expiring(&extension), None);
+
+        let token = |refresh_token: Option<&str>| {
+            let response = serde_json::json!({
+                "access_token": "access",
+                "token_type": "bearer",
+                "expires_in": 600,
+                "refresh_token": refresh_token,
+            });
+            StoredToken::new(
+                "client".to_string(),
+                serde_json::from_value(response).unwrap(),
+            )
+        };
+        let key = TokenKey::for_profile(uri, None).unwrap();
+        store.save(&key, &token(None)).unwrap();
+        let warning = sign_in_expiring(&extension).unwrap();
+        assert!(warning.contains("expires in"), "{}", warning);
+        assert!(warning.contains("goose auth login docs"), "{}", warning);
+
+        // Renewed without the user
+        store.save(&key, &token(Some("refresh"))).unwrap();
+        assert_eq!(sign_in_expiring(&extension), None);
+
+        store.save(&key, &token(None)).unwrap();
+        if let ExtensionConfig::StreamableHttp { auth_type, .. } = &mut extension {
+            *auth_type = McpAuthType::ClientCredentials;
+        }
+        assert_eq!(sign_in_expiring(&extension), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Optional status bar pinned to the bottom row of the terminal.
+
+use std::io::{IsTerminal, Write};
+
+use console::{measure_text_width, style, Term};
+use goose::config::Config;
+use goose::utils::safe_truncate;
+
+pub struct StatusInfo {
+    pub model: String,
+    pub mode: String,
+    pub total_tokens: usize,
+    pub context_limit: usize,
+    pub cost: Option<f64>,
+}
+
+pub fn status_line_enabled() -> bool {
+    std::env::var("GOOSE_CLI_STATUS_LINE")
+        .ok()
+        .map(|val| matches!(val.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or_else(|| {
+            Config::global()
+                .get_param::<bool>("GOOSE_CLI_STATUS_LINE")
+                .unwrap_or(false)
+        })
+}
+
+/// 1234 -> "1.2k", 128000 -> "128k"
+fn compact_count(count: usize) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=9_999 => format!("{:.1}k", count as f64 / 1_000.0),
+        10_000..=999_999 => format!("{}k", count / 1_000),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
+
+/// The plain text of the bar, cut to `width` columns
+pub fn format_status(info: &StatusInfo, width: usize) -> String {
+    let percentage = if info.context_limit == 0 {
+        0
+    } else {
+        ((info.total_tokens as f64 / info.context_limit as f64 * 100.0).round() as usize).min(100)
+    };
+
+    let mut fields = vec![
+        format!("model: {}", info.model),
+        format!("mode: {}", info.mode),
+        format!(
+            "context: {}% ({}/{})",
+            percentage,
+            compact_count(info.total_tokens),
+            compact_count(info.context_limit)
+        ),
+    ];
+    if let Some(cost) = info.cost {
+        fields.push(format!("cost: ${:.4}", cost));
+    }
+
+    let text = format!(" goose │ {} ", fields.join(" │ "));
+    if measure_text_width(&text) <= width {
+        format!("{:<width$}", text, width = width)
+    } else {
+        safe_truncate(&text, width)
+    }
+}
+
+/// Keeps the bottom row reserved while alive and gives it back to the terminal when dropped
+pub struct StatusLine {
+    rows: u16,
+}
+
+impl StatusLine {
+    /// Reserve the bottom row, if enabled and stdout is a terminal tall enough to spare it
+    pub fn start() -> Option<Self> {
+        if !status_line_enabled() || !std::io::stdout().is_terminal() {
+            return None;
+        }
+        let (rows, _) = Term::stdout().size_checked()?;
+        if rows < 5 {
+            return None;
+        }
+
+        // Scroll everything up a line first so the cursor isn't left on the reserved row, then
+        // limit scrolling to the rows above it. Setting the region homes the cursor, so it is
+        // saved and restored around that.
+        print!("\n\x1b[1A\x1b7\x1b[1;{}r\x1b8", rows - 1);
+        let _ = std::io::stdout().flush();
+        Some(Self { rows })
+    }
+
+    /// Redraw the bar, following the terminal if it was resized since the last turn
+    pub fn draw(&mut self, info: &StatusInfo) {
+        let Some((rows, cols)) = Term::stdout().size_checked() else {
+            return;
+        };
+        if rows != self.rows {
+            print!("\x1b7\x1b[1;{}r\x1b8", rows - 1);
+            self.rows = rows;
+        }
+
+        let text = format_status(info, cols as usize);
+        print!(
+            "\x1b7\x1b[{};1H\x1b[2K{}\x1b8",
+            rows,
+            style(text).reverse().dim()
+        );
+        let _ = std::io::stdout().flush();
+    }
+}
+
+impl Drop for StatusLine {
+    fn drop(&mut self) {
+        // Reset the scroll region and clear the bar
+        print!("\x1b7\x1b[r\x1b[{};1H\x1b[2K\x1b8", self.rows);
+        let _ = std::io::stdout().flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(cost: Option<f64>) -> StatusInfo {
+        StatusInfo {
+            model: "gpt-4o".to_string(),
+            mode: "smart_approve".to_string(),
+            total_tokens: 53_200,
+            context_limit: 128_000,
+            cost,
+        }
+    }
+
+    #[test]
+    fn test_compact_count() {
+        assert_eq!(compact_count(950), "950");
+        assert_eq!(compact_count(1_234), "1.2k");
+        assert_eq!(compact_count(128_000), "128k");
+        assert_eq!(compact_count(2_000_000), "2.0M");
+    }
+
+    #[test]
+    fn test_format_status() {
+        let text = format_status(&info(Some(0.01234)), 120);
+        assert_eq!(
+            text.trim_end(),
+            " goose │ model: gpt-4o │ mode: smart_approve │ context: 42% (53k/128k) │ cost: $0.0123"
+        );
+        assert_eq!(measure_text_width(&text), 120);
+
+        let text = format_status(&info(None), 120);
+        assert!(!text.contains("cost"));
+
+        let text = format_status(&info(None), 30);
+        assert!(measure_text_width(&text) <= 30);
+        assert!(text.starts_with(" goose │ model: gpt-4o"));
+    }
+}
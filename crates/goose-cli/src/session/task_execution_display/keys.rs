@@ -0,0 +1,198 @@
+//! The keys the task dashboard answers to while its tasks run.
+
+use tokio::sync::mpsc;
+
+/// A key the dashboard does something with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DashboardKey {
+    /// ↑ or `k`: focus the task above
+    Up,
+    /// ↓ or `j`: focus the task below
+    Down,
+    /// `c`: cancel the focused task
+    Cancel,
+    /// `r`: start the focused task over
+    Restart,
+    /// `i`: show as much of the focused task's output as fits, or go back to the last lines
+    Inspect,
+    /// `p`: start no more tasks until pressed again; the tasks already running finish
+    Pause,
+    /// Esc: focus whichever task printed last again
+    Follow,
+}
+
+impl DashboardKey {
+    /// The keys in `bytes` as a terminal sends them; others are ignored
+    pub fn parse(bytes: &[u8]) -> Vec<Self> {
+        let mut keys = Vec::new();
+        let mut rest = bytes;
+        while let Some((&byte, tail)) = rest.split_first() {
+            rest = tail;
+            let key = match byte {
+                0x1b => match rest {
+                    [b'[' | b'O', b'A', tail @ ..] => {
+                        rest = tail;
+                        Some(Self::Up)
+                    }
+                    [b'[' | b'O', b'B', tail @ ..] => {
+                        rest = tail;
+                        Some(Self::Down)
+                    }
+                    // Another escape sequence, e.g. another arrow
+                    [b'[' | b'O', _, tail @ ..] => {
+                        rest = tail;
+                        None
+                    }
+                    _ => Some(Self::Follow),
+                },
+                b'k' => Some(Self::Up),
+                b'j' => Some(Self::Down),
+                b'c' => Some(Self::Cancel),
+                b'r' => Some(Self::Restart),
+                b'i' => Some(Self::Inspect),
+                b'p' => Some(Self::Pause),
+                _ => None,
+            };
+            keys.extend(key);
+        }
+        keys
+    }
+}
+
+/// Reads the dashboard's keys from the terminal until dropped
+pub struct DashboardKeys {
+    keys: mpsc::UnboundedReceiver<DashboardKey>,
+    #[cfg(unix)]
+    _reader: Option<unix::Reader>,
+}
+
+impl DashboardKeys {
+    /// Start reading keys; when stdin isn't a terminal none ever come
+    pub fn start() -> Self {
+        #[cfg_attr(not(unix), allow(unused_variables))]
+        let (sender, keys) = mpsc::unbounded_channel();
+        Self {
+            keys,
+            #[cfg(unix)]
+            _reader: unix::Reader::start(sender)
+                .inspect_err(|e| tracing::debug!("Not reading dashboard keys: {}", e))
+                .ok(),
+        }
+    }
+
+    /// The next key pressed
+    pub async fn next(&mut self) -> Option<DashboardKey> {
+        self.keys.recv().await
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::io::{IsTerminal, Read};
+    use std::os::fd::AsFd;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+
+    use anyhow::{bail, Result};
+    use nix::errno::Errno;
+    use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+    use nix::sys::termios::{
+        tcgetattr, tcsetattr, LocalFlags, SetArg, SpecialCharacterIndices, Termios,
+    };
+    use tokio::sync::mpsc;
+
+    use super::DashboardKey;
+
+    /// How long the reading thread waits for a key before checking whether to stop, in ms
+    const POLL_MS: u16 = 100;
+
+    pub struct Reader {
+        stop: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+        /// How the terminal was set up before, to go back to
+        saved: Termios,
+    }
+
+    impl Reader {
+        pub fn start(keys: mpsc::UnboundedSender<DashboardKey>) -> Result<Self> {
+            let stdin = std::io::stdin();
+            if !stdin.is_terminal() {
+                bail!("stdin is not a terminal");
+            }
+            let input = std::fs::File::from(stdin.as_fd().try_clone_to_owned()?);
+            let saved = tcgetattr(stdin.as_fd())?;
+            let mut keypresses = saved.clone();
+            keypresses
+                .local_flags
+                .remove(LocalFlags::ICANON | LocalFlags::ECHO);
+            keypresses.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+            keypresses.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+            tcsetattr(stdin.as_fd(), SetArg::TCSANOW, &keypresses)?;
+
+            let stop = Arc::new(AtomicBool::new(false));
+            let stopping = stop.clone();
+            let thread = std::thread::spawn(move || {
+                let mut buffer = [0u8; 64];
+                while !stopping.load(Ordering::Acquire) {
+                    let mut fds = [PollFd::new(input.as_fd(), PollFlags::POLLIN)];
+                    match poll(&mut fds, PollTimeout::from(POLL_MS)) {
+                        Ok(0) | Err(Errno::EINTR) => continue,
+                        Ok(_) => {}
+                        Err(_) => return,
+                    }
+                    let read = match (&input).read(&mut buffer) {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => read,
+                    };
+                    for key in DashboardKey::parse(&buffer[..read]) {
+                        if keys.send(key).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+            Ok(Self {
+                stop,
+                thread: Some(thread),
+                saved,
+            })
+        }
+    }
+
+    impl Drop for Reader {
+        fn drop(&mut self) {
+            // Waited for, so no key meant for what comes next is taken
+            self.stop.store(true, Ordering::Release);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+            let _ = tcsetattr(std::io::stdin().as_fd(), SetArg::TCSANOW, &self.saved);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            DashboardKey::parse(b"\x1b[A\x1b[Bjkcrip"),
+            vec![
+                DashboardKey::Up,
+                DashboardKey::Down,
+                DashboardKey::Down,
+                DashboardKey::Up,
+                DashboardKey::Cancel,
+                DashboardKey::Restart,
+                DashboardKey::Inspect,
+                DashboardKey::Pause,
+            ]
+        );
+        // Esc on its own, and the arrows the dashboard doesn't use
+        assert_eq!(DashboardKey::parse(b"\x1b"), vec![DashboardKey::Follow]);
+        assert_eq!(DashboardKey::parse(b"\x1b[C\x1b[Dxq"), Vec::new());
+    }
+}
@@ -1,20 +1,38 @@
+use console::Term;
 use goose::agents::subagent_execution_tool::lib::TaskStatus;
 use goose::agents::subagent_execution_tool::notification_events::{
-    TaskExecutionNotificationEvent, TaskInfo,
+    TaskExecutionNotificationEvent, TaskExecutionStats, TaskInfo,
 };
+use goose::agents::subagent_execution_tool::task_usage::TaskUsage;
+use goose::recipe::TaskPriority;
 use goose::utils::safe_truncate;
 use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicBool, Ordering};
 
+mod keys;
 #[cfg(test)]
 mod tests;
 
+pub use keys::{DashboardKey, DashboardKeys};
+
 const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
 const MOVE_TO_PROGRESS_LINE: &str = "\x1b[4;1H";
 const CLEAR_TO_EOL: &str = "\x1b[K";
 const CLEAR_BELOW: &str = "\x1b[J";
 pub const TASK_EXECUTION_NOTIFICATION_TYPE: &str = "task_execution";
 
+/// Output lines kept per task for the detail pane
+const MAX_OUTPUT_LINES: usize = 500;
+/// Rows of output the detail pane shows at most
+const DETAIL_ROWS: usize = 8;
+/// The title, the progress line and the blank line under it
+const HEADER_ROWS: usize = 3;
+/// Rows the task list keeps however small the terminal
+const MIN_LIST_ROWS: usize = 3;
+const KEYS_HINT: &str = "↑↓ focus · c cancel · r restart · i inspect · p pause · esc follow";
+
 static INITIAL_SHOWN: AtomicBool = AtomicBool::new(false);
 
 fn format_result_data_for_display(result_data: &Value) -> String {
@@ -75,6 +93,14 @@ pub fn format_task_execution_notification(
                     Some(TASK_EXECUTION_NOTIFICATION_TYPE.to_string()),
                 )
             }
+            TaskExecutionNotificationEvent::TasksPlanned { .. } => {
+                let formatted_plan = format_tasks_planned_from_event(&event);
+                (
+                    formatted_plan,
+                    None,
+                    Some(TASK_EXECUTION_NOTIFICATION_TYPE.to_string()),
+                )
+            }
         });
     }
     None
@@ -92,17 +118,14 @@ fn format_tasks_update_from_event(event: &TaskExecutionNotificationEvent) -> Str
             display.push_str(MOVE_TO_PROGRESS_LINE);
         }
 
-        display.push_str(&format!(
-            "📊 Progress: {} total | ⏳ {} pending | 🏃 {} running | ✅ {} completed | ❌ {} failed", 
-            stats.total, stats.pending, stats.running, stats.completed, stats.failed
-        ));
+        display.push_str(&format_progress(stats));
         display.push_str(&format!("{}\n\n", CLEAR_TO_EOL));
 
         let mut sorted_tasks = tasks.clone();
-        sorted_tasks.sort_by(|a, b| a.id.cmp(&b.id));
+        sort_tasks(&mut sorted_tasks);
 
-        for task in sorted_tasks {
-            display.push_str(&format_task_display(&task));
+        for task in &sorted_tasks {
+            display.push_str(&format_task_display(task, &sorted_tasks));
         }
 
         display.push_str(CLEAR_BELOW);
@@ -116,6 +139,11 @@ fn format_tasks_complete_from_event(event: &TaskExecutionNotificationEvent) -> S
     if let TaskExecutionNotificationEvent::TasksComplete {
         stats,
         failed_tasks,
+        log_dir,
+        run_id,
+        artifacts,
+        artifacts_manifest,
+        reports,
     } = event
     {
         let mut summary = String::new();
@@ -125,7 +153,22 @@ fn format_tasks_complete_from_event(event: &TaskExecutionNotificationEvent) -> S
         summary.push_str(&format!("Total Tasks: {}\n", stats.total));
         summary.push_str(&format!("✅ Completed: {}\n", stats.completed));
         summary.push_str(&format!("❌ Failed: {}\n", stats.failed));
+        if stats.cancelled > 0 {
+            summary.push_str(&format!("🚫 Cancelled: {}\n", stats.cancelled));
+        }
+        if stats.stopped_by_policy > 0 {
+            summary.push_str(&format!(
+                "🛑 Not started (failure policy): {}\n",
+                stats.stopped_by_policy
+            ));
+        }
+        if stats.skipped > 0 {
+            summary.push_str(&format!("⏭️ Skipped: {}\n", stats.skipped));
+        }
         summary.push_str(&format!("📈 Success Rate: {:.1}%\n", stats.success_rate));
+        if let Some(usage) = &stats.usage {
+            summary.push_str(&format!("🪙 Usage: {}\n", format_usage(usage)));
+        }
 
         if !failed_tasks.is_empty() {
             summary.push_str("\n❌ Failed Tasks:\n");
@@ -134,9 +177,46 @@ fn format_tasks_complete_from_event(event: &TaskExecutionNotificationEvent) -> S
                 if let Some(error) = &task.error {
                     summary.push_str(&format!("     Error: {}\n", error));
                 }
+                if let Some(log_path) = &task.log_path {
+                    summary.push_str(&format!("     Log: {}\n", log_path));
+                }
+            }
+        }
+
+        if !artifacts.is_empty() {
+            summary.push_str("\n📦 Artifacts:\n");
+            for task in artifacts {
+                summary.push_str(&format!("   • {}\n", task.name));
+                for file in &task.files {
+                    summary.push_str(&format!("     {}\n", file));
+                }
+            }
+            if let Some(manifest) = artifacts_manifest {
+                summary.push_str(&format!("   Manifest: {}\n", manifest));
+            }
+        }
+
+        if !reports.is_empty() {
+            summary.push_str("\n🧾 Reports:\n");
+            for report in reports {
+                summary.push_str(&format!("   {}\n", report));
             }
         }
 
+        if let Some(log_dir) = log_dir {
+            summary.push_str(&format!(
+                "\n📂 Task logs: {} (follow one with `goose session tasks --tail <task_id>`)\n",
+                log_dir
+            ));
+        }
+
+        if let Some(run_id) = run_id.as_ref().filter(|_| stats.completed < stats.total) {
+            summary.push_str(&format!(
+                "\n🔁 Run the tasks that didn't complete again with `goose run --resume-tasks {}`\n",
+                run_id
+            ));
+        }
+
         summary.push_str("\n📝 Generating summary...\n");
         summary
     } else {
@@ -144,15 +224,174 @@ fn format_tasks_complete_from_event(event: &TaskExecutionNotificationEvent) -> S
     }
 }
 
-fn format_task_display(task: &TaskInfo) -> String {
-    let mut task_display = String::new();
+fn format_tasks_planned_from_event(event: &TaskExecutionNotificationEvent) -> String {
+    if let TaskExecutionNotificationEvent::TasksPlanned {
+        stats,
+        tasks,
+        stages,
+        estimated_concurrency,
+    } = event
+    {
+        let mut plan = String::new();
+        plan.push_str("🧪 Dry Run: nothing was executed\n");
+        plan.push_str("═══════════════════════════════\n\n");
+        plan.push_str(&format_progress(stats));
+        plan.push_str("\n\n");
+
+        let mut sorted_tasks = tasks.clone();
+        sort_tasks(&mut sorted_tasks);
+        for task in &sorted_tasks {
+            plan.push_str(&format_task_display(task, &sorted_tasks));
+        }
 
-    let status_icon = match task.status {
+        plan.push_str(&format!(
+            "🗂️ Stages: {} | ⚡ Estimated concurrency: {}\n",
+            stages, estimated_concurrency
+        ));
+        if stats.failed > 0 {
+            plan.push_str(&format!("❌ {} task(s) failed validation\n", stats.failed));
+        }
+        plan
+    } else {
+        String::new()
+    }
+}
+
+fn status_icon(status: &TaskStatus) -> &'static str {
+    match status {
         TaskStatus::Pending => "⏳",
         TaskStatus::Running => "🏃",
         TaskStatus::Completed => "✅",
         TaskStatus::Failed => "❌",
-    };
+        TaskStatus::Cancelled => "🚫",
+        TaskStatus::Skipped => "⏭️",
+    }
+}
+
+/// The icon of `task`, which sets apart the cancelled tasks the failure policy kept from starting
+/// and the tasks whose result came from the cache
+fn task_icon(task: &TaskInfo) -> &'static str {
+    if task.stopped_by_policy {
+        "🛑"
+    } else if task.cached {
+        "♻️"
+    } else {
+        status_icon(&task.status)
+    }
+}
+
+/// Sort `tasks` so each comes after the tasks it depends on, and by priority, the highest
+/// first, and id within a stage
+fn sort_tasks(tasks: &mut [TaskInfo]) {
+    let mut stages: HashMap<String, usize> = HashMap::new();
+    // A task's stage is one past its latest dependency's; settles in as many passes as the
+    // longest chain of dependencies
+    for _ in 0..tasks.len() {
+        let mut changed = false;
+        for task in tasks.iter() {
+            let stage = task
+                .depends_on
+                .iter()
+                .filter_map(|dependency| stages.get(dependency))
+                .map(|stage| stage + 1)
+                .max()
+                .unwrap_or(0);
+            if stages.insert(task.id.clone(), stage) != Some(stage) {
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    tasks.sort_by_cached_key(|task| (stages[&task.id], Reverse(task.priority), task.id.clone()));
+}
+
+/// What marks a task of `priority`; normal ones go unmarked
+fn format_priority(priority: TaskPriority) -> Option<String> {
+    match priority {
+        TaskPriority::Normal => None,
+        TaskPriority::High => Some(format!("🔺 {}", priority)),
+        TaskPriority::Low => Some(format!("🔻 {}", priority)),
+    }
+}
+
+/// The names of the tasks `task` depends on, as far as they're in `tasks`
+fn dependency_names(task: &TaskInfo, tasks: &[TaskInfo]) -> String {
+    task.depends_on
+        .iter()
+        .filter_map(|dependency| tasks.iter().find(|other| &other.id == dependency))
+        .map(|dependency| dependency.task_name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The tokens `usage` counts in and out, and their cost when the models' prices are known;
+/// a cost that leaves some of the tokens out is marked partial
+fn format_usage(usage: &TaskUsage) -> String {
+    let mut text = format!(
+        "{} in / {} out tokens",
+        usage.input_tokens, usage.output_tokens
+    );
+    if let Some(cost_usd) = usage.cost_usd {
+        text.push_str(&format!(", ${:.4}", cost_usd));
+        if usage.cost_partial {
+            text.push_str(" (partial)");
+        }
+    }
+    text
+}
+
+/// `secs` rounded to what's worth showing of an estimate: seconds under a minute, minutes and
+/// seconds under an hour, and hours and minutes past that
+fn format_eta(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn format_progress(stats: &TaskExecutionStats) -> String {
+    let mut progress = format!(
+        "📊 Progress: {} total | ⏳ {} pending | 🏃 {} running | ✅ {} completed | ❌ {} failed",
+        stats.total, stats.pending, stats.running, stats.completed, stats.failed
+    );
+    if stats.cancelled > 0 {
+        progress.push_str(&format!(" | 🚫 {} cancelled", stats.cancelled));
+    }
+    if stats.skipped > 0 {
+        progress.push_str(&format!(" | ⏭️ {} skipped", stats.skipped));
+    }
+    if let Some(on_failure) = &stats.on_failure {
+        progress.push_str(&format!(" | on_failure: {}", on_failure));
+    }
+    if stats.paused {
+        progress.push_str(" | ⏸️ paused, `goose session tasks --resume` to go on");
+    }
+    if let Some(usage) = &stats.usage {
+        progress.push_str(&format!(" | 🪙 {}", format_usage(usage)));
+    }
+    if let Some(eta_secs) = stats.eta_secs {
+        progress.push_str(&format!(" | 🏁 ~{} left", format_eta(eta_secs)));
+    }
+    if let Some(tasks_per_minute) = stats.tasks_per_minute {
+        progress.push_str(&format!(" | 🚀 {:.1} tasks/min", tasks_per_minute));
+    }
+    progress
+}
+
+/// A container id as short as the container runtimes show them
+fn short_container_id(container_id: &str) -> &str {
+    container_id.get(..12).unwrap_or(container_id)
+}
+
+/// How `task` of `tasks` is shown in the plain rendering
+fn format_task_display(task: &TaskInfo, tasks: &[TaskInfo]) -> String {
+    let mut task_display = String::new();
+
+    let status_icon = task_icon(task);
 
     task_display.push_str(&format!(
         "{} {} ({}){}\n",
@@ -170,6 +409,53 @@ fn format_task_display(task: &TaskInfo) -> String {
         task_display.push_str(&format!("   ⏱️  {:.1}s{}\n", duration_secs, CLEAR_TO_EOL));
     }
 
+    if let Some(priority) = format_priority(task.priority) {
+        task_display.push_str(&format!("   {} priority{}\n", priority, CLEAR_TO_EOL));
+    }
+
+    if task.cached {
+        task_display.push_str(&format!("   ♻️  Cached result{}\n", CLEAR_TO_EOL));
+    }
+
+    if let Some(container_id) = &task.container_id {
+        task_display.push_str(&format!(
+            "   🐳 Container: {}{}\n",
+            short_container_id(container_id),
+            CLEAR_TO_EOL
+        ));
+    }
+
+    if let Some(host) = &task.host {
+        task_display.push_str(&format!("   🖥️  Host: {}{}\n", host, CLEAR_TO_EOL));
+    }
+
+    if let Some(remaining_secs) = task.remaining_secs {
+        task_display.push_str(&format!(
+            "   ⌛ {:.0}s left{}\n",
+            remaining_secs, CLEAR_TO_EOL
+        ));
+    }
+
+    let after = dependency_names(task, tasks);
+    if !after.is_empty() {
+        task_display.push_str(&format!("   🔗 After: {}{}\n", after, CLEAR_TO_EOL));
+    }
+
+    if let Some(position) = task.queue_position {
+        task_display.push_str(&format!("   🕒 #{} in queue{}\n", position, CLEAR_TO_EOL));
+    }
+
+    if task.attempt > 1 {
+        task_display.push_str(&format!(
+            "   🔁 Attempt {} of {}{}\n",
+            task.attempt, task.max_attempts, CLEAR_TO_EOL
+        ));
+    }
+
+    if let Some(usage) = &task.usage {
+        task_display.push_str(&format!("   🪙 {}{}\n", format_usage(usage), CLEAR_TO_EOL));
+    }
+
     if matches!(task.status, TaskStatus::Running) && !task.current_output.trim().is_empty() {
         let processed_output = process_output_for_display(&task.current_output);
         if !processed_output.is_empty() {
@@ -186,7 +472,7 @@ fn format_task_display(task: &TaskInfo) -> String {
         }
     }
 
-    if matches!(task.status, TaskStatus::Failed) {
+    if matches!(task.status, TaskStatus::Failed | TaskStatus::Skipped) || task.stopped_by_policy {
         if let Some(error) = &task.error {
             let error_preview = safe_truncate(error, 80);
             task_display.push_str(&format!(
@@ -200,3 +486,334 @@ fn format_task_display(task: &TaskInfo) -> String {
     task_display.push_str(&format!("{}\n", CLEAR_TO_EOL));
     task_display
 }
+
+/// What a key asks to be done to a task, or to the run, which the session does through its
+/// agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskAction {
+    Cancel(String),
+    Restart(String),
+    Pause,
+    Resume,
+}
+
+/// The live view of a task run, redrawn in place below where it started
+#[derive(Default)]
+pub struct TaskDashboard {
+    stats: Option<TaskExecutionStats>,
+    tasks: Vec<TaskInfo>,
+    /// Everything each task printed, up to [`MAX_OUTPUT_LINES`]
+    output: HashMap<String, VecDeque<String>>,
+    /// The task in the detail pane: the last one to print, or else the first one running
+    focused: Option<String>,
+    /// The focus was moved with the keys, so it stays put
+    pinned: bool,
+    /// The detail pane shows as much of the output as fits, not just the last lines
+    inspecting: bool,
+    /// How the last key's action went
+    message: Option<String>,
+    /// Lines of the last frame, which the next one replaces
+    drawn: usize,
+}
+
+impl TaskDashboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the dashboard can be drawn, rather than printing updates as they arrive
+    pub fn is_supported() -> bool {
+        Term::stdout().is_term()
+    }
+
+    /// Take in a task execution notification, returning what to print for it
+    pub fn handle(&mut self, data: &Value) -> Option<String> {
+        let event = serde_json::from_value::<TaskExecutionNotificationEvent>(data.clone()).ok()?;
+        match event {
+            TaskExecutionNotificationEvent::LineOutput { task_id, output } => {
+                let lines = self.output.entry(task_id.clone()).or_default();
+                lines.extend(output.lines().map(str::to_string));
+                while lines.len() > MAX_OUTPUT_LINES {
+                    lines.pop_front();
+                }
+                if !self.pinned {
+                    self.focused = Some(task_id);
+                }
+            }
+            TaskExecutionNotificationEvent::TasksUpdate { stats, mut tasks } => {
+                sort_tasks(&mut tasks);
+                self.stats = Some(stats);
+                self.tasks = tasks;
+                let focus_done = !self.pinned
+                    && self
+                        .focused_task()
+                        .is_none_or(|task| !matches!(task.status, TaskStatus::Running));
+                if focus_done {
+                    if let Some(running) = self
+                        .tasks
+                        .iter()
+                        .find(|task| matches!(task.status, TaskStatus::Running))
+                    {
+                        self.focused = Some(running.id.clone());
+                    }
+                }
+            }
+            TaskExecutionNotificationEvent::TasksPlanned { .. } => {
+                return Some(format_tasks_planned_from_event(&event));
+            }
+            TaskExecutionNotificationEvent::TasksComplete { .. } => {
+                let mut display = self.redraw();
+                display.push_str(&format_tasks_complete_from_event(&event));
+                *self = Self::default();
+                return Some(display);
+            }
+        }
+        Some(self.redraw())
+    }
+
+    fn focused_task(&self) -> Option<&TaskInfo> {
+        let focused = self.focused.as_deref()?;
+        self.tasks.iter().find(|task| task.id == focused)
+    }
+
+    /// Whether a frame is on screen, which anything else printed has to go around
+    pub fn is_drawn(&self) -> bool {
+        self.drawn > 0
+    }
+
+    /// What clears the frame off the screen, for other output to take its place; the next
+    /// frame is drawn under that output
+    pub fn take_down(&mut self) -> String {
+        let display = match self.drawn {
+            0 => String::new(),
+            drawn => format!("\x1b[{}A\r{}", drawn, CLEAR_BELOW),
+        };
+        self.drawn = 0;
+        display
+    }
+
+    /// Take in a key pressed, returning what it asks to be done to a task, if anything
+    pub fn press(&mut self, key: DashboardKey) -> Option<TaskAction> {
+        let position = self
+            .focused_task()
+            .and_then(|focused| self.tasks.iter().position(|task| task.id == focused.id));
+        let focus = |dashboard: &mut Self, position: usize| {
+            dashboard.focused = dashboard.tasks.get(position).map(|task| task.id.clone());
+            dashboard.pinned = true;
+        };
+        self.message = None;
+        match key {
+            DashboardKey::Up => focus(self, position.map_or(0, |p| p.saturating_sub(1))),
+            DashboardKey::Down => focus(
+                self,
+                position.map_or(0, |p| (p + 1).min(self.tasks.len().saturating_sub(1))),
+            ),
+            DashboardKey::Follow => self.pinned = false,
+            DashboardKey::Inspect => self.inspecting = !self.inspecting,
+            DashboardKey::Cancel => {
+                return self.focused.clone().map(TaskAction::Cancel);
+            }
+            DashboardKey::Restart => {
+                return self.focused.clone().map(TaskAction::Restart);
+            }
+            DashboardKey::Pause => {
+                let paused = self.stats.as_ref().is_some_and(|stats| stats.paused);
+                return Some(if paused {
+                    TaskAction::Resume
+                } else {
+                    TaskAction::Pause
+                });
+            }
+        }
+        None
+    }
+
+    /// Show how `action` went on the next frame
+    pub fn report(&mut self, action: &TaskAction, outcome: Result<(), String>) {
+        let (verb, task_id) = match action {
+            TaskAction::Cancel(task_id) => ("Cancelling", task_id),
+            TaskAction::Restart(task_id) => ("Starting over", task_id),
+            TaskAction::Pause | TaskAction::Resume => {
+                self.message = Some(match outcome {
+                    Ok(()) if *action == TaskAction::Pause => {
+                        "Paused: no more tasks start until p is pressed again".to_string()
+                    }
+                    Ok(()) => "Resumed".to_string(),
+                    Err(e) => e,
+                });
+                return;
+            }
+        };
+        let name = self
+            .tasks
+            .iter()
+            .find(|task| &task.id == task_id)
+            .map_or(task_id.as_str(), |task| task.task_name.as_str());
+        self.message = Some(match outcome {
+            Ok(()) => format!("{} {}", verb, name),
+            Err(e) => e,
+        });
+    }
+
+    /// The frame drawn again, after a key changed what it shows
+    pub fn refresh(&mut self) -> String {
+        if self.is_drawn() {
+            self.redraw()
+        } else {
+            String::new()
+        }
+    }
+
+    /// The next frame, with what moves the cursor back over the last one
+    fn redraw(&mut self) -> String {
+        let (rows, columns) = Term::stdout().size();
+        let lines = self.frame(columns as usize, rows as usize);
+        let mut display = String::new();
+        if self.drawn > 0 {
+            display.push_str(&format!("\x1b[{}A\r{}", self.drawn, CLEAR_BELOW));
+        }
+        for line in &lines {
+            display.push_str(line);
+            display.push('\n');
+        }
+        self.drawn = lines.len();
+        display
+    }
+
+    /// The dashboard's lines, each cut to `width`, no more than fit a terminal `height` rows high
+    fn frame(&self, width: usize, height: usize) -> Vec<String> {
+        let fit =
+            |line: String| console::truncate_str(&line, width.saturating_sub(1), "…").into_owned();
+        // Keep a row free so the frame doesn't scroll the terminal
+        let rows = height.saturating_sub(1);
+        let mut lines = vec!["🎯 Task Execution Dashboard".to_string()];
+        lines.push(self.stats.as_ref().map(format_progress).unwrap_or_default());
+        lines.push(String::new());
+
+        let mut footer: Vec<String> = self.message.iter().cloned().collect();
+        footer.push(KEYS_HINT.to_string());
+        let focused = self.focused_task();
+        let detail_limit = if self.inspecting {
+            rows.saturating_sub(HEADER_ROWS + MIN_LIST_ROWS + footer.len() + 1)
+        } else {
+            DETAIL_ROWS
+        };
+        let detail: Vec<String> = match focused {
+            Some(task) => self.detail(task, detail_limit),
+            None => Vec::new(),
+        };
+        let detail_rows = if detail.is_empty() {
+            0
+        } else {
+            detail.len() + 1
+        };
+        let list_rows = rows
+            .saturating_sub(HEADER_ROWS + detail_rows + footer.len())
+            .max(MIN_LIST_ROWS);
+
+        let position = focused
+            .and_then(|focused| self.tasks.iter().position(|task| task.id == focused.id))
+            .unwrap_or(0);
+        let (start, shown) = if self.tasks.len() <= list_rows {
+            (0, self.tasks.len())
+        } else {
+            // One row says how many are out of view
+            let shown = list_rows - 1;
+            let start = position
+                .saturating_sub(shown / 2)
+                .min(self.tasks.len() - shown);
+            (start, shown)
+        };
+        for task in &self.tasks[start..start + shown] {
+            let marker = if Some(&task.id) == focused.map(|task| &task.id) {
+                "›"
+            } else {
+                " "
+            };
+            let mut duration = task
+                .duration_secs
+                .map(|secs| format!(" {:.1}s", secs))
+                .unwrap_or_default();
+            if let Some(remaining_secs) = task.remaining_secs {
+                duration.push_str(&format!(" ⌛ {:.0}s left", remaining_secs));
+            }
+            if task.attempt > 1 {
+                duration.push_str(&format!(" 🔁 {}/{}", task.attempt, task.max_attempts));
+            }
+            if let Some(priority) = format_priority(task.priority) {
+                duration.push_str(&format!(" {}", priority));
+            }
+            if let Some(usage) = &task.usage {
+                duration.push_str(&format!(" 🪙 {}", format_usage(usage)));
+            }
+            if let Some(container_id) = &task.container_id {
+                duration.push_str(&format!(" 🐳 {}", short_container_id(container_id)));
+            }
+            if let Some(host) = &task.host {
+                duration.push_str(&format!(" 🖥️ {}", host));
+            }
+            let after = dependency_names(task, &self.tasks);
+            if !after.is_empty() {
+                duration.push_str(&format!(" ⇠ {}", after));
+            }
+            let preview = match task.status {
+                TaskStatus::Failed | TaskStatus::Cancelled | TaskStatus::Skipped => {
+                    task.error.as_deref().unwrap_or_default().replace('\n', " ")
+                }
+                TaskStatus::Pending => task
+                    .queue_position
+                    .map(|position| format!("#{} in queue", position))
+                    .unwrap_or_default(),
+                _ => process_output_for_display(&task.current_output),
+            };
+            lines.push(fit(format!(
+                "{} {} {} ({}){}  {}",
+                marker,
+                task_icon(task),
+                task.task_name,
+                task.task_type,
+                duration,
+                preview
+            )));
+        }
+        let hidden = self.tasks.len() - shown;
+        if hidden > 0 {
+            lines.push(format!(
+                "  … {} more ({} above, {} below)",
+                hidden,
+                start,
+                hidden - start
+            ));
+        }
+
+        if let Some(task) = focused.filter(|_| !detail.is_empty()) {
+            lines.push(fit(format!("── {} ──────────", task.task_name)));
+            lines.extend(detail.into_iter().map(|line| fit(format!("  {}", line))));
+        }
+        lines.extend(footer.into_iter().map(fit));
+        // However small the terminal, drawing the frame never scrolls it
+        lines.truncate(rows);
+        lines
+    }
+
+    /// The last `limit` lines `task` printed, or how it ended
+    fn detail(&self, task: &TaskInfo, limit: usize) -> Vec<String> {
+        let ending = match task.status {
+            TaskStatus::Failed | TaskStatus::Cancelled | TaskStatus::Skipped => task.error.clone(),
+            TaskStatus::Completed => task
+                .result_data
+                .as_ref()
+                .map(format_result_data_for_display),
+            _ => None,
+        };
+        let mut lines: Vec<String> = match self.output.get(&task.id) {
+            Some(output) => output.iter().cloned().collect(),
+            None => task.current_output.lines().map(str::to_string).collect(),
+        };
+        if let Some(ending) = ending {
+            lines.extend(ending.lines().map(str::to_string));
+        }
+        let skip = lines.len().saturating_sub(limit);
+        lines.split_off(skip)
+    }
+}
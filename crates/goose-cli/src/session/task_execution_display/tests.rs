@@ -1,6 +1,6 @@
 use super::*;
 use goose::agents::subagent_execution_tool::notification_events::{
-    FailedTaskInfo, TaskCompletionStats, TaskExecutionStats,
+    ArtifactInfo, FailedTaskInfo, TaskCompletionStats, TaskExecutionStats,
 };
 use serde_json::json;
 
@@ -113,6 +113,17 @@ fn test_format_tasks_update_from_event() {
             task_metadata: "param=value".to_string(),
             error: None,
             result_data: None,
+            attempt: 1,
+            max_attempts: 1,
+            queue_position: None,
+            depends_on: Vec::new(),
+            remaining_secs: None,
+            stopped_by_policy: false,
+            usage: None,
+            container_id: None,
+            host: None,
+            priority: TaskPriority::Normal,
+            cached: false,
         },
         TaskInfo {
             id: "task-2".to_string(),
@@ -124,6 +135,17 @@ fn test_format_tasks_update_from_event() {
             task_metadata: "".to_string(),
             error: None,
             result_data: Some(json!({"result": "success"})),
+            attempt: 1,
+            max_attempts: 1,
+            queue_position: None,
+            depends_on: Vec::new(),
+            remaining_secs: None,
+            stopped_by_policy: false,
+            usage: None,
+            container_id: None,
+            host: None,
+            priority: TaskPriority::Normal,
+            cached: false,
         },
     ];
 
@@ -155,11 +177,17 @@ fn test_format_tasks_complete_from_event() {
         id: "task-3".to_string(),
         name: "failed-task".to_string(),
         error: Some("Connection timeout".to_string()),
+        log_path: None,
     }];
 
     let event = TaskExecutionNotificationEvent::TasksComplete {
         stats,
         failed_tasks,
+        log_dir: None,
+        run_id: None,
+        artifacts: Vec::new(),
+        artifacts_manifest: None,
+        reports: Vec::new(),
     };
     let result = format_tasks_complete_from_event(&event);
 
@@ -175,6 +203,56 @@ fn test_format_tasks_complete_from_event() {
     assert!(result.contains("📝 Generating summary..."));
 }
 
+#[test]
+fn test_format_tasks_complete_from_event_with_logs() {
+    let event = TaskExecutionNotificationEvent::TasksComplete {
+        stats: TaskCompletionStats::new(2, 1, 1),
+        failed_tasks: vec![FailedTaskInfo {
+            id: "task-2".to_string(),
+            name: "crawler".to_string(),
+            error: Some("Command failed".to_string()),
+            log_path: Some("/data/sessions/tasks/task-2.log".to_string()),
+        }],
+        log_dir: Some("/data/sessions/tasks".to_string()),
+        run_id: Some("20250101_120000_a1b2c3".to_string()),
+        artifacts: Vec::new(),
+        artifacts_manifest: None,
+        reports: Vec::new(),
+    };
+    let result = format_tasks_complete_from_event(&event);
+
+    assert!(result.contains("Log: /data/sessions/tasks/task-2.log"));
+    assert!(result.contains("📂 Task logs: /data/sessions/tasks"));
+    assert!(result.contains("goose run --resume-tasks 20250101_120000_a1b2c3"));
+}
+
+#[test]
+fn test_format_tasks_complete_lists_artifacts() {
+    let event = TaskExecutionNotificationEvent::TasksComplete {
+        stats: TaskCompletionStats::new(1, 1, 0),
+        failed_tasks: vec![],
+        log_dir: None,
+        run_id: None,
+        artifacts: vec![ArtifactInfo {
+            task_id: "task-1".to_string(),
+            name: "coverage".to_string(),
+            files: vec![
+                "artifacts/task-1/result.json".to_string(),
+                "artifacts/task-1/lcov.info".to_string(),
+            ],
+        }],
+        artifacts_manifest: Some("artifacts/manifest.json".to_string()),
+        reports: vec!["reports/junit.xml".to_string()],
+    };
+    let result = format_tasks_complete_from_event(&event);
+
+    assert!(result.contains(
+        "📦 Artifacts:\n   • coverage\n     artifacts/task-1/result.json\n     artifacts/task-1/lcov.info\n"
+    ));
+    assert!(result.contains("Manifest: artifacts/manifest.json"));
+    assert!(result.contains("🧾 Reports:\n   reports/junit.xml\n"));
+}
+
 #[test]
 fn test_format_tasks_complete_from_event_no_failures() {
     let stats = TaskCompletionStats::new(3, 3, 0);
@@ -183,10 +261,16 @@ fn test_format_tasks_complete_from_event_no_failures() {
     let event = TaskExecutionNotificationEvent::TasksComplete {
         stats,
         failed_tasks,
+        log_dir: None,
+        run_id: None,
+        artifacts: Vec::new(),
+        artifacts_manifest: None,
+        reports: Vec::new(),
     };
     let result = format_tasks_complete_from_event(&event);
 
     assert!(!result.contains("❌ Failed Tasks:"));
+    assert!(!result.contains("📂"));
     assert!(result.contains("📈 Success Rate: 100.0%"));
     assert!(result.contains("❌ Failed: 0"));
 }
@@ -203,14 +287,237 @@ fn test_format_task_display_running() {
         task_metadata: "input=file.txt,output=result.json".to_string(),
         error: None,
         result_data: None,
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
     };
 
-    let result = format_task_display(&task);
+    let result = format_task_display(&task, &[]);
 
     assert!(result.contains("🏃 data-processor (sub_recipe)"));
     assert!(result.contains("📋 Parameters: input=file.txt,output=result.json"));
     assert!(result.contains("⏱️  1.5s"));
     assert!(result.contains("💬 Processing data... ... Almost done..."));
+    assert!(!result.contains("🐳"));
+}
+
+#[test]
+fn test_format_task_display_in_container() {
+    let mut task = running_task(0);
+    task.container_id = Some("4f2a9c1e8b7d6a5f4e3d2c1b0a9f8e7d".to_string());
+
+    let result = format_task_display(&task, &[]);
+
+    assert!(result.contains("🐳 Container: 4f2a9c1e8b7d\u{1b}[K\n"));
+}
+
+#[test]
+fn test_format_task_display_on_a_worker_host() {
+    let mut task = running_task(0);
+    task.host = Some("me@build-1".to_string());
+
+    assert!(format_task_display(&task, &[]).contains("🖥️  Host: me@build-1"));
+}
+
+#[test]
+fn test_format_task_display_cached() {
+    let mut task = running_task(0);
+    task.status = TaskStatus::Completed;
+    task.cached = true;
+
+    let result = format_task_display(&task, &[]);
+    assert!(result.starts_with("♻️ recipe-00 (sub_recipe)"));
+    assert!(result.contains("♻️  Cached result"));
+    assert!(!format_task_display(&running_task(1), &[]).contains("Cached"));
+}
+
+#[test]
+fn test_format_task_display_retrying() {
+    let task = TaskInfo {
+        id: "task-1".to_string(),
+        status: TaskStatus::Running,
+        duration_secs: Some(0.5),
+        current_output: "".to_string(),
+        task_type: "sub_recipe".to_string(),
+        task_name: "fetcher".to_string(),
+        task_metadata: "".to_string(),
+        error: None,
+        result_data: None,
+        attempt: 2,
+        max_attempts: 3,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
+    };
+
+    let result = format_task_display(&task, &[]);
+
+    assert!(result.contains("🔁 Attempt 2 of 3"));
+}
+
+#[test]
+fn test_format_task_display_with_timeout() {
+    let mut task = running_task(1);
+    task.remaining_secs = Some(42.4);
+
+    assert!(format_task_display(&task, &[]).contains("⌛ 42s left"));
+
+    let mut dashboard = TaskDashboard::new();
+    dashboard.handle(&tasks_update(vec![task]));
+    assert!(dashboard
+        .frame(80, 12)
+        .iter()
+        .any(|line| line.contains("⌛ 42s left")));
+}
+
+#[test]
+fn test_tasks_stopped_by_policy_stand_out() {
+    let mut task = running_task(1);
+    task.status = TaskStatus::Cancelled;
+    task.stopped_by_policy = true;
+    task.error = Some("Not started: the fail_fast failure policy stopped the run".to_string());
+
+    let result = format_task_display(&task, &[]);
+    assert!(result.contains("🛑 recipe-01"));
+    assert!(result.contains("Not started: the fail_fast failure policy"));
+
+    let stats = TaskExecutionStats::new(2, 0, 0, 0, 1)
+        .with_cancelled(1)
+        .with_on_failure(Some("fail_fast".to_string()));
+    assert!(format_progress(&stats).contains("on_failure: fail_fast"));
+    assert!(!format_progress(&TaskExecutionStats::new(1, 1, 0, 0, 0)).contains("on_failure"));
+}
+
+#[test]
+fn test_task_usage_is_shown_with_a_batch_total() {
+    let mut task = running_task(1);
+    task.usage = Some(TaskUsage {
+        input_tokens: 1200,
+        output_tokens: 300,
+        cost_usd: Some(0.0125),
+        ..Default::default()
+    });
+    assert!(format_task_display(&task, &[]).contains("🪙 1200 in / 300 out tokens, $0.0125"));
+    assert!(!format_task_display(&running_task(2), &[]).contains("🪙"));
+
+    let total = TaskUsage {
+        input_tokens: 2000,
+        output_tokens: 500,
+        cost_usd: None,
+        ..Default::default()
+    };
+    let stats = TaskExecutionStats::new(2, 0, 2, 0, 0).with_usage(Some(total));
+    assert!(format_progress(&stats).ends_with("| 🪙 2000 in / 500 out tokens"));
+    let partial = TaskUsage {
+        cost_usd: Some(0.0125),
+        cost_partial: true,
+        ..total
+    };
+    let stats = TaskExecutionStats::new(2, 0, 2, 0, 0).with_usage(Some(partial));
+    assert!(format_progress(&stats).ends_with("tokens, $0.0125 (partial)"));
+
+    let event = TaskExecutionNotificationEvent::TasksComplete {
+        stats: TaskCompletionStats::new(2, 2, 0).with_usage(Some(total)),
+        failed_tasks: vec![],
+        log_dir: None,
+        run_id: None,
+        artifacts: Vec::new(),
+        artifacts_manifest: None,
+        reports: Vec::new(),
+    };
+    assert!(
+        format_tasks_complete_from_event(&event).contains("🪙 Usage: 2000 in / 500 out tokens\n")
+    );
+}
+
+#[test]
+fn test_format_task_display_queued() {
+    let task = TaskInfo {
+        id: "task-1".to_string(),
+        status: TaskStatus::Pending,
+        duration_secs: None,
+        current_output: "".to_string(),
+        task_type: "sub_recipe".to_string(),
+        task_name: "fetcher".to_string(),
+        task_metadata: "".to_string(),
+        error: None,
+        result_data: None,
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: Some(3),
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
+    };
+
+    let result = format_task_display(&task, &[]);
+
+    assert!(result.contains("⏳ fetcher (sub_recipe)"));
+    assert!(result.contains("🕒 #3 in queue"));
+}
+
+#[test]
+fn test_tasks_are_shown_after_their_dependencies() {
+    let mut build = running_task(1);
+    build.task_name = "build".to_string();
+    build.status = TaskStatus::Pending;
+    build.depends_on = vec!["task-02".to_string(), "task-03".to_string()];
+    let mut fetch = running_task(2);
+    fetch.task_name = "fetch".to_string();
+    let mut config = running_task(3);
+    config.task_name = "config".to_string();
+    let mut tasks = vec![build, fetch, config];
+
+    sort_tasks(&mut tasks);
+
+    let names: Vec<&str> = tasks.iter().map(|task| task.task_name.as_str()).collect();
+    assert_eq!(names, ["fetch", "config", "build"]);
+    assert!(format_task_display(&tasks[2], &tasks).contains("🔗 After: fetch, config"));
+    assert!(!format_task_display(&tasks[0], &tasks).contains("🔗"));
+}
+
+#[test]
+fn test_tasks_are_shown_by_priority_within_a_stage() {
+    let mut lint = running_task(1);
+    lint.task_name = "lint".to_string();
+    lint.priority = TaskPriority::Low;
+    let mut fetch = running_task(2);
+    fetch.task_name = "fetch".to_string();
+    let mut hotfix = running_task(3);
+    hotfix.task_name = "hotfix".to_string();
+    hotfix.priority = TaskPriority::High;
+    let mut deploy = running_task(4);
+    deploy.task_name = "deploy".to_string();
+    deploy.priority = TaskPriority::High;
+    deploy.depends_on = vec!["task-01".to_string()];
+    let mut tasks = vec![lint, fetch, hotfix, deploy];
+
+    sort_tasks(&mut tasks);
+
+    let names: Vec<&str> = tasks.iter().map(|task| task.task_name.as_str()).collect();
+    assert_eq!(names, ["hotfix", "fetch", "lint", "deploy"]);
+    assert!(format_task_display(&tasks[0], &tasks).contains("🔺 high priority"));
+    assert!(format_task_display(&tasks[2], &tasks).contains("🔻 low priority"));
+    assert!(!format_task_display(&tasks[1], &tasks).contains("priority"));
 }
 
 #[test]
@@ -225,9 +532,20 @@ fn test_format_task_display_completed() {
         task_metadata: "".to_string(),
         error: None,
         result_data: Some(json!({"status": "success", "count": 42})),
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
     };
 
-    let result = format_task_display(&task);
+    let result = format_task_display(&task, &[]);
 
     assert!(result.contains("✅ analyzer (text_instruction)"));
     assert!(result.contains("⏱️  3.2s"));
@@ -250,9 +568,20 @@ fn test_format_task_display_failed() {
                 .to_string(),
         ),
         result_data: None,
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
     };
 
-    let result = format_task_display(&task);
+    let result = format_task_display(&task, &[]);
 
     assert!(result.contains("❌ failing-task (sub_recipe)"));
     assert!(!result.contains("⏱️"));
@@ -272,9 +601,20 @@ fn test_format_task_display_pending() {
         task_metadata: "priority=high".to_string(),
         error: None,
         result_data: None,
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
     };
 
-    let result = format_task_display(&task);
+    let result = format_task_display(&task, &[]);
 
     assert!(result.contains("⏳ waiting-task (sub_recipe)"));
     assert!(result.contains("📋 Parameters: priority=high"));
@@ -296,9 +636,269 @@ fn test_format_task_display_empty_current_output() {
         task_metadata: "".to_string(),
         error: None,
         result_data: None,
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
     };
 
-    let result = format_task_display(&task);
+    let result = format_task_display(&task, &[]);
 
     assert!(!result.contains("💬"));
 }
+
+fn running_task(index: usize) -> TaskInfo {
+    TaskInfo {
+        id: format!("task-{:02}", index),
+        status: TaskStatus::Running,
+        duration_secs: Some(1.0),
+        current_output: String::new(),
+        task_type: "sub_recipe".to_string(),
+        task_name: format!("recipe-{:02}", index),
+        task_metadata: String::new(),
+        error: None,
+        result_data: None,
+        attempt: 1,
+        max_attempts: 1,
+        queue_position: None,
+        depends_on: Vec::new(),
+        remaining_secs: None,
+        stopped_by_policy: false,
+        usage: None,
+        container_id: None,
+        host: None,
+        priority: TaskPriority::Normal,
+        cached: false,
+    }
+}
+
+fn tasks_update(tasks: Vec<TaskInfo>) -> Value {
+    serde_json::to_value(TaskExecutionNotificationEvent::TasksUpdate {
+        stats: TaskExecutionStats {
+            total: tasks.len(),
+            pending: 0,
+            running: tasks.len(),
+            completed: 0,
+            failed: 0,
+            cancelled: 0,
+            skipped: 0,
+            on_failure: None,
+            usage: None,
+            eta_secs: None,
+            tasks_per_minute: None,
+            paused: false,
+        },
+        tasks,
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_dashboard_detail_pane() {
+    let mut dashboard = TaskDashboard::new();
+    dashboard.handle(&tasks_update(vec![running_task(1), running_task(2)]));
+    for line in 1..=12 {
+        dashboard.handle(&json!({
+            "subtype": "line_output",
+            "task_id": "task-02",
+            "output": format!("step {}", line)
+        }));
+    }
+
+    let frame = dashboard.frame(80, 40);
+    assert!(frame.iter().any(|line| line.starts_with("› 🏃 recipe-02")));
+    assert!(frame.iter().any(|line| line.contains("── recipe-02")));
+    // Only the latest output fits the pane
+    assert!(frame.iter().any(|line| line.trim() == "step 12"));
+    assert!(!frame.iter().any(|line| line.trim() == "step 4"));
+    assert!(frame
+        .iter()
+        .all(|line| console::measure_text_width(line) < 80));
+}
+
+#[test]
+fn test_dashboard_scrolls_to_focus() {
+    let mut dashboard = TaskDashboard::new();
+    dashboard.handle(&tasks_update((0..30).map(running_task).collect()));
+    dashboard.handle(&json!({
+        "subtype": "line_output",
+        "task_id": "task-25",
+        "output": "working"
+    }));
+
+    let frame = dashboard.frame(80, 20);
+    assert!(frame.len() < 20);
+    assert!(frame.iter().any(|line| line.starts_with("› 🏃 recipe-25")));
+    assert!(!frame.iter().any(|line| line.contains("recipe-00")));
+    assert!(frame.iter().any(|line| line.contains("more (")));
+
+    let first = dashboard.redraw();
+    let second = dashboard.redraw();
+    assert!(!first.starts_with("\x1b["));
+    assert!(second.starts_with(&format!("\x1b[{}A", first.lines().count())));
+}
+
+#[test]
+fn test_dry_run_shows_the_plan_without_a_dashboard() {
+    let mut fetch = running_task(1);
+    fetch.status = TaskStatus::Pending;
+    fetch.duration_secs = None;
+    let mut build = running_task(2);
+    build.status = TaskStatus::Failed;
+    build.duration_secs = None;
+    build.depends_on = vec![fetch.id.clone()];
+    build.error = Some("Missing required parameters: [\"target\"]".to_string());
+    let event = TaskExecutionNotificationEvent::TasksPlanned {
+        stats: TaskExecutionStats::new(2, 1, 0, 0, 1),
+        tasks: vec![build, fetch],
+        stages: 2,
+        estimated_concurrency: 1,
+    };
+
+    let plan = format_tasks_planned_from_event(&event);
+    assert!(plan.starts_with("🧪 Dry Run: nothing was executed"));
+    assert!(plan.find("recipe-01").unwrap() < plan.find("recipe-02").unwrap());
+    assert!(plan.contains("🔗 After: recipe-01"));
+    assert!(plan.contains("Missing required parameters"));
+    assert!(plan.contains("🗂️ Stages: 2 | ⚡ Estimated concurrency: 1\n"));
+    assert!(plan.contains("❌ 1 task(s) failed validation"));
+
+    let mut dashboard = TaskDashboard::new();
+    let shown = dashboard.handle(&serde_json::to_value(&event).unwrap());
+    assert_eq!(shown, Some(plan));
+    assert!(dashboard.stats.is_none() && dashboard.drawn == 0);
+}
+
+#[test]
+fn test_progress_shows_eta_and_throughput() {
+    assert_eq!(format_eta(42.4), "42s");
+    assert_eq!(format_eta(185.0), "3m 05s");
+    assert_eq!(format_eta(3720.0), "1h 02m");
+
+    let stats = TaskExecutionStats::new(40, 30, 4, 6, 0)
+        .with_eta_secs(Some(185.0))
+        .with_tasks_per_minute(Some(2.5));
+    assert!(format_progress(&stats).ends_with(" | 🏁 ~3m 05s left | 🚀 2.5 tasks/min"));
+    assert!(!format_progress(&TaskExecutionStats::new(2, 2, 0, 0, 0)).contains("🏁"));
+}
+
+#[test]
+fn test_paused_runs_say_how_to_resume() {
+    let stats = TaskExecutionStats::new(4, 3, 1, 0, 0).with_paused(true);
+    assert!(
+        format_progress(&stats).contains("| ⏸️ paused, `goose session tasks --resume` to go on")
+    );
+    assert!(!format_progress(&TaskExecutionStats::new(4, 3, 1, 0, 0)).contains("⏸️"));
+}
+
+#[test]
+fn test_dashboard_fits_the_terminal() {
+    let mut dashboard = TaskDashboard::new();
+    dashboard.handle(&tasks_update((0..30).map(running_task).collect()));
+    for line in 1..=100 {
+        dashboard.handle(&json!({
+            "subtype": "line_output",
+            "task_id": "task-03",
+            "output": format!("step {}", line)
+        }));
+    }
+    dashboard.press(DashboardKey::Inspect);
+
+    for height in [2, 6, 12, 24, 60] {
+        assert!(dashboard.frame(80, height).len() < height);
+    }
+    // Inspecting shows as much of the output as fits
+    let frame = dashboard.frame(80, 60);
+    assert!(frame.iter().any(|line| line.trim() == "step 60"));
+    assert!(frame.last().unwrap().contains("c cancel"));
+}
+
+#[test]
+fn test_dashboard_keys() {
+    let mut dashboard = TaskDashboard::new();
+    dashboard.handle(&tasks_update((0..3).map(running_task).collect()));
+    dashboard.handle(&json!({
+        "subtype": "line_output",
+        "task_id": "task-00",
+        "output": "working"
+    }));
+    assert_eq!(dashboard.press(DashboardKey::Down), None);
+    assert_eq!(dashboard.press(DashboardKey::Down), None);
+    assert_eq!(dashboard.press(DashboardKey::Down), None);
+
+    // The focus stays where the keys put it, whoever prints
+    dashboard.handle(&json!({
+        "subtype": "line_output",
+        "task_id": "task-00",
+        "output": "still working"
+    }));
+    let action = dashboard.press(DashboardKey::Cancel).unwrap();
+    assert_eq!(action, TaskAction::Cancel("task-02".to_string()));
+    dashboard.report(&action, Ok(()));
+    assert!(dashboard
+        .frame(80, 40)
+        .iter()
+        .any(|line| line == "Cancelling recipe-02"));
+
+    let action = dashboard.press(DashboardKey::Restart).unwrap();
+    dashboard.report(&action, Err("Task 'task-02' is not running".to_string()));
+    assert!(dashboard
+        .frame(80, 40)
+        .iter()
+        .any(|line| line == "Task 'task-02' is not running"));
+
+    dashboard.press(DashboardKey::Follow);
+    dashboard.handle(&json!({
+        "subtype": "line_output",
+        "task_id": "task-00",
+        "output": "done soon"
+    }));
+    assert_eq!(
+        dashboard.press(DashboardKey::Restart),
+        Some(TaskAction::Restart("task-00".to_string()))
+    );
+
+    // p pauses a run going, and resumes one paused
+    assert_eq!(
+        dashboard.press(DashboardKey::Pause),
+        Some(TaskAction::Pause)
+    );
+    dashboard.report(&TaskAction::Pause, Ok(()));
+    assert!(dashboard
+        .frame(80, 40)
+        .iter()
+        .any(|line| line.starts_with("Paused")));
+    dashboard.stats = dashboard.stats.take().map(|stats| stats.with_paused(true));
+    assert_eq!(
+        dashboard.press(DashboardKey::Pause),
+        Some(TaskAction::Resume)
+    );
+}
+
+#[test]
+fn test_other_output_takes_the_frame_down() {
+    let mut dashboard = TaskDashboard::new();
+    assert_eq!(dashboard.take_down(), "");
+    dashboard.handle(&tasks_update(vec![running_task(1)]));
+    assert!(dashboard.is_drawn());
+    let drawn = dashboard.drawn;
+
+    assert_eq!(
+        dashboard.take_down(),
+        format!("\x1b[{}A\r{}", drawn, CLEAR_BELOW)
+    );
+    assert!(!dashboard.is_drawn());
+    // The next frame starts under the output instead of moving back over it
+    assert!(!dashboard.refresh().starts_with("\x1b["));
+    assert!(!dashboard
+        .handle(&tasks_update(vec![running_task(1)]))
+        .unwrap()
+        .starts_with("\x1b["));
+}
@@ -491,6 +491,9 @@ impl ComputerControllerServer {
         let url = &params.url;
         let save_as = params.save_as;
 
+        goose::offline::check_url(format!("web_scrape of {}", url), url)
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_REQUEST, e.to_string(), None))?;
+
         // Fetch the content
         let response = self.http_client.get(url).send().await.map_err(|e| {
             ErrorData::new(
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use super::analyze::parser::ParserManager;
+use super::lang;
+
+/// Languages whose tree-sitter grammar is reliable enough to reject a write on syntax errors
+const SYNTAX_CHECKED_LANGUAGES: &[&str] = &["python", "rust", "go", "java", "javascript"];
+
+/// Check that content is plausible for the file's extension before it is written.
+///
+/// Returns a description of the first problem found, which the text editor reports back to the
+/// model as a failed tool call so it can fix the content instead of leaving a broken file.
+/// Set `GOOSE_DEVELOPER_VALIDATE_WRITES=false` to turn this off.
+pub fn validate_content(path: &Path, content: &str) -> Result<(), String> {
+    if std::env::var("GOOSE_DEVELOPER_VALIDATE_WRITES")
+        .map(|v| v.eq_ignore_ascii_case("false"))
+        .unwrap_or(false)
+    {
+        return Ok(());
+    }
+    if content.trim().is_empty() {
+        return Ok(());
+    }
+
+    match lang::get_language_identifier(path) {
+        "json" => validate_json(content),
+        "yaml" => validate_yaml(content),
+        language if SYNTAX_CHECKED_LANGUAGES.contains(&language) => {
+            validate_syntax(content, language)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_json(content: &str) -> Result<(), String> {
+    serde_json::from_str::<serde_json::Value>(content)
+        .map(|_| ())
+        .map_err(|e| format!("Content is not valid JSON: {}", e))
+}
+
+/// Every document of a YAML stream must parse
+fn validate_yaml(content: &str) -> Result<(), String> {
+    use serde::Deserialize;
+
+    for document in serde_yaml::Deserializer::from_str(content) {
+        serde_yaml::Value::deserialize(document)
+            .map_err(|e| format!("Content is not valid YAML: {}", e))?;
+    }
+    Ok(())
+}
+
+fn validate_syntax(content: &str, language: &str) -> Result<(), String> {
+    let Ok(tree) = ParserManager::new().parse(content, language) else {
+        return Ok(());
+    };
+    let root = tree.root_node();
+    if !root.has_error() {
+        return Ok(());
+    }
+
+    let mut cursor = root.walk();
+    loop {
+        let node = cursor.node();
+        if node.is_error() || node.is_missing() {
+            let position = node.start_position();
+            return Err(format!(
+                "Content has a {} syntax error near line {}, column {}",
+                language,
+                position.row + 1,
+                position.column + 1
+            ));
+        }
+        // Descend only into subtrees that contain the error
+        if node.has_error() && cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return Err(format!("Content has a {} syntax error", language));
+            }
+        }
+    }
+}
+
+/// Check an edit of a file, given its content `before` the edit (`None` for a file the edit
+/// creates) and `after` it.
+///
+/// Only edits that break a file valid before them are refused, so a file that is already broken
+/// can still be fixed one edit at a time.
+pub fn validate_edit(path: &Path, before: Option<&str>, after: &str) -> Result<(), String> {
+    if before.is_some_and(|before| validate_content(path, before).is_err()) {
+        return Ok(());
+    }
+    validate_content(path, after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_json_validation() {
+        let path = PathBuf::from("config.json");
+        assert!(validate_content(&path, "{\"a\": [1, 2]}\n").is_ok());
+        let err = validate_content(&path, "a: 1\nb: 2\n").unwrap_err();
+        assert!(err.contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_yaml_validation() {
+        let path = PathBuf::from("config.yaml");
+        assert!(validate_content(&path, "a:\n  b: 1\n").is_ok());
+        // JSON is a subset of YAML, so it is accepted
+        assert!(validate_content(&path, "{\"a\": 1}\n").is_ok());
+        assert!(validate_content(&path, "a: 1\n---\nb: 2\n").is_ok());
+        assert!(validate_content(&path, "a:\n\tb: 1\n").is_err());
+        let err = validate_content(&path, "a: [1, 2\nb: 3\n").unwrap_err();
+        assert!(err.contains("not valid YAML"));
+    }
+
+    #[test]
+    fn test_python_indentation_styles() {
+        let path = PathBuf::from("script.py");
+        assert!(validate_content(&path, "def f():\n    return 1\n").is_ok());
+        // Each block may pick its own indentation, and strings may hold tabs
+        assert!(validate_content(
+            &path,
+            "def f():\n    x = 1\n\ndef g():\n\treturn 2\n\ns = \"\"\"\n \tindented\n\"\"\"\n"
+        )
+        .is_ok());
+        let err = validate_content(&path, "def f(:\n    return 1\n").unwrap_err();
+        assert!(err.contains("python syntax error"));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let path = PathBuf::from("main.rs");
+        assert!(validate_content(&path, "fn main() {\n    println!(\"hi\");\n}\n").is_ok());
+        let err = validate_content(&path, "fn main() {\n    let x = ;\n}\n").unwrap_err();
+        assert!(err.contains("rust syntax error"));
+    }
+
+    #[test]
+    fn test_edits_of_broken_files_are_allowed() {
+        let path = PathBuf::from("config.json");
+        assert!(validate_edit(&path, Some("{\"a\": 1}"), "{\"a\": 1,}").is_err());
+        assert!(validate_edit(&path, None, "{\"a\": 1,}").is_err());
+        assert!(validate_edit(&path, Some("{\"a\": 1,,}"), "{\"a\": 1,}").is_ok());
+    }
+
+    #[test]
+    fn test_unknown_extension_is_not_checked() {
+        let path = PathBuf::from("notes.txt");
+        assert!(validate_content(&path, "{ not json").is_ok());
+    }
+}
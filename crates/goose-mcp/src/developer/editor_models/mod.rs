@@ -83,6 +83,12 @@ pub fn create_editor_model() -> Option<EditorModel> {
         return None;
     }
 
+    // Offline, edits are made without an editor model that isn't on this machine
+    if let Err(e) = goose::offline::check_url(format!("The editor model at {}", host), &host) {
+        tracing::warn!("{}", e);
+        return None;
+    }
+
     // Determine which editor to use based on the host
     if host.contains("relace.run") {
         Some(EditorModel::Relace(RelaceEditor::new(api_key, host, model)))
@@ -1,4 +1,5 @@
 pub mod analyze;
+mod content_validation;
 mod editor_models;
 mod goose_hints;
 mod lang;
@@ -11,6 +11,7 @@ use url::Url;
 
 use rmcp::model::{Content, ErrorCode, ErrorData, Role};
 
+use super::content_validation;
 use super::editor_models::EditorModel;
 use super::lang;
 use super::shell::normalize_line_endings;
@@ -119,6 +120,12 @@ fn validate_diff_size(diff_content: &str) -> Result<(), ErrorData> {
     Ok(())
 }
 
+/// Refuses an edit that breaks a file which was valid before it, so the model can correct it
+fn validate_edit(path: &Path, before: Option<&str>, after: &str) -> Result<(), ErrorData> {
+    content_validation::validate_edit(path, before, after)
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))
+}
+
 /// Counts line changes from the diff content
 fn count_line_changes(diff_content: &str) -> (usize, usize) {
     let lines_added = diff_content
@@ -222,6 +229,9 @@ fn apply_single_patch(
 
     // Save history before modifying
     let file_existed = file_path.exists();
+    let before = file_existed
+        .then(|| std::fs::read_to_string(&file_path).ok())
+        .flatten();
     if file_existed {
         save_file_history(&file_path, file_history)?;
     }
@@ -256,6 +266,29 @@ fn apply_single_patch(
         ),
     })?;
 
+    // Put back a file the patch broke, with the history saved for it
+    if let Ok(after) = std::fs::read_to_string(&file_path) {
+        if let Err(e) = validate_edit(&file_path, before.as_deref(), &after) {
+            match &before {
+                Some(before) => std::fs::write(&file_path, before),
+                None => std::fs::remove_file(&file_path),
+            }
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to restore '{}': {}", file_path.display(), e),
+                    None,
+                )
+            })?;
+            if file_existed {
+                if let Some(history) = file_history.lock().unwrap().get_mut(&file_path) {
+                    history.pop();
+                }
+            }
+            return Err(e);
+        }
+    }
+
     if !success {
         // Collect information about failed hunks for better error reporting
         let hunk_count = patch.hunks.len();
@@ -678,6 +711,10 @@ pub async fn text_editor_write(path: &PathBuf, file_text: &str) -> Result<Vec<Co
         normalized_text.push('\n');
     }
 
+    // Refuse to write content that is broken for its file type, so the model can correct it
+    content_validation::validate_content(path, &normalized_text)
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?;
+
     // Write to the file
     std::fs::write(path, &normalized_text) // Write the potentially modified text
         .map_err(|e| {
@@ -766,6 +803,7 @@ pub async fn text_editor_replace(
             Ok(updated_content) => {
                 // Write the updated content directly
                 let normalized_content = normalize_line_endings(&updated_content);
+                validate_edit(path, Some(&content), &normalized_content)?;
                 std::fs::write(path, &normalized_content).map_err(|e| {
                     ErrorData::new(
                         ErrorCode::INTERNAL_ERROR,
@@ -807,11 +845,13 @@ pub async fn text_editor_replace(
         return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "'old_str' must appear exactly once in the file, but it does not appear in the file. Make sure the string exactly matches existing file content, including whitespace!".to_string(), None));
     }
 
+    let new_content = content.replace(old_str, new_str);
+    let normalized_content = normalize_line_endings(&new_content);
+    validate_edit(path, Some(&content), &normalized_content)?;
+
     // Save history for undo (original behavior - after validation)
     save_file_history(path, file_history)?;
 
-    let new_content = content.replace(old_str, new_str);
-    let normalized_content = normalize_line_endings(&new_content);
     std::fs::write(path, &normalized_content).map_err(|e| {
         ErrorData::new(
             ErrorCode::INTERNAL_ERROR,
@@ -903,9 +943,6 @@ pub async fn text_editor_insert(
         )
     })?;
 
-    // Save history for undo
-    save_file_history(path, file_history)?;
-
     let lines: Vec<&str> = content.lines().collect();
     let total_lines = lines.len();
 
@@ -951,6 +988,10 @@ pub async fn text_editor_insert(
     } else {
         normalized_content
     };
+    validate_edit(path, Some(&content), &final_content)?;
+
+    // Save history for undo
+    save_file_history(path, file_history)?;
 
     std::fs::write(path, &final_content).map_err(|e| {
         ErrorData::new(
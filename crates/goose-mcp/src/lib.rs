@@ -13,9 +13,11 @@ pub mod developer;
 pub mod mcp_server_runner;
 mod memory;
 pub mod tutorial;
+pub mod web;
 
 pub use autovisualiser::AutoVisualiserRouter;
 pub use computercontroller::ComputerControllerServer;
 pub use developer::rmcp_developer::DeveloperServer;
 pub use memory::MemoryServer;
 pub use tutorial::TutorialServer;
+pub use web::WebServer;
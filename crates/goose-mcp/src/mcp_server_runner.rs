@@ -1,5 +1,6 @@
 use crate::{
     AutoVisualiserRouter, ComputerControllerServer, DeveloperServer, MemoryServer, TutorialServer,
+    WebServer,
 };
 use anyhow::{anyhow, Result};
 use rmcp::{transport::stdio, ServiceExt};
@@ -23,6 +24,7 @@ pub async fn run_mcp_server(name: &str) -> Result<()> {
         "developer" => serve_and_wait(DeveloperServer::new()).await,
         "memory" => serve_and_wait(MemoryServer::new()).await,
         "tutorial" => serve_and_wait(TutorialServer::new()).await,
+        "web" => serve_and_wait(WebServer::new()).await,
         _ => {
             tracing::warn!("Unknown MCP server name: {}", name);
             Err(anyhow!("Unknown MCP server name: {}", name))
@@ -0,0 +1,752 @@
+//! A small HTML to markdown converter that keeps the main content of a page.
+
+use url::Url;
+
+/// Elements whose content is never text for the reader
+const RAW_TEXT_TAGS: &[&str] = &["script", "style", "noscript", "textarea", "template"];
+
+/// Elements skipped entirely, along with everything inside them
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "iframe", "canvas", "object",
+    "button", "select", "form",
+];
+
+/// Elements that are boilerplate when extracting the main content of a page
+const BOILERPLATE_TAGS: &[&str] = &["nav", "header", "footer", "aside"];
+
+/// `class` or `id` fragments that mark boilerplate when extracting the main content
+const BOILERPLATE_HINTS: &[&str] = &[
+    "nav",
+    "menu",
+    "footer",
+    "sidebar",
+    "cookie",
+    "banner",
+    "advert",
+    "share",
+    "social",
+    "related",
+    "comment",
+    "breadcrumb",
+    "popup",
+    "newsletter",
+];
+
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Text(String),
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    Close(String),
+}
+
+impl Token {
+    pub(crate) fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            Token::Open { attrs, .. } => attrs
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Decode the character references in `text`
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                "copy" => Some('©'),
+                "reg" => Some('®'),
+                "trade" => Some('™'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|n| n.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parse_attrs(source: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/' || c == '>')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (raw, remaining) = match after_eq.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after_eq[1..];
+                    let end = body.find(quote).unwrap_or(body.len());
+                    (&body[..end], body.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_eq
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after_eq.len());
+                    (&after_eq[..end], &after_eq[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        } else if name.is_empty() {
+            // A stray `/` or other character that can't start an attribute
+            rest = rest.get(1..).unwrap_or("");
+        }
+
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+    attrs
+}
+
+/// Split HTML into text and tags; comments and doctypes are dropped, and the contents of
+/// script-like elements are skipped without being parsed as markup
+pub(crate) fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..start])));
+        }
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .find("-->")
+                .map(|end| &comment[end + 3..])
+                .unwrap_or("");
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+            continue;
+        }
+        if let Some(close) = rest.strip_prefix("</") {
+            let end = close.find('>').unwrap_or(close.len());
+            let name = close[..end].trim().to_lowercase();
+            tokens.push(Token::Close(name));
+            rest = close.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        if !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            tokens.push(Token::Text("<".to_string()));
+            rest = &rest[1..];
+            continue;
+        }
+
+        // Find the end of the tag, skipping `>` inside quoted attribute values
+        let mut quote = None;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices().skip(1) {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let inner = &rest[1..end];
+        rest = rest.get(end + 1..).unwrap_or("");
+
+        let name_end = inner
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(inner.len());
+        let name = inner[..name_end].to_lowercase();
+        let self_closing = inner.ends_with('/') || VOID_TAGS.contains(&name.as_str());
+        let attrs = parse_attrs(inner[name_end..].trim_end_matches('/'));
+
+        if RAW_TEXT_TAGS.contains(&name.as_str()) && !self_closing {
+            let closing = format!("</{}", name);
+            let body_end = rest.to_lowercase().find(&closing).unwrap_or(rest.len());
+            let body = &rest[..body_end];
+            rest = &rest[body_end..];
+            tokens.push(Token::Open {
+                name: name.clone(),
+                attrs,
+                self_closing,
+            });
+            if !body.is_empty() {
+                tokens.push(Token::Text(body.to_string()));
+            }
+            continue;
+        }
+
+        tokens.push(Token::Open {
+            name,
+            attrs,
+            self_closing,
+        });
+    }
+    tokens
+}
+
+/// The index of the token that closes the element opened at `open`, or the end of the tokens
+fn matching_close(tokens: &[Token], open: usize) -> usize {
+    let Token::Open { name, .. } = &tokens[open] else {
+        return open;
+    };
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Open {
+                name: n,
+                self_closing: false,
+                ..
+            } if n == name => depth += 1,
+            Token::Close(n) if n == name => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+fn is_boilerplate(token: &Token) -> bool {
+    let Token::Open { name, .. } = token else {
+        return false;
+    };
+    if BOILERPLATE_TAGS.contains(&name.as_str()) {
+        return true;
+    }
+    if token.attr("role").is_some_and(|role| {
+        matches!(
+            role,
+            "navigation" | "banner" | "contentinfo" | "complementary"
+        )
+    }) || token.attr("aria-hidden") == Some("true")
+    {
+        return true;
+    }
+    let hints = format!(
+        "{} {}",
+        token.attr("class").unwrap_or(""),
+        token.attr("id").unwrap_or("")
+    )
+    .to_lowercase();
+    hints
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .any(|word| BOILERPLATE_HINTS.contains(&word))
+}
+
+/// The range of tokens holding the main content: the first `<article>`, then `<main>` or
+/// `role="main"`, then `<body>`, falling back to everything
+fn main_content(tokens: &[Token]) -> (usize, usize) {
+    let find = |pred: &dyn Fn(&Token) -> bool| {
+        tokens
+            .iter()
+            .position(|token| matches!(token, Token::Open { .. }) && pred(token))
+    };
+    let is = |tag: &'static str| move |token: &Token| matches!(token, Token::Open { name, .. } if name == tag);
+
+    let start = find(&is("article"))
+        .or_else(|| find(&is("main")))
+        .or_else(|| find(&|token| token.attr("role") == Some("main")))
+        .or_else(|| find(&is("body")));
+    match start {
+        Some(start) => (start + 1, matching_close(tokens, start)),
+        None => (0, tokens.len()),
+    }
+}
+
+struct Converter<'a> {
+    out: String,
+    base_url: Option<&'a Url>,
+    /// Open lists: whether each is ordered, and the number of the next item
+    lists: Vec<(bool, usize)>,
+    /// Open links: their target and where their text starts in `out`
+    links: Vec<(Option<String>, usize)>,
+    /// Where each open blockquote starts in `out`
+    quotes: Vec<usize>,
+    pre_depth: usize,
+    /// Just after the opening fence of a code block, where a language may still be inserted
+    fence_at: Option<usize>,
+    /// Header cells in the current table row, to underline the row when it closes
+    header_cells: usize,
+}
+
+impl<'a> Converter<'a> {
+    fn new(base_url: Option<&'a Url>) -> Self {
+        Self {
+            out: String::new(),
+            base_url,
+            lists: Vec::new(),
+            links: Vec::new(),
+            quotes: Vec::new(),
+            pre_depth: 0,
+            fence_at: None,
+            header_cells: 0,
+        }
+    }
+
+    fn resolve(&self, href: &str) -> Option<String> {
+        let href = href.trim();
+        if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+            return None;
+        }
+        match self.base_url {
+            Some(base) => base.join(href).ok().map(String::from),
+            None => Some(href.to_string()),
+        }
+    }
+
+    fn newline(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn block(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.pre_depth > 0 {
+            self.out.push_str(text);
+            return;
+        }
+        let mut words = text.split_whitespace().peekable();
+        if words.peek().is_none() {
+            if !text.is_empty() && !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+                self.out.push(' ');
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace)
+            && !self.out.is_empty()
+            && !self.out.ends_with([' ', '\n'])
+        {
+            self.out.push(' ');
+        }
+        let joined: Vec<&str> = words.collect();
+        self.out.push_str(&joined.join(" "));
+        if text.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn open(&mut self, token: &Token) {
+        let Token::Open { name, .. } = token else {
+            return;
+        };
+        match name.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.block();
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.out.push_str(&format!("{} ", "#".repeat(level)));
+            }
+            "p" | "div" | "section" | "article" | "main" | "figure" | "dl" | "table" => {
+                self.block()
+            }
+            "br" => {
+                let trimmed = self.out.trim_end_matches(' ').len();
+                self.out.truncate(trimmed);
+                self.out.push('\n');
+            }
+            "hr" => {
+                self.block();
+                self.out.push_str("---\n\n");
+            }
+            "ul" | "ol" => {
+                if self.lists.is_empty() {
+                    self.block();
+                }
+                self.lists.push((name == "ol", 1));
+            }
+            "li" => {
+                self.newline();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some((true, number)) => {
+                        self.out.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            "dt" | "dd" | "figcaption" | "tr" => {
+                self.newline();
+                if name == "tr" {
+                    self.out.push('|');
+                    self.header_cells = 0;
+                }
+            }
+            "th" | "td" => {
+                if name == "th" {
+                    self.header_cells += 1;
+                }
+                self.out.push(' ');
+            }
+            "blockquote" => {
+                self.block();
+                self.quotes.push(self.out.len());
+            }
+            "pre" => {
+                self.block();
+                self.out.push_str("```");
+                self.fence_at = Some(self.out.len());
+                self.out.push('\n');
+                self.pre_depth += 1;
+            }
+            "code" if self.pre_depth > 0 => {
+                let language = token
+                    .attr("class")
+                    .unwrap_or("")
+                    .split_whitespace()
+                    .find_map(|class| {
+                        class
+                            .strip_prefix("language-")
+                            .or_else(|| class.strip_prefix("lang-"))
+                    });
+                if let (Some(at), Some(language)) = (self.fence_at, language) {
+                    if self.out.len() == at + 1 {
+                        self.out.insert_str(at, language);
+                    }
+                }
+            }
+            "code" => self.out.push('`'),
+            "strong" | "b" => self.out.push_str("**"),
+            "em" | "i" => self.out.push('*'),
+            "a" => {
+                let href = token.attr("href").and_then(|href| self.resolve(href));
+                self.links.push((href, self.out.len()));
+            }
+            "img" => {
+                let alt = token.attr("alt").unwrap_or("").trim();
+                if let Some(src) = token.attr("src").and_then(|src| self.resolve(src)) {
+                    if !alt.is_empty() {
+                        self.out.push_str(&format!("![{}]({})", alt, src));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "div" | "section" | "article"
+            | "main" | "figure" | "dl" | "table" => self.block(),
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block();
+                }
+            }
+            "th" | "td" => {
+                let trimmed = self.out.trim_end_matches(' ').len();
+                self.out.truncate(trimmed);
+                self.out.push_str(" |");
+            }
+            "tr" => {
+                if self.header_cells > 0 {
+                    self.out.push_str("\n|");
+                    self.out.push_str(&" --- |".repeat(self.header_cells));
+                    self.header_cells = 0;
+                }
+                self.newline();
+            }
+            "blockquote" => {
+                if let Some(start) = self.quotes.pop() {
+                    let quoted: String = self.out[start..]
+                        .trim()
+                        .lines()
+                        .map(|line| format!("> {}\n", line).replace("> \n", ">\n"))
+                        .collect();
+                    self.out.truncate(start);
+                    self.out.push_str(&quoted);
+                    self.block();
+                }
+            }
+            "pre" => {
+                if self.pre_depth > 0 {
+                    self.pre_depth -= 1;
+                    self.newline();
+                    self.out.push_str("```");
+                    self.fence_at = None;
+                    self.block();
+                }
+            }
+            "code" if self.pre_depth == 0 => self.out.push('`'),
+            "strong" | "b" => self.out.push_str("**"),
+            "em" | "i" => self.out.push('*'),
+            "a" => {
+                if let Some((href, start)) = self.links.pop() {
+                    let text = self.out[start..].trim().to_string();
+                    if let Some(href) = href.filter(|_| !text.is_empty()) {
+                        self.out.truncate(start);
+                        self.out.push_str(&format!("[{}]({})", text, href));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn convert(&mut self, tokens: &[Token], readability: bool) {
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            match token {
+                Token::Open {
+                    name, self_closing, ..
+                } => {
+                    if SKIPPED_TAGS.contains(&name.as_str())
+                        || (readability && is_boilerplate(token))
+                    {
+                        if !self_closing {
+                            i = matching_close(tokens, i);
+                        }
+                    } else {
+                        self.open(token);
+                        if name == "title" && !self_closing {
+                            // The title is returned separately, not as part of the text
+                            i = matching_close(tokens, i);
+                        }
+                    }
+                }
+                Token::Close(name) => self.close(name),
+                Token::Text(text) => self.text(text),
+            }
+            i += 1;
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut result = String::new();
+        let mut blank_lines = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+        result.trim().to_string()
+    }
+}
+
+/// The text of the page's `<title>`
+fn page_title(tokens: &[Token]) -> Option<String> {
+    let open = tokens
+        .iter()
+        .position(|token| matches!(token, Token::Open { name, .. } if name == "title"))?;
+    let close = matching_close(tokens, open);
+    let title: String = tokens[open + 1..close.min(tokens.len())]
+        .iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Convert a whole HTML document or fragment to markdown, resolving links against `base_url`
+pub fn html_to_markdown(html: &str, base_url: Option<&Url>) -> String {
+    let tokens = tokenize(html);
+    let mut converter = Converter::new(base_url);
+    converter.convert(&tokens, false);
+    converter.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub title: Option<String>,
+    pub markdown: String,
+}
+
+/// Extract the readable content of a page as markdown, leaving out navigation and boilerplate
+pub fn extract_page(html: &str, base_url: Option<&Url>) -> Page {
+    let tokens = tokenize(html);
+    let title = page_title(&tokens);
+    let (start, end) = main_content(&tokens);
+
+    let mut converter = Converter::new(base_url);
+    converter.convert(&tokens[start..end.min(tokens.len())], true);
+    let markdown = converter.finish();
+
+    // Some pages mark everything as boilerplate; the whole page is better than nothing
+    let markdown = if markdown.is_empty() {
+        html_to_markdown(html, base_url)
+    } else {
+        markdown
+    };
+    Page { title, markdown }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &amp; b &lt;c&gt; &#39;d&#x27; &nbsp;&mdash; &bogus; &"),
+            "a & b <c> 'd'  — &bogus; &"
+        );
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize(
+            "<!doctype html><p class=\"x > y\" data-a='1'>Hi<br/></p><!-- gone --><script>if (a < b) {}</script>",
+        );
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Open {
+                    name: "p".to_string(),
+                    attrs: vec![
+                        ("class".to_string(), "x > y".to_string()),
+                        ("data-a".to_string(), "1".to_string())
+                    ],
+                    self_closing: false,
+                },
+                Token::Text("Hi".to_string()),
+                Token::Open {
+                    name: "br".to_string(),
+                    attrs: vec![],
+                    self_closing: true,
+                },
+                Token::Close("p".to_string()),
+                Token::Open {
+                    name: "script".to_string(),
+                    attrs: vec![],
+                    self_closing: false,
+                },
+                Token::Text("if (a < b) {}".to_string()),
+                Token::Close("script".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_to_markdown() {
+        let base = Url::parse("https://example.com/docs/start").unwrap();
+        let html = r#"
+            <h1>Getting   started</h1>
+            <p>Read the <a href="guide">guide</a> and <strong>enjoy</strong> <em>it</em>.</p>
+            <ul><li>one</li><li>two<ol><li>nested</li></ol></li></ul>
+            <pre><code class="language-rust">fn main() {
+    println!("&lt;hi&gt;");
+}</code></pre>
+            <blockquote><p>Quoted</p></blockquote>
+            <table><tr><th>Name</th><th>Value</th></tr><tr><td>a</td><td>1</td></tr></table>
+        "#;
+        assert_eq!(
+            html_to_markdown(html, Some(&base)),
+            "# Getting started\n\n\
+             Read the [guide](https://example.com/docs/guide) and **enjoy** *it*.\n\n\
+             - one\n\
+             - two\n  \
+               1. nested\n\n\
+             ```rust\nfn main() {\n    println!(\"<hi>\");\n}\n```\n\n\
+             > Quoted\n\n\
+             | Name | Value |\n| --- | --- |\n| a | 1 |"
+        );
+    }
+
+    #[test]
+    fn test_extract_page() {
+        let html = r#"
+            <html><head><title> The   Title </title><style>p { color: red }</style></head>
+            <body>
+              <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+              <div class="cookie-banner">We use cookies</div>
+              <article>
+                <h2>Story</h2>
+                <p>The main text.</p>
+                <div class="share-buttons">Share this</div>
+              </article>
+              <footer>Copyright</footer>
+            </body></html>
+        "#;
+        let page = extract_page(html, None);
+        assert_eq!(page.title.as_deref(), Some("The Title"));
+        assert_eq!(page.markdown, "## Story\n\nThe main text.");
+
+        // Without main content markers the body is used, still without boilerplate
+        let page = extract_page("<body><header>Site</header><p>Only this</p></body>", None);
+        assert_eq!(page.markdown, "Only this");
+    }
+}
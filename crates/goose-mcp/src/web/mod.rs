@@ -0,0 +1,667 @@
+mod html;
+mod robots;
+mod search;
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use goose::config::Config;
+use indoc::indoc;
+use reqwest::{
+    header::{CONTENT_TYPE, LOCATION},
+    redirect, Client, Response, StatusCode,
+};
+use rmcp::{
+    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    model::{
+        CallToolResult, Content, ErrorCode, ErrorData, Implementation, ServerCapabilities,
+        ServerInfo,
+    },
+    schemars::JsonSchema,
+    tool, tool_handler, tool_router, ServerHandler,
+};
+use serde::{Deserialize, Serialize};
+use url::{Host, Url};
+
+pub use html::{extract_page, html_to_markdown, Page};
+pub use robots::{Robots, ROBOTS_AGENT};
+pub use search::{SearchBackend, SearchResult};
+
+/// Seconds to wait for a search or page before giving up
+pub const WEB_TIMEOUT_KEY: &str = "GOOSE_WEB_TIMEOUT";
+const DEFAULT_TIMEOUT_SECS: u64 = 20;
+
+const DEFAULT_MAX_RESULTS: usize = 5;
+const MAX_RESULTS: usize = 20;
+/// Characters of a page returned per web_fetch call
+const DEFAULT_MAX_LENGTH: usize = 20_000;
+/// Pages are cut off after this many bytes, however large they are
+const MAX_DOWNLOAD_BYTES: usize = 2 * 1024 * 1024;
+/// Redirects followed for a page, each checked like the URL it started from
+const MAX_REDIRECTS: usize = 10;
+
+/// Parameters for the web_search tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WebSearchParams {
+    /// What to search for
+    pub query: String,
+    /// How many results to return, 5 by default and at most 20
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+/// Parameters for the web_fetch tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WebFetchParams {
+    /// The http or https URL of the page
+    pub url: String,
+    /// Character offset to start reading from, to continue a page that was cut off
+    #[serde(default)]
+    pub start_index: Option<usize>,
+    /// How many characters to return, 20000 by default
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Return the whole page as markdown instead of only its main content
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// Parameters for the html_to_markdown tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct HtmlToMarkdownParams {
+    /// The HTML to convert
+    pub html: String,
+    /// URL the HTML came from, used to resolve relative links
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+/// A page fetched in this session, as markdown or text
+#[derive(Debug, Clone)]
+struct CachedPage {
+    /// Where the page ended up after redirects
+    url: String,
+    title: Option<String>,
+    content: String,
+    truncated: bool,
+}
+
+/// Web MCP Server: search, then read the pages worth reading
+#[derive(Clone)]
+pub struct WebServer {
+    tool_router: ToolRouter<Self>,
+    instructions: String,
+    http_client: Client,
+    timeout: Duration,
+    /// Pages by requested URL and whether they were fetched raw
+    pages: Arc<Mutex<HashMap<(String, bool), CachedPage>>>,
+    /// Parsed robots.txt by origin
+    robots: Arc<Mutex<HashMap<String, Robots>>>,
+}
+
+impl Default for WebServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn internal_error(message: String) -> ErrorData {
+    ErrorData::new(ErrorCode::INTERNAL_ERROR, message, None)
+}
+
+fn invalid_params(message: String) -> ErrorData {
+    ErrorData::new(ErrorCode::INVALID_PARAMS, message, None)
+}
+
+fn require_network(what: &str) -> Result<(), ErrorData> {
+    goose::offline::require_network(what)
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_REQUEST, e.to_string(), None))
+}
+
+/// Whether `ip` is on the public internet, rather than this machine, the local network or a
+/// cloud metadata service
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || first == 0
+                || (first == 100 && (second & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// The addresses `url`'s host resolves to, refusing hosts that aren't all public
+async fn resolve_public(url: &Url) -> Result<Vec<SocketAddr>, ErrorData> {
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|e| internal_error(format!("Failed to resolve {}: {}", domain, e)))?
+            .collect(),
+        None => return Err(invalid_params(format!("{} has no host", url))),
+    };
+    if addrs.is_empty() {
+        return Err(internal_error(format!(
+            "{} doesn't resolve to any address",
+            url.host_str().unwrap_or_default()
+        )));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        return Err(invalid_params(format!(
+            "{} can't be fetched: {} is a local or private address",
+            url,
+            addr.ip()
+        )));
+    }
+    Ok(addrs)
+}
+
+/// The `max_length` characters of `content` from `start`, with a note on how to read on
+fn paginate(content: &str, start: usize, max_length: usize) -> String {
+    let total = content.chars().count();
+    if start >= total {
+        return format!("[No more content: the page is {} characters long]", total);
+    }
+    let end = start.saturating_add(max_length).min(total);
+    let mut page: String = content.chars().skip(start).take(end - start).collect();
+    if end < total {
+        page.push_str(&format!(
+            "\n\n[Showing characters {}-{} of {}. Call web_fetch again with start_index={} to continue.]",
+            start, end, total, end
+        ));
+    }
+    page
+}
+
+fn is_html(content_type: &str) -> bool {
+    content_type.is_empty()
+        || content_type.contains("text/html")
+        || content_type.contains("application/xhtml")
+}
+
+fn is_text(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || ["json", "xml", "javascript", "yaml", "toml"]
+            .iter()
+            .any(|kind| content_type.contains(kind))
+}
+
+#[tool_router(router = tool_router)]
+impl WebServer {
+    pub fn new() -> Self {
+        let timeout = Config::global()
+            .get_param::<u64>(WEB_TIMEOUT_KEY)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let http_client = Client::builder()
+            .user_agent("goose/1.0")
+            .timeout(Duration::from_secs(timeout))
+            .build()
+            .unwrap();
+
+        let instructions = indoc! {r#"
+            The web extension searches the web and reads pages as markdown.
+
+            Search first with web_search, then read the most promising results with web_fetch rather
+            than fetching everything. web_fetch returns the main content of a page; long pages are
+            returned in parts, so continue with start_index only when the rest is needed. Pages are
+            cached for the session, so reading on or returning to a page doesn't download it again.
+
+            Use html_to_markdown for HTML you already have, for example from a file or a tool.
+            Sites that disallow goose in their robots.txt, and local or private network addresses,
+            can't be fetched.
+        "#}
+        .to_string();
+
+        Self {
+            tool_router: Self::tool_router(),
+            instructions,
+            http_client,
+            timeout: Duration::from_secs(timeout),
+            pages: Arc::new(Mutex::new(HashMap::new())),
+            robots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Search the web with the configured backend
+    #[tool(
+        name = "web_search",
+        description = "Search the web. Returns the title, URL and a snippet for each result; read a result with web_fetch."
+    )]
+    pub async fn web_search(
+        &self,
+        params: Parameters<WebSearchParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        require_network("web_search")?;
+        let params = params.0;
+        let max_results = params
+            .max_results
+            .unwrap_or(DEFAULT_MAX_RESULTS)
+            .clamp(1, MAX_RESULTS);
+
+        let backend = SearchBackend::from_config().map_err(internal_error)?;
+        let results = backend
+            .search(&self.http_client, &params.query, max_results)
+            .await
+            .map_err(internal_error)?;
+
+        if results.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No results for '{}'",
+                params.query
+            ))]));
+        }
+        let text = results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let mut entry = format!("{}. [{}]({})", i + 1, result.title, result.url);
+                if !result.snippet.is_empty() {
+                    entry.push_str(&format!("\n   {}", result.snippet));
+                }
+                entry
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Fetch a page and return its main content as markdown
+    #[tool(
+        name = "web_fetch",
+        description = "Fetch a web page and return its main content as markdown, leaving out navigation and other boilerplate. Long pages are returned in parts: call again with start_index to continue. Set raw to convert the whole page."
+    )]
+    pub async fn web_fetch(
+        &self,
+        params: Parameters<WebFetchParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        require_network("web_fetch")?;
+        let params = params.0;
+        let url = Url::parse(&params.url).map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("Invalid URL '{}': {}", params.url, e),
+                None,
+            )
+        })?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid_params(format!(
+                "Only http and https URLs can be fetched, not '{}'",
+                url
+            )));
+        }
+
+        let key = (url.to_string(), params.raw);
+        let cached = self.pages.lock().unwrap().get(&key).cloned();
+        let page = match cached {
+            Some(page) => page,
+            None => {
+                let page = self.download(&url, params.raw).await?;
+                self.pages.lock().unwrap().insert(key, page.clone());
+                page
+            }
+        };
+
+        let mut text = String::new();
+        if let Some(title) = &page.title {
+            text.push_str(&format!("# {}\n\n", title));
+        }
+        text.push_str(&format!("Source: {}\n\n", page.url));
+        if page.truncated {
+            text.push_str(&format!(
+                "[The page is larger than {} bytes and was cut off]\n\n",
+                MAX_DOWNLOAD_BYTES
+            ));
+        }
+        text.push_str(&paginate(
+            &page.content,
+            params.start_index.unwrap_or(0),
+            params.max_length.unwrap_or(DEFAULT_MAX_LENGTH).max(1),
+        ));
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Convert HTML to markdown
+    #[tool(
+        name = "html_to_markdown",
+        description = "Convert HTML to markdown. Pass base_url to resolve relative links."
+    )]
+    pub async fn html_to_markdown(
+        &self,
+        params: Parameters<HtmlToMarkdownParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let base_url = match params.base_url.as_deref() {
+            Some(base_url) => Some(Url::parse(base_url).map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("Invalid base_url '{}': {}", base_url, e),
+                    None,
+                )
+            })?),
+            None => None,
+        };
+        Ok(CallToolResult::success(vec![Content::text(
+            html_to_markdown(&params.html, base_url.as_ref()),
+        )]))
+    }
+
+    /// Whether robots.txt lets us fetch `url`; a missing or unreadable robots.txt allows all
+    async fn robots_allow(&self, url: &Url) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let cached = self.robots.lock().unwrap().get(&origin).cloned();
+        let robots = match cached {
+            Some(robots) => robots,
+            None => {
+                let response = match url.join("/robots.txt") {
+                    Ok(robots_url) => self.get_public(&robots_url).await.ok(),
+                    Err(_) => None,
+                };
+                let robots = match response {
+                    Some(response) if response.status() == StatusCode::OK => response
+                        .text()
+                        .await
+                        .map(|content| Robots::parse(&content, ROBOTS_AGENT))
+                        .unwrap_or_default(),
+                    _ => Robots::default(),
+                };
+                self.robots.lock().unwrap().insert(origin, robots.clone());
+                robots
+            }
+        };
+
+        let mut path = url.path().to_string();
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        robots.allows(&path)
+    }
+
+    /// GET `url`, following redirects only to public addresses. Each hop connects to the
+    /// addresses that were checked, so a host can't resolve differently the second time.
+    async fn get_public(&self, url: &Url) -> Result<Response, ErrorData> {
+        let mut url = url.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            let addrs = resolve_public(&url).await?;
+            let mut builder = Client::builder()
+                .user_agent("goose/1.0")
+                .timeout(self.timeout)
+                .redirect(redirect::Policy::none());
+            if let Some(Host::Domain(domain)) = url.host() {
+                builder = builder.resolve_to_addrs(domain, &addrs);
+            }
+            let client = builder
+                .build()
+                .map_err(|e| internal_error(format!("Failed to create an HTTP client: {}", e)))?;
+            let response = client
+                .get(url.clone())
+                .send()
+                .await
+                .map_err(|e| internal_error(format!("Failed to fetch {}: {}", url, e)))?;
+            if !response.status().is_redirection() {
+                return Ok(response);
+            }
+
+            let Some(location) = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+            else {
+                return Ok(response);
+            };
+            let next = url.join(location).map_err(|e| {
+                internal_error(format!("{} redirected to '{}': {}", url, location, e))
+            })?;
+            if !matches!(next.scheme(), "http" | "https") {
+                return Err(invalid_params(format!(
+                    "{} redirected to '{}', which isn't an http or https URL",
+                    url, next
+                )));
+            }
+            url = next;
+        }
+        Err(internal_error(format!(
+            "{} redirected more than {} times",
+            url, MAX_REDIRECTS
+        )))
+    }
+
+    async fn download(&self, url: &Url, raw: bool) -> Result<CachedPage, ErrorData> {
+        resolve_public(url).await?;
+        if !self.robots_allow(url).await {
+            return Err(internal_error(format!(
+                "{} disallows fetching {} in its robots.txt",
+                url.host_str().unwrap_or_default(),
+                url.path()
+            )));
+        }
+
+        let mut response = self.get_public(url).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(internal_error(format!(
+                "Fetching {} failed with status {}",
+                url, status
+            )));
+        }
+
+        let final_url = response.url().clone();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_lowercase();
+        if !is_html(&content_type) && !is_text(&content_type) {
+            return Err(internal_error(format!(
+                "{} is {}, which can't be read as text",
+                url, content_type
+            )));
+        }
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| internal_error(format!("Failed to read {}: {}", url, e)))?
+        {
+            let room = MAX_DOWNLOAD_BYTES - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let body = String::from_utf8_lossy(&body);
+
+        let (title, content) = if !is_html(&content_type) {
+            (None, body.into_owned())
+        } else if raw {
+            (None, html_to_markdown(&body, Some(&final_url)))
+        } else {
+            let page = extract_page(&body, Some(&final_url));
+            (page.title, page.markdown)
+        };
+        Ok(CachedPage {
+            url: final_url.to_string(),
+            title,
+            content,
+            truncated,
+        })
+    }
+}
+
+#[tool_handler(router = self.tool_router)]
+impl ServerHandler for WebServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            server_info: Implementation {
+                name: "goose-web".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+                title: None,
+                icons: None,
+                website_url: None,
+            },
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some(self.instructions.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let content = "ab€defghij";
+        assert_eq!(paginate(content, 0, 100), content);
+        assert_eq!(
+            paginate(content, 0, 3),
+            "ab€\n\n[Showing characters 0-3 of 10. Call web_fetch again with start_index=3 to continue.]"
+        );
+        assert_eq!(paginate(content, 8, 3), "ij");
+        assert!(paginate(content, 10, 3).starts_with("[No more content"));
+    }
+
+    #[test]
+    fn test_content_types() {
+        assert!(is_html("text/html; charset=utf-8"));
+        assert!(is_html(""));
+        assert!(!is_html("text/plain"));
+        assert!(is_text("text/plain"));
+        assert!(is_text("application/json"));
+        assert!(!is_text("application/pdf"));
+        assert!(!is_text("image/png"));
+    }
+
+    #[tokio::test]
+    async fn test_get_info() {
+        let server = WebServer::new();
+        let info = server.get_info();
+        assert_eq!(info.server_info.name, "goose-web");
+        assert!(info.instructions.unwrap().contains("web_search"));
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_serves_cached_pages() {
+        let server = WebServer::new();
+        server.pages.lock().unwrap().insert(
+            ("https://example.com/".to_string(), false),
+            CachedPage {
+                url: "https://example.com/".to_string(),
+                title: Some("Example".to_string()),
+                content: "Hello world".to_string(),
+                truncated: false,
+            },
+        );
+
+        let result = server
+            .web_fetch(Parameters(WebFetchParams {
+                url: "https://example.com/".to_string(),
+                start_index: Some(6),
+                max_length: None,
+                raw: false,
+            }))
+            .await
+            .unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert_eq!(text, "# Example\n\nSource: https://example.com/\n\nworld");
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_rejects_other_schemes() {
+        let server = WebServer::new();
+        let err = server
+            .web_fetch(Parameters(WebFetchParams {
+                url: "file:///etc/passwd".to_string(),
+                start_index: None,
+                max_length: None,
+                raw: false,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["93.184.215.14", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.0.0.8",
+            "172.16.5.4",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_rejects_local_addresses() {
+        let server = WebServer::new();
+        for url in [
+            "http://127.0.0.1:8080/",
+            "http://localhost/",
+            "http://[::1]/",
+            "http://169.254.169.254/latest/meta-data/",
+        ] {
+            let err = server
+                .web_fetch(Parameters(WebFetchParams {
+                    url: url.to_string(),
+                    start_index: None,
+                    max_length: None,
+                    raw: false,
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code, ErrorCode::INVALID_PARAMS, "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_html_to_markdown_tool() {
+        let server = WebServer::new();
+        let result = server
+            .html_to_markdown(Parameters(HtmlToMarkdownParams {
+                html: r#"<h1>Title</h1><p>See <a href="/docs">the docs</a></p>"#.to_string(),
+                base_url: Some("https://example.com/guide/".to_string()),
+            }))
+            .await
+            .unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.contains("# Title"));
+        assert!(text.contains("[the docs](https://example.com/docs)"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Just enough of robots.txt to respect the rules a site sets for its paths.
+
+/// The product token matched against `User-agent` lines
+pub const ROBOTS_AGENT: &str = "goose";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Robots {
+    /// `Allow` (true) and `Disallow` (false) rules for our agent
+    rules: Vec<(bool, String)>,
+}
+
+/// Whether a robots.txt path pattern matches `path`, with `*` wildcards and a `$` end anchor
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(mut rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+    let Some((tail, middle)) = parts[1..].split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    if anchored {
+        rest.ends_with(tail)
+    } else {
+        rest.contains(tail)
+    }
+}
+
+impl Robots {
+    /// Parse robots.txt, keeping the rules of the groups naming `agent`, or the `*` groups if
+    /// none do
+    pub fn parse(content: &str, agent: &str) -> Self {
+        let agent = agent.to_lowercase();
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+
+        let mut group_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group
+                    if in_rules {
+                        group_agents.clear();
+                        in_rules = false;
+                    }
+                    group_agents.push(value.to_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    let rule = (key == "allow", value.to_string());
+                    if group_agents
+                        .iter()
+                        .any(|a| !a.is_empty() && a != "*" && agent.contains(a.as_str()))
+                    {
+                        specific.push(rule.clone());
+                    }
+                    if group_agents.iter().any(|a| a == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let rules = if specific.is_empty() {
+            wildcard
+        } else {
+            specific
+        };
+        Self {
+            // An empty Disallow allows everything, so it isn't a rule at all
+            rules: rules
+                .into_iter()
+                .filter(|(_, pattern)| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Whether `path` (with its query) may be fetched: the longest matching rule wins, and
+    /// `Allow` wins a tie
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/private", "/private/page"));
+        assert!(!pattern_matches("/private", "/public"));
+        assert!(pattern_matches("/*.pdf$", "/docs/file.pdf"));
+        assert!(!pattern_matches("/*.pdf$", "/docs/file.pdf?x=1"));
+        assert!(pattern_matches("/search*q=", "/search?lang=en&q=rust"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exact/more"));
+    }
+
+    #[test]
+    fn test_robots_rules() {
+        let robots = Robots::parse(
+            "User-agent: *\nDisallow: /private\nAllow: /private/open\n\n\
+             User-agent: OtherBot\nDisallow: /\n",
+            ROBOTS_AGENT,
+        );
+        assert!(robots.allows("/"));
+        assert!(!robots.allows("/private/secret"));
+        assert!(robots.allows("/private/open/page"));
+
+        // Rules for our agent replace the wildcard group
+        let robots = Robots::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: goose\nUser-agent: other\nDisallow: /admin # no\n",
+            ROBOTS_AGENT,
+        );
+        assert!(robots.allows("/docs"));
+        assert!(!robots.allows("/admin"));
+
+        let robots = Robots::parse("User-agent: *\nDisallow:\n", ROBOTS_AGENT);
+        assert!(robots.allows("/anything"));
+    }
+}
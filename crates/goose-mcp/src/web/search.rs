@@ -0,0 +1,276 @@
+//! Web search backends: DuckDuckGo, Brave and SearXNG.
+
+use goose::config::Config;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use super::html::{tokenize, Token};
+
+pub const SEARCH_BACKEND_KEY: &str = "GOOSE_WEB_SEARCH_BACKEND";
+pub const SEARCH_URL_KEY: &str = "GOOSE_WEB_SEARCH_URL";
+pub const BRAVE_API_KEY: &str = "BRAVE_API_KEY";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchBackend {
+    DuckDuckGo,
+    Brave { api_key: String },
+    Searxng { url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+impl SearchBackend {
+    /// The configured backend, or an explanation of what configuration is missing
+    pub fn from_config() -> Result<Self, String> {
+        let config = Config::global();
+        let backend = config
+            .get_param::<String>(SEARCH_BACKEND_KEY)
+            .unwrap_or_else(|_| "duckduckgo".to_string());
+        match backend.to_lowercase().as_str() {
+            "duckduckgo" | "ddg" => Ok(SearchBackend::DuckDuckGo),
+            "brave" => config
+                .get_secret::<String>(BRAVE_API_KEY)
+                .map(|api_key| SearchBackend::Brave { api_key })
+                .map_err(|_| format!("The brave search backend needs {}", BRAVE_API_KEY)),
+            "searxng" => config
+                .get_param::<String>(SEARCH_URL_KEY)
+                .map(|url| SearchBackend::Searxng {
+                    url: url.trim_end_matches('/').to_string(),
+                })
+                .map_err(|_| format!("The searxng search backend needs {}", SEARCH_URL_KEY)),
+            other => Err(format!(
+                "Unknown {} '{}', expected duckduckgo, brave or searxng",
+                SEARCH_BACKEND_KEY, other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchBackend::DuckDuckGo => "duckduckgo",
+            SearchBackend::Brave { .. } => "brave",
+            SearchBackend::Searxng { .. } => "searxng",
+        }
+    }
+
+    pub async fn search(
+        &self,
+        client: &Client,
+        query: &str,
+        max_results: usize,
+    ) -> Result<Vec<SearchResult>, String> {
+        let request = match self {
+            SearchBackend::DuckDuckGo => client
+                .get("https://html.duckduckgo.com/html/")
+                .query(&[("q", query)]),
+            SearchBackend::Brave { api_key } => client
+                .get("https://api.search.brave.com/res/v1/web/search")
+                .header("X-Subscription-Token", api_key)
+                .header("Accept", "application/json")
+                .query(&[("q", query), ("count", &max_results.to_string())]),
+            SearchBackend::Searxng { url } => client
+                .get(format!("{}/search", url))
+                .query(&[("q", query), ("format", "json")]),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Search request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Search with {} failed with status {}",
+                self.name(),
+                response.status()
+            ));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read search results: {}", e))?;
+
+        let mut results = match self {
+            SearchBackend::DuckDuckGo => parse_duckduckgo(&body),
+            SearchBackend::Brave { .. } => {
+                parse_json_results(&body, "/web/results", "description")?
+            }
+            SearchBackend::Searxng { .. } => parse_json_results(&body, "/results", "content")?,
+        };
+        results.truncate(max_results);
+        Ok(results)
+    }
+}
+
+fn strip_tags(html: &str) -> String {
+    tokenize(html)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text),
+            _ => None,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Results from a JSON API, found at the `pointer` array with `title`, `url` and a snippet field
+fn parse_json_results(
+    body: &str,
+    pointer: &str,
+    snippet_field: &str,
+) -> Result<Vec<SearchResult>, String> {
+    let value: Value =
+        serde_json::from_str(body).map_err(|e| format!("Invalid search response: {}", e))?;
+    let field = |result: &Value, key: &str| {
+        result
+            .get(key)
+            .and_then(Value::as_str)
+            .map(strip_tags)
+            .unwrap_or_default()
+    };
+    Ok(value
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|result| {
+            let url = result.get("url").and_then(Value::as_str)?.to_string();
+            Some(SearchResult {
+                title: field(result, "title"),
+                url,
+                snippet: field(result, snippet_field),
+            })
+        })
+        .collect())
+}
+
+/// DuckDuckGo links results through a redirect that carries the target in `uddg`
+fn duckduckgo_target(href: &str) -> Option<String> {
+    let absolute = if href.starts_with("//") {
+        format!("https:{}", href)
+    } else {
+        href.to_string()
+    };
+    let url = Url::parse(&absolute).ok()?;
+    if url.path() == "/l/" {
+        return url
+            .query_pairs()
+            .find(|(key, _)| key == "uddg")
+            .map(|(_, value)| value.into_owned());
+    }
+    // Ads point back at duckduckgo.com itself
+    (url.domain() != Some("duckduckgo.com")).then_some(absolute)
+}
+
+/// Results from the DuckDuckGo HTML page: `result__a` links followed by `result__snippet`s
+fn parse_duckduckgo(html: &str) -> Vec<SearchResult> {
+    let tokens = tokenize(html);
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut capture: Option<(bool, String)> = None;
+    let mut skip_result = false;
+
+    for token in &tokens {
+        match token {
+            Token::Open { name, .. } if name == "a" => {
+                let class = token.attr("class").unwrap_or("");
+                if class.split_whitespace().any(|c| c == "result__a") {
+                    match token.attr("href").and_then(duckduckgo_target) {
+                        Some(url) => {
+                            results.push(SearchResult {
+                                title: String::new(),
+                                url,
+                                snippet: String::new(),
+                            });
+                            skip_result = false;
+                            capture = Some((true, String::new()));
+                        }
+                        None => skip_result = true,
+                    }
+                } else if class.split_whitespace().any(|c| c == "result__snippet") && !skip_result {
+                    capture = Some((false, String::new()));
+                }
+            }
+            Token::Text(text) => {
+                if let Some((_, captured)) = capture.as_mut() {
+                    captured.push_str(text);
+                }
+            }
+            Token::Close(name) if name == "a" => {
+                if let (Some((is_title, captured)), Some(result)) =
+                    (capture.take(), results.last_mut())
+                {
+                    let text = captured.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if is_title {
+                        result.title = text;
+                    } else {
+                        result.snippet = text;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duckduckgo() {
+        let html = r##"
+            <div class="result results_links">
+              <a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust-lang.org%2F&amp;rut=abc">Rust <b>Programming</b> Language</a>
+              <a class="result__snippet" href="//duckduckgo.com/l/?uddg=x">A language empowering <b>everyone</b>.</a>
+            </div>
+            <div class="result result--ad">
+              <a class="result__a" href="https://duckduckgo.com/y.js?ad=1">Sponsored</a>
+              <a class="result__snippet" href="#">Buy now</a>
+            </div>
+            <div class="result">
+              <a class="result__a" href="https://doc.rust-lang.org/book/">The Book</a>
+            </div>
+        "##;
+        assert_eq!(
+            parse_duckduckgo(html),
+            vec![
+                SearchResult {
+                    title: "Rust Programming Language".to_string(),
+                    url: "https://www.rust-lang.org/".to_string(),
+                    snippet: "A language empowering everyone.".to_string(),
+                },
+                SearchResult {
+                    title: "The Book".to_string(),
+                    url: "https://doc.rust-lang.org/book/".to_string(),
+                    snippet: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_json_results() {
+        let body = r#"{"web": {"results": [
+            {"title": "Rust", "url": "https://www.rust-lang.org/", "description": "A <strong>fast</strong> language"},
+            {"title": "No url"}
+        ]}}"#;
+        assert_eq!(
+            parse_json_results(body, "/web/results", "description").unwrap(),
+            vec![SearchResult {
+                title: "Rust".to_string(),
+                url: "https://www.rust-lang.org/".to_string(),
+                snippet: "A fast language".to_string(),
+            }]
+        );
+        assert!(parse_json_results("not json", "/results", "content").is_err());
+    }
+}
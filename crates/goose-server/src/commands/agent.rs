@@ -3,6 +3,8 @@ use crate::state;
 use anyhow::Result;
 use axum::middleware;
 use goose_server::auth::check_token;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
 
@@ -40,6 +42,25 @@ pub async fn run() -> Result<()> {
         ))
         .layer(cors);
 
+    #[cfg(unix)]
+    if let Some(socket_path) = &settings.socket_path {
+        if socket_path.exists() {
+            if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+                anyhow::bail!(
+                    "another goosed is already listening on {}",
+                    socket_path.display()
+                );
+            }
+            std::fs::remove_file(socket_path)?;
+        }
+        let listener = tokio::net::UnixListener::bind(socket_path)?;
+        // Only the user running goosed may connect
+        std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600))?;
+        info!("listening on {}", socket_path.display());
+        axum::serve(listener, app).await?;
+        return Ok(());
+    }
+
     let listener = tokio::net::TcpListener::bind(settings.socket_addr()).await?;
     info!("listening on {}", listener.local_addr()?);
     axum::serve(listener, app).await?;
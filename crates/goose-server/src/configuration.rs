@@ -2,6 +2,7 @@ use crate::error::{to_env_var, ConfigError};
 use config::{Config, Environment};
 use serde::Deserialize;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 #[derive(Debug, Default, Deserialize)]
 pub struct Settings {
@@ -9,6 +10,9 @@ pub struct Settings {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// Serve on a unix domain socket instead of TCP (GOOSE_SOCKET_PATH)
+    #[serde(default)]
+    pub socket_path: Option<PathBuf>,
 }
 
 impl Settings {
@@ -83,6 +87,7 @@ mod tests {
         let server_settings = Settings {
             host: "127.0.0.1".to_string(),
             port: 3000,
+            socket_path: None,
         };
         let addr = server_settings.socket_addr();
         assert_eq!(addr.to_string(), "127.0.0.1:3000");
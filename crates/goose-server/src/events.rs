@@ -0,0 +1,176 @@
+//! The events goose-server streams to UI clients, and the versions of their wire format.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::http::{HeaderMap, HeaderValue};
+use goose::conversation::message::Message;
+use rmcp::model::ServerNotification;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const EVENT_VERSION_HEADER: &str = "x-goose-event-version";
+pub const CURRENT_EVENT_VERSION: u32 = 2;
+pub const OLDEST_EVENT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerEvent {
+    Message {
+        message: Message,
+    },
+    Error {
+        error: String,
+    },
+    Finish {
+        reason: String,
+    },
+    ModelChange {
+        model: String,
+        mode: String,
+    },
+    Notification {
+        request_id: String,
+        message: ServerNotification,
+    },
+    /// The conversation was compacted; `messages` is the history from now on
+    HistoryReplaced {
+        messages: Vec<Message>,
+    },
+    Ping,
+}
+
+/// An event as sent at the current version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    pub version: u32,
+    pub seq: u64,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
+
+/// Rewrites an event of version `n` into version `n - 1`, or drops it when the older version
+/// has no such event
+type Downgrade = fn(Value) -> Option<Value>;
+
+/// `DOWNGRADES[i]` goes from version `i + 2` to `i + 1`
+const DOWNGRADES: &[Downgrade] = &[downgrade_2_to_1];
+
+fn downgrade_2_to_1(mut event: Value) -> Option<Value> {
+    if event.get("type").and_then(Value::as_str) == Some("HistoryReplaced") {
+        return None;
+    }
+    if let Some(fields) = event.as_object_mut() {
+        fields.remove("version");
+        fields.remove("seq");
+    }
+    Some(event)
+}
+
+/// The version a client asked for, capped at the current one. Unparseable or missing headers
+/// mean an old client and get the oldest version.
+pub fn negotiate_version(headers: &HeaderMap) -> u32 {
+    headers
+        .get(EVENT_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .map(|requested| requested.clamp(OLDEST_EVENT_VERSION, CURRENT_EVENT_VERSION))
+        .unwrap_or(OLDEST_EVENT_VERSION)
+}
+
+/// Encodes the events of one stream for the version its client negotiated
+#[derive(Debug)]
+pub struct EventEncoder {
+    version: u32,
+    seq: AtomicU64,
+}
+
+impl EventEncoder {
+    pub fn new(version: u32) -> Self {
+        Self {
+            version: version.clamp(OLDEST_EVENT_VERSION, CURRENT_EVENT_VERSION),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    pub fn header_value(&self) -> HeaderValue {
+        HeaderValue::from(self.version)
+    }
+
+    /// The event as JSON for this stream's version, or `None` if the version has no such event
+    pub fn encode(&self, event: ServerEvent) -> Option<String> {
+        let envelope = EventEnvelope {
+            version: CURRENT_EVENT_VERSION,
+            seq: self.seq.fetch_add(1, Ordering::Relaxed),
+            event,
+        };
+        let mut value = match serde_json::to_value(&envelope) {
+            Ok(value) => value,
+            Err(e) => {
+                return Some(format!(
+                    r#"{{"type":"Error","error":"Failed to serialize event: {}"}}"#,
+                    e
+                ))
+            }
+        };
+        for version in (self.version..CURRENT_EVENT_VERSION).rev() {
+            value = DOWNGRADES[version as usize - 1](value)?;
+        }
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_version() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(negotiate_version(&headers), 1);
+        headers.insert(EVENT_VERSION_HEADER, HeaderValue::from_static("2"));
+        assert_eq!(negotiate_version(&headers), 2);
+        headers.insert(EVENT_VERSION_HEADER, HeaderValue::from_static("99"));
+        assert_eq!(negotiate_version(&headers), CURRENT_EVENT_VERSION);
+        headers.insert(EVENT_VERSION_HEADER, HeaderValue::from_static("soon"));
+        assert_eq!(negotiate_version(&headers), 1);
+    }
+
+    #[test]
+    fn test_every_version_has_a_downgrade() {
+        assert_eq!(
+            DOWNGRADES.len() as u32,
+            CURRENT_EVENT_VERSION - OLDEST_EVENT_VERSION
+        );
+    }
+
+    #[test]
+    fn test_encode_for_each_version() {
+        let current = EventEncoder::new(2);
+        let encoded: Value = serde_json::from_str(
+            &current
+                .encode(ServerEvent::Finish {
+                    reason: "stop".to_string(),
+                })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            encoded,
+            serde_json::json!({"version": 2, "seq": 0, "type": "Finish", "reason": "stop"})
+        );
+        let envelope: EventEnvelope = serde_json::from_value(encoded).unwrap();
+        assert!(matches!(envelope.event, ServerEvent::Finish { .. }));
+        assert!(current
+            .encode(ServerEvent::HistoryReplaced { messages: vec![] })
+            .is_some());
+
+        let legacy = EventEncoder::new(1);
+        assert_eq!(
+            legacy.encode(ServerEvent::Ping).unwrap(),
+            r#"{"type":"Ping"}"#
+        );
+        assert!(legacy
+            .encode(ServerEvent::HistoryReplaced { messages: vec![] })
+            .is_none());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod events;
 pub mod openapi;
 pub mod routes;
 pub mod state;
@@ -1,6 +1,7 @@
 mod commands;
 mod configuration;
 mod error;
+mod events;
 mod logging;
 mod openapi;
 mod routes;
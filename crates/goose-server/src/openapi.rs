@@ -1,5 +1,6 @@
 use goose::agents::extension::Envs;
 use goose::agents::extension::ToolInfo;
+use goose::agents::remote_auth::RemoteAuth;
 use goose::agents::ExtensionConfig;
 use goose::config::permission::PermissionLevel;
 use goose::config::ExtensionEntry;
@@ -420,6 +421,7 @@ derive_utoipa!(Icon as IconSchema);
         ExtensionConfig,
         ConfigKey,
         Envs,
+        RemoteAuth,
         ToolSchema,
         ToolAnnotationsSchema,
         ToolInfo,
@@ -464,6 +466,9 @@ derive_utoipa!(Icon as IconSchema);
         goose::recipe::RecipeParameterRequirement,
         goose::recipe::Response,
         goose::recipe::SubRecipe,
+        goose::recipe::TaskResources,
+        goose::recipe::TaskPriority,
+        goose::recipe::TaskRetryPolicy,
         goose::agents::types::RetryConfig,
         goose::agents::types::SuccessCheck,
         super::routes::agent::AddSubRecipesRequest,
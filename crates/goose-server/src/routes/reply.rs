@@ -1,7 +1,8 @@
+use crate::events::{negotiate_version, EventEncoder, ServerEvent, EVENT_VERSION_HEADER};
 use crate::state::AppState;
 use axum::{
     extract::{DefaultBodyLimit, State},
-    http::{self, StatusCode},
+    http::{self, HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
     routing::post,
     Json, Router,
@@ -13,10 +14,9 @@ use goose::conversation::Conversation;
 use goose::permission::{Permission, PermissionConfirmation};
 use goose::session::SessionManager;
 use goose::{
-    agents::{AgentEvent, SessionConfig},
+    agents::{timer_extension, AgentEvent, SessionConfig},
     permission::permission_confirmation::PrincipalType,
 };
-use rmcp::model::ServerNotification;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
@@ -32,6 +32,21 @@ use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::CancellationToken;
 use utoipa::ToSchema;
 
+/// Tell the model about timers it set that fired since the last message, on the newest user
+/// message
+fn with_fired_timers(mut messages: Vec<Message>, session_id: &str) -> Vec<Message> {
+    // When it isn't the user's turn the timers stay due until it is
+    if messages.last().map(|last| &last.role) != Some(&rmcp::model::Role::User) {
+        return messages;
+    }
+    if let Some(note) = timer_extension::fired_timers_note(Some(session_id)) {
+        if let Some(last) = messages.pop() {
+            messages.push(last.with_text(note));
+        }
+    }
+    messages
+}
+
 fn track_tool_telemetry(content: &MessageContent, all_messages: &[Message]) {
     match content {
         MessageContent::ToolRequest(tool_request) => {
@@ -89,11 +104,12 @@ pub struct ChatRequest {
 
 pub struct SseResponse {
     rx: ReceiverStream<String>,
+    event_version: HeaderValue,
 }
 
 impl SseResponse {
-    fn new(rx: ReceiverStream<String>) -> Self {
-        Self { rx }
+    fn new(rx: ReceiverStream<String>, event_version: HeaderValue) -> Self {
+        Self { rx, event_version }
     }
 }
 
@@ -109,11 +125,13 @@ impl Stream for SseResponse {
 
 impl IntoResponse for SseResponse {
     fn into_response(self) -> axum::response::Response {
+        let event_version = self.event_version.clone();
         let stream = self;
         let body = axum::body::Body::from_stream(stream);
 
         http::Response::builder()
             .header("Content-Type", "text/event-stream")
+            .header(EVENT_VERSION_HEADER, event_version)
             .header("Cache-Control", "no-cache")
             .header("Connection", "keep-alive")
             .body(body)
@@ -121,40 +139,16 @@ impl IntoResponse for SseResponse {
     }
 }
 
-#[derive(Debug, Serialize)]
-#[serde(tag = "type")]
-enum MessageEvent {
-    Message {
-        message: Message,
-    },
-    Error {
-        error: String,
-    },
-    Finish {
-        reason: String,
-    },
-    ModelChange {
-        model: String,
-        mode: String,
-    },
-    Notification {
-        request_id: String,
-        message: ServerNotification,
-    },
-    Ping,
-}
-
 async fn stream_event(
-    event: MessageEvent,
+    event: ServerEvent,
+    encoder: &EventEncoder,
     tx: &mpsc::Sender<String>,
     cancel_token: &CancellationToken,
 ) {
-    let json = serde_json::to_string(&event).unwrap_or_else(|e| {
-        format!(
-            r#"{{"type":"Error","error":"Failed to serialize event: {}"}}"#,
-            e
-        )
-    });
+    // Events the client's version doesn't know are left out
+    let Some(json) = encoder.encode(event) else {
+        return;
+    };
     if tx.send(format!("data: {}\n\n", json)).await.is_err() {
         tracing::info!("client hung up");
         cancel_token.cancel();
@@ -174,8 +168,11 @@ async fn stream_event(
 )]
 pub async fn reply(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ChatRequest>,
 ) -> Result<SseResponse, StatusCode> {
+    let encoder = Arc::new(EventEncoder::new(negotiate_version(&headers)));
+    let event_version = encoder.header_value();
     let session_start = std::time::Instant::now();
 
     tracing::info!(
@@ -209,10 +206,11 @@ pub async fn reply(
     let stream = ReceiverStream::new(rx);
     let cancel_token = CancellationToken::new();
 
-    let messages = Conversation::new_unvalidated(request.messages);
+    let messages = Conversation::new_unvalidated(with_fired_timers(request.messages, &session_id));
 
     let task_cancel = cancel_token.clone();
     let task_tx = tx.clone();
+    let task_encoder = encoder.clone();
 
     drop(tokio::spawn(async move {
         let agent = match state.get_agent(session_id.clone()).await {
@@ -220,9 +218,10 @@ pub async fn reply(
             Err(e) => {
                 tracing::error!("Failed to get session agent: {}", e);
                 let _ = stream_event(
-                    MessageEvent::Error {
+                    ServerEvent::Error {
                         error: format!("Failed to get session agent: {}", e),
                     },
+                    &task_encoder,
                     &task_tx,
                     &task_cancel,
                 )
@@ -236,9 +235,10 @@ pub async fn reply(
             Err(e) => {
                 tracing::error!("Failed to read session for {}: {}", session_id, e);
                 let _ = stream_event(
-                    MessageEvent::Error {
+                    ServerEvent::Error {
                         error: format!("Failed to read session: {}", e),
                     },
+                    &task_encoder,
                     &task_tx,
                     &cancel_token,
                 )
@@ -268,9 +268,10 @@ pub async fn reply(
             Err(e) => {
                 tracing::error!("Failed to start reply stream: {:?}", e);
                 stream_event(
-                    MessageEvent::Error {
+                    ServerEvent::Error {
                         error: e.to_string(),
                     },
+                    &task_encoder,
                     &task_tx,
                     &cancel_token,
                 )
@@ -289,7 +290,7 @@ pub async fn reply(
                     break;
                 }
                 _ = heartbeat_interval.tick() => {
-                    stream_event(MessageEvent::Ping, &tx, &cancel_token).await;
+                    stream_event(ServerEvent::Ping, &task_encoder, &tx, &cancel_token).await;
                 }
                 response = timeout(Duration::from_millis(500), stream.next()) => {
                     match response {
@@ -302,31 +303,33 @@ pub async fn reply(
 
                             // Only send message to client if it's user_visible
                             if message.is_user_visible() {
-                                stream_event(MessageEvent::Message { message }, &tx, &cancel_token).await;
+                                stream_event(ServerEvent::Message { message }, &task_encoder, &tx, &cancel_token).await;
                             }
                         }
                         Ok(Some(Ok(AgentEvent::HistoryReplaced(new_messages)))) => {
                             // Replace the message history with the compacted messages
-                            all_messages = Conversation::new_unvalidated(new_messages);
-                            // Note: We don't send this as a stream event since it's an internal operation
-                            // The client will see the compaction notification message that was sent before this event
+                            all_messages = Conversation::new_unvalidated(new_messages.clone());
+                            // Clients before event version 2 only see the compaction notification
+                            // message that was sent before this event
+                            stream_event(ServerEvent::HistoryReplaced { messages: new_messages }, &task_encoder, &tx, &cancel_token).await;
                         }
                         Ok(Some(Ok(AgentEvent::ModelChange { model, mode }))) => {
-                            stream_event(MessageEvent::ModelChange { model, mode }, &tx, &cancel_token).await;
+                            stream_event(ServerEvent::ModelChange { model, mode }, &task_encoder, &tx, &cancel_token).await;
                         }
                         Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
-                            stream_event(MessageEvent::Notification{
+                            stream_event(ServerEvent::Notification{
                                 request_id: request_id.clone(),
                                 message: n,
-                            }, &tx, &cancel_token).await;
+                            }, &task_encoder, &tx, &cancel_token).await;
                         }
 
                         Ok(Some(Err(e))) => {
                             tracing::error!("Error processing message: {}", e);
                             stream_event(
-                                MessageEvent::Error {
+                                ServerEvent::Error {
                                     error: e.to_string(),
                                 },
+                                &task_encoder,
                                 &tx,
                                 &cancel_token,
                             ).await;
@@ -397,15 +400,16 @@ pub async fn reply(
         }
 
         let _ = stream_event(
-            MessageEvent::Finish {
+            ServerEvent::Finish {
                 reason: "stop".to_string(),
             },
+            &task_encoder,
             &task_tx,
             &cancel_token,
         )
         .await;
     }));
-    Ok(SseResponse::new(stream))
+    Ok(SseResponse::new(stream, event_version))
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -449,6 +453,7 @@ pub async fn confirm_permission(
             PermissionConfirmation {
                 principal_type: request.principal_type,
                 permission,
+                arguments: None,
             },
         )
         .await;
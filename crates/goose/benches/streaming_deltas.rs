@@ -0,0 +1,141 @@
+//! How long the provider stream parsers take to turn a long streamed response into messages.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use futures::{Stream, StreamExt};
+use goose::conversation::message::Message;
+use goose::providers::base::ProviderUsage;
+use goose::providers::formats::{anthropic, openai};
+use serde_json::json;
+use std::hint::black_box;
+
+const CODE_LINE: &str =
+    "        let total = items.iter().map(|item| item.price * item.quantity).sum::<f64>();\n";
+
+fn openai_lines(deltas: usize) -> Vec<String> {
+    let chunk = |delta: serde_json::Value, finish_reason: Option<&str>| {
+        let chunk = json!({
+            "id": "chatcmpl-bench",
+            "model": "gpt-4o",
+            "created": 1_700_000_000,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        });
+        format!("data: {}", chunk)
+    };
+
+    let mut lines = vec![chunk(
+        json!({"role": "assistant", "content": "```rust\n"}),
+        None,
+    )];
+    lines.extend((0..deltas).map(|_| chunk(json!({"content": CODE_LINE}), None)));
+    lines.push(chunk(
+        json!({"tool_calls": [{"index": 0, "id": "call_1", "type": "function",
+            "function": {"name": "developer__text_editor", "arguments": "{\"command\": \"write\", \"file_text\": \""}}]}),
+        None,
+    ));
+    lines.extend((0..deltas).map(|_| {
+        chunk(
+            json!({"tool_calls": [{"index": 0, "function": {"arguments": "let total = 0;\\n"}}]}),
+            None,
+        )
+    }));
+    lines.push(chunk(
+        json!({"tool_calls": [{"index": 0, "function": {"arguments": "\"}"}}]}),
+        Some("tool_calls"),
+    ));
+    lines.push("data: [DONE]".to_string());
+    lines
+}
+
+fn anthropic_lines(deltas: usize) -> Vec<String> {
+    let event = |event: serde_json::Value| format!("data: {}", event);
+
+    let mut lines = vec![
+        event(
+            json!({"type": "message_start", "message": {"id": "msg_bench", "model": "claude-sonnet-4",
+            "usage": {"input_tokens": 1000, "output_tokens": 1}}}),
+        ),
+        event(
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        ),
+    ];
+    lines.extend((0..deltas).map(|_| {
+        event(json!({"type": "content_block_delta", "index": 0,
+            "delta": {"type": "text_delta", "text": CODE_LINE}}))
+    }));
+    lines.push(event(json!({"type": "content_block_stop", "index": 0})));
+    lines.push(event(json!({"type": "content_block_start", "index": 1,
+        "content_block": {"type": "tool_use", "id": "toolu_1", "name": "developer__text_editor", "input": {}}})));
+    lines.push(event(json!({"type": "content_block_delta", "index": 1,
+        "delta": {"type": "input_json_delta", "partial_json": "{\"command\": \"write\", \"file_text\": \""}})));
+    lines.extend((0..deltas).map(|_| {
+        event(json!({"type": "content_block_delta", "index": 1,
+            "delta": {"type": "input_json_delta", "partial_json": "let total = 0;\\n"}}))
+    }));
+    lines.push(event(json!({"type": "content_block_delta", "index": 1,
+        "delta": {"type": "input_json_delta", "partial_json": "\"}"}})));
+    lines.push(event(json!({"type": "content_block_stop", "index": 1})));
+    lines.push(event(
+        json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"},
+        "usage": {"output_tokens": 5000}}),
+    ));
+    lines.push(event(json!({"type": "message_stop"})));
+    lines
+}
+
+async fn drain<S>(stream: S) -> usize
+where
+    S: Stream<Item = anyhow::Result<(Option<Message>, Option<ProviderUsage>)>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut messages = 0;
+    while let Some(item) = stream.next().await {
+        let (message, _usage) = item.expect("stream parses");
+        messages += usize::from(black_box(message).is_some());
+    }
+    messages
+}
+
+fn lines_stream(lines: Vec<String>) -> impl Stream<Item = anyhow::Result<String>> + Unpin {
+    futures::stream::iter(lines.into_iter().map(Ok::<String, anyhow::Error>))
+}
+
+fn bench_streaming(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("streaming_deltas");
+
+    for deltas in [100, 2_000] {
+        let lines = openai_lines(deltas);
+        group.throughput(Throughput::Elements(lines.len() as u64));
+        group.bench_with_input(BenchmarkId::new("openai", deltas), &lines, |b, lines| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| {
+                    runtime.block_on(drain(openai::response_to_streaming_message(lines_stream(
+                        lines,
+                    ))))
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        let lines = anthropic_lines(deltas);
+        group.throughput(Throughput::Elements(lines.len() as u64));
+        group.bench_with_input(BenchmarkId::new("anthropic", deltas), &lines, |b, lines| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| {
+                    runtime.block_on(drain(anthropic::response_to_streaming_message(
+                        lines_stream(lines),
+                    )))
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_streaming);
+criterion_main!(benches);
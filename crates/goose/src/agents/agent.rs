@@ -8,6 +8,7 @@ use futures::stream::BoxStream;
 use futures::{stream, FutureExt, Stream, StreamExt, TryStreamExt};
 use uuid::Uuid;
 
+use crate::agents::ask_user_tool::{ask_user_tool, ASK_USER_TOOL_NAME};
 use crate::agents::extension::{ExtensionConfig, ExtensionError, ExtensionResult, ToolInfo};
 use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
 use crate::agents::final_output_tool::{FINAL_OUTPUT_CONTINUATION_MESSAGE, FINAL_OUTPUT_TOOL_NAME};
@@ -26,20 +27,24 @@ use crate::agents::sub_recipe_manager::SubRecipeManager;
 use crate::agents::subagent_execution_tool::subagent_execute_task_tool::{
     self, SUBAGENT_EXECUTE_TASK_TOOL_NAME,
 };
+use crate::agents::subagent_execution_tool::task_usage::report_to_parent;
 use crate::agents::subagent_execution_tool::tasks_manager::TasksManager;
 use crate::agents::tool_route_manager::ToolRouteManager;
 use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
-use crate::agents::types::SessionConfig;
-use crate::agents::types::{FrontendTool, ToolResultReceiver};
-use crate::config::{Config, ExtensionConfigManager};
+use crate::agents::types::{FrontendTool, SessionConfig, StopReason, ToolResultReceiver};
+use crate::config::{Config, ExtensionConfigManager, PermissionManager};
 use crate::context_mgmt::auto_compact;
 use crate::conversation::{debug_conversation_fix, fix_conversation, Conversation};
 use crate::mcp_utils::ToolResult;
+use crate::permission::annotation_inspector::{
+    annotation_trust, AnnotatedTools, AnnotationInspector,
+};
 use crate::permission::permission_inspector::PermissionInspector;
 use crate::permission::permission_judge::PermissionCheckResult;
 use crate::permission::PermissionConfirmation;
 use crate::providers::base::Provider;
 use crate::providers::errors::ProviderError;
+use crate::providers::timeouts::RequestDeadline;
 use crate::recipe::{Author, Recipe, Response, Settings, SubRecipe};
 use crate::scheduler_trait::SchedulerTrait;
 use crate::security::security_inspector::SecurityInspector;
@@ -58,6 +63,7 @@ use tracing::{debug, error, info, instrument, warn};
 
 use super::final_output_tool::FinalOutputTool;
 use super::model_selector::autopilot::AutoPilot;
+use super::model_selector::task_router::{TaskCategory, TaskRouter};
 use super::platform_tools;
 use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
 use crate::agents::subagent_task_config::TaskConfig;
@@ -65,6 +71,8 @@ use crate::conversation::message::{Message, ToolRequest};
 use crate::session::SessionManager;
 
 const DEFAULT_MAX_TURNS: u32 = 1000;
+/// Times a request that timed out before the model said anything is sent again in one reply
+const MAX_TIMEOUT_RETRIES: u32 = 2;
 
 /// Context needed for the reply function
 pub struct ReplyContext {
@@ -103,6 +111,9 @@ pub struct Agent {
     pub(super) retry_manager: RetryManager,
     pub(super) tool_inspection_manager: ToolInspectionManager,
     pub(super) autopilot: Mutex<AutoPilot>,
+    pub(super) task_router: Mutex<TaskRouter>,
+    pub(super) stop_reason: Mutex<StopReason>,
+    pub(super) request_deadline: RequestDeadline,
 }
 
 #[derive(Clone, Debug)]
@@ -177,6 +188,9 @@ impl Agent {
             retry_manager: RetryManager::new(),
             tool_inspection_manager: Self::create_default_tool_inspection_manager(),
             autopilot: Mutex::new(AutoPilot::new()),
+            task_router: Mutex::new(TaskRouter::new()),
+            stop_reason: Mutex::new(StopReason::default()),
+            request_deadline: RequestDeadline::default(),
         }
     }
 
@@ -189,11 +203,19 @@ impl Agent {
 
         // Add permission inspector (medium-high priority)
         // Note: mode will be updated dynamically based on session config
-        tool_inspection_manager.add_inspector(Box::new(PermissionInspector::new(
-            "smart_approve".to_string(),
-            std::collections::HashSet::new(), // readonly tools - will be populated from extension manager
-            std::collections::HashSet::new(), // regular tools - will be populated from extension manager
-        )));
+        let permission_manager = Arc::new(Mutex::new(PermissionManager::default()));
+        tool_inspection_manager.add_inspector(Box::new(
+            PermissionInspector::with_permission_manager(
+                "smart_approve".to_string(),
+                std::collections::HashSet::new(), // readonly tools - populated from tool annotations
+                std::collections::HashSet::new(), // regular tools - will be populated from extension manager
+                permission_manager.clone(),
+            ),
+        ));
+
+        // Add annotation inspector (requires approval for destructive tools, even in auto mode)
+        tool_inspection_manager
+            .add_inspector(Box::new(AnnotationInspector::new(permission_manager)));
 
         // Add repetition inspector (lower priority - basic repetition checking)
         tool_inspection_manager.add_inspector(Box::new(RepetitionInspector::new(None)));
@@ -206,6 +228,16 @@ impl Agent {
         self.retry_manager.reset_attempts().await;
     }
 
+    /// Why the most recent reply stream ended
+    pub async fn stop_reason(&self) -> StopReason {
+        *self.stop_reason.lock().await
+    }
+
+    /// How long this agent's request to the model has left, once it is close to giving up
+    pub fn approaching_deadline(&self) -> Option<std::time::Duration> {
+        self.request_deadline.approaching()
+    }
+
     /// Increment the retry attempts counter and return the new value
     pub async fn increment_retry_attempts(&self) -> u32 {
         self.retry_manager.increment_attempts().await
@@ -230,9 +262,11 @@ impl Agent {
 
         match result {
             RetryResult::Retried => Ok(true),
-            RetryResult::Skipped
-            | RetryResult::MaxAttemptsReached
-            | RetryResult::SuccessChecksPassed => Ok(false),
+            RetryResult::MaxAttemptsReached => {
+                *self.stop_reason.lock().await = StopReason::ChecksFailed;
+                Ok(false)
+            }
+            RetryResult::Skipped | RetryResult::SuccessChecksPassed => Ok(false),
         }
     }
 
@@ -259,6 +293,15 @@ impl Agent {
         let (tools, toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
         let goose_mode = Self::determine_goose_mode(session.as_ref(), config);
 
+        let extensions = self.extension_manager.list_extensions().await?;
+        let bundled = self.extension_manager.list_bundled_extensions().await;
+        self.tool_inspection_manager
+            .update_tool_annotations(AnnotatedTools::from_tools(
+                &tools,
+                &annotation_trust(&extensions, &bundled),
+            ))
+            .await;
+
         // Update permission inspector mode to match the session mode
         self.tool_inspection_manager
             .update_permission_inspector_mode(goose_mode.clone())
@@ -363,6 +406,18 @@ impl Agent {
         self.frontend_tools.lock().await.get(name).cloned()
     }
 
+    /// Offer the model the `ask_user` tool. Its calls come back as frontend tool requests that
+    /// the client answers with `handle_tool_result`.
+    pub async fn enable_ask_user(&self) {
+        self.frontend_tools.lock().await.insert(
+            ASK_USER_TOOL_NAME.to_string(),
+            FrontendTool {
+                name: ASK_USER_TOOL_NAME.to_string(),
+                tool: ask_user_tool(),
+            },
+        );
+    }
+
     pub async fn add_final_output_tool(&self, response: Response) {
         let mut final_output_tool = self.final_output_tool.lock().await;
         let created_final_output_tool = FinalOutputTool::new(response);
@@ -371,6 +426,51 @@ impl Agent {
         self.extend_system_prompt(final_output_system_prompt).await;
     }
 
+    /// Stop one task of a sub-recipe or dynamic task run, leaving the others to finish
+    pub async fn cancel_task(&self, task_id: &str) -> Result<(), String> {
+        self.tasks_manager.cancel_task(task_id).await
+    }
+
+    /// Start the attempt one task of a sub-recipe or dynamic task run is making over
+    pub async fn restart_task(&self, task_id: &str) -> Result<(), String> {
+        self.tasks_manager.restart_task(task_id).await
+    }
+
+    /// Start no more tasks of sub-recipe or dynamic task runs until they're resumed; the tasks
+    /// already running finish
+    pub fn pause_task_scheduling(&self) {
+        self.tasks_manager.pause_scheduling();
+    }
+
+    pub fn resume_task_scheduling(&self) -> Result<(), String> {
+        self.tasks_manager.resume_scheduling()
+    }
+
+    /// Whether sub-recipe and dynamic tasks reuse the cached results of identical tasks that
+    /// completed before, instead of running again
+    pub fn set_cache_task_results(&self, cache: bool) {
+        self.tasks_manager.set_cache_results(cache);
+    }
+
+    /// Run again the tasks of the parallel run `run_id` that didn't complete, with this agent's
+    /// provider, sending their progress to `notifier`
+    pub async fn resume_tasks(
+        &self,
+        run_id: &str,
+        notifier: mpsc::Sender<ServerNotification>,
+        cancellation_token: Option<CancellationToken>,
+    ) -> Result<Value, String> {
+        let task_config = TaskConfig::new(self.provider().await.ok());
+        crate::agents::subagent_execution_tool::lib::resume_tasks(
+            run_id,
+            notifier,
+            task_config,
+            &self.tasks_manager,
+            cancellation_token,
+        )
+        .await
+    }
+
     pub async fn add_sub_recipes(&self, sub_recipes: Vec<SubRecipe>) {
         let mut sub_recipe_manager = self.sub_recipe_manager.lock().await;
         sub_recipe_manager.add_sub_recipe_tools(sub_recipes);
@@ -434,6 +534,8 @@ impl Agent {
         }
 
         debug!("WAITING_TOOL_START: {}", tool_call.name);
+        let filters = super::output_filters::filters_for(&tool_call);
+        let tool_lock = super::tool_locks::ToolLock::for_call(&tool_call);
         let result: ToolCallResult = if self
             .sub_recipe_manager
             .lock()
@@ -535,16 +637,22 @@ impl Agent {
         };
 
         debug!("WAITING_TOOL_END: {}", tool_call.name);
+        let session_id = self.extension_manager.get_context().await.session_id;
 
         (
             request_id,
             Ok(ToolCallResult {
                 notification_stream: result.notification_stream,
-                result: Box::new(
-                    result
-                        .result
-                        .map(super::large_response_handler::process_tool_response),
-                ),
+                result: Box::new(Box::pin(async move {
+                    let guard = tool_lock.acquire().await;
+                    let response = result.result.await;
+                    drop(guard);
+                    let response = super::output_filters::apply_filters(&filters, response).await;
+                    super::large_response_handler::process_tool_response(
+                        response,
+                        session_id.as_deref(),
+                    )
+                })),
             }),
         )
     }
@@ -759,6 +867,11 @@ impl Agent {
         prefixed_tools
     }
 
+    /// Names of the tools that always require approval because they are annotated as destructive
+    pub async fn destructive_tools(&self) -> std::collections::HashSet<String> {
+        self.tool_inspection_manager.destructive_tools().await
+    }
+
     pub async fn list_tools_for_router(&self) -> Vec<Tool> {
         self.tool_route_manager
             .list_tools_for_router(&self.extension_manager)
@@ -919,6 +1032,7 @@ impl Agent {
         } = context;
         let reply_span = tracing::Span::current();
         self.reset_retry_attempts().await;
+        *self.stop_reason.lock().await = StopReason::Completed;
 
         // This will need further refactoring. In the ideal world we pass the new message into
         // reply and load the existing conversation. Until we get to that point, fetch the conversation
@@ -971,6 +1085,7 @@ impl Agent {
         Ok(Box::pin(async_stream::try_stream! {
             let _ = reply_span.enter();
             let mut turns_taken = 0u32;
+            let mut timeout_retries = 0u32;
             let max_turns = session
                 .as_ref()
                 .and_then(|s| s.max_turns)
@@ -978,6 +1093,20 @@ impl Agent {
                     config.get_param("GOOSE_MAX_TURNS").unwrap_or(DEFAULT_MAX_TURNS)
                 });
 
+            {
+                let mut task_router = self.task_router.lock().await;
+                if let Some(decision) = task_router.route(&conversation, self.provider().await?).await? {
+                    debug!("Task router sending {} request to {}", decision.category, decision.model);
+                    self.update_provider(decision.provider).await?;
+
+                    let source = if decision.overridden { "route-override" } else { "route" };
+                    yield AgentEvent::ModelChange {
+                        model: decision.model,
+                        mode: format!("{}:{}", source, decision.category),
+                    };
+                }
+            }
+
             loop {
                 if is_token_cancelled(&cancel_token) {
                     break;
@@ -995,6 +1124,7 @@ impl Agent {
 
                 turns_taken += 1;
                 if turns_taken > max_turns {
+                    *self.stop_reason.lock().await = StopReason::MaxTurnsReached;
                     yield AgentEvent::Message(Message::assistant().with_text(
                         "I've reached the maximum number of actions I can do without user input. Would you like me to continue?"
                     ));
@@ -1014,17 +1144,30 @@ impl Agent {
                     }
                 }
 
-                let mut stream = Self::stream_response_from_provider(
+                let started = Self::stream_response_from_provider(
                     self.provider().await?,
                     &system_prompt,
                     conversation.messages(),
                     &tools,
                     &toolshim_tools,
-                ).await?;
+                    &self.request_deadline,
+                ).await;
+                let mut stream = match started {
+                    Ok(stream) => stream,
+                    Err(ProviderError::Timeout(details)) if timeout_retries < MAX_TIMEOUT_RETRIES => {
+                        timeout_retries += 1;
+                        turns_taken -= 1;
+                        warn!("Retrying the request ({}/{}): {}", timeout_retries, MAX_TIMEOUT_RETRIES, details);
+                        continue;
+                    }
+                    Err(e) => Err(e)?,
+                };
 
                 let mut no_tools_called = true;
                 let mut messages_to_add = Conversation::default();
                 let mut tools_updated = false;
+                let mut received = false;
+                let mut retry_after_timeout = false;
 
                 while let Some(next) = stream.next().await {
                     if is_token_cancelled(&cancel_token) {
@@ -1033,6 +1176,7 @@ impl Agent {
 
                     match next {
                         Ok((response, usage)) => {
+                            received = true;
                             // Emit model change event if provider is lead-worker
                             let provider = self.provider().await?;
                             if let Some(lead_worker) = provider.as_lead_worker() {
@@ -1060,6 +1204,10 @@ impl Agent {
                                     Self::update_session_metrics(session_config, usage).await?;
                                 }
                             }
+                            // A sub-recipe's run reports what it spent to the task that started it
+                            if let Some(ref usage) = usage {
+                                report_to_parent(provider.as_ref(), usage).await;
+                            }
 
                             if let Some(response) = response {
                                 messages_to_add.push(response.clone());
@@ -1149,10 +1297,12 @@ impl Agent {
                                     let tool_futures_arc = Arc::new(Mutex::new(tool_futures));
 
                                     // Process tools requiring approval
+                                    let edited_arguments = Arc::new(Mutex::new(HashMap::new()));
                                     let mut tool_approval_stream = self.handle_approval_tool_requests(
                                         &permission_check_result.needs_approval,
                                         tool_futures_arc.clone(),
                                         message_tool_response.clone(),
+                                        edited_arguments.clone(),
                                         cancel_token.clone(),
                                         &inspection_results,
                                     );
@@ -1161,6 +1311,18 @@ impl Agent {
                                         yield AgentEvent::Message(msg);
                                     }
 
+                                    // Keep the arguments the user edited in the history, so the model sees what ran
+                                    let edited_arguments = edited_arguments.lock().await.clone();
+                                    if !edited_arguments.is_empty() {
+                                        messages_to_add = Conversation::new_unvalidated(
+                                            messages_to_add.messages().iter().cloned().map(|message| {
+                                                edited_arguments.iter().fold(message, |message, (id, arguments)| {
+                                                    message.with_tool_call_arguments(id, arguments)
+                                                })
+                                            }),
+                                        );
+                                    }
+
                                     tool_futures = {
                                         let mut futures_lock = tool_futures_arc.lock().await;
                                         futures_lock.drain(..).collect::<Vec<_>>()
@@ -1237,6 +1399,13 @@ impl Agent {
                                 }
                             }
                         }
+                        // Nothing was shown yet, so the request can go again as if it never went
+                        Err(ProviderError::Timeout(details)) if !received && timeout_retries < MAX_TIMEOUT_RETRIES => {
+                            timeout_retries += 1;
+                            warn!("Retrying the request ({}/{}): {}", timeout_retries, MAX_TIMEOUT_RETRIES, details);
+                            retry_after_timeout = true;
+                            break;
+                        }
                         Err(e) => {
                             error!("Error: {}", e);
                             yield AgentEvent::Message(Message::assistant().with_text(
@@ -1246,6 +1415,10 @@ impl Agent {
                         }
                     }
                 }
+                if retry_after_timeout {
+                    turns_taken -= 1;
+                    continue;
+                }
                 if tools_updated {
                     (tools, toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
                 }
@@ -1316,6 +1489,15 @@ impl Agent {
         prompt_manager.add_system_prompt_extra(instruction);
     }
 
+    /// Route the next user message to `category`'s model instead of classifying it
+    pub async fn set_route_override(&self, category: Option<TaskCategory>) {
+        self.task_router.lock().await.set_override(category);
+    }
+
+    pub async fn is_task_routing_enabled(&self) -> bool {
+        self.task_router.lock().await.is_enabled()
+    }
+
     pub async fn update_provider(&self, provider: Arc<dyn Provider>) -> Result<()> {
         let mut current_provider = self.provider.lock().await;
         *current_provider = Some(provider.clone());
@@ -1670,6 +1852,10 @@ mod tests {
             inspector_names.contains(&"security"),
             "Tool inspection manager should contain security inspector"
         );
+        assert!(
+            inspector_names.contains(&"annotations"),
+            "Tool inspection manager should contain annotation inspector"
+        );
 
         Ok(())
     }
@@ -0,0 +1,291 @@
+//! The `ask_user` tool, for when the model needs the user to settle something.
+
+use indoc::indoc;
+use rmcp::model::{JsonObject, Tool, ToolAnnotations};
+use rmcp::object;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const ASK_USER_TOOL_NAME: &str = "ask_user";
+pub const ASK_USER_DEFAULTS_KEY: &str = "GOOSE_ASK_USER_DEFAULTS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    Choice,
+    Text,
+    File,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Question {
+    /// Stable key for the question, which `GOOSE_ASK_USER_DEFAULTS` answers are looked up by
+    pub id: Option<String>,
+    pub question: String,
+    pub kind: QuestionKind,
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Whether more than one option may be picked
+    #[serde(default)]
+    pub multiple: bool,
+    pub default: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Choice(Vec<String>),
+    Text(String),
+    File(PathBuf),
+}
+
+pub fn ask_user_tool() -> Tool {
+    Tool::new(
+        ASK_USER_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Ask the user a clarifying question and wait for the answer. Use it only when you
+            can't reasonably go on without their decision, not to confirm things you could
+            check yourself.
+
+            kinds:
+            - choice: the user picks from `options` (several of them when `multiple` is true)
+            - text: the user types an answer
+            - file: the user names a file that exists
+
+            Give the question a short `id` (e.g. "deploy_target") so unattended runs can be
+            configured to answer it, and a `default` when there is a sensible one. The answer
+            comes back as JSON: {"answer": ...}, a list for multiple choice and a path for
+            files.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "required": ["question", "kind"],
+            "properties": {
+                "id": {"type": "string", "description": "Short stable key for the question"},
+                "question": {"type": "string"},
+                "kind": {"type": "string", "enum": ["choice", "text", "file"]},
+                "options": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "The options for a choice; at least two"
+                },
+                "multiple": {"type": "boolean", "default": false},
+                "default": {
+                    "description": "The answer to use when nobody can be asked: an option (or list of options), text, or a path"
+                }
+            }
+        }),
+    )
+    .annotate(ToolAnnotations {
+        title: Some("Ask the user".to_string()),
+        read_only_hint: Some(true),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(false),
+        open_world_hint: Some(false),
+    })
+}
+
+impl Question {
+    pub fn from_arguments(arguments: &JsonObject) -> Result<Self, String> {
+        let question: Question = serde_json::from_value(Value::Object(arguments.clone()))
+            .map_err(|e| format!("Invalid question: {}", e))?;
+        if question.question.trim().is_empty() {
+            return Err("The question is empty".to_string());
+        }
+        if question.kind == QuestionKind::Choice && question.options.len() < 2 {
+            return Err("A choice needs at least two options".to_string());
+        }
+        if let Some(default) = &question.default {
+            question
+                .answer_from(default)
+                .map_err(|e| format!("Invalid default: {}", e))?;
+        }
+        Ok(question)
+    }
+
+    /// Check a given answer, from the config or the question's default, against the question
+    pub fn answer_from(&self, value: &Value) -> Result<Answer, String> {
+        match self.kind {
+            QuestionKind::Choice => {
+                let picked = match value {
+                    Value::String(option) => vec![option.clone()],
+                    Value::Array(options) => options
+                        .iter()
+                        .map(|option| {
+                            option
+                                .as_str()
+                                .map(str::to_string)
+                                .ok_or_else(|| "options must be strings".to_string())
+                        })
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err("expected an option".to_string()),
+                };
+                self.choice(picked)
+            }
+            QuestionKind::Text => match value {
+                Value::String(text) => Ok(Answer::Text(text.clone())),
+                _ => Err("expected text".to_string()),
+            },
+            QuestionKind::File => match value {
+                Value::String(path) => Ok(Answer::File(PathBuf::from(path))),
+                _ => Err("expected a path".to_string()),
+            },
+        }
+    }
+
+    pub fn choice(&self, picked: Vec<String>) -> Result<Answer, String> {
+        if picked.is_empty() {
+            return Err("nothing was picked".to_string());
+        }
+        if !self.multiple && picked.len() > 1 {
+            return Err("only one option may be picked".to_string());
+        }
+        if let Some(unknown) = picked.iter().find(|option| !self.options.contains(option)) {
+            return Err(format!("{} is not one of the options", unknown));
+        }
+        Ok(Answer::Choice(picked))
+    }
+
+    /// The answer for a run nobody can be asked in: the configured one for this question's id,
+    /// then the question's default
+    pub fn unattended_answer(&self, defaults: &HashMap<String, Value>) -> Result<Answer, String> {
+        let configured = self.id.as_ref().and_then(|id| defaults.get(id));
+        match configured.or(self.default.as_ref()) {
+            Some(value) => self.answer_from(value),
+            None => Err(match &self.id {
+                Some(id) => format!(
+                    "Nobody can answer \"{}\" in this run. Set {}.{} in the config to answer it.",
+                    self.question, ASK_USER_DEFAULTS_KEY, id
+                ),
+                None => format!(
+                    "Nobody can answer \"{}\" in this run, and it has no id to configure an answer for.",
+                    self.question
+                ),
+            }),
+        }
+    }
+}
+
+/// Whether `path` names an existing file, relative paths taken from `working_dir`
+pub fn resolve_file(path: &str, working_dir: &Path) -> Result<PathBuf, String> {
+    let path = working_dir.join(path.trim());
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!("{} is not a file", path.display()))
+    }
+}
+
+impl Answer {
+    pub fn to_value(&self, question: &Question) -> Value {
+        match self {
+            Answer::Choice(picked) if !question.multiple => json!({"answer": picked[0]}),
+            Answer::Choice(picked) => json!({"answer": picked}),
+            Answer::Text(text) => json!({"answer": text}),
+            Answer::File(path) => json!({"answer": path}),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(arguments: Value) -> Result<Question, String> {
+        Question::from_arguments(arguments.as_object().unwrap())
+    }
+
+    #[test]
+    fn test_from_arguments() {
+        let choice = question(json!({
+            "id": "target",
+            "question": "Deploy where?",
+            "kind": "choice",
+            "options": ["staging", "production"],
+            "default": "staging"
+        }))
+        .unwrap();
+        assert_eq!(choice.kind, QuestionKind::Choice);
+
+        assert!(
+            question(json!({"question": "Which?", "kind": "choice", "options": ["a"]})).is_err()
+        );
+        assert!(question(json!({"question": "", "kind": "text"})).is_err());
+        assert!(question(json!({
+            "question": "Which?",
+            "kind": "choice",
+            "options": ["a", "b"],
+            "default": "c"
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_choice() {
+        let single =
+            question(json!({"question": "Which?", "kind": "choice", "options": ["a", "b"]}))
+                .unwrap();
+        assert_eq!(
+            single.answer_from(&json!("b")).unwrap().to_value(&single),
+            json!({"answer": "b"})
+        );
+        assert!(single
+            .choice(vec!["a".to_string(), "b".to_string()])
+            .is_err());
+
+        let multiple = question(json!({
+            "question": "Which?",
+            "kind": "choice",
+            "options": ["a", "b"],
+            "multiple": true
+        }))
+        .unwrap();
+        assert_eq!(
+            multiple
+                .answer_from(&json!(["a", "b"]))
+                .unwrap()
+                .to_value(&multiple),
+            json!({"answer": ["a", "b"]})
+        );
+        assert!(multiple.choice(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_unattended_answer() {
+        let asked = question(json!({
+            "id": "name",
+            "question": "Project name?",
+            "kind": "text",
+            "default": "demo"
+        }))
+        .unwrap();
+        assert_eq!(
+            asked.unattended_answer(&HashMap::new()),
+            Ok(Answer::Text("demo".to_string()))
+        );
+        let configured = HashMap::from([("name".to_string(), json!("goose"))]);
+        assert_eq!(
+            asked.unattended_answer(&configured),
+            Ok(Answer::Text("goose".to_string()))
+        );
+
+        let unanswerable =
+            question(json!({"id": "name", "question": "Project name?", "kind": "text"})).unwrap();
+        let error = unanswerable.unattended_answer(&HashMap::new()).unwrap_err();
+        assert!(error.contains("GOOSE_ASK_USER_DEFAULTS.name"));
+    }
+
+    #[test]
+    fn test_resolve_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+        assert_eq!(
+            resolve_file("notes.md", dir.path()),
+            Ok(dir.path().join("notes.md"))
+        );
+        assert!(resolve_file("missing.md", dir.path()).is_err());
+        assert!(resolve_file(".", dir.path()).is_err());
+    }
+}
@@ -0,0 +1,675 @@
+//! The client for streamable HTTP and SSE extensions that signed in with OAuth.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use rmcp::model::{
+    CallToolResult, ErrorCode, ErrorData, GetPromptResult, InitializeResult, JsonObject,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, ReadResourceResult,
+    ServerNotification,
+};
+use rmcp::service::ClientInitializeError;
+use rmcp::transport::auth::AuthClient;
+use rmcp::transport::sse_client::SseClientConfig;
+use rmcp::transport::streamable_http_client::{
+    StreamableHttpClientTransportConfig, StreamableHttpError,
+};
+use rmcp::transport::{DynamicTransportError, SseClientTransport, StreamableHttpClientTransport};
+use rmcp::ServiceError;
+use serde_json::Value;
+use tokio::sync::{mpsc, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use super::mcp_client::{Error, McpClient, McpClientTrait};
+use crate::oauth::events::redact;
+use crate::oauth::resource_metadata::challenge_param;
+use crate::oauth::TokenRefresher;
+
+/// How the extension talks to its server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    StreamableHttp,
+    Sse,
+}
+
+pub struct AuthorizedClient {
+    inner: RwLock<McpClient>,
+    info: Option<InitializeResult>,
+    transport: Transport,
+    uri: String,
+    timeout: Duration,
+    refresher: TokenRefresher,
+}
+
+async fn connect_with(
+    transport: Transport,
+    uri: &str,
+    timeout: Duration,
+    refresher: &TokenRefresher,
+) -> Result<McpClient, ClientInitializeError> {
+    let client = AuthClient {
+        http_client: reqwest::Client::default(),
+        auth_manager: refresher.auth_manager(),
+    };
+    match transport {
+        Transport::StreamableHttp => {
+            let transport = StreamableHttpClientTransport::with_client(
+                client,
+                StreamableHttpClientTransportConfig {
+                    uri: uri.to_string().into(),
+                    ..Default::default()
+                },
+            );
+            McpClient::connect(transport, timeout).await
+        }
+        Transport::Sse => {
+            let transport = SseClientTransport::start_with_client(
+                client,
+                SseClientConfig {
+                    sse_endpoint: uri.to_string().into(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| {
+                ClientInitializeError::transport::<SseClientTransport<AuthClient<reqwest::Client>>>(
+                    e, "connect",
+                )
+            })?;
+            McpClient::connect(transport, timeout).await
+        }
+    }
+}
+
+/// Whether an HTTP error says the server wants a (different) access token: a 401 status as
+/// reqwest reports it, or rmcp asking for authorization. A 403 is a refusal the token can't
+/// change, unless it is an `insufficient_scope`, which [`insufficient_scope`] handles.
+pub(crate) fn mentions_unauthorized(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "401 unauthorized",
+        "auth required",
+        "authorization required",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+/// Whether the server answered the request with 401, rejecting the access token. Only an
+/// HTTP response says so; a closed transport or a failed send doesn't.
+fn is_unauthorized(error: &Error) -> bool {
+    let ServiceError::TransportSend(DynamicTransportError { error, .. }) = error else {
+        return false;
+    };
+    match error.downcast_ref::<StreamableHttpError<reqwest::Error>>() {
+        // The 401 came with a WWW-Authenticate challenge
+        Some(StreamableHttpError::AuthRequired(_)) => true,
+        Some(StreamableHttpError::Client(e)) => e.status() == Some(StatusCode::UNAUTHORIZED),
+        Some(_) => false,
+        // The SSE transport only reports the status in its message
+        None => mentions_unauthorized(&error.to_string()),
+    }
+}
+
+/// The scopes named by an `insufficient_scope` error, which say what the token lacks
+fn scopes_refused(message: &str) -> Option<Vec<String>> {
+    if !message.to_lowercase().contains("insufficient_scope") {
+        return None;
+    }
+    let scopes: Vec<String> = challenge_param(message, "scope")?
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    Some(scopes).filter(|scopes| !scopes.is_empty())
+}
+
+/// The scopes a request was refused for, when the server answered that the token lacks some
+fn insufficient_scope(error: &Error) -> Option<Vec<String>> {
+    let ServiceError::TransportSend(DynamicTransportError { error, .. }) = error else {
+        return None;
+    };
+    match error.downcast_ref::<StreamableHttpError<reqwest::Error>>() {
+        // The message leaves the challenge out
+        Some(StreamableHttpError::AuthRequired(challenge)) => {
+            scopes_refused(&challenge.www_authenticate_header)
+        }
+        _ => scopes_refused(&error.to_string()),
+    }
+}
+
+impl AuthorizedClient {
+    /// Connect to a streamable HTTP server
+    pub async fn connect(
+        uri: &str,
+        timeout: Duration,
+        refresher: TokenRefresher,
+    ) -> Result<Self, ClientInitializeError> {
+        Self::connect_over(Transport::StreamableHttp, uri, timeout, refresher).await
+    }
+
+    /// Connect to an SSE server; `uri` is its event stream
+    pub async fn connect_sse(
+        uri: &str,
+        timeout: Duration,
+        refresher: TokenRefresher,
+    ) -> Result<Self, ClientInitializeError> {
+        Self::connect_over(Transport::Sse, uri, timeout, refresher).await
+    }
+
+    async fn connect_over(
+        transport: Transport,
+        uri: &str,
+        timeout: Duration,
+        refresher: TokenRefresher,
+    ) -> Result<Self, ClientInitializeError> {
+        let client = connect_with(transport, uri, timeout, &refresher).await?;
+        Ok(Self {
+            info: client.get_info().cloned(),
+            inner: RwLock::new(client),
+            transport,
+            uri: uri.to_string(),
+            timeout,
+            refresher,
+        })
+    }
+
+    /// After a failed request: if the token may have been rejected, refresh it, or sign in
+    /// again for the scopes it lacked, and reconnect. True when the request should be sent again.
+    /// When the user has to sign in again, `result` says so instead of failing with the bare 401.
+    async fn should_retry<T>(&self, result: &mut Result<T, Error>) -> bool {
+        let Err(error) = result else {
+            return false;
+        };
+        let reauthorized = if let Some(scopes) = insufficient_scope(error) {
+            self.refresher.request_scopes(&scopes).await
+        } else if is_unauthorized(error) {
+            self.refresher.refresh().await
+        } else {
+            return false;
+        };
+        if let Err(e) = reauthorized {
+            warn!(
+                "Could not reauthorize {}: {}",
+                self.uri,
+                redact(&e.to_string())
+            );
+            if let Some(hint) = self.refresher.sign_in_hint() {
+                *result = Err(ServiceError::McpError(ErrorData::new(
+                    ErrorCode::INVALID_REQUEST,
+                    format!(
+                        "{} no longer accepts the sign-in: {}; {}",
+                        self.uri, e, hint
+                    ),
+                    None,
+                )));
+            }
+            return false;
+        }
+        match connect_with(self.transport, &self.uri, self.timeout, &self.refresher).await {
+            Ok(client) => {
+                *self.inner.write().await = client;
+                true
+            }
+            Err(e) => {
+                warn!("Could not reconnect to {}: {}", self.uri, e);
+                false
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl McpClientTrait for AuthorizedClient {
+    async fn list_resources(
+        &self,
+        next_cursor: Option<String>,
+        cancel_token: CancellationToken,
+    ) -> Result<ListResourcesResult, Error> {
+        self.refresher.refresh_if_expiring().await;
+        let mut result = self
+            .inner
+            .read()
+            .await
+            .list_resources(next_cursor.clone(), cancel_token.clone())
+            .await;
+        if !self.should_retry(&mut result).await {
+            return result;
+        }
+        self.inner
+            .read()
+            .await
+            .list_resources(next_cursor, cancel_token)
+            .await
+    }
+
+    async fn read_resource(
+        &self,
+        uri: &str,
+        cancel_token: CancellationToken,
+    ) -> Result<ReadResourceResult, Error> {
+        self.refresher.refresh_if_expiring().await;
+        let mut result = self
+            .inner
+            .read()
+            .await
+            .read_resource(uri, cancel_token.clone())
+            .await;
+        if !self.should_retry(&mut result).await {
+            return result;
+        }
+        self.inner
+            .read()
+            .await
+            .read_resource(uri, cancel_token)
+            .await
+    }
+
+    async fn list_tools(
+        &self,
+        next_cursor: Option<String>,
+        cancel_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        self.refresher.refresh_if_expiring().await;
+        let mut result = self
+            .inner
+            .read()
+            .await
+            .list_tools(next_cursor.clone(), cancel_token.clone())
+            .await;
+        if !self.should_retry(&mut result).await {
+            return result;
+        }
+        self.inner
+            .read()
+            .await
+            .list_tools(next_cursor, cancel_token)
+            .await
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        cancel_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        self.refresher.refresh_if_expiring().await;
+        let mut result = self
+            .inner
+            .read()
+            .await
+            .call_tool(name, arguments.clone(), cancel_token.clone())
+            .await;
+        if !self.should_retry(&mut result).await {
+            return result;
+        }
+        self.inner
+            .read()
+            .await
+            .call_tool(name, arguments, cancel_token)
+            .await
+    }
+
+    async fn list_prompts(
+        &self,
+        next_cursor: Option<String>,
+        cancel_token: CancellationToken,
+    ) -> Result<ListPromptsResult, Error> {
+        self.refresher.refresh_if_expiring().await;
+        let mut result = self
+            .inner
+            .read()
+            .await
+            .list_prompts(next_cursor.clone(), cancel_token.clone())
+            .await;
+        if !self.should_retry(&mut result).await {
+            return result;
+        }
+        self.inner
+            .read()
+            .await
+            .list_prompts(next_cursor, cancel_token)
+            .await
+    }
+
+    async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: Value,
+        cancel_token: CancellationToken,
+    ) -> Result<GetPromptResult, Error> {
+        self.refresher.refresh_if_expiring().await;
+        let mut result = self
+            .inner
+            .read()
+            .await
+            .get_prompt(name, arguments.clone(), cancel_token.clone())
+            .await;
+        if !self.should_retry(&mut result).await {
+            return result;
+        }
+        self.inner
+            .read()
+            .await
+            .get_prompt(name, arguments, cancel_token)
+            .await
+    }
+
+    /// Notifications of the connection at the time; a request retried on a new connection
+    /// reports its progress there
+    async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+        self.inner.read().await.subscribe().await
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        self.info.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use axum::extract::{Query, State};
+    use axum::http::header;
+    use axum::response::sse::{Event, Sse};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Form, Json, Router};
+    use futures::StreamExt;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    use crate::oauth::client_credentials::ClientCredentials;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    const INVALID_TOKEN: &str = r#"Bearer error="invalid_token""#;
+
+    /// An MCP server behind OAuth, with its token endpoint, that refuses the first `refusals`
+    /// tool calls with a 401 and `challenge`
+    struct MockServer {
+        base: String,
+        challenge: String,
+        refusals: AtomicUsize,
+        calls: AtomicUsize,
+        initializes: AtomicUsize,
+        /// The `scope` of each token request
+        token_scopes: Mutex<Vec<String>>,
+        /// Where the SSE sessions get their responses, by session id
+        sessions: Mutex<HashMap<String, mpsc::UnboundedSender<Value>>>,
+    }
+
+    enum Answer {
+        Accepted,
+        Refused,
+        Reply(Value),
+    }
+
+    impl MockServer {
+        async fn start(refusals: usize, challenge: &str) -> Arc<Self> {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let server = Arc::new(Self {
+                base: format!("http://{}", listener.local_addr().unwrap()),
+                challenge: challenge.to_string(),
+                refusals: AtomicUsize::new(refusals),
+                calls: AtomicUsize::new(0),
+                initializes: AtomicUsize::new(0),
+                token_scopes: Mutex::new(Vec::new()),
+                sessions: Mutex::new(HashMap::new()),
+            });
+            let app = Router::new()
+                .route("/.well-known/oauth-authorization-server", get(metadata))
+                .route("/token", post(token))
+                .route("/mcp", post(streamable_http))
+                .route("/sse", get(events))
+                .route("/message", post(message))
+                .with_state(server.clone());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            server
+        }
+
+        fn answer(&self, message: &Value) -> Answer {
+            let Some(id) = message.get("id") else {
+                return Answer::Accepted;
+            };
+            let result = match message["method"].as_str() {
+                Some("initialize") => {
+                    self.initializes.fetch_add(1, Ordering::SeqCst);
+                    json!({
+                        "protocolVersion": "2025-03-26",
+                        "capabilities": {"tools": {}},
+                        "serverInfo": {"name": "mock", "version": "1.0.0"}
+                    })
+                }
+                Some("tools/call") => {
+                    self.calls.fetch_add(1, Ordering::SeqCst);
+                    let refused = self
+                        .refusals
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                        .is_ok();
+                    if refused {
+                        return Answer::Refused;
+                    }
+                    json!({"content": [{"type": "text", "text": "done"}], "isError": false})
+                }
+                _ => json!({}),
+            };
+            Answer::Reply(json!({"jsonrpc": "2.0", "id": id, "result": result}))
+        }
+
+        fn refusal(&self) -> Response {
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, self.challenge.clone())],
+            )
+                .into_response()
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        fn initializes(&self) -> usize {
+            self.initializes.load(Ordering::SeqCst)
+        }
+
+        fn token_scopes(&self) -> Vec<String> {
+            self.token_scopes.lock().unwrap().clone()
+        }
+
+        async fn refresher(&self, uri: &str) -> TokenRefresher {
+            let credentials = ClientCredentials {
+                client_id: "service-account".to_string(),
+                client_secret: "s3cret".to_string(),
+                scope: Some("files:read".to_string()),
+                token_url: Some(format!("{}/token", self.base)),
+            };
+            TokenRefresher::client_credentials(uri, "mock", credentials, reqwest::Client::new())
+                .await
+                .unwrap()
+        }
+    }
+
+    async fn metadata(State(server): State<Arc<MockServer>>) -> Json<Value> {
+        Json(json!({
+            "issuer": server.base,
+            "authorization_endpoint": format!("{}/authorize", server.base),
+            "token_endpoint": format!("{}/token", server.base),
+            "registration_endpoint": format!("{}/register", server.base),
+            "response_types_supported": ["code"],
+            "code_challenge_methods_supported": ["S256"]
+        }))
+    }
+
+    async fn token(
+        State(server): State<Arc<MockServer>>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Json<Value> {
+        let mut scopes = server.token_scopes.lock().unwrap();
+        scopes.push(form.get("scope").cloned().unwrap_or_default());
+        Json(json!({
+            "access_token": format!("token-{}", scopes.len()),
+            "token_type": "bearer",
+            "expires_in": 3600
+        }))
+    }
+
+    async fn streamable_http(
+        State(server): State<Arc<MockServer>>,
+        Json(message): Json<Value>,
+    ) -> Response {
+        match server.answer(&message) {
+            Answer::Accepted => StatusCode::ACCEPTED.into_response(),
+            Answer::Refused => server.refusal(),
+            Answer::Reply(reply) => Json(reply).into_response(),
+        }
+    }
+
+    /// The event stream of a new SSE session, which starts by naming where to post messages
+    async fn events(State(server): State<Arc<MockServer>>) -> Response {
+        let (sender, receiver) = mpsc::unbounded_channel::<Value>();
+        let session = {
+            let mut sessions = server.sessions.lock().unwrap();
+            let session = sessions.len().to_string();
+            sessions.insert(session.clone(), sender);
+            session
+        };
+        let endpoint = Event::default()
+            .event("endpoint")
+            .data(format!("/message?sessionId={}", session));
+        let replies = UnboundedReceiverStream::new(receiver)
+            .map(|reply| Event::default().event("message").data(reply.to_string()));
+        let stream = futures::stream::once(async { endpoint })
+            .chain(replies)
+            .map(Ok::<_, Infallible>);
+        Sse::new(stream).into_response()
+    }
+
+    async fn message(
+        State(server): State<Arc<MockServer>>,
+        Query(query): Query<HashMap<String, String>>,
+        Json(message): Json<Value>,
+    ) -> Response {
+        match server.answer(&message) {
+            Answer::Accepted => {}
+            Answer::Refused => return server.refusal(),
+            Answer::Reply(reply) => {
+                let sessions = server.sessions.lock().unwrap();
+                let _ = sessions[&query["sessionId"]].send(reply);
+            }
+        }
+        StatusCode::ACCEPTED.into_response()
+    }
+
+    async fn call(client: &AuthorizedClient) -> Result<CallToolResult, Error> {
+        client
+            .call_tool("search", None, CancellationToken::new())
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_refused_call_is_replayed_once() {
+        let server = MockServer::start(1, INVALID_TOKEN).await;
+        let uri = format!("{}/mcp", server.base);
+        let client = AuthorizedClient::connect(&uri, TIMEOUT, server.refresher(&uri).await)
+            .await
+            .unwrap();
+
+        assert!(call(&client).await.is_ok());
+        assert_eq!(server.calls(), 2);
+        // A new token, sent on a new connection
+        assert_eq!(server.token_scopes().len(), 2);
+        assert_eq!(server.initializes(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_second_refusal_fails() {
+        let server = MockServer::start(usize::MAX, INVALID_TOKEN).await;
+        let uri = format!("{}/mcp", server.base);
+        let client = AuthorizedClient::connect(&uri, TIMEOUT, server.refresher(&uri).await)
+            .await
+            .unwrap();
+
+        let error = call(&client).await.unwrap_err();
+        assert!(is_unauthorized(&error));
+        assert_eq!(server.calls(), 2);
+        assert_eq!(server.token_scopes().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_insufficient_scope_reauthorizes() {
+        let server = MockServer::start(
+            1,
+            r#"Bearer error="insufficient_scope", scope="files:write""#,
+        )
+        .await;
+        let uri = format!("{}/mcp", server.base);
+        let client = AuthorizedClient::connect(&uri, TIMEOUT, server.refresher(&uri).await)
+            .await
+            .unwrap();
+
+        assert!(call(&client).await.is_ok());
+        assert_eq!(server.calls(), 2);
+        assert_eq!(
+            server.token_scopes(),
+            vec![
+                "files:read".to_string(),
+                "files:read files:write".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sse_reconnects() {
+        let server = MockServer::start(1, INVALID_TOKEN).await;
+        let uri = format!("{}/sse", server.base);
+        let client = AuthorizedClient::connect_sse(&uri, TIMEOUT, server.refresher(&uri).await)
+            .await
+            .unwrap();
+
+        assert!(call(&client).await.is_ok());
+        assert_eq!(server.calls(), 2);
+        assert_eq!(server.sessions.lock().unwrap().len(), 2);
+        assert_eq!(server.initializes(), 2);
+    }
+
+    #[test]
+    fn test_is_unauthorized() {
+        // The request may have reached the server before the connection dropped
+        assert!(!is_unauthorized(&ServiceError::TransportClosed));
+        // An error the server answered with is not a rejected token
+        assert!(!is_unauthorized(&ServiceError::McpError(ErrorData::new(
+            ErrorCode::INTERNAL_ERROR,
+            "401 Unauthorized",
+            None
+        ))));
+        assert!(mentions_unauthorized(
+            "HTTP status client error (401 Unauthorized) for url (https://mcp.example.com/mcp)"
+        ));
+        assert!(mentions_unauthorized("Auth required"));
+        // A policy refusal, and numbers that happen to read 401, don't call for a new token
+        assert!(!mentions_unauthorized(
+            "Unexpected status 403 Forbidden while opening the event stream"
+        ));
+        assert!(!mentions_unauthorized("failed to read chunk 401 of 512"));
+        assert!(!mentions_unauthorized("connection reset by peer"));
+    }
+
+    #[test]
+    fn test_scopes_refused() {
+        assert_eq!(
+            scopes_refused(
+                r#"403 Forbidden: Bearer error="insufficient_scope", scope="files:read files:write""#
+            ),
+            Some(vec!["files:read".to_string(), "files:write".to_string()])
+        );
+        assert_eq!(scopes_refused(r#"401: Bearer error="invalid_token""#), None);
+        assert_eq!(scopes_refused(r#"error="insufficient_scope""#), None);
+        assert_eq!(insufficient_scope(&ServiceError::TransportClosed), None);
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Ok;
 
 use crate::conversation::message::{Message, MessageMetadata};
 use crate::conversation::Conversation;
-use crate::token_counter::create_async_token_counter;
+use crate::token_counter::{create_async_token_counter, ContextBreakdown};
 
 use crate::context_mgmt::summarize::summarize_messages;
 use crate::context_mgmt::truncate::{truncate_messages, OldestFirstTruncation};
@@ -11,6 +11,25 @@ use crate::context_mgmt::{estimate_target_context_limit, get_messages_token_coun
 use super::super::agents::Agent;
 
 impl Agent {
+    /// Estimate how the context window is split between the system prompt, tool schemas,
+    /// conversation history and tool results for the next request.
+    pub async fn context_breakdown(
+        &self,
+        messages: &[Message],
+    ) -> Result<ContextBreakdown, anyhow::Error> {
+        let (tools, _toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
+        let token_counter = create_async_token_counter()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create token counter: {}", e))?;
+        let visible: Vec<Message> = messages
+            .iter()
+            .filter(|m| m.metadata.agent_visible)
+            .cloned()
+            .collect();
+
+        Ok(token_counter.count_chat_tokens_by_category(&system_prompt, &visible, &tools))
+    }
+
     /// Public API to truncate oldest messages so that the conversation's token count is within the allowed context limit.
     pub async fn truncate_context(
         &self,
@@ -1,10 +1,13 @@
+use crate::agents::timer_extension;
 use crate::agents::todo_extension;
 use std::collections::HashMap;
 
 use crate::agents::mcp_client::McpClientTrait;
+use crate::agents::remote_auth::RemoteAuth;
 use crate::config;
 use crate::config::extensions::name_to_key;
 use crate::config::permission::PermissionLevel;
+use crate::oauth::http::OAuthHttpSettings;
 use once_cell::sync::Lazy;
 use rmcp::model::Tool;
 use rmcp::service::ClientInitializeError;
@@ -15,7 +18,7 @@ use tracing::warn;
 use utoipa::ToSchema;
 
 #[derive(Error, Debug)]
-#[error("process quit before initialization: stderr = {stderr}")]
+#[error("server crashed before finishing initialization, startup log: {stderr}")]
 pub struct ProcessExit {
     stderr: String,
     #[source]
@@ -49,6 +52,18 @@ pub static PLATFORM_EXTENSIONS: Lazy<HashMap<&'static str, PlatformExtensionDef>
             },
         );
 
+        map.insert(
+            timer_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: timer_extension::EXTENSION_NAME,
+                description:
+                    "Let Goose set timers to check on slow work later instead of waiting for it",
+                // Headless runs wait for the timers the agent sets, so they're opt-in
+                default_enabled: false,
+                client_factory: |ctx| Box::new(timer_extension::TimerClient::new(ctx).unwrap()),
+            },
+        );
+
         map
     });
 
@@ -82,6 +97,9 @@ pub enum ExtensionError {
     InitializeError(#[from] ClientInitializeError),
     #[error("{0}")]
     ProcessExit(#[from] ProcessExit),
+    /// The server is still running but never answered `initialize`
+    #[error("server is running but did not finish initializing within {timeout}s (raise GOOSE_EXTENSION_INIT_TIMEOUT if it needs longer to start), startup log: {stderr}")]
+    InitializeTimeout { timeout: u64, stderr: String },
 }
 
 pub type ExtensionResult<T> = Result<T, ExtensionError>;
@@ -175,6 +193,27 @@ impl Envs {
     }
 }
 
+/// How a streamable HTTP extension signs in when its server requires OAuth
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum McpAuthType {
+    /// The user approves access in the browser when the server first asks for it
+    #[default]
+    AuthorizationCode,
+    /// Machine-to-machine: `OAUTH_CLIENT_ID` and `OAUTH_CLIENT_SECRET` from the extension's
+    /// `envs` or `env_keys` are traded for a token up front, without a browser
+    ClientCredentials,
+    /// Service account (RFC 7523): an assertion signed with the key file at
+    /// `OAUTH_SERVICE_ACCOUNT_KEY_FILE` is traded for a token up front, without a browser
+    JwtBearer,
+}
+
+impl McpAuthType {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Represents the different types of MCP extensions that can be added to the manager
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 #[serde(tag = "type")]
@@ -190,6 +229,9 @@ pub enum ExtensionConfig {
         envs: Envs,
         #[serde(default)]
         env_keys: Vec<String>,
+        /// A static token or API key from the secrets store, for servers that don't use OAuth
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth: Option<RemoteAuth>,
         // NOTE: set timeout to be optional for compatibility.
         // However, new configurations should include this field.
         timeout: Option<u64>,
@@ -253,6 +295,21 @@ pub enum ExtensionConfig {
         env_keys: Vec<String>,
         #[serde(default)]
         headers: HashMap<String, String>,
+        #[serde(default, skip_serializing_if = "McpAuthType::is_default")]
+        auth_type: McpAuthType,
+        /// A static token or API key from the secrets store; when set, `auth_type` and the
+        /// OAuth settings are not used
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth: Option<RemoteAuth>,
+        /// OAuth scopes to ask for when signing in; the server's default scopes when empty
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        scopes: Vec<String>,
+        /// Proxy and certificates for signing in, over the `GOOSE_OAUTH_*` settings
+        #[serde(default, skip_serializing_if = "OAuthHttpSettings::is_default")]
+        oauth_http: OAuthHttpSettings,
+        /// Which of the user's accounts to sign in with; the host's default profile when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        oauth_profile: Option<String>,
         // NOTE: set timeout to be optional for compatibility.
         // However, new configurations should include this field.
         timeout: Option<u64>,
@@ -314,6 +371,7 @@ impl ExtensionConfig {
             uri: uri.into(),
             envs: Envs::default(),
             env_keys: Vec::new(),
+            auth: None,
             description: description.into(),
             timeout: Some(timeout.into()),
             bundled: None,
@@ -333,6 +391,11 @@ impl ExtensionConfig {
             envs: Envs::default(),
             env_keys: Vec::new(),
             headers: HashMap::new(),
+            auth_type: McpAuthType::default(),
+            auth: None,
+            scopes: Vec::new(),
+            oauth_http: OAuthHttpSettings::default(),
+            oauth_profile: None,
             description: description.into(),
             timeout: Some(timeout.into()),
             bundled: None,
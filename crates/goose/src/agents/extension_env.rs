@@ -0,0 +1,226 @@
+//! The environment stdio extensions are started with.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{get_config_dir, Config};
+
+pub const INHERIT_ENV_KEY: &str = "GOOSE_EXTENSION_INHERIT_ENV";
+pub const ENV_ALLOWLIST_KEY: &str = "GOOSE_EXTENSION_ENV_ALLOWLIST";
+
+/// Remembers the variables each extension was last started with, so the user is told once
+const SEEN_ENV_FILENAME: &str = "extension_env.json";
+
+/// Variables every extension gets, if they are set
+const SAFE_ENV_VARS: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LANGUAGE",
+    "TERM",
+    "TZ",
+    "TMPDIR",
+    "SSL_CERT_FILE",
+    "SSL_CERT_DIR",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "NO_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "no_proxy",
+    // Where toolchains that launch MCP servers (npx, uvx, ...) live
+    "JAVA_HOME",
+    "GOPATH",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "NVM_DIR",
+    "VIRTUAL_ENV",
+    // Offline mode, for extensions that can stay off the network
+    crate::offline::OFFLINE_KEY,
+    // Windows processes fail in odd ways without these
+    "SystemRoot",
+    "SystemDrive",
+    "windir",
+    "PATHEXT",
+    "ComSpec",
+    "TEMP",
+    "TMP",
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "HOMEDRIVE",
+    "HOMEPATH",
+    "ProgramData",
+    "ProgramFiles",
+    "ProgramFiles(x86)",
+    "PROCESSOR_ARCHITECTURE",
+    "NUMBER_OF_PROCESSORS",
+    "OS",
+];
+
+/// Families of variables every extension gets: locale and XDG base directories
+const SAFE_ENV_PREFIXES: &[&str] = &["LC_", "XDG_"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvPolicy {
+    /// Pass on the whole environment
+    Inherit,
+    /// Pass on the safe defaults and these extra variables
+    Allowlist(BTreeSet<String>),
+}
+
+fn same_key(a: &str, b: &str) -> bool {
+    // Environment variable names are case-insensitive on Windows
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+impl EnvPolicy {
+    /// The policy for the extension `name`; `inherit` is the command line opt-out
+    pub fn for_extension(name: &str, inherit: bool) -> Self {
+        let config = Config::global();
+        if inherit || config.get_param::<bool>(INHERIT_ENV_KEY).unwrap_or(false) {
+            return EnvPolicy::Inherit;
+        }
+        let allowed = config
+            .get_param::<HashMap<String, Vec<String>>>(ENV_ALLOWLIST_KEY)
+            .unwrap_or_default()
+            .remove(name)
+            .unwrap_or_default();
+        EnvPolicy::Allowlist(allowed.into_iter().collect())
+    }
+
+    pub fn allows(&self, key: &str) -> bool {
+        match self {
+            EnvPolicy::Inherit => true,
+            EnvPolicy::Allowlist(allowed) => {
+                SAFE_ENV_VARS.iter().any(|safe| same_key(safe, key))
+                    || SAFE_ENV_PREFIXES
+                        .iter()
+                        .any(|prefix| key.starts_with(prefix))
+                    || allowed.iter().any(|allowed| same_key(allowed, key))
+            }
+        }
+    }
+
+    /// The variables of `parent` to pass on, or `None` if the child should inherit everything
+    pub fn inherited(
+        &self,
+        parent: impl IntoIterator<Item = (String, String)>,
+    ) -> Option<HashMap<String, String>> {
+        match self {
+            EnvPolicy::Inherit => None,
+            EnvPolicy::Allowlist(_) => Some(
+                parent
+                    .into_iter()
+                    .filter(|(key, _)| self.allows(key))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// An extension was started with a different environment than the last time
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvNotice {
+    pub extension: String,
+    /// Whether it got the full environment, in which case `variables` are only its own
+    pub inherit: bool,
+    pub variables: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SeenEnv {
+    inherit: bool,
+    variables: BTreeSet<String>,
+}
+
+fn seen_env_path() -> PathBuf {
+    get_config_dir().join(SEEN_ENV_FILENAME)
+}
+
+/// Record that `extension` was started with `variables` (or the full environment plus
+/// `variables` if `inherit`), returning a notice if that's the first time or it changed since
+pub fn note_first_use(
+    extension: &str,
+    inherit: bool,
+    variables: BTreeSet<String>,
+) -> Option<EnvNotice> {
+    let path = seen_env_path();
+    let mut seen: BTreeMap<String, SeenEnv> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let current = SeenEnv { inherit, variables };
+    if seen.get(extension) == Some(&current) {
+        return None;
+    }
+
+    let notice = EnvNotice {
+        extension: extension.to_string(),
+        inherit,
+        variables: current.variables.iter().cloned().collect(),
+    };
+    seen.insert(extension.to_string(), current);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = serde_json::to_string_pretty(&seen)
+        .map_err(std::io::Error::other)
+        .and_then(|content| fs::write(&path, content))
+    {
+        tracing::warn!(
+            "Failed to record extension environment in {:?}: {}",
+            path,
+            e
+        );
+    }
+    Some(notice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_allowlist_filters_parent_environment() {
+        let parent = vars(&[
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/me"),
+            ("LC_ALL", "C"),
+            ("OPENAI_API_KEY", "sk-secret"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("GH_HOST", "github.example.com"),
+        ]);
+
+        let policy = EnvPolicy::Allowlist(BTreeSet::from(["GH_HOST".to_string()]));
+        let inherited = policy.inherited(parent.clone()).unwrap();
+        let mut keys: Vec<_> = inherited.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["GH_HOST", "HOME", "LC_ALL", "PATH"]);
+
+        let policy = EnvPolicy::Allowlist(BTreeSet::new());
+        assert!(!policy.allows("GH_HOST"));
+        assert!(!policy.allows("OPENAI_API_KEY"));
+
+        assert_eq!(EnvPolicy::Inherit.inherited(parent), None);
+        assert!(EnvPolicy::Inherit.allows("OPENAI_API_KEY"));
+    }
+}
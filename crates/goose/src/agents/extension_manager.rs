@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use futures::stream::{FuturesUnordered, StreamExt};
 use futures::{future, FutureExt};
 use rmcp::service::ClientInitializeError;
+use rmcp::transport::sse_client::SseClientConfig;
 use rmcp::transport::streamable_http_client::{
     AuthRequiredError, StreamableHttpClientTransportConfig, StreamableHttpError,
 };
@@ -11,8 +12,9 @@ use rmcp::transport::{
     ConfigureCommandExt, DynamicTransportError, SseClientTransport, StreamableHttpClientTransport,
     TokioChildProcess,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tempfile::{tempdir, TempDir};
@@ -24,22 +26,31 @@ use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, warn};
 
+use super::authorized_client::{mentions_unauthorized, AuthorizedClient};
 use super::extension::{
-    ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, PlatformExtensionContext,
-    ToolInfo, PLATFORM_EXTENSIONS,
+    ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, McpAuthType,
+    PlatformExtensionContext, ToolInfo, PLATFORM_EXTENSIONS,
 };
 use super::tool_execution::ToolCallResult;
 use crate::agents::extension::{Envs, ProcessExit};
+use crate::agents::extension_env::{self, EnvNotice, EnvPolicy};
 use crate::agents::extension_malware_check;
 use crate::agents::mcp_client::{McpClient, McpClientTrait};
+use crate::agents::remote_auth::RemoteAuth;
+use crate::agents::tool_recording::{recording_path, RecordingClient};
+use crate::config::extensions::extension_secret_key;
 use crate::config::{Config, ExtensionConfigManager};
-use crate::oauth::oauth_flow;
+use crate::oauth::client_credentials::ClientCredentials;
+use crate::oauth::http::OAuthHttpSettings;
+use crate::oauth::jwt_bearer::JwtBearer;
+use crate::oauth::registration::ClientRegistration;
+use crate::oauth::token_store::TokenKey;
+use crate::oauth::{merge_scopes, oauth_flow, sign_in_hint, AuthError, TokenRefresher};
 use crate::prompt_template;
 use rmcp::model::{
-    CallToolRequestParam, Content, ErrorCode, ErrorData, GetPromptResult, Prompt, ResourceContents,
-    ServerInfo, Tool,
+    CallToolRequestParam, CallToolResult, Content, ErrorCode, ErrorData, GetPromptResult, Prompt,
+    ResourceContents, ServerInfo, Tool,
 };
-use rmcp::transport::auth::AuthClient;
 use serde_json::Value;
 
 type McpClientBox = Arc<Mutex<Box<dyn McpClientTrait>>>;
@@ -89,6 +100,8 @@ impl Extension {
 pub struct ExtensionManager {
     extensions: Mutex<HashMap<String, Extension>>,
     context: Mutex<PlatformExtensionContext>,
+    inherit_env: AtomicBool,
+    env_notices: Mutex<Vec<EnvNotice>>,
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -173,9 +186,97 @@ impl Default for ExtensionManager {
     }
 }
 
+/// How a stdio server gets retried when it fails to answer `initialize`.
+///
+/// Some servers need a moment after spawn before they can talk MCP, so an attempt that times out
+/// kills the process and starts a fresh one after an exponentially growing pause. A server that
+/// exits during `initialize` crashed, and is reported straight away.
+#[derive(Debug, Clone, PartialEq)]
+struct InitRetryPolicy {
+    attempts: u32,
+    attempt_timeout: Duration,
+    backoff: Duration,
+}
+
+const DEFAULT_INIT_ATTEMPTS: u32 = 3;
+const DEFAULT_INIT_BACKOFF_MS: u64 = 500;
+const MAX_INIT_BACKOFF: Duration = Duration::from_secs(10);
+/// How much of a failed server's stderr to show the user
+const STARTUP_LOG_TAIL: usize = 4000;
+
+impl InitRetryPolicy {
+    fn from_config(timeout: &Option<u64>) -> Self {
+        let config = Config::global();
+        let timeout = timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT);
+        let attempt_timeout = config
+            .get_param::<u64>("GOOSE_EXTENSION_INIT_TIMEOUT")
+            .unwrap_or(timeout);
+        Self {
+            attempts: config
+                .get_param::<u32>("GOOSE_EXTENSION_INIT_ATTEMPTS")
+                .unwrap_or(DEFAULT_INIT_ATTEMPTS)
+                .max(1),
+            attempt_timeout: Duration::from_secs(attempt_timeout.max(1)),
+            backoff: Duration::from_millis(
+                config
+                    .get_param::<u64>("GOOSE_EXTENSION_INIT_BACKOFF_MS")
+                    .unwrap_or(DEFAULT_INIT_BACKOFF_MS),
+            ),
+        }
+    }
+
+    /// Pause before the given retry (1 being the first retry), growing up to `MAX_INIT_BACKOFF`
+    /// or the configured backoff when that is longer
+    fn delay_before(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(MAX_INIT_BACKOFF.max(self.backoff))
+    }
+}
+
+fn startup_log_tail(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let text = text.trim();
+    if text.is_empty() {
+        return "(empty)".to_string();
+    }
+    match text.char_indices().rev().nth(STARTUP_LOG_TAIL) {
+        Some((start, _)) => format!("...{}", &text[start..]),
+        None => text.to_string(),
+    }
+}
+
 async fn child_process_client(
+    make_command: impl Fn() -> Command,
+    timeout: &Option<u64>,
+) -> ExtensionResult<McpClient> {
+    let policy = InitRetryPolicy::from_config(timeout);
+    let mut retry = 0;
+    loop {
+        let last_attempt = retry + 1 >= policy.attempts;
+        match initialize_child_process(make_command(), timeout, &policy).await {
+            Ok(client) => return Ok(client),
+            Err(error @ ExtensionError::InitializeTimeout { .. }) if !last_attempt => {
+                retry += 1;
+                let delay = policy.delay_before(retry);
+                warn!(
+                    attempt = retry,
+                    attempts = policy.attempts,
+                    error = %error,
+                    "Extension failed to initialize, retrying in {:?}",
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+async fn initialize_child_process(
     mut command: Command,
     timeout: &Option<u64>,
+    policy: &InitRetryPolicy,
 ) -> ExtensionResult<McpClient> {
     #[cfg(unix)]
     command.process_group(0);
@@ -188,30 +289,98 @@ async fn child_process_client(
         ExtensionError::SetupError("failed to attach child process stderr".to_owned())
     })?;
 
-    let stderr_task = tokio::spawn(async move {
-        let mut all_stderr = Vec::new();
-        stderr.read_to_end(&mut all_stderr).await?;
-        Ok::<String, std::io::Error>(String::from_utf8_lossy(&all_stderr).into())
+    // Collect stderr as it arrives so a server that hangs still has its startup log captured
+    let startup_log = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let stderr_task = tokio::spawn({
+        let startup_log = startup_log.clone();
+        async move {
+            let mut buf = [0u8; 4096];
+            loop {
+                match stderr.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => startup_log
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .extend_from_slice(&buf[..n]),
+                }
+            }
+        }
     });
+    let captured_log = |log: &std::sync::Mutex<Vec<u8>>| {
+        startup_log_tail(&log.lock().unwrap_or_else(|e| e.into_inner()))
+    };
 
-    let client_result = McpClient::connect(
+    let connect = McpClient::connect(
         transport,
         Duration::from_secs(timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT)),
-    )
-    .await;
-
-    match client_result {
-        Ok(client) => Ok(client),
-        Err(error) => {
-            let error_task_out = stderr_task.await?;
-            Err::<McpClient, ExtensionError>(match error_task_out {
-                Ok(stderr_content) => ProcessExit::new(stderr_content, error).into(),
-                Err(e) => e.into(),
+    );
+    match tokio::time::timeout(policy.attempt_timeout, connect).await {
+        Ok(Ok(client)) => Ok(client),
+        Ok(Err(error)) => {
+            // The connection closed underneath us, so let the process finish writing its last words
+            let _ = tokio::time::timeout(Duration::from_secs(1), stderr_task).await;
+            Err(ProcessExit::new(captured_log(&startup_log), error).into())
+        }
+        Err(_) => {
+            // Dropping the transport kills the process
+            stderr_task.abort();
+            Err(ExtensionError::InitializeTimeout {
+                timeout: policy.attempt_timeout.as_secs(),
+                stderr: captured_log(&startup_log),
             })
         }
     }
 }
 
+/// An HTTP client for a remote extension that sends `headers`, and the credential of `auth`,
+/// with every request
+fn remote_http_client(
+    headers: &HashMap<String, String>,
+    auth: Option<&RemoteAuth>,
+) -> Result<reqwest::Client, ExtensionError> {
+    let mut default_headers = HeaderMap::new();
+    for (key, value) in headers {
+        default_headers.insert(
+            HeaderName::try_from(key)
+                .map_err(|_| ExtensionError::ConfigError(format!("invalid header: {}", key)))?,
+            value.parse().map_err(|_| {
+                ExtensionError::ConfigError(format!("invalid header value: {}", key))
+            })?,
+        );
+    }
+    if let Some(auth) = auth {
+        auth.apply(&mut default_headers)
+            .map_err(|e| ExtensionError::ConfigError(e.to_string()))?;
+    }
+    reqwest::Client::builder()
+        .default_headers(default_headers)
+        .build()
+        .map_err(|_| ExtensionError::ConfigError("could not construct http client".to_string()))
+}
+
+/// The error for an extension whose server turned its static credential down
+fn credential_rejected(name: &str, auth: &RemoteAuth) -> ExtensionError {
+    ExtensionError::ConfigError(format!(
+        "{} rejected the {} credential in the secret {}; update it with `goose configure`",
+        name, auth.scheme, auth.secret
+    ))
+}
+
+/// The error for an extension whose sign-in failed, with what the user can do about it
+fn sign_in_error(name: &str, e: AuthError) -> ExtensionError {
+    let message = format!(
+        "auth error: {}; {}",
+        e,
+        e.hint(name).unwrap_or_else(|| sign_in_hint(name))
+    );
+    // Signing in again won't help until the configuration changes
+    if e.needs_configuration() {
+        ExtensionError::ConfigError(message)
+    } else {
+        ExtensionError::SetupError(message)
+    }
+}
+
 fn extract_auth_error(
     res: &Result<McpClient, ClientInitializeError>,
 ) -> Option<&AuthRequiredError> {
@@ -239,9 +408,21 @@ impl ExtensionManager {
         Self {
             extensions: Mutex::new(HashMap::new()),
             context: Mutex::new(PlatformExtensionContext { session_id: None }),
+            inherit_env: AtomicBool::new(false),
+            env_notices: Mutex::new(Vec::new()),
         }
     }
 
+    /// Start stdio extensions with the full environment instead of the allowlisted variables
+    pub fn set_inherit_env(&self, inherit: bool) {
+        self.inherit_env.store(inherit, Ordering::Relaxed);
+    }
+
+    /// Extensions started with variables the user hasn't been told about yet
+    pub async fn take_env_notices(&self) -> Vec<EnvNotice> {
+        std::mem::take(&mut *self.env_notices.lock().await)
+    }
+
     pub async fn set_context(&self, context: PlatformExtensionContext) {
         *self.context.lock().await = context;
     }
@@ -279,7 +460,11 @@ impl ExtensionManager {
                     continue;
                 }
 
-                match config_instance.get(key, true) {
+                // A value saved for this extension only wins over one shared by all of them
+                let value = config_instance
+                    .get(&extension_secret_key(ext_name, key), true)
+                    .or_else(|_| config_instance.get(key, true));
+                match value {
                     Ok(value) => {
                         if value.is_null() {
                             warn!(
@@ -321,68 +506,134 @@ impl ExtensionManager {
         }
 
         let client: Box<dyn McpClientTrait> = match &config {
-            ExtensionConfig::Sse { uri, timeout, .. } => {
-                let transport = SseClientTransport::start(uri.to_string()).await.map_err(
-                    |transport_error| {
-                        ClientInitializeError::transport::<SseClientTransport<reqwest::Client>>(
-                            transport_error,
-                            "connect",
-                        )
+            ExtensionConfig::Sse {
+                uri,
+                timeout,
+                envs,
+                env_keys,
+                auth,
+                ..
+            } => {
+                crate::offline::check_extension_uri(&config_name, uri)
+                    .map_err(|e| ExtensionError::ConfigError(e.to_string()))?;
+                let timeout = Duration::from_secs(
+                    timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
+                );
+                let started = SseClientTransport::start_with_client(
+                    remote_http_client(&HashMap::new(), auth.as_ref())?,
+                    SseClientConfig {
+                        sse_endpoint: uri.clone().into(),
+                        ..Default::default()
                     },
-                )?;
-                Box::new(
-                    McpClient::connect(
-                        transport,
-                        Duration::from_secs(
-                            timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
-                        ),
-                    )
-                    .await?,
                 )
+                .await;
+                match started {
+                    Ok(transport) => Box::new(McpClient::connect(transport, timeout).await?),
+                    Err(transport_error) if mentions_unauthorized(&transport_error.to_string()) => {
+                        if let Some(auth) = auth {
+                            return Err(credential_rejected(&config_name, auth));
+                        }
+                        // The server wants a sign-in before it opens the event stream
+                        let registration = ClientRegistration::from_envs(
+                            &merge_environments(envs, env_keys, &config_name).await?,
+                        );
+                        let http = OAuthHttpSettings::from_config(&config_name)
+                            .client()
+                            .map_err(|e| ExtensionError::ConfigError(e.to_string()))?;
+                        let key = TokenKey::for_resource(uri)
+                            .map_err(|e| ExtensionError::ConfigError(e.to_string()))?
+                            .with_client(registration.as_ref().map(|r| r.client_id.as_str()));
+                        let am = oauth_flow(
+                            uri,
+                            &config_name,
+                            &key,
+                            registration.as_ref(),
+                            None,
+                            &[],
+                            &http,
+                        )
+                        .await
+                        .map_err(|e| sign_in_error(&config_name, e))?;
+                        let refresher = TokenRefresher::new(
+                            uri,
+                            &config_name,
+                            key,
+                            am,
+                            registration,
+                            Vec::new(),
+                            http,
+                        )
+                        .map_err(|e| ExtensionError::SetupError(e.to_string()))?;
+                        Box::new(AuthorizedClient::connect_sse(uri, timeout, refresher).await?)
+                    }
+                    Err(transport_error) => {
+                        return Err(ClientInitializeError::transport::<
+                            SseClientTransport<reqwest::Client>,
+                        >(transport_error, "connect")
+                        .into())
+                    }
+                }
             }
             ExtensionConfig::StreamableHttp {
                 uri,
                 timeout,
                 headers,
                 name,
+                envs,
+                env_keys,
+                auth_type,
+                auth,
+                scopes,
+                oauth_http,
+                oauth_profile,
                 ..
             } => {
-                let mut default_headers = HeaderMap::new();
-                for (key, value) in headers {
-                    default_headers.insert(
-                        HeaderName::try_from(key).map_err(|_| {
-                            ExtensionError::ConfigError(format!("invalid header: {}", key))
-                        })?,
-                        value.parse().map_err(|_| {
-                            ExtensionError::ConfigError(format!("invalid header value: {}", key))
-                        })?,
-                    );
-                }
-                let client = reqwest::Client::builder()
-                    .default_headers(default_headers)
-                    .build()
-                    .map_err(|_| {
-                        ExtensionError::ConfigError("could not construct http client".to_string())
-                    })?;
-                let transport = StreamableHttpClientTransport::with_client(
-                    client,
-                    StreamableHttpClientTransportConfig {
-                        uri: uri.clone().into(),
-                        ..Default::default()
-                    },
-                );
-                let client_res = McpClient::connect(
-                    transport,
-                    Duration::from_secs(
-                        timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
-                    ),
-                )
-                .await;
-                let client = if let Some(_auth_error) = extract_auth_error(&client_res) {
-                    let am = oauth_flow(uri, name)
-                        .await
-                        .map_err(|_| ExtensionError::SetupError("auth error".to_string()))?;
-                    let client = AuthClient::new(reqwest::Client::default(), am);
+                crate::offline::check_extension_uri(name, uri)
+                    .map_err(|e| ExtensionError::ConfigError(e.to_string()))?;
+                let oauth_client = || {
+                    oauth_http
+                        .clone()
+                        .or(OAuthHttpSettings::from_config(name))
+                        .client()
+                        .map_err(|e| ExtensionError::ConfigError(e.to_string()))
+                };
+                // A static credential stands in for OAuth altogether
+                if auth.is_none() && *auth_type != McpAuthType::AuthorizationCode {
+                    let all_envs = merge_environments(envs, env_keys, &config_name).await?;
+                    let with_scopes = |scope: &mut Option<String>| {
+                        if !scopes.is_empty() {
+                            let configured: Vec<String> = scope
+                                .iter()
+                                .flat_map(|scope| scope.split_whitespace().map(str::to_string))
+                                .collect();
+                            *scope = Some(merge_scopes(&configured, scopes).join(" "));
+                        }
+                    };
+                    let refresher = if *auth_type == McpAuthType::JwtBearer {
+                        let mut grant = JwtBearer::from_envs(&all_envs)
+                            .map_err(|e| ExtensionError::ConfigError(e.to_string()))?;
+                        with_scopes(&mut grant.scope);
+                        TokenRefresher::jwt_bearer(uri, name, grant, oauth_client()?).await
+                    } else {
+                        let mut credentials = ClientCredentials::from_envs(&all_envs)
+                            .map_err(|e| ExtensionError::ConfigError(e.to_string()))?;
+                        with_scopes(&mut credentials.scope);
+                        TokenRefresher::client_credentials(uri, name, credentials, oauth_client()?)
+                            .await
+                    }
+                    .map_err(|e| ExtensionError::SetupError(e.to_string()))?;
+                    Box::new(
+                        AuthorizedClient::connect(
+                            uri,
+                            Duration::from_secs(
+                                timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
+                            ),
+                            refresher,
+                        )
+                        .await?,
+                    )
+                } else {
+                    let client = remote_http_client(headers, auth.as_ref())?;
                     let transport = StreamableHttpClientTransport::with_client(
                         client,
                         StreamableHttpClientTransportConfig {
@@ -390,17 +641,61 @@ impl ExtensionManager {
                             ..Default::default()
                         },
                     );
-                    McpClient::connect(
+                    let client_res = McpClient::connect(
                         transport,
                         Duration::from_secs(
                             timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
                         ),
                     )
-                    .await?
-                } else {
-                    client_res?
-                };
-                Box::new(client)
+                    .await;
+                    if let Some(auth_error) = extract_auth_error(&client_res) {
+                        if let Some(auth) = auth {
+                            return Err(credential_rejected(name, auth));
+                        }
+                        // A client registered ahead of time for servers without dynamic
+                        // registration
+                        let registration = ClientRegistration::from_envs(
+                            &merge_environments(envs, env_keys, &config_name).await?,
+                        );
+                        let http = oauth_client()?;
+                        let key = TokenKey::for_profile(uri, oauth_profile.as_deref())
+                            .map_err(|e| ExtensionError::ConfigError(e.to_string()))?
+                            .with_client(registration.as_ref().map(|r| r.client_id.as_str()));
+                        let am = oauth_flow(
+                            uri,
+                            name,
+                            &key,
+                            registration.as_ref(),
+                            Some(auth_error.www_authenticate_header.as_str()),
+                            scopes,
+                            &http,
+                        )
+                        .await
+                        .map_err(|e| sign_in_error(name, e))?;
+                        let refresher = TokenRefresher::new(
+                            uri,
+                            name,
+                            key,
+                            am,
+                            registration,
+                            scopes.clone(),
+                            http,
+                        )
+                        .map_err(|e| ExtensionError::SetupError(e.to_string()))?;
+                        Box::new(
+                            AuthorizedClient::connect(
+                                uri,
+                                Duration::from_secs(
+                                    timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
+                                ),
+                                refresher,
+                            )
+                            .await?,
+                        )
+                    } else {
+                        Box::new(client_res?)
+                    }
+                }
             }
             ExtensionConfig::Stdio {
                 cmd,
@@ -410,13 +705,51 @@ impl ExtensionManager {
                 timeout,
                 ..
             } => {
-                let all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
-                let command = Command::new(cmd).configure(|command| {
-                    command.args(args).envs(all_envs);
-                });
+                let all_envs = merge_environments(envs, env_keys, &config_name).await?;
+                let policy = EnvPolicy::for_extension(
+                    &sanitized_name,
+                    self.inherit_env.load(Ordering::Relaxed),
+                );
+                let inherited = policy.inherited(std::env::vars());
+
+                // With the full environment only the extension's own variables are worth listing
+                let passed: BTreeSet<String> = inherited
+                    .iter()
+                    .flat_map(|inherited| inherited.keys())
+                    .chain(all_envs.keys())
+                    .cloned()
+                    .collect();
+                tracing::debug!(
+                    ext_name = %sanitized_name,
+                    inherit = inherited.is_none(),
+                    variables = ?passed,
+                    "Starting extension with environment"
+                );
+                if let Some(notice) =
+                    extension_env::note_first_use(&sanitized_name, inherited.is_none(), passed)
+                {
+                    warn!(
+                        ext_name = %notice.extension,
+                        variables = ?notice.variables,
+                        "Extension started with a new set of environment variables"
+                    );
+                    self.env_notices.lock().await.push(notice);
+                }
+
+                let command = || {
+                    Command::new(cmd).configure(|command| {
+                        if let Some(inherited) = &inherited {
+                            command.env_clear().envs(inherited);
+                        }
+                        command.args(args).envs(&all_envs);
+                    })
+                };
 
-                // Check for malicious packages before launching the process
-                extension_malware_check::deny_if_malicious_cmd_args(cmd, args).await?;
+                // Check for malicious packages before launching the process; the check needs the
+                // network, and offline the package can only come from the local cache anyway
+                if !crate::offline::is_offline() {
+                    extension_malware_check::deny_if_malicious_cmd_args(cmd, args).await?;
+                }
 
                 let client = child_process_client(command, timeout).await?;
                 Box::new(client)
@@ -444,9 +777,11 @@ impl ExtensionManager {
                             e
                         ))
                     })?;
-                let command = Command::new(cmd).configure(|command| {
-                    command.arg("mcp").arg(name);
-                });
+                let command = || {
+                    Command::new(&cmd).configure(|command| {
+                        command.arg("mcp").arg(name);
+                    })
+                };
                 let client = child_process_client(command, timeout).await?;
                 Box::new(client)
             }
@@ -469,15 +804,17 @@ impl ExtensionManager {
                 temp_dir = Some(dir);
                 std::fs::write(&file_path, code)?;
 
-                let command = Command::new("uvx").configure(|command| {
-                    command.arg("--with").arg("mcp");
+                let command = || {
+                    Command::new("uvx").configure(|command| {
+                        command.arg("--with").arg("mcp");
 
-                    dependencies.iter().flatten().for_each(|dep| {
-                        command.arg("--with").arg(dep);
-                    });
+                        dependencies.iter().flatten().for_each(|dep| {
+                            command.arg("--with").arg(dep);
+                        });
 
-                    command.arg("python").arg(file_path.to_str().unwrap());
-                });
+                        command.arg("python").arg(file_path.to_str().unwrap());
+                    })
+                };
 
                 let client = child_process_client(command, timeout).await?;
 
@@ -490,6 +827,14 @@ impl ExtensionManager {
             }
         };
 
+        let client: Box<dyn McpClientTrait> = match recording_path(&sanitized_name) {
+            Some(path) => match RecordingClient::new(client, &path) {
+                Ok(recorder) => Box::new(recorder),
+                Err(e) => return Err(ExtensionError::SetupError(e.to_string())),
+            },
+            None => client,
+        };
+
         let server_info = client.get_info().cloned();
         self.add_client(
             sanitized_name,
@@ -576,6 +921,22 @@ impl ExtensionManager {
         Ok(self.extensions.lock().await.keys().cloned().collect())
     }
 
+    /// Names of the loaded extensions that ship with goose
+    pub async fn list_bundled_extensions(&self) -> Vec<String> {
+        self.extensions
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, extension)| {
+                matches!(
+                    extension.config,
+                    ExtensionConfig::Builtin { .. } | ExtensionConfig::Platform { .. }
+                )
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
     /// Get all tools from all clients with proper prefixing
     pub async fn get_prefixed_tools(
         &self,
@@ -877,26 +1238,30 @@ impl ExtensionManager {
         }
     }
 
-    pub async fn dispatch_tool_call(
-        &self,
-        tool_call: CallToolRequestParam,
-        cancellation_token: CancellationToken,
-    ) -> Result<ToolCallResult> {
+    /// The name an extension knows the tool `prefixed_name` by, and the extension's client
+    async fn resolve_tool(&self, prefixed_name: &str) -> Result<(String, McpClientBox)> {
         // Dispatch tool call based on the prefix naming convention
         let (client_name, client) =
-            self.get_client_for_tool(&tool_call.name)
+            self.get_client_for_tool(prefixed_name)
                 .await
                 .ok_or_else(|| {
-                    ErrorData::new(ErrorCode::RESOURCE_NOT_FOUND, tool_call.name.clone(), None)
+                    ErrorData::new(
+                        ErrorCode::RESOURCE_NOT_FOUND,
+                        prefixed_name.to_string(),
+                        None,
+                    )
                 })?;
 
         // rsplit returns the iterator in reverse, tool_name is then at 0
-        let tool_name = tool_call
-            .name
+        let tool_name = prefixed_name
             .strip_prefix(client_name.as_str())
             .and_then(|s| s.strip_prefix("__"))
             .ok_or_else(|| {
-                ErrorData::new(ErrorCode::RESOURCE_NOT_FOUND, tool_call.name.clone(), None)
+                ErrorData::new(
+                    ErrorCode::RESOURCE_NOT_FOUND,
+                    prefixed_name.to_string(),
+                    None,
+                )
             })?
             .to_string();
 
@@ -914,8 +1279,32 @@ impl ExtensionManager {
             }
         }
 
+        Ok((tool_name, client))
+    }
+
+    /// Call a tool and return all of its result, including whether it is an error, which
+    /// [`Self::dispatch_tool_call`] leaves to the content
+    pub async fn call_tool(
+        &self,
+        tool_call: CallToolRequestParam,
+        cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult> {
+        let (tool_name, client) = self.resolve_tool(&tool_call.name).await?;
+        let client = client.lock().await;
+        client
+            .call_tool(&tool_name, tool_call.arguments, cancellation_token)
+            .await
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None).into())
+    }
+
+    pub async fn dispatch_tool_call(
+        &self,
+        tool_call: CallToolRequestParam,
+        cancellation_token: CancellationToken,
+    ) -> Result<ToolCallResult> {
+        let (tool_name, client) = self.resolve_tool(&tool_call.name).await?;
+
         let arguments = tool_call.arguments.clone();
-        let client = client.clone();
         let notifications_receiver = client.lock().await.subscribe().await;
 
         let fut = async move {
@@ -1115,6 +1504,97 @@ mod tests {
     use serde_json::json;
     use tokio::sync::mpsc;
 
+    #[test]
+    fn test_init_retry_backoff() {
+        let policy = InitRetryPolicy {
+            attempts: 5,
+            attempt_timeout: Duration::from_secs(30),
+            backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.delay_before(1), Duration::from_millis(500));
+        assert_eq!(policy.delay_before(2), Duration::from_secs(1));
+        assert_eq!(policy.delay_before(3), Duration::from_secs(2));
+        assert_eq!(policy.delay_before(30), MAX_INIT_BACKOFF);
+
+        // A configured backoff longer than the cap is kept
+        let policy = InitRetryPolicy {
+            backoff: Duration::from_secs(30),
+            ..policy
+        };
+        assert_eq!(policy.delay_before(1), Duration::from_secs(30));
+        assert_eq!(policy.delay_before(3), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_startup_log_tail() {
+        assert_eq!(startup_log_tail(b"  \n"), "(empty)");
+        assert_eq!(startup_log_tail(b"listening\n"), "listening");
+        let long = "x".repeat(STARTUP_LOG_TAIL + 10) + "end";
+        let tail = startup_log_tail(long.as_bytes());
+        assert!(tail.starts_with("..."));
+        assert!(tail.ends_with("end"));
+        assert_eq!(tail.len(), STARTUP_LOG_TAIL + 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_crashing_server_reports_startup_log() {
+        let policy = InitRetryPolicy {
+            attempts: 1,
+            attempt_timeout: Duration::from_secs(10),
+            backoff: Duration::ZERO,
+        };
+        let command = Command::new("sh").configure(|command| {
+            command.arg("-c").arg("echo 'missing API key' >&2; exit 1");
+        });
+        let err = initialize_child_process(command, &None, &policy)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ExtensionError::ProcessExit(_)));
+        assert!(err.to_string().contains("missing API key"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_crashing_server_is_not_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("started");
+        let make_command = || {
+            Command::new("sh").configure(|command| {
+                command
+                    .arg("-c")
+                    .arg(format!("echo x >> '{}'; exit 1", marker.display()));
+            })
+        };
+        assert!(child_process_client(make_command, &None).await.is_err());
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "x\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_slow_server_times_out() {
+        let policy = InitRetryPolicy {
+            attempts: 1,
+            attempt_timeout: Duration::from_secs(1),
+            backoff: Duration::ZERO,
+        };
+        let command = Command::new("sh").configure(|command| {
+            command.arg("-c").arg("echo 'warming up' >&2; sleep 30");
+        });
+        let err = initialize_child_process(command, &None, &policy)
+            .await
+            .err()
+            .unwrap();
+        match err {
+            ExtensionError::InitializeTimeout { timeout, stderr } => {
+                assert_eq!(timeout, 1);
+                assert_eq!(stderr, "warming up");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
     impl ExtensionManager {
         async fn add_mock_extension(&self, name: String, client: McpClientBox) {
             self.add_mock_extension_with_tools(name, client, vec![])
@@ -2,77 +2,215 @@ use chrono::Utc;
 use rmcp::model::{Content, ErrorData};
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::session::blob_store::BlobStore;
+use crate::session::session_manager::ensure_session_dir;
+
+const DEFAULT_SPILL_THRESHOLD_BYTES: usize = 200_000;
+const DEFAULT_RETENTION_DAYS: u64 = 7;
+/// Lines shown from each end of a spilled response
+const PREVIEW_LINES: usize = 20;
+/// Upper bound on each end of the preview, for output with very long lines
+const PREVIEW_CHARS: usize = 2_000;
+
+/// Where oversized tool output goes and how long it is kept
+#[derive(Debug, Clone)]
+pub struct SpillConfig {
+    pub threshold_bytes: usize,
+    pub retention: Duration,
+    /// Root of all spill directories; files are pruned across it
+    pub root: PathBuf,
+    /// Directory for this session's spilled output
+    pub dir: PathBuf,
+    /// The content-addressed store and the session to reference stored output from. Output
+    /// only goes to `dir`, and is pruned by age, when there is no session or storing fails.
+    pub blobs: Option<(BlobStore, String)>,
+}
 
-const LARGE_TEXT_THRESHOLD: usize = 200_000;
+impl SpillConfig {
+    /// Read `GOOSE_TOOL_OUTPUT_SPILL_BYTES` and `GOOSE_TOOL_OUTPUT_RETENTION_DAYS`, and put spilled
+    /// output under the session directory when the call belongs to a session
+    pub fn from_config(session_id: Option<&str>) -> Self {
+        let config = Config::global();
+        let threshold_bytes = config
+            .get_param::<usize>("GOOSE_TOOL_OUTPUT_SPILL_BYTES")
+            .unwrap_or(DEFAULT_SPILL_THRESHOLD_BYTES);
+        let retention_days = config
+            .get_param::<u64>("GOOSE_TOOL_OUTPUT_RETENTION_DAYS")
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+
+        let (root, dir) = match session_id.zip(ensure_session_dir().ok()) {
+            Some((id, sessions)) => {
+                let root = sessions.join("tool_output");
+                let dir = root.join(id);
+                (root, dir)
+            }
+            None => {
+                let root = std::env::temp_dir().join("goose_mcp_responses");
+                (root.clone(), root)
+            }
+        };
+
+        let blobs =
+            session_id.and_then(|id| BlobStore::open().ok().map(|store| (store, id.to_string())));
+
+        Self {
+            threshold_bytes,
+            retention: Duration::from_secs(retention_days * 24 * 60 * 60),
+            root,
+            dir,
+            blobs,
+        }
+    }
+}
 
 /// Process tool response and handle large text content
 pub fn process_tool_response(
     response: Result<Vec<Content>, ErrorData>,
+    session_id: Option<&str>,
+) -> Result<Vec<Content>, ErrorData> {
+    spill_large_contents(response, &SpillConfig::from_config(session_id))
+}
+
+fn spill_large_contents(
+    response: Result<Vec<Content>, ErrorData>,
+    config: &SpillConfig,
 ) -> Result<Vec<Content>, ErrorData> {
-    match response {
-        Ok(contents) => {
-            let mut processed_contents = Vec::new();
-
-            for content in contents {
-                match content.as_text() {
-                    Some(text_content) => {
-                        // Check if text exceeds threshold
-                        if text_content.text.chars().count() > LARGE_TEXT_THRESHOLD {
-                            // Write to temp file
-                            match write_large_text_to_file(&text_content.text) {
-                                Ok(file_path) => {
-                                    // Create a new text content with reference to the file
-                                    let message = format!(
-                                        "The response returned from the tool call was larger ({} characters) and is stored in the file which you can use other tools to examine or search in: {}",
-                                        text_content.text.chars().count(),
-                                        file_path
-                                    );
-                                    processed_contents.push(Content::text(message));
-                                }
-                                Err(e) => {
-                                    // If file writing fails, include original content with warning
-                                    let warning = format!(
-                                        "Warning: Failed to write large response to file: {}. Showing full content instead.\n\n{}",
-                                        e,
-                                        text_content.text
-                                    );
-                                    processed_contents.push(Content::text(warning));
-                                }
-                            }
-                        } else {
-                            // Keep original content for smaller texts
-                            processed_contents.push(content);
-                        }
+    let contents = response?;
+    let mut processed_contents = Vec::new();
+    let mut spilled = false;
+
+    for content in contents {
+        match content.as_text() {
+            Some(text_content) if text_content.text.len() > config.threshold_bytes => {
+                spilled = true;
+                match store_large_text(&text_content.text, config) {
+                    Ok(file_path) => {
+                        let message = format!(
+                            "The response returned from the tool call was larger than {} bytes ({} bytes), so the full output is stored in the file: {}\n\
+                            Use other tools to examine or search in it. Preview:\n{}",
+                            config.threshold_bytes,
+                            text_content.text.len(),
+                            file_path.display(),
+                            preview(&text_content.text)
+                        );
+                        processed_contents.push(Content::text(message));
                     }
-                    None => {
-                        // Pass through other content types unchanged
-                        processed_contents.push(content);
+                    Err(e) => {
+                        // If file writing fails, include original content with warning
+                        let warning = format!(
+                            "Warning: Failed to write large response to file: {}. Showing full content instead.\n\n{}",
+                            e,
+                            text_content.text
+                        );
+                        processed_contents.push(Content::text(warning));
                     }
                 }
             }
+            // Keep smaller texts and other content types unchanged
+            _ => processed_contents.push(content),
+        }
+    }
+
+    if spilled {
+        prune_expired(&config.root, config.retention);
+    }
+    Ok(processed_contents)
+}
+
+/// The first and last lines of `text`, with a marker for what was left out
+fn preview(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let (head, tail, omitted) = if lines.len() > PREVIEW_LINES * 2 {
+        (
+            lines[..PREVIEW_LINES].join("\n"),
+            lines[lines.len() - PREVIEW_LINES..].join("\n"),
+            format!("{} lines", lines.len() - PREVIEW_LINES * 2),
+        )
+    } else {
+        let chars = text.chars().count();
+        if chars <= PREVIEW_CHARS * 2 {
+            return text.to_string();
+        }
+        (
+            text.to_string(),
+            text.to_string(),
+            format!("{} characters", chars - PREVIEW_CHARS * 2),
+        )
+    };
+    format!(
+        "{}\n... [{} omitted] ...\n{}",
+        head.chars().take(PREVIEW_CHARS).collect::<String>(),
+        omitted,
+        last_chars(&tail, PREVIEW_CHARS)
+    )
+}
+
+fn last_chars(text: &str, n: usize) -> &str {
+    match text.char_indices().rev().nth(n.saturating_sub(1)) {
+        Some((start, _)) => &text[start..],
+        None => text,
+    }
+}
 
-            Ok(processed_contents)
+/// Store large text in the session's blob store, or a file in the spill directory without one
+fn store_large_text(content: &str, config: &SpillConfig) -> Result<PathBuf, std::io::Error> {
+    if let Some((store, session_id)) = &config.blobs {
+        match store.put(session_id, content.as_bytes()) {
+            Ok(path) => return Ok(path),
+            Err(e) => tracing::warn!("Failed to store tool output in the blob store: {}", e),
         }
-        Err(e) => Err(e),
     }
+    write_large_text_to_file(content, &config.dir)
 }
 
-/// Write large text content to a temporary file
-fn write_large_text_to_file(content: &str) -> Result<String, std::io::Error> {
-    // Create temp directory if it doesn't exist
-    let temp_dir = std::env::temp_dir().join("goose_mcp_responses");
-    std::fs::create_dir_all(&temp_dir)?;
+/// Write large text content to a file in `dir`
+fn write_large_text_to_file(content: &str, dir: &Path) -> Result<PathBuf, std::io::Error> {
+    std::fs::create_dir_all(dir)?;
 
-    // Generate a unique filename with timestamp
+    // Timestamp for readability, random suffix so calls in the same second don't collide
     let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("mcp_response_{}.txt", timestamp);
-    let file_path = temp_dir.join(&filename);
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    let file_path = dir.join(format!("mcp_response_{}_{}.txt", timestamp, suffix));
 
-    // Write content to file
     let mut file = File::create(&file_path)?;
     file.write_all(content.as_bytes())?;
 
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(file_path)
+}
+
+/// Remove spilled files older than `retention`, and session directories left empty
+fn prune_expired(root: &Path, retention: Duration) {
+    let Some(cutoff) = SystemTime::now().checked_sub(retention) else {
+        return;
+    };
+    prune_dir(root, cutoff, false);
+}
+
+fn prune_dir(dir: &Path, cutoff: SystemTime, remove_if_empty: bool) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            prune_dir(&path, cutoff, true);
+        } else if metadata.modified().map(|m| m < cutoff).unwrap_or(false)
+            && path.extension().is_some_and(|ext| ext == "txt")
+        {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    if remove_if_empty {
+        // Fails harmlessly when the directory still has files in it
+        let _ = std::fs::remove_dir(dir);
+    }
 }
 
 #[cfg(test)]
@@ -92,7 +230,7 @@ mod tests {
         let response = Ok(vec![content]);
 
         // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = process_tool_response(response, None).unwrap();
 
         // Verify the response is unchanged
         assert_eq!(processed.len(), 1);
@@ -106,13 +244,13 @@ mod tests {
     #[test]
     fn test_large_text_response_redirected_to_file() {
         // Create a text larger than the threshold
-        let large_text = "a".repeat(LARGE_TEXT_THRESHOLD + 1000);
+        let large_text = "a".repeat(DEFAULT_SPILL_THRESHOLD_BYTES + 1000);
         let content = Content::text(large_text.clone());
 
         let response = Ok(vec![content]);
 
         // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = process_tool_response(response, None).unwrap();
 
         // Verify the response contains a message about the file
         assert_eq!(processed.len(), 1);
@@ -123,7 +261,12 @@ mod tests {
             assert!(text_content.text.contains("characters"));
 
             // Extract the file path from the message
-            if let Some(file_path) = text_content.text.split("stored in the file: ").nth(1) {
+            if let Some(file_path) = text_content
+                .text
+                .split("stored in the file: ")
+                .nth(1)
+                .and_then(|rest| rest.lines().next())
+            {
                 // Verify the file exists and contains the original text
                 let path = Path::new(file_path.trim());
                 if path.exists() {
@@ -149,7 +292,7 @@ mod tests {
         let response = Ok(vec![image_content]);
 
         // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = process_tool_response(response, None).unwrap();
 
         // Verify the response is unchanged
         assert_eq!(processed.len(), 1);
@@ -165,13 +308,13 @@ mod tests {
     fn test_mixed_content_handled_correctly() {
         // Create a response with mixed content types
         let small_text = Content::text("Small text");
-        let large_text = Content::text("a".repeat(LARGE_TEXT_THRESHOLD + 1000));
+        let large_text = Content::text("a".repeat(DEFAULT_SPILL_THRESHOLD_BYTES + 1000));
         let image = Content::image("image_data".to_string(), "image/jpeg".to_string());
 
         let response = Ok(vec![small_text, large_text, image]);
 
         // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = process_tool_response(response, None).unwrap();
 
         // Verify each item is handled correctly
         assert_eq!(processed.len(), 3);
@@ -190,7 +333,12 @@ mod tests {
                 .contains("The response returned from the tool call was larger"));
 
             // Extract the file path and clean up
-            if let Some(file_path) = text_content.text.split("stored in the file: ").nth(1) {
+            if let Some(file_path) = text_content
+                .text
+                .split("stored in the file: ")
+                .nth(1)
+                .and_then(|rest| rest.lines().next())
+            {
                 let path = Path::new(file_path.trim());
                 if path.exists() {
                     let _ = fs::remove_file(path); // Ignore errors on cleanup
@@ -209,6 +357,97 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_spill_under_session_dir_with_preview() {
+        let root = tempfile::tempdir().unwrap();
+        let config = SpillConfig {
+            threshold_bytes: 100,
+            retention: Duration::from_secs(3600),
+            root: root.path().to_path_buf(),
+            dir: root.path().join("session-1"),
+            blobs: None,
+        };
+        let text = (1..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let processed =
+            spill_large_contents(Ok(vec![Content::text(text.clone())]), &config).unwrap();
+        let message = &processed[0].as_text().unwrap().text;
+        assert!(message.contains("line 1\n"));
+        assert!(message.contains("[60 lines omitted]"));
+        assert!(message.ends_with("line 100"));
+        assert!(!message.contains("line 50\n"));
+
+        let file_path = message
+            .split("stored in the file: ")
+            .nth(1)
+            .and_then(|rest| rest.lines().next())
+            .unwrap();
+        assert!(Path::new(file_path).starts_with(root.path().join("session-1")));
+        assert_eq!(fs::read_to_string(file_path).unwrap(), text);
+    }
+
+    #[test]
+    fn test_spill_into_blob_store_deduplicates() {
+        let root = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(root.path().join("blobs"));
+        let spill = |session: &str| {
+            let config = SpillConfig {
+                threshold_bytes: 10,
+                retention: Duration::from_secs(3600),
+                root: root.path().to_path_buf(),
+                dir: root.path().join(session),
+                blobs: Some((store.clone(), session.to_string())),
+            };
+            let processed =
+                spill_large_contents(Ok(vec![Content::text("x".repeat(100))]), &config).unwrap();
+            let message = processed[0].as_text().unwrap().text.clone();
+            message
+                .split("stored in the file: ")
+                .nth(1)
+                .and_then(|rest| rest.lines().next())
+                .unwrap()
+                .to_string()
+        };
+
+        let first = spill("session-1");
+        assert_eq!(first, spill("session-2"));
+        assert!(Path::new(&first).starts_with(root.path().join("blobs")));
+        assert_eq!(fs::read_to_string(&first).unwrap(), "x".repeat(100));
+        assert!(!root.path().join("session-1").exists());
+    }
+
+    #[test]
+    fn test_prune_expired() {
+        let root = tempfile::tempdir().unwrap();
+        let session_dir = root.path().join("old-session");
+        let file = write_large_text_to_file("stale", &session_dir).unwrap();
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(2 * 3600))
+            .unwrap();
+
+        prune_expired(root.path(), Duration::from_secs(3 * 3600));
+        assert!(file.exists());
+
+        prune_expired(root.path(), Duration::from_secs(3600));
+        assert!(!file.exists());
+        assert!(!session_dir.exists());
+    }
+
+    #[test]
+    fn test_preview_of_single_long_line() {
+        let text = "x".repeat(PREVIEW_CHARS * 3);
+        let preview = preview(&text);
+        assert!(preview.contains(&format!("[{} characters omitted]", PREVIEW_CHARS)));
+        assert!(preview.len() < text.len());
+        assert_eq!(super::preview("short"), "short");
+    }
+
     #[test]
     fn test_error_response_passes_through() {
         // Create an error response
@@ -220,7 +459,7 @@ mod tests {
         let response: Result<Vec<Content>, ErrorData> = Err(error);
 
         // Process the response
-        let processed = process_tool_response(response);
+        let processed = process_tool_response(response, None);
 
         // Verify the error is passed through unchanged
         assert!(processed.is_err());
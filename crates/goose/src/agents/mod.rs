@@ -1,15 +1,20 @@
 mod agent;
+pub mod ask_user_tool;
+pub mod authorized_client;
 mod context;
 pub mod extension;
+pub mod extension_env;
 pub mod extension_malware_check;
 pub mod extension_manager;
 pub mod final_output_tool;
 mod large_response_handler;
 pub mod mcp_client;
 pub mod model_selector;
+mod output_filters;
 pub mod platform_tools;
 pub mod prompt_manager;
 pub mod recipe_tools;
+pub mod remote_auth;
 mod reply_parts;
 pub mod retry;
 mod router_tool_selector;
@@ -20,8 +25,11 @@ pub mod subagent;
 pub mod subagent_execution_tool;
 pub mod subagent_handler;
 mod subagent_task_config;
+pub mod timer_extension;
 pub(crate) mod todo_extension;
 mod tool_execution;
+mod tool_locks;
+pub mod tool_recording;
 mod tool_route_manager;
 mod tool_router_index_manager;
 pub mod types;
@@ -32,4 +40,4 @@ pub use extension_manager::ExtensionManager;
 pub use prompt_manager::PromptManager;
 pub use subagent::{SubAgent, SubAgentProgress, SubAgentStatus};
 pub use subagent_task_config::TaskConfig;
-pub use types::{FrontendTool, RetryConfig, SessionConfig, SuccessCheck};
+pub use types::{FrontendTool, RetryConfig, SessionConfig, StopReason, SuccessCheck};
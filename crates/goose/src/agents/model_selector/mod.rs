@@ -1 +1,2 @@
 pub mod autopilot;
+pub mod task_router;
@@ -0,0 +1,302 @@
+//! Route each user request to the model configured for its kind of work.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::providers;
+use crate::providers::base::Provider;
+
+pub const TASK_ROUTER_KEY: &str = "GOOSE_TASK_ROUTER";
+
+/// Longest slice of the user's message sent to the classifier
+const MAX_CLASSIFIER_INPUT_CHARS: usize = 2000;
+
+const CLASSIFIER_PROMPT: &str = "Classify the user's request into exactly one category and reply \
+with only the category name.\n\
+code_edit: writing, changing, fixing or refactoring code or files\n\
+question: asking for an explanation or information, no changes needed\n\
+planning: designing an approach, breaking down a large task, weighing options\n\
+data_transform: converting, extracting, reformatting or analysing data";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskCategory {
+    CodeEdit,
+    Question,
+    Planning,
+    DataTransform,
+}
+
+impl TaskCategory {
+    pub const ALL: [TaskCategory; 4] = [
+        TaskCategory::CodeEdit,
+        TaskCategory::Question,
+        TaskCategory::Planning,
+        TaskCategory::DataTransform,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskCategory::CodeEdit => "code_edit",
+            TaskCategory::Question => "question",
+            TaskCategory::Planning => "planning",
+            TaskCategory::DataTransform => "data_transform",
+        }
+    }
+
+    /// Find the category a classifier answered with, tolerating extra words and punctuation
+    fn from_classifier_reply(reply: &str) -> Option<Self> {
+        let reply = reply.to_lowercase().replace(['-', ' '], "_");
+        Self::ALL
+            .into_iter()
+            .filter_map(|category| reply.find(category.as_str()).map(|pos| (pos, category)))
+            .min_by_key(|(pos, _)| *pos)
+            .map(|(_, category)| category)
+    }
+}
+
+impl fmt::Display for TaskCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase().replace(['-', ' '], "_");
+        Self::ALL
+            .into_iter()
+            .find(|category| category.as_str() == normalized)
+            .ok_or_else(|| {
+                format!(
+                    "unknown task category '{}', expected one of: {}",
+                    s,
+                    Self::ALL.map(|c| c.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteTarget {
+    pub provider: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RouterConfig {
+    #[serde(default)]
+    pub classifier: Option<RouteTarget>,
+    #[serde(default)]
+    pub routes: HashMap<TaskCategory, RouteTarget>,
+}
+
+pub struct RoutingDecision {
+    pub category: TaskCategory,
+    pub model: String,
+    /// Whether the category came from a user override rather than the classifier
+    pub overridden: bool,
+    pub provider: Arc<dyn Provider>,
+}
+
+pub struct TaskRouter {
+    config: Option<RouterConfig>,
+    /// The provider the session started with, used for categories without a route
+    default_provider: Option<Arc<dyn Provider>>,
+    override_next: Option<TaskCategory>,
+    current: Option<TaskCategory>,
+}
+
+impl TaskRouter {
+    pub fn new() -> Self {
+        let config = Config::global()
+            .get_param::<RouterConfig>(TASK_ROUTER_KEY)
+            .ok()
+            .filter(|config| !config.routes.is_empty());
+        Self {
+            config,
+            default_provider: None,
+            override_next: None,
+            current: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Use `category` for the next user message instead of asking the classifier
+    pub fn set_override(&mut self, category: Option<TaskCategory>) {
+        self.override_next = category;
+    }
+
+    /// Pick the provider for the latest user message, if it should change
+    pub async fn route(
+        &mut self,
+        conversation: &Conversation,
+        current_provider: Arc<dyn Provider>,
+    ) -> Result<Option<RoutingDecision>> {
+        let Some(config) = self.config.clone() else {
+            return Ok(None);
+        };
+        let Some(request) = latest_user_text(conversation) else {
+            return Ok(None);
+        };
+        let default_provider = self
+            .default_provider
+            .get_or_insert_with(|| current_provider.clone())
+            .clone();
+
+        let (category, overridden) = match self.override_next.take() {
+            Some(category) => (category, true),
+            None => match classify(&config, &request, &default_provider).await {
+                Some(category) => (category, false),
+                None => return Ok(None),
+            },
+        };
+        debug!(
+            "Task router classified request as {} (override: {})",
+            category, overridden
+        );
+
+        if self.current == Some(category) {
+            return Ok(None);
+        }
+        self.current = Some(category);
+
+        let (provider, model) = match config.routes.get(&category) {
+            Some(target) => {
+                let model_config = crate::model::ModelConfig::new_or_fail(&target.model);
+                (
+                    providers::create(&target.provider, model_config)?,
+                    target.model.clone(),
+                )
+            }
+            None => {
+                let model = default_provider.get_model_config().model_name;
+                (default_provider, model)
+            }
+        };
+
+        Ok(Some(RoutingDecision {
+            category,
+            model,
+            overridden,
+            provider,
+        }))
+    }
+}
+
+impl Default for TaskRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Text of the last message, if it is a message the user typed
+fn latest_user_text(conversation: &Conversation) -> Option<String> {
+    let last = conversation.messages().last()?;
+    if last.role != rmcp::model::Role::User || !last.has_only_text_content() {
+        return None;
+    }
+    let text = last.as_concat_text();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.chars().take(MAX_CLASSIFIER_INPUT_CHARS).collect())
+}
+
+async fn classify(
+    config: &RouterConfig,
+    request: &str,
+    default_provider: &Arc<dyn Provider>,
+) -> Option<TaskCategory> {
+    let messages = [Message::user().with_text(request)];
+    let reply = match &config.classifier {
+        Some(target) => {
+            let model_config = crate::model::ModelConfig::new_or_fail(&target.model);
+            match providers::create(&target.provider, model_config) {
+                Ok(classifier) => classifier.complete(CLASSIFIER_PROMPT, &messages, &[]).await,
+                Err(e) => {
+                    warn!("Failed to create task classifier: {}", e);
+                    return None;
+                }
+            }
+        }
+        None => {
+            default_provider
+                .complete_fast(CLASSIFIER_PROMPT, &messages, &[])
+                .await
+        }
+    };
+
+    match reply {
+        Ok((message, _)) => {
+            let category = TaskCategory::from_classifier_reply(&message.as_concat_text());
+            if category.is_none() {
+                warn!(
+                    "Task classifier gave an unrecognised answer: {}",
+                    message.as_concat_text()
+                );
+            }
+            category
+        }
+        Err(e) => {
+            warn!("Task classification failed: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_parsing() {
+        assert_eq!(
+            "code-edit".parse::<TaskCategory>().unwrap(),
+            TaskCategory::CodeEdit
+        );
+        assert_eq!(
+            " Data Transform ".parse::<TaskCategory>().unwrap(),
+            TaskCategory::DataTransform
+        );
+        assert!("refactor".parse::<TaskCategory>().is_err());
+    }
+
+    #[test]
+    fn test_classifier_reply() {
+        assert_eq!(
+            TaskCategory::from_classifier_reply("planning"),
+            Some(TaskCategory::Planning)
+        );
+        assert_eq!(
+            TaskCategory::from_classifier_reply("Category: Code edit."),
+            Some(TaskCategory::CodeEdit)
+        );
+        assert_eq!(TaskCategory::from_classifier_reply("no idea"), None);
+    }
+
+    #[test]
+    fn test_router_config() {
+        let config: RouterConfig = serde_yaml::from_str(
+            "routes:\n  code_edit:\n    provider: anthropic\n    model: claude-sonnet-4\n",
+        )
+        .unwrap();
+        assert!(config.classifier.is_none());
+        assert_eq!(
+            config.routes[&TaskCategory::CodeEdit].model,
+            "claude-sonnet-4"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Shell commands, configured in `GOOSE_TOOL_OUTPUT_FILTERS`, that tool output is piped through.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use rmcp::model::{CallToolRequestParam, Content, JsonObject, RawContent};
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::mcp_utils::ToolResult;
+
+pub const OUTPUT_FILTERS_KEY: &str = "GOOSE_TOOL_OUTPUT_FILTERS";
+const DEFAULT_FILTER_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutputFilter {
+    /// Pattern for the full tool name, e.g. `developer__shell`
+    pub tool: String,
+    /// Patterns that the named string arguments must match for the filter to apply
+    #[serde(default)]
+    pub arguments: HashMap<String, String>,
+    /// Shell command that receives the tool output on stdin and prints the filtered output
+    pub command: String,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn is_match(pattern: &str, text: &str, whole: bool) -> bool {
+    let pattern = if whole {
+        format!("^(?:{})$", pattern)
+    } else {
+        pattern.to_string()
+    };
+    match Regex::new(&pattern) {
+        Ok(re) => re.is_match(text),
+        Err(e) => {
+            warn!(
+                "Ignoring output filter with invalid pattern {}: {}",
+                pattern, e
+            );
+            false
+        }
+    }
+}
+
+impl OutputFilter {
+    pub fn matches(&self, tool_name: &str, arguments: Option<&JsonObject>) -> bool {
+        if !is_match(&self.tool, tool_name, true) {
+            return false;
+        }
+        self.arguments.iter().all(|(name, pattern)| {
+            arguments
+                .and_then(|args| args.get(name))
+                .and_then(|value| value.as_str())
+                .is_some_and(|value| is_match(pattern, value, false))
+        })
+    }
+
+    async fn run(&self, input: &str) -> Result<String> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut child = Command::new(shell)
+            .arg(flag)
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("failed to start output filter '{}'", self.command))?;
+
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("failed to open filter stdin"))?;
+        let input = input.to_string();
+        // Feed stdin concurrently so a filter that writes before reading everything can't deadlock
+        let writer = tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+
+        let timeout = Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_FILTER_TIMEOUT_SECS));
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| anyhow!("output filter '{}' timed out", self.command))??;
+        let _ = writer.await;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "output filter '{}' exited with {}: {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// The configured filters that apply to this tool call
+pub fn filters_for(tool_call: &CallToolRequestParam) -> Vec<OutputFilter> {
+    Config::global()
+        .get_param::<Vec<OutputFilter>>(OUTPUT_FILTERS_KEY)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|f| f.matches(&tool_call.name, tool_call.arguments.as_ref()))
+        .collect()
+}
+
+/// Run the text parts of a successful tool result through each filter in turn
+pub async fn apply_filters(
+    filters: &[OutputFilter],
+    response: ToolResult<Vec<Content>>,
+) -> ToolResult<Vec<Content>> {
+    let contents = response?;
+    if filters.is_empty() {
+        return Ok(contents);
+    }
+
+    let mut filtered = Vec::with_capacity(contents.len());
+    for mut content in contents {
+        // Only the text is replaced, so annotations such as the audience still apply
+        if let RawContent::Text(raw) = &mut content.raw {
+            for filter in filters {
+                match filter.run(&raw.text).await {
+                    Ok(output) => raw.text = output,
+                    Err(e) => warn!("{}", e),
+                }
+            }
+        }
+        filtered.push(content);
+    }
+    Ok(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Role;
+    use rmcp::object;
+
+    fn filter(tool: &str, arguments: &[(&str, &str)], command: &str) -> OutputFilter {
+        OutputFilter {
+            tool: tool.to_string(),
+            arguments: arguments
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            command: command.to_string(),
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let cargo = filter("developer__shell", &[("command", "^cargo test")], "cat");
+        let args = object!({"command": "cargo test -p goose"});
+        assert!(cargo.matches("developer__shell", Some(&args)));
+        assert!(!cargo.matches("developer__shell", Some(&object!({"command": "ls"}))));
+        assert!(!cargo.matches("developer__shell", None));
+        assert!(!cargo.matches("other__developer__shell", Some(&args)));
+
+        let api = filter("api__.*", &[], "cat");
+        assert!(api.matches("api__list_users", None));
+        assert!(!api.matches("myapi__list_users", None));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_apply_filters() {
+        let filters = vec![
+            filter("developer__shell", &[], "grep -v Compiling"),
+            filter("developer__shell", &[], "tr a-z A-Z"),
+        ];
+        let response = Ok(vec![
+            Content::text("   Compiling goose\ntest result: ok\n"),
+            Content::text("for the user only\n")
+                .with_audience(vec![Role::User])
+                .with_priority(0.2),
+        ]);
+        let filtered = apply_filters(&filters, response).await.unwrap();
+        assert_eq!(filtered[0].as_text().unwrap().text, "TEST RESULT: OK\n");
+        assert_eq!(filtered[1].as_text().unwrap().text, "FOR THE USER ONLY\n");
+        assert_eq!(filtered[1].audience(), Some(&vec![Role::User]));
+        assert_eq!(filtered[1].priority(), Some(0.2));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_filter_keeps_output() {
+        let filters = vec![filter("developer__shell", &[], "exit 3")];
+        let response = Ok(vec![Content::text("original")]);
+        let filtered = apply_filters(&filters, response).await.unwrap();
+        assert_eq!(filtered[0].as_text().unwrap().text, "original");
+    }
+}
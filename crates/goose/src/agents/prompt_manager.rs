@@ -4,6 +4,7 @@ use std::collections::HashMap;
 
 use crate::agents::extension::ExtensionInfo;
 use crate::agents::router_tools::llm_search_tool_prompt;
+use crate::config::prompt_sections::{applicable_sections, PromptSectionManager};
 use crate::providers::base::get_current_model;
 use crate::{config::Config, prompt_template, utils::sanitize_unicode_tags};
 
@@ -71,6 +72,8 @@ impl PromptManager {
         router_enabled: bool,
     ) -> String {
         let mut context: HashMap<&str, Value> = HashMap::new();
+        let enabled_extensions: Vec<String> =
+            extensions_info.iter().map(|ext| ext.name.clone()).collect();
         let mut extensions_info = extensions_info.clone();
 
         // Add frontend instructions to extensions_info to simplify json rendering
@@ -151,6 +154,13 @@ impl PromptManager {
                 .push("Right now you are *NOT* in the chat only mode and have access to tool use and system.".to_string());
         }
 
+        let user_sections = PromptSectionManager::get_all();
+        system_prompt_extras.extend(
+            applicable_sections(&user_sections, &goose_mode, &enabled_extensions)
+                .into_iter()
+                .map(|section| section.text.clone()),
+        );
+
         let sanitized_system_prompt_extras: Vec<String> = system_prompt_extras
             .into_iter()
             .map(|extra| sanitize_unicode_tags(&extra))
@@ -65,6 +65,35 @@ pub fn create_dynamic_task_tool() -> Tool {
                             "return_last_only": {
                                 "type": "boolean",
                                 "description": "If true, return only the last message from the subagent (default: false, returns full conversation)"
+                            },
+                            "depends_on": {
+                                "type": "array",
+                                "items": {"type": "integer"},
+                                "description": "Positions (from 0) in task_parameters of the tasks that must complete before this one starts; if one fails, this task is skipped. Only applies to parallel execution."
+                            },
+                            "timeout_seconds": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "description": "How long the task may run before it is stopped and fails (default: no limit)"
+                            },
+                            "on_timeout": {
+                                "type": "string",
+                                "enum": ["kill", "wrap_up"],
+                                "description": "What happens when the task runs out of time: kill stops it right away; wrap_up first asks the subagent to summarize what it has (default: kill)"
+                            },
+                            "priority": {
+                                "type": "string",
+                                "enum": ["low", "normal", "high"],
+                                "description": "Which tasks start first when more are waiting than may run at once; higher priorities start before lower ones (default: normal). Only applies to parallel execution."
+                            },
+                            "artifacts": {
+                                "type": "array",
+                                "items": {"type": "string"},
+                                "description": "Files or directories, relative to the working directory, the task leaves for later steps; once it completes they are copied into artifacts/<task_id>/ with its result.json"
+                            },
+                            "cache": {
+                                "type": "boolean",
+                                "description": "If true, a task with the same recipe, inputs and model that completed earlier gives its result instead of running again. Only for tasks whose work has no effects, like summarizing (default: false)"
                             }
                         }
                     },
@@ -238,6 +267,30 @@ fn extract_task_parameters(params: &Value) -> Vec<Value> {
         .unwrap_or_default()
 }
 
+/// Turn the positions in each task's `depends_on` into the ids of those tasks
+fn resolve_dependencies(tasks: &mut [Task], task_params: &[Value]) -> Result<(), String> {
+    let ids: Vec<String> = tasks.iter().map(|task| task.id.clone()).collect();
+    for (index, (task, task_param)) in tasks.iter_mut().zip(task_params).enumerate() {
+        let Some(depends_on) = task_param.get("depends_on").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for position in depends_on {
+            match position.as_u64().map(|p| p as usize) {
+                Some(position) if position < ids.len() && position != index => {
+                    task.depends_on.push(ids[position].clone())
+                }
+                _ => {
+                    return Err(format!(
+                        "Task {} can't depend on {}; use the positions of other tasks in task_parameters",
+                        index, position
+                    ))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn create_task_execution_payload(tasks: Vec<Task>, execution_mode: ExecutionMode) -> Value {
     let task_ids: Vec<String> = tasks.iter().map(|task| task.id.clone()).collect();
     json!({
@@ -289,8 +342,20 @@ pub async fn create_dynamic_task(
                     task_type: TaskType::InlineRecipe,
                     payload: json!({
                         "recipe": recipe_json,
-                        "return_last_only": return_last_only
+                        "return_last_only": return_last_only,
+                        "artifacts": task_param.get("artifacts"),
+                        "cache": task_param.get("cache")
                     }),
+                    depends_on: Vec::new(),
+                    timeout_seconds: task_param.get("timeout_seconds").and_then(|v| v.as_u64()),
+                    on_timeout: task_param
+                        .get("on_timeout")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                        .unwrap_or_default(),
+                    priority: task_param
+                        .get("priority")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                        .unwrap_or_default(),
                 };
                 tasks.push(task);
             }
@@ -304,6 +369,14 @@ pub async fn create_dynamic_task(
         }
     }
 
+    if let Err(e) = resolve_dependencies(&mut tasks, &task_params_array) {
+        return ToolCallResult::from(Err(ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: Cow::from(e),
+            data: None,
+        }));
+    }
+
     let execution_mode = params
         .get("execution_mode")
         .and_then(|v| v.as_str())
@@ -12,6 +12,13 @@ fn setup_default_sub_recipe() -> SubRecipe {
         values: Some(HashMap::from([("key1".to_string(), "value1".to_string())])),
         sequential_when_repeated: true,
         description: Some("Test subrecipe".to_string()),
+        resources: None,
+        retry: None,
+        timeout_seconds: None,
+        priority: None,
+        artifacts: Vec::new(),
+        depends_on: Vec::new(),
+        cache: false,
     }
 }
 
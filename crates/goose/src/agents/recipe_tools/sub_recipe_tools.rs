@@ -7,7 +7,7 @@ use rmcp::model::{Tool, ToolAnnotations};
 use serde_json::{json, Map, Value};
 
 use crate::agents::subagent_execution_tool::lib::ExecutionMode;
-use crate::agents::subagent_execution_tool::task_types::{Task, TaskType};
+use crate::agents::subagent_execution_tool::task_types::{OnTimeout, Task, TaskType};
 use crate::agents::subagent_execution_tool::tasks_manager::TasksManager;
 use crate::recipe::{Recipe, RecipeParameter, RecipeParameterRequirement, SubRecipe};
 
@@ -17,6 +17,15 @@ pub const SUB_RECIPE_TASK_TOOL_NAME_PREFIX: &str = "subrecipe__create_task";
 
 pub fn create_sub_recipe_task_tool(sub_recipe: &SubRecipe) -> Tool {
     let input_schema = get_input_schema(sub_recipe).unwrap();
+    let dependencies = if sub_recipe.depends_on.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\n\nThese tasks wait for the tasks of the {} sub recipes. Create those tasks first, \
+            then run them together with these in one parallel call.",
+            sub_recipe.depends_on.join(", ")
+        )
+    };
 
     Tool::new(
         format!("{}_{}", SUB_RECIPE_TASK_TOOL_NAME_PREFIX, sub_recipe.name),
@@ -27,8 +36,9 @@ pub fn create_sub_recipe_task_tool(sub_recipe: &SubRecipe) -> Tool {
             - For multiple tasks: provide an array with multiple parameter sets, each with different values\n\n\
             Each task will run the same sub recipe but with different parameter values. \
             This is useful when you need to execute the same sub recipe multiple times with varying inputs. \
-            After creating the tasks and execution_mode is provided, pass them to the task executor to run these tasks",
-            sub_recipe.name
+            After creating the tasks and execution_mode is provided, pass them to the task executor to run these tasks{}",
+            sub_recipe.name,
+            dependencies
         ),
         Arc::new(input_schema.as_object().unwrap().clone())
     ).annotate(ToolAnnotations {
@@ -51,9 +61,12 @@ fn extract_task_parameters(params: &Value) -> Vec<Value> {
         .unwrap_or_default()
 }
 
+/// A task for each set of `command_params`, each depending on `depends_on`, the tasks of the
+/// sub-recipes `sub_recipe` depends on
 fn create_tasks_from_params(
     sub_recipe: &SubRecipe,
     command_params: &[std::collections::HashMap<String, String>],
+    depends_on: &[String],
 ) -> Vec<Task> {
     let tasks: Vec<Task> = command_params
         .iter()
@@ -63,13 +76,21 @@ fn create_tasks_from_params(
                     "name": sub_recipe.name.clone(),
                     "command_parameters": task_command_param,
                     "recipe_path": sub_recipe.path.clone(),
-                    "sequential_when_repeated": sub_recipe.sequential_when_repeated
+                    "sequential_when_repeated": sub_recipe.sequential_when_repeated,
+                    "resources": sub_recipe.resources,
+                    "retry": sub_recipe.retry,
+                    "artifacts": sub_recipe.artifacts,
+                    "cache": sub_recipe.cache
                 }
             });
             Task {
                 id: uuid::Uuid::new_v4().to_string(),
                 task_type: TaskType::SubRecipe,
                 payload,
+                depends_on: depends_on.to_vec(),
+                timeout_seconds: sub_recipe.timeout_seconds,
+                on_timeout: OnTimeout::Kill,
+                priority: sub_recipe.priority.unwrap_or_default(),
             }
         })
         .collect();
@@ -97,7 +118,10 @@ pub async fn create_sub_recipe_task(
 ) -> Result<String> {
     let task_params_array = extract_task_parameters(&params);
     let command_params = prepare_command_params(sub_recipe, task_params_array.clone())?;
-    let tasks = create_tasks_from_params(sub_recipe, &command_params);
+    let depends_on = tasks_manager
+        .sub_recipe_task_ids(&sub_recipe.depends_on)
+        .await;
+    let tasks = create_tasks_from_params(sub_recipe, &command_params, &depends_on);
     let task_execution_payload = create_task_execution_payload(&tasks, sub_recipe);
 
     let tasks_json = serde_json::to_string(&task_execution_payload)
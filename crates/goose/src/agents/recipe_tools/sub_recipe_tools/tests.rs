@@ -12,6 +12,13 @@ fn setup_default_sub_recipe() -> SubRecipe {
         values: Some(HashMap::from([("key1".to_string(), "value1".to_string())])),
         sequential_when_repeated: true,
         description: Some("Test subrecipe".to_string()),
+        resources: None,
+        retry: None,
+        timeout_seconds: None,
+        priority: None,
+        artifacts: Vec::new(),
+        depends_on: Vec::new(),
+        cache: false,
     }
 }
 
@@ -126,3 +133,37 @@ mod get_input_schema {
         );
     }
 }
+
+mod create_sub_recipe_task {
+    use super::*;
+    use crate::agents::recipe_tools::sub_recipe_tools::create_sub_recipe_task;
+    use crate::agents::subagent_execution_tool::tasks_manager::TasksManager;
+
+    async fn created_tasks(
+        sub_recipe: &SubRecipe,
+        tasks_manager: &TasksManager,
+    ) -> Vec<crate::agents::subagent_execution_tool::task_types::Task> {
+        let payload = create_sub_recipe_task(sub_recipe, json!({}), tasks_manager)
+            .await
+            .unwrap();
+        let payload: Value = serde_json::from_str(&payload).unwrap();
+        let task_ids: Vec<String> = serde_json::from_value(payload["task_ids"].clone()).unwrap();
+        tasks_manager.get_tasks(&task_ids).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tasks_depend_on_the_tasks_of_their_sub_recipes_dependencies() {
+        let tasks_manager = TasksManager::new();
+        let mut fetch = setup_default_sub_recipe();
+        fetch.name = "fetch".to_string();
+        let mut summarize = setup_default_sub_recipe();
+        summarize.name = "summarize".to_string();
+        summarize.depends_on = vec!["fetch".to_string()];
+
+        let fetched = created_tasks(&fetch, &tasks_manager).await;
+        assert!(fetched[0].depends_on.is_empty());
+
+        let summaries = created_tasks(&summarize, &tasks_manager).await;
+        assert_eq!(summaries[0].depends_on, vec![fetched[0].id.clone()]);
+    }
+}
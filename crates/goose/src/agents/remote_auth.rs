@@ -0,0 +1,160 @@
+//! Static credentials for remote extensions whose servers don't use OAuth.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+/// How the secret is sent: `bearer`, `basic`, or `header:<name>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <secret>`
+    Bearer,
+    /// `Authorization: Basic ...`, with the secret as `user:password`
+    Basic,
+    /// The secret as it is, in the named header, such as `X-API-Key`
+    Header(String),
+}
+
+impl FromStr for AuthScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(name) = s.strip_prefix("header:") {
+            let name = name.trim();
+            HeaderName::from_str(name)
+                .with_context(|| format!("'{}' is not a valid header name", name))?;
+            return Ok(Self::Header(name.to_string()));
+        }
+        match s.to_lowercase().as_str() {
+            "bearer" => Ok(Self::Bearer),
+            "basic" => Ok(Self::Basic),
+            _ => bail!(
+                "Unknown auth scheme '{}'; expected bearer, basic or header:<name>",
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AuthScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bearer => write!(f, "bearer"),
+            Self::Basic => write!(f, "basic"),
+            Self::Header(name) => write!(f, "header:{}", name),
+        }
+    }
+}
+
+impl Serialize for AuthScheme {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for AuthScheme {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The `auth` section of a remote extension. When it is set the extension doesn't sign in
+/// with OAuth.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RemoteAuth {
+    #[schema(value_type = String, example = "header:X-API-Key")]
+    pub scheme: AuthScheme,
+    /// The name of the secret holding the token, the API key, or `user:password` for `basic`
+    pub secret: String,
+}
+
+impl RemoteAuth {
+    /// The header that carries `value` under this scheme
+    pub fn header(&self, value: &str) -> Result<(HeaderName, HeaderValue)> {
+        let (name, value) = match &self.scheme {
+            AuthScheme::Bearer => (AUTHORIZATION, format!("Bearer {}", value)),
+            AuthScheme::Basic => {
+                if !value.contains(':') {
+                    bail!(
+                        "The secret {} must be user:password for basic auth",
+                        self.secret
+                    );
+                }
+                let encoded = base64::engine::general_purpose::STANDARD.encode(value);
+                (AUTHORIZATION, format!("Basic {}", encoded))
+            }
+            AuthScheme::Header(name) => (HeaderName::from_str(name)?, value.to_string()),
+        };
+        let mut value = HeaderValue::from_str(&value)
+            .map_err(|_| anyhow!("The secret {} is not a valid header value", self.secret))?;
+        value.set_sensitive(true);
+        Ok((name, value))
+    }
+
+    /// The header for the secret as it is in the secrets store now
+    pub fn resolve(&self) -> Result<(HeaderName, HeaderValue)> {
+        let value: String = Config::global()
+            .get_secret(&self.secret)
+            .with_context(|| format!("The secret {} is not set", self.secret))?;
+        self.header(value.trim())
+    }
+
+    /// Add the header to `headers`, over any header of the same name
+    pub fn apply(&self, headers: &mut HeaderMap) -> Result<()> {
+        let (name, value) = self.resolve()?;
+        headers.insert(name, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(scheme: &str) -> RemoteAuth {
+        RemoteAuth {
+            scheme: scheme.parse().unwrap(),
+            secret: "DOCS_API_KEY".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_scheme_round_trip() {
+        let auth: RemoteAuth =
+            serde_yaml::from_str("scheme: header:X-API-Key\nsecret: DOCS_API_KEY").unwrap();
+        assert_eq!(auth.scheme, AuthScheme::Header("X-API-Key".to_string()));
+        let yaml = serde_yaml::to_string(&auth).unwrap();
+        assert_eq!(serde_yaml::from_str::<RemoteAuth>(&yaml).unwrap(), auth);
+        assert_eq!("Bearer".parse::<AuthScheme>().unwrap(), AuthScheme::Bearer);
+        assert!("digest".parse::<AuthScheme>().is_err());
+        assert!("header:not a header".parse::<AuthScheme>().is_err());
+    }
+
+    #[test]
+    fn test_header() {
+        let (name, value) = auth("bearer").header("abc").unwrap();
+        assert_eq!(name, AUTHORIZATION);
+        assert_eq!(value, "Bearer abc");
+        assert!(value.is_sensitive());
+
+        let (name, value) = auth("basic").header("dev:s3cret").unwrap();
+        assert_eq!(name, AUTHORIZATION);
+        assert_eq!(value, "Basic ZGV2OnMzY3JldA==");
+        assert!(auth("basic").header("no-colon").is_err());
+
+        let (name, value) = auth("header:X-API-Key").header("abc").unwrap();
+        assert_eq!(name.as_str(), "x-api-key");
+        assert_eq!(value, "abc");
+        assert!(auth("bearer").header("line\nbreak").is_err());
+    }
+}
@@ -10,6 +10,7 @@ use crate::conversation::message::{Message, MessageContent, ToolRequest};
 use crate::conversation::Conversation;
 use crate::providers::base::{stream_from_single_message, MessageStream, Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
+use crate::providers::timeouts::{with_timeouts, RequestDeadline, RequestTimeouts};
 use crate::providers::toolshim::{
     augment_message_with_tool_calls, convert_tool_messages_to_text,
     modify_system_prompt_for_tool_json, OllamaInterpreter,
@@ -134,6 +135,7 @@ impl Agent {
         messages: &[Message],
         tools: &[Tool],
         toolshim_tools: &[Tool],
+        deadline: &RequestDeadline,
     ) -> Result<MessageStream, ProviderError> {
         let config = provider.get_model_config();
 
@@ -150,43 +152,52 @@ impl Agent {
         let toolshim_tools = toolshim_tools.to_owned();
         let provider = provider.clone();
 
-        let mut stream = if provider.supports_streaming() {
-            debug!("WAITING_LLM_STREAM_START");
-            let msg_stream = provider
-                .stream(
-                    system_prompt.as_str(),
-                    messages_for_provider.messages(),
-                    &tools,
-                )
-                .await?;
-            debug!("WAITING_LLM_STREAM_END");
-            msg_stream
-        } else {
-            debug!("WAITING_LLM_START");
-            let (message, mut usage) = provider
-                .complete(
-                    system_prompt.as_str(),
-                    messages_for_provider.messages(),
-                    &tools,
-                )
-                .await?;
-            debug!("WAITING_LLM_END");
-
-            // Ensure we have token counts for non-streaming case
-            usage
-                .ensure_tokens(
-                    system_prompt.as_str(),
-                    messages_for_provider.messages(),
-                    &message,
-                    &tools,
-                )
-                .await?;
-
-            stream_from_single_message(message, usage)
+        let start = async {
+            if provider.supports_streaming() {
+                debug!("WAITING_LLM_STREAM_START");
+                let msg_stream = provider
+                    .stream(
+                        system_prompt.as_str(),
+                        messages_for_provider.messages(),
+                        &tools,
+                    )
+                    .await?;
+                debug!("WAITING_LLM_STREAM_END");
+                Ok::<_, ProviderError>(msg_stream)
+            } else {
+                debug!("WAITING_LLM_START");
+                let (message, mut usage) = provider
+                    .complete(
+                        system_prompt.as_str(),
+                        messages_for_provider.messages(),
+                        &tools,
+                    )
+                    .await?;
+                debug!("WAITING_LLM_END");
+
+                // Ensure we have token counts for non-streaming case
+                usage
+                    .ensure_tokens(
+                        system_prompt.as_str(),
+                        messages_for_provider.messages(),
+                        &message,
+                        &tools,
+                    )
+                    .await?;
+
+                Ok(stream_from_single_message(message, usage))
+            }
         };
+        let mut stream = with_timeouts(RequestTimeouts::from_config(), deadline, start).await?;
 
         Ok(Box::pin(try_stream! {
-            while let Some(Ok((mut message, usage))) = stream.next().await {
+            while let Some(next) = stream.next().await {
+                let (mut message, usage) = match next {
+                    Ok(item) => item,
+                    // The agent retries timeouts; other errors end the response as they always did
+                    Err(e @ ProviderError::Timeout(_)) => Err(e)?,
+                    Err(_) => break,
+                };
                 // Store the model information in the global store
                 if let Some(usage) = usage.as_ref() {
                     crate::providers::base::set_current_model(&usage.model);
@@ -293,15 +304,27 @@ impl Agent {
             accumulate(session.accumulated_input_tokens, usage.usage.input_tokens);
         let accumulated_output =
             accumulate(session.accumulated_output_tokens, usage.usage.output_tokens);
+        let accumulated_cached_input = accumulate(
+            session.accumulated_cached_input_tokens,
+            usage.usage.cached_input_tokens,
+        );
+        let accumulated_reasoning = accumulate(
+            session.accumulated_reasoning_tokens,
+            usage.usage.reasoning_tokens,
+        );
 
         SessionManager::update_session(session_id)
             .schedule_id(session_config.schedule_id.clone())
             .total_tokens(usage.usage.total_tokens)
             .input_tokens(usage.usage.input_tokens)
             .output_tokens(usage.usage.output_tokens)
+            .cached_input_tokens(usage.usage.cached_input_tokens)
+            .reasoning_tokens(usage.usage.reasoning_tokens)
             .accumulated_total_tokens(accumulated_total)
             .accumulated_input_tokens(accumulated_input)
             .accumulated_output_tokens(accumulated_output)
+            .accumulated_cached_input_tokens(accumulated_cached_input)
+            .accumulated_reasoning_tokens(accumulated_reasoning)
             .apply()
             .await?;
 
@@ -1,3 +1,4 @@
+use crate::agents::subagent_execution_tool::task_usage::TaskUsage;
 use crate::agents::subagent_task_config::DEFAULT_SUBAGENT_MAX_TURNS;
 use crate::{
     agents::extension::ExtensionConfig,
@@ -162,16 +163,37 @@ impl SubAgent {
         loop {
             loop_count += 1;
 
+            let wrap_up = self
+                .config
+                .wrap_up
+                .as_ref()
+                .filter(|wrap_up| wrap_up.signal.is_cancelled());
+            if let Some(wrap_up) = wrap_up {
+                debug!("Subagent {} is asked to wrap up", self.id);
+                messages.push(Message::user().with_text(wrap_up.prompt.clone()));
+            }
+            let turn_tools: &[Tool] = if wrap_up.is_some() { &[] } else { &tools };
+
             match Agent::generate_response_from_provider(
                 Arc::clone(provider),
                 &system_prompt,
                 messages.messages(),
-                &tools,
+                turn_tools,
                 &toolshim_tools,
             )
             .await
             {
-                Ok((response, _usage)) => {
+                Ok((response, usage)) => {
+                    if let Some(report) = &self.config.usage {
+                        report
+                            .tracker
+                            .add_usage(
+                                &report.task_id,
+                                &TaskUsage::of(provider.as_ref(), &usage).await,
+                            )
+                            .await;
+                    }
+
                     // Process any tool calls in the response
                     let tool_requests: Vec<ToolRequest> = response
                         .content
@@ -186,7 +208,7 @@ impl SubAgent {
                         .collect();
 
                     // If there are no tool requests, we're done
-                    if tool_requests.is_empty() || loop_count >= max_turns {
+                    if tool_requests.is_empty() || loop_count >= max_turns || wrap_up.is_some() {
                         self.add_message(response.clone()).await;
                         messages.push(response.clone());
 
@@ -0,0 +1,315 @@
+//! Collect the files tasks declare into `artifacts/<task_id>/`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+
+pub const ARTIFACTS_DIR_KEY: &str = "GOOSE_TASK_ARTIFACTS_DIR";
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const RESULT_FILE: &str = "result.json";
+
+/// What one task left behind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskArtifacts {
+    pub task_id: String,
+    pub name: String,
+    /// The copies, in `artifacts/<task_id>/`
+    pub files: Vec<String>,
+    /// Declared artifacts the task didn't leave
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+    /// Declared artifacts that can't be collected, with why
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rejected: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ArtifactManifest {
+    /// By task id
+    pub tasks: BTreeMap<String, TaskArtifacts>,
+}
+
+/// The name of the lock tasks declaring `declared` hold while they run, for a path inside the
+/// working directory
+pub fn artifact_lock(declared: &str) -> Option<String> {
+    match Path::new(declared)
+        .components()
+        .find(|c| !matches!(c, Component::CurDir))?
+    {
+        Component::Normal(top) => Some(format!("artifact:{}", top.to_string_lossy())),
+        _ => None,
+    }
+}
+
+/// Why the artifact `declared`, found at `source`, can't be collected into one of `roots`,
+/// the artifacts directory and where it'd be in the task's working directory, if it can't
+fn rejection(declared: &Path, source: &Path, roots: &[PathBuf]) -> Option<&'static str> {
+    let inside = declared
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Some("only paths inside the working directory are collected");
+    }
+    if roots
+        .iter()
+        .any(|root| root.starts_with(source) || source.starts_with(root))
+    {
+        return Some("it holds the artifacts directory or is in it");
+    }
+    match fs::symlink_metadata(source) {
+        Ok(metadata) if metadata.file_type().is_symlink() => Some("symbolic links aren't followed"),
+        _ => None,
+    }
+}
+
+/// Copy the file or directory `source` to `dest`, returning the files copied. Symbolic links
+/// in directories are left out, so nothing outside them is copied and links can't loop.
+fn copy_path(source: &Path, dest: &Path) -> io::Result<Vec<PathBuf>> {
+    let file_type = fs::symlink_metadata(source)?.file_type();
+    if file_type.is_symlink() {
+        return Ok(Vec::new());
+    }
+    if file_type.is_dir() {
+        let mut copied = Vec::new();
+        fs::create_dir_all(dest)?;
+        let mut entries = fs::read_dir(source)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            copied.extend(copy_path(&entry.path(), &dest.join(entry.file_name()))?);
+        }
+        return Ok(copied);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(source, dest)?;
+    Ok(vec![dest.to_path_buf()])
+}
+
+/// What a task returned, as JSON; sub-recipe runs return theirs as a string of JSON
+fn result_json(data: &Value) -> Value {
+    match data {
+        Value::String(text) => serde_json::from_str(text).unwrap_or_else(|_| data.clone()),
+        _ => data.clone(),
+    }
+}
+
+/// The `artifacts` directory of a working directory. Collecting is kept to one task at a time,
+/// so the manifest takes in every one of them.
+pub struct ArtifactStore {
+    root: PathBuf,
+    manifest: Mutex<()>,
+}
+
+impl Default for ArtifactStore {
+    fn default() -> Self {
+        let root = Config::global()
+            .get_param::<String>(ARTIFACTS_DIR_KEY)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("artifacts"));
+        Self::in_dir(root)
+    }
+}
+
+impl ArtifactStore {
+    pub fn in_dir(root: PathBuf) -> Self {
+        Self {
+            root,
+            manifest: Mutex::new(()),
+        }
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join(MANIFEST_FILE)
+    }
+
+    /// Copy the `declared` artifacts of the task `task_id` from `base`, the working directory
+    /// or the task's copy of it, into its directory, with `result` as its `result.json`, and
+    /// add them to the manifest
+    pub async fn collect(
+        &self,
+        task_id: &str,
+        name: &str,
+        base: &Path,
+        declared: &[String],
+        result: Option<&Value>,
+    ) -> Result<TaskArtifacts> {
+        let _manifest = self.manifest.lock().await;
+        let root = std::env::current_dir()?.join(&self.root);
+        let roots = [root.clone(), base.join(&self.root)];
+        let manifest_path = root.join(MANIFEST_FILE);
+        let base = base.to_path_buf();
+        let declared = declared.to_vec();
+        let result = result.cloned();
+        let mut artifacts = TaskArtifacts {
+            task_id: task_id.to_string(),
+            name: name.to_string(),
+            files: Vec::new(),
+            missing: Vec::new(),
+            rejected: BTreeMap::new(),
+        };
+        // The copying is blocking, and can take a while
+        tokio::task::spawn_blocking(move || {
+            let dir = root.join(&artifacts.task_id);
+            fs::create_dir_all(&dir)?;
+            if let Some(result) = result {
+                let path = dir.join(RESULT_FILE);
+                fs::write(&path, serde_json::to_vec_pretty(&result_json(&result))?)?;
+                artifacts.files.push(path.display().to_string());
+            }
+            for declared_path in declared {
+                let source = base.join(&declared_path);
+                if let Some(reason) = rejection(Path::new(&declared_path), &source, &roots) {
+                    artifacts
+                        .rejected
+                        .insert(declared_path.clone(), reason.to_string());
+                    continue;
+                }
+                if fs::symlink_metadata(&source).is_err() {
+                    artifacts.missing.push(declared_path);
+                    continue;
+                }
+                artifacts.files.extend(
+                    copy_path(&source, &dir.join(&declared_path))?
+                        .iter()
+                        .map(|path| path.display().to_string()),
+                );
+            }
+
+            let mut manifest: ArtifactManifest = fs::read_to_string(&manifest_path)
+                .ok()
+                .and_then(|contents| serde_json::from_str(&contents).ok())
+                .unwrap_or_default();
+            manifest
+                .tasks
+                .insert(artifacts.task_id.clone(), artifacts.clone());
+            fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)?;
+            Ok(artifacts)
+        })
+        .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_artifact_lock() {
+        assert_eq!(
+            artifact_lock("./coverage/html"),
+            Some("artifact:coverage".to_string())
+        );
+        assert_eq!(
+            artifact_lock("summary.md"),
+            Some("artifact:summary.md".to_string())
+        );
+        assert_eq!(artifact_lock("/tmp/out"), None);
+        assert_eq!(artifact_lock("../out"), None);
+    }
+
+    #[tokio::test]
+    async fn test_collect() {
+        let work = tempfile::tempdir().unwrap();
+        fs::create_dir_all(work.path().join("coverage/html")).unwrap();
+        fs::write(work.path().join("summary.md"), "all good").unwrap();
+        fs::write(work.path().join("coverage/html/index.html"), "<html>").unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let store = ArtifactStore::in_dir(root.path().to_path_buf());
+
+        let declared = [
+            "summary.md".to_string(),
+            "coverage".to_string(),
+            "missing.txt".to_string(),
+        ];
+        let result = json!(r#"{"passed": 12}"#);
+        let artifacts = store
+            .collect("task-1", "test", work.path(), &declared, Some(&result))
+            .await
+            .unwrap();
+
+        let dir = root.path().join("task-1");
+        let files: Vec<PathBuf> = artifacts.files.iter().map(PathBuf::from).collect();
+        assert_eq!(
+            files,
+            [
+                dir.join(RESULT_FILE),
+                dir.join("summary.md"),
+                dir.join("coverage/html/index.html")
+            ]
+        );
+        assert_eq!(artifacts.missing, ["missing.txt"]);
+        assert!(artifacts.rejected.is_empty());
+        let saved: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join(RESULT_FILE)).unwrap()).unwrap();
+        assert_eq!(saved, json!({"passed": 12}));
+
+        store
+            .collect("task-2", "lint", work.path(), &[], None)
+            .await
+            .unwrap();
+        let manifest: ArtifactManifest =
+            serde_json::from_str(&fs::read_to_string(store.manifest_path()).unwrap()).unwrap();
+        assert_eq!(manifest.tasks.len(), 2);
+        assert_eq!(manifest.tasks["task-1"], artifacts);
+    }
+
+    #[tokio::test]
+    async fn test_collect_only_what_is_inside_the_working_directory() {
+        let work = tempfile::tempdir().unwrap();
+        fs::create_dir_all(work.path().join("out")).unwrap();
+        fs::write(work.path().join("out/report.md"), "report").unwrap();
+        let store = ArtifactStore::in_dir(work.path().join("artifacts"));
+
+        let declared = [
+            ".".to_string(),
+            "artifacts".to_string(),
+            "artifacts/manifest.json".to_string(),
+            "../elsewhere".to_string(),
+            "/etc/hostname".to_string(),
+        ];
+        let artifacts = store
+            .collect("task-1", "test", work.path(), &declared, None)
+            .await
+            .unwrap();
+        assert!(artifacts.files.is_empty());
+        let rejected: Vec<&String> = artifacts.rejected.keys().collect();
+        assert_eq!(
+            rejected,
+            [
+                ".",
+                "../elsewhere",
+                "/etc/hostname",
+                "artifacts",
+                "artifacts/manifest.json"
+            ]
+        );
+
+        #[cfg(unix)]
+        {
+            // Neither links declared nor links inside declared directories are followed
+            std::os::unix::fs::symlink(work.path(), work.path().join("out/loop")).unwrap();
+            std::os::unix::fs::symlink("/etc/hostname", work.path().join("host")).unwrap();
+            let declared = ["out".to_string(), "host".to_string()];
+            let artifacts = store
+                .collect("task-2", "test", work.path(), &declared, None)
+                .await
+                .unwrap();
+            let dir = work.path().join("artifacts/task-2");
+            assert_eq!(
+                artifacts.files,
+                [dir.join("out/report.md").display().to_string()]
+            );
+            assert_eq!(artifacts.rejected.keys().collect::<Vec<_>>(), ["host"]);
+        }
+    }
+}
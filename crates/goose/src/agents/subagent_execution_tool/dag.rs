@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::agents::subagent_execution_tool::task_types::{Task, TaskResult, TaskStatus};
+
+/// Refuse tasks that depend on themselves through a cycle
+pub fn validate(tasks: &[Task]) -> Result<(), String> {
+    // Kahn's algorithm: whatever can't be ordered is on a cycle
+    let (mut graph, mut ready) = TaskGraph::new(tasks.to_vec());
+    let mut ordered = 0;
+    while let Some(task) = ready.pop() {
+        ordered += 1;
+        ready.extend(graph.finish(&task.id, true).ready);
+    }
+    if ordered < tasks.len() {
+        let mut on_cycle: Vec<&str> = graph.waiting.keys().map(String::as_str).collect();
+        on_cycle.sort();
+        return Err(format!(
+            "The dependencies of tasks {} form a cycle",
+            on_cycle.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// The stage of each of `tasks`, counting from 0: the tasks of a stage can start once every
+/// earlier stage completed. `tasks` must not have a cycle.
+pub fn stages(tasks: &[Task]) -> HashMap<String, usize> {
+    let (mut graph, mut ready) = TaskGraph::new(tasks.to_vec());
+    let mut stages = HashMap::new();
+    let mut stage = 0;
+    while !ready.is_empty() {
+        let mut next = Vec::new();
+        for task in ready {
+            next.extend(graph.finish(&task.id, true).ready);
+            stages.insert(task.id, stage);
+        }
+        ready = next;
+        stage += 1;
+    }
+    stages
+}
+
+/// What finishing a task lets happen next
+#[derive(Debug, Default)]
+pub struct Next {
+    /// Tasks whose dependencies have all completed
+    pub ready: Vec<Task>,
+    /// Tasks that won't run, because something they depend on didn't complete
+    pub skipped: Vec<TaskResult>,
+}
+
+/// The tasks of a parallel run still waiting on others. Tasks without dependencies are ready
+/// from the start; the others become ready once everything they depend on completed, or are
+/// skipped, along with whatever depends on them, as soon as one of those didn't.
+pub struct TaskGraph {
+    /// Each waiting task, with the dependencies it still waits for
+    waiting: HashMap<String, (Task, HashSet<String>)>,
+    dependents: HashMap<String, Vec<String>>,
+    names: HashMap<String, String>,
+}
+
+impl TaskGraph {
+    /// The graph of `tasks`, and the ones that can start right away
+    pub fn new(tasks: Vec<Task>) -> (Self, Vec<Task>) {
+        let mut graph = Self {
+            waiting: HashMap::new(),
+            dependents: HashMap::new(),
+            names: HashMap::new(),
+        };
+        let ids: HashSet<String> = tasks.iter().map(|task| task.id.clone()).collect();
+        let mut ready = Vec::new();
+        for task in tasks {
+            graph.names.insert(
+                task.id.clone(),
+                task.get_sub_recipe_name().unwrap_or(&task.id).to_string(),
+            );
+            // Dependencies outside the run ran before it, as when tasks are run one at a time
+            let dependencies: HashSet<String> = task
+                .depends_on
+                .iter()
+                .filter(|dependency| ids.contains(*dependency))
+                .cloned()
+                .collect();
+            if dependencies.is_empty() {
+                ready.push(task);
+                continue;
+            }
+            for dependency in &dependencies {
+                graph
+                    .dependents
+                    .entry(dependency.clone())
+                    .or_default()
+                    .push(task.id.clone());
+            }
+            graph.waiting.insert(task.id.clone(), (task, dependencies));
+        }
+        (graph, ready)
+    }
+
+    /// Whether every task has been started or skipped
+    pub fn is_done(&self) -> bool {
+        self.waiting.is_empty()
+    }
+
+    /// Take out every task still waiting, for a run that won't start any more
+    pub fn drain(&mut self) -> Vec<Task> {
+        self.dependents.clear();
+        self.waiting.drain().map(|(_, (task, _))| task).collect()
+    }
+
+    /// Note that the task `task_id` finished, and whether it `completed`
+    pub fn finish(&mut self, task_id: &str, completed: bool) -> Next {
+        let mut next = Next::default();
+        let mut finished = vec![(task_id.to_string(), completed)];
+        while let Some((task_id, completed)) = finished.pop() {
+            for dependent in self.dependents.remove(&task_id).unwrap_or_default() {
+                if !completed {
+                    if let Some((task, _)) = self.waiting.remove(&dependent) {
+                        next.skipped.push(self.skipped_result(&task, &task_id));
+                        finished.push((dependent, false));
+                    }
+                    continue;
+                }
+                let now_ready = match self.waiting.get_mut(&dependent) {
+                    Some((_, dependencies)) => {
+                        dependencies.remove(&task_id);
+                        dependencies.is_empty()
+                    }
+                    None => false,
+                };
+                if now_ready {
+                    if let Some((task, _)) = self.waiting.remove(&dependent) {
+                        next.ready.push(task);
+                    }
+                }
+            }
+        }
+        next
+    }
+
+    fn skipped_result(&self, task: &Task, because_of: &str) -> TaskResult {
+        TaskResult {
+            task_id: task.id.clone(),
+            status: TaskStatus::Skipped,
+            data: None,
+            error: Some(format!(
+                "Skipped because '{}' did not complete",
+                self.names
+                    .get(because_of)
+                    .map(String::as_str)
+                    .unwrap_or(because_of)
+            )),
+            attempts: Vec::new(),
+            artifacts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::subagent_execution_tool::task_types::TaskType;
+    use serde_json::Value;
+
+    fn task(id: &str, depends_on: &[&str]) -> Task {
+        Task {
+            id: id.to_string(),
+            task_type: TaskType::InlineRecipe,
+            payload: Value::Null,
+            depends_on: depends_on.iter().map(|id| id.to_string()).collect(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        }
+    }
+
+    fn ids(tasks: &[Task]) -> Vec<&str> {
+        let mut ids: Vec<&str> = tasks.iter().map(|task| task.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_tasks_start_once_their_dependencies_complete() {
+        let (mut graph, ready) = TaskGraph::new(vec![
+            task("fetch", &[]),
+            task("config", &[]),
+            task("build", &["fetch", "config"]),
+            task("test", &["build"]),
+        ]);
+        assert_eq!(ids(&ready), ["config", "fetch"]);
+
+        assert!(graph.finish("fetch", true).ready.is_empty());
+        assert_eq!(ids(&graph.finish("config", true).ready), ["build"]);
+        assert_eq!(ids(&graph.finish("build", true).ready), ["test"]);
+        assert!(graph.is_done());
+    }
+
+    #[test]
+    fn test_failures_skip_dependents() {
+        let (mut graph, _) = TaskGraph::new(vec![
+            task("fetch", &[]),
+            task("lint", &[]),
+            task("build", &["fetch"]),
+            task("test", &["build", "lint"]),
+        ]);
+        let next = graph.finish("fetch", false);
+        assert!(next.ready.is_empty());
+        let mut skipped: Vec<&str> = next.skipped.iter().map(|r| r.task_id.as_str()).collect();
+        skipped.sort();
+        assert_eq!(skipped, ["build", "test"]);
+        assert!(next
+            .skipped
+            .iter()
+            .all(|result| result.status == TaskStatus::Skipped));
+        assert!(graph.is_done());
+        assert!(graph.finish("lint", true).ready.is_empty());
+    }
+
+    #[test]
+    fn test_drain() {
+        let (mut graph, _) = TaskGraph::new(vec![
+            task("fetch", &[]),
+            task("build", &["fetch"]),
+            task("test", &["build"]),
+        ]);
+        assert_eq!(ids(&graph.drain()), ["build", "test"]);
+        assert!(graph.is_done());
+        let next = graph.finish("fetch", true);
+        assert!(next.ready.is_empty() && next.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_stages() {
+        let stages = stages(&[
+            task("fetch", &[]),
+            task("config", &[]),
+            task("build", &["fetch"]),
+            task("test", &["build", "config"]),
+            task("lint", &["earlier"]),
+        ]);
+        assert_eq!(stages["fetch"], 0);
+        assert_eq!(stages["config"], 0);
+        assert_eq!(stages["lint"], 0);
+        assert_eq!(stages["build"], 1);
+        assert_eq!(stages["test"], 2);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[task("a", &[]), task("b", &["a"])]).is_ok());
+        assert!(validate(&[task("a", &["earlier"])]).is_ok());
+        assert!(
+            validate(&[task("a", &["b"]), task("b", &["a"]), task("c", &[])])
+                .unwrap_err()
+                .contains("a, b form a cycle")
+        );
+        assert!(validate(&[task("a", &["a"])]).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! What a parallel run of tasks would do, without doing any of it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rmcp::model::ServerNotification;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+
+use crate::agents::subagent_execution_tool::dag;
+use crate::agents::subagent_execution_tool::progress_events::ProgressEvents;
+use crate::agents::subagent_execution_tool::resources::max_parallel_tasks;
+use crate::agents::subagent_execution_tool::task_execution_tracker::{
+    DisplayMode, TaskExecutionTracker,
+};
+use crate::agents::subagent_execution_tool::task_types::{Task, TaskType};
+use crate::recipe::build_recipe::build_recipe_from_template;
+use crate::recipe::read_recipe_file_content::read_recipe_file;
+use crate::recipe::Recipe;
+
+#[allow(clippy::type_complexity)]
+const NO_USER_PROMPT: Option<fn(&str, &str) -> Result<String, anyhow::Error>> = None;
+
+#[derive(Debug, Serialize)]
+pub struct PlannedTask {
+    pub id: String,
+    pub name: String,
+    pub task_type: String,
+    /// Counting from 0; a stage starts once the ones before it completed
+    pub stage: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Why the task couldn't start, if it couldn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Plan {
+    /// In the order the tasks would be queued
+    pub tasks: Vec<PlannedTask>,
+    pub stages: usize,
+    /// The most tasks that would run at once
+    pub estimated_concurrency: usize,
+}
+
+impl Plan {
+    pub fn new(tasks: &[Task], max_parallel: Option<usize>) -> Result<Self, String> {
+        dag::validate(tasks)?;
+        let stages = dag::stages(tasks);
+        let stage_count = stages.values().max().map_or(0, |last| last + 1);
+        let mut widths = vec![0; stage_count];
+        for stage in stages.values() {
+            widths[*stage] += 1;
+        }
+        let one_at_a_time = tasks.iter().any(|task| task.get_sequential_when_repeated());
+        let estimated_concurrency = if one_at_a_time {
+            tasks.len().min(1)
+        } else {
+            let widest = widths.into_iter().max().unwrap_or(0);
+            widest.min(max_parallel_tasks(max_parallel).max(1))
+        };
+
+        let tasks = tasks
+            .iter()
+            .map(|task| PlannedTask {
+                id: task.id.clone(),
+                name: task.get_sub_recipe_name().unwrap_or(&task.id).to_string(),
+                task_type: task.task_type.to_string(),
+                stage: stages.get(&task.id).copied().unwrap_or_default(),
+                depends_on: task.depends_on.clone(),
+                error: validate_task(task).err(),
+            })
+            .collect();
+        Ok(Self {
+            tasks,
+            stages: stage_count,
+            estimated_concurrency,
+        })
+    }
+
+    /// The tasks that couldn't start, with why, by task id
+    pub fn errors(&self) -> HashMap<String, String> {
+        self.tasks
+            .iter()
+            .filter_map(|task| Some((task.id.clone(), task.error.clone()?)))
+            .collect()
+    }
+}
+
+/// Check that `task` could start: its recipe reads and renders with the parameters it's given
+pub fn validate_task(task: &Task) -> Result<(), String> {
+    match task.task_type {
+        TaskType::SubRecipe => {
+            let path = task
+                .get_sub_recipe_path()
+                .ok_or_else(|| "Missing sub_recipe path".to_string())?;
+            let params = task
+                .get_command_parameters()
+                .map(|params| {
+                    params
+                        .iter()
+                        .map(|(key, value)| {
+                            let value = value.as_str().unwrap_or(&value.to_string()).to_string();
+                            (key.clone(), value)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let recipe_file = read_recipe_file(path).map_err(|e| e.to_string())?;
+            build_recipe_from_template(recipe_file, params, NO_USER_PROMPT)
+                .map_err(|e| e.to_string())?;
+        }
+        TaskType::InlineRecipe => {
+            let recipe_value = task
+                .payload
+                .get("recipe")
+                .ok_or_else(|| "Missing recipe in inline_recipe task payload".to_string())?;
+            let recipe: Recipe = serde_json::from_value(recipe_value.clone())
+                .map_err(|e| format!("Invalid recipe in payload: {}", e))?;
+            if recipe.instructions.is_none() && recipe.prompt.is_none() {
+                return Err("No instructions or prompt in recipe".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Show what running `tasks` in parallel would do in the dashboard, and return it as the plan
+pub async fn dry_run(
+    tasks: Vec<Task>,
+    max_parallel: Option<usize>,
+    notifier: Sender<ServerNotification>,
+    cancellation_token: Option<CancellationToken>,
+) -> Result<Value, String> {
+    let plan = Plan::new(&tasks, max_parallel)?;
+    let tracker = Arc::new(
+        TaskExecutionTracker::new(
+            tasks,
+            DisplayMode::MultipleTasksOutput,
+            notifier,
+            cancellation_token,
+        )
+        .with_progress_events(ProgressEvents::global().await),
+    );
+    tracker
+        .send_tasks_planned(&plan.errors(), plan.stages, plan.estimated_concurrency)
+        .await;
+    serde_json::to_value(plan).map_err(|e| format!("Failed to serialize plan: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn inline_task(id: &str, depends_on: &[&str], recipe: Value) -> Task {
+        Task {
+            id: id.to_string(),
+            task_type: TaskType::InlineRecipe,
+            payload: json!({ "recipe": recipe }),
+            depends_on: depends_on.iter().map(|id| id.to_string()).collect(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        }
+    }
+
+    fn recipe(instructions: &str) -> Value {
+        json!({
+            "version": "1.0.0",
+            "title": "Task",
+            "description": "A task",
+            "instructions": instructions,
+        })
+    }
+
+    #[test]
+    fn test_plan() {
+        let tasks = [
+            inline_task("fetch", &[], recipe("Fetch the data")),
+            inline_task("config", &[], json!({"title": "Config"})),
+            inline_task("build", &["fetch", "config"], recipe("Build it")),
+        ];
+        let plan = Plan::new(&tasks, Some(4)).unwrap();
+        assert_eq!(plan.stages, 2);
+        assert_eq!(plan.estimated_concurrency, 2);
+        let stages: Vec<usize> = plan.tasks.iter().map(|task| task.stage).collect();
+        assert_eq!(stages, [0, 0, 1]);
+        assert_eq!(plan.errors().len(), 1);
+        assert!(plan.errors()["config"].starts_with("Invalid recipe"));
+
+        let plan = Plan::new(&tasks, Some(1)).unwrap();
+        assert_eq!(plan.estimated_concurrency, 1);
+
+        let cycle = [
+            inline_task("a", &["b"], recipe("A")),
+            inline_task("b", &["a"], recipe("B")),
+        ];
+        assert!(Plan::new(&cycle, None).is_err());
+    }
+
+    #[test]
+    fn test_validate_sub_recipe_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("greet.yaml");
+        std::fs::write(
+            &path,
+            r#"version: "1.0.0"
+title: "Greet"
+description: "Greets someone"
+instructions: "Say hello to {{ name }}"
+parameters:
+  - key: name
+    input_type: string
+    requirement: required
+    description: "Who to greet"
+"#,
+        )
+        .unwrap();
+        let task = |params: Value| Task {
+            id: "greet".to_string(),
+            task_type: TaskType::SubRecipe,
+            payload: json!({
+                "sub_recipe": {
+                    "name": "greet",
+                    "recipe_path": path.to_str().unwrap(),
+                    "command_parameters": params,
+                }
+            }),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        };
+
+        assert!(validate_task(&task(json!({"name": "goose"}))).is_ok());
+        assert!(validate_task(&task(json!({})))
+            .unwrap_err()
+            .contains("Missing required parameters"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(validate_task(&task(json!({"name": "goose"})))
+            .unwrap_err()
+            .contains("Failed to read recipe file"));
+    }
+}
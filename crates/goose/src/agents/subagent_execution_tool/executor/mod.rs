@@ -1,13 +1,24 @@
+use crate::agents::subagent_execution_tool::dag::TaskGraph;
 use crate::agents::subagent_execution_tool::lib::{
-    ExecutionResponse, ExecutionStats, SharedState, Task, TaskResult, TaskStatus,
+    ExecutionResponse, ExecutionStats, FailurePolicy, SharedState, Task, TaskResult, TaskStatus,
 };
+use crate::agents::subagent_execution_tool::progress_events::ProgressEvents;
+use crate::agents::subagent_execution_tool::resources::ResourceScheduler;
+use crate::agents::subagent_execution_tool::result_cache::ResultCache;
+use crate::agents::subagent_execution_tool::run_history::RunHistory;
+use crate::agents::subagent_execution_tool::run_manifest::{Checkpoint, RunManifest};
+use crate::agents::subagent_execution_tool::run_report::ReportPaths;
 use crate::agents::subagent_execution_tool::task_execution_tracker::{
     DisplayMode, TaskExecutionTracker,
 };
-use crate::agents::subagent_execution_tool::tasks::process_task;
+use crate::agents::subagent_execution_tool::tasks::{cached_result, process_task, stopped_result};
+use crate::agents::subagent_execution_tool::tasks_manager::TasksManager;
+use crate::agents::subagent_execution_tool::webhooks::Webhooks;
 use crate::agents::subagent_execution_tool::workers::spawn_worker;
 use crate::agents::subagent_task_config::TaskConfig;
 use rmcp::model::ServerNotification;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -16,33 +27,51 @@ use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 
 const EXECUTION_STATUS_COMPLETED: &str = "completed";
-const DEFAULT_MAX_WORKERS: usize = 10;
 
 pub async fn execute_single_task(
     task: &Task,
     notifier: mpsc::Sender<ServerNotification>,
     task_config: TaskConfig,
+    tasks_manager: &TasksManager,
     cancellation_token: Option<CancellationToken>,
 ) -> ExecutionResponse {
     let start_time = Instant::now();
-    let task_execution_tracker = Arc::new(TaskExecutionTracker::new(
-        vec![task.clone()],
-        DisplayMode::SingleTaskOutput,
-        notifier,
-        cancellation_token.clone(),
-    ));
-    let result = process_task(
-        task,
-        task_execution_tracker.clone(),
-        task_config,
-        cancellation_token.unwrap_or_default(),
-    )
-    .await;
+    let task_execution_tracker = Arc::new(
+        TaskExecutionTracker::new(
+            vec![task.clone()],
+            DisplayMode::SingleTaskOutput,
+            notifier,
+            cancellation_token.clone(),
+        )
+        .with_progress_events(ProgressEvents::global().await)
+        .with_reports(ReportPaths::from_config())
+        .with_history(RunHistory::global().await)
+        .with_result_cache(result_cache(tasks_manager))
+        .with_restarts(tasks_manager.restarts()),
+    );
+    let task_token = tasks_manager
+        .track_cancellation(&task.id, cancellation_token.as_ref())
+        .await;
+    let result = match cached_result(task, &task_execution_tracker, &task_config).await {
+        Some(result) => result,
+        None => {
+            task_execution_tracker.start_task(&task.id).await;
+            process_task(
+                task,
+                task_execution_tracker.clone(),
+                task_config,
+                task_token,
+            )
+            .await
+        }
+    };
+    tasks_manager.untrack_cancellation(&task.id).await;
 
     // Complete the task in the tracker
     task_execution_tracker
         .complete_task(&result.task_id, result.clone())
         .await;
+    task_execution_tracker.finish_single_task().await;
 
     let execution_time = start_time.elapsed().as_millis();
     let stats = calculate_stats(std::slice::from_ref(&result), execution_time);
@@ -51,6 +80,7 @@ pub async fn execute_single_task(
         status: EXECUTION_STATUS_COMPLETED.to_string(),
         results: vec![result],
         stats,
+        run_id: None,
     }
 }
 
@@ -58,14 +88,75 @@ pub async fn execute_tasks_in_parallel(
     tasks: Vec<Task>,
     notifier: Sender<ServerNotification>,
     task_config: TaskConfig,
+    max_parallel: Option<usize>,
+    failure_policy: FailurePolicy,
+    tasks_manager: &TasksManager,
+    cancellation_token: Option<CancellationToken>,
+) -> ExecutionResponse {
+    if tasks.is_empty() {
+        return create_empty_response();
+    }
+    let checkpoint = Checkpoint::start(tasks.clone(), failure_policy).await;
+    run_in_parallel(
+        tasks,
+        checkpoint,
+        notifier,
+        task_config,
+        max_parallel,
+        tasks_manager,
+        cancellation_token,
+    )
+    .await
+}
+
+/// Run the tasks of `manifest`, kept in `dir`, that didn't complete, adding their results to it
+pub async fn resume_tasks_in_parallel(
+    manifest: RunManifest,
+    dir: PathBuf,
+    notifier: Sender<ServerNotification>,
+    task_config: TaskConfig,
+    tasks_manager: &TasksManager,
     cancellation_token: Option<CancellationToken>,
 ) -> ExecutionResponse {
-    let task_execution_tracker = Arc::new(TaskExecutionTracker::new(
-        tasks.clone(),
-        DisplayMode::MultipleTasksOutput,
+    let tasks = manifest.remaining();
+    let checkpoint = Checkpoint::in_dir(Some(dir), manifest).await;
+    run_in_parallel(
+        tasks,
+        checkpoint,
         notifier,
-        cancellation_token.clone(),
-    ));
+        task_config,
+        None,
+        tasks_manager,
+        cancellation_token,
+    )
+    .await
+}
+
+async fn run_in_parallel(
+    tasks: Vec<Task>,
+    checkpoint: Checkpoint,
+    notifier: Sender<ServerNotification>,
+    task_config: TaskConfig,
+    max_parallel: Option<usize>,
+    tasks_manager: &TasksManager,
+    cancellation_token: Option<CancellationToken>,
+) -> ExecutionResponse {
+    let task_execution_tracker = Arc::new(
+        TaskExecutionTracker::new(
+            tasks.clone(),
+            DisplayMode::MultipleTasksOutput,
+            notifier,
+            cancellation_token.clone(),
+        )
+        .with_progress_events(ProgressEvents::global().await)
+        .with_failure_policy(checkpoint.on_failure())
+        .with_reports(ReportPaths::from_config())
+        .with_history(RunHistory::global().await)
+        .with_webhooks(Webhooks::from_config())
+        .with_result_cache(result_cache(tasks_manager))
+        .with_pause(tasks_manager.pause_control())
+        .with_restarts(tasks_manager.restarts()),
+    );
     let start_time = Instant::now();
     let task_count = tasks.len();
 
@@ -74,11 +165,25 @@ pub async fn execute_tasks_in_parallel(
     }
 
     task_execution_tracker.refresh_display().await;
+    task_execution_tracker.start_ticker();
 
     let (task_tx, task_rx, result_tx, mut result_rx) = create_channels(task_count);
 
-    if let Err(e) = send_tasks_to_channel(tasks, task_tx).await {
+    let cancellation_token = cancellation_token.unwrap_or_default();
+    // Tokens for every task up front, so tasks can be cancelled before they start
+    let task_ids: Vec<String> = tasks.iter().map(|task| task.id.clone()).collect();
+    let mut task_tokens = HashMap::new();
+    for task_id in &task_ids {
+        let token = tasks_manager
+            .track_cancellation(task_id, Some(&cancellation_token))
+            .await;
+        task_tokens.insert(task_id.clone(), token);
+    }
+
+    let (graph, ready) = TaskGraph::new(tasks);
+    if let Err(e) = send_tasks_to_channel(ready, &task_tx).await {
         tracing::error!("Task execution failed: {}", e);
+        untrack_cancellations(tasks_manager, &task_ids).await;
         return create_error_response(e);
     }
 
@@ -86,17 +191,31 @@ pub async fn execute_tasks_in_parallel(
         task_rx,
         result_tx,
         task_execution_tracker.clone(),
-        cancellation_token.unwrap_or_default(),
+        cancellation_token,
+        task_tokens,
+        ResourceScheduler::from_config(max_parallel).with_pause(tasks_manager.pause_control()),
+        checkpoint.on_failure(),
     );
 
-    let worker_count = std::cmp::min(task_count, DEFAULT_MAX_WORKERS);
+    // A worker per task; the scheduler's slots decide how many of them run at once, and the
+    // others wait there as pending
     let mut worker_handles = Vec::new();
-    for i in 0..worker_count {
+    for i in 0..task_count {
         let handle = spawn_worker(shared_state.clone(), i, task_config.clone());
         worker_handles.push(handle);
     }
 
-    let results = collect_results(&mut result_rx, task_execution_tracker.clone(), task_count).await;
+    let results = collect_results(
+        &mut result_rx,
+        task_execution_tracker.clone(),
+        &checkpoint,
+        graph,
+        task_tx,
+        task_count,
+        &shared_state.stop_starting,
+    )
+    .await;
+    untrack_cancellations(tasks_manager, &task_ids).await;
 
     for handle in worker_handles {
         if let Err(e) = handle.await {
@@ -104,7 +223,10 @@ pub async fn execute_tasks_in_parallel(
         }
     }
 
-    task_execution_tracker.send_tasks_complete().await;
+    let run_id = checkpoint.run_id().await;
+    task_execution_tracker
+        .send_tasks_complete(run_id.clone())
+        .await;
 
     let execution_time = start_time.elapsed().as_millis();
     let stats = calculate_stats(&results, execution_time);
@@ -113,6 +235,7 @@ pub async fn execute_tasks_in_parallel(
         status: EXECUTION_STATUS_COMPLETED.to_string(),
         results,
         stats,
+        run_id,
     }
 }
 
@@ -125,15 +248,31 @@ fn calculate_stats(results: &[TaskResult], execution_time_ms: u128) -> Execution
         .iter()
         .filter(|r| matches!(r.status, TaskStatus::Failed))
         .count();
+    let cancelled = results
+        .iter()
+        .filter(|r| matches!(r.status, TaskStatus::Cancelled))
+        .count();
+    let skipped = results
+        .iter()
+        .filter(|r| matches!(r.status, TaskStatus::Skipped))
+        .count();
 
     ExecutionStats {
         total_tasks: results.len(),
         completed,
         failed,
+        cancelled,
+        skipped,
         execution_time_ms,
     }
 }
 
+async fn untrack_cancellations(tasks_manager: &TasksManager, task_ids: &[String]) {
+    for task_id in task_ids {
+        tasks_manager.untrack_cancellation(task_id).await;
+    }
+}
+
 fn create_channels(
     task_count: usize,
 ) -> (
@@ -152,6 +291,9 @@ fn create_shared_state(
     result_tx: mpsc::Sender<TaskResult>,
     task_execution_tracker: Arc<TaskExecutionTracker>,
     cancellation_token: CancellationToken,
+    task_tokens: HashMap<String, CancellationToken>,
+    resources: ResourceScheduler,
+    failure_policy: FailurePolicy,
 ) -> Arc<SharedState> {
     Arc::new(SharedState {
         task_receiver: Arc::new(tokio::sync::Mutex::new(task_rx)),
@@ -159,13 +301,25 @@ fn create_shared_state(
         active_workers: Arc::new(AtomicUsize::new(0)),
         task_execution_tracker,
         cancellation_token,
+        resources: Arc::new(resources),
+        task_tokens,
+        failure_policy,
+        stop_starting: CancellationToken::new(),
     })
 }
 
+/// The cache of task results, unless it's off for these tasks
+fn result_cache(tasks_manager: &TasksManager) -> Option<Arc<ResultCache>> {
+    ResultCache::from_config().filter(|_| tasks_manager.caches_results())
+}
+
+/// Queue `tasks` for the workers, those of the highest priority first so they are the first
+/// to ask for a slot
 async fn send_tasks_to_channel(
-    tasks: Vec<Task>,
-    task_tx: mpsc::Sender<Task>,
+    mut tasks: Vec<Task>,
+    task_tx: &mpsc::Sender<Task>,
 ) -> Result<(), String> {
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.priority));
     for task in tasks {
         task_tx
             .send(task)
@@ -183,22 +337,69 @@ fn create_empty_response() -> ExecutionResponse {
             total_tasks: 0,
             completed: 0,
             failed: 0,
+            cancelled: 0,
+            skipped: 0,
             execution_time_ms: 0,
         },
+        run_id: None,
     }
 }
+
+/// Gather the results, queueing tasks as their dependencies complete and skipping the ones
+/// whose dependencies didn't. Once the failure policy says so, `stop_starting` is cancelled and
+/// the tasks not started yet are stopped.
 async fn collect_results(
     result_rx: &mut mpsc::Receiver<TaskResult>,
     task_execution_tracker: Arc<TaskExecutionTracker>,
+    checkpoint: &Checkpoint,
+    mut graph: TaskGraph,
+    task_tx: mpsc::Sender<Task>,
     expected_count: usize,
+    stop_starting: &CancellationToken,
 ) -> Vec<TaskResult> {
+    // Idle workers stop once the queue closes, so it closes when nothing is left to queue
+    let mut task_tx = (!graph.is_done()).then_some(task_tx);
     let mut results = Vec::new();
+    let mut failures = 0;
     while let Some(result) = result_rx.recv().await {
         task_execution_tracker
             .complete_task(&result.task_id, result.clone())
             .await;
+        checkpoint.record(&result).await;
+        if result.status == TaskStatus::Failed {
+            failures += 1;
+        }
 
+        let next = graph.finish(&result.task_id, result.status == TaskStatus::Completed);
         results.push(result);
+        if let Some(task_tx) = &task_tx {
+            if let Err(e) = send_tasks_to_channel(next.ready, task_tx).await {
+                tracing::error!("Task execution failed: {}", e);
+            }
+        }
+        for skipped in next.skipped {
+            task_execution_tracker
+                .complete_task(&skipped.task_id, skipped.clone())
+                .await;
+            checkpoint.record(&skipped).await;
+            results.push(skipped);
+        }
+        if !stop_starting.is_cancelled() && checkpoint.on_failure().stops_after(failures) {
+            // Queued tasks are stopped by their workers; the ones still waiting on others here
+            stop_starting.cancel();
+            for task in graph.drain() {
+                let stopped = stopped_result(&task, checkpoint.on_failure());
+                task_execution_tracker.stop_by_policy(&task.id).await;
+                task_execution_tracker
+                    .complete_task(&task.id, stopped.clone())
+                    .await;
+                checkpoint.record(&stopped).await;
+                results.push(stopped);
+            }
+        }
+        if graph.is_done() {
+            task_tx = None;
+        }
         if results.len() >= expected_count {
             break;
         }
@@ -215,7 +416,10 @@ fn create_error_response(error: String) -> ExecutionResponse {
             total_tasks: 0,
             completed: 0,
             failed: 1,
+            cancelled: 0,
+            skipped: 0,
             execution_time_ms: 0,
         },
+        run_id: None,
     }
 }
@@ -13,6 +13,7 @@ fn create_test_task_result(task_id: &str, status: TaskStatus) -> TaskResult {
         } else {
             None
         },
+        attempts: Vec::new(),
     }
 }
 
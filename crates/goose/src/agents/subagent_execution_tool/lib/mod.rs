@@ -1,8 +1,12 @@
 pub use crate::agents::subagent_execution_tool::task_types::{
-    ExecutionMode, ExecutionResponse, ExecutionStats, SharedState, Task, TaskResult, TaskStatus,
+    ExecutionMode, ExecutionResponse, ExecutionStats, FailurePolicy, SharedState, Task, TaskResult,
+    TaskStatus,
 };
 use crate::agents::subagent_execution_tool::{
-    executor::{execute_single_task, execute_tasks_in_parallel},
+    dag,
+    dry_run::dry_run,
+    executor::{execute_single_task, execute_tasks_in_parallel, resume_tasks_in_parallel},
+    run_manifest::{manifest_dir, RunManifest},
     tasks_manager::TasksManager,
 };
 use crate::agents::subagent_task_config::TaskConfig;
@@ -28,13 +32,40 @@ pub async fn execute_tasks(
     .map_err(|e| format!("Failed to parse task_ids: {}", e))?;
 
     let tasks = tasks_manager.get_tasks(&task_ids).await?;
+    let max_parallel = input
+        .get("max_parallel")
+        .and_then(Value::as_u64)
+        .map(|max_parallel| max_parallel as usize);
+    let failure_policy = input
+        .get("on_failure")
+        .and_then(Value::as_str)
+        .map(FailurePolicy::parse)
+        .transpose()?
+        .unwrap_or_default();
+
+    if input
+        .get("dry_run")
+        .and_then(Value::as_bool)
+        .unwrap_or_default()
+    {
+        if execution_mode == ExecutionMode::Sequential {
+            return Err("dry_run applies only to parallel execution".to_string());
+        }
+        return dry_run(tasks, max_parallel, notifier, cancellation_token).await;
+    }
 
     let task_count = tasks.len();
     match execution_mode {
         ExecutionMode::Sequential => {
             if task_count == 1 {
-                let response =
-                    execute_single_task(&tasks[0], notifier, task_config, cancellation_token).await;
+                let response = execute_single_task(
+                    &tasks[0],
+                    notifier,
+                    task_config,
+                    tasks_manager,
+                    cancellation_token,
+                )
+                .await;
                 handle_response(response)
             } else {
                 Err("Sequential execution mode requires exactly one task".to_string())
@@ -50,10 +81,14 @@ pub async fn execute_tasks(
                     }
                 ))
             } else {
+                dag::validate(&tasks)?;
                 let response: ExecutionResponse = execute_tasks_in_parallel(
                     tasks,
                     notifier.clone(),
                     task_config,
+                    max_parallel,
+                    failure_policy,
+                    tasks_manager,
                     cancellation_token,
                 )
                 .await;
@@ -63,6 +98,31 @@ pub async fn execute_tasks(
     }
 }
 
+/// Run again the tasks of the parallel run `run_id` that didn't complete
+pub async fn resume_tasks(
+    run_id: &str,
+    notifier: Sender<ServerNotification>,
+    task_config: TaskConfig,
+    tasks_manager: &TasksManager,
+    cancellation_token: Option<CancellationToken>,
+) -> Result<Value, String> {
+    let dir = manifest_dir().map_err(|e| e.to_string())?;
+    let manifest = RunManifest::load(&dir, run_id).map_err(|e| format!("{:#}", e))?;
+    if manifest.remaining().is_empty() {
+        return Err(format!("Every task of run {} already completed", run_id));
+    }
+    let response = resume_tasks_in_parallel(
+        manifest,
+        dir,
+        notifier,
+        task_config,
+        tasks_manager,
+        cancellation_token,
+    )
+    .await;
+    handle_response(response)
+}
+
 fn extract_failed_tasks(results: &[TaskResult]) -> Vec<String> {
     results
         .iter()
@@ -117,5 +177,8 @@ fn handle_response(response: ExecutionResponse) -> Result<Value, String> {
 }
 
 fn get_task_description(result: &TaskResult) -> String {
-    format!("ID: {}", result.task_id)
+    match result.attempts.len() {
+        0 | 1 => format!("ID: {}", result.task_id),
+        attempts => format!("ID: {}, failed {} attempts", result.task_id, attempts),
+    }
 }
@@ -13,6 +13,7 @@ fn create_test_task_result(task_id: &str, status: TaskStatus, error: Option<Stri
         status,
         data: Some(json!({"partial_output": "test output"})),
         error,
+        attempts: Vec::new(),
     }
 }
 
@@ -27,6 +28,8 @@ fn create_test_execution_response(
             total_tasks: results.len(),
             completed: results.len() - failed_count,
             failed: failed_count,
+            cancelled: 0,
+            skipped: 0,
             execution_time_ms: 1000,
         },
     }
@@ -1,10 +1,25 @@
+pub mod artifacts;
+pub mod dag;
+pub mod dry_run;
 mod executor;
 pub mod lib;
 pub mod notification_events;
+pub mod pause;
+pub mod progress_events;
+pub mod resources;
+pub mod result_cache;
+pub mod run_history;
+pub mod run_manifest;
+pub mod run_report;
+pub mod sandbox;
+pub mod ssh_workers;
 pub mod subagent_execute_task_tool;
 pub mod task_execution_tracker;
+pub mod task_logs;
 pub mod task_types;
+pub mod task_usage;
 pub mod tasks;
 pub mod tasks_manager;
 pub mod utils;
+pub mod webhooks;
 pub mod workers;
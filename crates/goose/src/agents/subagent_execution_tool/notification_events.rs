@@ -1,4 +1,6 @@
 use crate::agents::subagent_execution_tool::task_types::TaskStatus;
+use crate::agents::subagent_execution_tool::task_usage::TaskUsage;
+use crate::recipe::TaskPriority;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -16,6 +18,29 @@ pub enum TaskExecutionNotificationEvent {
     TasksComplete {
         stats: TaskCompletionStats,
         failed_tasks: Vec<FailedTaskInfo>,
+        /// Where the tasks' output was logged, if they printed any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        log_dir: Option<String>,
+        /// The run to resume for the tasks that didn't complete
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        run_id: Option<String>,
+        /// What the tasks left behind, in the order they were queued
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        artifacts: Vec<ArtifactInfo>,
+        /// The manifest listing every task's artifacts, once there are any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        artifacts_manifest: Option<String>,
+        /// The JUnit and JSON reports written of the run
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        reports: Vec<String>,
+    },
+    /// The tasks a dry run would run, none of them started
+    #[serde(rename = "tasks_planned")]
+    TasksPlanned {
+        stats: TaskExecutionStats,
+        tasks: Vec<TaskInfo>,
+        stages: usize,
+        estimated_concurrency: usize,
     },
 }
 
@@ -26,6 +51,25 @@ pub struct TaskExecutionStats {
     pub running: usize,
     pub completed: usize,
     pub failed: usize,
+    #[serde(default)]
+    pub cancelled: usize,
+    #[serde(default)]
+    pub skipped: usize,
+    /// The run's failure policy, unless it is `continue`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<String>,
+    /// What the tasks spent so far, all together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TaskUsage>,
+    /// How long the run has left, estimated from the tasks that finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<f64>,
+    /// How many tasks finish a minute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tasks_per_minute: Option<f64>,
+    /// No more tasks start until the run is resumed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,7 +77,17 @@ pub struct TaskCompletionStats {
     pub total: usize,
     pub completed: usize,
     pub failed: usize,
+    #[serde(default)]
+    pub cancelled: usize,
+    #[serde(default)]
+    pub skipped: usize,
+    /// Of the cancelled tasks, the ones the failure policy kept from starting
+    #[serde(default)]
+    pub stopped_by_policy: usize,
     pub success_rate: f64,
+    /// What the tasks spent, all together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TaskUsage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +101,47 @@ pub struct TaskInfo {
     pub task_metadata: String,
     pub error: Option<String>,
     pub result_data: Option<Value>,
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    #[serde(default = "first_attempt")]
+    pub max_attempts: u32,
+    /// Where a pending task is in the queue, counting from 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+    /// Ids of the tasks this one waits for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// How long a running task with a timeout has left
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_secs: Option<f64>,
+    /// Cancelled because the run's failure policy kept it from starting
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stopped_by_policy: bool,
+    /// The tokens the task spent so far, once it spent any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TaskUsage>,
+    /// The container the task runs in, when tasks run in a sandbox
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_id: Option<String>,
+    /// The worker host the task runs on, when tasks are spread across hosts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    /// The result came from the result cache rather than a run
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+}
+
+fn first_attempt() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactInfo {
+    pub task_id: String,
+    pub name: String,
+    pub files: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +149,8 @@ pub struct FailedTaskInfo {
     pub id: String,
     pub name: String,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
 }
 
 impl TaskExecutionNotificationEvent {
@@ -65,10 +162,37 @@ impl TaskExecutionNotificationEvent {
         Self::TasksUpdate { stats, tasks }
     }
 
-    pub fn tasks_complete(stats: TaskCompletionStats, failed_tasks: Vec<FailedTaskInfo>) -> Self {
+    pub fn tasks_complete(
+        stats: TaskCompletionStats,
+        failed_tasks: Vec<FailedTaskInfo>,
+        log_dir: Option<String>,
+        run_id: Option<String>,
+        artifacts: Vec<ArtifactInfo>,
+        artifacts_manifest: Option<String>,
+        reports: Vec<String>,
+    ) -> Self {
         Self::TasksComplete {
             stats,
             failed_tasks,
+            log_dir,
+            run_id,
+            artifacts,
+            artifacts_manifest,
+            reports,
+        }
+    }
+
+    pub fn tasks_planned(
+        stats: TaskExecutionStats,
+        tasks: Vec<TaskInfo>,
+        stages: usize,
+        estimated_concurrency: usize,
+    ) -> Self {
+        Self::TasksPlanned {
+            stats,
+            tasks,
+            stages,
+            estimated_concurrency,
         }
     }
 
@@ -102,8 +226,50 @@ impl TaskExecutionStats {
             running,
             completed,
             failed,
+            cancelled: 0,
+            skipped: 0,
+            on_failure: None,
+            usage: None,
+            eta_secs: None,
+            tasks_per_minute: None,
+            paused: false,
         }
     }
+
+    pub fn with_cancelled(mut self, cancelled: usize) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub fn with_skipped(mut self, skipped: usize) -> Self {
+        self.skipped = skipped;
+        self
+    }
+
+    pub fn with_on_failure(mut self, on_failure: Option<String>) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: Option<TaskUsage>) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn with_eta_secs(mut self, eta_secs: Option<f64>) -> Self {
+        self.eta_secs = eta_secs;
+        self
+    }
+
+    pub fn with_tasks_per_minute(mut self, tasks_per_minute: Option<f64>) -> Self {
+        self.tasks_per_minute = tasks_per_minute;
+        self
+    }
+
+    pub fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
 }
 
 impl TaskCompletionStats {
@@ -118,9 +284,33 @@ impl TaskCompletionStats {
             total,
             completed,
             failed,
+            cancelled: 0,
+            skipped: 0,
+            stopped_by_policy: 0,
             success_rate,
+            usage: None,
         }
     }
+
+    pub fn with_cancelled(mut self, cancelled: usize) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub fn with_skipped(mut self, skipped: usize) -> Self {
+        self.skipped = skipped;
+        self
+    }
+
+    pub fn with_stopped_by_policy(mut self, stopped_by_policy: usize) -> Self {
+        self.stopped_by_policy = stopped_by_policy;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: Option<TaskUsage>) -> Self {
+        self.usage = usage;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -154,6 +344,17 @@ mod tests {
             task_metadata: "param=value".to_string(),
             error: None,
             result_data: None,
+            attempt: 1,
+            max_attempts: 1,
+            queue_position: None,
+            depends_on: Vec::new(),
+            remaining_secs: None,
+            stopped_by_policy: false,
+            usage: None,
+            container_id: None,
+            host: None,
+            priority: TaskPriority::default(),
+            cached: false,
         }];
 
         let event = TaskExecutionNotificationEvent::tasks_update(stats, tasks);
@@ -0,0 +1,126 @@
+//! Pausing the scheduling of parallel runs.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::agents::subagent_execution_tool::task_logs::log_dir;
+
+pub const PAUSE_FILE: &str = "PAUSED";
+/// How often a paused run checks whether it was resumed
+const PAUSE_POLL_INTERVAL_MS: u64 = 500;
+/// How often a run paused by the file warns that it is
+const PAUSE_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The file that pauses every run while it exists
+pub fn pause_file() -> Result<PathBuf> {
+    Ok(log_dir()?.join(PAUSE_FILE))
+}
+
+#[derive(Debug, Clone)]
+pub struct PauseControl {
+    paused: Arc<AtomicBool>,
+    file: Option<PathBuf>,
+    /// When a task last warned the file is holding it up, so the tasks waiting don't all warn
+    warned: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Default for PauseControl {
+    fn default() -> Self {
+        Self::with_file(
+            pause_file()
+                .inspect_err(|e| tracing::warn!("Task runs can only be paused by goose: {}", e))
+                .ok(),
+        )
+    }
+}
+
+impl PauseControl {
+    /// A control also paused while `file` exists, if there is one
+    pub fn with_file(file: Option<PathBuf>) -> Self {
+        Self {
+            paused: Arc::new(AtomicBool::new(false)),
+            file,
+            warned: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// Let tasks start again, however they were paused
+    pub fn resume(&self) -> Result<()> {
+        self.paused.store(false, Ordering::Release);
+        if let Some(file) = self.file.as_ref().filter(|file| file.exists()) {
+            std::fs::remove_file(file)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire) || self.paused_by_file().is_some()
+    }
+
+    /// The file pausing the run, if it exists
+    fn paused_by_file(&self) -> Option<&PathBuf> {
+        self.file.as_ref().filter(|file| file.exists())
+    }
+
+    pub async fn wait_until_resumed(&self) {
+        while self.is_paused() {
+            if let Some(file) = self.paused_by_file() {
+                self.warn_paused_by(file);
+            }
+            sleep(Duration::from_millis(PAUSE_POLL_INTERVAL_MS)).await;
+        }
+    }
+
+    /// Say that `file` holds the run up, unless that was said not long ago
+    fn warn_paused_by(&self, file: &Path) {
+        let mut warned = self.warned.lock().unwrap();
+        if warned.is_none_or(|at| at.elapsed() >= PAUSE_WARNING_INTERVAL) {
+            tracing::warn!(
+                "No more tasks start while {} exists; run `goose session tasks --resume` or \
+                 delete it to go on",
+                file.display()
+            );
+            *warned = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(PAUSE_FILE);
+        let control = PauseControl::with_file(Some(file.clone()));
+        assert!(!control.is_paused());
+        control.wait_until_resumed().await;
+
+        control.pause();
+        assert!(control.is_paused());
+        let waiting = control.clone();
+        let waiter = tokio::spawn(async move { waiting.wait_until_resumed().await });
+        sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+        control.resume().unwrap();
+        timeout(Duration::from_secs(2), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+
+        std::fs::write(&file, "").unwrap();
+        assert!(control.is_paused());
+        control.resume().unwrap();
+        assert!(!file.exists() && !control.is_paused());
+    }
+}
@@ -0,0 +1,226 @@
+//! Task progress as newline-delimited JSON, for CI systems to follow a run.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::agents::subagent_execution_tool::notification_events::TaskCompletionStats;
+use crate::agents::subagent_execution_tool::task_types::TaskStatus;
+use crate::config::Config;
+
+pub const TASK_EVENTS_KEY: &str = "GOOSE_TASK_EVENTS";
+/// Turns the stream off, as it is for sub-recipes, whose output goes to their parent
+pub const TASK_EVENTS_OFF: &str = "off";
+
+static GLOBAL: OnceCell<Option<Arc<ProgressEvents>>> = OnceCell::const_new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    TaskStarted {
+        task_id: String,
+        name: String,
+        task_type: String,
+    },
+    TaskOutput {
+        task_id: String,
+        line: String,
+    },
+    TaskCompleted {
+        task_id: String,
+        name: String,
+        /// `completed`, `failed`, `cancelled` or `skipped`
+        status: String,
+        duration_secs: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Summary {
+        /// The run to resume for the tasks that didn't complete
+        #[serde(skip_serializing_if = "Option::is_none")]
+        run_id: Option<String>,
+        #[serde(flatten)]
+        stats: TaskCompletionStats,
+    },
+}
+
+impl ProgressEvent {
+    pub fn task_completed(
+        task_id: String,
+        name: String,
+        status: &TaskStatus,
+        duration_secs: Option<f64>,
+        error: Option<String>,
+    ) -> Self {
+        Self::TaskCompleted {
+            task_id,
+            name,
+            status: status.to_string().to_lowercase(),
+            duration_secs,
+            error,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Stderr,
+    /// A file, appended to, or a named pipe
+    Path(PathBuf),
+}
+
+impl Target {
+    /// What a `GOOSE_TASK_EVENTS` value asks for, or `None` when it turns the stream off
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "" | TASK_EVENTS_OFF => None,
+            "stderr" | "stdout" | "-" => Some(Self::Stderr),
+            path => Some(Self::Path(PathBuf::from(path))),
+        }
+    }
+}
+
+/// Where progress events go
+pub struct ProgressEvents {
+    writer: Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+}
+
+impl ProgressEvents {
+    pub async fn open(target: &Target) -> Result<Self> {
+        let writer: Box<dyn AsyncWrite + Send + Unpin> = match target {
+            Target::Stderr => Box::new(tokio::io::stderr()),
+            Target::Path(path) => Box::new(
+                tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?,
+            ),
+        };
+        Ok(Self {
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// The stream `GOOSE_TASK_EVENTS` asks for, opened the first time it is needed and kept
+    /// open for the rest of the process, so a pipe's reader sees one stream across runs
+    pub async fn global() -> Option<Arc<Self>> {
+        GLOBAL
+            .get_or_init(|| async {
+                let value = Config::global().get_param::<String>(TASK_EVENTS_KEY).ok()?;
+                let target = Target::parse(&value)?;
+                if value.trim() == "stdout" {
+                    tracing::warn!("Task events go to stderr, as goose's own output is on stdout");
+                }
+                match Self::open(&target).await {
+                    Ok(events) => Some(Arc::new(events)),
+                    Err(e) => {
+                        tracing::warn!("Failed to open {} for task events: {}", value, e);
+                        None
+                    }
+                }
+            })
+            .await
+            .clone()
+    }
+
+    pub async fn emit(&self, event: ProgressEvent) {
+        let line = Line {
+            timestamp: Utc::now(),
+            event: &event,
+        };
+        let mut json = match serde_json::to_string(&line) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::warn!("Failed to serialize a task event: {}", e);
+                return;
+            }
+        };
+        json.push('\n');
+        let mut writer = self.writer.lock().await;
+        let written = match writer.write_all(json.as_bytes()).await {
+            Ok(()) => writer.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            tracing::warn!("Failed to write a task event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(Target::parse("stderr"), Some(Target::Stderr));
+        assert_eq!(Target::parse("stdout"), Some(Target::Stderr));
+        assert_eq!(Target::parse("-"), Some(Target::Stderr));
+        assert_eq!(Target::parse("off"), None);
+        assert_eq!(Target::parse(""), None);
+        assert_eq!(
+            Target::parse("/tmp/goose-events"),
+            Some(Target::Path(PathBuf::from("/tmp/goose-events")))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_emit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.ndjson");
+        let events = ProgressEvents::open(&Target::Path(path.clone()))
+            .await
+            .unwrap();
+
+        events
+            .emit(ProgressEvent::TaskOutput {
+                task_id: "task-1".to_string(),
+                line: "fetching".to_string(),
+            })
+            .await;
+        events
+            .emit(ProgressEvent::task_completed(
+                "task-1".to_string(),
+                "fetch".to_string(),
+                &TaskStatus::Completed,
+                Some(1.5),
+                None,
+            ))
+            .await;
+        events
+            .emit(ProgressEvent::Summary {
+                run_id: None,
+                stats: TaskCompletionStats::new(1, 1, 0),
+            })
+            .await;
+
+        let lines: Vec<Value> = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["event"], "task_output");
+        assert_eq!(lines[0]["line"], "fetching");
+        assert!(lines[0]["timestamp"].is_string());
+        assert_eq!(lines[1]["event"], "task_completed");
+        assert_eq!(lines[1]["status"], "completed");
+        assert!(lines[1].get("error").is_none());
+        assert_eq!(lines[2]["event"], "summary");
+        assert_eq!(lines[2]["success_rate"], 100.0);
+    }
+}
@@ -0,0 +1,506 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{Notify, OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+use crate::agents::subagent_execution_tool::pause::PauseControl;
+use crate::config::Config;
+use crate::recipe::{TaskPriority, TaskResources};
+
+/// How many cpu-heavy tasks may run at once, the number of cores by default
+pub const MAX_CPU_HEAVY_TASKS_KEY: &str = "GOOSE_SUBAGENT_MAX_CPU_HEAVY_TASKS";
+/// How many network-heavy tasks may run at once
+pub const MAX_NETWORK_HEAVY_TASKS_KEY: &str = "GOOSE_SUBAGENT_MAX_NETWORK_HEAVY_TASKS";
+const DEFAULT_MAX_NETWORK_HEAVY_TASKS: usize = 4;
+/// How many tasks of a parallel run may run at once, unless the run asks for fewer or more
+pub const MAX_PARALLEL_TASKS_KEY: &str = "GOOSE_SUBAGENT_MAX_PARALLEL_TASKS";
+const DEFAULT_MAX_PARALLEL_TASKS: usize = 10;
+/// Whether tasks wait to start at all while any task of a higher priority is still waiting
+pub const HOLD_LOWER_PRIORITY_TASKS_KEY: &str = "GOOSE_SUBAGENT_HOLD_LOWER_PRIORITY_TASKS";
+
+/// How many tasks of a parallel run may run at once: `max_parallel` of the run, or else the
+/// configured limit
+pub fn max_parallel_tasks(max_parallel: Option<usize>) -> usize {
+    max_parallel.unwrap_or_else(|| {
+        Config::global()
+            .get_param::<usize>(MAX_PARALLEL_TASKS_KEY)
+            .unwrap_or(DEFAULT_MAX_PARALLEL_TASKS)
+    })
+}
+
+/// Hands out what tasks declare they need, so a parallel run never has two tasks holding the
+/// same lock or more heavy tasks going than the machine handles well.
+///
+/// The slot every task needs to run at all is taken first, so tasks queued behind the parallel
+/// limit hold nothing others could use. Locks are then taken in name order and before the cpu
+/// and network slots, so tasks waiting on each other can't deadlock. A free slot goes to the
+/// task of the highest priority waiting for one; holding lower priorities back makes tasks wait
+/// before taking anything while a task of a higher priority is still waiting. While the run is
+/// paused, tasks that got everything wait there until it's resumed.
+pub struct ResourceScheduler {
+    cpu_heavy: Arc<Semaphore>,
+    network_heavy: Arc<Semaphore>,
+    /// Tasks running at once, whatever they declared; unlimited when `None`
+    parallel: Option<Arc<Semaphore>>,
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    pause: Option<PauseControl>,
+    waiting: Mutex<Waiting>,
+    /// Told whenever a slot is freed or a task stops waiting
+    turn: Arc<Notify>,
+    hold_lower_priority: bool,
+}
+
+/// The tasks in [`ResourceScheduler::acquire`], counted by priority
+#[derive(Default)]
+struct Waiting {
+    /// Every task that hasn't got everything yet
+    queued: BTreeMap<TaskPriority, usize>,
+    /// Of those, the tasks that only wait for a slot
+    for_slot: BTreeMap<TaskPriority, usize>,
+}
+
+#[derive(Clone, Copy)]
+enum Stage {
+    Queued,
+    ForSlot,
+}
+
+impl Waiting {
+    fn counts(&mut self, stage: Stage) -> &mut BTreeMap<TaskPriority, usize> {
+        match stage {
+            Stage::Queued => &mut self.queued,
+            Stage::ForSlot => &mut self.for_slot,
+        }
+    }
+
+    fn any_above(&mut self, stage: Stage, priority: TaskPriority) -> bool {
+        self.counts(stage)
+            .range((Bound::Excluded(priority), Bound::Unbounded))
+            .next()
+            .is_some()
+    }
+}
+
+/// A task counted as waiting until it's dropped, taken off the count even when the task is
+/// cancelled while it waits
+struct WaitTicket<'a> {
+    scheduler: &'a ResourceScheduler,
+    stage: Stage,
+    priority: TaskPriority,
+}
+
+impl Drop for WaitTicket<'_> {
+    fn drop(&mut self) {
+        let mut waiting = self.scheduler.waiting.lock().unwrap();
+        let counts = waiting.counts(self.stage);
+        if let Some(count) = counts.get_mut(&self.priority) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.priority);
+            }
+        }
+        drop(waiting);
+        self.scheduler.turn.notify_waiters();
+    }
+}
+
+/// A slot to run in, handed to the next task waiting once dropped
+struct SlotPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    turn: Arc<Notify>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        self.turn.notify_waiters();
+    }
+}
+
+/// Everything a running task holds, released when it's dropped
+pub struct ResourceGuard {
+    _locks: Vec<OwnedMutexGuard<()>>,
+    _permits: Vec<OwnedSemaphorePermit>,
+    _slot: Option<SlotPermit>,
+}
+
+impl ResourceScheduler {
+    pub fn new(max_cpu_heavy: usize, max_network_heavy: usize) -> Self {
+        Self {
+            cpu_heavy: Arc::new(Semaphore::new(max_cpu_heavy.max(1))),
+            network_heavy: Arc::new(Semaphore::new(max_network_heavy.max(1))),
+            parallel: None,
+            locks: Mutex::new(HashMap::new()),
+            pause: None,
+            waiting: Mutex::new(Waiting::default()),
+            turn: Arc::new(Notify::new()),
+            hold_lower_priority: false,
+        }
+    }
+
+    /// Let no more than `max_parallel` tasks run at once
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.parallel = Some(Arc::new(Semaphore::new(max_parallel.max(1))));
+        self
+    }
+
+    /// Start no tasks while `pause` is paused
+    pub fn with_pause(mut self, pause: PauseControl) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Have tasks wait to take anything while a task of a higher priority still waits
+    pub fn with_hold_lower_priority(mut self, hold: bool) -> Self {
+        self.hold_lower_priority = hold;
+        self
+    }
+
+    /// The limits in the config, with `max_parallel` of the run over the configured one
+    pub fn from_config(max_parallel: Option<usize>) -> Self {
+        let config = Config::global();
+        let cores = std::thread::available_parallelism()
+            .map(|cores| cores.get())
+            .unwrap_or(1);
+        Self::new(
+            config
+                .get_param::<usize>(MAX_CPU_HEAVY_TASKS_KEY)
+                .unwrap_or(cores),
+            config
+                .get_param::<usize>(MAX_NETWORK_HEAVY_TASKS_KEY)
+                .unwrap_or(DEFAULT_MAX_NETWORK_HEAVY_TASKS),
+        )
+        .with_max_parallel(max_parallel_tasks(max_parallel))
+        .with_hold_lower_priority(
+            config
+                .get_param::<bool>(HOLD_LOWER_PRIORITY_TASKS_KEY)
+                .unwrap_or(false),
+        )
+    }
+
+    fn lock(&self, name: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.locks
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn enqueue(&self, stage: Stage, priority: TaskPriority) -> WaitTicket<'_> {
+        *self
+            .waiting
+            .lock()
+            .unwrap()
+            .counts(stage)
+            .entry(priority)
+            .or_default() += 1;
+        WaitTicket {
+            scheduler: self,
+            stage,
+            priority,
+        }
+    }
+
+    /// Wait until no task of a priority above `priority` waits at `stage`, and until `ready`
+    /// gives something
+    async fn wait_turn<T>(
+        &self,
+        stage: Stage,
+        priority: TaskPriority,
+        mut ready: impl FnMut() -> Option<T>,
+    ) -> T {
+        loop {
+            let turn = self.turn.notified();
+            tokio::pin!(turn);
+            // Registered before looking, so a slot freed in between isn't missed
+            turn.as_mut().enable();
+            if !self.waiting.lock().unwrap().any_above(stage, priority) {
+                if let Some(value) = ready() {
+                    return value;
+                }
+            }
+            turn.await;
+        }
+    }
+
+    /// Wait until everything in `resources` is free and take it, and until the run isn't paused.
+    /// Tasks of a higher `priority` get a slot first.
+    pub async fn acquire(
+        &self,
+        resources: &TaskResources,
+        priority: TaskPriority,
+    ) -> ResourceGuard {
+        let _queued = self.enqueue(Stage::Queued, priority);
+        if self.hold_lower_priority {
+            self.wait_turn(Stage::Queued, priority, || Some(())).await;
+        }
+
+        let mut slot = None;
+        if let Some(parallel) = &self.parallel {
+            let _for_slot = self.enqueue(Stage::ForSlot, priority);
+            let permit = self
+                .wait_turn(Stage::ForSlot, priority, || {
+                    parallel.clone().try_acquire_owned().ok()
+                })
+                .await;
+            slot = Some(SlotPermit {
+                permit: Some(permit),
+                turn: self.turn.clone(),
+            });
+        }
+
+        let names: BTreeSet<&str> = resources.locks.iter().map(String::as_str).collect();
+        let mut locks = Vec::with_capacity(names.len());
+        for name in names {
+            locks.push(self.lock(name).lock_owned().await);
+        }
+
+        let mut permits = Vec::new();
+        for (needed, semaphore) in [
+            (resources.cpu_heavy, &self.cpu_heavy),
+            (resources.network_heavy, &self.network_heavy),
+        ] {
+            if needed {
+                // The semaphores are never closed
+                if let Ok(permit) = semaphore.clone().acquire_owned().await {
+                    permits.push(permit);
+                }
+            }
+        }
+        if let Some(pause) = &self.pause {
+            pause.wait_until_resumed().await;
+        }
+
+        ResourceGuard {
+            _locks: locks,
+            _permits: permits,
+            _slot: slot,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    fn resources(cpu_heavy: bool, locks: &[&str]) -> TaskResources {
+        TaskResources {
+            cpu_heavy,
+            network_heavy: false,
+            locks: locks.iter().map(|lock| lock.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paused_runs_start_nothing_until_resumed() {
+        let pause = PauseControl::with_file(None);
+        let scheduler = ResourceScheduler::new(4, 4).with_pause(pause.clone());
+        pause.pause();
+        assert!(timeout(
+            Duration::from_millis(50),
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Normal)
+        )
+        .await
+        .is_err());
+
+        pause.resume().unwrap();
+        assert!(timeout(
+            Duration::from_secs(2),
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tasks_sharing_a_lock_wait_for_each_other() {
+        let scheduler = ResourceScheduler::new(4, 4);
+        let held = scheduler
+            .acquire(&resources(false, &["db"]), TaskPriority::Normal)
+            .await;
+
+        let short = Duration::from_millis(50);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&resources(false, &["db", "cache"]), TaskPriority::Normal)
+        )
+        .await
+        .is_err());
+        // Other locks and undeclared tasks aren't held up
+        assert!(timeout(
+            short,
+            scheduler.acquire(&resources(false, &["cache"]), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+        assert!(timeout(
+            short,
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+
+        drop(held);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&resources(false, &["db"]), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cpu_heavy_tasks_are_limited() {
+        let scheduler = ResourceScheduler::new(2, 4);
+        let _first = scheduler
+            .acquire(&resources(true, &[]), TaskPriority::Normal)
+            .await;
+        let second = scheduler
+            .acquire(&resources(true, &[]), TaskPriority::Normal)
+            .await;
+
+        let short = Duration::from_millis(50);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&resources(true, &[]), TaskPriority::Normal)
+        )
+        .await
+        .is_err());
+        assert!(timeout(
+            short,
+            scheduler.acquire(&resources(false, &[]), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+
+        drop(second);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&resources(true, &[]), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_tasks_are_limited() {
+        let scheduler = ResourceScheduler::new(4, 4).with_max_parallel(2);
+        let first = scheduler
+            .acquire(&TaskResources::default(), TaskPriority::Normal)
+            .await;
+        let _second = scheduler
+            .acquire(&resources(true, &["db"]), TaskPriority::Normal)
+            .await;
+
+        let short = Duration::from_millis(50);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Normal)
+        )
+        .await
+        .is_err());
+
+        drop(first);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Normal)
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tasks_waiting_for_a_slot_hold_nothing() {
+        let scheduler = Arc::new(ResourceScheduler::new(1, 4).with_max_parallel(1));
+        let running = scheduler
+            .acquire(&TaskResources::default(), TaskPriority::Normal)
+            .await;
+        let waiting = spawn_acquire(&scheduler, resources(true, &["db"]), TaskPriority::Normal);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(scheduler.lock("db").try_lock().is_ok());
+        assert_eq!(scheduler.cpu_heavy.available_permits(), 1);
+
+        drop(running);
+        let _waiting = timeout(Duration::from_secs(2), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(scheduler.lock("db").try_lock().is_err());
+        assert_eq!(scheduler.cpu_heavy.available_permits(), 0);
+    }
+
+    fn spawn_acquire(
+        scheduler: &Arc<ResourceScheduler>,
+        resources: TaskResources,
+        priority: TaskPriority,
+    ) -> tokio::task::JoinHandle<ResourceGuard> {
+        let scheduler = scheduler.clone();
+        tokio::spawn(async move { scheduler.acquire(&resources, priority).await })
+    }
+
+    #[tokio::test]
+    async fn test_free_slots_go_to_higher_priorities_first() {
+        let scheduler = Arc::new(ResourceScheduler::new(4, 4).with_max_parallel(1));
+        let running = scheduler
+            .acquire(&TaskResources::default(), TaskPriority::Normal)
+            .await;
+
+        let short = Duration::from_millis(50);
+        let low = spawn_acquire(&scheduler, TaskResources::default(), TaskPriority::Low);
+        tokio::time::sleep(short).await;
+        let high = spawn_acquire(&scheduler, TaskResources::default(), TaskPriority::High);
+        tokio::time::sleep(short).await;
+
+        drop(running);
+        let high = timeout(Duration::from_secs(2), high)
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::sleep(short).await;
+        assert!(!low.is_finished());
+
+        drop(high);
+        assert!(timeout(Duration::from_secs(2), low).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_lower_priorities_can_be_held_back() {
+        let scheduler = Arc::new(ResourceScheduler::new(4, 4).with_hold_lower_priority(true));
+        let running = scheduler
+            .acquire(&resources(false, &["db"]), TaskPriority::Normal)
+            .await;
+        let high = spawn_acquire(&scheduler, resources(false, &["db"]), TaskPriority::High);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Nothing it needs is taken, but a task of a higher priority is still waiting
+        let short = Duration::from_millis(50);
+        assert!(timeout(
+            short,
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Low)
+        )
+        .await
+        .is_err());
+        assert!(timeout(
+            short,
+            scheduler.acquire(&TaskResources::default(), TaskPriority::High)
+        )
+        .await
+        .is_ok());
+
+        drop(running);
+        let _high = timeout(Duration::from_secs(2), high)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(timeout(
+            short,
+            scheduler.acquire(&TaskResources::default(), TaskPriority::Low)
+        )
+        .await
+        .is_ok());
+    }
+}
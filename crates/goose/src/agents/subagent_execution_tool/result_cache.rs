@@ -0,0 +1,294 @@
+//! Results of completed tasks, kept in `<session_dir>/task_cache/` for tasks asked for again.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::agents::subagent_execution_tool::task_types::{Task, TaskType};
+use crate::agents::subagent_execution_tool::utils::{prune_files, PruneReport};
+use crate::config::Config;
+use crate::providers::base::Provider;
+use crate::session::session_manager::ensure_session_dir;
+
+pub const TASK_CACHE_KEY: &str = "GOOSE_TASK_CACHE";
+pub const TASK_CACHE_TTL_KEY: &str = "GOOSE_TASK_CACHE_TTL";
+pub const CACHE_DIR: &str = "task_cache";
+/// How long a result is kept unless `GOOSE_TASK_CACHE_TTL` says otherwise
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Part of every key, so results kept before keys changed are never taken for new ones
+const KEY_VERSION: u32 = 2;
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Remove the results in `dir` kept for longer than `ttl`; with a `ttl` of zero, all of them.
+/// With `dry_run` nothing is deleted, only reported.
+pub fn prune(dir: &Path, ttl: Duration, dry_run: bool) -> Result<PruneReport> {
+    prune_files(dir, &["json", "partial"], ttl, dry_run)
+}
+
+/// The provider and model answering `task`, as far as they're known: the run's own for an
+/// inline recipe, and the configured ones for a sub-recipe, which runs as a `goose run` of its
+/// own
+fn model_of(task: &Task, provider: Option<&dyn Provider>) -> Value {
+    match (&task.task_type, provider) {
+        (TaskType::InlineRecipe, Some(provider)) => json!({
+            "provider": provider.provider_name(),
+            "model": provider.get_model_config().model_name,
+        }),
+        _ => {
+            let config = Config::global();
+            json!({
+                "provider": config.get_param::<String>("GOOSE_PROVIDER").ok(),
+                "model": config.get_param::<String>("GOOSE_MODEL").ok(),
+            })
+        }
+    }
+}
+
+/// A result kept for a key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResult {
+    pub task_name: String,
+    pub cached_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+pub struct ResultCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResultCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            ttl: DEFAULT_CACHE_TTL,
+        }
+    }
+
+    /// Take no results kept for longer than `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The directory of the cache in the session directory
+    pub fn dir() -> Result<PathBuf> {
+        Ok(ensure_session_dir()?.join(CACHE_DIR))
+    }
+
+    /// How long results are kept, from `GOOSE_TASK_CACHE_TTL` in seconds
+    pub fn ttl_from_config() -> Duration {
+        Config::global()
+            .get_param::<u64>(TASK_CACHE_TTL_KEY)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_CACHE_TTL)
+    }
+
+    /// The cache in the session directory, unless `GOOSE_TASK_CACHE` turns it off
+    pub fn from_config() -> Option<Arc<Self>> {
+        if !Config::global()
+            .get_param::<bool>(TASK_CACHE_KEY)
+            .unwrap_or(true)
+        {
+            return None;
+        }
+        Self::dir()
+            .inspect_err(|e| tracing::warn!("Task results won't be cached: {}", e))
+            .ok()
+            .map(|dir| Arc::new(Self::new(dir).with_ttl(Self::ttl_from_config())))
+    }
+
+    /// The key of `task`'s result when `provider` runs it, or `None` for a task that isn't
+    /// cached: one that didn't ask to be, one that leaves artifacts, or a sub-recipe whose file
+    /// can't be read
+    pub async fn key(task: &Task, provider: Option<&dyn Provider>) -> Option<String> {
+        if !task.get_cache() || !task.get_artifacts().is_empty() {
+            return None;
+        }
+        let workdir = std::env::current_dir().ok()?;
+        let (recipe, parameters) = match task.task_type {
+            TaskType::SubRecipe => {
+                let recipe = tokio::fs::read(task.get_sub_recipe_path()?).await.ok()?;
+                let parameters = task.get_command_parameters().cloned().unwrap_or_default();
+                (json!(sha256_hex(&recipe)), parameters)
+            }
+            TaskType::InlineRecipe => (
+                json!({
+                    "recipe": task.payload.get("recipe"),
+                    "return_last_only": task.payload.get("return_last_only"),
+                }),
+                Default::default(),
+            ),
+        };
+        let parameters: BTreeMap<String, Value> = parameters.into_iter().collect();
+
+        let mut files = BTreeMap::new();
+        for (name, value) in &parameters {
+            let Some(path) = value.as_str().map(|path| workdir.join(path)) else {
+                continue;
+            };
+            if path.is_file() {
+                if let Ok(contents) = tokio::fs::read(&path).await {
+                    files.insert(name.as_str(), sha256_hex(&contents));
+                }
+            }
+        }
+
+        let key = json!({
+            "version": KEY_VERSION,
+            "task_type": task.task_type,
+            "workdir": workdir.to_string_lossy(),
+            "recipe": recipe,
+            "parameters": parameters,
+            "files": files,
+            "model": model_of(task, provider),
+        });
+        Some(sha256_hex(key.to_string().as_bytes()))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// The result kept for `key`, if there is one that hasn't expired
+    pub async fn get(&self, key: &str) -> Option<CachedResult> {
+        let contents = tokio::fs::read(self.path(key)).await.ok()?;
+        let cached: CachedResult = serde_json::from_slice(&contents)
+            .inspect_err(|e| tracing::warn!("Ignoring cached task result {}: {}", key, e))
+            .ok()?;
+        let age = (Utc::now() - cached.cached_at).to_std().unwrap_or_default();
+        (age < self.ttl).then_some(cached)
+    }
+
+    /// Keep `data`, the result of the task `task_name`, for `key`
+    pub async fn put(&self, key: &str, task_name: &str, data: &Value) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let entry = CachedResult {
+            task_name: task_name.to_string(),
+            cached_at: Utc::now(),
+            data: data.clone(),
+        };
+        // Written aside and moved in, so tasks looking the key up never read half of it
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let partial = self.dir.join(format!("{}.{}.partial", key, &suffix[..8]));
+        tokio::fs::write(&partial, serde_json::to_vec(&entry)?)
+            .await
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        tokio::fs::rename(&partial, self.path(key))
+            .await
+            .with_context(|| format!("Failed to cache the result of {}", task_name))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sub_recipe_task(recipe_path: &str, parameters: Value) -> Task {
+        Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            task_type: TaskType::SubRecipe,
+            payload: json!({
+                "sub_recipe": {
+                    "name": "summarize",
+                    "recipe_path": recipe_path,
+                    "command_parameters": parameters,
+                    "cache": true,
+                }
+            }),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        }
+    }
+
+    async fn key(recipe_path: &str, parameters: Value) -> Option<String> {
+        ResultCache::key(&sub_recipe_task(recipe_path, parameters), None).await
+    }
+
+    #[tokio::test]
+    async fn test_keys_follow_the_recipe_parameters_and_input_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let recipe = dir.path().join("summarize.yaml");
+        let input = dir.path().join("notes.md");
+        std::fs::write(&recipe, "title: summarize").unwrap();
+        std::fs::write(&input, "first draft").unwrap();
+        let recipe = recipe.to_str().unwrap();
+        let input = input.to_str().unwrap();
+
+        let first = key(recipe, json!({"file": input, "style": "short"}))
+            .await
+            .unwrap();
+        // Other ids and parameter orders don't matter
+        assert_eq!(
+            key(recipe, json!({"style": "short", "file": input}))
+                .await
+                .unwrap(),
+            first
+        );
+        assert_ne!(
+            key(recipe, json!({"file": input, "style": "long"}))
+                .await
+                .unwrap(),
+            first
+        );
+
+        std::fs::write(input, "second draft").unwrap();
+        let edited = key(recipe, json!({"file": input, "style": "short"}))
+            .await
+            .unwrap();
+        assert_ne!(edited, first);
+
+        std::fs::write(recipe, "title: summarize better").unwrap();
+        assert_ne!(
+            key(recipe, json!({"file": input, "style": "short"}))
+                .await
+                .unwrap(),
+            edited
+        );
+
+        let missing = dir.path().join("missing.yaml");
+        assert_eq!(key(missing.to_str().unwrap(), json!({})).await, None);
+
+        // Tasks that didn't ask to be cached have no key
+        let mut uncached = sub_recipe_task(recipe, json!({"file": input, "style": "short"}));
+        uncached.payload["sub_recipe"]["cache"] = json!(false);
+        assert_eq!(ResultCache::key(&uncached, None).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResultCache::new(dir.path().join(CACHE_DIR));
+        assert_eq!(cache.get("abc").await, None);
+
+        cache
+            .put("abc", "summarize", &json!({"result": "done"}))
+            .await
+            .unwrap();
+        let cached = cache.get("abc").await.unwrap();
+        assert_eq!(cached.task_name, "summarize");
+        assert_eq!(cached.data, json!({"result": "done"}));
+
+        // Expired results aren't taken, and pruning with no time to live removes them all
+        let expired = ResultCache::new(dir.path().join(CACHE_DIR)).with_ttl(Duration::ZERO);
+        assert_eq!(expired.get("abc").await, None);
+        let report = prune(&dir.path().join(CACHE_DIR), Duration::ZERO, false).unwrap();
+        assert_eq!(report.removed, 1);
+        assert_eq!(cache.get("abc").await, None);
+    }
+}
@@ -0,0 +1,493 @@
+//! Every run of tasks, kept in `<session_dir>/task_runs.db` once it finishes.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Pool, Sqlite};
+use tokio::sync::OnceCell;
+
+use crate::agents::subagent_execution_tool::task_types::TaskStatus;
+use crate::agents::subagent_execution_tool::task_usage::TaskUsage;
+use crate::session::session_manager::ensure_session_dir;
+
+pub const HISTORY_FILE: &str = "task_runs.db";
+const CURRENT_SCHEMA_VERSION: i32 = 1;
+
+static GLOBAL: OnceCell<Option<Arc<RunHistory>>> = OnceCell::const_new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub task_id: String,
+    pub name: String,
+    pub task_type: String,
+    /// What a sub-recipe task was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+    pub status: TaskStatus,
+    pub duration_secs: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub usage: TaskUsage,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Across every time the run ran, when it was resumed
+    pub duration_secs: f64,
+    /// In the order the tasks were queued
+    pub tasks: Vec<TaskRecord>,
+}
+
+/// How a run went, for listing runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunListing {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: f64,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub usage: TaskUsage,
+}
+
+fn status_name(status: &TaskStatus) -> String {
+    status.to_string().to_lowercase()
+}
+
+fn parse_status(name: &str) -> TaskStatus {
+    match name {
+        "pending" => TaskStatus::Pending,
+        "running" => TaskStatus::Running,
+        "completed" => TaskStatus::Completed,
+        "failed" => TaskStatus::Failed,
+        "skipped" => TaskStatus::Skipped,
+        _ => TaskStatus::Cancelled,
+    }
+}
+
+pub struct RunHistory {
+    pool: Pool<Sqlite>,
+}
+
+impl RunHistory {
+    /// The history in `path`, made if it isn't there yet
+    pub async fn open(path: &Path) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .busy_timeout(std::time::Duration::from_secs(5))
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
+        let pool = sqlx::SqlitePool::connect_with(options)
+            .await
+            .with_context(|| format!("Failed to open the run history at {}", path.display()))?;
+
+        let history = Self { pool };
+        history.run_migrations().await?;
+        Ok(history)
+    }
+
+    async fn run_migrations(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        let current_version =
+            sqlx::query_scalar::<_, Option<i32>>("SELECT MAX(version) FROM schema_version")
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(0);
+        for version in (current_version + 1)..=CURRENT_SCHEMA_VERSION {
+            self.apply_migration(version).await?;
+            sqlx::query("INSERT INTO schema_version (version) VALUES (?)")
+                .bind(version)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn apply_migration(&self, version: i32) -> Result<()> {
+        match version {
+            1 => {
+                // Histories made before there were versions already have these tables
+                sqlx::query(
+                    r#"
+                    CREATE TABLE IF NOT EXISTS runs (
+                        id TEXT PRIMARY KEY,
+                        started_at TIMESTAMP NOT NULL,
+                        finished_at TIMESTAMP NOT NULL,
+                        duration_secs REAL NOT NULL
+                    )
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+                sqlx::query(
+                    r#"
+                    CREATE TABLE IF NOT EXISTS run_tasks (
+                        run_id TEXT NOT NULL REFERENCES runs(id),
+                        task_id TEXT NOT NULL,
+                        position INTEGER NOT NULL,
+                        name TEXT NOT NULL,
+                        task_type TEXT NOT NULL,
+                        parameters TEXT,
+                        status TEXT NOT NULL,
+                        duration_secs REAL,
+                        error TEXT,
+                        input_tokens INTEGER NOT NULL DEFAULT 0,
+                        output_tokens INTEGER NOT NULL DEFAULT 0,
+                        cost_usd REAL,
+                        PRIMARY KEY (run_id, task_id)
+                    )
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+                sqlx::query("CREATE INDEX IF NOT EXISTS idx_runs_started ON runs(started_at DESC)")
+                    .execute(&self.pool)
+                    .await?;
+            }
+            _ => {
+                anyhow::bail!("Unknown migration version: {}", version);
+            }
+        }
+        Ok(())
+    }
+
+    /// The history in the sessions directory, opened the first time it is needed
+    pub async fn global() -> Option<Arc<Self>> {
+        GLOBAL
+            .get_or_init(|| async {
+                let opened = match ensure_session_dir() {
+                    Ok(dir) => Self::open(&dir.join(HISTORY_FILE)).await,
+                    Err(e) => Err(e),
+                };
+                opened
+                    .inspect_err(|e| tracing::warn!("Task runs won't be recorded: {:#}", e))
+                    .ok()
+                    .map(Arc::new)
+            })
+            .await
+            .clone()
+    }
+
+    /// Add `run` to the history, or to what it has of the run when it was resumed
+    pub async fn record(&self, run: &RunRecord) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO runs (id, started_at, finished_at, duration_secs) VALUES (?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                finished_at = excluded.finished_at,
+                duration_secs = runs.duration_secs + excluded.duration_secs
+        "#,
+        )
+        .bind(&run.run_id)
+        .bind(run.started_at)
+        .bind(run.finished_at)
+        .bind(run.duration_secs)
+        .execute(&mut *transaction)
+        .await?;
+
+        let first_position =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM run_tasks WHERE run_id = ?")
+                .bind(&run.run_id)
+                .fetch_one(&mut *transaction)
+                .await?;
+        for (position, task) in run.tasks.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO run_tasks (
+                    run_id, task_id, position, name, task_type, parameters, status,
+                    duration_secs, error, input_tokens, output_tokens, cost_usd
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(run_id, task_id) DO UPDATE SET
+                    status = excluded.status,
+                    duration_secs = excluded.duration_secs,
+                    error = excluded.error,
+                    input_tokens = run_tasks.input_tokens + excluded.input_tokens,
+                    output_tokens = run_tasks.output_tokens + excluded.output_tokens,
+                    cost_usd = COALESCE(run_tasks.cost_usd + excluded.cost_usd,
+                        run_tasks.cost_usd, excluded.cost_usd)
+            "#,
+            )
+            .bind(&run.run_id)
+            .bind(&task.task_id)
+            .bind(first_position + position as i64)
+            .bind(&task.name)
+            .bind(&task.task_type)
+            .bind(task.parameters.as_ref().map(Value::to_string))
+            .bind(status_name(&task.status))
+            .bind(task.duration_secs)
+            .bind(&task.error)
+            .bind(task.usage.input_tokens as i64)
+            .bind(task.usage.output_tokens as i64)
+            .bind(task.usage.cost_usd)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// The `limit` runs that started last, the latest first
+    pub async fn list(&self, limit: usize) -> Result<Vec<RunListing>> {
+        type Row = (
+            String,
+            DateTime<Utc>,
+            DateTime<Utc>,
+            f64,
+            i64,
+            i64,
+            i64,
+            i64,
+            i64,
+            Option<f64>,
+            i64,
+        );
+        let rows = sqlx::query_as::<_, Row>(
+            r#"
+            SELECT r.id, r.started_at, r.finished_at, r.duration_secs,
+                COUNT(t.task_id),
+                COALESCE(SUM(t.status = 'completed'), 0),
+                COALESCE(SUM(t.status = 'failed'), 0),
+                COALESCE(SUM(t.input_tokens), 0),
+                COALESCE(SUM(t.output_tokens), 0),
+                SUM(t.cost_usd),
+                COALESCE(SUM(t.cost_usd IS NULL AND t.input_tokens + t.output_tokens > 0), 0)
+            FROM runs r LEFT JOIN run_tasks t ON t.run_id = r.id
+            GROUP BY r.id
+            ORDER BY r.started_at DESC
+            LIMIT ?
+        "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| RunListing {
+                run_id: row.0,
+                started_at: row.1,
+                finished_at: row.2,
+                duration_secs: row.3,
+                total: row.4 as usize,
+                completed: row.5 as usize,
+                failed: row.6 as usize,
+                usage: TaskUsage {
+                    input_tokens: row.7 as u64,
+                    output_tokens: row.8 as u64,
+                    cost_usd: row.9,
+                    // Some tasks spent tokens that have no price
+                    cost_partial: row.9.is_some() && row.10 > 0,
+                },
+            })
+            .collect())
+    }
+
+    /// The run `run_id`, if it's in the history
+    pub async fn get(&self, run_id: &str) -> Result<Option<RunRecord>> {
+        let Some((started_at, finished_at, duration_secs)) =
+            sqlx::query_as::<_, (DateTime<Utc>, DateTime<Utc>, f64)>(
+                "SELECT started_at, finished_at, duration_secs FROM runs WHERE id = ?",
+            )
+            .bind(run_id)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        type Row = (
+            String,
+            String,
+            String,
+            Option<String>,
+            String,
+            Option<f64>,
+            Option<String>,
+            i64,
+            i64,
+            Option<f64>,
+        );
+        let rows = sqlx::query_as::<_, Row>(
+            r#"
+            SELECT task_id, name, task_type, parameters, status, duration_secs, error,
+                input_tokens, output_tokens, cost_usd
+            FROM run_tasks WHERE run_id = ? ORDER BY position
+        "#,
+        )
+        .bind(run_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let tasks = rows
+            .into_iter()
+            .map(|row| TaskRecord {
+                task_id: row.0,
+                name: row.1,
+                task_type: row.2,
+                parameters: row.3.and_then(|json| serde_json::from_str(&json).ok()),
+                status: parse_status(&row.4),
+                duration_secs: row.5,
+                error: row.6,
+                usage: TaskUsage {
+                    input_tokens: row.7 as u64,
+                    output_tokens: row.8 as u64,
+                    cost_usd: row.9,
+                    cost_partial: false,
+                },
+            })
+            .collect();
+        Ok(Some(RunRecord {
+            run_id: run_id.to_string(),
+            started_at,
+            finished_at,
+            duration_secs,
+            tasks,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn task(task_id: &str, status: TaskStatus, input_tokens: u64) -> TaskRecord {
+        TaskRecord {
+            task_id: task_id.to_string(),
+            name: "crawler".to_string(),
+            task_type: "sub_recipe".to_string(),
+            parameters: Some(json!({"site": task_id})),
+            status,
+            duration_secs: Some(2.0),
+            error: None,
+            usage: TaskUsage {
+                input_tokens,
+                output_tokens: 10,
+                cost_usd: Some(0.5),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn run(run_id: &str, started_at: DateTime<Utc>, tasks: Vec<TaskRecord>) -> RunRecord {
+        RunRecord {
+            run_id: run_id.to_string(),
+            started_at,
+            finished_at: started_at + chrono::Duration::seconds(5),
+            duration_secs: 5.0,
+            tasks,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_histories_are_migrated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        let history = RunHistory::open(&path).await.unwrap();
+        history
+            .record(&run(
+                "first",
+                Utc::now(),
+                vec![task("a", TaskStatus::Completed, 1)],
+            ))
+            .await
+            .unwrap();
+        // Made before there were versions
+        sqlx::query("DROP TABLE schema_version")
+            .execute(&history.pool)
+            .await
+            .unwrap();
+        drop(history);
+
+        for _ in 0..2 {
+            let history = RunHistory::open(&path).await.unwrap();
+            let versions = sqlx::query_scalar::<_, i32>("SELECT version FROM schema_version")
+                .fetch_all(&history.pool)
+                .await
+                .unwrap();
+            assert_eq!(versions, [CURRENT_SCHEMA_VERSION]);
+            assert!(history.get("first").await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runs_are_recorded_and_resumed_runs_updated() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = RunHistory::open(&dir.path().join(HISTORY_FILE))
+            .await
+            .unwrap();
+        let earlier = Utc::now() - chrono::Duration::hours(1);
+        history
+            .record(&run(
+                "first",
+                earlier,
+                vec![task("a", TaskStatus::Completed, 100)],
+            ))
+            .await
+            .unwrap();
+        let mut failed = task("c", TaskStatus::Failed, 50);
+        failed.error = Some("Connection refused".to_string());
+        history
+            .record(&run(
+                "second",
+                Utc::now(),
+                vec![task("b", TaskStatus::Completed, 100), failed],
+            ))
+            .await
+            .unwrap();
+
+        let runs = history.list(10).await.unwrap();
+        let ids: Vec<&str> = runs.iter().map(|run| run.run_id.as_str()).collect();
+        assert_eq!(ids, ["second", "first"]);
+        assert_eq!(
+            (runs[0].total, runs[0].completed, runs[0].failed),
+            (2, 1, 1)
+        );
+        assert_eq!(runs[0].usage.input_tokens, 150);
+        assert_eq!(runs[0].usage.cost_usd, Some(1.0));
+        assert_eq!(history.list(1).await.unwrap().len(), 1);
+
+        // Resuming runs the failed task again
+        history
+            .record(&run(
+                "second",
+                Utc::now(),
+                vec![task("c", TaskStatus::Completed, 30)],
+            ))
+            .await
+            .unwrap();
+        let resumed = history.get("second").await.unwrap().unwrap();
+        assert_eq!(resumed.duration_secs, 10.0);
+        let tasks: Vec<(&str, &TaskStatus)> = resumed
+            .tasks
+            .iter()
+            .map(|task| (task.task_id.as_str(), &task.status))
+            .collect();
+        assert_eq!(
+            tasks,
+            [("b", &TaskStatus::Completed), ("c", &TaskStatus::Completed)]
+        );
+        assert_eq!(resumed.tasks[1].error, None);
+        assert_eq!(resumed.tasks[1].usage.input_tokens, 80);
+        assert_eq!(resumed.tasks[1].parameters, Some(json!({"site": "c"})));
+        assert!(history.get("missing").await.unwrap().is_none());
+    }
+}
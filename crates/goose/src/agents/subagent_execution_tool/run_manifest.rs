@@ -0,0 +1,282 @@
+//! The state of each parallel run, kept so `--resume-tasks` can pick it up again.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::agents::subagent_execution_tool::task_types::{
+    FailurePolicy, Task, TaskResult, TaskStatus,
+};
+use crate::agents::subagent_execution_tool::utils::{prune_files, PruneReport};
+use crate::session::session_manager::ensure_session_dir;
+
+/// Runs that nothing happened in within this long are removed by a prune
+pub const DEFAULT_RUN_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The directory run manifests are kept in, made if it isn't there yet
+pub fn manifest_dir() -> Result<PathBuf> {
+    let dir = ensure_session_dir()?.join("runs");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Where the manifest of the run `run_id` is kept in `dir`
+pub fn manifest_path(dir: &Path, run_id: &str) -> PathBuf {
+    dir.join(format!("{}.json", run_id))
+}
+
+/// Where the results of the run `run_id` are added to in `dir`, a line each
+fn results_path(dir: &Path, run_id: &str) -> PathBuf {
+    dir.join(format!("{}.results.jsonl", run_id))
+}
+
+/// Refuse a run id that isn't a plain name, as one given to `--resume-tasks` could be anything,
+/// a path out of `dir` included
+pub fn validate_run_id(run_id: &str) -> Result<()> {
+    let plain = run_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if run_id.is_empty() || !plain {
+        bail!(
+            "Invalid task run id '{}'; run ids have only letters, digits, '_' and '-'",
+            run_id
+        );
+    }
+    Ok(())
+}
+
+/// Remove the runs in `dir` nothing was saved of for `retention`. With `dry_run` nothing is
+/// deleted, only reported.
+pub fn prune(dir: &Path, retention: Duration, dry_run: bool) -> Result<PruneReport> {
+    prune_files(dir, &["json", "jsonl"], retention, dry_run)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub run_id: String,
+    pub created_at: DateTime<Utc>,
+    pub tasks: Vec<Task>,
+    /// When the run stops starting tasks, kept for resuming it the same way
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+    /// The results of the tasks that finished, by task id
+    #[serde(default)]
+    pub results: HashMap<String, TaskResult>,
+}
+
+/// An id for a run starting now
+pub fn new_run_id() -> String {
+    let suffix = Uuid::new_v4().simple().to_string();
+    format!("{}_{}", Local::now().format("%Y%m%d_%H%M%S"), &suffix[..6])
+}
+
+impl RunManifest {
+    pub fn new(tasks: Vec<Task>, on_failure: FailurePolicy) -> Self {
+        Self {
+            run_id: new_run_id(),
+            created_at: Utc::now(),
+            tasks,
+            on_failure,
+            results: HashMap::new(),
+        }
+    }
+
+    pub fn load(dir: &Path, run_id: &str) -> Result<Self> {
+        validate_run_id(run_id)?;
+        let path = manifest_path(dir, run_id);
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("No task run {} in {}", run_id, dir.display()))?;
+        let mut manifest: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to read the manifest {}", path.display()))?;
+        // A line cut short by a crash is the only one that doesn't parse
+        let results = std::fs::read_to_string(results_path(dir, run_id)).unwrap_or_default();
+        for result in results
+            .lines()
+            .filter_map(|line| serde_json::from_str::<TaskResult>(line).ok())
+        {
+            manifest.results.insert(result.task_id.clone(), result);
+        }
+        Ok(manifest)
+    }
+
+    /// Write the manifest, with every result so far, through a temporary file, so a crash
+    /// mid-write leaves the last one
+    pub async fn save(&self, dir: &Path) -> Result<()> {
+        let path = manifest_path(dir, &self.run_id);
+        let partial = path.with_extension("json.partial");
+        tokio::fs::write(&partial, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        // Its results are all in the manifest now
+        match tokio::fs::remove_file(results_path(dir, &self.run_id)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Add `result` to the results saved in `dir`, without writing the whole manifest again
+    async fn save_result(&self, dir: &Path, result: &TaskResult) -> Result<()> {
+        let mut line = serde_json::to_string(result)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(results_path(dir, &self.run_id))
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// The tasks to run when resuming: the ones without a result, or whose result isn't a
+    /// completion
+    pub fn remaining(&self) -> Vec<Task> {
+        self.tasks
+            .iter()
+            .filter(|task| {
+                self.results
+                    .get(&task.id)
+                    .is_none_or(|result| result.status != TaskStatus::Completed)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// A run's manifest, with each result saved as its task finishes. A manifest that can't be saved
+/// is warned about; the run goes on either way, it just can't be resumed.
+pub struct Checkpoint {
+    dir: Option<PathBuf>,
+    on_failure: FailurePolicy,
+    manifest: Mutex<RunManifest>,
+}
+
+impl Checkpoint {
+    /// A checkpoint for a new run of `tasks`
+    pub async fn start(tasks: Vec<Task>, on_failure: FailurePolicy) -> Self {
+        let dir = manifest_dir()
+            .inspect_err(|e| tracing::warn!("Task runs won't be resumable: {}", e))
+            .ok();
+        Self::in_dir(dir, RunManifest::new(tasks, on_failure)).await
+    }
+
+    /// A checkpoint that goes on with `manifest`, kept in `dir`
+    pub async fn in_dir(dir: Option<PathBuf>, manifest: RunManifest) -> Self {
+        if let Some(dir) = &dir {
+            if let Err(e) = manifest.save(dir).await {
+                tracing::warn!("Failed to save task run {}: {}", manifest.run_id, e);
+            }
+        }
+        Self {
+            dir,
+            on_failure: manifest.on_failure,
+            manifest: Mutex::new(manifest),
+        }
+    }
+
+    pub fn on_failure(&self) -> FailurePolicy {
+        self.on_failure
+    }
+
+    /// The run's id, if its manifest is saved anywhere
+    pub async fn run_id(&self) -> Option<String> {
+        let manifest = self.manifest.lock().await;
+        self.dir.as_ref().map(|_| manifest.run_id.clone())
+    }
+
+    pub async fn record(&self, result: &TaskResult) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let mut manifest = self.manifest.lock().await;
+        manifest
+            .results
+            .insert(result.task_id.clone(), result.clone());
+        if let Err(e) = manifest.save_result(dir, result).await {
+            tracing::warn!("Failed to save task run {}: {}", manifest.run_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::subagent_execution_tool::task_types::TaskType;
+    use serde_json::Value;
+
+    fn task(id: &str) -> Task {
+        Task {
+            id: id.to_string(),
+            task_type: TaskType::InlineRecipe,
+            payload: Value::Null,
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        }
+    }
+
+    fn result(id: &str, status: TaskStatus) -> TaskResult {
+        TaskResult {
+            task_id: id.to_string(),
+            status,
+            data: None,
+            error: None,
+            attempts: Vec::new(),
+            artifacts: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_resumes_what_did_not_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = RunManifest::new(
+            vec![task("fetch"), task("build"), task("test")],
+            FailurePolicy::FailFast,
+        );
+        let run_id = manifest.run_id.clone();
+        let checkpoint = Checkpoint::in_dir(Some(dir.path().to_path_buf()), manifest).await;
+        assert_eq!(checkpoint.run_id().await, Some(run_id.clone()));
+        assert_eq!(checkpoint.on_failure(), FailurePolicy::FailFast);
+
+        checkpoint
+            .record(&result("fetch", TaskStatus::Completed))
+            .await;
+        checkpoint
+            .record(&result("build", TaskStatus::Failed))
+            .await;
+
+        let manifest = RunManifest::load(dir.path(), &run_id).unwrap();
+        assert_eq!(manifest.results.len(), 2);
+        assert_eq!(manifest.on_failure, FailurePolicy::FailFast);
+        let remaining: Vec<String> = manifest.remaining().into_iter().map(|t| t.id).collect();
+        assert_eq!(remaining, ["build", "test"]);
+        assert!(RunManifest::load(dir.path(), "missing").is_err());
+
+        // Resuming saves the results into the manifest itself
+        let checkpoint = Checkpoint::in_dir(Some(dir.path().to_path_buf()), manifest).await;
+        assert!(!results_path(dir.path(), &run_id).exists());
+        checkpoint
+            .record(&result("build", TaskStatus::Completed))
+            .await;
+        let manifest = RunManifest::load(dir.path(), &run_id).unwrap();
+        let remaining: Vec<String> = manifest.remaining().into_iter().map(|t| t.id).collect();
+        assert_eq!(remaining, ["test"]);
+    }
+
+    #[test]
+    fn test_run_ids_are_plain_names() {
+        assert!(validate_run_id("20250101_120000_a1b2c3").is_ok());
+        for run_id in ["", "../secrets", "/etc/passwd", "runs/other", "a.b"] {
+            assert!(validate_run_id(run_id).is_err(), "{}", run_id);
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let err = RunManifest::load(dir.path(), "../outside").unwrap_err();
+        assert!(err.to_string().contains("Invalid task run id"));
+    }
+}
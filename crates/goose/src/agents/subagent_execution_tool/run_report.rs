@@ -0,0 +1,259 @@
+//! JUnit and JSON reports of a finished run, for CI systems.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::agents::subagent_execution_tool::notification_events::TaskCompletionStats;
+use crate::agents::subagent_execution_tool::task_types::TaskStatus;
+use crate::config::Config;
+
+pub const JUNIT_REPORT_KEY: &str = "GOOSE_TASK_JUNIT_REPORT";
+pub const JSON_REPORT_KEY: &str = "GOOSE_TASK_JSON_REPORT";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskReport {
+    pub id: String,
+    /// The task's name, with its parameters when it has any
+    pub name: String,
+    pub task_type: String,
+    pub status: TaskStatus,
+    pub duration_secs: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub finished_at: DateTime<Utc>,
+    /// From the first task starting to the last one finishing
+    pub duration_secs: f64,
+    pub stats: TaskCompletionStats,
+    /// In the order the tasks were queued
+    pub tasks: Vec<TaskReport>,
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace aren't allowed in XML at all
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl RunReport {
+    /// The report as JUnit XML: a test suite for the run, with a test case per task. Failed
+    /// tasks are failures, and tasks that didn't finish, were cancelled or skipped are skipped.
+    pub fn to_junit(&self) -> String {
+        let failures = self
+            .tasks
+            .iter()
+            .filter(|task| task.status == TaskStatus::Failed)
+            .count();
+        let skipped = self
+            .tasks
+            .iter()
+            .filter(|task| !matches!(task.status, TaskStatus::Completed | TaskStatus::Failed))
+            .count();
+        let suite_name = match &self.run_id {
+            Some(run_id) => format!("goose tasks {}", run_id),
+            None => "goose tasks".to_string(),
+        };
+        let counts = format!(
+            r#"tests="{}" failures="{}" errors="0" skipped="{}" time="{:.3}""#,
+            self.tasks.len(),
+            failures,
+            skipped,
+            self.duration_secs
+        );
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!("<testsuites name=\"goose\" {}>\n", counts));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" {} timestamp=\"{}\">\n",
+            escape_xml(&suite_name),
+            counts,
+            self.finished_at.format("%Y-%m-%dT%H:%M:%S")
+        ));
+        for task in &self.tasks {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"goose.{}\" time=\"{:.3}\"",
+                escape_xml(&task.name),
+                escape_xml(&task.task_type),
+                task.duration_secs.unwrap_or_default()
+            ));
+            let error = escape_xml(task.error.as_deref().unwrap_or_default());
+            match task.status {
+                TaskStatus::Completed => xml.push_str("/>\n"),
+                TaskStatus::Failed => {
+                    let message = error.lines().next().unwrap_or("Task failed");
+                    xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\">{}</failure>\n",
+                        message, error
+                    ));
+                    if let Some(log_path) = &task.log_path {
+                        xml.push_str(&format!(
+                            "      <system-out>Log: {}</system-out>\n",
+                            escape_xml(log_path)
+                        ));
+                    }
+                    xml.push_str("    </testcase>\n");
+                }
+                _ => {
+                    let message = if error.is_empty() {
+                        task.status.to_string().to_lowercase()
+                    } else {
+                        error
+                    };
+                    xml.push_str(&format!(
+                        ">\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                        message
+                    ));
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+/// Where a run's reports go; neither is written unless it's configured
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportPaths {
+    pub junit: Option<PathBuf>,
+    pub json: Option<PathBuf>,
+}
+
+impl ReportPaths {
+    /// The paths `GOOSE_TASK_JUNIT_REPORT` and `GOOSE_TASK_JSON_REPORT` name
+    pub fn from_config() -> Self {
+        let path = |key: &str| {
+            Config::global()
+                .get_param::<String>(key)
+                .ok()
+                .filter(|path| !path.trim().is_empty())
+                .map(PathBuf::from)
+        };
+        Self {
+            junit: path(JUNIT_REPORT_KEY),
+            json: path(JSON_REPORT_KEY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.junit.is_none() && self.json.is_none()
+    }
+
+    /// Write `report` wherever it's asked for, returning the reports written
+    pub async fn write(&self, report: &RunReport) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        if let Some(path) = &self.junit {
+            write_report(path, report.to_junit().as_bytes()).await?;
+            written.push(path.clone());
+        }
+        if let Some(path) = &self.json {
+            write_report(path, &serde_json::to_vec_pretty(report)?).await?;
+            written.push(path.clone());
+        }
+        Ok(written)
+    }
+}
+
+async fn write_report(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, contents).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, status: TaskStatus, error: Option<&str>) -> TaskReport {
+        TaskReport {
+            id: id.to_string(),
+            name: format!("{} (target=<all>)", id),
+            task_type: "sub_recipe".to_string(),
+            status,
+            duration_secs: Some(1.25),
+            error: error.map(str::to_string),
+            log_path: None,
+        }
+    }
+
+    fn report() -> RunReport {
+        RunReport {
+            run_id: Some("20250101_120000_abcdef".to_string()),
+            finished_at: Utc::now(),
+            duration_secs: 2.5,
+            stats: TaskCompletionStats::new(3, 1, 1).with_skipped(1),
+            tasks: vec![
+                task("build", TaskStatus::Completed, None),
+                task(
+                    "test",
+                    TaskStatus::Failed,
+                    Some("3 tests \"failed\"\nsee log"),
+                ),
+                task("deploy", TaskStatus::Skipped, None),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_to_junit() {
+        let xml = report().to_junit();
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains(r#"tests="3" failures="1" errors="0" skipped="1" time="2.500""#));
+        assert!(xml.contains(
+            r#"<testcase name="build (target=&lt;all&gt;)" classname="goose.sub_recipe" time="1.250"/>"#
+        ));
+        assert!(xml.contains(
+            r#"<failure message="3 tests &quot;failed&quot;">3 tests &quot;failed&quot;"#
+        ));
+        assert!(xml.contains(r#"<skipped message="skipped"/>"#));
+        assert!(xml.trim_end().ends_with("</testsuites>"));
+    }
+
+    #[tokio::test]
+    async fn test_write() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ReportPaths::default().is_empty());
+        let paths = ReportPaths {
+            junit: Some(dir.path().join("reports/junit.xml")),
+            json: Some(dir.path().join("report.json")),
+        };
+
+        let written = paths.write(&report()).await.unwrap();
+        assert_eq!(written.len(), 2);
+        let saved: RunReport =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("report.json")).unwrap())
+                .unwrap();
+        assert_eq!(saved.tasks.len(), 3);
+        assert_eq!(saved.tasks[1].status, TaskStatus::Failed);
+        assert!(
+            std::fs::read_to_string(dir.path().join("reports/junit.xml"))
+                .unwrap()
+                .contains("<testsuite name=\"goose tasks 20250101_120000_abcdef\"")
+        );
+    }
+}
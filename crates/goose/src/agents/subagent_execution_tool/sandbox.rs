@@ -0,0 +1,413 @@
+//! Run sub-recipe tasks in containers, apart from each other and from the host.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tempfile::TempDir;
+use tokio::process::Command;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::config::{Config, ConfigError};
+
+pub const SANDBOX_KEY: &str = "GOOSE_TASK_SANDBOX";
+/// Turns the sandbox off, as it is for the tasks of a task already in a container
+pub const SANDBOX_OFF: &str = "off";
+pub const CONTAINER_WORKDIR: &str = "/workspace";
+/// How long a container may take to be created, pulling its image included
+const CONTAINER_ID_WAIT_SECS: u64 = 300;
+const CONTAINER_ID_POLL_INTERVAL_MS: u64 = 250;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn program(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+        }
+    }
+}
+
+fn default_network() -> String {
+    "bridge".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Sandbox {
+    #[serde(default)]
+    pub runtime: ContainerRuntime,
+    pub image: String,
+    #[serde(default)]
+    pub mounts: Vec<String>,
+    #[serde(default = "default_network")]
+    pub network: String,
+    #[serde(default)]
+    pub env: Vec<String>,
+}
+
+impl Sandbox {
+    /// The sandbox `GOOSE_TASK_SANDBOX` asks for, if it asks for one
+    pub fn from_config() -> Option<Self> {
+        let value = match Config::global().get_param::<Value>(SANDBOX_KEY) {
+            Ok(value) => value,
+            Err(ConfigError::NotFound(_)) => return None,
+            Err(e) => {
+                tracing::warn!("Ignoring {}: {}", SANDBOX_KEY, e);
+                return None;
+            }
+        };
+        Self::parse(value)
+            .inspect_err(|e| tracing::warn!("Ignoring {}: {}", SANDBOX_KEY, e))
+            .ok()
+            .flatten()
+    }
+
+    /// The sandbox a `GOOSE_TASK_SANDBOX` value asks for, or `None` when it turns it off
+    pub fn parse(value: Value) -> Result<Option<Self>> {
+        match value {
+            Value::Null => Ok(None),
+            Value::String(text) if text.trim().is_empty() || text.trim() == SANDBOX_OFF => Ok(None),
+            value => Ok(Some(serde_json::from_value(value)?)),
+        }
+    }
+}
+
+/// A `--mount` value binding `source` to `target`. Fields holding a comma or a quote are
+/// quoted, as the runtimes read the value as CSV.
+fn bind_mount(source: &Path, target: &Path, readonly: bool) -> String {
+    let field = |key: &str, path: &Path| {
+        let field = format!("{}={}", key, path.display());
+        if field.contains([',', '"']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field
+        }
+    };
+    let mut mount = format!(
+        "type=bind,{},{}",
+        field("source", source),
+        field("target", target)
+    );
+    if readonly {
+        mount.push_str(",readonly");
+    }
+    mount
+}
+
+/// A `--mount` value for a mount given as `host:container[:ro]`
+fn configured_mount(mount: &str) -> Result<String> {
+    let (paths, readonly) = match mount.strip_suffix(":ro") {
+        Some(paths) => (paths, true),
+        None => (mount.strip_suffix(":rw").unwrap_or(mount), false),
+    };
+    match paths.rsplit_once(':') {
+        Some((host, container)) if !host.is_empty() && !container.is_empty() => {
+            Ok(bind_mount(Path::new(host), Path::new(container), readonly))
+        }
+        _ => bail!("Mount {} is not host:container[:ro]", mount),
+    }
+}
+
+/// Copy the directory `source` into `dest`, with links copied as links, leaving out `copy`,
+/// where it all goes, when that's inside `source`
+fn copy_dir(source: &Path, dest: &Path, copy: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if entry.path() == copy {
+            continue;
+        }
+        let target = dest.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target, copy)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// The container a task runs in
+pub struct Container {
+    sandbox: Sandbox,
+    name: String,
+    /// Holds the file the runtime writes the container's id to
+    dir: TempDir,
+    /// The task's copy of the working directory, once it has one
+    workspace: Option<TempDir>,
+}
+
+impl Container {
+    pub fn new(sandbox: Sandbox, task_id: &str) -> Result<Self> {
+        let task_id: String = task_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        Ok(Self {
+            sandbox,
+            name: format!("goose-task-{}-{}", task_id, &suffix[..8]),
+            dir: tempfile::tempdir().context("Failed to make a directory for the container id")?,
+            workspace: None,
+        })
+    }
+
+    /// Give the task a copy of `workdir` of its own to work in, mounted instead of `workdir`
+    pub async fn copy_workdir(&mut self, workdir: &Path) -> Result<()> {
+        let workspace = tempfile::Builder::new()
+            .prefix("goose-task-workspace-")
+            .tempdir()
+            .context("Failed to make a directory for the task's workspace")?;
+        let (source, dest) = (workdir.to_path_buf(), workspace.path().to_path_buf());
+        tokio::task::spawn_blocking(move || copy_dir(&source, &dest, &dest))
+            .await?
+            .with_context(|| format!("Failed to copy {} for the task", workdir.display()))?;
+        self.workspace = Some(workspace);
+        Ok(())
+    }
+
+    /// The copy of the working directory the task worked in, if it had one
+    pub fn take_workspace(&mut self) -> Option<TempDir> {
+        self.workspace.take()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cidfile(&self) -> PathBuf {
+        self.dir.path().join("cid")
+    }
+
+    /// `command` run in the container instead, with its environment. `shared` paths outside
+    /// the working directory are mounted read-only at the same paths, so the task can still read
+    /// them; only the `writable` ones, such as the file its usage is added to, can be written.
+    pub fn wrap(&self, command: &Command, shared: &[&Path], writable: &[&Path]) -> Result<Command> {
+        let inner = command.as_std();
+        let workdir = match (&self.workspace, inner.get_current_dir()) {
+            (Some(workspace), _) => workspace.path().to_path_buf(),
+            (None, Some(dir)) => dir.to_path_buf(),
+            (None, None) => std::env::current_dir()?,
+        };
+        let mut wrapped = Command::new(self.sandbox.runtime.program());
+        wrapped
+            .args(["run", "--rm", "--name", self.name.as_str()])
+            .arg("--cidfile")
+            .arg(self.cidfile())
+            .arg("--network")
+            .arg(&self.sandbox.network)
+            .arg("--mount")
+            .arg(bind_mount(&workdir, Path::new(CONTAINER_WORKDIR), false))
+            .arg("--workdir")
+            .arg(CONTAINER_WORKDIR);
+        for mount in &self.sandbox.mounts {
+            wrapped.arg("--mount").arg(configured_mount(mount)?);
+        }
+        for path in shared {
+            wrapped.arg("--mount").arg(bind_mount(path, path, true));
+        }
+        for path in writable {
+            wrapped.arg("--mount").arg(bind_mount(path, path, false));
+        }
+        let envs = inner
+            .get_envs()
+            .filter_map(|(key, value)| Some((key, value?)));
+        for (key, value) in envs {
+            wrapped.arg("--env").arg(format!(
+                "{}={}",
+                key.to_string_lossy(),
+                value.to_string_lossy()
+            ));
+        }
+        for key in &self.sandbox.env {
+            // Without a value, the runtime passes the host's
+            wrapped.arg("--env").arg(key);
+        }
+        wrapped
+            .arg(&self.sandbox.image)
+            .arg(inner.get_program())
+            .args(inner.get_args());
+        Ok(wrapped)
+    }
+
+    /// The container's id, once the runtime created it
+    pub async fn wait_for_id(&self) -> Option<String> {
+        read_container_id(&self.cidfile()).await
+    }
+
+    /// Take the container down, for a task cancelled while it runs
+    pub async fn remove(&self) {
+        let removed = Command::new(self.sandbox.runtime.program())
+            .args(["rm", "--force", self.name.as_str()])
+            .output()
+            .await;
+        if let Err(e) = removed {
+            tracing::warn!("Failed to remove container {}: {}", self.name, e);
+        }
+    }
+}
+
+async fn read_container_id(cidfile: &Path) -> Option<String> {
+    let deadline = Instant::now() + Duration::from_secs(CONTAINER_ID_WAIT_SECS);
+    while Instant::now() < deadline {
+        if let Ok(id) = tokio::fs::read_to_string(cidfile).await {
+            let id = id.trim();
+            if !id.is_empty() {
+                return Some(id.to_string());
+            }
+        }
+        sleep(Duration::from_millis(CONTAINER_ID_POLL_INTERVAL_MS)).await;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Sandbox::parse(json!("off")).unwrap(), None);
+        assert_eq!(Sandbox::parse(json!("")).unwrap(), None);
+        let sandbox = Sandbox::parse(json!({"image": "goose:latest", "runtime": "podman"}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(sandbox.runtime, ContainerRuntime::Podman);
+        assert_eq!(sandbox.network, "bridge");
+        assert!(Sandbox::parse(json!({"runtime": "docker"})).is_err());
+    }
+
+    #[test]
+    fn test_wrap() {
+        let sandbox = Sandbox {
+            runtime: ContainerRuntime::Docker,
+            image: "goose:latest".to_string(),
+            mounts: vec!["/data:/data:ro".to_string()],
+            network: "none".to_string(),
+            env: vec!["OPENAI_API_KEY".to_string()],
+        };
+        let container = Container::new(sandbox, "task 1").unwrap();
+        assert!(container.name().starts_with("goose-task-task-1-"));
+
+        let mut command = Command::new("goose");
+        command
+            .args(["run", "--recipe", "/recipes/crawl.yaml"])
+            .env("GOOSE_TASK_EVENTS", "off")
+            .current_dir("/home/me/project");
+        let wrapped = container
+            .wrap(
+                &command,
+                &[Path::new("/recipes")],
+                &[Path::new("/tmp/usage.json")],
+            )
+            .unwrap();
+        assert_eq!(wrapped.as_std().get_program(), "docker");
+        let args = args(&wrapped);
+        let joined = args.join(" ");
+        assert!(joined.starts_with(&format!("run --rm --name {}", container.name())));
+        assert!(joined.contains("--network none"));
+        assert!(joined.contains(
+            "--mount type=bind,source=/home/me/project,target=/workspace --workdir /workspace"
+        ));
+        assert!(joined.contains("--mount type=bind,source=/data,target=/data,readonly"));
+        // The recipe's directory can only be read; the usage file is the one thing written
+        assert!(joined.contains("--mount type=bind,source=/recipes,target=/recipes,readonly"));
+        assert!(joined
+            .contains("--mount type=bind,source=/tmp/usage.json,target=/tmp/usage.json --env"));
+        assert!(joined.contains("--env GOOSE_TASK_EVENTS=off"));
+        assert!(joined.contains("--env OPENAI_API_KEY"));
+        assert!(joined.ends_with("goose:latest goose run --recipe /recipes/crawl.yaml"));
+    }
+
+    #[test]
+    fn test_mounts() {
+        assert_eq!(
+            bind_mount(Path::new("/home/me/a:b,c"), Path::new("/workspace"), false),
+            r#"type=bind,"source=/home/me/a:b,c",target=/workspace"#
+        );
+        assert_eq!(
+            configured_mount("/mnt/c:/data:/data:ro").unwrap(),
+            "type=bind,source=/mnt/c:/data,target=/data,readonly"
+        );
+        assert!(configured_mount("/data").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tasks_work_in_a_copy_of_the_working_directory() {
+        let work = tempfile::tempdir().unwrap();
+        fs::create_dir_all(work.path().join("src")).unwrap();
+        fs::write(work.path().join("src/main.rs"), "fn main() {}").unwrap();
+        let sandbox = Sandbox::parse(json!({"image": "goose:latest"}))
+            .unwrap()
+            .unwrap();
+        let mut container = Container::new(sandbox, "task-1").unwrap();
+        container.copy_workdir(work.path()).await.unwrap();
+
+        let command = Command::new("goose");
+        let wrapped = container.wrap(&command, &[work.path()], &[]).unwrap();
+        let workspace = container.take_workspace().unwrap();
+        assert_eq!(
+            fs::read_to_string(workspace.path().join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        let mount = format!(
+            "type=bind,source={},target=/workspace",
+            workspace.path().display()
+        );
+        let args = args(&wrapped);
+        assert!(args.contains(&mount));
+
+        // What the task writes goes to its copy; the original is only ever mounted read-only
+        fs::write(
+            workspace.path().join("src/main.rs"),
+            "fn main() { edited() }",
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(work.path().join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        let original = format!("source={},", work.path().display());
+        for mount in args.iter().filter(|arg| arg.contains(&original)) {
+            assert!(mount.ends_with(",readonly"), "{} is writable", mount);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_container_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let cidfile = dir.path().join("cid");
+        let path = cidfile.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            tokio::fs::write(path, "0123456789abcdef\n").await.unwrap();
+        });
+        assert_eq!(
+            read_container_id(&cidfile).await.as_deref(),
+            Some("0123456789abcdef")
+        );
+    }
+}
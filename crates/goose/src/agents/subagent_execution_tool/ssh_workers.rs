@@ -0,0 +1,405 @@
+//! Spread sub-recipe tasks across other machines over SSH.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::{Notify, OnceCell};
+
+use crate::agents::subagent_execution_tool::task_usage::TASK_USAGE_FILE_KEY;
+use crate::config::{Config, ConfigError};
+
+pub const SSH_WORKERS_KEY: &str = "GOOSE_TASK_SSH_WORKERS";
+/// Keeps tasks on the machine they were started on, as a remote task's own tasks are
+pub const SSH_WORKERS_OFF: &str = "off";
+/// Where a task's recipe is copied to on its host
+const REMOTE_RECIPE_DIR: &str = "/tmp";
+
+static GLOBAL: OnceCell<Option<Arc<SshPool>>> = OnceCell::const_new();
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SshHost {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    #[serde(default)]
+    pub workdir: Option<String>,
+    /// The goose to run on the host, `goose` on its path unless given
+    #[serde(default)]
+    pub goose: Option<String>,
+    /// As many as it's given when not set
+    #[serde(default)]
+    pub max_tasks: Option<usize>,
+}
+
+/// `text` quoted for a POSIX shell
+pub fn shell_quote(text: &str) -> String {
+    if !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+%".contains(c))
+    {
+        return text.to_string();
+    }
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// The hosts tasks are spread across, and how many tasks each of them runs
+pub struct SshPool {
+    hosts: Vec<SshHost>,
+    running: Mutex<Vec<usize>>,
+    freed: Notify,
+}
+
+impl SshPool {
+    pub fn new(hosts: Vec<SshHost>) -> Self {
+        Self {
+            running: Mutex::new(vec![0; hosts.len()]),
+            hosts,
+            freed: Notify::new(),
+        }
+    }
+
+    /// The hosts a `GOOSE_TASK_SSH_WORKERS` value lists, or `None` when it lists none
+    pub fn parse(value: Value) -> Result<Option<Self>> {
+        match value {
+            Value::Null => Ok(None),
+            Value::String(text) if text.trim().is_empty() || text.trim() == SSH_WORKERS_OFF => {
+                Ok(None)
+            }
+            value => {
+                let hosts: Vec<SshHost> = serde_json::from_value(value)?;
+                Ok((!hosts.is_empty()).then(|| Self::new(hosts)))
+            }
+        }
+    }
+
+    /// The pool of the hosts `GOOSE_TASK_SSH_WORKERS` lists, made the first time it's needed, so
+    /// every run of the process shares the counts of what the hosts run
+    pub async fn global() -> Option<Arc<Self>> {
+        GLOBAL
+            .get_or_init(|| async {
+                let value = match Config::global().get_param::<Value>(SSH_WORKERS_KEY) {
+                    Ok(value) => value,
+                    Err(ConfigError::NotFound(_)) => return None,
+                    Err(e) => {
+                        tracing::warn!("Ignoring {}: {}", SSH_WORKERS_KEY, e);
+                        return None;
+                    }
+                };
+                Self::parse(value)
+                    .inspect_err(|e| tracing::warn!("Ignoring {}: {}", SSH_WORKERS_KEY, e))
+                    .ok()
+                    .flatten()
+                    .map(Arc::new)
+            })
+            .await
+            .clone()
+    }
+
+    /// A host for a task: the one running the fewest tasks, waiting while every host runs as
+    /// many as it may
+    pub async fn acquire(self: &Arc<Self>) -> SshLease {
+        loop {
+            let freed = self.freed.notified();
+            if let Some(index) = self.take_least_busy() {
+                return SshLease {
+                    pool: self.clone(),
+                    index,
+                };
+            }
+            freed.await;
+        }
+    }
+
+    fn take_least_busy(&self) -> Option<usize> {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let index = (0..self.hosts.len())
+            .filter(|index| {
+                self.hosts[*index]
+                    .max_tasks
+                    .is_none_or(|max_tasks| running[*index] < max_tasks)
+            })
+            .min_by_key(|index| running[*index])?;
+        running[index] += 1;
+        Some(index)
+    }
+}
+
+/// A task's hold on its host, given back when dropped
+pub struct SshLease {
+    pool: Arc<SshPool>,
+    index: usize,
+}
+
+impl SshLease {
+    pub fn host(&self) -> &SshHost {
+        &self.pool.hosts[self.index]
+    }
+}
+
+impl Drop for SshLease {
+    fn drop(&mut self) {
+        let mut running = self.pool.running.lock().unwrap_or_else(|e| e.into_inner());
+        running[self.index] = running[self.index].saturating_sub(1);
+        drop(running);
+        self.pool.freed.notify_waiters();
+    }
+}
+
+/// A task run on another host
+pub struct RemoteTask {
+    lease: SshLease,
+    /// Where the recipe goes on the host
+    recipe_path: String,
+    recipe: Vec<u8>,
+}
+
+impl RemoteTask {
+    pub fn new(lease: SshLease, task_id: &str) -> Self {
+        let task_id: String = task_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        Self {
+            lease,
+            recipe_path: format!(
+                "{}/goose-task-{}-{}",
+                REMOTE_RECIPE_DIR,
+                task_id,
+                &suffix[..8]
+            ),
+            recipe: Vec::new(),
+        }
+    }
+
+    pub fn host(&self) -> &SshHost {
+        self.lease.host()
+    }
+
+    /// The recipe, for the `ssh` of [`RemoteTask::wrap`] to be given on its input
+    pub fn recipe(&self) -> &[u8] {
+        &self.recipe
+    }
+
+    fn ssh(&self) -> Command {
+        let host = self.host();
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes"]);
+        if let Some(port) = host.port {
+            ssh.arg("-p").arg(port.to_string());
+        }
+        if let Some(identity_file) = &host.identity_file {
+            ssh.arg("-i").arg(identity_file);
+        }
+        ssh.arg(&host.host).arg("--");
+        ssh
+    }
+
+    /// `command`, a `goose run --recipe`, run on the host instead with its environment. The
+    /// recipe goes to the host on the command's input, so it's read here.
+    pub fn wrap(&mut self, command: &Command) -> Result<Command> {
+        let inner = command.as_std();
+        let args: Vec<String> = inner
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        let recipe_index = args
+            .iter()
+            .position(|arg| arg == "--recipe")
+            .map(|index| index + 1)
+            .filter(|index| *index < args.len())
+            .ok_or_else(|| anyhow!("Only recipes can be run on other hosts"))?;
+        let local_recipe = Path::new(&args[recipe_index]);
+        self.recipe = std::fs::read(local_recipe)
+            .with_context(|| format!("Failed to read recipe {}", local_recipe.display()))?;
+        // The recipe keeps its extension, which says how to read it
+        if let Some(extension) = local_recipe.extension() {
+            self.recipe_path = format!("{}.{}", self.recipe_path, extension.to_string_lossy());
+        }
+
+        let envs: Vec<String> = inner
+            .get_envs()
+            .filter(|(key, _)| *key != TASK_USAGE_FILE_KEY)
+            .filter_map(|(key, value)| {
+                Some(format!(
+                    "{}={}",
+                    key.to_string_lossy(),
+                    shell_quote(&value?.to_string_lossy())
+                ))
+            })
+            .collect();
+        let goose = self.host().goose.as_deref().unwrap_or("goose");
+        let mut goose_args = vec![shell_quote(goose)];
+        for (index, arg) in args.iter().enumerate() {
+            goose_args.push(if index == recipe_index {
+                shell_quote(&self.recipe_path)
+            } else {
+                shell_quote(arg)
+            });
+        }
+        let recipe_path = shell_quote(&self.recipe_path);
+        let mut script = String::new();
+        if let Some(workdir) = &self.host().workdir {
+            script.push_str(&format!("cd {} && ", shell_quote(workdir)));
+        }
+        script.push_str(&format!(
+            "cat > {recipe} && env {envs} {goose}; status=$?; rm -f {recipe}; exit $status",
+            recipe = recipe_path,
+            envs = envs.join(" "),
+            goose = goose_args.join(" ")
+        ));
+
+        let mut ssh = self.ssh();
+        ssh.arg(script);
+        Ok(ssh)
+    }
+
+    /// What stops the task on its host. The pattern is bracketed so it doesn't match the
+    /// shell running it, which would be killed before removing the recipe.
+    fn stop_script(&self) -> String {
+        let escaped: String = self
+            .recipe_path
+            .chars()
+            .flat_map(|c| {
+                let special = r"\.[]()*+?{}|^$".contains(c);
+                special.then_some('\\').into_iter().chain([c])
+            })
+            .collect();
+        format!(
+            "pkill -f -- {pattern}; rm -f {recipe}",
+            pattern = shell_quote(&format!("[r]un --recipe {}", escaped)),
+            recipe = shell_quote(&self.recipe_path)
+        )
+    }
+
+    /// Stop the task on its host, for a task cancelled while it runs
+    pub async fn stop(&self) {
+        let stopped = self.ssh().arg(self.stop_script()).output().await;
+        if let Err(e) = stopped {
+            tracing::warn!("Failed to stop the task on {}: {}", self.host().host, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::time::{timeout, Duration};
+
+    fn host(name: &str, max_tasks: Option<usize>) -> SshHost {
+        SshHost {
+            host: name.to_string(),
+            port: None,
+            identity_file: None,
+            workdir: None,
+            goose: None,
+            max_tasks,
+        }
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/tmp/recipe.yaml"), "/tmp/recipe.yaml");
+        assert_eq!(shell_quote("two words"), "'two words'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(SshPool::parse(json!("off")).unwrap().is_none());
+        assert!(SshPool::parse(json!([])).unwrap().is_none());
+        let pool = SshPool::parse(json!([{"host": "me@build-1", "max_tasks": 2}]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.hosts, [host("me@build-1", Some(2))]);
+        assert!(SshPool::parse(json!([{"port": 22}])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tasks_go_to_the_least_busy_host() {
+        let pool = Arc::new(SshPool::new(vec![
+            host("build-1", Some(1)),
+            host("build-2", None),
+        ]));
+        let first = pool.acquire().await;
+        let second = pool.acquire().await;
+        let third = pool.acquire().await;
+        let hosts: Vec<&str> = [&first, &second, &third]
+            .iter()
+            .map(|lease| lease.host().host.as_str())
+            .collect();
+        assert_eq!(hosts, ["build-1", "build-2", "build-2"]);
+
+        drop(first);
+        let fourth = timeout(Duration::from_secs(1), pool.acquire())
+            .await
+            .unwrap();
+        assert_eq!(fourth.host().host, "build-1");
+    }
+
+    #[tokio::test]
+    async fn test_wrap() {
+        let dir = tempfile::tempdir().unwrap();
+        let recipe = dir.path().join("crawl.yaml");
+        std::fs::write(&recipe, "title: Crawl\n").unwrap();
+        let pool = Arc::new(SshPool::new(vec![SshHost {
+            port: Some(2222),
+            workdir: Some("/srv/my project".to_string()),
+            ..host("me@build-1", None)
+        }]));
+        let mut remote = RemoteTask::new(pool.acquire().await, "task 1");
+
+        let mut command = Command::new("goose");
+        command
+            .arg("run")
+            .arg("--recipe")
+            .arg(&recipe)
+            .args(["--no-session", "--params", "site=example.com's"])
+            .env("GOOSE_TASK_EVENTS", "off")
+            .env(TASK_USAGE_FILE_KEY, "/tmp/usage");
+        let ssh = remote.wrap(&command).unwrap();
+        assert_eq!(remote.recipe(), b"title: Crawl\n");
+
+        let args: Vec<String> = ssh
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(ssh.as_std().get_program(), "ssh");
+        assert_eq!(
+            args[..6],
+            ["-o", "BatchMode=yes", "-p", "2222", "me@build-1", "--"]
+        );
+        let script = &args[6];
+        let recipe_path = &remote.recipe_path;
+        assert!(
+            recipe_path.starts_with("/tmp/goose-task-task-1-") && recipe_path.ends_with(".yaml")
+        );
+        assert_eq!(
+            *script,
+            format!(
+                "cd '/srv/my project' && cat > {0} && env GOOSE_TASK_EVENTS=off goose run \
+                 --recipe {0} --no-session --params 'site=example.com'\\''s'; status=$?; \
+                 rm -f {0}; exit $status",
+                recipe_path
+            )
+        );
+        assert_eq!(
+            remote.stop_script(),
+            format!(
+                r"pkill -f -- '[r]un --recipe {}'; rm -f {}",
+                recipe_path.replace('.', r"\."),
+                recipe_path
+            )
+        );
+    }
+}
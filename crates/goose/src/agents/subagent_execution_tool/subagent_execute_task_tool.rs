@@ -42,6 +42,22 @@ pub fn create_subagent_execute_task_tool() -> Tool {
                     "default": "sequential",
                     "description": "Execution strategy for multiple tasks. Use 'sequential' (default) unless user explicitly requests parallel execution with words like 'parallel', 'simultaneously', 'at the same time', or 'concurrently'."
                 },
+                "max_parallel": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "How many of the tasks may run at once in parallel execution; the rest wait their turn. Only set it when the user asks for a limit; the configured one applies otherwise."
+                },
+                "on_failure": {
+                    "type": "string",
+                    "pattern": "^(continue|fail_fast|threshold:[1-9][0-9]*)$",
+                    "default": "continue",
+                    "description": "What parallel execution does when tasks fail: 'continue' runs every task, 'fail_fast' starts no more tasks after the first failure, and 'threshold:N' starts no more after N failures. Tasks already running finish either way; the ones not started are cancelled."
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Check the tasks without running them: resolve their recipes and parameters, work out the order their dependencies put them in and how many would run at once, and show that plan. Use it when the user asks what a run would do. Only for parallel execution."
+                },
                 "task_ids": {
                     "type": "array",
                     "items": {
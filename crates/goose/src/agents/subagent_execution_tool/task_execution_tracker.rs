@@ -3,17 +3,35 @@ use rmcp::model::{
     LoggingMessageNotificationParam, ServerNotification,
 };
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tempfile::TempDir;
+use tokio::sync::{mpsc, Mutex, Notify, RwLock};
 use tokio::time::{sleep, Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::agents::subagent_execution_tool::artifacts::ArtifactStore;
 use crate::agents::subagent_execution_tool::notification_events::{
-    FailedTaskInfo, TaskCompletionStats, TaskExecutionNotificationEvent, TaskExecutionStats,
-    TaskInfo as EventTaskInfo,
+    ArtifactInfo, FailedTaskInfo, TaskCompletionStats, TaskExecutionNotificationEvent,
+    TaskExecutionStats, TaskInfo as EventTaskInfo,
 };
-use crate::agents::subagent_execution_tool::task_types::{Task, TaskInfo, TaskResult, TaskStatus};
-use crate::agents::subagent_execution_tool::utils::{count_by_status, get_task_name};
+use crate::agents::subagent_execution_tool::pause::PauseControl;
+use crate::agents::subagent_execution_tool::progress_events::{ProgressEvent, ProgressEvents};
+use crate::agents::subagent_execution_tool::result_cache::ResultCache;
+use crate::agents::subagent_execution_tool::run_history::{RunHistory, RunRecord, TaskRecord};
+use crate::agents::subagent_execution_tool::run_manifest::new_run_id;
+use crate::agents::subagent_execution_tool::run_report::{ReportPaths, RunReport, TaskReport};
+use crate::agents::subagent_execution_tool::task_logs::TaskLogs;
+use crate::agents::subagent_execution_tool::task_types::{
+    FailurePolicy, Task, TaskInfo, TaskResult, TaskStatus,
+};
+use crate::agents::subagent_execution_tool::task_usage::TaskUsage;
+use crate::agents::subagent_execution_tool::tasks_manager::Restarts;
+use crate::agents::subagent_execution_tool::utils::{
+    count_by_status, count_with_status, estimate_progress, get_task_name,
+};
+use crate::agents::subagent_execution_tool::webhooks::{WebhookQueue, Webhooks};
 use crate::utils::is_token_cancelled;
 use serde_json::Value;
 use tokio::sync::mpsc::Sender;
@@ -26,6 +44,66 @@ pub enum DisplayMode {
 
 const THROTTLE_INTERVAL_MS: u64 = 250;
 const COMPLETION_NOTIFICATION_DELAY_MS: u64 = 500;
+/// How often the ticker redraws while tasks are producing output
+const ACTIVE_REFRESH_INTERVAL_MS: u64 = 250;
+/// How often it redraws while tasks run quietly, so their durations keep counting
+const IDLE_REFRESH_INTERVAL_MS: u64 = 1000;
+/// Output within this long counts as activity
+const ACTIVITY_WINDOW_MS: u64 = 2000;
+/// Status changes wait this long so the ones arriving together share a redraw
+const BATCH_WINDOW_MS: u64 = 50;
+/// Lines of output kept for the dashboard; all of it goes to the task's log
+const RECENT_OUTPUT_LINES: usize = 10;
+
+/// How long the ticker waits before its next redraw, given how long ago a task last printed
+fn refresh_interval(since_output: Duration) -> Duration {
+    if since_output < Duration::from_millis(ACTIVITY_WINDOW_MS) {
+        Duration::from_millis(ACTIVE_REFRESH_INTERVAL_MS)
+    } else {
+        Duration::from_millis(IDLE_REFRESH_INTERVAL_MS)
+    }
+}
+
+/// How long `task_info` has left at `now`, if it is running with a timeout
+fn remaining_secs(task_info: &TaskInfo, now: Instant) -> Option<f64> {
+    let timeout = task_info.task.timeout_seconds? as f64;
+    let start = task_info.start_time?;
+    matches!(task_info.status, TaskStatus::Running)
+        .then(|| (timeout - now.duration_since(start).as_secs_f64()).max(0.0))
+}
+
+/// Add `line` to `output`, dropping the oldest lines past [`RECENT_OUTPUT_LINES`]
+fn push_recent_output(output: &mut String, line: &str) {
+    output.push_str(line);
+    output.push('\n');
+    let excess = output
+        .matches('\n')
+        .count()
+        .saturating_sub(RECENT_OUTPUT_LINES);
+    if let Some((end, _)) = excess
+        .checked_sub(1)
+        .and_then(|last| output.match_indices('\n').nth(last))
+    {
+        output.drain(..=end);
+    }
+}
+
+/// How the tasks of a finished run ended, and what they used
+fn completion_stats(tasks: &HashMap<String, TaskInfo>) -> TaskCompletionStats {
+    let (total, _, _, completed, failed) = count_by_status(tasks);
+    TaskCompletionStats::new(total, completed, failed)
+        .with_cancelled(count_with_status(tasks, TaskStatus::Cancelled))
+        .with_skipped(count_with_status(tasks, TaskStatus::Skipped))
+        .with_stopped_by_policy(
+            tasks
+                .values()
+                .filter(|task_info| task_info.stopped_by_policy)
+                .count(),
+        )
+        .with_usage(TaskUsage::total(
+            tasks.values().map(|task_info| &task_info.usage),
+        ))
+}
 
 fn format_task_metadata(task_info: &TaskInfo) -> String {
     if let Some(params) = task_info.task.get_command_parameters() {
@@ -51,10 +129,35 @@ fn format_task_metadata(task_info: &TaskInfo) -> String {
 
 pub struct TaskExecutionTracker {
     tasks: Arc<RwLock<HashMap<String, TaskInfo>>>,
+    /// Task ids in the order the tasks were queued, the highest priority first
+    queue_order: Vec<String>,
     last_refresh: Arc<RwLock<Instant>>,
+    last_output: Arc<RwLock<Instant>>,
+    /// Something changed since the last redraw
+    dirty: AtomicBool,
+    /// A task started or finished; wakes the ticker early
+    status_changed: Arc<Notify>,
+    ticking: AtomicBool,
+    ticker_stop: CancellationToken,
     notifier: mpsc::Sender<ServerNotification>,
     display_mode: DisplayMode,
     cancellation_token: Option<CancellationToken>,
+    logs: TaskLogs,
+    /// Where progress goes instead of notifications, when it goes somewhere else
+    events: Option<Arc<ProgressEvents>>,
+    webhooks: Option<WebhookQueue>,
+    failure_policy: FailurePolicy,
+    artifacts: ArtifactStore,
+    reports: ReportPaths,
+    history: Option<Arc<RunHistory>>,
+    result_cache: Option<Arc<ResultCache>>,
+    pause: Option<PauseControl>,
+    /// Whether the last redraw showed the run paused
+    shown_paused: AtomicBool,
+    restarts: Restarts,
+    /// The copies of the working directory sandboxed tasks worked in, by task id, until their
+    /// artifacts are collected
+    workspaces: Mutex<HashMap<String, TempDir>>,
 }
 
 impl TaskExecutionTracker {
@@ -64,6 +167,9 @@ impl TaskExecutionTracker {
         notifier: Sender<ServerNotification>,
         cancellation_token: Option<CancellationToken>,
     ) -> Self {
+        let mut queued: Vec<&Task> = tasks.iter().collect();
+        queued.sort_by_key(|task| std::cmp::Reverse(task.priority));
+        let queue_order = queued.into_iter().map(|task| task.id.clone()).collect();
         let task_map = tasks
             .into_iter()
             .map(|task| {
@@ -77,6 +183,12 @@ impl TaskExecutionTracker {
                         end_time: None,
                         result: None,
                         current_output: String::new(),
+                        attempt: 1,
+                        stopped_by_policy: false,
+                        usage: TaskUsage::default(),
+                        container_id: None,
+                        host: None,
+                        cached: false,
                     },
                 )
             })
@@ -84,10 +196,99 @@ impl TaskExecutionTracker {
 
         Self {
             tasks: Arc::new(RwLock::new(task_map)),
+            queue_order,
             last_refresh: Arc::new(RwLock::new(Instant::now())),
+            last_output: Arc::new(RwLock::new(Instant::now())),
+            dirty: AtomicBool::new(false),
+            status_changed: Arc::new(Notify::new()),
+            ticking: AtomicBool::new(false),
+            ticker_stop: CancellationToken::new(),
             notifier,
             display_mode,
             cancellation_token,
+            logs: TaskLogs::default(),
+            events: None,
+            webhooks: None,
+            failure_policy: FailurePolicy::default(),
+            artifacts: ArtifactStore::default(),
+            reports: ReportPaths::default(),
+            history: None,
+            result_cache: None,
+            pause: None,
+            shown_paused: AtomicBool::new(false),
+            restarts: Restarts::default(),
+            workspaces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Collect the tasks' artifacts into `artifacts`
+    pub fn with_artifacts(mut self, artifacts: ArtifactStore) -> Self {
+        self.artifacts = artifacts;
+        self
+    }
+
+    /// Show whether `pause` holds the run's tasks back
+    pub fn with_pause(mut self, pause: PauseControl) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Let `restarts` start the tasks' running attempts over
+    pub fn with_restarts(mut self, restarts: Restarts) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    pub fn restarts(&self) -> &Restarts {
+        &self.restarts
+    }
+
+    /// Write the reports of the run to `reports` once it's over
+    pub fn with_reports(mut self, reports: ReportPaths) -> Self {
+        self.reports = reports;
+        self
+    }
+
+    /// Record the run in `history` once it's over, if given
+    pub fn with_history(mut self, history: Option<Arc<RunHistory>>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Show `failure_policy` as the run's policy
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Report progress to `events`, if given, rather than with notifications
+    pub fn with_progress_events(mut self, events: Option<Arc<ProgressEvents>>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Tell `webhooks`, if given, how the tasks are going
+    pub fn with_webhooks(mut self, webhooks: Option<Arc<Webhooks>>) -> Self {
+        self.webhooks = webhooks.map(WebhookQueue::start);
+        self
+    }
+
+    /// Reuse the results `result_cache` kept of tasks like these, and keep theirs
+    pub fn with_result_cache(mut self, result_cache: Option<Arc<ResultCache>>) -> Self {
+        self.result_cache = result_cache;
+        self
+    }
+
+    pub fn result_cache(&self) -> Option<&Arc<ResultCache>> {
+        self.result_cache.as_ref()
+    }
+
+    async fn emit(&self, event: ProgressEvent) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.push(event.clone());
+        }
+        if let Some(events) = &self.events {
+            events.emit(event).await;
         }
     }
 
@@ -102,6 +303,9 @@ impl TaskExecutionTracker {
     }
 
     fn try_send_notification(&self, event: TaskExecutionNotificationEvent, context: &str) {
+        if self.events.is_some() {
+            return;
+        }
         if let Err(e) = self
             .notifier
             .try_send(ServerNotification::LoggingMessageNotification(
@@ -122,23 +326,185 @@ impl TaskExecutionTracker {
 
     pub async fn start_task(&self, task_id: &str) {
         let mut tasks = self.tasks.write().await;
-        if let Some(task_info) = tasks.get_mut(task_id) {
+        let started = tasks.get_mut(task_id).map(|task_info| {
             task_info.status = TaskStatus::Running;
             task_info.start_time = Some(Instant::now());
+            ProgressEvent::TaskStarted {
+                task_id: task_id.to_string(),
+                name: get_task_name(task_info).to_string(),
+                task_type: task_info.task.task_type.to_string(),
+            }
+        });
+        drop(tasks);
+        if let Some(started) = started {
+            self.emit(started).await;
+        }
+        self.status_changed().await;
+    }
+
+    /// Note that `task_id` failed and is run again, as its `attempt`th run
+    pub async fn retry_task(&self, task_id: &str, attempt: u32) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(task_info) = tasks.get_mut(task_id) {
+            task_info.attempt = attempt;
         }
         drop(tasks);
-        self.force_refresh_display().await;
+        self.status_changed().await;
     }
 
-    pub async fn complete_task(&self, task_id: &str, result: TaskResult) {
+    /// Note that the task `task_id` won't start, because the run's failure policy stopped it
+    pub async fn stop_by_policy(&self, task_id: &str) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(task_info) = tasks.get_mut(task_id) {
+            task_info.stopped_by_policy = true;
+        }
+    }
+
+    /// Add what `task_id` spent to its usage, shown on the next redraw
+    pub async fn add_usage(&self, task_id: &str, usage: &TaskUsage) {
         let mut tasks = self.tasks.write().await;
         if let Some(task_info) = tasks.get_mut(task_id) {
+            task_info.usage.add(usage);
+        }
+        drop(tasks);
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Note the container `task_id` runs in
+    pub async fn set_container_id(&self, task_id: &str, container_id: String) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(task_info) = tasks.get_mut(task_id) {
+            task_info.container_id = Some(container_id);
+        }
+        drop(tasks);
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Note that `task_id` worked in `workspace`, a copy of the working directory, for its
+    /// artifacts to be collected from there
+    pub async fn set_workspace(&self, task_id: &str, workspace: TempDir) {
+        self.workspaces
+            .lock()
+            .await
+            .insert(task_id.to_string(), workspace);
+    }
+
+    /// Note the worker host `task_id` runs on
+    pub async fn set_host(&self, task_id: &str, host: String) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(task_info) = tasks.get_mut(task_id) {
+            task_info.host = Some(host);
+        }
+        drop(tasks);
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Show the task as one whose result came from the cache
+    pub async fn mark_cached(&self, task_id: &str) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(task_info) = tasks.get_mut(task_id) {
+            task_info.cached = true;
+        }
+        drop(tasks);
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    pub async fn complete_task(&self, task_id: &str, result: TaskResult) {
+        let mut tasks = self.tasks.write().await;
+        let completed = tasks.get_mut(task_id).map(|task_info| {
+            let end_time = Instant::now();
+            let event = ProgressEvent::task_completed(
+                task_id.to_string(),
+                get_task_name(task_info).to_string(),
+                &result.status,
+                task_info
+                    .start_time
+                    .map(|start| end_time.duration_since(start).as_secs_f64()),
+                result.error.clone(),
+            );
             task_info.status = result.status.clone();
-            task_info.end_time = Some(Instant::now());
+            task_info.end_time = Some(end_time);
             task_info.result = Some(result);
-        }
+            event
+        });
         drop(tasks);
-        self.force_refresh_display().await;
+        if let Some(completed) = completed {
+            self.emit(completed).await;
+        }
+        self.status_changed().await;
+    }
+
+    /// Copy what `task` declared it leaves behind, once it completed, listing the copies on
+    /// `result`. Artifacts that can't be collected don't fail the task; they are reported in
+    /// its output and left out. Those of tasks that ran on a worker host stay there, and those
+    /// of sandboxed tasks are collected from their copy of the working directory, which is
+    /// removed after.
+    pub async fn collect_artifacts(&self, task: &Task, result: TaskResult) -> TaskResult {
+        let workspace = self.workspaces.lock().await.remove(&task.id);
+        let base = match &workspace {
+            Some(workspace) => workspace.path().to_path_buf(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        let result = self.collect_artifacts_from(task, result, &base).await;
+        if let Some(workspace) = workspace {
+            // Removing a whole copy of the working directory takes a while
+            tokio::task::spawn_blocking(move || drop(workspace));
+        }
+        result
+    }
+
+    async fn collect_artifacts_from(
+        &self,
+        task: &Task,
+        mut result: TaskResult,
+        base: &Path,
+    ) -> TaskResult {
+        let declared = task.get_artifacts();
+        if declared.is_empty() || result.status != TaskStatus::Completed {
+            return result;
+        }
+        let host = self
+            .tasks
+            .read()
+            .await
+            .get(&task.id)
+            .and_then(|task_info| task_info.host.clone());
+        if let Some(host) = host {
+            let message = format!("Artifacts aren't collected from {}; they stay there", host);
+            self.send_live_output(&task.id, &message).await;
+            return result;
+        }
+        let name = task.get_sub_recipe_name().unwrap_or(&task.id);
+        match self
+            .artifacts
+            .collect(&task.id, name, base, &declared, result.data.as_ref())
+            .await
+        {
+            Ok(artifacts) => {
+                for missing in &artifacts.missing {
+                    self.send_live_output(&task.id, &format!("Artifact {} not found", missing))
+                        .await;
+                }
+                for (declared, reason) in &artifacts.rejected {
+                    let message = format!("Artifact {} not collected: {}", declared, reason);
+                    self.send_live_output(&task.id, &message).await;
+                }
+                result.artifacts = artifacts.files;
+            }
+            Err(e) => {
+                self.send_live_output(&task.id, &format!("Failed to collect artifacts: {}", e))
+                    .await;
+            }
+        }
+        result
+    }
+
+    /// The log of `task_id`, if it printed anything
+    fn written_log(&self, task_id: &str) -> Option<String> {
+        self.logs
+            .path(task_id)
+            .filter(|path| path.exists())
+            .map(|path| path.display().to_string())
     }
 
     pub async fn get_current_output(&self, task_id: &str) -> Option<String> {
@@ -165,6 +531,12 @@ impl TaskExecutionTracker {
     }
 
     pub async fn send_live_output(&self, task_id: &str, line: &str) {
+        self.logs.append(task_id, line).await;
+        self.emit(ProgressEvent::TaskOutput {
+            task_id: task_id.to_string(),
+            line: line.to_string(),
+        })
+        .await;
         match self.display_mode {
             DisplayMode::SingleTaskOutput => {
                 let tasks = self.tasks.read().await;
@@ -182,12 +554,14 @@ impl TaskExecutionTracker {
             DisplayMode::MultipleTasksOutput => {
                 let mut tasks = self.tasks.write().await;
                 if let Some(task_info) = tasks.get_mut(task_id) {
-                    task_info.current_output.push_str(line);
-                    task_info.current_output.push('\n');
+                    push_recent_output(&mut task_info.current_output, line);
                 }
                 drop(tasks);
+                *self.last_output.write().await = Instant::now();
 
-                if !self.should_throttle_refresh().await {
+                if self.ticking.load(Ordering::Acquire) {
+                    self.dirty.store(true, Ordering::Release);
+                } else if !self.should_throttle_refresh().await {
                     self.refresh_display().await;
                 }
             }
@@ -211,11 +585,45 @@ impl TaskExecutionTracker {
             return;
         }
 
-        let tasks = self.tasks.read().await;
+        let (stats, event_tasks) = self.describe(&*self.tasks.read().await);
+        let event = TaskExecutionNotificationEvent::tasks_update(stats, event_tasks);
+
+        self.try_send_notification(event, "tasks update");
+    }
+
+    /// The progress counts and task list a tasks update shows for `tasks`
+    fn describe(
+        &self,
+        tasks: &HashMap<String, TaskInfo>,
+    ) -> (TaskExecutionStats, Vec<EventTaskInfo>) {
         let task_list: Vec<_> = tasks.values().collect();
-        let (total, pending, running, completed, failed) = count_by_status(&tasks);
+        let (total, pending, running, completed, failed) = count_by_status(tasks);
+        let (eta_secs, tasks_per_minute) = estimate_progress(tasks, Instant::now());
 
-        let stats = TaskExecutionStats::new(total, pending, running, completed, failed);
+        let stats = TaskExecutionStats::new(total, pending, running, completed, failed)
+            .with_cancelled(count_with_status(tasks, TaskStatus::Cancelled))
+            .with_skipped(count_with_status(tasks, TaskStatus::Skipped))
+            .with_on_failure(
+                (self.failure_policy != FailurePolicy::Continue)
+                    .then(|| self.failure_policy.to_string()),
+            )
+            .with_usage(TaskUsage::total(
+                tasks.values().map(|task_info| &task_info.usage),
+            ))
+            .with_eta_secs(eta_secs)
+            .with_tasks_per_minute(tasks_per_minute)
+            .with_paused(self.pause.as_ref().is_some_and(PauseControl::is_paused));
+        let queue_positions: HashMap<&str, usize> = self
+            .queue_order
+            .iter()
+            .filter(|id| {
+                tasks
+                    .get(*id)
+                    .is_some_and(|task_info| matches!(task_info.status, TaskStatus::Pending))
+            })
+            .enumerate()
+            .map(|(position, id)| (id.as_str(), position + 1))
+            .collect();
 
         let event_tasks: Vec<EventTaskInfo> = task_list
             .iter()
@@ -237,13 +645,25 @@ impl TaskExecutionTracker {
                     task_metadata: format_task_metadata(task_info),
                     error: task_info.error().cloned(),
                     result_data: task_info.data().cloned(),
+                    attempt: task_info.attempt,
+                    max_attempts: task_info
+                        .task
+                        .get_retry_policy()
+                        .map_or(1, |policy| policy.max_attempts.max(1)),
+                    queue_position: queue_positions.get(task_info.task.id.as_str()).copied(),
+                    depends_on: task_info.task.depends_on.clone(),
+                    remaining_secs: remaining_secs(task_info, now),
+                    stopped_by_policy: task_info.stopped_by_policy,
+                    usage: (!task_info.usage.is_empty()).then_some(task_info.usage),
+                    container_id: task_info.container_id.clone(),
+                    host: task_info.host.clone(),
+                    priority: task_info.task.priority,
+                    cached: task_info.cached,
                 }
             })
             .collect();
 
-        let event = TaskExecutionNotificationEvent::tasks_update(stats, event_tasks);
-
-        self.try_send_notification(event, "tasks update");
+        (stats, event_tasks)
     }
 
     pub async fn refresh_display(&self) {
@@ -258,6 +678,65 @@ impl TaskExecutionTracker {
         }
     }
 
+    /// Redraw the dashboard from a background task instead of on every change. Starts and
+    /// completions only mark it out of date and wake the ticker, which waits a moment so the
+    /// changes arriving together share one redraw; output is picked up on the next tick.
+    /// While tasks run it also ticks on its own, quickly while they print and slower while
+    /// they don't, so their durations keep counting. Stops at `send_tasks_complete`.
+    pub fn start_ticker(self: &Arc<Self>) {
+        if self.display_mode != DisplayMode::MultipleTasksOutput
+            || self.ticking.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        let tracker = Arc::downgrade(self);
+        let stop = self.ticker_stop.clone();
+        let cancellation_token = self.cancellation_token.clone().unwrap_or_default();
+        let status_changed = self.status_changed.clone();
+        tokio::spawn(async move {
+            loop {
+                let interval = match tracker.upgrade() {
+                    Some(tracker) => refresh_interval(tracker.last_output.read().await.elapsed()),
+                    None => break,
+                };
+                tokio::select! {
+                    _ = stop.cancelled() => break,
+                    _ = cancellation_token.cancelled() => break,
+                    _ = sleep(interval) => {}
+                    _ = status_changed.notified() => {
+                        sleep(Duration::from_millis(BATCH_WINDOW_MS)).await;
+                    }
+                }
+                let Some(tracker) = tracker.upgrade() else {
+                    break;
+                };
+                tracker.redraw_if_needed().await;
+            }
+        });
+    }
+
+    async fn redraw_if_needed(&self) {
+        if self.ticker_stop.is_cancelled() {
+            return;
+        }
+        let paused = self.pause.as_ref().is_some_and(PauseControl::is_paused);
+        let changed = self.dirty.swap(false, Ordering::AcqRel)
+            | (self.shown_paused.swap(paused, Ordering::AcqRel) != paused);
+        let (_, _, running, _, _) = count_by_status(&*self.tasks.read().await);
+        if changed || running > 0 {
+            self.send_tasks_update().await;
+        }
+    }
+
+    async fn status_changed(&self) {
+        if self.ticking.load(Ordering::Acquire) {
+            self.dirty.store(true, Ordering::Release);
+            self.status_changed.notify_one();
+        } else {
+            self.force_refresh_display().await;
+        }
+    }
+
     // Force refresh without throttling - used for important status changes
     async fn force_refresh_display(&self) {
         match self.display_mode {
@@ -275,15 +754,62 @@ impl TaskExecutionTracker {
         }
     }
 
-    pub async fn send_tasks_complete(&self) {
+    /// Show the tasks of a dry run as they would be queued, the ones that couldn't start
+    /// marked failed with why, by task id in `errors`
+    pub async fn send_tasks_planned(
+        &self,
+        errors: &HashMap<String, String>,
+        stages: usize,
+        estimated_concurrency: usize,
+    ) {
+        let mut tasks = self.tasks.write().await;
+        for (task_id, error) in errors {
+            if let Some(task_info) = tasks.get_mut(task_id) {
+                task_info.status = TaskStatus::Failed;
+                task_info.result = Some(TaskResult {
+                    task_id: task_id.clone(),
+                    status: TaskStatus::Failed,
+                    data: None,
+                    error: Some(error.clone()),
+                    attempts: Vec::new(),
+                    artifacts: Vec::new(),
+                });
+            }
+        }
+        let (stats, event_tasks) = self.describe(&tasks);
+        drop(tasks);
+        let event = TaskExecutionNotificationEvent::tasks_planned(
+            stats,
+            event_tasks,
+            stages,
+            estimated_concurrency,
+        );
+        self.try_send_notification(event, "tasks planned");
+    }
+
+    pub async fn send_tasks_complete(&self, run_id: Option<String>) {
+        self.ticker_stop.cancel();
+        if self.dirty.swap(false, Ordering::AcqRel) {
+            self.send_tasks_update().await;
+        }
+        // Cancelled runs too, since they are the ones to run again
+        if let Some(run_id) = &run_id {
+            self.record_history(run_id).await;
+        }
         if self.is_cancelled() {
+            self.drain_webhooks().await;
             return;
         }
 
         let tasks = self.tasks.read().await;
-        let (total, _, _, completed, failed) = count_by_status(&tasks);
-
-        let stats = TaskCompletionStats::new(total, completed, failed);
+        let stats = completion_stats(&tasks);
+        self.emit(ProgressEvent::Summary {
+            run_id: run_id.clone(),
+            stats: stats.clone(),
+        })
+        .await;
+        // The run is over, so nothing but the process exiting would stop the last of them
+        self.drain_webhooks().await;
 
         let failed_tasks: Vec<FailedTaskInfo> = tasks
             .values()
@@ -292,12 +818,577 @@ impl TaskExecutionTracker {
                 id: task_info.task.id.clone(),
                 name: get_task_name(task_info).to_string(),
                 error: task_info.error().cloned(),
+                log_path: self.written_log(&task_info.task.id),
             })
             .collect();
+        let log_dir = self
+            .logs
+            .dir()
+            .filter(|_| tasks.keys().any(|id| self.written_log(id).is_some()))
+            .map(|dir| dir.display().to_string());
+        let artifacts: Vec<ArtifactInfo> = self
+            .queue_order
+            .iter()
+            .filter_map(|id| tasks.get(id))
+            .filter_map(|task_info| {
+                let files = &task_info.result.as_ref()?.artifacts;
+                (!files.is_empty()).then(|| ArtifactInfo {
+                    task_id: task_info.task.id.clone(),
+                    name: get_task_name(task_info).to_string(),
+                    files: files.clone(),
+                })
+            })
+            .collect();
+        let artifacts_manifest =
+            (!artifacts.is_empty()).then(|| self.artifacts.manifest_path().display().to_string());
+        let reports = self.write_reports(&tasks, &stats, run_id.clone()).await;
 
-        let event = TaskExecutionNotificationEvent::tasks_complete(stats, failed_tasks);
+        let event = TaskExecutionNotificationEvent::tasks_complete(
+            stats,
+            failed_tasks,
+            log_dir,
+            run_id,
+            artifacts,
+            artifacts_manifest,
+            reports,
+        );
         self.try_send_notification(event, "tasks complete");
         // Wait for the notification to be recieved and displayed before clearing the tasks
         sleep(Duration::from_millis(COMPLETION_NOTIFICATION_DELAY_MS)).await;
     }
+
+    /// Wait for the webhooks to be sent every event of the run
+    async fn drain_webhooks(&self) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.drain().await;
+        }
+    }
+
+    /// Add a run of a single task to the history and write its reports, as the end of a
+    /// parallel run does; a single task shows no summary once it's over
+    pub async fn finish_single_task(&self) {
+        let run_id = new_run_id();
+        self.record_history(&run_id).await;
+        if self.is_cancelled() {
+            return;
+        }
+        let tasks = self.tasks.read().await;
+        self.write_reports(&tasks, &completion_stats(&tasks), Some(run_id))
+            .await;
+    }
+
+    /// Write the run's reports, if any are asked for, returning the ones written. Reports that
+    /// can't be written are warned about.
+    async fn write_reports(
+        &self,
+        tasks: &HashMap<String, TaskInfo>,
+        stats: &TaskCompletionStats,
+        run_id: Option<String>,
+    ) -> Vec<String> {
+        if self.reports.is_empty() {
+            return Vec::new();
+        }
+        let task_infos: Vec<&TaskInfo> = self
+            .queue_order
+            .iter()
+            .filter_map(|id| tasks.get(id))
+            .collect();
+        let first_start = task_infos.iter().filter_map(|info| info.start_time).min();
+        let last_end = task_infos.iter().filter_map(|info| info.end_time).max();
+        let report = RunReport {
+            run_id,
+            finished_at: chrono::Utc::now(),
+            duration_secs: first_start
+                .zip(last_end)
+                .map_or(0.0, |(start, end)| end.duration_since(start).as_secs_f64()),
+            stats: stats.clone(),
+            tasks: task_infos
+                .iter()
+                .map(|task_info| {
+                    let metadata = format_task_metadata(task_info);
+                    let name = get_task_name(task_info);
+                    TaskReport {
+                        id: task_info.task.id.clone(),
+                        name: if metadata.is_empty() {
+                            name.to_string()
+                        } else {
+                            format!("{} ({})", name, metadata)
+                        },
+                        task_type: task_info.task.task_type.to_string(),
+                        status: task_info.status.clone(),
+                        duration_secs: task_info
+                            .start_time
+                            .zip(task_info.end_time)
+                            .map(|(start, end)| end.duration_since(start).as_secs_f64()),
+                        error: task_info.error().cloned(),
+                        log_path: self.written_log(&task_info.task.id),
+                    }
+                })
+                .collect(),
+        };
+        match self.reports.write(&report).await {
+            Ok(written) => written
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Failed to write the task run's reports: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Add the run to the history, if it's kept; a run that can't be recorded is warned about
+    async fn record_history(&self, run_id: &str) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let tasks = self.tasks.read().await;
+        let task_infos: Vec<&TaskInfo> = self
+            .queue_order
+            .iter()
+            .filter_map(|id| tasks.get(id))
+            .collect();
+        let first_start = task_infos.iter().filter_map(|info| info.start_time).min();
+        let last_end = task_infos.iter().filter_map(|info| info.end_time).max();
+        let finished_at = chrono::Utc::now();
+        let since_start = first_start.map_or(Duration::ZERO, |start| start.elapsed());
+        let run = RunRecord {
+            run_id: run_id.to_string(),
+            started_at: finished_at - chrono::Duration::from_std(since_start).unwrap_or_default(),
+            finished_at,
+            duration_secs: first_start
+                .zip(last_end)
+                .map_or(0.0, |(start, end)| end.duration_since(start).as_secs_f64()),
+            tasks: task_infos
+                .iter()
+                .map(|task_info| TaskRecord {
+                    task_id: task_info.task.id.clone(),
+                    name: get_task_name(task_info).to_string(),
+                    task_type: task_info.task.task_type.to_string(),
+                    parameters: task_info
+                        .task
+                        .get_command_parameters()
+                        .map(|params| Value::Object(params.clone())),
+                    status: task_info.status.clone(),
+                    duration_secs: task_info
+                        .start_time
+                        .zip(task_info.end_time)
+                        .map(|(start, end)| end.duration_since(start).as_secs_f64()),
+                    error: task_info.error().cloned(),
+                    usage: task_info.usage,
+                })
+                .collect(),
+        };
+        drop(tasks);
+        if let Err(e) = history.record(&run).await {
+            tracing::warn!("Failed to record task run {}: {:#}", run_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::subagent_execution_tool::task_types::TaskType;
+    use crate::recipe::TaskPriority;
+
+    fn tracker(
+        count: usize,
+    ) -> (
+        Arc<TaskExecutionTracker>,
+        mpsc::Receiver<ServerNotification>,
+    ) {
+        let tasks = (0..count)
+            .map(|i| Task {
+                id: format!("task-{}", i),
+                task_type: TaskType::InlineRecipe,
+                payload: Value::Null,
+                depends_on: Vec::new(),
+                timeout_seconds: None,
+                on_timeout: Default::default(),
+                priority: Default::default(),
+            })
+            .collect();
+        let (tx, rx) = mpsc::channel(100);
+        let tracker = TaskExecutionTracker::new(tasks, DisplayMode::MultipleTasksOutput, tx, None);
+        (Arc::new(tracker), rx)
+    }
+
+    fn drain(rx: &mut mpsc::Receiver<ServerNotification>) -> usize {
+        let mut count = 0;
+        while rx.try_recv().is_ok() {
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn test_push_recent_output() {
+        let mut output = String::new();
+        for line in 1..=RECENT_OUTPUT_LINES + 3 {
+            push_recent_output(&mut output, &format!("line {}", line));
+        }
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), RECENT_OUTPUT_LINES);
+        assert_eq!(lines[0], "line 4");
+        assert_eq!(
+            lines.last().copied(),
+            Some(format!("line {}", RECENT_OUTPUT_LINES + 3).as_str())
+        );
+    }
+
+    #[test]
+    fn test_refresh_interval() {
+        assert_eq!(
+            refresh_interval(Duration::from_millis(100)),
+            Duration::from_millis(ACTIVE_REFRESH_INTERVAL_MS)
+        );
+        assert_eq!(
+            refresh_interval(Duration::from_secs(10)),
+            Duration::from_millis(IDLE_REFRESH_INTERVAL_MS)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_changes_share_a_redraw() {
+        let (tracker, mut rx) = tracker(5);
+        tracker.start_ticker();
+        for i in 0..5 {
+            tracker.start_task(&format!("task-{}", i)).await;
+        }
+        assert_eq!(drain(&mut rx), 0);
+
+        sleep(Duration::from_millis(BATCH_WINDOW_MS + 100)).await;
+        assert_eq!(drain(&mut rx), 1);
+    }
+
+    #[tokio::test]
+    async fn test_without_ticker_status_changes_redraw_at_once() {
+        let (tracker, mut rx) = tracker(2);
+        tracker.start_task("task-0").await;
+        tracker.start_task("task-1").await;
+        assert_eq!(drain(&mut rx), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_tasks_have_queue_positions() {
+        let (tracker, mut rx) = tracker(3);
+        tracker.start_task("task-1").await;
+
+        let Some(ServerNotification::LoggingMessageNotification(notification)) = rx.recv().await
+        else {
+            panic!("expected a tasks update");
+        };
+        let positions: HashMap<String, Option<usize>> = notification.params.data["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| {
+                (
+                    task["id"].as_str().unwrap().to_string(),
+                    task["queue_position"].as_u64().map(|p| p as usize),
+                )
+            })
+            .collect();
+        assert_eq!(positions["task-0"], Some(1));
+        assert_eq!(positions["task-1"], None);
+        assert_eq!(positions["task-2"], Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_higher_priorities_are_queued_first() {
+        let tasks = [TaskPriority::Low, TaskPriority::Normal, TaskPriority::High]
+            .into_iter()
+            .enumerate()
+            .map(|(i, priority)| Task {
+                id: format!("task-{}", i),
+                task_type: TaskType::InlineRecipe,
+                payload: Value::Null,
+                depends_on: Vec::new(),
+                timeout_seconds: None,
+                on_timeout: Default::default(),
+                priority,
+            })
+            .collect();
+        let (tx, _rx) = mpsc::channel(100);
+        let tracker = TaskExecutionTracker::new(tasks, DisplayMode::MultipleTasksOutput, tx, None);
+
+        let (_, event_tasks) = tracker.describe(&*tracker.tasks.read().await);
+        let positions: HashMap<&str, (Option<usize>, TaskPriority)> = event_tasks
+            .iter()
+            .map(|task| (task.id.as_str(), (task.queue_position, task.priority)))
+            .collect();
+        assert_eq!(positions["task-2"], (Some(1), TaskPriority::High));
+        assert_eq!(positions["task-1"], (Some(2), TaskPriority::Normal));
+        assert_eq!(positions["task-0"], (Some(3), TaskPriority::Low));
+    }
+
+    #[tokio::test]
+    async fn test_usage_adds_up_per_task_and_in_total() {
+        let (tracker, mut rx) = tracker(3);
+        let usage = TaskUsage {
+            input_tokens: 100,
+            output_tokens: 20,
+            cost_usd: Some(0.5),
+            ..Default::default()
+        };
+        tracker.add_usage("task-0", &usage).await;
+        tracker.add_usage("task-0", &usage).await;
+        tracker
+            .add_usage(
+                "task-1",
+                &TaskUsage {
+                    input_tokens: 50,
+                    output_tokens: 10,
+                    cost_usd: None,
+                    ..Default::default()
+                },
+            )
+            .await;
+        tracker.refresh_display().await;
+
+        let Some(ServerNotification::LoggingMessageNotification(notification)) = rx.recv().await
+        else {
+            panic!("expected a tasks update");
+        };
+        let data = &notification.params.data;
+        assert_eq!(
+            data["stats"]["usage"],
+            serde_json::json!({
+                "input_tokens": 250,
+                "output_tokens": 50,
+                "cost_usd": 1.0,
+                "cost_partial": true
+            })
+        );
+        let usage: HashMap<&str, &Value> = data["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| (task["id"].as_str().unwrap(), &task["usage"]))
+            .collect();
+        assert_eq!(usage["task-0"]["input_tokens"], 200);
+        assert!(usage["task-1"].get("cost_usd").is_none());
+        assert!(usage["task-2"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_reports_are_written_when_the_run_completes() {
+        use crate::agents::subagent_execution_tool::run_report::RunReport;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (tracker, _rx) = tracker(2);
+        let tracker = Arc::try_unwrap(tracker)
+            .ok()
+            .unwrap()
+            .with_reports(ReportPaths {
+                junit: Some(dir.path().join("junit.xml")),
+                json: Some(dir.path().join("report.json")),
+            });
+
+        for (task_id, status) in [
+            ("task-0", TaskStatus::Completed),
+            ("task-1", TaskStatus::Failed),
+        ] {
+            tracker.start_task(task_id).await;
+            tracker
+                .complete_task(
+                    task_id,
+                    TaskResult {
+                        task_id: task_id.to_string(),
+                        status,
+                        data: None,
+                        error: None,
+                        attempts: Vec::new(),
+                        artifacts: Vec::new(),
+                    },
+                )
+                .await;
+        }
+        tracker.send_tasks_complete(Some("run-1".to_string())).await;
+
+        let report: RunReport =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("report.json")).unwrap())
+                .unwrap();
+        assert_eq!(report.run_id.as_deref(), Some("run-1"));
+        let ids: Vec<&str> = report.tasks.iter().map(|task| task.id.as_str()).collect();
+        assert_eq!(ids, ["task-0", "task-1"]);
+        assert!(report.tasks.iter().all(|task| task.duration_secs.is_some()));
+        let junit = std::fs::read_to_string(dir.path().join("junit.xml")).unwrap();
+        assert!(junit.contains(r#"tests="2" failures="1""#));
+    }
+
+    #[tokio::test]
+    async fn test_single_task_runs_write_reports_too() {
+        use crate::agents::subagent_execution_tool::run_report::RunReport;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (tracker, _rx) = tracker(1);
+        let tracker = Arc::try_unwrap(tracker)
+            .ok()
+            .unwrap()
+            .with_reports(ReportPaths {
+                junit: None,
+                json: Some(dir.path().join("report.json")),
+            });
+
+        tracker.start_task("task-0").await;
+        tracker
+            .complete_task(
+                "task-0",
+                TaskResult {
+                    task_id: "task-0".to_string(),
+                    status: TaskStatus::Completed,
+                    data: None,
+                    error: None,
+                    attempts: Vec::new(),
+                    artifacts: Vec::new(),
+                },
+            )
+            .await;
+        tracker.finish_single_task().await;
+
+        let report: RunReport =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("report.json")).unwrap())
+                .unwrap();
+        assert!(report.run_id.is_some());
+        assert_eq!(report.stats.completed, 1);
+        assert_eq!(report.tasks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_runs_are_recorded_in_the_history() {
+        use crate::agents::subagent_execution_tool::run_history::HISTORY_FILE;
+
+        let dir = tempfile::tempdir().unwrap();
+        let history = Arc::new(
+            RunHistory::open(&dir.path().join(HISTORY_FILE))
+                .await
+                .unwrap(),
+        );
+        let (tracker, _rx) = tracker(2);
+        let tracker = Arc::try_unwrap(tracker)
+            .ok()
+            .unwrap()
+            .with_history(Some(history.clone()));
+
+        tracker.start_task("task-0").await;
+        tracker
+            .complete_task(
+                "task-0",
+                TaskResult {
+                    task_id: "task-0".to_string(),
+                    status: TaskStatus::Failed,
+                    data: None,
+                    error: Some("Boom".to_string()),
+                    attempts: Vec::new(),
+                    artifacts: Vec::new(),
+                },
+            )
+            .await;
+        tracker.send_tasks_complete(Some("run-1".to_string())).await;
+
+        let run = history.get("run-1").await.unwrap().unwrap();
+        let tasks: Vec<(&str, &TaskStatus)> = run
+            .tasks
+            .iter()
+            .map(|task| (task.task_id.as_str(), &task.status))
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                ("task-0", &TaskStatus::Failed),
+                ("task-1", &TaskStatus::Pending)
+            ]
+        );
+        assert_eq!(run.tasks[0].error.as_deref(), Some("Boom"));
+        assert!(run.tasks[0].duration_secs.is_some());
+        assert!(run.started_at <= run.finished_at);
+    }
+
+    #[tokio::test]
+    async fn test_progress_events_replace_notifications() {
+        use crate::agents::subagent_execution_tool::progress_events::Target;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.ndjson");
+        let events = ProgressEvents::open(&Target::Path(path.clone()))
+            .await
+            .unwrap();
+        let (tracker, mut rx) = tracker(1);
+        let tracker = Arc::try_unwrap(tracker)
+            .ok()
+            .unwrap()
+            .with_progress_events(Some(Arc::new(events)));
+
+        tracker.start_task("task-0").await;
+        tracker.send_live_output("task-0", "working").await;
+        tracker
+            .complete_task(
+                "task-0",
+                TaskResult {
+                    task_id: "task-0".to_string(),
+                    status: TaskStatus::Completed,
+                    data: None,
+                    error: None,
+                    attempts: Vec::new(),
+                    artifacts: Vec::new(),
+                },
+            )
+            .await;
+        tracker.send_tasks_complete(None).await;
+        assert_eq!(drain(&mut rx), 0);
+
+        let events: Vec<String> = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let event: Value = serde_json::from_str(line).unwrap();
+                event["event"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(
+            events,
+            ["task_started", "task_output", "task_completed", "summary"]
+        );
+    }
+
+    #[test]
+    fn test_remaining_secs() {
+        let mut task_info = TaskInfo {
+            task: Task {
+                id: "task-0".to_string(),
+                task_type: TaskType::InlineRecipe,
+                payload: Value::Null,
+                depends_on: Vec::new(),
+                timeout_seconds: Some(60),
+                on_timeout: Default::default(),
+                priority: Default::default(),
+            },
+            status: TaskStatus::Pending,
+            start_time: None,
+            end_time: None,
+            result: None,
+            current_output: String::new(),
+            attempt: 1,
+            stopped_by_policy: false,
+            usage: TaskUsage::default(),
+            container_id: None,
+            host: None,
+            cached: false,
+        };
+        let start = Instant::now();
+        assert_eq!(remaining_secs(&task_info, start), None);
+
+        task_info.status = TaskStatus::Running;
+        task_info.start_time = Some(start);
+        let remaining = remaining_secs(&task_info, start + Duration::from_secs(45)).unwrap();
+        assert!((remaining - 15.0).abs() < 0.01);
+        let later = start + Duration::from_secs(90);
+        assert_eq!(remaining_secs(&task_info, later), Some(0.0));
+
+        task_info.task.timeout_seconds = None;
+        assert_eq!(remaining_secs(&task_info, later), None);
+    }
 }
@@ -0,0 +1,165 @@
+//! Everything a task prints, kept in `<session_dir>/tasks/<task_id>.log`.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::agents::subagent_execution_tool::utils::{prune_files, PruneReport};
+use crate::session::session_manager::ensure_session_dir;
+
+/// The directory task logs are written to, made if it isn't there yet
+pub fn log_dir() -> Result<PathBuf> {
+    let dir = ensure_session_dir()?.join("tasks");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Where the log of the task `task_id` is kept in `dir`
+pub fn log_path(dir: &Path, task_id: &str) -> PathBuf {
+    dir.join(format!("{}.log", task_id))
+}
+
+/// Logs written to within this long survive a prune
+pub const DEFAULT_LOG_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Remove the logs in `dir` last written to more than `retention` ago; the logs of tasks still
+/// running are written to as they go, so they stay. With `dry_run` nothing is deleted, only
+/// reported.
+pub fn prune(dir: &Path, retention: Duration, dry_run: bool) -> Result<PruneReport> {
+    prune_files(dir, &["log"], retention, dry_run)
+}
+
+/// The logs the tasks of a run write to. A log that can't be written is warned about and left
+/// out; the task runs on either way.
+pub struct TaskLogs {
+    dir: Option<PathBuf>,
+    files: Mutex<HashMap<String, File>>,
+}
+
+impl Default for TaskLogs {
+    fn default() -> Self {
+        match log_dir() {
+            Ok(dir) => Self::in_dir(dir),
+            Err(e) => {
+                tracing::warn!("Task output won't be logged: {}", e);
+                Self {
+                    dir: None,
+                    files: Mutex::new(HashMap::new()),
+                }
+            }
+        }
+    }
+}
+
+impl TaskLogs {
+    pub fn in_dir(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn path(&self, task_id: &str) -> Option<PathBuf> {
+        self.dir.as_deref().map(|dir| log_path(dir, task_id))
+    }
+
+    /// Add `line` to the log of `task_id`, flushed so followers see it right away
+    pub async fn append(&self, task_id: &str, line: &str) {
+        let Some(path) = self.path(task_id) else {
+            return;
+        };
+        let mut files = self.files.lock().await;
+        let file = match files.entry(task_id.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                match OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .await
+                {
+                    Ok(file) => entry.insert(file),
+                    Err(e) => {
+                        tracing::warn!("Failed to open {}: {}", path.display(), e);
+                        return;
+                    }
+                }
+            }
+        };
+        let written = match file.write_all(format!("{}\n", line).as_bytes()).await {
+            Ok(()) => file.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            tracing::warn!("Failed to write to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = TaskLogs::in_dir(dir.path().to_path_buf());
+
+        logs.append("task-1", "fetching").await;
+        logs.append("task-2", "linting").await;
+        logs.append("task-1", "done").await;
+
+        let path = logs.path("task-1").unwrap();
+        assert_eq!(path, dir.path().join("task-1.log"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "fetching\ndone\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("task-2.log")).unwrap(),
+            "linting\n"
+        );
+    }
+    #[test]
+    fn test_prune_removes_only_old_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = log_path(dir.path(), "old");
+        let fresh = log_path(dir.path(), "fresh");
+        let other = dir.path().join("notes.txt");
+        for path in [&old, &fresh, &other] {
+            std::fs::write(path, "output").unwrap();
+        }
+        for path in [&old, &other] {
+            std::fs::File::options()
+                .append(true)
+                .open(path)
+                .unwrap()
+                .set_modified(std::time::SystemTime::now() - 2 * DEFAULT_LOG_RETENTION)
+                .unwrap();
+        }
+
+        let dry = prune(dir.path(), DEFAULT_LOG_RETENTION, true).unwrap();
+        assert_eq!(dry.removed, 1);
+        assert!(old.exists());
+
+        let report = prune(dir.path(), DEFAULT_LOG_RETENTION, false).unwrap();
+        assert_eq!(
+            report,
+            PruneReport {
+                removed: 1,
+                freed_bytes: 6,
+                kept: 1,
+            }
+        );
+        assert!(!old.exists());
+        assert!(fresh.exists());
+        assert!(other.exists());
+    }
+}
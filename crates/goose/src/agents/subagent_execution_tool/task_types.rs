@@ -1,12 +1,17 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+use crate::agents::subagent_execution_tool::artifacts::artifact_lock;
+use crate::agents::subagent_execution_tool::resources::ResourceScheduler;
 use crate::agents::subagent_execution_tool::task_execution_tracker::TaskExecutionTracker;
+use crate::agents::subagent_execution_tool::task_usage::TaskUsage;
+use crate::recipe::{TaskPriority, TaskResources, TaskRetryPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -32,11 +37,102 @@ impl fmt::Display for TaskType {
     }
 }
 
+/// What happens to a task still running when its time is up
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OnTimeout {
+    /// Stop it right away
+    #[default]
+    Kill,
+    /// Ask the subagent of an inline recipe to stop and report what it has, and stop it
+    /// [`WRAP_UP_GRACE_SECONDS`] later if it hasn't. Sub-recipes are stopped right away.
+    WrapUp,
+}
+
+/// What a parallel run does once its tasks start failing. Tasks already running finish either
+/// way; the policy only decides whether more are started.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(try_from = "String", into = "String")]
+pub enum FailurePolicy {
+    /// Run every task, whatever fails
+    #[default]
+    Continue,
+    /// Start no more tasks after the first failure
+    FailFast,
+    /// Start no more tasks after this many failures
+    Threshold(usize),
+}
+
+impl FailurePolicy {
+    /// A policy as the execute tool takes it: `continue`, `fail_fast` or `threshold:N`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "continue" => Ok(Self::Continue),
+            "fail_fast" => Ok(Self::FailFast),
+            other => match other.strip_prefix("threshold:").map(str::parse::<usize>) {
+                Some(Ok(failures)) if failures > 0 => Ok(Self::Threshold(failures)),
+                _ => Err(format!(
+                    "on_failure must be continue, fail_fast or threshold:N with N at least 1, not '{}'",
+                    value
+                )),
+            },
+        }
+    }
+
+    /// Whether `failures` failed tasks stop the run from starting more
+    pub fn stops_after(&self, failures: usize) -> bool {
+        match self {
+            Self::Continue => false,
+            Self::FailFast => failures >= 1,
+            Self::Threshold(threshold) => failures >= *threshold,
+        }
+    }
+}
+
+impl fmt::Display for FailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Continue => write!(f, "continue"),
+            Self::FailFast => write!(f, "fail_fast"),
+            Self::Threshold(failures) => write!(f, "threshold:{}", failures),
+        }
+    }
+}
+
+impl TryFrom<String> for FailurePolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<FailurePolicy> for String {
+    fn from(policy: FailurePolicy) -> Self {
+        policy.to_string()
+    }
+}
+
+pub const WRAP_UP_GRACE_SECONDS: u64 = 60;
+
+pub const WRAP_UP_PROMPT: &str = "You are out of time. Stop working on the task and reply with a summary of what you did and found so far, and what is left to do.";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub task_type: TaskType,
     pub payload: Value,
+    /// Ids of the tasks that must complete before this one starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// How long the task may run, retries included, before it fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub on_timeout: OnTimeout,
+    /// Which of the tasks waiting for a slot to run starts first
+    #[serde(default)]
+    pub priority: TaskPriority,
 }
 
 impl Task {
@@ -58,6 +154,52 @@ impl Task {
             .unwrap_or_default()
     }
 
+    /// What the task declared it needs, from its sub-recipe or the payload of an inline recipe,
+    /// and a lock for each artifact it leaves, so tasks leaving the same ones never run at once
+    pub fn get_resources(&self) -> TaskResources {
+        let mut resources: TaskResources = self
+            .get_sub_recipe()
+            .and_then(|sr| sr.get("resources"))
+            .or_else(|| self.payload.get("resources"))
+            .filter(|resources| !resources.is_null())
+            .and_then(|resources| serde_json::from_value(resources.clone()).ok())
+            .unwrap_or_default();
+        resources.locks.extend(
+            self.get_artifacts()
+                .iter()
+                .filter_map(|declared| artifact_lock(declared)),
+        );
+        resources
+    }
+
+    /// How the task is retried when it fails, if it is
+    pub fn get_retry_policy(&self) -> Option<TaskRetryPolicy> {
+        self.get_sub_recipe()
+            .and_then(|sr| sr.get("retry"))
+            .or_else(|| self.payload.get("retry"))
+            .filter(|retry| !retry.is_null())
+            .and_then(|retry| serde_json::from_value(retry.clone()).ok())
+    }
+
+    /// The files and directories the task leaves behind for the steps after it
+    pub fn get_artifacts(&self) -> Vec<String> {
+        self.get_sub_recipe()
+            .and_then(|sr| sr.get("artifacts"))
+            .or_else(|| self.payload.get("artifacts"))
+            .filter(|artifacts| !artifacts.is_null())
+            .and_then(|artifacts| serde_json::from_value(artifacts.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether the task asked for its result to be cached, and taken from the cache
+    pub fn get_cache(&self) -> bool {
+        self.get_sub_recipe()
+            .and_then(|sr| sr.get("cache"))
+            .or_else(|| self.payload.get("cache"))
+            .and_then(|cache| cache.as_bool())
+            .unwrap_or(false)
+    }
+
     pub fn get_sub_recipe_name(&self) -> Option<&str> {
         self.get_sub_recipe()
             .and_then(|sr| sr.get("name"))
@@ -79,14 +221,34 @@ pub struct TaskResult {
     pub data: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Every run of a task with a retry policy, the last one included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<TaskAttempt>,
+    /// The copies of the artifacts the task left, once it completed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAttempt {
+    pub attempt: u32,
+    pub status: TaskStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TaskStatus {
     Pending,
     Running,
     Completed,
     Failed,
+    /// Stopped by `cancel_task` before it finished, or never started because the run's
+    /// [`FailurePolicy`] stopped it
+    Cancelled,
+    /// Never run, because a task it depends on didn't complete
+    Skipped,
 }
 
 impl std::fmt::Display for TaskStatus {
@@ -96,6 +258,8 @@ impl std::fmt::Display for TaskStatus {
             TaskStatus::Running => write!(f, "Running"),
             TaskStatus::Completed => write!(f, "Completed"),
             TaskStatus::Failed => write!(f, "Failed"),
+            TaskStatus::Cancelled => write!(f, "Cancelled"),
+            TaskStatus::Skipped => write!(f, "Skipped"),
         }
     }
 }
@@ -108,6 +272,18 @@ pub struct TaskInfo {
     pub end_time: Option<tokio::time::Instant>,
     pub result: Option<TaskResult>,
     pub current_output: String,
+    /// The run under way, or the last one, counting from 1
+    pub attempt: u32,
+    /// Never started, because the run's [`FailurePolicy`] stopped it
+    pub stopped_by_policy: bool,
+    /// The tokens it spent so far, across its attempts
+    pub usage: TaskUsage,
+    /// The container it runs in, when tasks run in a sandbox
+    pub container_id: Option<String>,
+    /// The worker host it runs on, when tasks are spread across hosts
+    pub host: Option<String>,
+    /// Its result came from the result cache rather than a run
+    pub cached: bool,
 }
 
 impl TaskInfo {
@@ -126,6 +302,12 @@ pub struct SharedState {
     pub active_workers: Arc<AtomicUsize>,
     pub task_execution_tracker: Arc<TaskExecutionTracker>,
     pub cancellation_token: CancellationToken,
+    pub resources: Arc<ResourceScheduler>,
+    /// Each task's own token, a child of `cancellation_token`
+    pub task_tokens: HashMap<String, CancellationToken>,
+    pub failure_policy: FailurePolicy,
+    /// Cancelled once `failure_policy` says to start no more tasks
+    pub stop_starting: CancellationToken,
 }
 
 impl SharedState {
@@ -136,6 +318,13 @@ impl SharedState {
     pub fn decrement_active_workers(&self) {
         self.active_workers.fetch_sub(1, Ordering::SeqCst);
     }
+
+    pub fn task_token(&self, task_id: &str) -> CancellationToken {
+        self.task_tokens
+            .get(task_id)
+            .cloned()
+            .unwrap_or_else(|| self.cancellation_token.child_token())
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -143,6 +332,8 @@ pub struct ExecutionStats {
     pub total_tasks: usize,
     pub completed: usize,
     pub failed: usize,
+    pub cancelled: usize,
+    pub skipped: usize,
     pub execution_time_ms: u128,
 }
 
@@ -151,4 +342,7 @@ pub struct ExecutionResponse {
     pub status: String,
     pub results: Vec<TaskResult>,
     pub stats: ExecutionStats,
+    /// The parallel run these results belong to, for resuming it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
 }
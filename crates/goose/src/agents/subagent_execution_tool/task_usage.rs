@@ -0,0 +1,172 @@
+//! The tokens each task spends, and what they are estimated to cost.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::providers::base::{Provider, ProviderName, ProviderUsage};
+use crate::providers::pricing::find_model_pricing;
+
+pub const TASK_USAGE_FILE_KEY: &str = "GOOSE_TASK_USAGE_FILE";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// In US dollars, from the prices of the models that answered; missing when none of them
+    /// has a known price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Whether `cost_usd` leaves out tokens spent with models of no known price
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cost_partial: bool,
+}
+
+/// The name of the provider `provider` answered `model` with
+fn provider_name(provider: &dyn Provider, model: &str) -> String {
+    match provider.as_lead_worker() {
+        Some(lead_worker) => lead_worker.get_provider_name_for(model),
+        None => provider.provider_name(),
+    }
+}
+
+impl TaskUsage {
+    /// What one response of `provider` spent, priced for the provider and model that answered
+    pub async fn of(provider: &dyn Provider, usage: &ProviderUsage) -> Self {
+        let count = |tokens: Option<i32>| tokens.unwrap_or(0).max(0) as usize;
+        let input = count(usage.usage.input_tokens);
+        let output = count(usage.usage.output_tokens);
+        let cost_usd = find_model_pricing(&provider_name(provider, &usage.model), &usage.model)
+            .await
+            .map(|pricing| pricing.cost(input, count(usage.usage.cached_input_tokens), output));
+        Self {
+            input_tokens: input as u64,
+            output_tokens: output as u64,
+            cost_usd,
+            cost_partial: false,
+        }
+    }
+
+    /// Whether tokens were spent that have no price
+    fn is_unpriced(&self) -> bool {
+        self.cost_usd.is_none() && (self.input_tokens > 0 || self.output_tokens > 0)
+    }
+
+    pub fn add(&mut self, other: &TaskUsage) {
+        self.cost_partial = self.cost_partial
+            || other.cost_partial
+            || match (self.cost_usd, other.cost_usd) {
+                (Some(_), None) => other.is_unpriced(),
+                (None, Some(_)) => self.is_unpriced(),
+                _ => false,
+            };
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost_usd = match (self.cost_usd, other.cost_usd) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens == 0 && self.output_tokens == 0 && self.cost_usd.is_none()
+    }
+
+    /// What `usages` add up to, unless none of them spent anything
+    pub fn total<'a>(usages: impl IntoIterator<Item = &'a TaskUsage>) -> Option<TaskUsage> {
+        let mut total = TaskUsage::default();
+        for usage in usages {
+            total.add(usage);
+        }
+        (!total.is_empty()).then_some(total)
+    }
+}
+
+/// What the `goose run` of a task wrote to `path`, if it wrote anything
+pub async fn read_usage_file(path: &Path) -> Option<TaskUsage> {
+    let contents = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// Add `usage`, spent with `provider`, to the file `GOOSE_TASK_USAGE_FILE` names, when goose runs
+/// as a sub-recipe task
+pub async fn report_to_parent(provider: &dyn Provider, usage: &ProviderUsage) {
+    let Ok(path) = std::env::var(TASK_USAGE_FILE_KEY) else {
+        return;
+    };
+    let path = Path::new(&path);
+    if let Err(e) = add_to_usage_file(path, &TaskUsage::of(provider, usage).await).await {
+        tracing::warn!("Failed to report token usage to {}: {}", path.display(), e);
+    }
+}
+
+async fn add_to_usage_file(path: &Path, usage: &TaskUsage) -> Result<()> {
+    let mut total = read_usage_file(path).await.unwrap_or_default();
+    total.add(usage);
+    tokio::fs::write(path, serde_json::to_vec(&total)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: u64, output_tokens: u64, cost_usd: Option<f64>) -> TaskUsage {
+        TaskUsage {
+            input_tokens,
+            output_tokens,
+            cost_usd,
+            cost_partial: false,
+        }
+    }
+
+    fn partial(usage: TaskUsage) -> TaskUsage {
+        TaskUsage {
+            cost_partial: true,
+            ..usage
+        }
+    }
+
+    #[test]
+    fn test_total() {
+        assert_eq!(TaskUsage::total(std::iter::empty()), None);
+        assert_eq!(TaskUsage::total(&[TaskUsage::default()]), None);
+        assert_eq!(
+            TaskUsage::total(&[
+                usage(1000, 200, Some(0.5)),
+                usage(500, 100, None),
+                usage(300, 50, Some(0.25)),
+            ]),
+            Some(partial(usage(1800, 350, Some(0.75))))
+        );
+        assert_eq!(
+            TaskUsage::total(&[usage(1000, 200, Some(0.5)), usage(300, 50, Some(0.25))]),
+            Some(usage(1300, 250, Some(0.75)))
+        );
+        // Nothing spent without a price
+        assert_eq!(
+            TaskUsage::total(&[usage(1000, 200, Some(0.5)), TaskUsage::default()]),
+            Some(usage(1000, 200, Some(0.5)))
+        );
+        assert_eq!(
+            TaskUsage::total(&[usage(10, 5, None)]),
+            Some(usage(10, 5, None))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.json");
+        assert_eq!(read_usage_file(&path).await, None);
+
+        std::fs::write(&path, r#"{"input_tokens":42,"output_tokens":7}"#).unwrap();
+        assert_eq!(read_usage_file(&path).await, Some(usage(42, 7, None)));
+
+        add_to_usage_file(&path, &usage(8, 3, Some(0.5)))
+            .await
+            .unwrap();
+        assert_eq!(read_usage_file(&path).await, Some(usage(50, 10, Some(0.5))));
+    }
+}
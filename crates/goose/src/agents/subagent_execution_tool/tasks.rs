@@ -1,41 +1,387 @@
+use regex::Regex;
 use serde_json::Value;
+use std::path::Path;
 use std::process::Stdio;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
 use tokio_util::sync::CancellationToken;
 
+use crate::agents::subagent_execution_tool::progress_events::{TASK_EVENTS_KEY, TASK_EVENTS_OFF};
+use crate::agents::subagent_execution_tool::result_cache::ResultCache;
+use crate::agents::subagent_execution_tool::run_report::{JSON_REPORT_KEY, JUNIT_REPORT_KEY};
+use crate::agents::subagent_execution_tool::sandbox::{
+    Container, Sandbox, SANDBOX_KEY, SANDBOX_OFF,
+};
+use crate::agents::subagent_execution_tool::ssh_workers::{
+    RemoteTask, SshPool, SSH_WORKERS_KEY, SSH_WORKERS_OFF,
+};
 use crate::agents::subagent_execution_tool::task_execution_tracker::TaskExecutionTracker;
-use crate::agents::subagent_execution_tool::task_types::{Task, TaskResult, TaskStatus, TaskType};
+use crate::agents::subagent_execution_tool::task_types::{
+    FailurePolicy, OnTimeout, Task, TaskAttempt, TaskResult, TaskStatus, TaskType,
+    WRAP_UP_GRACE_SECONDS, WRAP_UP_PROMPT,
+};
+use crate::agents::subagent_execution_tool::task_usage::{read_usage_file, TASK_USAGE_FILE_KEY};
 use crate::agents::subagent_execution_tool::utils::strip_ansi_codes;
-use crate::agents::subagent_task_config::TaskConfig;
+use crate::agents::subagent_execution_tool::webhooks::TASK_WEBHOOKS_KEY;
+use crate::agents::subagent_task_config::{TaskConfig, UsageReport, WrapUp};
+use crate::offline::require_network;
+use crate::recipe::TaskRetryPolicy;
 
+/// The cache `task`'s result is kept in, and its key there, when it is cached
+async fn cache_entry(
+    task: &Task,
+    task_execution_tracker: &TaskExecutionTracker,
+    task_config: &TaskConfig,
+) -> Option<(Arc<ResultCache>, String)> {
+    let cache = task_execution_tracker.result_cache()?.clone();
+    let provider = task_config.provider().map(|provider| provider.as_ref());
+    let key = ResultCache::key(task, provider).await?;
+    Some((cache, key))
+}
+
+/// The result a task like `task` completed with earlier, if it is cached and there is one; the
+/// task is then shown as cached, without ever starting
+pub async fn cached_result(
+    task: &Task,
+    task_execution_tracker: &TaskExecutionTracker,
+    task_config: &TaskConfig,
+) -> Option<TaskResult> {
+    let (cache, key) = cache_entry(task, task_execution_tracker, task_config).await?;
+    let cached = cache.get(&key).await?;
+    task_execution_tracker.mark_cached(&task.id).await;
+    Some(TaskResult {
+        task_id: task.id.clone(),
+        status: TaskStatus::Completed,
+        data: Some(cached.data),
+        error: None,
+        attempts: Vec::new(),
+        artifacts: Vec::new(),
+    })
+}
+
+/// Run `task`, keeping its result in the cache when it is cached and completes. Whether a result
+/// is kept already is for the caller to look up first, with [`cached_result`].
 pub async fn process_task(
     task: &Task,
     task_execution_tracker: Arc<TaskExecutionTracker>,
     task_config: TaskConfig,
     cancellation_token: CancellationToken,
 ) -> TaskResult {
-    match get_task_result(
-        task.clone(),
-        task_execution_tracker,
+    // The key is taken before the task runs, from the inputs it ran with
+    let cache_entry = cache_entry(task, &task_execution_tracker, &task_config).await;
+    let result = run_task(
+        task,
+        task_execution_tracker.clone(),
         task_config,
         cancellation_token,
     )
-    .await
+    .await;
+    let result = task_execution_tracker.collect_artifacts(task, result).await;
+    if let (Some((cache, key)), TaskStatus::Completed, Some(data)) =
+        (&cache_entry, &result.status, &result.data)
     {
-        Ok(data) => TaskResult {
-            task_id: task.id.clone(),
-            status: TaskStatus::Completed,
-            data: Some(data),
-            error: None,
-        },
-        Err(error) => TaskResult {
-            task_id: task.id.clone(),
-            status: TaskStatus::Failed,
-            data: None,
-            error: Some(error),
-        },
+        let name = task.get_sub_recipe_name().unwrap_or(&task.id);
+        if let Err(e) = cache.put(key, name, data).await {
+            tracing::warn!("{:#}", e);
+        }
+    }
+    result
+}
+
+/// Run `task` within its timeout, if it has one
+async fn run_task(
+    task: &Task,
+    task_execution_tracker: Arc<TaskExecutionTracker>,
+    mut task_config: TaskConfig,
+    cancellation_token: CancellationToken,
+) -> TaskResult {
+    if cancellation_token.is_cancelled() {
+        return cancelled_result(task);
+    }
+    let Some(timeout) = task.timeout_seconds.map(Duration::from_secs) else {
+        return run_attempts(
+            task,
+            task_execution_tracker,
+            task_config,
+            cancellation_token,
+        )
+        .await;
+    };
+
+    // Stopping the run when time is up, rather than the task's own token, keeps it from
+    // counting as cancelled
+    let run_token = cancellation_token.child_token();
+    let wrap_up = (task.on_timeout == OnTimeout::WrapUp
+        && task.task_type == TaskType::InlineRecipe)
+        .then(|| WrapUp {
+            prompt: WRAP_UP_PROMPT.to_string(),
+            signal: CancellationToken::new(),
+        });
+    task_config.wrap_up = wrap_up.clone();
+    let run = run_attempts(
+        task,
+        task_execution_tracker.clone(),
+        task_config,
+        run_token.clone(),
+    );
+    tokio::pin!(run);
+    tokio::select! {
+        result = &mut run => return result,
+        _ = tokio::time::sleep(timeout) => {}
+    }
+
+    let mut wrapped_up = None;
+    if let Some(wrap_up) = wrap_up {
+        task_execution_tracker
+            .send_live_output(
+                &task.id,
+                &format!(
+                    "Out of time after {}s, asking it to wrap up",
+                    timeout.as_secs()
+                ),
+            )
+            .await;
+        wrap_up.signal.cancel();
+        let grace = Duration::from_secs(WRAP_UP_GRACE_SECONDS);
+        wrapped_up = tokio::time::timeout(grace, &mut run).await.ok();
+    }
+    let result = match wrapped_up {
+        Some(result) => result,
+        None => {
+            run_token.cancel();
+            run.await
+        }
+    };
+    if cancellation_token.is_cancelled() {
+        return TaskResult {
+            attempts: result.attempts,
+            ..cancelled_result(task)
+        };
+    }
+    timed_out_result(task, timeout, result)
+}
+
+/// Run `task` until it completes, fails for good or is cancelled, retrying it as its policy
+/// says
+async fn run_attempts(
+    task: &Task,
+    task_execution_tracker: Arc<TaskExecutionTracker>,
+    task_config: TaskConfig,
+    cancellation_token: CancellationToken,
+) -> TaskResult {
+    let policy = task.get_retry_policy();
+    let max_attempts = policy
+        .as_ref()
+        .map_or(1, |policy| policy.max_attempts.max(1));
+    let mut attempts = Vec::new();
+    let mut attempt = 1;
+    loop {
+        let started = Instant::now();
+        let restarts = task_execution_tracker.restarts().clone();
+        let attempt_token = restarts.begin(&task.id, &cancellation_token);
+        let outcome = get_task_result(
+            task.clone(),
+            task_execution_tracker.clone(),
+            task_config.clone(),
+            attempt_token.clone(),
+        )
+        .await;
+        restarts.end(&task.id);
+        // Only this attempt was stopped, for the task to start over
+        let restarted = attempt_token.is_cancelled() && !cancellation_token.is_cancelled();
+        // Whatever came of a task stopped halfway, it didn't finish
+        let status = match &outcome {
+            _ if attempt_token.is_cancelled() => TaskStatus::Cancelled,
+            Ok(_) => TaskStatus::Completed,
+            Err(_) => TaskStatus::Failed,
+        };
+        if policy.is_some() {
+            attempts.push(TaskAttempt {
+                attempt,
+                status: status.clone(),
+                error: outcome.as_ref().err().cloned(),
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        if restarted {
+            attempt += 1;
+            task_execution_tracker
+                .send_live_output(&task.id, "Starting over, as asked")
+                .await;
+            task_execution_tracker.retry_task(&task.id, attempt).await;
+            continue;
+        }
+
+        let error = match (status, outcome) {
+            (TaskStatus::Completed, Ok(data)) => {
+                return TaskResult {
+                    task_id: task.id.clone(),
+                    status: TaskStatus::Completed,
+                    data: Some(data),
+                    error: None,
+                    attempts,
+                    artifacts: Vec::new(),
+                }
+            }
+            (TaskStatus::Failed, Err(error)) => error,
+            _ => {
+                return TaskResult {
+                    attempts,
+                    ..cancelled_result(task)
+                }
+            }
+        };
+        // A run that failed while wrapping up isn't worth starting over
+        let wrapping_up = task_config
+            .wrap_up
+            .as_ref()
+            .is_some_and(|wrap_up| wrap_up.signal.is_cancelled());
+        let retry = policy.as_ref().filter(|policy| {
+            attempt < max_attempts && !wrapping_up && should_retry(policy, &error)
+        });
+        let Some(policy) = retry else {
+            return TaskResult {
+                task_id: task.id.clone(),
+                status: TaskStatus::Failed,
+                data: None,
+                error: Some(error),
+                attempts,
+                artifacts: Vec::new(),
+            };
+        };
+
+        let wait = retry_delay(policy, attempt);
+        task_execution_tracker
+            .send_live_output(
+                &task.id,
+                &format!(
+                    "Attempt {} of {} failed, retrying in {}s: {}",
+                    attempt,
+                    max_attempts,
+                    wait.as_secs(),
+                    error.lines().next().unwrap_or_default()
+                ),
+            )
+            .await;
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = cancellation_token.cancelled() => {
+                return TaskResult {
+                    attempts,
+                    ..cancelled_result(task)
+                };
+            }
+        }
+        attempt += 1;
+        task_execution_tracker.retry_task(&task.id, attempt).await;
+    }
+}
+
+pub fn cancelled_result(task: &Task) -> TaskResult {
+    TaskResult {
+        task_id: task.id.clone(),
+        status: TaskStatus::Cancelled,
+        data: None,
+        error: Some("Task cancelled".to_string()),
+        attempts: Vec::new(),
+        artifacts: Vec::new(),
+    }
+}
+
+/// `task` never started, because the run's failure policy stopped it
+pub fn stopped_result(task: &Task, policy: FailurePolicy) -> TaskResult {
+    TaskResult {
+        task_id: task.id.clone(),
+        status: TaskStatus::Cancelled,
+        data: None,
+        error: Some(format!(
+            "Not started: the {} failure policy stopped the run",
+            policy
+        )),
+        attempts: Vec::new(),
+        artifacts: Vec::new(),
+    }
+}
+
+/// `task` failed for running out of `timeout`. What it came up with while wrapping up is kept.
+fn timed_out_result(task: &Task, timeout: Duration, run: TaskResult) -> TaskResult {
+    TaskResult {
+        task_id: task.id.clone(),
+        status: TaskStatus::Failed,
+        data: run.data,
+        error: Some(format!("Task timed out after {}s", timeout.as_secs())),
+        attempts: run.attempts,
+        artifacts: Vec::new(),
+    }
+}
+
+/// Whether `error` is one `policy` retries. Recipes with a pattern that isn't a valid regular
+/// expression don't load, so such a pattern never matches here.
+fn should_retry(policy: &TaskRetryPolicy, error: &str) -> bool {
+    policy.retry_on.is_empty()
+        || policy
+            .retry_on
+            .iter()
+            .any(|pattern| Regex::new(pattern).is_ok_and(|re| re.is_match(error)))
+}
+
+/// How long to wait after the `attempt`th run failed
+fn retry_delay(policy: &TaskRetryPolicy, attempt: u32) -> Duration {
+    let seconds = policy
+        .backoff_seconds
+        .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+        .min(policy.max_backoff_seconds);
+    Duration::from_secs(seconds)
+}
+
+/// Where a sub-recipe task's command runs, when it doesn't run on this machine as it is
+enum Placement {
+    Container(Container),
+    Remote(RemoteTask),
+}
+
+impl Placement {
+    /// Where `task` goes: to a worker host when there are any, once one is free, or else into
+    /// a container when there's a sandbox. Worker hosts run no containers, so having both
+    /// is refused rather than running tasks unsandboxed; in offline mode, so are worker hosts.
+    async fn of(
+        task: &Task,
+        cancellation_token: &CancellationToken,
+    ) -> Result<Option<Self>, String> {
+        let pool = SshPool::global().await;
+        Self::choose(task, pool, Sandbox::from_config(), cancellation_token).await
+    }
+
+    async fn choose(
+        task: &Task,
+        pool: Option<Arc<SshPool>>,
+        sandbox: Option<Sandbox>,
+        cancellation_token: &CancellationToken,
+    ) -> Result<Option<Self>, String> {
+        if let Some(pool) = pool {
+            require_network(format!("Running tasks on {} hosts", SSH_WORKERS_KEY))
+                .map_err(|e| e.to_string())?;
+            if sandbox.is_some() {
+                return Err(format!(
+                    "Tasks on {} hosts don't run in containers, so they don't run while {} is \
+                     set too; turn one of them off",
+                    SSH_WORKERS_KEY, SANDBOX_KEY
+                ));
+            }
+            let lease = tokio::select! {
+                lease = pool.acquire() => lease,
+                _ = cancellation_token.cancelled() => {
+                    return Err("Task cancelled".to_string());
+                }
+            };
+            return Ok(Some(Self::Remote(RemoteTask::new(lease, &task.id))));
+        }
+        sandbox
+            .map(|sandbox| Container::new(sandbox, &task.id).map(Self::Container))
+            .transpose()
+            .map_err(|e| format!("Failed to set up the task's container: {:#}", e))
     }
 }
 
@@ -47,18 +393,86 @@ async fn get_task_result(
 ) -> Result<Value, String> {
     match task.task_type {
         TaskType::InlineRecipe => {
+            if Sandbox::from_config().is_some() {
+                return Err(format!(
+                    "Inline tasks run in goose itself, where no container holds their shell \
+                     commands and file edits, so they don't run while {} is set; set it to {} \
+                     to run them on the host",
+                    SANDBOX_KEY, SANDBOX_OFF
+                ));
+            }
+            let task_config = TaskConfig {
+                usage: Some(UsageReport {
+                    task_id: task.id.clone(),
+                    tracker: task_execution_tracker,
+                }),
+                ..task_config
+            };
             handle_inline_recipe_task(task, task_config, cancellation_token).await
         }
         TaskType::SubRecipe => {
-            let (command, output_identifier) = build_command(&task)?;
-            let (stdout_output, stderr_output, success) = run_command(
+            let (mut command, output_identifier) = build_command(&task)?;
+            let mut placement = Placement::of(&task, &cancellation_token).await?;
+            // The sub-recipe's run adds what it spends to this file, out of reach of other hosts
+            let usage_file = tempfile::NamedTempFile::new()
+                .inspect_err(|e| {
+                    tracing::warn!("Task {}'s tokens won't be counted: {}", task.id, e)
+                })
+                .ok()
+                .filter(|_| !matches!(placement, Some(Placement::Remote(_))));
+            if let Some(usage_file) = &usage_file {
+                command.env(TASK_USAGE_FILE_KEY, usage_file.path());
+            }
+            match &mut placement {
+                Some(Placement::Container(container)) => {
+                    let recipe_dir = task
+                        .get_sub_recipe_path()
+                        .map(Path::new)
+                        .filter(|path| path.is_absolute())
+                        .and_then(Path::parent);
+                    let shared: Vec<&Path> = recipe_dir.into_iter().collect();
+                    let writable: Vec<&Path> = usage_file.iter().map(|file| file.path()).collect();
+                    let workdir = std::env::current_dir()
+                        .map_err(|e| format!("Failed to read the working directory: {}", e))?;
+                    container
+                        .copy_workdir(&workdir)
+                        .await
+                        .map_err(|e| format!("Failed to set up the task's container: {:#}", e))?;
+                    command = container
+                        .wrap(&command, &shared, &writable)
+                        .map_err(|e| format!("Failed to set up the task's container: {:#}", e))?;
+                }
+                Some(Placement::Remote(remote)) => {
+                    let host = remote.host().host.clone();
+                    command = remote
+                        .wrap(&command)
+                        .map_err(|e| format!("Failed to send the task to {}: {:#}", host, e))?;
+                    task_execution_tracker.set_host(&task.id, host).await;
+                }
+                None => {}
+            }
+            let outcome = run_command(
                 command,
                 &output_identifier,
                 &task.id,
-                task_execution_tracker,
+                placement.as_ref(),
+                task_execution_tracker.clone(),
                 cancellation_token,
             )
-            .await?;
+            .await;
+            if let Some(usage_file) = &usage_file {
+                if let Some(usage) = read_usage_file(usage_file.path()).await {
+                    task_execution_tracker.add_usage(&task.id, &usage).await;
+                }
+            }
+            if let Some(Placement::Container(container)) = &mut placement {
+                if let Some(workspace) = container.take_workspace() {
+                    task_execution_tracker
+                        .set_workspace(&task.id, workspace)
+                        .await;
+                }
+            }
+            let (stdout_output, stderr_output, success) = outcome?;
 
             if success {
                 process_output(stdout_output)
@@ -137,7 +551,17 @@ fn build_command(task: &Task) -> Result<(Command, String), String> {
         .arg("run")
         .arg("--recipe")
         .arg(path)
-        .arg("--no-session");
+        .arg("--no-session")
+        // The sub-recipe's progress reaches the stream as this task's output
+        .env(TASK_EVENTS_KEY, TASK_EVENTS_OFF)
+        // and its own task runs don't write over this run's reports
+        .env(JUNIT_REPORT_KEY, "")
+        .env(JSON_REPORT_KEY, "")
+        // nor tell this run's webhooks about them
+        .env(TASK_WEBHOOKS_KEY, "[]")
+        // and they run wherever it does
+        .env(SANDBOX_KEY, SANDBOX_OFF)
+        .env(SSH_WORKERS_KEY, SSH_WORKERS_OFF);
 
     for (key, value) in command_parameters {
         let key_str = key.to_string();
@@ -147,9 +571,6 @@ fn build_command(task: &Task) -> Result<(Command, String), String> {
             .arg(format!("{}={}", key_str, value_str));
     }
 
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-
     Ok((command, format!("sub-recipe {}", sub_recipe_name)))
 }
 
@@ -157,12 +578,32 @@ async fn run_command(
     mut command: Command,
     output_identifier: &str,
     task_id: &str,
+    placement: Option<&Placement>,
     task_execution_tracker: Arc<TaskExecutionTracker>,
     cancellation_token: CancellationToken,
 ) -> Result<(String, String, bool), String> {
-    let mut child = command
-        .spawn()
-        .map_err(|e| format!("Failed to spawn goose: {}", e))?;
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+    if let Some(Placement::Remote(_)) = placement {
+        command.stdin(Stdio::piped());
+    }
+    let mut child = command.spawn().map_err(|e| {
+        format!(
+            "Failed to spawn {}: {}",
+            command.as_std().get_program().to_string_lossy(),
+            e
+        )
+    })?;
+    if let (Some(Placement::Remote(remote)), Some(mut stdin)) = (placement, child.stdin.take()) {
+        // Closing the input once the recipe is written lets the host start the run
+        if let Err(e) = stdin.write_all(remote.recipe()).await {
+            tracing::warn!("Failed to send task {}'s recipe: {}", task_id, e);
+        }
+    }
+    let container = match placement {
+        Some(Placement::Container(container)) => Some(container),
+        _ => None,
+    };
 
     let stdout = child.stdout.take().expect("Failed to capture stdout");
     let stderr = child.stderr.take().expect("Failed to capture stderr");
@@ -182,18 +623,40 @@ async fn run_command(
         task_execution_tracker.clone(),
     );
 
-    let result = tokio::select! {
-        _ = cancellation_token.cancelled() => {
-            if let Err(e) = child.kill().await {
-                tracing::warn!("Failed to kill child process: {}", e);
-            }
-
-            stdout_task.abort();
-            stderr_task.abort();
-            return Err("Command cancelled".to_string());
+    let container_id = async {
+        match container {
+            Some(container) => container.wait_for_id().await,
+            None => None,
         }
-        status_result = child.wait() => {
-            status_result.map_err(|e| format!("Failed to wait for process: {}", e))?
+    };
+    tokio::pin!(container_id);
+    let mut container_id_known = container.is_none();
+    let result = loop {
+        tokio::select! {
+            _ = cancellation_token.cancelled() => {
+                if let Err(e) = child.kill().await {
+                    tracing::warn!("Failed to kill child process: {}", e);
+                }
+                // Killing the runtime's or ssh's client leaves the task running
+                match placement {
+                    Some(Placement::Container(container)) => container.remove().await,
+                    Some(Placement::Remote(remote)) => remote.stop().await,
+                    None => {}
+                }
+
+                stdout_task.abort();
+                stderr_task.abort();
+                return Err("Command cancelled".to_string());
+            }
+            id = &mut container_id, if !container_id_known => {
+                container_id_known = true;
+                if let Some(id) = id {
+                    task_execution_tracker.set_container_id(task_id, id).await;
+                }
+            }
+            status_result = child.wait() => {
+                break status_result.map_err(|e| format!("Failed to wait for process: {}", e))?;
+            }
         }
     };
 
@@ -261,3 +724,157 @@ fn process_output(stdout_output: String) -> Result<Value, String> {
         Ok(Value::String(stdout_output))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::subagent_execution_tool::ssh_workers::SshHost;
+    use crate::agents::subagent_execution_tool::task_execution_tracker::DisplayMode;
+    use crate::offline::OFFLINE_KEY;
+    use serial_test::serial;
+    use temp_env::with_var;
+
+    #[test]
+    fn test_should_retry() {
+        let mut policy = TaskRetryPolicy::default();
+        assert!(should_retry(&policy, "anything"));
+
+        policy.retry_on = vec!["timed out".to_string(), "HTTP 5[0-9]{2}".to_string()];
+        assert!(should_retry(&policy, "Command failed:\nrequest timed out"));
+        assert!(should_retry(&policy, "got HTTP 503 from upstream"));
+        assert!(!should_retry(&policy, "got HTTP 404 from upstream"));
+
+        policy.retry_on = vec!["rate limit (".to_string()];
+        assert!(!should_retry(&policy, "hit the rate limit (429)"));
+        policy.retry_on = vec![r"rate limit \(".to_string()];
+        assert!(should_retry(&policy, "hit the rate limit (429)"));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = TaskRetryPolicy {
+            backoff_seconds: 2,
+            max_backoff_seconds: 10,
+            ..Default::default()
+        };
+        assert_eq!(retry_delay(&policy, 1), Duration::from_secs(2));
+        assert_eq!(retry_delay(&policy, 2), Duration::from_secs(4));
+        assert_eq!(retry_delay(&policy, 3), Duration::from_secs(8));
+        assert_eq!(retry_delay(&policy, 4), Duration::from_secs(10));
+        assert_eq!(retry_delay(&policy, 40), Duration::from_secs(10));
+    }
+
+    #[test]
+    #[serial]
+    fn test_no_tasks_go_to_worker_hosts_offline() {
+        let task = Task {
+            id: "task-1".to_string(),
+            task_type: TaskType::SubRecipe,
+            payload: serde_json::json!({"sub_recipe": {"recipe_path": "/recipes/crawl.yaml"}}),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        };
+        // A host busy with as many tasks as it may run, so waiting for it would never end
+        let pool = Arc::new(SshPool::new(vec![SshHost {
+            host: "worker-1".to_string(),
+            port: None,
+            identity_file: None,
+            workdir: None,
+            goose: None,
+            max_tasks: Some(0),
+        }]));
+        with_var(OFFLINE_KEY, Some("true"), || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let placement = runtime.block_on(Placement::choose(
+                &task,
+                Some(pool),
+                None,
+                &CancellationToken::new(),
+            ));
+            let error = placement.err().unwrap();
+            assert!(error.contains("offline mode"), "{}", error);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cached_results_are_reused_and_failures_not_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Arc::new(ResultCache::new(dir.path().to_path_buf()));
+        // Not a recipe that runs, so the task fails straight away when it isn't cached
+        let task = Task {
+            id: "task-1".to_string(),
+            task_type: TaskType::InlineRecipe,
+            payload: serde_json::json!({"recipe": {"title": "broken"}, "cache": true}),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        };
+        let key = ResultCache::key(&task, None).await.unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let tracker = Arc::new(
+            TaskExecutionTracker::new(vec![task.clone()], DisplayMode::SingleTaskOutput, tx, None)
+                .with_result_cache(Some(cache.clone())),
+        );
+        let config = TaskConfig::new(None);
+
+        assert!(cached_result(&task, &tracker, &config).await.is_none());
+        let failed = process_task(&task, tracker.clone(), config.clone(), Default::default()).await;
+        assert_eq!(failed.status, TaskStatus::Failed);
+        assert_eq!(cache.get(&key).await, None);
+
+        let data = serde_json::json!({"result": "from an earlier run"});
+        cache.put(&key, "broken", &data).await.unwrap();
+        let cached = cached_result(&task, &tracker, &config).await.unwrap();
+        assert_eq!(cached.status, TaskStatus::Completed);
+        assert_eq!(cached.data, Some(data));
+    }
+    #[tokio::test]
+    async fn test_kept_results_are_missed_when_expired_or_not_asked_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut task = Task {
+            id: "task-1".to_string(),
+            task_type: TaskType::InlineRecipe,
+            payload: serde_json::json!({"recipe": {"title": "summarize"}, "cache": true}),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        };
+        let key = ResultCache::key(&task, None).await.unwrap();
+        let data = serde_json::json!({"result": "from an earlier run"});
+        ResultCache::new(dir.path().to_path_buf())
+            .put(&key, "summarize", &data)
+            .await
+            .unwrap();
+        let tracker = |cache: ResultCache, task: &Task| {
+            let (tx, _rx) = tokio::sync::mpsc::channel(100);
+            Arc::new(
+                TaskExecutionTracker::new(
+                    vec![task.clone()],
+                    DisplayMode::SingleTaskOutput,
+                    tx,
+                    None,
+                )
+                .with_result_cache(Some(Arc::new(cache))),
+            )
+        };
+        let config = TaskConfig::new(None);
+
+        let fresh = tracker(ResultCache::new(dir.path().to_path_buf()), &task);
+        assert!(cached_result(&task, &fresh, &config).await.is_some());
+
+        let expired = tracker(
+            ResultCache::new(dir.path().to_path_buf()).with_ttl(Duration::ZERO),
+            &task,
+        );
+        assert!(cached_result(&task, &expired, &config).await.is_none());
+
+        // The same task, but not asking to be cached, runs whatever is kept
+        task.payload["cache"] = serde_json::json!(false);
+        let uncached = tracker(ResultCache::new(dir.path().to_path_buf()), &task);
+        assert!(cached_result(&task, &uncached, &config).await.is_none());
+    }
+}
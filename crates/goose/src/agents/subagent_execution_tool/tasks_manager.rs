@@ -1,15 +1,63 @@
 use anyhow::Result;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 
+use crate::agents::subagent_execution_tool::pause::PauseControl;
 use crate::agents::subagent_execution_tool::task_types::Task;
 #[cfg(test)]
 use crate::agents::subagent_execution_tool::task_types::TaskType;
 
+/// The tokens of the attempts running, which `restart` cancels for the task to start over
+#[derive(Debug, Clone, Default)]
+pub struct Restarts(Arc<Mutex<HashMap<String, CancellationToken>>>);
+
+impl Restarts {
+    /// A token for the attempt of `task_id` starting now, cancelled with `task_token` too
+    pub fn begin(&self, task_id: &str, task_token: &CancellationToken) -> CancellationToken {
+        let token = task_token.child_token();
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(task_id.to_string(), token.clone());
+        token
+    }
+
+    pub fn end(&self, task_id: &str) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(task_id);
+    }
+
+    /// Stop the running attempt of `task_id` so it starts over; false when none is running
+    pub fn restart(&self, task_id: &str) -> bool {
+        match self
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(task_id)
+        {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TasksManager {
     tasks: Arc<RwLock<HashMap<String, Task>>>,
+    /// Tokens of the tasks queued or running, which `cancel_task` cancels
+    cancellations: Arc<RwLock<HashMap<String, CancellationToken>>>,
+    pause: PauseControl,
+    restarts: Restarts,
+    /// Whether tasks reuse the results kept of tasks like them
+    cache_results: Arc<AtomicBool>,
 }
 
 impl Default for TasksManager {
@@ -22,9 +70,41 @@ impl TasksManager {
     pub fn new() -> Self {
         Self {
             tasks: Arc::new(RwLock::new(HashMap::new())),
+            cancellations: Arc::new(RwLock::new(HashMap::new())),
+            pause: PauseControl::default(),
+            restarts: Restarts::default(),
+            cache_results: Arc::new(AtomicBool::new(true)),
         }
     }
 
+    /// What pauses the runs of these tasks, for a run to check before starting a task
+    pub fn pause_control(&self) -> PauseControl {
+        self.pause.clone()
+    }
+
+    /// What starts the attempts of these tasks over, for a run to register them with
+    pub fn restarts(&self) -> Restarts {
+        self.restarts.clone()
+    }
+
+    /// Start no more tasks until `resume_scheduling`; the ones running finish
+    pub fn pause_scheduling(&self) {
+        self.pause.pause();
+    }
+
+    pub fn resume_scheduling(&self) -> Result<(), String> {
+        self.pause.resume().map_err(|e| e.to_string())
+    }
+
+    /// Run every task, rather than reusing the results kept of tasks like it, unless `cache`
+    pub fn set_cache_results(&self, cache: bool) {
+        self.cache_results.store(cache, Ordering::Relaxed);
+    }
+
+    pub fn caches_results(&self) -> bool {
+        self.cache_results.load(Ordering::Relaxed)
+    }
+
     pub async fn save_tasks(&self, tasks: Vec<Task>) {
         let mut task_map = self.tasks.write().await;
         for task in tasks {
@@ -32,6 +112,19 @@ impl TasksManager {
         }
     }
 
+    /// The ids of every task created for one of the sub-recipes `names`
+    pub async fn sub_recipe_task_ids(&self, names: &[String]) -> Vec<String> {
+        let tasks = self.tasks.read().await;
+        tasks
+            .values()
+            .filter(|task| {
+                task.get_sub_recipe_name()
+                    .is_some_and(|name| names.iter().any(|n| n == name))
+            })
+            .map(|task| task.id.clone())
+            .collect()
+    }
+
     pub async fn get_task(&self, task_id: &str) -> Option<Task> {
         let tasks = self.tasks.read().await;
         tasks.get(task_id).cloned()
@@ -52,6 +145,54 @@ impl TasksManager {
         }
         Ok(tasks)
     }
+
+    /// A token for `task_id` to stop at, cancelled with `parent` or by `cancel_task`
+    pub async fn track_cancellation(
+        &self,
+        task_id: &str,
+        parent: Option<&CancellationToken>,
+    ) -> CancellationToken {
+        let token = parent.map(|p| p.child_token()).unwrap_or_default();
+        self.cancellations
+            .write()
+            .await
+            .insert(task_id.to_string(), token.clone());
+        token
+    }
+
+    pub async fn untrack_cancellation(&self, task_id: &str) {
+        self.cancellations.write().await.remove(task_id);
+    }
+
+    /// Ask the task with `task_id` to stop. A task still waiting to start doesn't start; a
+    /// running one is stopped where it next checks, and ends as cancelled.
+    pub async fn cancel_task(&self, task_id: &str) -> Result<(), String> {
+        if let Some(token) = self.cancellations.read().await.get(task_id) {
+            token.cancel();
+            return Ok(());
+        }
+        self.not_running(task_id).await
+    }
+
+    /// Stop the attempt the task with `task_id` is running and start it again, whatever its
+    /// retry policy says
+    pub async fn restart_task(&self, task_id: &str) -> Result<(), String> {
+        if self.restarts.restart(task_id) {
+            return Ok(());
+        }
+        self.not_running(task_id).await
+    }
+
+    async fn not_running(&self, task_id: &str) -> Result<(), String> {
+        if self.get_task(task_id).await.is_some() {
+            Err(format!("Task '{}' is not running", task_id))
+        } else {
+            Err(format!(
+                "Task with ID '{}' not found in TasksManager",
+                task_id
+            ))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -70,6 +211,10 @@ mod tests {
                     "recipe_path": "/test/path"
                 }
             }),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
         }
     }
 
@@ -102,4 +247,48 @@ mod tests {
         assert_eq!(task1.unwrap().id, "task1");
         assert_eq!(task2.unwrap().id, "task2");
     }
+
+    #[tokio::test]
+    async fn test_cancel_task() {
+        let manager = TasksManager::new();
+        manager
+            .save_tasks(vec![
+                create_test_task("task1", "weather"),
+                create_test_task("task2", "news"),
+            ])
+            .await;
+        let parent = CancellationToken::new();
+        let token1 = manager.track_cancellation("task1", Some(&parent)).await;
+        let token2 = manager.track_cancellation("task2", Some(&parent)).await;
+
+        manager.cancel_task("task1").await.unwrap();
+        assert!(token1.is_cancelled());
+        assert!(!token2.is_cancelled());
+        assert!(!parent.is_cancelled());
+
+        manager.untrack_cancellation("task2").await;
+        assert!(manager.cancel_task("task2").await.is_err());
+        assert!(manager.cancel_task("task3").await.is_err());
+
+        parent.cancel();
+        assert!(token2.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_restart_task() {
+        let manager = TasksManager::new();
+        manager
+            .save_tasks(vec![create_test_task("task1", "weather")])
+            .await;
+        assert!(manager.restart_task("task1").await.is_err());
+
+        let task_token = manager.track_cancellation("task1", None).await;
+        let attempt = manager.restarts().begin("task1", &task_token);
+        manager.restart_task("task1").await.unwrap();
+        assert!(attempt.is_cancelled());
+        assert!(!task_token.is_cancelled());
+
+        manager.restarts().end("task1");
+        assert!(manager.restart_task("task1").await.is_err());
+    }
 }
@@ -1,7 +1,15 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use tokio::time::Instant;
 
 use crate::agents::subagent_execution_tool::task_types::{TaskInfo, TaskStatus};
 
+/// How many of the tasks that finished last the average duration is taken over
+const ESTIMATE_WINDOW: usize = 10;
+
 pub fn get_task_name(task_info: &TaskInfo) -> &str {
     task_info
         .task
@@ -18,11 +26,63 @@ pub fn count_by_status(tasks: &HashMap<String, TaskInfo>) -> (usize, usize, usiz
             TaskStatus::Running => (pending, running + 1, completed, failed),
             TaskStatus::Completed => (pending, running, completed + 1, failed),
             TaskStatus::Failed => (pending, running, completed, failed + 1),
+            TaskStatus::Cancelled | TaskStatus::Skipped => (pending, running, completed, failed),
         },
     );
     (total, pending, running, completed, failed)
 }
 
+pub fn count_with_status(tasks: &HashMap<String, TaskInfo>, status: TaskStatus) -> usize {
+    tasks.values().filter(|task| task.status == status).count()
+}
+
+/// How long `tasks` have left at `now`, in seconds, and how many of them finish a minute. Both
+/// come from the moving average of the durations of the tasks that finished last, so neither
+/// is known before a task finished, and there's nothing left once none are pending or running.
+pub fn estimate_progress(
+    tasks: &HashMap<String, TaskInfo>,
+    now: Instant,
+) -> (Option<f64>, Option<f64>) {
+    let mut finished: Vec<(Instant, f64)> = tasks
+        .values()
+        .filter_map(|task| {
+            let (start, end) = task.start_time.zip(task.end_time)?;
+            Some((end, end.duration_since(start).as_secs_f64()))
+        })
+        .collect();
+    if finished.is_empty() {
+        return (None, None);
+    }
+    finished.sort_by_key(|(end, _)| *end);
+    let recent = &finished[finished.len().saturating_sub(ESTIMATE_WINDOW)..];
+    let average = recent.iter().map(|(_, duration)| duration).sum::<f64>() / recent.len() as f64;
+
+    let minutes = tasks
+        .values()
+        .filter_map(|task| task.start_time)
+        .min()
+        .map_or(0.0, |first_start| {
+            now.duration_since(first_start).as_secs_f64() / 60.0
+        });
+    let tasks_per_minute = (minutes > 0.0).then(|| finished.len() as f64 / minutes);
+
+    // What the running tasks have left if they take as long as the average, and the pending
+    // ones spread over as many at once as are running now
+    let running: Vec<f64> = tasks
+        .values()
+        .filter(|task| matches!(task.status, TaskStatus::Running))
+        .filter_map(|task| task.start_time)
+        .map(|start| (average - now.duration_since(start).as_secs_f64()).max(0.0))
+        .collect();
+    let pending = count_with_status(tasks, TaskStatus::Pending);
+    let eta_secs = (pending > 0 || !running.is_empty()).then(|| {
+        let work = pending as f64 * average + running.iter().sum::<f64>();
+        let longest_running = running.iter().copied().fold(0.0, f64::max);
+        (work / running.len().max(1) as f64).max(longest_running)
+    });
+    (eta_secs, tasks_per_minute)
+}
+
 pub fn strip_ansi_codes(text: &str) -> String {
     let mut result = String::new();
     let mut chars = text.chars();
@@ -56,5 +116,53 @@ pub fn strip_ansi_codes(text: &str) -> String {
     result
 }
 
+#[derive(Debug, Default, PartialEq)]
+pub struct PruneReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub kept: usize,
+}
+
+/// Remove the files in `dir` with one of `extensions` last written to more than `retention`
+/// ago. With `dry_run` nothing is deleted, only reported.
+pub fn prune_files(
+    dir: &Path,
+    extensions: &[&str],
+    retention: Duration,
+    dry_run: bool,
+) -> Result<PruneReport> {
+    let mut report = PruneReport::default();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(report);
+    };
+    let cutoff = SystemTime::now().checked_sub(retention);
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let matches = path
+            .extension()
+            .is_some_and(|extension| extensions.iter().any(|e| extension == *e));
+        if !matches {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let recent = match (metadata.modified(), cutoff) {
+            (Ok(modified), Some(cutoff)) => modified > cutoff,
+            _ => true,
+        };
+        if recent {
+            report.kept += 1;
+            continue;
+        }
+        report.removed += 1;
+        report.freed_bytes += metadata.len();
+        if !dry_run {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,6 +1,7 @@
 use crate::agents::subagent_execution_tool::task_types::{Task, TaskInfo, TaskStatus, TaskType};
+use crate::agents::subagent_execution_tool::task_usage::TaskUsage;
 use crate::agents::subagent_execution_tool::utils::{
-    count_by_status, get_task_name, strip_ansi_codes,
+    count_by_status, count_with_status, estimate_progress, get_task_name, strip_ansi_codes,
 };
 use serde_json::json;
 use std::collections::HashMap;
@@ -13,6 +14,12 @@ fn create_task_info_with_defaults(task: Task, status: TaskStatus) -> TaskInfo {
         end_time: None,
         result: None,
         current_output: String::new(),
+        attempt: 1,
+        stopped_by_policy: false,
+        usage: TaskUsage::default(),
+        container_id: None,
+        host: None,
+        cached: false,
     }
 }
 
@@ -30,6 +37,10 @@ mod test_get_task_name {
                     "recipe_path": "/path/to/recipe"
                 }
             }),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
         };
 
         let task_info = create_task_info_with_defaults(sub_recipe_task, TaskStatus::Pending);
@@ -43,6 +54,10 @@ mod test_get_task_name {
             id: "task_2".to_string(),
             task_type: TaskType::InlineRecipe,
             payload: json!({"recipe": {"instructions": "do something"}}),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
         };
 
         let task_info = create_task_info_with_defaults(inline_task, TaskStatus::Pending);
@@ -61,6 +76,10 @@ mod test_get_task_name {
                     // missing "name" field
                 }
             }),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
         };
 
         let task_info = create_task_info_with_defaults(malformed_task, TaskStatus::Pending);
@@ -74,6 +93,10 @@ mod test_get_task_name {
             id: "task_4".to_string(),
             task_type: TaskType::SubRecipe,
             payload: json!({}), // missing "sub_recipe" field
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
         };
 
         let task_info = create_task_info_with_defaults(malformed_task, TaskStatus::Pending);
@@ -90,6 +113,10 @@ mod count_by_status {
             id: id.to_string(),
             task_type: TaskType::InlineRecipe,
             payload: json!({}),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
         };
         create_task_info_with_defaults(task, status)
     }
@@ -153,6 +180,27 @@ mod count_by_status {
             (5, 1, 1, 2, 1)
         );
     }
+
+    #[test]
+    fn counts_cancelled_in_total_only() {
+        let mut tasks = HashMap::new();
+        tasks.insert(
+            "task1".to_string(),
+            create_test_task("task1", TaskStatus::Cancelled),
+        );
+        tasks.insert(
+            "task2".to_string(),
+            create_test_task("task2", TaskStatus::Completed),
+        );
+
+        let (total, pending, running, completed, failed) = count_by_status(&tasks);
+        assert_eq!(
+            (total, pending, running, completed, failed),
+            (2, 0, 0, 1, 0)
+        );
+        assert_eq!(count_with_status(&tasks, TaskStatus::Cancelled), 1);
+        assert_eq!(count_with_status(&tasks, TaskStatus::Skipped), 0);
+    }
 }
 
 mod strip_ansi_codes {
@@ -175,3 +223,49 @@ mod strip_ansi_codes {
         assert_eq!(strip_ansi_codes(""), "");
     }
 }
+
+mod estimate_progress {
+    use super::*;
+    use tokio::time::{Duration, Instant};
+
+    #[test]
+    fn test_estimate_progress() {
+        let base = Instant::now();
+        let secs = |secs: u64| base + Duration::from_secs(secs);
+        let mut tasks = HashMap::new();
+        for (id, status, start, end) in [
+            ("done1", TaskStatus::Completed, Some(0), Some(10)),
+            ("done2", TaskStatus::Completed, Some(0), Some(20)),
+            ("run1", TaskStatus::Running, Some(20), None),
+            ("run2", TaskStatus::Running, Some(20), None),
+            ("wait1", TaskStatus::Pending, None, None),
+            ("wait2", TaskStatus::Pending, None, None),
+            ("wait3", TaskStatus::Pending, None, None),
+            ("wait4", TaskStatus::Pending, None, None),
+        ] {
+            let task = Task {
+                id: id.to_string(),
+                task_type: TaskType::InlineRecipe,
+                payload: json!({}),
+                depends_on: Vec::new(),
+                timeout_seconds: None,
+                on_timeout: Default::default(),
+                priority: Default::default(),
+            };
+            let mut task = create_task_info_with_defaults(task, status);
+            task.start_time = start.map(secs);
+            task.end_time = end.map(secs);
+            tasks.insert(id.to_string(), task);
+        }
+
+        // 15s a task on average: four pending and 5s left of each running one, two at a time
+        let (eta_secs, tasks_per_minute) = estimate_progress(&tasks, secs(30));
+        assert_eq!(eta_secs, Some(35.0));
+        assert_eq!(tasks_per_minute, Some(4.0));
+
+        tasks.retain(|id, _| id.starts_with("done"));
+        assert_eq!(estimate_progress(&tasks, secs(30)).0, None);
+        tasks.clear();
+        assert_eq!(estimate_progress(&tasks, secs(30)), (None, None));
+    }
+}
@@ -0,0 +1,402 @@
+//! Webhooks told about the tasks of parallel runs.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use minijinja::Environment;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+use crate::agents::subagent_execution_tool::progress_events::ProgressEvent;
+use crate::config::{Config, ConfigError};
+use crate::offline::check_url;
+
+pub const TASK_WEBHOOKS_KEY: &str = "GOOSE_TASK_WEBHOOKS";
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    TaskStarted,
+    TaskCompleted,
+    TaskFailed,
+    RunFinished,
+}
+
+impl WebhookEvent {
+    /// What `event` is to webhooks; task output isn't sent to them
+    pub fn of(event: &ProgressEvent) -> Option<Self> {
+        match event {
+            ProgressEvent::TaskStarted { .. } => Some(Self::TaskStarted),
+            ProgressEvent::TaskOutput { .. } => None,
+            ProgressEvent::TaskCompleted { status, .. } if status == "failed" => {
+                Some(Self::TaskFailed)
+            }
+            ProgressEvent::TaskCompleted { .. } => Some(Self::TaskCompleted),
+            ProgressEvent::Summary { .. } => Some(Self::RunFinished),
+        }
+    }
+}
+
+/// A line saying what `event` was about
+fn describe(event: &ProgressEvent) -> String {
+    match event {
+        ProgressEvent::TaskStarted { name, .. } => format!("▶️ Task {} started", name),
+        ProgressEvent::TaskOutput { line, .. } => line.clone(),
+        ProgressEvent::TaskCompleted {
+            name,
+            status,
+            duration_secs,
+            error,
+            ..
+        } => {
+            let duration = duration_secs
+                .map(|secs| format!(" after {:.1}s", secs))
+                .unwrap_or_default();
+            match (status.as_str(), error) {
+                ("completed", _) => format!("✅ Task {} completed{}", name, duration),
+                ("failed", Some(error)) => {
+                    format!("❌ Task {} failed{}: {}", name, duration, error)
+                }
+                (status, _) => format!("⚠️ Task {} {}{}", name, status, duration),
+            }
+        }
+        ProgressEvent::Summary { run_id, stats } => {
+            let run = run_id
+                .as_ref()
+                .map(|run_id| format!(" {}", run_id))
+                .unwrap_or_default();
+            format!(
+                "🏁 Task run{} finished: {} of {} completed, {} failed",
+                run, stats.completed, stats.total, stats.failed
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Every event when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+impl Webhook {
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    /// The body to POST for an event with the fields `context`
+    pub fn payload(&self, context: &Value) -> Result<String> {
+        let Some(template) = &self.template else {
+            return Ok(context.to_string());
+        };
+        let mut env = Environment::new();
+        env.add_filter("tojson", |value: minijinja::Value| {
+            serde_json::to_string(&value).map_err(|e| {
+                minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, e.to_string())
+            })
+        });
+        env.render_str(template, context)
+            .with_context(|| format!("Failed to render the webhook template for {}", self.url))
+    }
+}
+
+/// The webhooks of a run
+pub struct Webhooks {
+    hooks: Vec<Webhook>,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    pub fn new(hooks: Vec<Webhook>) -> Self {
+        Self {
+            hooks,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The webhooks `GOOSE_TASK_WEBHOOKS` lists, if it lists any
+    pub fn from_config() -> Option<Arc<Self>> {
+        let hooks = match Config::global().get_param::<Vec<Webhook>>(TASK_WEBHOOKS_KEY) {
+            Ok(hooks) => hooks,
+            Err(ConfigError::NotFound(_)) => return None,
+            Err(e) => {
+                tracing::warn!("Ignoring {}: {}", TASK_WEBHOOKS_KEY, e);
+                return None;
+            }
+        };
+        (!hooks.is_empty()).then(|| Arc::new(Self::new(hooks)))
+    }
+
+    /// POST `event` to every webhook that wants it. Webhooks that can't be reached or refuse it
+    /// are warned about; the run goes on regardless.
+    pub async fn notify(&self, event: &ProgressEvent) {
+        let Some(kind) = WebhookEvent::of(event) else {
+            return;
+        };
+        let mut context = json!({ "timestamp": Utc::now() });
+        if let (Some(fields), Ok(Value::Object(event_fields))) =
+            (context.as_object_mut(), serde_json::to_value(event))
+        {
+            fields.extend(event_fields);
+            fields.insert("event".to_string(), json!(kind));
+            fields.insert("text".to_string(), json!(describe(event)));
+        }
+
+        let sends = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.wants(kind))
+            .map(|hook| self.send(hook, &context));
+        for result in futures::future::join_all(sends).await {
+            if let Err(e) = result {
+                tracing::warn!("Task webhook failed: {:#}", e);
+            }
+        }
+    }
+
+    async fn send(&self, hook: &Webhook, context: &Value) -> Result<()> {
+        check_url(format!("The task webhook {}", hook.url), &hook.url)?;
+        let mut request = self
+            .client
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(hook.payload(context)?);
+        for (name, value) in &hook.headers {
+            request = request.header(name, value);
+        }
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to POST to {}", hook.url))?;
+        Ok(())
+    }
+}
+
+/// Sends the events of a run to its webhooks one after another, so none arrives before the
+/// ones that happened earlier
+pub struct WebhookQueue {
+    events: Mutex<Option<mpsc::UnboundedSender<ProgressEvent>>>,
+    sending: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl WebhookQueue {
+    pub fn start(webhooks: Arc<Webhooks>) -> Self {
+        let (events, mut queued) = mpsc::unbounded_channel::<ProgressEvent>();
+        let sending = tokio::spawn(async move {
+            while let Some(event) = queued.recv().await {
+                webhooks.notify(&event).await;
+            }
+        });
+        Self {
+            events: Mutex::new(Some(events)),
+            sending: tokio::sync::Mutex::new(Some(sending)),
+        }
+    }
+
+    /// Send `event` once the events before it were; events pushed after draining are dropped
+    pub fn push(&self, event: ProgressEvent) {
+        if let Some(events) = self.events.lock().unwrap().as_ref() {
+            let _ = events.send(event);
+        }
+    }
+
+    /// Wait for every event pushed to be sent, taking no more
+    pub async fn drain(&self) {
+        self.events.lock().unwrap().take();
+        if let Some(sending) = self.sending.lock().await.take() {
+            if let Err(e) = sending.await {
+                tracing::warn!("Task webhooks stopped: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::subagent_execution_tool::notification_events::TaskCompletionStats;
+    use crate::agents::subagent_execution_tool::task_types::TaskStatus;
+    use crate::offline::OFFLINE_KEY;
+    use serial_test::serial;
+    use temp_env::with_var;
+    use wiremock::matchers::{body_json, body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn failed() -> ProgressEvent {
+        ProgressEvent::task_completed(
+            "task-1".to_string(),
+            "crawl".to_string(),
+            &TaskStatus::Failed,
+            Some(2.0),
+            Some("Connection \"refused\"".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_events() {
+        assert_eq!(WebhookEvent::of(&failed()), Some(WebhookEvent::TaskFailed));
+        assert_eq!(
+            WebhookEvent::of(&ProgressEvent::TaskOutput {
+                task_id: "task-1".to_string(),
+                line: "fetching".to_string(),
+            }),
+            None
+        );
+        let summary = ProgressEvent::Summary {
+            run_id: Some("run-1".to_string()),
+            stats: TaskCompletionStats::new(4, 3, 1),
+        };
+        assert_eq!(WebhookEvent::of(&summary), Some(WebhookEvent::RunFinished));
+        assert_eq!(
+            describe(&summary),
+            "🏁 Task run run-1 finished: 3 of 4 completed, 1 failed"
+        );
+
+        let hooks: Vec<Webhook> = serde_json::from_value(json!([
+            {"url": "https://example.com/all"},
+            {"url": "https://example.com/failures", "events": ["task_failed", "run_finished"]},
+        ]))
+        .unwrap();
+        assert!(hooks[0].wants(WebhookEvent::TaskStarted));
+        assert!(!hooks[1].wants(WebhookEvent::TaskStarted));
+        assert!(hooks[1].wants(WebhookEvent::TaskFailed));
+    }
+
+    #[test]
+    #[serial]
+    fn test_only_local_webhooks_are_sent_to_offline() {
+        let hook = |url: String| Webhook {
+            url,
+            events: Vec::new(),
+            headers: HashMap::new(),
+            template: None,
+        };
+        with_var(OFFLINE_KEY, Some("true"), || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async {
+                let server = MockServer::start().await;
+                Mock::given(method("POST"))
+                    .respond_with(ResponseTemplate::new(200))
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+                let webhooks = Webhooks::new(Vec::new());
+                let context = json!({"text": "done"});
+
+                let remote = hook("https://hooks.example.com/goose".to_string());
+                let error = webhooks.send(&remote, &context).await.unwrap_err();
+                assert!(error.to_string().contains("offline mode"), "{}", error);
+                webhooks.send(&hook(server.uri()), &context).await.unwrap();
+            });
+        });
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .and(header("authorization", "Bearer s3cret"))
+            .and(body_json(json!({
+                "text": "❌ Task crawl failed after 2.0s: Connection \"refused\""
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/raw"))
+            .and(body_partial_json(json!({
+                "event": "task_failed",
+                "task_id": "task-1",
+                "status": "failed",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/runs"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let webhooks = Webhooks::new(vec![
+            Webhook {
+                url: format!("{}/slack", server.uri()),
+                events: vec![WebhookEvent::TaskFailed],
+                headers: HashMap::from([(
+                    "Authorization".to_string(),
+                    "Bearer s3cret".to_string(),
+                )]),
+                template: Some(r#"{"text": {{ text|tojson }}}"#.to_string()),
+            },
+            Webhook {
+                url: format!("{}/raw", server.uri()),
+                events: Vec::new(),
+                headers: HashMap::new(),
+                template: None,
+            },
+            Webhook {
+                url: format!("{}/runs", server.uri()),
+                events: vec![WebhookEvent::RunFinished],
+                headers: HashMap::new(),
+                template: None,
+            },
+        ]);
+        webhooks.notify(&failed()).await;
+    }
+
+    #[tokio::test]
+    async fn test_queued_events_arrive_in_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let queue = WebhookQueue::start(Arc::new(Webhooks::new(vec![Webhook {
+            url: server.uri(),
+            events: Vec::new(),
+            headers: HashMap::new(),
+            template: Some("{{ event }}".to_string()),
+        }])));
+
+        queue.push(ProgressEvent::TaskStarted {
+            task_id: "task-1".to_string(),
+            name: "crawl".to_string(),
+            task_type: "sub_recipe".to_string(),
+        });
+        queue.push(failed());
+        queue.push(ProgressEvent::Summary {
+            run_id: None,
+            stats: TaskCompletionStats::new(1, 0, 1),
+        });
+        queue.drain().await;
+        queue.push(failed());
+
+        let bodies: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| String::from_utf8_lossy(&request.body).to_string())
+            .collect();
+        assert_eq!(bodies, ["task_started", "task_failed", "run_finished"]);
+    }
+}
@@ -1,13 +1,21 @@
-use crate::agents::subagent_execution_tool::task_types::{SharedState, Task};
-use crate::agents::subagent_execution_tool::tasks::process_task;
+use crate::agents::subagent_execution_tool::task_types::{SharedState, Task, TaskResult};
+use crate::agents::subagent_execution_tool::tasks::{
+    cached_result, cancelled_result, process_task, stopped_result,
+};
 use crate::agents::subagent_task_config::TaskConfig;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 async fn receive_task(state: &SharedState) -> Option<Task> {
     let mut receiver = state.task_receiver.lock().await;
     receiver.recv().await
 }
 
+async fn stopped_by_policy(state: &SharedState, task: &Task) -> TaskResult {
+    state.task_execution_tracker.stop_by_policy(&task.id).await;
+    stopped_result(task, state.failure_policy)
+}
+
 pub fn spawn_worker(
     state: Arc<SharedState>,
     worker_id: usize,
@@ -20,38 +28,137 @@ pub fn spawn_worker(
     })
 }
 
-async fn worker_loop(state: Arc<SharedState>, _worker_id: usize, task_config: TaskConfig) {
-    loop {
-        tokio::select! {
-            task_option = receive_task(&state) => {
-                match task_option {
-                    Some(task) => {
-                        state.task_execution_tracker.start_task(&task.id).await;
-                        let result = process_task(
-                            &task,
-                            state.task_execution_tracker.clone(),
-                            task_config.clone(),
-                            state.cancellation_token.clone(),
-                        )
-                        .await;
-
-                        if let Err(e) = state.result_sender.send(result).await {
-                            // Only log error if not cancelled (channel close is expected during cancellation)
-                            if !state.cancellation_token.is_cancelled() {
-                                tracing::error!("Worker failed to send result: {}", e);
-                            }
-                            break;
-                        }
-                    }
-                    None => break, // No more tasks
-                }
+/// Run `task` once what it needs is free; until then it stays pending
+async fn run_when_free(
+    state: &SharedState,
+    task: &Task,
+    task_config: &TaskConfig,
+    task_token: &CancellationToken,
+) -> TaskResult {
+    let needs = task.get_resources();
+    tokio::select! {
+        biased;
+        // Cancelled before it started, with the run or on its own
+        _ = task_token.cancelled() => cancelled_result(task),
+        _ = state.stop_starting.cancelled() => stopped_by_policy(state, task).await,
+        guard = state.resources.acquire(&needs, task.priority) => {
+            let _resources = guard;
+            if state.stop_starting.is_cancelled() {
+                stopped_by_policy(state, task).await
+            } else {
+                state.task_execution_tracker.start_task(&task.id).await;
+                process_task(
+                    task,
+                    state.task_execution_tracker.clone(),
+                    task_config.clone(),
+                    task_token.clone(),
+                )
+                .await
             }
-            _ = state.cancellation_token.cancelled() => {
-                tracing::debug!("Worker cancelled");
-                break;
+        }
+    }
+}
+
+async fn worker_loop(state: Arc<SharedState>, _worker_id: usize, task_config: TaskConfig) {
+    // Tasks queued after the run is cancelled are still taken, to be reported as cancelled;
+    // the queue closes once every task has a result
+    while let Some(task) = receive_task(&state).await {
+        let task_token = state.task_token(&task.id);
+        // A result kept of a task like it is given without taking a slot or starting the task
+        let cached = if task_token.is_cancelled() {
+            None
+        } else {
+            cached_result(&task, &state.task_execution_tracker, &task_config).await
+        };
+        let result = match cached {
+            Some(result) => result,
+            None => run_when_free(&state, &task, &task_config, &task_token).await,
+        };
+
+        if let Err(e) = state.result_sender.send(result).await {
+            // Only log error if not cancelled (channel close is expected during cancellation)
+            if !state.cancellation_token.is_cancelled() {
+                tracing::error!("Worker failed to send result: {}", e);
             }
+            break;
         }
     }
 
     state.decrement_active_workers();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::subagent_execution_tool::resources::ResourceScheduler;
+    use crate::agents::subagent_execution_tool::result_cache::ResultCache;
+    use crate::agents::subagent_execution_tool::task_execution_tracker::{
+        DisplayMode, TaskExecutionTracker,
+    };
+    use crate::agents::subagent_execution_tool::task_types::{TaskStatus, TaskType};
+    use std::collections::HashMap;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_cached_results_need_no_slot_or_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Arc::new(ResultCache::new(dir.path().to_path_buf()));
+        let task = Task {
+            id: "task-1".to_string(),
+            task_type: TaskType::InlineRecipe,
+            payload: serde_json::json!({
+                "recipe": {"title": "summarize"},
+                "resources": {"locks": ["db"]},
+                "cache": true
+            }),
+            depends_on: Vec::new(),
+            timeout_seconds: None,
+            on_timeout: Default::default(),
+            priority: Default::default(),
+        };
+        let key = ResultCache::key(&task, None).await.unwrap();
+        let data = serde_json::json!({"result": "from an earlier run"});
+        cache.put(&key, "summarize", &data).await.unwrap();
+
+        let (notifier, _notifications) = mpsc::channel(100);
+        let (task_tx, task_rx) = mpsc::channel(1);
+        let (result_tx, mut result_rx) = mpsc::channel(1);
+        let state = Arc::new(SharedState {
+            task_receiver: Arc::new(tokio::sync::Mutex::new(task_rx)),
+            result_sender: result_tx,
+            active_workers: Arc::new(AtomicUsize::new(0)),
+            task_execution_tracker: Arc::new(
+                TaskExecutionTracker::new(
+                    vec![task.clone()],
+                    DisplayMode::SingleTaskOutput,
+                    notifier,
+                    None,
+                )
+                .with_result_cache(Some(cache)),
+            ),
+            cancellation_token: CancellationToken::new(),
+            resources: Arc::new(ResourceScheduler::new(1, 1).with_max_parallel(1)),
+            task_tokens: HashMap::new(),
+            failure_policy: Default::default(),
+            stop_starting: CancellationToken::new(),
+        });
+        // A running task holds the only slot and the lock the cached one declares
+        let _running = state
+            .resources
+            .acquire(&task.get_resources(), task.priority)
+            .await;
+
+        task_tx.send(task).await.unwrap();
+        drop(task_tx);
+        let worker = spawn_worker(state, 0, TaskConfig::new(None));
+        let result = tokio::time::timeout(Duration::from_secs(5), result_rx.recv())
+            .await
+            .expect("the cached result waited for the slot")
+            .unwrap();
+        assert_eq!(result.status, TaskStatus::Completed);
+        assert_eq!(result.data, Some(data));
+        worker.await.unwrap();
+    }
+}
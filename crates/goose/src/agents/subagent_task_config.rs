@@ -1,7 +1,9 @@
+use crate::agents::subagent_execution_tool::task_execution_tracker::TaskExecutionTracker;
 use crate::providers::base::Provider;
 use std::env;
 use std::fmt;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 /// Default maximum number of turns for task execution
@@ -10,6 +12,21 @@ pub const DEFAULT_SUBAGENT_MAX_TURNS: usize = 25;
 /// Environment variable name for configuring max turns
 pub const GOOSE_SUBAGENT_MAX_TURNS_ENV_VAR: &str = "GOOSE_SUBAGENT_MAX_TURNS";
 
+/// A way to ask a running subagent to stop and report what it has
+#[derive(Debug, Clone)]
+pub struct WrapUp {
+    pub prompt: String,
+    /// Cancelled when the subagent should wrap up
+    pub signal: CancellationToken,
+}
+
+/// Where a subagent running as a task of a parallel run reports the tokens it spends
+#[derive(Clone)]
+pub struct UsageReport {
+    pub task_id: String,
+    pub tracker: Arc<TaskExecutionTracker>,
+}
+
 /// Configuration for task execution with all necessary dependencies
 #[derive(Clone)]
 pub struct TaskConfig {
@@ -17,6 +34,10 @@ pub struct TaskConfig {
     pub provider: Option<Arc<dyn Provider>>,
     pub max_turns: Option<usize>,
     pub extensions: Option<Vec<crate::agents::extension::ExtensionConfig>>,
+    /// Once signalled, the subagent's next turn sends the prompt and offers no tools, so that
+    /// turn is its last
+    pub wrap_up: Option<WrapUp>,
+    pub usage: Option<UsageReport>,
 }
 
 impl fmt::Debug for TaskConfig {
@@ -26,6 +47,8 @@ impl fmt::Debug for TaskConfig {
             .field("provider", &"<dyn Provider>")
             .field("max_turns", &self.max_turns)
             .field("extensions", &self.extensions)
+            .field("wrap_up", &self.wrap_up)
+            .field("usage", &self.usage.as_ref().map(|usage| &usage.task_id))
             .finish()
     }
 }
@@ -43,6 +66,8 @@ impl TaskConfig {
                     .unwrap_or(DEFAULT_SUBAGENT_MAX_TURNS),
             ),
             extensions: None,
+            wrap_up: None,
+            usage: None,
         }
     }
 
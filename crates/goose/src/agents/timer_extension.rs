@@ -0,0 +1,440 @@
+//! Timers the agent sets for itself, such as "check the deploy status in 5 minutes".
+
+use crate::agents::extension::PlatformExtensionContext;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use once_cell::sync::Lazy;
+use rmcp::model::{
+    CallToolResult, Content, GetPromptResult, Implementation, InitializeResult, JsonObject,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, ProtocolVersion, ReadResourceResult,
+    ServerCapabilities, ServerNotification, Tool, ToolAnnotations, ToolsCapability,
+};
+use rmcp::object;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+pub static EXTENSION_NAME: &str = "timer";
+
+const MAX_DELAY_SECONDS: u64 = 24 * 60 * 60;
+const MAX_PENDING_TIMERS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timer {
+    pub id: u32,
+    pub note: String,
+    pub due: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct SessionTimers {
+    next_id: u32,
+    pending: Vec<Timer>,
+}
+
+/// Pending timers by session id; sessionless runs share the empty id
+static TIMERS: Lazy<Mutex<HashMap<String, SessionTimers>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn with_timers<T>(session_id: Option<&str>, f: impl FnOnce(&mut SessionTimers) -> T) -> T {
+    let mut timers = TIMERS.lock().unwrap_or_else(|e| e.into_inner());
+    f(timers
+        .entry(session_id.unwrap_or_default().to_string())
+        .or_default())
+}
+
+fn set_timer(session_id: Option<&str>, delay_seconds: u64, note: String) -> Result<Timer, String> {
+    if delay_seconds == 0 || delay_seconds > MAX_DELAY_SECONDS {
+        return Err(format!(
+            "delay_seconds must be between 1 and {}",
+            MAX_DELAY_SECONDS
+        ));
+    }
+    with_timers(session_id, |timers| {
+        if timers.pending.len() >= MAX_PENDING_TIMERS {
+            return Err(format!(
+                "Too many pending timers ({}); cancel one first",
+                MAX_PENDING_TIMERS
+            ));
+        }
+        timers.next_id += 1;
+        let timer = Timer {
+            id: timers.next_id,
+            note,
+            due: Utc::now() + chrono::Duration::seconds(delay_seconds as i64),
+        };
+        timers.pending.push(timer.clone());
+        Ok(timer)
+    })
+}
+
+fn cancel_timer(session_id: Option<&str>, id: u32) -> bool {
+    with_timers(session_id, |timers| {
+        let before = timers.pending.len();
+        timers.pending.retain(|timer| timer.id != id);
+        timers.pending.len() != before
+    })
+}
+
+/// The session's pending timers, soonest first
+pub fn pending_timers(session_id: Option<&str>) -> Vec<Timer> {
+    let mut pending = with_timers(session_id, |timers| timers.pending.clone());
+    pending.sort_by_key(|timer| timer.due);
+    pending
+}
+
+/// When the session's next timer fires, if it has any
+pub fn next_timer_due(session_id: Option<&str>) -> Option<DateTime<Utc>> {
+    pending_timers(session_id).first().map(|timer| timer.due)
+}
+
+/// Remove the timers that are due at `now` and return them
+fn take_due(session_id: Option<&str>, now: DateTime<Utc>) -> Vec<Timer> {
+    let mut due = with_timers(session_id, |timers| {
+        let (due, pending): (Vec<Timer>, Vec<Timer>) =
+            timers.pending.drain(..).partition(|timer| timer.due <= now);
+        timers.pending = pending;
+        due
+    });
+    due.sort_by_key(|timer| timer.due);
+    due
+}
+
+/// A note for the model about the timers that fired since it last heard from the user, or
+/// `None` if none did. Each timer is reported once.
+pub fn fired_timers_note(session_id: Option<&str>) -> Option<String> {
+    let fired = take_due(session_id, Utc::now());
+    if fired.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = fired
+        .iter()
+        .map(|timer| {
+            format!(
+                "- Timer {} (due {}): {}",
+                timer.id,
+                timer.due.format("%H:%M:%S UTC"),
+                timer.note
+            )
+        })
+        .collect();
+    Some(format!(
+        "[Timers you set have fired]\n{}\nFollow up on them now.",
+        lines.join("\n")
+    ))
+}
+
+fn describe_delay(seconds: i64) -> String {
+    match seconds {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {}s", s / 60, s % 60),
+        s => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+    }
+}
+
+pub struct TimerClient {
+    info: InitializeResult,
+    context: PlatformExtensionContext,
+}
+
+impl TimerClient {
+    pub fn new(context: PlatformExtensionContext) -> Result<Self> {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: EXTENSION_NAME.to_string(),
+                title: Some("Timer".to_string()),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(indoc! {r#"
+                Timers
+
+                When you need to wait for something (a deploy, a build, a job) and check on it later,
+                use timer_set instead of sleeping in the shell. Finish what you can now and end your
+                turn; when the timer is due you get a note about it and can follow up.
+
+                Don't set a timer and then keep polling by hand.
+            "#}.to_string()),
+        };
+
+        Ok(Self { info, context })
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.context.session_id.as_deref()
+    }
+
+    fn handle_set(&self, arguments: Option<JsonObject>) -> Result<Vec<Content>, String> {
+        let arguments = arguments.ok_or("Missing arguments")?;
+        let delay_seconds = arguments
+            .get("delay_seconds")
+            .and_then(|v| v.as_u64())
+            .ok_or("Missing required parameter: delay_seconds")?;
+        let note = arguments
+            .get("note")
+            .and_then(|v| v.as_str())
+            .filter(|note| !note.trim().is_empty())
+            .ok_or("Missing required parameter: note")?
+            .to_string();
+
+        let timer = set_timer(self.session_id(), delay_seconds, note)?;
+        Ok(vec![Content::text(format!(
+            "Timer {} set for {} (in {}). You'll get a note when it is due; end your turn until then.",
+            timer.id,
+            timer.due.format("%H:%M:%S UTC"),
+            describe_delay(delay_seconds as i64)
+        ))])
+    }
+
+    fn handle_list(&self) -> Result<Vec<Content>, String> {
+        let pending = pending_timers(self.session_id());
+        if pending.is_empty() {
+            return Ok(vec![Content::text("No pending timers")]);
+        }
+        let now = Utc::now();
+        let lines: Vec<String> = pending
+            .iter()
+            .map(|timer| {
+                format!(
+                    "{}: in {} - {}",
+                    timer.id,
+                    describe_delay((timer.due - now).num_seconds().max(0)),
+                    timer.note
+                )
+            })
+            .collect();
+        Ok(vec![Content::text(lines.join("\n"))])
+    }
+
+    fn handle_cancel(&self, arguments: Option<JsonObject>) -> Result<Vec<Content>, String> {
+        let id = arguments
+            .as_ref()
+            .ok_or("Missing arguments")?
+            .get("id")
+            .and_then(|v| v.as_u64())
+            .and_then(|id| u32::try_from(id).ok())
+            .ok_or("Missing required parameter: id")?;
+        if cancel_timer(self.session_id(), id) {
+            Ok(vec![Content::text(format!("Timer {} cancelled", id))])
+        } else {
+            Err(format!("No pending timer {}", id))
+        }
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        vec![
+            Tool::new(
+                "timer_set".to_string(),
+                indoc! {r#"
+                    Set a timer that reminds you of something after a delay.
+
+                    When the timer is due you get the note back at the start of a later turn (in
+                    headless runs goose waits for it and starts that turn itself). Use it to check on
+                    slow work instead of running sleep in the shell.
+                "#}.to_string(),
+                object!({
+                    "type": "object",
+                    "properties": {
+                        "delay_seconds": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": MAX_DELAY_SECONDS,
+                            "description": "How long from now the timer fires"
+                        },
+                        "note": {
+                            "type": "string",
+                            "description": "What to do when it fires, e.g. 'check the deploy status of build 42'"
+                        }
+                    },
+                    "required": ["delay_seconds", "note"]
+                }),
+            ).annotate(ToolAnnotations {
+                title: Some("Set timer".to_string()),
+                read_only_hint: Some(false),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(false),
+                open_world_hint: Some(false),
+            }),
+            Tool::new(
+                "timer_list".to_string(),
+                "List the pending timers with their ids and how long until they fire.".to_string(),
+                object!({
+                    "type": "object",
+                    "properties": {},
+                    "required": []
+                }),
+            ).annotate(ToolAnnotations {
+                title: Some("List timers".to_string()),
+                read_only_hint: Some(true),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+            }),
+            Tool::new(
+                "timer_cancel".to_string(),
+                "Cancel a pending timer by its id.".to_string(),
+                object!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "description": "The id timer_set returned"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ).annotate(ToolAnnotations {
+                title: Some("Cancel timer".to_string()),
+                read_only_hint: Some(false),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+            }),
+        ]
+    }
+}
+
+#[async_trait]
+impl McpClientTrait for TimerClient {
+    async fn list_resources(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListResourcesResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn read_resource(
+        &self,
+        _uri: &str,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ReadResourceResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn list_tools(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: Self::get_tools(),
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let content = match name {
+            "timer_set" => self.handle_set(arguments),
+            "timer_list" => self.handle_list(),
+            "timer_cancel" => self.handle_cancel(arguments),
+            _ => Err(format!("Unknown tool: {}", name)),
+        };
+
+        match content {
+            Ok(content) => Ok(CallToolResult::success(content)),
+            Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {}",
+                error
+            ))])),
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListPromptsResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn get_prompt(
+        &self,
+        _name: &str,
+        _arguments: Value,
+        _cancellation_token: CancellationToken,
+    ) -> Result<GetPromptResult, Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+        mpsc::channel(1).1
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timers_fire_once_in_order() {
+        let session = Some("timer-test-fire");
+        let later = set_timer(session, 600, "later".to_string()).unwrap();
+        let soon = set_timer(session, 60, "soon".to_string()).unwrap();
+        assert_eq!(next_timer_due(session), Some(soon.due));
+        assert!(fired_timers_note(session).is_none());
+
+        let fired = take_due(session, soon.due + chrono::Duration::seconds(1));
+        assert_eq!(fired, vec![soon]);
+        assert_eq!(pending_timers(session), vec![later.clone()]);
+
+        assert!(cancel_timer(session, later.id));
+        assert!(!cancel_timer(session, later.id));
+        assert_eq!(next_timer_due(session), None);
+    }
+
+    #[test]
+    fn test_set_timer_limits() {
+        let session = Some("timer-test-limits");
+        assert!(set_timer(session, 0, "now".to_string()).is_err());
+        assert!(set_timer(session, MAX_DELAY_SECONDS + 1, "too late".to_string()).is_err());
+        for _ in 0..MAX_PENDING_TIMERS {
+            set_timer(session, 60, "poll".to_string()).unwrap();
+        }
+        assert!(set_timer(session, 60, "one more".to_string()).is_err());
+        // Other sessions have their own timers
+        assert!(set_timer(Some("timer-test-other"), 60, "poll".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_fired_timers_note() {
+        let session = Some("timer-test-note");
+        with_timers(session, |timers| {
+            timers.pending.push(Timer {
+                id: 7,
+                note: "check the deploy".to_string(),
+                due: Utc::now() - chrono::Duration::seconds(5),
+            })
+        });
+        let note = fired_timers_note(session).unwrap();
+        assert!(note.contains("Timer 7"));
+        assert!(note.contains("check the deploy"));
+        assert!(fired_timers_note(session).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::sync::Arc;
 
@@ -10,7 +11,7 @@ use tokio_util::sync::CancellationToken;
 use crate::config::permission::PermissionLevel;
 use crate::mcp_utils::ToolResult;
 use crate::permission::Permission;
-use rmcp::model::{Content, ServerNotification};
+use rmcp::model::{Content, JsonObject, ServerNotification};
 
 // ToolCallResult combines the result of a tool call with an optional notification stream that
 // can be used to receive notifications from the tool.
@@ -52,6 +53,7 @@ impl Agent {
         tool_requests: &'a [ToolRequest],
         tool_futures: Arc<Mutex<Vec<(String, ToolStream)>>>,
         message_tool_response: Arc<Mutex<Message>>,
+        edited_arguments: Arc<Mutex<HashMap<String, JsonObject>>>,
         cancellation_token: Option<CancellationToken>,
         inspection_results: &'a [crate::tool_inspection::InspectionResult],
     ) -> BoxStream<'a, anyhow::Result<Message>> {
@@ -90,6 +92,12 @@ impl Agent {
                             }
 
                             if confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow {
+                                let mut tool_call = tool_call.clone();
+                                if let Some(arguments) = confirmation.arguments {
+                                    tracing::info!("Calling {} with arguments edited by the user", tool_call.name);
+                                    edited_arguments.lock().await.insert(request.id.clone(), arguments.clone());
+                                    tool_call.arguments = Some(arguments);
+                                }
                                 let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone(), cancellation_token.clone()).await;
                                 let mut futures = tool_futures.lock().await;
 
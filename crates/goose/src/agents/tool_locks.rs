@@ -0,0 +1,248 @@
+//! Serialize tool calls that write to the same file.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use rmcp::model::CallToolRequestParam;
+use serde_json::Value;
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+use tracing::info;
+
+/// Arguments that hold a path the tool works on
+const PATH_ARGUMENTS: &[&str] = &["path", "file_path", "filepath", "filename", "target_file"];
+const PATH_LIST_ARGUMENTS: &[&str] = &["paths", "file_paths", "files"];
+/// Values of a `command` argument that only read the file
+const READ_COMMANDS: &[&str] = &["view", "read", "cat", "list"];
+
+static LOCKS: Lazy<ToolLocks> = Lazy::new(ToolLocks::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+enum Guard {
+    Read(OwnedRwLockReadGuard<()>),
+    Write(OwnedRwLockWriteGuard<()>),
+}
+
+/// Held while a tool call runs; releases its locks when dropped
+pub struct ResourceGuard {
+    _guards: Vec<Guard>,
+    pub waited: Duration,
+}
+
+#[derive(Default)]
+struct ToolLocks {
+    locks: Mutex<HashMap<PathBuf, Arc<RwLock<()>>>>,
+}
+
+impl ToolLocks {
+    fn lock_for(&self, path: &Path) -> Arc<RwLock<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget locks nobody holds or waits for, so the map doesn't grow with every file
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(path.to_path_buf()).or_default().clone()
+    }
+
+    async fn acquire(&self, resources: &BTreeMap<PathBuf, Access>) -> ResourceGuard {
+        let started = Instant::now();
+        let mut guards = Vec::with_capacity(resources.len());
+        for (path, access) in resources {
+            let lock = self.lock_for(path);
+            guards.push(match access {
+                Access::Read => Guard::Read(lock.read_owned().await),
+                Access::Write => Guard::Write(lock.write_owned().await),
+            });
+        }
+        ResourceGuard {
+            _guards: guards,
+            waited: started.elapsed(),
+        }
+    }
+}
+
+/// `path` made absolute with `.` and `..` resolved and symlinks followed where it exists, so
+/// different spellings of one file share a lock. Files that don't exist yet go by their
+/// canonical parent.
+fn canonical_path(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+    let absolute = if let Some(rest) = path.strip_prefix("~/") {
+        dirs::home_dir()?.join(rest)
+    } else if Path::new(path).is_absolute() {
+        PathBuf::from(path)
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+    if let Ok(canonical) = absolute.canonicalize() {
+        return Some(canonical);
+    }
+
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    match (normalized.parent(), normalized.file_name()) {
+        (Some(parent), Some(name)) => Some(
+            parent
+                .canonicalize()
+                .map(|parent| parent.join(name))
+                .unwrap_or(normalized.clone()),
+        ),
+        _ => Some(normalized),
+    }
+}
+
+/// The files a tool call works on and whether it changes them
+fn resources_for(tool_call: &CallToolRequestParam) -> BTreeMap<PathBuf, Access> {
+    let mut resources = BTreeMap::new();
+    let Some(arguments) = &tool_call.arguments else {
+        return resources;
+    };
+
+    let access = match arguments.get("command").and_then(Value::as_str) {
+        Some(command) if READ_COMMANDS.contains(&command) => Access::Read,
+        _ => Access::Write,
+    };
+    let single = PATH_ARGUMENTS
+        .iter()
+        .filter_map(|name| arguments.get(*name))
+        .filter_map(Value::as_str);
+    let lists = PATH_LIST_ARGUMENTS
+        .iter()
+        .filter_map(|name| arguments.get(*name))
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(Value::as_str);
+    for path in single.chain(lists).filter_map(canonical_path) {
+        let entry = resources.entry(path).or_insert(access);
+        if access == Access::Write {
+            *entry = Access::Write;
+        }
+    }
+    resources
+}
+
+/// The locks a tool call needs, worked out before it is dispatched
+pub struct ToolLock {
+    tool: String,
+    resources: BTreeMap<PathBuf, Access>,
+}
+
+impl ToolLock {
+    pub fn for_call(tool_call: &CallToolRequestParam) -> Self {
+        Self {
+            tool: tool_call.name.to_string(),
+            resources: resources_for(tool_call),
+        }
+    }
+
+    /// Wait until the call may run without racing other calls on the same files. Calls that
+    /// don't name a file get `None` straight away.
+    pub async fn acquire(self) -> Option<ResourceGuard> {
+        if self.resources.is_empty() {
+            return None;
+        }
+        let guard = LOCKS.acquire(&self.resources).await;
+        info!(
+            histogram.tool_lock_wait_ms = guard.waited.as_millis() as u64,
+            tool = %self.tool,
+            resources = self.resources.len(),
+            "tool lock acquired"
+        );
+        Some(guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str, arguments: Value) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: arguments.as_object().cloned(),
+        }
+    }
+
+    #[test]
+    fn test_resources_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("main.rs");
+        std::fs::write(&file, "fn main() {}").unwrap();
+        let canonical = file.canonicalize().unwrap();
+
+        let edit = call(
+            "developer__text_editor",
+            json!({"command": "str_replace", "path": file.to_str().unwrap()}),
+        );
+        assert_eq!(
+            resources_for(&edit),
+            BTreeMap::from([(canonical.clone(), Access::Write)])
+        );
+
+        // Another spelling of the same file, read only
+        let dotted = dir.path().join("sub/../main.rs");
+        let view = call(
+            "developer__text_editor",
+            json!({"command": "view", "path": dotted.to_str().unwrap()}),
+        );
+        assert_eq!(
+            resources_for(&view),
+            BTreeMap::from([(canonical.clone(), Access::Read)])
+        );
+
+        // A file that doesn't exist yet goes by its canonical parent
+        let new_file = dir.path().join("new.rs");
+        let write = call(
+            "developer__text_editor",
+            json!({"command": "write", "path": new_file.to_str().unwrap()}),
+        );
+        assert_eq!(
+            resources_for(&write),
+            BTreeMap::from([(
+                dir.path().canonicalize().unwrap().join("new.rs"),
+                Access::Write
+            )])
+        );
+
+        assert!(resources_for(&call("developer__shell", json!({"command": "ls"}))).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_writes_to_one_file_serialize() {
+        let locks = Arc::new(ToolLocks::default());
+        let resources = BTreeMap::from([(PathBuf::from("/tmp/shared.txt"), Access::Write)]);
+
+        let first = locks.acquire(&resources).await;
+        let waiting = {
+            let locks = locks.clone();
+            let resources = resources.clone();
+            tokio::spawn(async move { locks.acquire(&resources).await.waited })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        drop(first);
+        assert!(waiting.await.unwrap() >= Duration::from_millis(40));
+
+        // Readers share the lock
+        let reads = BTreeMap::from([(PathBuf::from("/tmp/shared.txt"), Access::Read)]);
+        let _a = locks.acquire(&reads).await;
+        let b = tokio::time::timeout(Duration::from_millis(100), locks.acquire(&reads)).await;
+        assert!(b.is_ok());
+    }
+}
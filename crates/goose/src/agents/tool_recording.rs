@@ -0,0 +1,369 @@
+//! Record tool calls made against an MCP server and replay them against another build of it.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rmcp::model::{
+    CallToolResult, GetPromptResult, InitializeResult, JsonObject, ListPromptsResult,
+    ListResourcesResult, ListToolsResult, ReadResourceResult, ServerNotification,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use super::mcp_client::{Error, McpClientTrait};
+use crate::config::Config;
+
+pub const RECORDING_DIR_KEY: &str = "GOOSE_TOOL_RECORDING_DIR";
+
+/// A single recorded tool invocation and what the server answered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRecord {
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<JsonObject>,
+    /// The serialized `CallToolResult`, or the error message if the call failed
+    #[serde(flatten)]
+    pub outcome: ToolOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolOutcome {
+    Result(Value),
+    Error(String),
+}
+
+impl ToolOutcome {
+    fn from_call(result: &Result<CallToolResult, Error>) -> Self {
+        match result {
+            Ok(result) => match serde_json::to_value(result) {
+                Ok(value) => ToolOutcome::Result(value),
+                Err(e) => ToolOutcome::Error(format!("unserializable result: {}", e)),
+            },
+            Err(e) => ToolOutcome::Error(e.to_string()),
+        }
+    }
+}
+
+/// Where recordings for an extension go, if recording is enabled
+pub fn recording_path(extension_name: &str) -> Option<PathBuf> {
+    let dir = std::env::var(RECORDING_DIR_KEY)
+        .ok()
+        .or_else(|| Config::global().get_param::<String>(RECORDING_DIR_KEY).ok())?;
+    if dir.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(dir).join(format!("{}.jsonl", extension_name)))
+}
+
+/// Wraps an MCP client and appends every tool call to a JSONL file.
+pub struct RecordingClient {
+    inner: Box<dyn McpClientTrait>,
+    file: std::sync::Mutex<File>,
+}
+
+impl RecordingClient {
+    pub fn new(inner: Box<dyn McpClientTrait>, path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open recording file {}", path.display()))?;
+        Ok(Self {
+            inner,
+            file: std::sync::Mutex::new(file),
+        })
+    }
+
+    fn record(&self, record: &ToolRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize tool record: {}", e);
+                return;
+            }
+        };
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(file, "{}", line) {
+            warn!("Failed to write tool record: {}", e);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl McpClientTrait for RecordingClient {
+    async fn list_resources(
+        &self,
+        next_cursor: Option<String>,
+        cancel_token: CancellationToken,
+    ) -> Result<ListResourcesResult, Error> {
+        self.inner.list_resources(next_cursor, cancel_token).await
+    }
+
+    async fn read_resource(
+        &self,
+        uri: &str,
+        cancel_token: CancellationToken,
+    ) -> Result<ReadResourceResult, Error> {
+        self.inner.read_resource(uri, cancel_token).await
+    }
+
+    async fn list_tools(
+        &self,
+        next_cursor: Option<String>,
+        cancel_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        self.inner.list_tools(next_cursor, cancel_token).await
+    }
+
+    async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+        cancel_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let result = self
+            .inner
+            .call_tool(name, arguments.clone(), cancel_token)
+            .await;
+        self.record(&ToolRecord {
+            tool: name.to_string(),
+            arguments,
+            outcome: ToolOutcome::from_call(&result),
+        });
+        result
+    }
+
+    async fn list_prompts(
+        &self,
+        next_cursor: Option<String>,
+        cancel_token: CancellationToken,
+    ) -> Result<ListPromptsResult, Error> {
+        self.inner.list_prompts(next_cursor, cancel_token).await
+    }
+
+    async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: Value,
+        cancel_token: CancellationToken,
+    ) -> Result<GetPromptResult, Error> {
+        self.inner.get_prompt(name, arguments, cancel_token).await
+    }
+
+    async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+        self.inner.subscribe().await
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        self.inner.get_info()
+    }
+}
+
+pub fn load_records(path: &Path) -> Result<Vec<ToolRecord>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open recording file {}", path.display()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+        .map(|(i, line)| {
+            let line = line?;
+            serde_json::from_str(&line)
+                .with_context(|| format!("Invalid record on line {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+/// A recorded call whose replayed outcome differs from the original
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayDiff {
+    /// Index of the call in the recording
+    pub index: usize,
+    pub tool: String,
+    /// JSON pointers to the fields that differ, "" meaning the whole outcome
+    pub paths: Vec<String>,
+    pub expected: ToolOutcome,
+    pub actual: ToolOutcome,
+}
+
+/// Re-issue each recorded call against `client`, returning the calls whose outcome changed.
+pub async fn replay(
+    records: &[ToolRecord],
+    client: &dyn McpClientTrait,
+    cancel_token: CancellationToken,
+) -> Vec<ReplayDiff> {
+    let mut diffs = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        let result = client
+            .call_tool(&record.tool, record.arguments.clone(), cancel_token.clone())
+            .await;
+        let actual = ToolOutcome::from_call(&result);
+        let paths = match (&record.outcome, &actual) {
+            (ToolOutcome::Result(expected), ToolOutcome::Result(actual)) => {
+                let mut paths = Vec::new();
+                diff_values("", expected, actual, &mut paths);
+                paths
+            }
+            (expected, actual) if expected == actual => Vec::new(),
+            _ => vec![String::new()],
+        };
+        if !paths.is_empty() {
+            diffs.push(ReplayDiff {
+                index,
+                tool: record.tool.clone(),
+                paths,
+                expected: record.outcome.clone(),
+                actual,
+            });
+        }
+    }
+    diffs
+}
+
+fn diff_values(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_values(&child, x, y, out),
+                    _ => out.push(child),
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                diff_values(&format!("{}/{}", path, i), x, y, out);
+            }
+        }
+        _ if expected != actual => out.push(path.to_string()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+    use rmcp::object;
+
+    struct EchoClient {
+        suffix: &'static str,
+    }
+
+    #[async_trait::async_trait]
+    impl McpClientTrait for EchoClient {
+        async fn list_resources(
+            &self,
+            _next_cursor: Option<String>,
+            _cancel_token: CancellationToken,
+        ) -> Result<ListResourcesResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn read_resource(
+            &self,
+            _uri: &str,
+            _cancel_token: CancellationToken,
+        ) -> Result<ReadResourceResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn list_tools(
+            &self,
+            _next_cursor: Option<String>,
+            _cancel_token: CancellationToken,
+        ) -> Result<ListToolsResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn call_tool(
+            &self,
+            name: &str,
+            arguments: Option<JsonObject>,
+            _cancel_token: CancellationToken,
+        ) -> Result<CallToolResult, Error> {
+            if name != "echo" {
+                return Err(Error::TransportClosed);
+            }
+            let message = arguments
+                .and_then(|args| args.get("message").cloned())
+                .and_then(|m| m.as_str().map(str::to_string))
+                .unwrap_or_default();
+            Ok(CallToolResult::success(vec![Content::text(format!(
+                "{}{}",
+                message, self.suffix
+            ))]))
+        }
+
+        async fn list_prompts(
+            &self,
+            _next_cursor: Option<String>,
+            _cancel_token: CancellationToken,
+        ) -> Result<ListPromptsResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn get_prompt(
+            &self,
+            _name: &str,
+            _arguments: Value,
+            _cancel_token: CancellationToken,
+        ) -> Result<GetPromptResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn subscribe(&self) -> mpsc::Receiver<ServerNotification> {
+            mpsc::channel(1).1
+        }
+
+        fn get_info(&self) -> Option<&InitializeResult> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("echo.jsonl");
+
+        let recorder = RecordingClient::new(Box::new(EchoClient { suffix: "" }), &path).unwrap();
+        for tool in ["echo", "missing"] {
+            let _ = recorder
+                .call_tool(
+                    tool,
+                    Some(object!({"message": "hi"})),
+                    CancellationToken::default(),
+                )
+                .await;
+        }
+
+        let records = load_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tool, "echo");
+        assert!(matches!(records[1].outcome, ToolOutcome::Error(_)));
+
+        let same = EchoClient { suffix: "" };
+        assert!(replay(&records, &same, CancellationToken::default())
+            .await
+            .is_empty());
+
+        let changed = EchoClient { suffix: "!" };
+        let diffs = replay(&records, &changed, CancellationToken::default()).await;
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].index, 0);
+        assert_eq!(diffs[0].paths, vec!["/content/0/text".to_string()]);
+    }
+}
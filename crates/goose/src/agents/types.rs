@@ -77,6 +77,18 @@ pub struct FrontendTool {
     pub tool: Tool,
 }
 
+/// Why the last reply stopped, which headless runs turn into an exit code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StopReason {
+    /// The agent finished on its own, or no reply has run yet
+    #[default]
+    Completed,
+    /// The turn limit was reached before the agent finished
+    MaxTurnsReached,
+    /// The recipe's success checks still failed after the last retry
+    ChecksFailed,
+}
+
 /// Session configuration for an agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionConfig {
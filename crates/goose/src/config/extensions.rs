@@ -28,6 +28,12 @@ pub fn name_to_key(name: &str) -> String {
         .to_lowercase()
 }
 
+/// The secret holding `key` for the extension `name` alone, so extensions that need the same
+/// variable, such as `OAUTH_CLIENT_SECRET`, don't overwrite each other's value
+pub fn extension_secret_key(name: &str, key: &str) -> String {
+    format!("{}__{}", key, name_to_key(name))
+}
+
 pub struct ExtensionConfigManager;
 
 impl ExtensionConfigManager {
@@ -97,7 +103,7 @@ impl ExtensionConfigManager {
                                 bundled: Some(true),
                                 available_tools: Vec::new(),
                             },
-                            enabled: true,
+                            enabled: def.default_enabled,
                         },
                     );
                 }
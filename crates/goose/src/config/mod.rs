@@ -3,6 +3,7 @@ pub mod custom_providers;
 mod experiments;
 pub mod extensions;
 pub mod permission;
+pub mod prompt_sections;
 pub mod signup_openrouter;
 pub mod signup_tetrate;
 
@@ -12,6 +13,7 @@ pub use custom_providers::CustomProviderConfig;
 pub use experiments::ExperimentManager;
 pub use extensions::{ExtensionConfigManager, ExtensionEntry};
 pub use permission::PermissionManager;
+pub use prompt_sections::{PromptSection, PromptSectionManager};
 pub use signup_openrouter::configure_openrouter;
 pub use signup_tetrate::configure_tetrate;
 
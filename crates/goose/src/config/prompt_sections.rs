@@ -0,0 +1,144 @@
+//! Sections users append to the system prompt.
+
+use super::base::Config;
+use super::extensions::name_to_key;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+const PROMPT_SECTIONS_CONFIG_KEY: &str = "prompt_sections";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptSection {
+    pub name: String,
+    pub text: String,
+    /// goose modes to add the section in; all modes when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<String>,
+    /// Extensions of which at least one has to be enabled; no requirement when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+}
+
+impl PromptSection {
+    /// Whether the section is added in `mode` with `extensions` enabled
+    pub fn applies(&self, mode: &str, extensions: &[String]) -> bool {
+        let mode_matches =
+            self.modes.is_empty() || self.modes.iter().any(|m| m.eq_ignore_ascii_case(mode));
+        let extension_matches = self.extensions.is_empty()
+            || self.extensions.iter().any(|wanted| {
+                extensions
+                    .iter()
+                    .any(|enabled| name_to_key(enabled) == name_to_key(wanted))
+            });
+        mode_matches && extension_matches
+    }
+
+    fn group(&self) -> u8 {
+        match (self.modes.is_empty(), self.extensions.is_empty()) {
+            (true, true) => 0,
+            (false, true) => 1,
+            (true, false) => 2,
+            (false, false) => 3,
+        }
+    }
+}
+
+/// The sections added in `mode` with `extensions` enabled, in prompt order
+pub fn applicable_sections<'a>(
+    sections: &'a [PromptSection],
+    mode: &str,
+    extensions: &[String],
+) -> Vec<&'a PromptSection> {
+    let mut applicable: Vec<&PromptSection> = sections
+        .iter()
+        .filter(|section| section.applies(mode, extensions))
+        .collect();
+    applicable.sort_by(|a, b| (a.group(), &a.name).cmp(&(b.group(), &b.name)));
+    applicable
+}
+
+pub struct PromptSectionManager;
+
+impl PromptSectionManager {
+    pub fn get_all() -> Vec<PromptSection> {
+        Config::global()
+            .get_param::<Vec<PromptSection>>(PROMPT_SECTIONS_CONFIG_KEY)
+            .unwrap_or_default()
+    }
+
+    /// Add `section`, replacing any section of the same name
+    pub fn set(section: PromptSection) -> Result<()> {
+        if section.name.trim().is_empty() {
+            bail!("A prompt section needs a name");
+        }
+        if section.text.trim().is_empty() {
+            bail!("Prompt section '{}' has no text", section.name);
+        }
+        let mut sections = Self::get_all();
+        sections.retain(|existing| existing.name != section.name);
+        sections.push(section);
+        Self::save(sections)
+    }
+
+    /// Remove the section called `name`; false if there was none
+    pub fn remove(name: &str) -> Result<bool> {
+        let mut sections = Self::get_all();
+        let before = sections.len();
+        sections.retain(|section| section.name != name);
+        if sections.len() == before {
+            return Ok(false);
+        }
+        Self::save(sections)?;
+        Ok(true)
+    }
+
+    fn save(sections: Vec<PromptSection>) -> Result<()> {
+        Config::global().set_param(PROMPT_SECTIONS_CONFIG_KEY, serde_json::to_value(sections)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, modes: &[&str], extensions: &[&str]) -> PromptSection {
+        PromptSection {
+            name: name.to_string(),
+            text: format!("{} text", name),
+            modes: modes.iter().map(|m| m.to_string()).collect(),
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_applies() {
+        let shell = section("shell", &[], &["developer"]);
+        assert!(shell.applies("auto", &["Developer".to_string()]));
+        assert!(!shell.applies("auto", &["memory".to_string()]));
+
+        let approve = section("approve", &["smart_approve", "approve"], &[]);
+        assert!(approve.applies("Approve", &[]));
+        assert!(!approve.applies("auto", &[]));
+    }
+
+    #[test]
+    fn test_applicable_sections_order() {
+        let sections = vec![
+            section("z-both", &["auto"], &["developer"]),
+            section("shell", &[], &["developer"]),
+            section("b-always", &[], &[]),
+            section("auto-only", &["auto"], &[]),
+            section("a-always", &[], &[]),
+            section("chat-only", &["chat"], &[]),
+        ];
+        let names: Vec<&str> = applicable_sections(&sections, "auto", &["developer".to_string()])
+            .into_iter()
+            .map(|section| section.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["a-always", "b-always", "auto-only", "shell", "z-both"]
+        );
+    }
+}
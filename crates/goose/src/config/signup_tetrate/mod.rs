@@ -133,6 +133,7 @@ impl PkceAuthFlow {
 
     /// Complete flow: open browser, wait for callback, exchange code
     pub async fn complete_flow(&mut self) -> Result<String> {
+        crate::offline::require_network("Signing up")?;
         let auth_url = self.get_auth_url();
 
         println!("Opening browser for Tetrate Agent Router Service authentication...");
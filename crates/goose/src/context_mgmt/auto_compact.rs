@@ -324,9 +324,13 @@ mod tests {
             total_tokens: Some(100),
             input_tokens: Some(50),
             output_tokens: Some(50),
+            cached_input_tokens: None,
+            reasoning_tokens: None,
             accumulated_total_tokens: Some(100),
             accumulated_input_tokens: Some(50),
             accumulated_output_tokens: Some(50),
+            accumulated_cached_input_tokens: None,
+            accumulated_reasoning_tokens: None,
             extension_data: extension_data::ExtensionData::new(),
             conversation: Some(conversation),
             message_count,
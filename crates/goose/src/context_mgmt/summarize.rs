@@ -108,11 +108,7 @@ mod tests {
                 ),
                 ProviderUsage::new(
                     "mock".to_string(),
-                    Usage {
-                        input_tokens: Some(100),
-                        output_tokens: Some(50),
-                        total_tokens: Some(150),
-                    },
+                    Usage::new(Some(100), Some(50), Some(150)),
                 ),
             ))
         }
@@ -555,6 +555,23 @@ impl Message {
         self.with_content(MessageContent::tool_request(id, tool_call))
     }
 
+    /// Replace the arguments of the tool request `id`, as when the user edited them before
+    /// allowing the call
+    pub fn with_tool_call_arguments(mut self, id: &str, arguments: &JsonObject) -> Self {
+        for content in &mut self.content {
+            let MessageContent::ToolRequest(request) = content else {
+                continue;
+            };
+            if request.id != id {
+                continue;
+            }
+            if let Ok(tool_call) = &mut request.tool_call {
+                tool_call.arguments = Some(arguments.clone());
+            }
+        }
+        self
+    }
+
     /// Add a tool response to the message
     pub fn with_tool_response<S: Into<String>>(
         self,
@@ -786,6 +803,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_with_tool_call_arguments() {
+        let message = Message::assistant()
+            .with_tool_request(
+                "edited",
+                Ok(CallToolRequestParam {
+                    name: "shell".into(),
+                    arguments: Some(object!({"command": "rm -rf build"})),
+                }),
+            )
+            .with_tool_request(
+                "kept",
+                Ok(CallToolRequestParam {
+                    name: "shell".into(),
+                    arguments: Some(object!({"command": "ls"})),
+                }),
+            )
+            .with_tool_call_arguments("edited", &object!({"command": "rm -rf build/tmp"}));
+
+        let arguments: Vec<_> = message
+            .content
+            .iter()
+            .filter_map(|content| content.as_tool_request())
+            .map(|request| {
+                request
+                    .tool_call
+                    .as_ref()
+                    .unwrap()
+                    .arguments
+                    .clone()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            arguments,
+            vec![
+                object!({"command": "rm -rf build/tmp"}),
+                object!({"command": "ls"})
+            ]
+        );
+    }
+
     #[test]
     fn test_error_serialization() {
         let message = Message::assistant().with_tool_request(
@@ -7,6 +7,7 @@ pub mod logging;
 pub mod mcp_utils;
 pub mod model;
 pub mod oauth;
+pub mod offline;
 pub mod permission;
 pub mod prompt_template;
 pub mod providers;
@@ -0,0 +1,334 @@
+//! Retries and a circuit breaker for the requests of signing in and refreshing tokens.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::error::leading_status;
+use super::events::redact;
+use super::AuthError;
+
+/// Tries of one request or operation, the first included
+const ATTEMPTS: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+/// The longest pause between tries; a server asking for more is not tried again
+const MAX_DELAY: Duration = Duration::from_secs(30);
+/// Failed requests or operations in a row that open a host's circuit
+const FAILURE_THRESHOLD: u32 = 3;
+pub const COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+static BREAKERS: Lazy<std::sync::Mutex<HashMap<String, Breaker>>> = Lazy::new(Default::default);
+
+/// The host a circuit belongs to, `host:port` as in token keys
+fn host_of(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Fail fast while `host`'s circuit is open
+fn check(host: &str) -> Result<(), AuthError> {
+    let breakers = BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    match breakers.get(host).and_then(|breaker| breaker.open_until) {
+        Some(open_until) if open_until > now => Err(AuthError::Throttled {
+            host: host.to_string(),
+            retry_in: open_until - now,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Note how the last request or operation to `host` went, after its retries
+fn record(host: &str, failed: bool) {
+    let mut breakers = BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    if !failed {
+        breakers.remove(host);
+        return;
+    }
+    let breaker = breakers.entry(host.to_string()).or_default();
+    breaker.failures += 1;
+    if breaker.failures >= FAILURE_THRESHOLD {
+        warn!(
+            "{} failed {} times in a row; leaving it alone for {}s",
+            host,
+            breaker.failures,
+            COOLDOWN.as_secs()
+        );
+        breaker.open_until = Some(Instant::now() + COOLDOWN);
+    }
+}
+
+/// The pause a `Retry-After` header asks for, in seconds or as an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// How long to wait before try `attempt` (counting from 1 for the first retry), or `None` when
+/// the server wants a longer pause than is worth waiting for
+fn delay(attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+    if let Some(retry_after) = retry_after {
+        return (retry_after <= MAX_DELAY).then_some(retry_after);
+    }
+    let exponential = BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt - 1));
+    // Up to a quarter more, so clients that failed together don't retry together
+    let jitter = exponential.mul_f64(rand::random::<f64>() / 4.0);
+    Some((exponential + jitter).min(MAX_DELAY))
+}
+
+fn is_transient_code(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    is_transient_code(status.as_u16())
+}
+
+/// Send `request`, trying again while the server is unreachable, overloaded or erroring. The
+/// last response is returned whatever its status, for the caller to make sense of.
+pub async fn send(request: RequestBuilder) -> Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let host = host_of(request.url().as_str());
+    check(&host)?;
+
+    let mut attempt = 1;
+    loop {
+        let Some(copy) = request.try_clone() else {
+            // A streaming body can only be sent once
+            return Ok(client.execute(request).await?);
+        };
+        let outcome = client.execute(copy).await;
+        let wait = match &outcome {
+            Ok(resp) if is_transient_status(resp.status()) => {
+                delay(attempt, retry_after(resp.headers()))
+            }
+            Err(e) if e.is_connect() || e.is_timeout() => delay(attempt, None),
+            Ok(_) => {
+                record(&host, false);
+                return Ok(outcome?);
+            }
+            Err(_) => return Ok(outcome?),
+        };
+        match wait {
+            Some(wait) if attempt < ATTEMPTS => {
+                debug!(
+                    "{} failed, trying again in {}ms",
+                    request.url(),
+                    wait.as_millis()
+                );
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+            }
+            _ => {
+                record(&host, true);
+                return Ok(outcome?);
+            }
+        }
+    }
+}
+
+/// Send `request` once, for requests that must not be repeated such as trading a single-use
+/// authorization code. An open circuit still fails it fast, and its outcome counts towards the
+/// host's circuit like any other.
+pub async fn send_once(request: RequestBuilder) -> Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let host = host_of(request.url().as_str());
+    check(&host)?;
+    let outcome = client.execute(request).await;
+    let failed = match &outcome {
+        Ok(resp) => is_transient_status(resp.status()),
+        Err(e) => e.is_connect() || e.is_timeout(),
+    };
+    record(&host, failed);
+    Ok(outcome?)
+}
+
+/// Whether an operation failed in a way that may pass when tried again shortly
+fn is_transient(error: &AuthError) -> bool {
+    match error {
+        AuthError::DiscoveryFailed(_) => true,
+        AuthError::TokenExchangeFailed {
+            status: Some(status),
+            ..
+        } => is_transient_code(*status),
+        // rmcp only gives the refresh endpoint's answer as text
+        AuthError::RefreshFailed(message) => leading_status(message).is_some_and(is_transient_code),
+        _ => false,
+    }
+}
+
+/// Run `operation` against the authorization server at `url` until it works, fails for good,
+/// or has been tried [`ATTEMPTS`] times
+pub async fn with_retries<T, E, F, Fut>(url: &str, mut operation: F) -> Result<T, AuthError>
+where
+    E: Into<AuthError>,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let host = host_of(url);
+    check(&host)?;
+    let mut attempt = 1;
+    loop {
+        match operation().await.map_err(Into::into) {
+            Ok(value) => {
+                record(&host, false);
+                return Ok(value);
+            }
+            Err(e) if is_transient(&e) && attempt < ATTEMPTS => {
+                let wait = delay(attempt, None).unwrap_or(MAX_DELAY);
+                debug!(
+                    "Signing in at {} failed, trying again in {}ms: {}",
+                    host,
+                    wait.as_millis(),
+                    redact(&e.to_string())
+                );
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                record(&host, is_transient(&e));
+                return Err(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_delay() {
+        let first = delay(1, None).unwrap();
+        assert!(first >= BASE_DELAY && first <= BASE_DELAY.mul_f64(1.25));
+        let third = delay(3, None).unwrap();
+        assert!(third >= BASE_DELAY * 4);
+        assert_eq!(delay(30, None), Some(MAX_DELAY));
+        assert_eq!(
+            delay(1, Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(delay(1, Some(Duration::from_secs(600))), None);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_send_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let http = reqwest::Client::new();
+        let resp = send(
+            http.post(format!("{}/token", server.uri()))
+                .form(&[("grant_type", "client_credentials")]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_send_once() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .mount(&server)
+            .await;
+
+        let http = reqwest::Client::new();
+        let resp = send_once(
+            http.post(format!("{}/token", server.uri()))
+                .form(&[("grant_type", "authorization_code"), ("code", "abc")]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_breaker() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500).insert_header("Retry-After", "0"))
+            .mount(&server)
+            .await;
+        let http = reqwest::Client::new();
+        let url = format!("{}/.well-known/oauth-authorization-server", server.uri());
+
+        for _ in 0..FAILURE_THRESHOLD {
+            let resp = send(http.get(&url)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        let requests = server.received_requests().await.unwrap().len();
+        assert_eq!(requests, (FAILURE_THRESHOLD * ATTEMPTS) as usize);
+
+        let error = send(http.get(&url)).await.unwrap_err();
+        assert!(matches!(
+            AuthError::from(error),
+            AuthError::Throttled { .. }
+        ));
+        assert!(matches!(
+            with_retries(&url, || async { Ok::<_, AuthError>(()) }).await,
+            Err(AuthError::Throttled { .. })
+        ));
+        assert_eq!(server.received_requests().await.unwrap().len(), requests);
+    }
+}
@@ -0,0 +1,146 @@
+//! The page the browser lands on when the authorization server redirects back to goose.
+
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use minijinja::{context, AutoEscape, Environment};
+use tracing::warn;
+
+use crate::config::Config;
+
+const DEFAULT_SUCCESS_TEMPLATE: &str = include_str!("oauth_callback.html");
+const DEFAULT_ERROR_TEMPLATE: &str =
+    "<h2>Sign-in to {{ name }} failed</h2><p>{{ message }}</p><p>You can close this window.</p>";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallbackPage {
+    /// URL to send the browser to once signed in, instead of showing a page
+    pub success_redirect: Option<String>,
+    /// HTML template file for the success page
+    pub success_template: Option<String>,
+    /// HTML template file for the error page
+    pub error_template: Option<String>,
+}
+
+impl CallbackPage {
+    pub fn from_config(service: &str) -> Self {
+        let config = Config::global();
+        let get = |setting: &str| {
+            config
+                .get_param::<String>(&format!(
+                    "{}_OAUTH_{}",
+                    service.to_uppercase().replace(['-', ' '], "_"),
+                    setting
+                ))
+                .or_else(|_| config.get_param::<String>(&format!("GOOSE_OAUTH_{}", setting)))
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        Self {
+            success_redirect: get("SUCCESS_REDIRECT"),
+            success_template: get("SUCCESS_TEMPLATE"),
+            error_template: get("ERROR_TEMPLATE"),
+        }
+    }
+
+    pub fn success(&self, name: &str) -> Response {
+        if let Some(url) = &self.success_redirect {
+            return Redirect::to(url).into_response();
+        }
+        let html = render(
+            self.success_template.as_deref(),
+            DEFAULT_SUCCESS_TEMPLATE,
+            context! { name },
+        );
+        Html(html).into_response()
+    }
+
+    pub fn error(&self, name: &str, message: &str) -> Response {
+        let html = render(
+            self.error_template.as_deref(),
+            DEFAULT_ERROR_TEMPLATE,
+            context! { name, message },
+        );
+        Html(html).into_response()
+    }
+}
+
+/// The template at `path` rendered with `context`, or else `default`. A broken template
+/// shouldn't cost the user a sign-in that already worked, so it only gets a warning.
+fn render(path: Option<&str>, default: &str, context: minijinja::Value) -> String {
+    let mut env = Environment::new();
+    // `message` comes from the redirect's query string
+    env.set_auto_escape_callback(|_| AutoEscape::Html);
+    if let Some(path) = path {
+        match std::fs::read_to_string(path) {
+            Ok(template) => match env.render_str(&template, context.clone()) {
+                Ok(html) => return html,
+                Err(e) => warn!("Failed to render the OAuth page template {}: {}", path, e),
+            },
+            Err(e) => warn!("Failed to read the OAuth page template {}: {}", path, e),
+        }
+    }
+    env.render_str(default, context)
+        .unwrap_or_else(|_| default.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header, StatusCode};
+
+    #[test]
+    fn test_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path().join("success.html");
+        std::fs::write(
+            &template,
+            "<p>Signed in to {{ name }}</p><script>window.close()</script>",
+        )
+        .unwrap();
+        let path = template.to_string_lossy().to_string();
+        assert_eq!(
+            render(
+                Some(&path),
+                DEFAULT_SUCCESS_TEMPLATE,
+                context! { name => "docs" }
+            ),
+            "<p>Signed in to docs</p><script>window.close()</script>"
+        );
+
+        let missing = dir
+            .path()
+            .join("missing.html")
+            .to_string_lossy()
+            .to_string();
+        let html = render(
+            Some(&missing),
+            DEFAULT_ERROR_TEMPLATE,
+            context! { name => "docs", message => "access_denied" },
+        );
+        assert!(html.contains("access_denied"));
+
+        let html = render(
+            None,
+            DEFAULT_ERROR_TEMPLATE,
+            context! { name => "docs", message => "<script>alert(1)</script>" },
+        );
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_success_redirect() {
+        let page = CallbackPage {
+            success_redirect: Some("https://intranet.example.com/signed-in".to_string()),
+            ..Default::default()
+        };
+        let response = page.success("docs");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "https://intranet.example.com/signed-in"
+        );
+        assert_eq!(
+            CallbackPage::default().success("docs").status(),
+            StatusCode::OK
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! The client credentials grant (RFC 6749 section 4.4) for MCP servers used by machines.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use rmcp::transport::AuthorizationManager;
+
+use super::backoff;
+use super::oidc;
+use super::resource_metadata;
+use super::restore_authorization;
+use super::token_store::{OAuthTokenResponse, StoredToken};
+use super::AuthError;
+
+pub const CLIENT_ID_ENV: &str = "OAUTH_CLIENT_ID";
+pub const CLIENT_SECRET_ENV: &str = "OAUTH_CLIENT_SECRET";
+/// Optional, space separated
+pub const SCOPE_ENV: &str = "OAUTH_SCOPE";
+/// Optional; without it the token endpoint is discovered from the server's OAuth metadata
+pub const TOKEN_URL_ENV: &str = "OAUTH_TOKEN_URL";
+
+/// `token_url` if set, or else the token endpoint in the OAuth metadata of the authorization
+/// server (or of the MCP server itself)
+pub(super) async fn token_endpoint(
+    token_url: Option<&str>,
+    mcp_server_url: &str,
+    authorization_server: Option<&str>,
+    http: &reqwest::Client,
+) -> Result<String> {
+    if let Some(token_url) = token_url {
+        return Ok(token_url.to_string());
+    }
+    let discovery_url = authorization_server.unwrap_or(mcp_server_url);
+    let mut authorization_manager = AuthorizationManager::new(discovery_url).await?;
+    authorization_manager.with_client(http.clone())?;
+    let metadata = authorization_manager
+        .discover_metadata()
+        .await
+        .with_context(|| format!("Failed to discover OAuth metadata for {}", discovery_url))?;
+    Ok(metadata.token_endpoint)
+}
+
+#[derive(Clone)]
+pub struct ClientCredentials {
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+    pub token_url: Option<String>,
+}
+
+impl std::fmt::Debug for ClientCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCredentials")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"[redacted]")
+            .field("scope", &self.scope)
+            .field("token_url", &self.token_url)
+            .finish()
+    }
+}
+
+impl ClientCredentials {
+    /// Read the credentials from an extension's environment (its `envs` merged with the
+    /// secrets named in `env_keys`)
+    pub fn from_envs(envs: &HashMap<String, String>) -> Result<Self> {
+        let get = |key: &str| {
+            envs.get(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Ok(Self {
+            client_id: get(CLIENT_ID_ENV)
+                .ok_or_else(|| anyhow!("{} is not set for this extension", CLIENT_ID_ENV))?,
+            client_secret: get(CLIENT_SECRET_ENV)
+                .ok_or_else(|| anyhow!("{} is not set for this extension", CLIENT_SECRET_ENV))?,
+            scope: get(SCOPE_ENV),
+            token_url: get(TOKEN_URL_ENV),
+        })
+    }
+
+    /// A token from the token endpoint, and its `id_token` if the server sent one
+    pub async fn request_token(
+        &self,
+        mcp_server_url: &str,
+        authorization_server: Option<&str>,
+        http: &reqwest::Client,
+    ) -> Result<(OAuthTokenResponse, Option<String>)> {
+        let token_endpoint = token_endpoint(
+            self.token_url.as_deref(),
+            mcp_server_url,
+            authorization_server,
+            http,
+        )
+        .await?;
+        let mut params = vec![("grant_type", "client_credentials")];
+        if let Some(scope) = &self.scope {
+            params.push(("scope", scope.as_str()));
+        }
+
+        let resp = backoff::send(
+            http.post(&token_endpoint)
+                .basic_auth(&self.client_id, Some(&self.client_secret))
+                .form(&params),
+        )
+        .await?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let err_text = resp.text().await?;
+            return Err(AuthError::TokenExchangeFailed {
+                status: Some(status),
+                message: format!("client credentials refused: {}", err_text),
+            }
+            .into());
+        }
+        let body: serde_json::Value = resp.json().await?;
+        let id_token = oidc::id_token_of(&body);
+        Ok((serde_json::from_value(body)?, id_token))
+    }
+
+    /// A fresh token for `mcp_server_url`, ready for the transport
+    pub async fn authorize(
+        &self,
+        mcp_server_url: &str,
+        http: &reqwest::Client,
+    ) -> Result<(AuthorizationManager, StoredToken)> {
+        let authorization_server = match &self.token_url {
+            Some(_) => None,
+            None => resource_metadata::authorization_server(mcp_server_url, None, http).await,
+        };
+        let (token_response, id_token) = self
+            .request_token(mcp_server_url, authorization_server.as_deref(), http)
+            .await?;
+        let mut token = StoredToken::new(self.client_id.clone(), token_response);
+        if let (Some(id_token), Some(issuer)) = (
+            id_token,
+            oidc::issuer_for(mcp_server_url, authorization_server.as_deref()),
+        ) {
+            token.identity = oidc::identify(&token, &id_token, None, &issuer, http).await;
+        }
+        let token = token.with_authorization_server(authorization_server);
+        let authorization_manager =
+            restore_authorization(mcp_server_url, &token, None, http).await?;
+        Ok((authorization_manager, token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oauth2::TokenResponse;
+    use wiremock::matchers::{body_string_contains, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_from_envs() {
+        let envs = HashMap::from([
+            (CLIENT_ID_ENV.to_string(), "service-account".to_string()),
+            (CLIENT_SECRET_ENV.to_string(), "s3cret".to_string()),
+        ]);
+        let credentials = ClientCredentials::from_envs(&envs).unwrap();
+        assert_eq!(credentials.client_id, "service-account");
+        assert!(credentials.scope.is_none());
+        assert!(!format!("{:?}", credentials).contains("s3cret"));
+
+        let missing = HashMap::from([(CLIENT_ID_ENV.to_string(), "service-account".to_string())]);
+        assert!(ClientCredentials::from_envs(&missing).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(header_exists("authorization"))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .and(body_string_contains("scope=mcp%3Aread"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "machine-token",
+                "token_type": "bearer",
+                "expires_in": 3600
+            })))
+            .mount(&server)
+            .await;
+
+        let credentials = ClientCredentials {
+            client_id: "service-account".to_string(),
+            client_secret: "s3cret".to_string(),
+            scope: Some("mcp:read".to_string()),
+            token_url: Some(format!("{}/oauth/token", server.uri())),
+        };
+        let (token, id_token) = credentials
+            .request_token(
+                &format!("{}/mcp", server.uri()),
+                None,
+                &reqwest::Client::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token().secret(), "machine-token");
+        assert!(id_token.is_none());
+    }
+}
@@ -0,0 +1,206 @@
+//! Why signing in to an MCP server failed, in terms a caller can act on.
+
+use std::time::Duration;
+
+use rmcp::transport::AuthError as RmcpAuthError;
+
+use super::sign_in_hint;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    /// The OAuth metadata of the server, or of its authorization server, can't be had
+    #[error("Could not find out how to sign in: {0}")]
+    DiscoveryFailed(String),
+
+    /// The server publishes no OAuth metadata, so there is no sign-in to try again
+    #[error("The server publishes no OAuth metadata to sign in with")]
+    NoAuthorizationSupport,
+
+    /// There is no pre-registered client and the server doesn't do dynamic registration
+    #[error("Dynamic client registration failed: {0}")]
+    RegistrationUnsupported(String),
+
+    /// The user, or a policy of the authorization server, turned the sign-in down
+    #[error("Sign-in was denied: {0}")]
+    UserDenied(String),
+
+    /// The browser didn't come back in time
+    #[error("Sign-in was not finished within {}s", .0.as_secs())]
+    Timeout(Duration),
+
+    /// Ctrl-C while waiting for the browser
+    #[error("Sign-in was cancelled")]
+    Cancelled,
+
+    /// The token endpoint refused the code, credentials or assertion; `status` is its HTTP
+    /// status when known
+    #[error("The token endpoint refused to issue a token{}: {message}", status_suffix(.status))]
+    TokenExchangeFailed {
+        status: Option<u16>,
+        message: String,
+    },
+
+    /// The refresh token was turned down or has run out
+    #[error("Could not refresh the access token: {0}")]
+    RefreshFailed(String),
+
+    /// `host` kept failing, so goose doesn't contact it for a while; see [`super::backoff`]
+    #[error("{host} keeps failing; not asking it again for {}s", .retry_in.as_secs())]
+    Throttled { host: String, retry_in: Duration },
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl AuthError {
+    /// The error a redirect carried back from the authorization server, per RFC 6749
+    /// section 4.1.2.1
+    pub fn from_redirect(error: &str, description: Option<&str>) -> Self {
+        let message = match description {
+            Some(description) => format!("{} ({})", error, description),
+            None => error.to_string(),
+        };
+        if error == "access_denied" {
+            Self::UserDenied(message)
+        } else {
+            Self::Other(anyhow::anyhow!("Authorization failed: {}", message))
+        }
+    }
+
+    /// Whether trying again as it is may work
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::DiscoveryFailed(_) | Self::Timeout(_) | Self::Throttled { .. }
+        ) || matches!(self, Self::TokenExchangeFailed { status: Some(status), .. } if *status >= 500)
+    }
+
+    /// Whether the user has to sign in again in the browser
+    pub fn needs_sign_in(&self) -> bool {
+        matches!(self, Self::RefreshFailed(_))
+            || matches!(
+                self,
+                Self::TokenExchangeFailed {
+                    status: Some(400 | 401),
+                    ..
+                }
+            )
+    }
+
+    /// Whether the extension can't sign in until its configuration changes
+    pub fn needs_configuration(&self) -> bool {
+        matches!(
+            self,
+            Self::NoAuthorizationSupport | Self::RegistrationUnsupported(_)
+        )
+    }
+
+    /// What the user can do about it when signing `name` in failed this way
+    pub fn hint(&self, name: &str) -> Option<String> {
+        if self.needs_configuration() {
+            Some(format!("edit {} with `goose configure`", name))
+        } else if self.needs_sign_in() || self.is_retryable() {
+            Some(sign_in_hint(name))
+        } else {
+            None
+        }
+    }
+}
+
+fn status_suffix(status: &Option<u16>) -> String {
+    status
+        .map(|status| format!(" ({})", status))
+        .unwrap_or_default()
+}
+
+/// The HTTP status in a token endpoint error, as rmcp formats them
+pub(super) fn leading_status(message: &str) -> Option<u16> {
+    message
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 3)
+        .and_then(|part| part.parse().ok())
+        .filter(|status| (400..600).contains(status))
+}
+
+impl From<RmcpAuthError> for AuthError {
+    fn from(error: RmcpAuthError) -> Self {
+        match error {
+            RmcpAuthError::MetadataError(reason) => Self::DiscoveryFailed(reason),
+            RmcpAuthError::NoAuthorizationSupport => Self::NoAuthorizationSupport,
+            RmcpAuthError::RegistrationFailed(reason) => Self::RegistrationUnsupported(reason),
+            RmcpAuthError::TokenExchangeFailed(message) => Self::TokenExchangeFailed {
+                status: leading_status(&message),
+                message,
+            },
+            RmcpAuthError::TokenRefreshFailed(reason) => Self::RefreshFailed(reason),
+            other => Self::Other(other.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AuthError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<AuthError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        match error.downcast::<RmcpAuthError>() {
+            Ok(error) => error.into(),
+            Err(error) => Self::Other(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_rmcp() {
+        let error: AuthError =
+            RmcpAuthError::TokenExchangeFailed("400 Bad Request: invalid_grant".to_string()).into();
+        assert!(matches!(
+            error,
+            AuthError::TokenExchangeFailed {
+                status: Some(400),
+                ..
+            }
+        ));
+        assert!(error.needs_sign_in());
+
+        let error: AuthError = RmcpAuthError::NoAuthorizationSupport.into();
+        assert!(!error.is_retryable() && error.needs_configuration());
+
+        let error: AuthError =
+            RmcpAuthError::RegistrationFailed("404 Not Found".to_string()).into();
+        assert!(error.needs_configuration());
+        assert_eq!(
+            error.hint("docs").unwrap(),
+            "edit docs with `goose configure`"
+        );
+
+        // Typed errors survive a trip through anyhow
+        let error: AuthError = anyhow::Error::from(AuthError::Timeout(Duration::from_secs(5)))
+            .context("Signing in to docs")
+            .into();
+        assert!(matches!(error, AuthError::Timeout(_)));
+        assert!(error.is_retryable());
+        // The user gave up, so trying again on their behalf would be wrong
+        assert!(!AuthError::Cancelled.is_retryable());
+        assert!(matches!(
+            AuthError::from(anyhow::anyhow!("something else")),
+            AuthError::Other(_)
+        ));
+    }
+
+    #[test]
+    fn test_from_redirect() {
+        assert!(matches!(
+            AuthError::from_redirect("access_denied", Some("The user said no")),
+            AuthError::UserDenied(message) if message.contains("The user said no")
+        ));
+        let error = AuthError::from_redirect("server_error", None);
+        assert!(!error.is_retryable() && !error.needs_sign_in());
+        assert_eq!(error.hint("docs"), None);
+    }
+}
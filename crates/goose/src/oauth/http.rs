@@ -0,0 +1,166 @@
+//! The HTTP client for signing in and refreshing tokens.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+/// Proxy value that ignores the proxy variables and connects directly
+pub const DIRECT: &str = "direct";
+
+/// Every OAuth call is a single small request; don't let one hang a sign-in
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct OAuthHttpSettings {
+    /// Proxy URL for OAuth requests, or `direct` to use none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Comma-separated hosts to reach without the proxy, as in `NO_PROXY`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    /// PEM file of extra certificate authorities to trust, such as a corporate proxy's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    /// Accept any TLS certificate. Only for proxies whose CA can't be had any other way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_insecure: Option<bool>,
+}
+
+fn config_key(service: &str, setting: &str) -> String {
+    format!(
+        "{}_OAUTH_{}",
+        service.to_uppercase().replace(['-', ' '], "_"),
+        setting
+    )
+}
+
+impl OAuthHttpSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The settings configured for `service`, falling back to those for all services
+    pub fn from_config(service: &str) -> Self {
+        fn get<T: serde::de::DeserializeOwned>(service: &str, setting: &str) -> Option<T> {
+            let config = Config::global();
+            config
+                .get_param::<T>(&config_key(service, setting))
+                .or_else(|_| config.get_param::<T>(&config_key("GOOSE", setting)))
+                .ok()
+        }
+        let text = |setting: &str| {
+            get::<String>(service, setting).filter(|value| !value.trim().is_empty())
+        };
+        Self {
+            proxy: text("PROXY"),
+            no_proxy: text("NO_PROXY"),
+            ca_bundle: text("CA_BUNDLE"),
+            tls_insecure: get::<bool>(service, "TLS_INSECURE"),
+        }
+    }
+
+    /// These settings, with the ones they leave out taken from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            proxy: self.proxy.or(fallback.proxy),
+            no_proxy: self.no_proxy.or(fallback.no_proxy),
+            ca_bundle: self.ca_bundle.or(fallback.ca_bundle),
+            tls_insecure: self.tls_insecure.or(fallback.tls_insecure),
+        }
+    }
+
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+        match self.proxy.as_deref().map(str::trim) {
+            Some(proxy) if proxy.eq_ignore_ascii_case(DIRECT) => builder = builder.no_proxy(),
+            Some(proxy) => {
+                let no_proxy = self
+                    .no_proxy
+                    .clone()
+                    .or_else(|| std::env::var("NO_PROXY").ok())
+                    .or_else(|| std::env::var("no_proxy").ok());
+                let proxy = reqwest::Proxy::all(proxy)
+                    .with_context(|| format!("Invalid OAuth proxy '{}'", proxy))?
+                    .no_proxy(no_proxy.as_deref().and_then(reqwest::NoProxy::from_string));
+                builder = builder.proxy(proxy);
+            }
+            None => {}
+        }
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read the CA bundle {}", path))?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("{} is not a PEM certificate bundle", path))?;
+            if certificates.is_empty() {
+                anyhow::bail!("The CA bundle {} has no certificates in it", path);
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if self.tls_insecure == Some(true) {
+            tracing::warn!("TLS certificates are not verified for OAuth requests");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        builder
+            .build()
+            .context("Failed to set up the HTTP client for OAuth")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_key() {
+        assert_eq!(config_key("databricks", "PROXY"), "DATABRICKS_OAUTH_PROXY");
+        assert_eq!(
+            config_key("my-docs server", "CA_BUNDLE"),
+            "MY_DOCS_SERVER_OAUTH_CA_BUNDLE"
+        );
+    }
+
+    #[test]
+    fn test_or() {
+        let extension = OAuthHttpSettings {
+            proxy: Some("http://proxy.internal:3128".to_string()),
+            ..Default::default()
+        };
+        let global = OAuthHttpSettings {
+            proxy: Some("http://other:8080".to_string()),
+            ca_bundle: Some("/etc/corp-ca.pem".to_string()),
+            ..Default::default()
+        };
+        let merged = extension.or(global);
+        assert_eq!(merged.proxy.as_deref(), Some("http://proxy.internal:3128"));
+        assert_eq!(merged.ca_bundle.as_deref(), Some("/etc/corp-ca.pem"));
+        assert_eq!(merged.tls_insecure, None);
+    }
+
+    #[test]
+    fn test_client() {
+        assert!(OAuthHttpSettings::default().client().is_ok());
+        assert!(OAuthHttpSettings {
+            proxy: Some(DIRECT.to_string()),
+            tls_insecure: Some(true),
+            ..Default::default()
+        }
+        .client()
+        .is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let not_pem = dir.path().join("ca.pem");
+        std::fs::write(&not_pem, "not a certificate").unwrap();
+        let error = OAuthHttpSettings {
+            ca_bundle: Some(not_pem.to_string_lossy().to_string()),
+            ..Default::default()
+        }
+        .client()
+        .unwrap_err();
+        assert!(error.to_string().contains("ca.pem"));
+    }
+}
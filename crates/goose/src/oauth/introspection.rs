@@ -0,0 +1,201 @@
+//! Checking cached tokens with the authorization server (RFC 7662) before connecting.
+
+use anyhow::{anyhow, Result};
+use oauth2::TokenResponse;
+use rmcp::transport::AuthorizationManager;
+use serde::Deserialize;
+use tracing::debug;
+
+use super::backoff;
+use super::registration::ClientRegistration;
+use super::token_store::StoredToken;
+use crate::config::Config;
+
+pub const INTROSPECT_KEY: &str = "GOOSE_OAUTH_INTROSPECT";
+
+/// What the authorization server said about a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStatus {
+    Active,
+    /// Revoked, expired or unknown to the server
+    Inactive,
+    /// The server can't, or won't, say
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+}
+
+/// Whether cached tokens of `service` are checked before use
+pub fn enabled(service: &str) -> bool {
+    let config = Config::global();
+    config
+        .get_param::<bool>(&format!(
+            "{}_OAUTH_INTROSPECT",
+            service.to_uppercase().replace(['-', ' '], "_")
+        ))
+        .or_else(|_| config.get_param::<bool>(INTROSPECT_KEY))
+        .unwrap_or(false)
+}
+
+/// The introspection endpoint in the OAuth metadata found at `discovery_url`, if it has one
+async fn introspection_endpoint(
+    discovery_url: &str,
+    http: &reqwest::Client,
+) -> Result<Option<String>> {
+    let mut authorization_manager = AuthorizationManager::new(discovery_url).await?;
+    authorization_manager.with_client(http.clone())?;
+    let metadata = serde_json::to_value(authorization_manager.discover_metadata().await?)?;
+    Ok(metadata
+        .get("introspection_endpoint")
+        .and_then(|endpoint| endpoint.as_str())
+        .map(str::to_string))
+}
+
+/// Ask `endpoint` whether the access token of `stored` is active. A pre-registered client
+/// authenticates with its secret; otherwise the request only names the client.
+pub async fn introspect_at(
+    endpoint: &str,
+    stored: &StoredToken,
+    registration: Option<&ClientRegistration>,
+    http: &reqwest::Client,
+) -> Result<TokenStatus> {
+    let access_token = stored.token_response.access_token().secret();
+    let mut params = vec![
+        ("token", access_token.as_str()),
+        ("token_type_hint", "access_token"),
+    ];
+    let request = match registration.and_then(|registration| {
+        registration
+            .client_secret
+            .as_ref()
+            .map(|secret| (&registration.client_id, secret))
+    }) {
+        Some((client_id, secret)) => http.post(endpoint).basic_auth(client_id, Some(secret)),
+        None => {
+            params.push(("client_id", stored.client_id.as_str()));
+            http.post(endpoint)
+        }
+    };
+    let resp = backoff::send(request.form(&params)).await?;
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "{} answered {}: {}",
+            endpoint,
+            resp.status(),
+            resp.text().await.unwrap_or_default()
+        ));
+    }
+    let response: IntrospectionResponse = resp.json().await?;
+    Ok(if response.active {
+        TokenStatus::Active
+    } else {
+        TokenStatus::Inactive
+    })
+}
+
+/// What the authorization server of `stored` (or the MCP server, when the token doesn't name
+/// one) says about its access token
+pub async fn introspect(
+    mcp_server_url: &str,
+    stored: &StoredToken,
+    registration: Option<&ClientRegistration>,
+    http: &reqwest::Client,
+) -> TokenStatus {
+    let discovery_url = stored
+        .authorization_server
+        .as_deref()
+        .unwrap_or(mcp_server_url);
+    let endpoint = match introspection_endpoint(discovery_url, http).await {
+        Ok(Some(endpoint)) => endpoint,
+        Ok(None) => {
+            debug!("{} has no introspection endpoint", discovery_url);
+            return TokenStatus::Unknown;
+        }
+        Err(e) => {
+            debug!("No OAuth metadata for {}: {}", discovery_url, e);
+            return TokenStatus::Unknown;
+        }
+    };
+    match introspect_at(&endpoint, stored, registration, http).await {
+        Ok(status) => status,
+        Err(e) => {
+            debug!(
+                "Could not introspect the token for {}: {}",
+                mcp_server_url, e
+            );
+            TokenStatus::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{basic_auth, body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn stored(access_token: &str) -> StoredToken {
+        StoredToken::new(
+            "goose".to_string(),
+            serde_json::from_value(json!({
+                "access_token": access_token,
+                "token_type": "Bearer",
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_introspect_at() {
+        let server = MockServer::start().await;
+        for (token, active) in [("live", true), ("revoked", false)] {
+            Mock::given(method("POST"))
+                .and(path("/introspect"))
+                .and(body_string_contains(format!("token={}", token)))
+                .and(body_string_contains("client_id=goose"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"active": active})))
+                .mount(&server)
+                .await;
+        }
+        let endpoint = format!("{}/introspect", server.uri());
+        let http = reqwest::Client::new();
+
+        let status = introspect_at(&endpoint, &stored("live"), None, &http).await;
+        assert_eq!(status.unwrap(), TokenStatus::Active);
+        let status = introspect_at(&endpoint, &stored("revoked"), None, &http).await;
+        assert_eq!(status.unwrap(), TokenStatus::Inactive);
+    }
+
+    #[tokio::test]
+    async fn test_introspect_at_with_secret() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/introspect"))
+            .and(basic_auth("goose-desktop", "s3cret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"active": true})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let endpoint = format!("{}/introspect", server.uri());
+        let http = reqwest::Client::new();
+        let registration = ClientRegistration {
+            client_id: "goose-desktop".to_string(),
+            client_secret: Some("s3cret".to_string()),
+            scopes: Vec::new(),
+        };
+
+        let status = introspect_at(&endpoint, &stored("live"), Some(&registration), &http).await;
+        assert_eq!(status.unwrap(), TokenStatus::Active);
+        // A server that won't tell is not taken as a revoked token
+        assert!(introspect_at(&endpoint, &stored("live"), None, &http)
+            .await
+            .is_err());
+    }
+}
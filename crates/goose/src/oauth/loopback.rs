@@ -0,0 +1,242 @@
+//! Where the browser sign-in's callback server listens.
+
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::{anyhow, bail, Context, Result};
+use tokio::net::TcpListener;
+use url::{Host, Url};
+
+use crate::config::Config;
+
+pub const REDIRECT_URI_KEY: &str = "GOOSE_OAUTH_REDIRECT_URI";
+pub const CALLBACK_BIND_KEY: &str = "GOOSE_OAUTH_CALLBACK_BIND";
+const DEFAULT_REDIRECT_URI: &str = "http://localhost/oauth_callback";
+
+#[derive(Debug, Clone)]
+pub struct CallbackAddress {
+    /// The redirect URI, without a port when any free one will do
+    redirect_uri: Url,
+    /// The addresses to try listening on, in order
+    bind: Vec<IpAddr>,
+}
+
+/// A loopback address, or an error saying why `ip` won't do
+fn loopback(ip: IpAddr) -> Result<IpAddr> {
+    if !ip.is_loopback() {
+        bail!(
+            "The OAuth callback server only listens on loopback addresses, not {}",
+            ip
+        );
+    }
+    Ok(ip)
+}
+
+/// Whether `error` is a port being taken
+fn is_taken(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == ErrorKind::AddrInUse)
+}
+
+impl CallbackAddress {
+    /// The address configured for `service`, falling back to the one for all services
+    pub fn from_config(service: &str) -> Result<Self> {
+        let config = Config::global();
+        let get = |setting: &str, all: &str| {
+            config
+                .get_param::<String>(&format!(
+                    "{}_OAUTH_{}",
+                    service.to_uppercase().replace(['-', ' '], "_"),
+                    setting
+                ))
+                .or_else(|_| config.get_param::<String>(all))
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        Self::parse(
+            get("REDIRECT_URI", REDIRECT_URI_KEY).as_deref(),
+            get("CALLBACK_BIND", CALLBACK_BIND_KEY).as_deref(),
+        )
+    }
+
+    /// `redirect_uri` is the URI the browser is sent back to, `bind` the address to listen on
+    /// when its host is a name
+    pub fn parse(redirect_uri: Option<&str>, bind: Option<&str>) -> Result<Self> {
+        let redirect_uri = redirect_uri.unwrap_or(DEFAULT_REDIRECT_URI).trim();
+        let mut url = Url::parse(redirect_uri)
+            .with_context(|| format!("'{}' is not a valid redirect URI", redirect_uri))?;
+        if url.scheme() != "http" {
+            bail!(
+                "The redirect URI {} must be http; the callback server has no certificate",
+                url
+            );
+        }
+        let bind = match bind {
+            Some(bind) => {
+                let bind = bind.trim().trim_start_matches('[').trim_end_matches(']');
+                let ip = bind
+                    .parse::<IpAddr>()
+                    .with_context(|| format!("'{}' is not an IP address", bind))?;
+                vec![loopback(ip)?]
+            }
+            None => match url.host() {
+                Some(Host::Ipv4(ip)) => vec![loopback(IpAddr::V4(ip))?],
+                Some(Host::Ipv6(ip)) => vec![loopback(IpAddr::V6(ip))?],
+                Some(Host::Domain(domain)) if domain.eq_ignore_ascii_case("localhost") => vec![
+                    IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(Ipv6Addr::LOCALHOST),
+                ],
+                _ => bail!(
+                    "The redirect URI {} must point at this machine (localhost, 127.0.0.1 or \
+                     [::1]), or set {} to where it arrives",
+                    url,
+                    CALLBACK_BIND_KEY
+                ),
+            },
+        };
+        if url.port() == Some(0) {
+            url.set_port(None)
+                .map_err(|_| anyhow!("The redirect URI {} can't take a port", url))?;
+        }
+        Ok(Self {
+            redirect_uri: url,
+            bind,
+        })
+    }
+
+    /// The path the callback arrives on
+    pub fn path(&self) -> &str {
+        self.redirect_uri.path()
+    }
+
+    fn port(&self) -> u16 {
+        self.redirect_uri.port().unwrap_or(0)
+    }
+
+    /// The redirect URI once the server listens on `port`
+    fn redirect_uri_for(&self, port: u16) -> String {
+        let mut url = self.redirect_uri.clone();
+        // Only fails for URIs that can't have a port, which `parse` refused
+        let _ = url.set_port(Some(port));
+        url.to_string()
+    }
+
+    /// Listen for the callback, returning the listener and the redirect URI it answers. A port
+    /// taken on one address isn't looked for on the next, where the browser might not go.
+    pub async fn bind(&self) -> Result<(TcpListener, String)> {
+        let mut last_error = None;
+        for ip in &self.bind {
+            match TcpListener::bind(SocketAddr::new(*ip, self.port())).await {
+                Ok(listener) => {
+                    let port = listener.local_addr()?.port();
+                    return Ok((listener, self.redirect_uri_for(port)));
+                }
+                Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                    return Err(anyhow::Error::new(e).context(format!(
+                        "Port {} is taken on {}",
+                        self.port(),
+                        ip
+                    )));
+                }
+                Err(e) => last_error = Some(anyhow!("Can't listen on {}: {}", ip, e)),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No address to listen on")))
+    }
+
+    /// Listen for the callback on the first of `ports` that's free, where 0 is any free one,
+    /// instead of on the redirect URI's own port
+    pub async fn bind_first_free(&self, ports: &[u16]) -> Result<(TcpListener, String)> {
+        for &port in ports {
+            let mut address = self.clone();
+            address
+                .redirect_uri
+                .set_port((port != 0).then_some(port))
+                .map_err(|_| anyhow!("The redirect URI {} can't take a port", self.redirect_uri))?;
+            match address.bind().await {
+                Err(e) if is_taken(&e) => tracing::debug!("OAuth callback port {} is taken", port),
+                bound => return bound,
+            }
+        }
+        bail!("No free port for the OAuth callback (tried {:?})", ports)
+    }
+
+    /// The redirect URI for a sign-in where nothing listens, on a port that was free a moment
+    /// ago
+    pub fn reserve(&self) -> Result<String> {
+        let mut last_error = None;
+        for ip in &self.bind {
+            match std::net::TcpListener::bind(SocketAddr::new(*ip, self.port())) {
+                Ok(listener) => return Ok(self.redirect_uri_for(listener.local_addr()?.port())),
+                Err(e) => last_error = Some(anyhow!("Can't listen on {}: {}", ip, e)),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No address to listen on")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let address = CallbackAddress::parse(None, None).unwrap();
+        assert_eq!(address.path(), "/oauth_callback");
+        assert_eq!(address.port(), 0);
+        assert_eq!(
+            address.bind,
+            vec![
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(Ipv6Addr::LOCALHOST)
+            ]
+        );
+        assert_eq!(
+            address.redirect_uri_for(8020),
+            "http://localhost:8020/oauth_callback"
+        );
+
+        let address = CallbackAddress::parse(Some("http://[::1]:9000/callback"), None).unwrap();
+        assert_eq!(address.bind, vec![IpAddr::V6(Ipv6Addr::LOCALHOST)]);
+        assert_eq!(address.port(), 9000);
+        assert_eq!(address.path(), "/callback");
+        assert_eq!(address.redirect_uri_for(9000), "http://[::1]:9000/callback");
+
+        let address = CallbackAddress::parse(Some("http://localhost/cb"), Some("[::1]")).unwrap();
+        assert_eq!(address.bind, vec![IpAddr::V6(Ipv6Addr::LOCALHOST)]);
+    }
+
+    #[test]
+    fn test_parse_refuses() {
+        assert!(CallbackAddress::parse(None, Some("0.0.0.0")).is_err());
+        assert!(CallbackAddress::parse(Some("http://0.0.0.0/cb"), None).is_err());
+        assert!(CallbackAddress::parse(Some("http://goose.example.com/cb"), None).is_err());
+        assert!(CallbackAddress::parse(Some("https://localhost/cb"), None).is_err());
+        assert!(CallbackAddress::parse(None, Some("localhost")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bind() {
+        let address = CallbackAddress::parse(Some("http://127.0.0.1/cb"), None).unwrap();
+        let (listener, redirect_uri) = address.bind().await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_eq!(redirect_uri, format!("http://127.0.0.1:{}/cb", port));
+    }
+
+    #[tokio::test]
+    async fn test_bind_first_free() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken_port = taken.local_addr().unwrap().port();
+        let address = CallbackAddress::parse(Some("http://localhost/cb"), None).unwrap();
+
+        // Taken on 127.0.0.1, so not tried on ::1 either, where the browser may not look
+        let error = address.bind_first_free(&[taken_port]).await.unwrap_err();
+        assert!(error.to_string().contains("No free port"), "{}", error);
+
+        let (listener, redirect_uri) = address.bind_first_free(&[taken_port, 0]).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_ne!(port, taken_port);
+        assert_eq!(redirect_uri, format!("http://localhost:{}/cb", port));
+    }
+}
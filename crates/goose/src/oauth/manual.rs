@@ -0,0 +1,148 @@
+//! Signing in without the loopback redirect listener, by pasting the code.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use anyhow::{bail, Context, Result};
+use url::Url;
+
+use super::AuthError;
+use crate::config::Config;
+use crate::providers::oauth::OAUTH_FLOW_KEY;
+
+pub const MANUAL_FLOW: &str = "manual";
+
+pub fn is_manual() -> bool {
+    Config::global()
+        .get_param::<String>(OAUTH_FLOW_KEY)
+        .is_ok_and(|flow| flow.trim().eq_ignore_ascii_case(MANUAL_FLOW))
+}
+
+/// Use the manual flow for this process and the extensions it starts
+pub fn set_manual() {
+    std::env::set_var(OAUTH_FLOW_KEY, MANUAL_FLOW);
+}
+
+/// The `state` parameter of an authorization URL, which the pasted answer has to carry back
+pub fn state_of(authorization_url: &str) -> Option<String> {
+    Url::parse(authorization_url)
+        .ok()?
+        .query_pairs()
+        .find(|(key, _)| key == "state")
+        .map(|(_, value)| value.into_owned())
+}
+
+/// The authorization code in what the user pasted: the redirect URL, its query string, or the
+/// bare code. A redirect that carries an error, or a state other than `expected_state`, is
+/// refused.
+pub fn parse_pasted(pasted: &str, expected_state: Option<&str>) -> Result<String> {
+    let pasted = pasted.trim();
+    if pasted.is_empty() {
+        bail!("Nothing was pasted");
+    }
+    let query = match Url::parse(pasted) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {
+            url.query().unwrap_or_default().to_string()
+        }
+        // Codes may end in base64 padding, so a bare `=` doesn't make a query string
+        _ if pasted.starts_with('?') || pasted.contains("code=") || pasted.contains("error=") => {
+            pasted.trim_start_matches('?').to_string()
+        }
+        _ => return Ok(pasted.to_string()),
+    };
+    let param = |name: &str| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    if let Some(error) = param("error") {
+        let description = param("error_description");
+        return Err(AuthError::from_redirect(&error, description.as_deref()).into());
+    }
+    let code = param("code").context("The pasted URL has no authorization code")?;
+    if let (Some(expected), Some(state)) = (expected_state, param("state")) {
+        if state != expected {
+            bail!("The pasted URL is from a different sign-in (state mismatch)");
+        }
+    }
+    Ok(code)
+}
+
+/// Show `authorization_url` and wait for the user to paste what the redirect gave them
+pub async fn read_code(name: &str, authorization_url: &str) -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "Signing in to {} manually needs a terminal to paste the code into; unset {} to use the browser",
+            name,
+            OAUTH_FLOW_KEY
+        );
+    }
+    let expected_state = state_of(authorization_url);
+    eprintln!("Open this URL in a browser to authorize {}:", name);
+    eprintln!("  {}", authorization_url);
+    eprintln!(
+        "After signing in the browser is sent to a localhost page that won't load; copy its \
+         whole URL from the address bar (or just the code) and paste it here."
+    );
+    loop {
+        eprint!("Authorization code or URL: ");
+        std::io::stderr().flush()?;
+        let line = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line).map(|_| line)
+        })
+        .await??;
+        if line.is_empty() {
+            bail!("No authorization code was entered");
+        }
+        match parse_pasted(&line, expected_state.as_deref()) {
+            Ok(code) => return Ok(code),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pasted() {
+        assert_eq!(
+            parse_pasted(
+                "http://localhost:8020/oauth_callback?code=abc%2F123&state=xyz\n",
+                Some("xyz")
+            )
+            .unwrap(),
+            "abc/123"
+        );
+        assert_eq!(
+            parse_pasted("?code=abc&state=xyz", Some("xyz")).unwrap(),
+            "abc"
+        );
+        assert_eq!(parse_pasted("  abc123 ", Some("xyz")).unwrap(), "abc123");
+        assert_eq!(parse_pasted("YWJj==", None).unwrap(), "YWJj==");
+
+        assert!(parse_pasted("http://localhost/?code=abc&state=other", Some("xyz")).is_err());
+        let denied = parse_pasted(
+            "http://localhost/?error=access_denied&error_description=User+said+no",
+            None,
+        )
+        .unwrap_err();
+        assert!(denied.to_string().contains("User said no"));
+        assert!(matches!(
+            denied.downcast_ref::<AuthError>(),
+            Some(AuthError::UserDenied(_))
+        ));
+        assert!(parse_pasted("http://localhost/?state=xyz", None).is_err());
+        assert!(parse_pasted("   ", None).is_err());
+    }
+
+    #[test]
+    fn test_state_of() {
+        assert_eq!(
+            state_of("https://auth.example.com/authorize?client_id=goose&state=s%201").as_deref(),
+            Some("s 1")
+        );
+        assert_eq!(state_of("https://auth.example.com/authorize"), None);
+    }
+}
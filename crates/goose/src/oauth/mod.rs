@@ -1,25 +1,68 @@
 use axum::extract::{Query, State};
-use axum::response::Html;
 use axum::routing::get;
 use axum::Router;
-use minijinja::render;
+use chrono::{DateTime, Utc};
+use oauth2::TokenResponse;
+use once_cell::sync::Lazy;
 use rmcp::transport::auth::OAuthState;
-use rmcp::transport::AuthorizationManager;
+use rmcp::transport::{AuthError as RmcpAuthError, AuthorizationManager};
 use serde::Deserialize;
-use std::net::SocketAddr;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{oneshot, Mutex};
-use tracing::warn;
+use tokio::sync::{oneshot, Mutex, MutexGuard};
+use tracing::{info, warn};
 
-use crate::oauth::persist::{clear_credentials, load_cached_state, save_credentials};
+use crate::oauth::client_credentials::{ClientCredentials, CLIENT_ID_ENV, CLIENT_SECRET_ENV};
+use crate::oauth::events::{redact, AuthAction};
+use crate::oauth::introspection::TokenStatus;
+use crate::oauth::jwt_bearer::JwtBearer;
+use crate::oauth::loopback::CallbackAddress;
+use crate::oauth::pkce::{Pkce, PkceMethod};
+use crate::oauth::registration::ClientRegistration;
+use crate::oauth::token_store::{
+    migrate_legacy_credentials, token_store, Expiry, StoredToken, TokenKey, TokenStore,
+};
 
-mod persist;
+pub mod backoff;
+pub mod callback_page;
+pub mod client_credentials;
+pub mod error;
+pub mod events;
+pub mod http;
+pub mod introspection;
+pub mod jwt_bearer;
+pub mod loopback;
+pub mod manual;
+pub mod oidc;
+pub mod pkce;
+pub mod registration;
+pub mod resource_metadata;
+pub mod token_store;
+pub mod wait;
 
-const CALLBACK_TEMPLATE: &str = include_str!("oauth_callback.html");
+pub use error::AuthError;
+
+/// Refresh access tokens this long before they expire, so requests don't race the expiry
+const REFRESH_AHEAD_SECS: i64 = 300;
+/// Only used to start a sign-in, which a restored authorization manager never does
+const RESTORED_REDIRECT_URI: &str = "http://localhost/oauth_callback";
+
+/// One interactive sign-in per OAuth host at a time. Extensions on the same host that start
+/// together would each open a browser tab and a callback server otherwise; the ones that wait
+/// pick up the tokens the first one saved.
+static SIGN_IN_LOCKS: Lazy<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>> =
+    Lazy::new(Default::default);
+
+fn sign_in_lock(oauth_host: &str) -> Arc<Mutex<()>> {
+    let mut locks = SIGN_IN_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    // Forget locks nobody holds or waits for
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    locks.entry(oauth_host.to_string()).or_default().clone()
+}
 
 #[derive(Clone)]
 struct AppState {
-    code_receiver: Arc<Mutex<Option<oneshot::Sender<CallbackParams>>>>,
+    code_receiver: Arc<Mutex<Option<oneshot::Sender<Callback>>>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,76 +71,1041 @@ struct CallbackParams {
     state: String,
 }
 
-pub async fn oauth_flow(
-    mcp_server_url: &String,
-    name: &String,
-) -> Result<AuthorizationManager, anyhow::Error> {
-    if let Ok(oauth_state) = load_cached_state(mcp_server_url, name).await {
-        if let Some(authorization_manager) = oauth_state.into_authorization_manager() {
-            if authorization_manager.refresh_token().await.is_ok() {
-                return Ok(authorization_manager);
-            }
+/// What the redirect brought back: the code, or why there is none
+type Callback = Result<CallbackParams, AuthError>;
+
+/// Rebuild an authorization manager from cached credentials. A pre-registered client is set up
+/// again with its secret, which confidential clients need to refresh their tokens.
+pub(crate) async fn restore_authorization(
+    mcp_server_url: &str,
+    stored: &StoredToken,
+    registration: Option<&ClientRegistration>,
+    http: &reqwest::Client,
+) -> Result<AuthorizationManager, RmcpAuthError> {
+    // The manager only uses its URL to discover the OAuth metadata
+    let discovery_url = stored
+        .authorization_server
+        .as_deref()
+        .unwrap_or(mcp_server_url);
+    let mut oauth_state = OAuthState::new(discovery_url, Some(http.clone())).await?;
+    oauth_state
+        .set_credentials(&stored.client_id, stored.token_response.clone())
+        .await?;
+    let mut authorization_manager = oauth_state
+        .into_authorization_manager()
+        .ok_or_else(|| RmcpAuthError::InternalError("No authorization manager".to_string()))?;
+    if let Some(registration) = registration {
+        authorization_manager
+            .configure_client(registration.client_config(RESTORED_REDIRECT_URI))?;
+    }
+    Ok(authorization_manager)
+}
+
+/// Whether an access token expiring at `expires_at` should be refreshed now
+fn expires_soon(expires_at: Option<DateTime<Utc>>) -> bool {
+    expires_at.is_some_and(|expires_at| {
+        expires_at <= Utc::now() + chrono::Duration::seconds(REFRESH_AHEAD_SECS)
+    })
+}
+
+/// What to tell the user when the extension `name` needs them to sign in again
+pub fn sign_in_hint(name: &str) -> String {
+    format!("run `goose auth login {}` to sign in again", name)
+}
+
+/// The cached sign-in under `key`, when it ran out or is about to and goose can't renew it
+/// without the user
+pub fn sign_in_expiry(key: &TokenKey) -> Option<Expiry> {
+    token_store().load(key).ok().flatten()?.expiry()
+}
+
+/// `base` followed by the scopes of `extra` it doesn't have yet
+pub fn merge_scopes(base: &[String], extra: &[String]) -> Vec<String> {
+    let mut merged = base.to_vec();
+    for scope in extra {
+        if !merged.contains(scope) {
+            merged.push(scope.clone());
         }
+    }
+    merged
+}
+
+/// The scopes in a space separated `scope` parameter
+fn scope_list(scope: Option<&str>) -> Vec<String> {
+    scope
+        .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Trade the refresh token in `stored` for a new access token and save it, trying again while
+/// the authorization server has trouble
+async fn refresh_stored(
+    store: &dyn TokenStore,
+    key: &TokenKey,
+    mcp_server_url: &str,
+    stored: StoredToken,
+    registration: Option<&ClientRegistration>,
+    http: &reqwest::Client,
+) -> Result<(AuthorizationManager, StoredToken), AuthError> {
+    let authorization_manager =
+        restore_authorization(mcp_server_url, &stored, registration, http).await?;
+    let discovery_url = stored
+        .authorization_server
+        .as_deref()
+        .unwrap_or(mcp_server_url);
+    let mut token_response =
+        backoff::with_retries(discovery_url, || authorization_manager.refresh_token()).await?;
+    // Servers may rotate the refresh token, but don't have to send it again
+    if token_response.refresh_token().is_none() {
+        token_response.set_refresh_token(stored.token_response.refresh_token().cloned());
+    }
+    // A refreshed token keeps the scopes of the original grant
+    let refreshed = StoredToken::new(stored.client_id, token_response)
+        .with_authorization_server(stored.authorization_server)
+        .with_requested_scopes(&stored.scopes)
+        .with_identity(stored.identity);
+    if let Err(e) = store.save(key, &refreshed) {
+        warn!("Failed to save refreshed OAuth tokens: {}", e);
+    }
+    Ok((authorization_manager, refreshed))
+}
+
+/// The cached authorization for `key`, refreshed first if the access token expires soon, or if
+/// [`introspection`] is on and the authorization server says it is no longer active. `None`
+/// means there is nothing usable, or it lacks some of `scopes`, and the browser flow has to run.
+async fn cached_authorization(
+    store: &dyn TokenStore,
+    key: &TokenKey,
+    mcp_server_url: &str,
+    name: &str,
+    registration: Option<&ClientRegistration>,
+    scopes: &[String],
+    http: &reqwest::Client,
+) -> Option<AuthorizationManager> {
+    let stored = match store.load(key) {
+        Ok(Some(stored)) => stored,
+        // Credentials from before the token store belong to the default profile
+        Ok(None) if key.profile.is_none() => migrate_legacy_credentials(store, key, name)?,
+        Err(e) => {
+            warn!("Failed to read cached OAuth tokens for {}: {}", name, e);
+            return None;
+        }
+    };
 
-        if let Err(e) = clear_credentials(name) {
+    if !stored.grants(scopes) {
+        info!(
+            "Signing in to {} again to ask for scopes {}",
+            name,
+            scopes.join(" ")
+        );
+        return None;
+    }
+
+    let forget = || {
+        if let Err(e) = store.remove(key) {
             warn!("error clearing bad credentials: {}", e);
         }
+    };
+    let expiring = expires_soon(stored.expires_at);
+    let revoked = !expiring
+        && introspection::enabled(name)
+        && introspection::introspect(mcp_server_url, &stored, registration, http).await
+            == TokenStatus::Inactive;
+    if revoked {
+        info!(
+            "The authorization server no longer accepts the token of {}",
+            name
+        );
+    }
+    if expiring || revoked {
+        match refresh_stored(
+            store,
+            key,
+            mcp_server_url,
+            stored.clone(),
+            registration,
+            http,
+        )
+        .await
+        {
+            Ok((authorization_manager, _)) => return Some(authorization_manager),
+            Err(e) if revoked || stored.is_expired() => {
+                warn!(
+                    "Failed to refresh OAuth tokens for {}: {}",
+                    name,
+                    redact(&e.to_string())
+                );
+                forget();
+                return None;
+            }
+            // Still good for a little while, the next request tries again
+            Err(e) => warn!(
+                "Failed to refresh OAuth tokens for {}: {}",
+                name,
+                redact(&e.to_string())
+            ),
+        }
+    }
+
+    match restore_authorization(mcp_server_url, &stored, registration, http).await {
+        Ok(authorization_manager) => Some(authorization_manager),
+        Err(e) => {
+            warn!(
+                "Cached OAuth tokens for {} are unusable: {}",
+                name,
+                redact(&e.to_string())
+            );
+            forget();
+            None
+        }
+    }
+}
+
+/// How new tokens are obtained
+#[derive(Clone)]
+enum Grant {
+    /// With the refresh token, or by signing in again in the browser, as a pre-registered
+    /// client if there is one
+    AuthorizationCode(Option<ClientRegistration>),
+    ClientCredentials(ClientCredentials),
+    JwtBearer(JwtBearer),
+}
+
+/// Keeps the access token of a connected MCP server fresh. It shares the authorization manager
+/// with the transport's `AuthClient`, so the next request picks up a refreshed token without
+/// reconnecting.
+#[derive(Clone)]
+pub struct TokenRefresher {
+    mcp_server_url: String,
+    name: String,
+    grant: Grant,
+    /// Asked for when signing in again or requesting a token; grows when the server wants more
+    scopes: Arc<std::sync::Mutex<Vec<String>>>,
+    key: TokenKey,
+    store: Arc<dyn TokenStore>,
+    auth_manager: Arc<Mutex<AuthorizationManager>>,
+    expires_at: Arc<std::sync::Mutex<Option<DateTime<Utc>>>>,
+    http: reqwest::Client,
+}
+
+impl TokenRefresher {
+    /// `key` picks the profile whose tokens are refreshed
+    pub fn new(
+        mcp_server_url: &str,
+        name: &str,
+        key: TokenKey,
+        auth_manager: AuthorizationManager,
+        registration: Option<ClientRegistration>,
+        scopes: Vec<String>,
+        http: reqwest::Client,
+    ) -> anyhow::Result<Self> {
+        let store = token_store();
+        let expires_at = store
+            .load(&key)
+            .ok()
+            .flatten()
+            .and_then(|stored| stored.expires_at);
+        Ok(Self {
+            mcp_server_url: mcp_server_url.to_string(),
+            name: name.to_string(),
+            grant: Grant::AuthorizationCode(registration),
+            scopes: Arc::new(std::sync::Mutex::new(scopes)),
+            key,
+            store,
+            auth_manager: Arc::new(Mutex::new(auth_manager)),
+            expires_at: Arc::new(std::sync::Mutex::new(expires_at)),
+            http,
+        })
+    }
+
+    /// Authorize with the client credentials grant; new tokens are requested the same way, and
+    /// are not cached since getting one needs nobody's help
+    pub async fn client_credentials(
+        mcp_server_url: &str,
+        name: &str,
+        credentials: ClientCredentials,
+        http: reqwest::Client,
+    ) -> anyhow::Result<Self> {
+        let key = TokenKey::for_resource(mcp_server_url)?;
+        let (auth_manager, token) = events::observe(
+            AuthAction::MachineGrant,
+            name,
+            Some(&key.oauth_host),
+            credentials.authorize(mcp_server_url, &http),
+        )
+        .await?;
+        Ok(Self {
+            mcp_server_url: mcp_server_url.to_string(),
+            name: name.to_string(),
+            scopes: Arc::new(std::sync::Mutex::new(scope_list(
+                credentials.scope.as_deref(),
+            ))),
+            grant: Grant::ClientCredentials(credentials),
+            key,
+            store: token_store(),
+            auth_manager: Arc::new(Mutex::new(auth_manager)),
+            expires_at: Arc::new(std::sync::Mutex::new(token.expires_at)),
+            http,
+        })
+    }
+
+    /// Authorize with a service account's signed assertion; like the client credentials grant,
+    /// new tokens are requested the same way and not cached
+    pub async fn jwt_bearer(
+        mcp_server_url: &str,
+        name: &str,
+        grant: JwtBearer,
+        http: reqwest::Client,
+    ) -> anyhow::Result<Self> {
+        let key = TokenKey::for_resource(mcp_server_url)?;
+        let (auth_manager, token) = events::observe(
+            AuthAction::MachineGrant,
+            name,
+            Some(&key.oauth_host),
+            grant.authorize(mcp_server_url, &http),
+        )
+        .await?;
+        Ok(Self {
+            mcp_server_url: mcp_server_url.to_string(),
+            name: name.to_string(),
+            scopes: Arc::new(std::sync::Mutex::new(scope_list(grant.scope.as_deref()))),
+            grant: Grant::JwtBearer(grant),
+            key,
+            store: token_store(),
+            auth_manager: Arc::new(Mutex::new(auth_manager)),
+            expires_at: Arc::new(std::sync::Mutex::new(token.expires_at)),
+            http,
+        })
+    }
+
+    /// How the user signs in again when a new token can't be had without them; `None` for the
+    /// machine grants, which only need their credentials fixed
+    pub fn sign_in_hint(&self) -> Option<String> {
+        match self.grant {
+            Grant::AuthorizationCode(_) => Some(sign_in_hint(&self.name)),
+            Grant::ClientCredentials(_) | Grant::JwtBearer(_) => None,
+        }
+    }
+
+    pub fn auth_manager(&self) -> Arc<Mutex<AuthorizationManager>> {
+        self.auth_manager.clone()
+    }
+
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        *self.expires_at.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn scopes(&self) -> Vec<String> {
+        self.scopes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// The `scope` parameter of the machine grants' token requests
+    fn scope_param(&self) -> Option<String> {
+        Some(self.scopes().join(" ")).filter(|scope| !scope.is_empty())
+    }
+
+    /// Refresh ahead of time if the access token expires soon
+    pub async fn refresh_if_expiring(&self) {
+        if !expires_soon(self.expires_at()) {
+            return;
+        }
+        let auth_manager = self.auth_manager.lock().await;
+        // Another request may have refreshed while we waited for the lock
+        if !expires_soon(self.expires_at()) {
+            return;
+        }
+        if let Err(e) = self.refresh_locked(auth_manager).await {
+            warn!(
+                "Failed to refresh OAuth tokens for {}: {}",
+                self.name,
+                redact(&e.to_string())
+            );
+        }
+    }
+
+    /// Get a new access token: with the client credentials or a new assertion, or with the
+    /// refresh token and by signing in again in the browser if that doesn't work
+    pub async fn refresh(&self) -> Result<(), AuthError> {
+        let auth_manager = self.auth_manager.lock().await;
+        self.refresh_locked(auth_manager).await
+    }
+
+    /// Sign in again asking for `extra` scopes as well, after the server said a request needs
+    /// them; the machine grants ask for them with a new token
+    pub async fn request_scopes(&self, extra: &[String]) -> Result<(), AuthError> {
+        let mut auth_manager = self.auth_manager.lock().await;
+        let scopes = {
+            let mut scopes = self.scopes.lock().unwrap_or_else(|e| e.into_inner());
+            *scopes = merge_scopes(&scopes, extra);
+            scopes.clone()
+        };
+        let Grant::AuthorizationCode(registration) = &self.grant else {
+            return self.refresh_locked(auth_manager).await;
+        };
+        *auth_manager = oauth_flow(
+            &self.mcp_server_url,
+            &self.name,
+            &self.key,
+            registration.as_ref(),
+            None,
+            &scopes,
+            &self.http,
+        )
+        .await?;
+        *self.expires_at.lock().unwrap_or_else(|e| e.into_inner()) = self
+            .store
+            .load(&self.key)
+            .ok()
+            .flatten()
+            .and_then(|stored| stored.expires_at);
+        Ok(())
+    }
+
+    async fn refresh_locked(
+        &self,
+        auth_manager: MutexGuard<'_, AuthorizationManager>,
+    ) -> Result<(), AuthError> {
+        let action = match self.grant {
+            Grant::AuthorizationCode(_) => AuthAction::Refresh,
+            Grant::ClientCredentials(_) | Grant::JwtBearer(_) => AuthAction::MachineGrant,
+        };
+        events::observe(
+            action,
+            &self.name,
+            Some(&self.key.oauth_host),
+            self.renew(auth_manager),
+        )
+        .await
+    }
+
+    async fn renew(
+        &self,
+        mut auth_manager: MutexGuard<'_, AuthorizationManager>,
+    ) -> Result<(), AuthError> {
+        let registration = match &self.grant {
+            Grant::ClientCredentials(credentials) => {
+                let credentials = ClientCredentials {
+                    scope: self.scope_param(),
+                    ..credentials.clone()
+                };
+                let (refreshed_manager, token) = credentials
+                    .authorize(&self.mcp_server_url, &self.http)
+                    .await?;
+                *auth_manager = refreshed_manager;
+                *self.expires_at.lock().unwrap_or_else(|e| e.into_inner()) = token.expires_at;
+                return Ok(());
+            }
+            Grant::JwtBearer(grant) => {
+                let grant = JwtBearer {
+                    scope: self.scope_param(),
+                    ..grant.clone()
+                };
+                let (refreshed_manager, token) =
+                    grant.authorize(&self.mcp_server_url, &self.http).await?;
+                *auth_manager = refreshed_manager;
+                *self.expires_at.lock().unwrap_or_else(|e| e.into_inner()) = token.expires_at;
+                return Ok(());
+            }
+            Grant::AuthorizationCode(registration) => registration.as_ref(),
+        };
+
+        let refreshed = match self.store.load(&self.key) {
+            Ok(Some(stored)) => {
+                refresh_stored(
+                    &self.store,
+                    &self.key,
+                    &self.mcp_server_url,
+                    stored,
+                    registration,
+                    &self.http,
+                )
+                .await
+            }
+            Ok(None) => Err(AuthError::RefreshFailed("no cached tokens".to_string())),
+            Err(e) => Err(e.into()),
+        };
+        let (refreshed_manager, expires_at) = match refreshed {
+            Ok((refreshed_manager, stored)) => (refreshed_manager, stored.expires_at),
+            Err(e) => {
+                warn!(
+                    "Failed to refresh OAuth tokens for {}, signing in again: {}",
+                    self.name,
+                    redact(&e.to_string())
+                );
+                if let Err(e) = self.store.remove(&self.key) {
+                    warn!("error clearing bad credentials: {}", e);
+                }
+                let refreshed_manager = oauth_flow(
+                    &self.mcp_server_url,
+                    &self.name,
+                    &self.key,
+                    registration,
+                    None,
+                    &self.scopes(),
+                    &self.http,
+                )
+                .await?;
+                let expires_at = self
+                    .store
+                    .load(&self.key)
+                    .ok()
+                    .flatten()
+                    .and_then(|stored| stored.expires_at);
+                (refreshed_manager, expires_at)
+            }
+        };
+        *auth_manager = refreshed_manager;
+        *self.expires_at.lock().unwrap_or_else(|e| e.into_inner()) = expires_at;
+        Ok(())
+    }
+}
+
+/// An authorization in progress: goose registered itself dynamically, or signs in as a
+/// pre-registered client
+enum PendingAuthorization {
+    Dynamic(OAuthState),
+    Registered(AuthorizationManager, ClientRegistration),
+    /// A pre-registered client at a server without S256 PKCE, whose code goose trades itself
+    Downgraded {
+        discovery_url: String,
+        registration: ClientRegistration,
+        pkce: Pkce,
+        authorization_url: String,
+        token_endpoint: String,
+        redirect_uri: String,
+        http: reqwest::Client,
+    },
+}
+
+impl PendingAuthorization {
+    /// `discovery_url` is where the authorization server's metadata is looked for: the server
+    /// itself, or the MCP server when it doesn't name one. Where goose trades the code itself
+    /// the request carries `nonce`, for the `id_token` to repeat.
+    async fn start(
+        discovery_url: &str,
+        redirect_uri: &str,
+        registration: Option<&ClientRegistration>,
+        scopes: &[String],
+        nonce: &str,
+        http: &reqwest::Client,
+    ) -> Result<Self, RmcpAuthError> {
+        match registration {
+            Some(registration) => {
+                let mut registration = registration.clone();
+                registration.scopes = scopes.to_vec();
+                let mut authorization_manager = AuthorizationManager::new(discovery_url).await?;
+                authorization_manager.with_client(http.clone())?;
+                let metadata = authorization_manager.discover_metadata().await?;
+                let supported = serde_json::to_value(&metadata)
+                    .ok()
+                    .and_then(|metadata| pkce::supported_methods(&metadata));
+                let method =
+                    pkce::negotiate(supported.as_deref(), registration.client_secret.is_some());
+                let token_endpoint = metadata.token_endpoint.clone();
+                authorization_manager.set_metadata(metadata);
+                authorization_manager.configure_client(registration.client_config(redirect_uri))?;
+                if method == PkceMethod::S256 {
+                    return Ok(Self::Registered(authorization_manager, registration));
+                }
+                warn!(
+                    "{} doesn't support S256 PKCE (it supports {}); signing in with {}",
+                    discovery_url,
+                    supported.unwrap_or_default().join(", "),
+                    method
+                );
+                let pkce = Pkce::new(method);
+                let authorization_url = authorization_manager
+                    .get_authorization_url(&registration.scopes())
+                    .await?;
+                let authorization_url = pkce
+                    .apply(&authorization_url)
+                    .and_then(|authorization_url| oidc::with_nonce(&authorization_url, nonce))
+                    .map_err(|e| RmcpAuthError::InternalError(e.to_string()))?;
+                Ok(Self::Downgraded {
+                    discovery_url: discovery_url.to_string(),
+                    registration,
+                    pkce,
+                    authorization_url,
+                    token_endpoint,
+                    redirect_uri: redirect_uri.to_string(),
+                    http: http.clone(),
+                })
+            }
+            None => {
+                let mut oauth_state = OAuthState::new(discovery_url, Some(http.clone())).await?;
+                let scopes: Vec<&str> = scopes.iter().map(String::as_str).collect();
+                oauth_state
+                    .start_authorization(&scopes, redirect_uri)
+                    .await
+                    .map_err(|e| match e {
+                        RmcpAuthError::RegistrationFailed(reason) => {
+                            RmcpAuthError::RegistrationFailed(format!(
+                                "{}. If the server doesn't offer dynamic client registration, \
+                                 register a client with it and set {} (and {} for confidential \
+                                 clients) for this extension",
+                                reason, CLIENT_ID_ENV, CLIENT_SECRET_ENV
+                            ))
+                        }
+                        other => other,
+                    })?;
+                Ok(Self::Dynamic(oauth_state))
+            }
+        }
     }
 
-    let (code_sender, code_receiver) = oneshot::channel::<CallbackParams>();
+    async fn authorization_url(&self) -> Result<String, RmcpAuthError> {
+        match self {
+            Self::Dynamic(oauth_state) => oauth_state.get_authorization_url().await,
+            Self::Registered(authorization_manager, registration) => {
+                authorization_manager
+                    .get_authorization_url(&registration.scopes())
+                    .await
+            }
+            Self::Downgraded {
+                authorization_url, ..
+            } => Ok(authorization_url.clone()),
+        }
+    }
+
+    /// Trade the code from the callback for tokens, returning the manager, what to cache and
+    /// the `id_token`, which only comes back where goose trades the code itself
+    async fn finish(
+        self,
+        code: &str,
+        csrf_token: &str,
+    ) -> anyhow::Result<(AuthorizationManager, Option<StoredToken>, Option<String>)> {
+        match self {
+            Self::Dynamic(mut oauth_state) => {
+                oauth_state.handle_callback(code, csrf_token).await?;
+                let stored = match oauth_state.get_credentials().await {
+                    Ok((client_id, Some(token_response))) => {
+                        Some(StoredToken::new(client_id, token_response))
+                    }
+                    Ok((_, None)) => None,
+                    Err(e) => {
+                        warn!("Failed to read credentials: {}", e);
+                        None
+                    }
+                };
+                let authorization_manager = oauth_state
+                    .into_authorization_manager()
+                    .ok_or_else(|| anyhow::anyhow!("Failed to get authorization manager"))?;
+                Ok((authorization_manager, stored, None))
+            }
+            Self::Registered(authorization_manager, registration) => {
+                let token_response = authorization_manager
+                    .exchange_code_for_token(code, csrf_token)
+                    .await?;
+                Ok((
+                    authorization_manager,
+                    Some(StoredToken::new(registration.client_id, token_response)),
+                    None,
+                ))
+            }
+            Self::Downgraded {
+                discovery_url,
+                registration,
+                pkce,
+                authorization_url,
+                token_endpoint,
+                redirect_uri,
+                http,
+            } => {
+                pkce::check_state(manual::state_of(&authorization_url).as_deref(), csrf_token)?;
+                let (token_response, id_token) = pkce
+                    .exchange_code(&token_endpoint, code, &redirect_uri, &registration, &http)
+                    .await?;
+                let stored = StoredToken::new(registration.client_id.clone(), token_response);
+                let authorization_manager =
+                    restore_authorization(&discovery_url, &stored, Some(&registration), &http)
+                        .await?;
+                Ok((authorization_manager, Some(stored), id_token))
+            }
+        }
+    }
+}
+
+/// Listen on loopback for the authorization redirect, at the [`loopback`] address configured
+/// for `name`. Returns the redirect URI, where its parameters arrive, and a sender that shuts
+/// the server down when dropped.
+async fn serve_callback(
+    name: &str,
+    address: &CallbackAddress,
+) -> Result<(String, oneshot::Receiver<Callback>, oneshot::Sender<()>), anyhow::Error> {
+    let (code_sender, code_receiver) = oneshot::channel::<Callback>();
     let app_state = AppState {
         code_receiver: Arc::new(Mutex::new(Some(code_sender))),
     };
 
-    let rendered = render!(CALLBACK_TEMPLATE, name => name);
-    let handler = move |Query(params): Query<CallbackParams>, State(state): State<AppState>| {
-        let rendered = rendered.clone();
+    let page = callback_page::CallbackPage::from_config(name);
+    let name = name.to_string();
+    let handler = move |Query(params): Query<HashMap<String, String>>,
+                        State(state): State<AppState>| {
+        let page = page.clone();
+        let name = name.clone();
         async move {
+            let (Some(code), Some(state_param)) = (params.get("code"), params.get("state")) else {
+                let message = params
+                    .get("error_description")
+                    .or_else(|| params.get("error"))
+                    .map_or(
+                        "The authorization server sent no code back.",
+                        String::as_str,
+                    );
+                // Stop waiting for a code that isn't coming
+                if let (Some(error), Some(sender)) =
+                    (params.get("error"), state.code_receiver.lock().await.take())
+                {
+                    let description = params.get("error_description").map(String::as_str);
+                    let _ = sender.send(Err(AuthError::from_redirect(error, description)));
+                }
+                return page.error(&name, message);
+            };
             if let Some(sender) = state.code_receiver.lock().await.take() {
-                let _ = sender.send(params);
+                let _ = sender.send(Ok(CallbackParams {
+                    code: code.clone(),
+                    state: state_param.clone(),
+                }));
             }
-            Html(rendered)
+            page.success(&name)
         }
     };
     let app = Router::new()
-        .route("/oauth_callback", get(handler))
+        .route(address.path(), get(handler))
         .with_state(app_state);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 0));
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    let used_addr = listener.local_addr()?;
+    let (listener, redirect_uri) = address.bind().await?;
+    let (shutdown, shutdown_receiver) = oneshot::channel::<()>();
     tokio::spawn(async move {
-        let result = axum::serve(listener, app).await;
+        let result = axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_receiver.await;
+            })
+            .await;
         if let Err(e) = result {
             eprintln!("Callback server error: {}", e);
         }
     });
 
-    let mut oauth_state = OAuthState::new(mcp_server_url, None).await?;
-    let redirect_uri = format!("http://localhost:{}/oauth_callback", used_addr.port());
-    oauth_state
-        .start_authorization(&[], redirect_uri.as_str())
-        .await?;
+    Ok((redirect_uri, code_receiver, shutdown))
+}
 
-    let authorization_url = oauth_state.get_authorization_url().await?;
-    if webbrowser::open(authorization_url.as_str()).is_err() {
-        eprintln!("Open the following URL to authorize {}:", name);
-        eprintln!("  {}", authorization_url);
+/// `authorization_url` asking the authorization server to let the user pick an account, and
+/// suggesting `login_hint`, the account the profile signed in with last time. Without it a
+/// browser already signed in to another account would sign the profile in as that one.
+fn with_account_choice(authorization_url: &str, login_hint: Option<&str>) -> String {
+    let Ok(mut url) = url::Url::parse(authorization_url) else {
+        return authorization_url.to_string();
+    };
+    let has = |param: &str| url.query_pairs().any(|(name, _)| name == param);
+    let (prompted, hinted) = (has("prompt"), has("login_hint"));
+    let mut query = url.query_pairs_mut();
+    if !prompted {
+        query.append_pair("prompt", "select_account");
+    }
+    if let Some(login_hint) = login_hint.filter(|_| !hinted) {
+        query.append_pair("login_hint", login_hint);
     }
+    drop(query);
+    url.to_string()
+}
 
+/// Sign in to `mcp_server_url` in the browser, unless cached tokens are still good. Without a
+/// pre-registered client goose registers itself with the server first. `www_authenticate` is
+/// the header of the 401 that asked for authorization, which may say where the authorization
+/// server is. `scopes` are asked for along with those of the pre-registered client; cached
+/// tokens without all of them don't count. In the [`manual`] flow the user pastes the code into
+/// the terminal instead of the browser handing it to a local listener. `http` makes the OAuth
+/// requests, with the proxy and certificates of [`http::OAuthHttpSettings`]. Tokens are cached
+/// under `key`, which says which profile is signing in. Only one sign-in per OAuth host runs at
+/// a time; the others wait for it and use its tokens if they fit. The browser gets
+/// [`wait::timeout`] to come back, and Ctrl-C gives up; from a terminal either offers a retry.
+/// Each sign-in is reported as an [`events::AuthEvent`].
+pub async fn oauth_flow(
+    mcp_server_url: &str,
+    name: &str,
+    key: &TokenKey,
+    registration: Option<&ClientRegistration>,
+    www_authenticate: Option<&str>,
+    scopes: &[String],
+    http: &reqwest::Client,
+) -> Result<AuthorizationManager, AuthError> {
+    events::observe(
+        AuthAction::SignIn,
+        name,
+        Some(&key.oauth_host),
+        sign_in(
+            mcp_server_url,
+            name,
+            key,
+            registration,
+            www_authenticate,
+            scopes,
+            false,
+            http,
+        ),
+    )
+    .await
+}
+
+/// Sign in to `mcp_server_url` in the browser even when cached tokens are still good, as
+/// `goose auth login` does. The cached tokens are only replaced once the new sign-in works, so
+/// giving up on it leaves them as they were.
+pub async fn sign_in_again(
+    mcp_server_url: &str,
+    name: &str,
+    key: &TokenKey,
+    registration: Option<&ClientRegistration>,
+    scopes: &[String],
+    http: &reqwest::Client,
+) -> Result<AuthorizationManager, AuthError> {
+    events::observe(
+        AuthAction::SignIn,
+        name,
+        Some(&key.oauth_host),
+        sign_in(
+            mcp_server_url,
+            name,
+            key,
+            registration,
+            None,
+            scopes,
+            true,
+            http,
+        ),
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn sign_in(
+    mcp_server_url: &str,
+    name: &str,
+    key: &TokenKey,
+    registration: Option<&ClientRegistration>,
+    www_authenticate: Option<&str>,
+    scopes: &[String],
+    skip_cache: bool,
+    http: &reqwest::Client,
+) -> Result<AuthorizationManager, AuthError> {
+    let scopes = match registration {
+        Some(registration) => merge_scopes(&registration.scopes, scopes),
+        None => scopes.to_vec(),
+    };
+    let store = token_store();
+    if !skip_cache {
+        if let Some(authorization_manager) = cached_authorization(
+            &store,
+            key,
+            mcp_server_url,
+            name,
+            registration,
+            &scopes,
+            http,
+        )
+        .await
+        {
+            return Ok(authorization_manager);
+        }
+    }
+
+    let lock = sign_in_lock(&key.oauth_host);
+    let _signing_in = match lock.clone().try_lock_owned() {
+        Ok(guard) => guard,
+        Err(_) => {
+            info!(
+                "Waiting for another sign-in to {} to finish before {}",
+                key.oauth_host, name
+            );
+            lock.lock_owned().await
+        }
+    };
+    // A sign-in may have finished between reading the cache and taking the lock
+    if !skip_cache {
+        if let Some(authorization_manager) = cached_authorization(
+            &store,
+            key,
+            mcp_server_url,
+            name,
+            registration,
+            &scopes,
+            http,
+        )
+        .await
+        {
+            return Ok(authorization_manager);
+        }
+    }
+
+    // In the manual flow nothing listens for the redirect; the user pastes where it went
+    // The callback server stops once `_callback_server` goes, however the sign-in ends
+    let address = CallbackAddress::from_config(name)?;
+    let (redirect_uri, code_receiver, _callback_server) = if manual::is_manual() {
+        (address.reserve()?, None, None)
+    } else {
+        let (redirect_uri, code_receiver, shutdown) = serve_callback(name, &address).await?;
+        (redirect_uri, Some(code_receiver), Some(shutdown))
+    };
+    let authorization_server =
+        resource_metadata::authorization_server(mcp_server_url, www_authenticate, http).await;
+    let discovery_url = authorization_server.as_deref().unwrap_or(mcp_server_url);
+    let nonce = oidc::nonce();
+    let pending = backoff::with_retries(discovery_url, || {
+        PendingAuthorization::start(
+            discovery_url,
+            &redirect_uri,
+            registration,
+            &scopes,
+            &nonce,
+            http,
+        )
+    })
+    .await?;
+
+    let authorization_url = pending.authorization_url().await?;
+    let authorization_url = if let Some(profile) = &key.profile {
+        eprintln!("Sign in to {} with your {} account", name, profile);
+        let login_hint = store
+            .load(key)
+            .ok()
+            .flatten()
+            .and_then(|stored| stored.identity)
+            .and_then(|identity| identity.email);
+        with_account_choice(&authorization_url, login_hint.as_deref())
+    } else {
+        authorization_url
+    };
     let CallbackParams {
         code: auth_code,
         state: csrf_token,
-    } = code_receiver.await?;
-    oauth_state.handle_callback(&auth_code, &csrf_token).await?;
+    } = match code_receiver {
+        Some(mut code_receiver) => {
+            let timeout = wait::timeout(name);
+            loop {
+                if webbrowser::open(authorization_url.as_str()).is_err() {
+                    eprintln!("Open the following URL to authorize {}:", name);
+                    eprintln!("  {}", authorization_url);
+                }
+                match wait::wait_for_code(&mut code_receiver, timeout).await {
+                    Ok(Ok(params)) => break params,
+                    Ok(Err(e)) => return Err(e),
+                    Err(e) if wait::offer_retry(name, &e).await => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        None => CallbackParams {
+            code: manual::read_code(name, &authorization_url).await?,
+            state: manual::state_of(&authorization_url).unwrap_or_default(),
+        },
+    };
+    let (auth_manager, stored, id_token) = pending.finish(&auth_code, &csrf_token).await?;
 
-    if let Err(e) = save_credentials(name, &oauth_state).await {
-        warn!("Failed to save credentials: {}", e);
+    match stored {
+        Some(stored) => {
+            let stored = stored
+                .with_authorization_server(authorization_server.clone())
+                .with_requested_scopes(&scopes);
+            let issuer = oidc::issuer_for(mcp_server_url, authorization_server.as_deref());
+            let identity = match (id_token, issuer) {
+                (Some(id_token), Some(issuer)) => {
+                    oidc::identify(&stored, &id_token, Some(&nonce), &issuer, http).await
+                }
+                _ => None,
+            };
+            if let Some(identity) = &identity {
+                info!("Signed in to {} as {}", name, identity);
+            }
+            let stored = stored.with_identity(identity);
+            if let Err(e) = store.save(key, &stored) {
+                warn!("Failed to save credentials: {}", e);
+            }
+        }
+        None => warn!("No token to save after authorizing {}", name),
     }
 
-    let auth_manager = oauth_state
-        .into_authorization_manager()
-        .ok_or_else(|| anyhow::anyhow!("Failed to get authorization manager"))?;
-
     Ok(auth_manager)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oauth::token_store::{set_token_store, MemoryStore};
+    use wiremock::MockServer;
+
+    #[test]
+    fn test_expires_soon() {
+        assert!(!expires_soon(None));
+        assert!(!expires_soon(Some(Utc::now() + chrono::Duration::hours(1))));
+        assert!(expires_soon(Some(
+            Utc::now() + chrono::Duration::minutes(2)
+        )));
+        assert!(expires_soon(Some(
+            Utc::now() - chrono::Duration::minutes(2)
+        )));
+    }
+
+    #[test]
+    fn test_with_account_choice() {
+        let url = "https://auth.example.com/authorize?client_id=goose&state=abc";
+        assert_eq!(
+            with_account_choice(url, None),
+            format!("{}&prompt=select_account", url)
+        );
+        assert_eq!(
+            with_account_choice(url, Some("me@work.example.com")),
+            format!(
+                "{}&prompt=select_account&login_hint=me%40work.example.com",
+                url
+            )
+        );
+        let prompted = "https://auth.example.com/authorize?prompt=consent";
+        assert_eq!(with_account_choice(prompted, None), prompted);
+    }
+
+    #[test]
+    fn test_merge_scopes() {
+        let scopes = |s: &str| s.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            merge_scopes(&scopes("openid mcp:read"), &scopes("mcp:write openid")),
+            scopes("openid mcp:read mcp:write")
+        );
+        assert_eq!(merge_scopes(&[], &scopes("mcp:read")), scopes("mcp:read"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_lock() {
+        let held = sign_in_lock("auth.example.com").try_lock_owned().unwrap();
+        assert!(sign_in_lock("auth.example.com").try_lock_owned().is_err());
+        assert!(sign_in_lock("other.example.com").try_lock_owned().is_ok());
+        drop(held);
+        assert!(sign_in_lock("auth.example.com").try_lock_owned().is_ok());
+    }
+    #[tokio::test]
+    async fn test_failed_sign_in_keeps_the_old_one() {
+        // Nothing to sign in with: no OAuth metadata and no client registration
+        let server = MockServer::start().await;
+        let url = format!("{}/mcp", server.uri());
+        let store = Arc::new(MemoryStore::default());
+        set_token_store(store.clone());
+        let key = TokenKey::for_resource(&url).unwrap();
+        let response = serde_json::json!({
+            "access_token": "still-good",
+            "token_type": "bearer",
+            "expires_in": 3600
+        });
+        let stored = StoredToken::new(
+            "client".to_string(),
+            serde_json::from_value(response).unwrap(),
+        );
+        store.save(&key, &stored).unwrap();
+
+        let signed_in = sign_in_again(&url, "docs", &key, None, &[], &reqwest::Client::new()).await;
+        assert!(signed_in.is_err());
+        let kept = store.load(&key).unwrap().unwrap();
+        assert_eq!(kept.token_response.access_token().secret(), "still-good");
+    }
+}
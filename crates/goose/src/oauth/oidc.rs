@@ -0,0 +1,301 @@
+//! OpenID Connect: which account a token belongs to.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+use url::Url;
+
+use super::token_store::StoredToken;
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Who a token was issued for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl std::fmt::Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.email, &self.name) {
+            (Some(email), _) => write!(f, "{}", email),
+            (None, Some(name)) => write!(f, "{} ({})", name, self.subject),
+            (None, None) => write!(f, "{}", self.subject),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    jwks_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    email: Option<String>,
+    name: Option<String>,
+    nonce: Option<String>,
+}
+
+/// A nonce for the authorization request, which its `id_token` has to repeat
+pub fn nonce() -> String {
+    nanoid::nanoid!(32)
+}
+
+/// `authorization_url` asking for an `id_token` with `nonce` in it
+pub fn with_nonce(authorization_url: &str, nonce: &str) -> Result<String> {
+    let mut url = Url::parse(authorization_url)?;
+    let params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "nonce")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(&params)
+        .append_pair("nonce", nonce);
+    Ok(url.to_string())
+}
+
+impl From<Claims> for Identity {
+    fn from(claims: Claims) -> Self {
+        Self {
+            subject: claims.sub,
+            email: claims.email,
+            name: claims.name,
+        }
+    }
+}
+
+/// The issuer tokens for `mcp_server_url` come from: its authorization server, or the MCP host
+pub fn issuer_for(mcp_server_url: &str, authorization_server: Option<&str>) -> Option<String> {
+    if let Some(authorization_server) = authorization_server {
+        return Some(authorization_server.trim_end_matches('/').to_string());
+    }
+    let url = Url::parse(mcp_server_url).ok()?;
+    Some(url.origin().ascii_serialization())
+}
+
+/// The `id_token` of a token response, which `OAuthTokenResponse` has no field for
+pub fn id_token_of(token_response: &Value) -> Option<String> {
+    token_response
+        .get("id_token")
+        .and_then(Value::as_str)
+        .filter(|id_token| !id_token.is_empty())
+        .map(str::to_string)
+}
+
+async fn provider_metadata(issuer: &str, http: &reqwest::Client) -> Result<ProviderMetadata> {
+    let url = format!("{}{}", issuer.trim_end_matches('/'), DISCOVERY_PATH);
+    let metadata: ProviderMetadata = http
+        .get(&url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("{} is not OpenID provider metadata", url))?;
+    // OpenID Connect Discovery 1.0 section 4.3
+    if metadata.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+        bail!(
+            "OpenID provider metadata at {} is for issuer {}",
+            url,
+            metadata.issuer
+        );
+    }
+    Ok(metadata)
+}
+
+/// The identity in `id_token`, once it is verified to come from `issuer` for `client_id`, and
+/// to answer the authorization request that sent `nonce` if one was sent
+pub async fn validate_id_token(
+    id_token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: Option<&str>,
+    http: &reqwest::Client,
+) -> Result<Identity> {
+    let header = jsonwebtoken::decode_header(id_token).context("The id_token is not a JWT")?;
+    // A shared-secret algorithm would have the issuer's public key taken as the secret
+    if matches!(
+        header.alg,
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+    ) {
+        bail!(
+            "The id_token is signed with {:?}, not a public key",
+            header.alg
+        );
+    }
+
+    let metadata = provider_metadata(issuer, http).await?;
+    let jwks_uri = metadata
+        .jwks_uri
+        .ok_or_else(|| anyhow!("{} publishes no JWKS", issuer))?;
+    let jwks: JwkSet = http
+        .get(&jwks_uri)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("{} is not a JWKS", jwks_uri))?;
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| anyhow!("No key in {} signed the id_token", jwks_uri))?;
+    let key = DecodingKey::from_jwk(jwk)?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[metadata.issuer.as_str()]);
+    validation.set_audience(&[client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let claims = jsonwebtoken::decode::<Claims>(id_token, &key, &validation)
+        .context("The id_token failed validation")?
+        .claims;
+    check_nonce(nonce, claims.nonce.as_deref())?;
+    Ok(claims.into())
+}
+
+/// OpenID Connect Core 1.0 section 3.1.3.7: an `id_token` answering a request with a nonce
+/// carries the same one, so one issued for another sign-in can't be replayed
+fn check_nonce(expected: Option<&str>, received: Option<&str>) -> Result<()> {
+    match expected {
+        Some(expected) if received != Some(expected) => {
+            bail!("The id_token was not issued for this sign-in: its nonce doesn't match")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Who `token` belongs to, from the `id_token` of its response; `None` when the `id_token` is
+/// not valid. `nonce` is the one the browser sign-in sent, `None` for other grants.
+pub async fn identify(
+    token: &StoredToken,
+    id_token: &str,
+    nonce: Option<&str>,
+    issuer: &str,
+    http: &reqwest::Client,
+) -> Option<Identity> {
+    match validate_id_token(id_token, issuer, &token.client_id, nonce, http).await {
+        Ok(identity) => Some(identity),
+        Err(e) => {
+            warn!("Ignoring the id_token from {}: {:#}", issuer, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn provider(server: &MockServer, issuer: &str) {
+        Mock::given(method("GET"))
+            .and(path(DISCOVERY_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": issuer,
+                "jwks_uri": format!("{}/jwks", server.uri()),
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn test_issuer_for() {
+        assert_eq!(
+            issuer_for("https://mcp.example.com/mcp", None).as_deref(),
+            Some("https://mcp.example.com")
+        );
+        assert_eq!(
+            issuer_for(
+                "https://mcp.example.com/mcp",
+                Some("https://auth.example.com/")
+            )
+            .as_deref(),
+            Some("https://auth.example.com")
+        );
+        assert_eq!(
+            id_token_of(&json!({"access_token": "a", "id_token": "eyJ"})).as_deref(),
+            Some("eyJ")
+        );
+        assert!(id_token_of(&json!({"access_token": "a"})).is_none());
+    }
+
+    #[test]
+    fn test_nonce() {
+        let nonce = nonce();
+        let url = with_nonce(
+            "https://idp.example.com/authorize?state=xyz&nonce=old",
+            &nonce,
+        )
+        .unwrap();
+        let params: Vec<(String, String)> = Url::parse(&url)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                ("state".to_string(), "xyz".to_string()),
+                ("nonce".to_string(), nonce.clone()),
+            ]
+        );
+
+        assert!(check_nonce(Some(&nonce), Some(&nonce)).is_ok());
+        assert!(check_nonce(Some(&nonce), Some("replayed")).is_err());
+        assert!(check_nonce(Some(&nonce), None).is_err());
+        assert!(check_nonce(None, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_validate_id_token_checks() {
+        let server = MockServer::start().await;
+        provider(&server, "https://someone-else.example.com").await;
+        let http = reqwest::Client::new();
+
+        // Signed with a shared secret
+        let hs256 = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &json!({"sub": "user-1", "iss": server.uri(), "aud": "goose", "exp": 4_000_000_000u64}),
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let error = validate_id_token(&hs256, &server.uri(), "goose", None, &http)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("public key"));
+
+        // Metadata for another issuer
+        let rs256 = format!(
+            "{}.e30.c2ln",
+            base64::Engine::encode(
+                &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+                br#"{"alg":"RS256","kid":"k1"}"#
+            )
+        );
+        let error = validate_id_token(&rs256, &server.uri(), "goose", None, &http)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("someone-else"));
+    }
+}
@@ -0,0 +1,254 @@
+//! PKCE (RFC 7636) for the browser sign-in.
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use url::Url;
+
+use super::backoff;
+use super::oidc;
+use super::registration::ClientRegistration;
+use super::token_store::OAuthTokenResponse;
+use super::AuthError;
+
+/// Verifiers are 43 to 128 characters (RFC 7636 section 4.1); nanoid's alphabet is all
+/// unreserved characters
+const VERIFIER_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PkceMethod {
+    S256,
+    Plain,
+    /// No challenge; only for confidential clients, which prove themselves with their secret
+    None,
+}
+
+impl std::fmt::Display for PkceMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::S256 => write!(f, "S256"),
+            Self::Plain => write!(f, "plain"),
+            Self::None => write!(f, "no PKCE"),
+        }
+    }
+}
+
+/// `code_challenge_methods_supported` of the authorization server's metadata, if it says
+pub fn supported_methods(metadata: &Value) -> Option<Vec<String>> {
+    let methods = metadata
+        .get("code_challenge_methods_supported")?
+        .as_array()?;
+    Some(
+        methods
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// The method to sign in with, given what the server supports
+pub fn negotiate(supported: Option<&[String]>, confidential: bool) -> PkceMethod {
+    let Some(supported) = supported.filter(|supported| !supported.is_empty()) else {
+        return PkceMethod::S256;
+    };
+    let supports = |method: &str| supported.iter().any(|supported| supported == method);
+    if supports("S256") {
+        PkceMethod::S256
+    } else if supports("plain") {
+        PkceMethod::Plain
+    } else if confidential {
+        PkceMethod::None
+    } else {
+        // A public client has nothing else to protect its code with; try S256 anyway
+        PkceMethod::S256
+    }
+}
+
+/// A challenge other than rmcp's S256 one
+#[derive(Debug, Clone)]
+pub struct Pkce {
+    pub method: PkceMethod,
+    verifier: Option<String>,
+}
+
+impl Pkce {
+    pub fn new(method: PkceMethod) -> Self {
+        let verifier = (method == PkceMethod::Plain).then(|| nanoid::nanoid!(VERIFIER_LENGTH));
+        Self { method, verifier }
+    }
+
+    /// `authorization_url` with its S256 challenge replaced by this one
+    pub fn apply(&self, authorization_url: &str) -> Result<String> {
+        let mut url = Url::parse(authorization_url)?;
+        let params: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != "code_challenge" && key != "code_challenge_method")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        {
+            let mut query = url.query_pairs_mut();
+            query.clear().extend_pairs(&params);
+            if let Some(verifier) = &self.verifier {
+                query
+                    .append_pair("code_challenge", verifier)
+                    .append_pair("code_challenge_method", "plain");
+            }
+        }
+        Ok(url.to_string())
+    }
+
+    /// Trade `code` for tokens at `token_endpoint`, sending the verifier if there is one. The
+    /// `id_token` of the response comes along when there is one.
+    pub async fn exchange_code(
+        &self,
+        token_endpoint: &str,
+        code: &str,
+        redirect_uri: &str,
+        registration: &ClientRegistration,
+        http: &reqwest::Client,
+    ) -> Result<(OAuthTokenResponse, Option<String>)> {
+        let mut params = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+        ];
+        if let Some(verifier) = &self.verifier {
+            params.push(("code_verifier", verifier.as_str()));
+        }
+        let request = match &registration.client_secret {
+            Some(secret) => http
+                .post(token_endpoint)
+                .basic_auth(&registration.client_id, Some(secret)),
+            None => {
+                params.push(("client_id", registration.client_id.as_str()));
+                http.post(token_endpoint)
+            }
+        };
+        // The code can only be used once, so a failed exchange isn't sent again
+        let resp = backoff::send_once(request.form(&params)).await?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let err_text = resp.text().await?;
+            return Err(AuthError::TokenExchangeFailed {
+                status: Some(status),
+                message: format!("the code was refused ({}): {}", self.method, err_text),
+            }
+            .into());
+        }
+        let body: Value = resp.json().await?;
+        let id_token = oidc::id_token_of(&body);
+        Ok((serde_json::from_value(body)?, id_token))
+    }
+}
+
+/// Refuse a callback whose `state` isn't the one the sign-in started with
+pub fn check_state(expected: Option<&str>, received: &str) -> Result<()> {
+    match expected {
+        Some(expected) if expected != received => {
+            bail!("The authorization server sent back a different state; sign in again")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oauth2::TokenResponse;
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const AUTHORIZATION_URL: &str = "https://idp.example.com/authorize?response_type=code&client_id=goose&state=xyz&code_challenge=abc&code_challenge_method=S256";
+
+    #[test]
+    fn test_negotiate() {
+        let methods = |methods: &[&str]| methods.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+        assert_eq!(negotiate(None, false), PkceMethod::S256);
+        assert_eq!(negotiate(Some(&[]), true), PkceMethod::S256);
+        assert_eq!(
+            negotiate(Some(&methods(&["plain", "S256"])), false),
+            PkceMethod::S256
+        );
+        assert_eq!(
+            negotiate(Some(&methods(&["plain"])), false),
+            PkceMethod::Plain
+        );
+        assert_eq!(negotiate(Some(&methods(&["S384"])), true), PkceMethod::None);
+        assert_eq!(
+            negotiate(Some(&methods(&["S384"])), false),
+            PkceMethod::S256
+        );
+
+        assert_eq!(
+            supported_methods(&json!({"code_challenge_methods_supported": ["plain"]})),
+            Some(vec!["plain".to_string()])
+        );
+        assert_eq!(supported_methods(&json!({"issuer": "https://idp"})), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let plain = Pkce::new(PkceMethod::Plain);
+        let url = Url::parse(&plain.apply(AUTHORIZATION_URL).unwrap()).unwrap();
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(param("code_challenge_method"), Some("plain"));
+        assert_eq!(param("code_challenge"), plain.verifier.as_deref());
+        assert_eq!(param("state"), Some("xyz"));
+        assert_eq!(plain.verifier.as_ref().unwrap().len(), VERIFIER_LENGTH);
+
+        let none = Pkce::new(PkceMethod::None)
+            .apply(AUTHORIZATION_URL)
+            .unwrap();
+        assert!(!none.contains("code_challenge"));
+        assert!(none.contains("client_id=goose"));
+
+        assert!(check_state(Some("xyz"), "xyz").is_ok());
+        assert!(check_state(Some("xyz"), "abc").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_exchange_code() {
+        let server = MockServer::start().await;
+        let pkce = Pkce::new(PkceMethod::Plain);
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=authorization_code"))
+            .and(body_string_contains(format!(
+                "code_verifier={}",
+                pkce.verifier.as_ref().unwrap()
+            )))
+            .and(body_string_contains("client_id=goose"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "access",
+                "token_type": "Bearer",
+                "id_token": "eyJ",
+            })))
+            .mount(&server)
+            .await;
+
+        let registration = ClientRegistration {
+            client_id: "goose".to_string(),
+            client_secret: None,
+            scopes: Vec::new(),
+        };
+        let (token, id_token) = pkce
+            .exchange_code(
+                &format!("{}/token", server.uri()),
+                "code-1",
+                "http://localhost:1234/oauth_callback",
+                &registration,
+                &reqwest::Client::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token().secret(), "access");
+        assert_eq!(id_token.as_deref(), Some("eyJ"));
+    }
+}
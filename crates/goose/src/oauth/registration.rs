@@ -0,0 +1,106 @@
+//! Clients registered with the authorization server ahead of time.
+
+use std::collections::HashMap;
+
+use rmcp::transport::auth::OAuthClientConfig;
+
+use super::client_credentials::{CLIENT_ID_ENV, CLIENT_SECRET_ENV, SCOPE_ENV};
+use crate::agents::extension::Envs;
+use crate::config::extensions::extension_secret_key;
+use crate::config::Config;
+
+#[derive(Clone)]
+pub struct ClientRegistration {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+impl std::fmt::Debug for ClientRegistration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientRegistration")
+            .field("client_id", &self.client_id)
+            .field(
+                "client_secret",
+                &self.client_secret.as_ref().map(|_| "[redacted]"),
+            )
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+impl ClientRegistration {
+    /// The pre-registered client in an extension's environment, if it names one
+    pub fn from_envs(envs: &HashMap<String, String>) -> Option<Self> {
+        let get = |key: &str| {
+            envs.get(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Some(Self {
+            client_id: get(CLIENT_ID_ENV)?,
+            client_secret: get(CLIENT_SECRET_ENV),
+            scopes: get(SCOPE_ENV)
+                .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// The pre-registered client of the extension `name`, from its `envs` or else the secrets
+    /// `env_keys` names, looked up as when it starts
+    pub fn from_extension(name: &str, envs: &Envs, env_keys: &[String]) -> Option<Self> {
+        let mut all_envs = envs.get_env();
+        let config = Config::global();
+        for key in env_keys {
+            if all_envs.contains_key(key) {
+                continue;
+            }
+            let value = config
+                .get(&extension_secret_key(name, key), true)
+                .or_else(|_| config.get(key, true));
+            if let Ok(serde_json::Value::String(value)) = value {
+                all_envs.insert(key.clone(), value);
+            }
+        }
+        Self::from_envs(&all_envs)
+    }
+
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scopes.iter().map(String::as_str).collect()
+    }
+
+    pub fn client_config(&self, redirect_uri: &str) -> OAuthClientConfig {
+        OAuthClientConfig {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+            scopes: self.scopes.clone(),
+            redirect_uri: redirect_uri.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_envs() {
+        assert!(ClientRegistration::from_envs(&HashMap::new()).is_none());
+
+        let envs = HashMap::from([
+            (CLIENT_ID_ENV.to_string(), "goose-desktop".to_string()),
+            (CLIENT_SECRET_ENV.to_string(), "s3cret".to_string()),
+            (SCOPE_ENV.to_string(), "openid  mcp:read".to_string()),
+        ]);
+        let registration = ClientRegistration::from_envs(&envs).unwrap();
+        assert_eq!(registration.client_id, "goose-desktop");
+        assert_eq!(registration.scopes(), vec!["openid", "mcp:read"]);
+        assert!(!format!("{:?}", registration).contains("s3cret"));
+
+        let public = HashMap::from([(CLIENT_ID_ENV.to_string(), "goose-cli".to_string())]);
+        assert!(ClientRegistration::from_envs(&public)
+            .unwrap()
+            .client_secret
+            .is_none());
+    }
+}
@@ -0,0 +1,260 @@
+//! Protected resource metadata (RFC 9728): which authorization server protects an MCP server.
+
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tracing::{debug, warn};
+use url::Url;
+
+use super::backoff;
+
+const WELL_KNOWN_PATH: &str = "/.well-known/oauth-protected-resource";
+const PARAM: &str = "resource_metadata";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+pub struct ProtectedResourceMetadata {
+    /// The resource the metadata is about; has to match the server it was fetched for
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub authorization_servers: Vec<String>,
+    pub scopes_supported: Option<Vec<String>>,
+}
+
+/// The `resource_metadata` parameter of a `WWW-Authenticate` header, in whichever challenge it
+/// appears
+pub fn resource_metadata_url(www_authenticate: &str) -> Option<String> {
+    challenge_param(www_authenticate, PARAM)
+}
+
+/// The parameter `name` of a `WWW-Authenticate` header, matched case-insensitively, with
+/// quoting removed. Empty values count as missing.
+pub fn challenge_param(www_authenticate: &str, name: &str) -> Option<String> {
+    // Lowercasing ASCII keeps byte offsets, so positions carry over to the original
+    let lowered = www_authenticate.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    let mut start = 0;
+    while let Some(found) = lowered[start..].find(&name) {
+        let at = start + found;
+        start = at + name.len();
+        // Only a whole parameter name, not the end of a longer one
+        let preceded_by = www_authenticate[..at].chars().last();
+        if preceded_by.is_some_and(|c| !matches!(c, ' ' | '\t' | ',')) {
+            continue;
+        }
+        let Some(value) = www_authenticate[start..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let value = match value.strip_prefix('"') {
+            Some(quoted) => unquote(quoted),
+            None => value
+                .split([',', ' ', '\t'])
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        return Some(value).filter(|value| !value.is_empty());
+    }
+    None
+}
+
+/// The contents of a quoted string up to its closing quote, with escapes resolved
+fn unquote(quoted: &str) -> String {
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next()),
+            '"' => break,
+            c => value.push(c),
+        }
+    }
+    value
+}
+
+/// Where to look for the metadata of `mcp_server_url`: where its challenge points, otherwise the
+/// well-known locations for its path and for its host
+fn metadata_urls(mcp_server_url: &Url, www_authenticate: Option<&str>) -> Vec<Url> {
+    if let Some(url) = www_authenticate.and_then(resource_metadata_url) {
+        return mcp_server_url.join(&url).into_iter().collect();
+    }
+    let mut urls = Vec::new();
+    let path = mcp_server_url.path().trim_end_matches('/');
+    for suffix in [path, ""] {
+        let mut url = mcp_server_url.clone();
+        url.set_path(&format!("{}{}", WELL_KNOWN_PATH, suffix));
+        url.set_query(None);
+        url.set_fragment(None);
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Whether metadata naming `resource` may be used for `mcp_server_url`. The RFC wants them
+/// identical; a resource that is a prefix of the server's URL (often just its origin) is
+/// accepted too, since it covers the server as well.
+fn resource_matches(resource: &str, mcp_server_url: &Url) -> bool {
+    let resource = resource.trim_end_matches('/');
+    let server = mcp_server_url.as_str().trim_end_matches('/');
+    server == resource
+        || server
+            .strip_prefix(resource)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+async fn fetch(url: &Url, http: &reqwest::Client) -> Result<ProtectedResourceMetadata> {
+    let resp = backoff::send(
+        http.get(url.clone())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(FETCH_TIMEOUT),
+    )
+    .await?;
+    if !resp.status().is_success() {
+        bail!("{} answered {}", url, resp.status());
+    }
+    Ok(resp.json().await?)
+}
+
+/// The authorization server that protects `mcp_server_url`, from its protected resource
+/// metadata. `www_authenticate` is the header of the server's 401, if there was one. `None`
+/// means the server publishes no usable metadata, and the authorization server metadata should
+/// be looked for on the MCP host itself.
+pub async fn authorization_server(
+    mcp_server_url: &str,
+    www_authenticate: Option<&str>,
+    http: &reqwest::Client,
+) -> Option<String> {
+    let server_url = Url::parse(mcp_server_url).ok()?;
+    for url in metadata_urls(&server_url, www_authenticate) {
+        let metadata = match fetch(&url, http).await {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                debug!("No protected resource metadata at {}: {}", url, e);
+                continue;
+            }
+        };
+        if let Some(resource) = &metadata.resource {
+            if !resource_matches(resource, &server_url) {
+                warn!(
+                    "Ignoring protected resource metadata at {}: it is for {}, not {}",
+                    url, resource, mcp_server_url
+                );
+                continue;
+            }
+        }
+        match metadata.authorization_servers.into_iter().next() {
+            Some(authorization_server) => return Some(authorization_server),
+            None => debug!("Protected resource metadata at {} names no server", url),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_resource_metadata_url() {
+        assert_eq!(
+            resource_metadata_url(
+                r#"Bearer realm="mcp", error="invalid_token", resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource""#
+            )
+            .as_deref(),
+            Some("https://mcp.example.com/.well-known/oauth-protected-resource")
+        );
+        assert_eq!(
+            resource_metadata_url(r#"Basic realm="x", Bearer Resource_Metadata = "/meta\"data""#)
+                .as_deref(),
+            Some(r#"/meta"data"#)
+        );
+        assert_eq!(
+            resource_metadata_url("Bearer resource_metadata=/meta, error=invalid").as_deref(),
+            Some("/meta")
+        );
+        assert_eq!(
+            resource_metadata_url(r#"Bearer x_resource_metadata="/other""#),
+            None
+        );
+        assert_eq!(resource_metadata_url(r#"Bearer realm="mcp""#), None);
+    }
+
+    #[test]
+    fn test_challenge_param() {
+        let header = r#"Bearer error="insufficient_scope", scope="files:read files:write""#;
+        assert_eq!(
+            challenge_param(header, "scope").as_deref(),
+            Some("files:read files:write")
+        );
+        assert_eq!(
+            challenge_param(header, "error").as_deref(),
+            Some("insufficient_scope")
+        );
+        assert_eq!(challenge_param(r#"Bearer scope="""#, "scope"), None);
+    }
+
+    #[test]
+    fn test_metadata_urls() {
+        let server = Url::parse("https://mcp.example.com/v1/mcp").unwrap();
+        let urls = |header| {
+            metadata_urls(&server, header)
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            urls(Some(r#"Bearer resource_metadata="/meta""#)),
+            vec!["https://mcp.example.com/meta"]
+        );
+        assert_eq!(
+            urls(None),
+            vec![
+                "https://mcp.example.com/.well-known/oauth-protected-resource/v1/mcp",
+                "https://mcp.example.com/.well-known/oauth-protected-resource",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resource_matches() {
+        let server = Url::parse("https://mcp.example.com/v1/mcp").unwrap();
+        assert!(resource_matches("https://mcp.example.com/v1/mcp/", &server));
+        assert!(resource_matches("https://mcp.example.com", &server));
+        assert!(!resource_matches("https://mcp.example.com/v1/m", &server));
+        assert!(!resource_matches("https://other.example.com", &server));
+    }
+
+    #[tokio::test]
+    async fn test_authorization_server_from_challenge() {
+        let server = MockServer::start().await;
+        let mcp_url = format!("{}/mcp", server.uri());
+        Mock::given(method("GET"))
+            .and(path("/resource-meta"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "resource": mcp_url,
+                "authorization_servers": ["https://auth.example.com"]
+            })))
+            .mount(&server)
+            .await;
+
+        let header = format!(
+            r#"Bearer resource_metadata="{}/resource-meta""#,
+            server.uri()
+        );
+        let http = reqwest::Client::new();
+        assert_eq!(
+            authorization_server(&mcp_url, Some(&header), &http)
+                .await
+                .as_deref(),
+            Some("https://auth.example.com")
+        );
+        // Nothing at the well-known locations
+        assert_eq!(authorization_server(&mcp_url, None, &http).await, None);
+    }
+}
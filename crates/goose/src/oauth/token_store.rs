@@ -0,0 +1,909 @@
+//! Where OAuth tokens for MCP servers are kept between sessions.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use keyring::Entry;
+use oauth2::{
+    basic::BasicTokenType, AccessToken, EmptyExtraTokenFields, StandardTokenResponse, TokenResponse,
+};
+use once_cell::sync::Lazy;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+use super::oidc::Identity;
+use crate::config::Config;
+
+const KEYRING_SERVICE: &str = "goose-oauth";
+const TOKENS_FILE: &str = "tokens.enc";
+/// The keyring entry holding the token file's key
+const FILE_KEY_ACCOUNT: &str = "token-file-key";
+/// Bound to every sealed token file, so other data sealed with the same key can't pass for one
+const FILE_AAD: &[u8] = b"goose oauth tokens v1";
+/// The client of imported access tokens that can't be refreshed, which nothing asks for
+const IMPORTED_CLIENT_ID: &str = "goose-import";
+
+/// The base64 key of the token file on machines without a keyring
+pub const TOKEN_FILE_KEY_ENV: &str = "GOOSE_OAUTH_TOKEN_KEY";
+
+/// The profile used when none is chosen
+pub const DEFAULT_PROFILE: &str = "default";
+/// Map from OAuth host to the profile its extensions sign in with unless they pick one
+pub const DEFAULT_PROFILES_KEY: &str = "GOOSE_OAUTH_PROFILES";
+
+/// How long ahead of time the user is warned that a sign-in which can't be renewed stops working
+pub const EXPIRY_WARNING_SECS: i64 = 3600;
+
+pub type OAuthTokenResponse = StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>;
+
+/// Identifies cached tokens: the authorization server that issued them, the client they were
+/// issued to and the profile they belong to. `resource`, the MCP server, only finds tokens
+/// saved before they were shared by servers on the same host.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenKey {
+    pub oauth_host: String,
+    pub resource: String,
+    /// The pre-registered client; `None` for the one goose registers itself
+    pub client_id: Option<String>,
+    /// `None` for the default profile
+    pub profile: Option<String>,
+}
+
+/// `profile` as stored in a key: `None` for the default profile
+fn named_profile(profile: Option<&str>) -> Option<String> {
+    profile
+        .map(str::trim)
+        .filter(|profile| !profile.is_empty() && !profile.eq_ignore_ascii_case(DEFAULT_PROFILE))
+        .map(str::to_string)
+}
+
+fn default_profiles() -> HashMap<String, String> {
+    Config::global()
+        .get_param::<HashMap<String, String>>(DEFAULT_PROFILES_KEY)
+        .unwrap_or_default()
+}
+
+/// The profile extensions of `oauth_host` sign in with when they don't pick one
+pub fn default_profile(oauth_host: &str) -> Option<String> {
+    named_profile(default_profiles().get(oauth_host).map(String::as_str))
+}
+
+/// Make `profile` the default for `oauth_host`; `default` goes back to the unnamed profile
+pub fn set_default_profile(oauth_host: &str, profile: &str) -> Result<()> {
+    let mut profiles = default_profiles();
+    match named_profile(Some(profile)) {
+        Some(profile) => profiles.insert(oauth_host.to_string(), profile),
+        None => profiles.remove(oauth_host),
+    };
+    Config::global().set_param(DEFAULT_PROFILES_KEY, serde_json::to_value(profiles)?)?;
+    Ok(())
+}
+
+impl TokenKey {
+    /// The key for an MCP server; its authorization server metadata is discovered on the same
+    /// host, so that host issues its tokens
+    pub fn for_resource(resource: &str) -> Result<Self> {
+        let url = Url::parse(resource).with_context(|| format!("Invalid MCP URL {}", resource))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("MCP URL {} has no host", resource))?;
+        let oauth_host = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        Ok(Self {
+            oauth_host,
+            resource: resource.trim_end_matches('/').to_string(),
+            client_id: None,
+            profile: None,
+        })
+    }
+
+    /// The key for tokens of the pre-registered client `client_id`
+    pub fn with_client(mut self, client_id: Option<&str>) -> Self {
+        self.client_id = client_id.map(str::to_string);
+        self
+    }
+
+    /// The key for an MCP server signed in to as `profile`, or as its host's default profile
+    /// when none is given
+    pub fn for_profile(resource: &str, profile: Option<&str>) -> Result<Self> {
+        let mut key = Self::for_resource(resource)?;
+        key.profile = match profile {
+            Some(profile) => named_profile(Some(profile)),
+            None => default_profile(&key.oauth_host),
+        };
+        Ok(key)
+    }
+
+    pub fn profile_name(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    fn account(&self) -> String {
+        let mut account = self.oauth_host.clone();
+        if let Some(client_id) = &self.client_id {
+            account.push_str(&format!("|client={}", client_id));
+        }
+        if let Some(profile) = &self.profile {
+            account.push_str(&format!("|profile={}", profile));
+        }
+        account
+    }
+
+    /// Where tokens were kept when each MCP server had its own
+    fn legacy_account(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("{}|{}|{}", self.oauth_host, self.resource, profile),
+            None => format!("{}|{}", self.oauth_host, self.resource),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub client_id: String,
+    pub token_response: OAuthTokenResponse,
+    /// When the access token stops working, if the server said
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The authorization server named by the MCP server's protected resource metadata, where
+    /// its OAuth metadata is discovered; without one it is discovered on the MCP host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_server: Option<String>,
+    /// The scopes the token was granted; empty for tokens saved before scopes were tracked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+    /// Who signed in, when the authorization server is an OpenID provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<Identity>,
+}
+
+impl StoredToken {
+    pub fn new(client_id: String, token_response: OAuthTokenResponse) -> Self {
+        let expires_at = token_response
+            .expires_in()
+            .and_then(|expires_in| chrono::Duration::from_std(expires_in).ok())
+            .map(|expires_in| Utc::now() + expires_in);
+        let scopes = token_response
+            .scopes()
+            .map(|scopes| scopes.iter().map(|scope| scope.to_string()).collect())
+            .unwrap_or_default();
+        Self {
+            client_id,
+            token_response,
+            expires_at,
+            authorization_server: None,
+            scopes,
+            identity: None,
+        }
+    }
+
+    /// Servers only have to say which scopes they granted when it differs from the request, so
+    /// without an answer the token has the scopes that were asked for
+    pub fn with_requested_scopes(mut self, requested: &[String]) -> Self {
+        if self.scopes.is_empty() {
+            self.scopes = requested.to_vec();
+        }
+        self
+    }
+
+    /// Whether the token was granted every scope in `needed`, which may hold several scopes to
+    /// an entry separated by spaces as in a `scope` parameter. Tokens saved before scopes were
+    /// tracked don't say, so they are used until the server answers `insufficient_scope`.
+    pub fn grants(&self, needed: &[String]) -> bool {
+        if self.scopes.is_empty() {
+            return true;
+        }
+        let granted: HashSet<&str> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.split_whitespace())
+            .collect();
+        needed
+            .iter()
+            .flat_map(|scope| scope.split_whitespace())
+            .all(|scope| granted.contains(scope))
+    }
+
+    pub fn with_authorization_server(mut self, authorization_server: Option<String>) -> Self {
+        self.authorization_server = authorization_server;
+        self
+    }
+
+    pub fn with_identity(mut self, identity: Option<Identity>) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Whether an expired access token can be renewed without the user
+    pub fn has_refresh_token(&self) -> bool {
+        self.token_response.refresh_token().is_some()
+    }
+
+    /// Whether the user has to sign in again within [`EXPIRY_WARNING_SECS`]: the access token
+    /// runs out and there is no refresh token to renew it with. A refresh token can still be
+    /// turned down, which `TokenRefresher` finds out when it is used.
+    pub fn expiry(&self) -> Option<Expiry> {
+        if self.has_refresh_token() {
+            return None;
+        }
+        let expires_at = self.expires_at?;
+        let now = Utc::now();
+        if expires_at <= now {
+            Some(Expiry::Expired(expires_at))
+        } else if expires_at <= now + chrono::Duration::seconds(EXPIRY_WARNING_SECS) {
+            Some(Expiry::ExpiresSoon(expires_at))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the access token has expired, or will within a minute; tokens without an expiry
+    /// are used until the server rejects them
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now() + chrono::Duration::seconds(60))
+    }
+
+    /// A token provisioned by hand: what `goose auth export` wrote, an OAuth token response
+    /// (whose `expires_in` counts from now), or a bare access token. Refreshing needs the
+    /// client the token was issued to, so a refresh token without one is refused.
+    pub fn import(contents: &str, client_id: Option<&str>) -> Result<Self> {
+        let contents = contents.trim();
+        if let Ok(mut stored) = serde_json::from_str::<StoredToken>(contents) {
+            if let Some(client_id) = client_id {
+                stored.client_id = client_id.to_string();
+            }
+            return Ok(stored);
+        }
+        let token_response = match serde_json::from_str::<OAuthTokenResponse>(contents) {
+            Ok(token_response) => token_response,
+            Err(_) if contents.starts_with('{') => bail!(
+                "The token file is neither an export of goose auth nor an OAuth token response"
+            ),
+            Err(_) if contents.is_empty() || contents.contains(char::is_whitespace) => {
+                bail!("The token file should hold one access token, or JSON")
+            }
+            Err(_) => OAuthTokenResponse::new(
+                AccessToken::new(contents.to_string()),
+                BasicTokenType::Bearer,
+                EmptyExtraTokenFields {},
+            ),
+        };
+        let client_id = match client_id {
+            Some(client_id) => client_id.to_string(),
+            None if token_response.refresh_token().is_some() => bail!(
+                "The token has a refresh token; name the client it was issued to so goose can use it"
+            ),
+            None => IMPORTED_CLIENT_ID.to_string(),
+        };
+        Ok(Self::new(client_id, token_response))
+    }
+}
+
+/// A sign-in that needs the user again soon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    Expired(DateTime<Utc>),
+    ExpiresSoon(DateTime<Utc>),
+}
+
+impl std::fmt::Display for Expiry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expiry::Expired(at) => write!(
+                f,
+                "expired {}",
+                at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            ),
+            Expiry::ExpiresSoon(at) => {
+                let minutes = (*at - Utc::now()).num_minutes().max(1);
+                write!(
+                    f,
+                    "expires in {} minute{}",
+                    minutes,
+                    if minutes == 1 { "" } else { "s" }
+                )
+            }
+        }
+    }
+}
+
+/// Where cached OAuth tokens are kept. The CLI and goosed go through the shared store of
+/// [`token_store`], so a sign-in in one is used by the other instead of signing in again.
+pub trait TokenStore: Send + Sync {
+    fn load(&self, key: &TokenKey) -> Result<Option<StoredToken>>;
+    fn save(&self, key: &TokenKey, token: &StoredToken) -> Result<()>;
+    fn remove(&self, key: &TokenKey) -> Result<()>;
+}
+
+fn default_dir() -> PathBuf {
+    // - macOS/Linux: ~/.config/goose/oauth
+    // - Windows:     ~\AppData\Roaming\Block\goose\config\oauth\
+    choose_app_strategy(crate::config::APP_STRATEGY.clone())
+        .expect("goose requires a home dir")
+        .in_config_dir("oauth")
+}
+
+/// The keyring, or only the encrypted file when GOOSE_DISABLE_KEYRING is set
+fn default_store() -> Arc<dyn TokenStore> {
+    let file = EncryptedFileStore::in_dir(default_dir());
+    if std::env::var("GOOSE_DISABLE_KEYRING").is_ok() {
+        Arc::new(file)
+    } else {
+        Arc::new(KeyringStore::new(
+            KEYRING_SERVICE,
+            file.with_keyring(KEYRING_SERVICE),
+        ))
+    }
+}
+
+static TOKEN_STORE: Lazy<RwLock<Arc<dyn TokenStore>>> = Lazy::new(|| RwLock::new(default_store()));
+
+/// The store OAuth tokens are cached in
+pub fn token_store() -> Arc<dyn TokenStore> {
+    TOKEN_STORE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Cache OAuth tokens in `store` from now on, e.g. a [`MemoryStore`] in tests or an embedding
+/// that keeps credentials itself. Connected extensions keep the store they started with.
+pub fn set_token_store(store: Arc<dyn TokenStore>) {
+    *TOKEN_STORE.write().unwrap_or_else(|e| e.into_inner()) = store;
+}
+
+/// Tokens in the OS keyring, in `fallback` when the keyring is unavailable
+pub struct KeyringStore {
+    service: String,
+    fallback: EncryptedFileStore,
+}
+
+impl KeyringStore {
+    pub fn new(service: &str, fallback: EncryptedFileStore) -> Self {
+        Self {
+            service: service.to_string(),
+            fallback,
+        }
+    }
+}
+
+impl TokenStore for KeyringStore {
+    fn load(&self, key: &TokenKey) -> Result<Option<StoredToken>> {
+        let get = |account: &str| Entry::new(&self.service, account)?.get_password();
+        match get(&key.account()).or_else(|e| match e {
+            keyring::Error::NoEntry => get(&key.legacy_account()),
+            e => Err(e),
+        }) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(keyring::Error::NoEntry) => self.fallback.load(key),
+            Err(e) => {
+                warn!("Keyring unavailable for OAuth tokens, using file: {}", e);
+                self.fallback.load(key)
+            }
+        }
+    }
+
+    fn save(&self, key: &TokenKey, token: &StoredToken) -> Result<()> {
+        let json = serde_json::to_string(token)?;
+        match Entry::new(&self.service, &key.account()).and_then(|e| e.set_password(&json)) {
+            Ok(()) => {
+                if let Ok(legacy) = Entry::new(&self.service, &key.legacy_account()) {
+                    let _ = legacy.delete_credential();
+                }
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Keyring unavailable for OAuth tokens, using file: {}", e);
+                self.fallback.save(key, token)
+            }
+        }
+    }
+
+    fn remove(&self, key: &TokenKey) -> Result<()> {
+        for account in [key.account(), key.legacy_account()] {
+            match Entry::new(&self.service, &account).and_then(|e| e.delete_credential()) {
+                Ok(()) | Err(keyring::Error::NoEntry) => {}
+                Err(e) => warn!("Failed to remove OAuth tokens from the keyring: {}", e),
+            }
+        }
+        self.fallback.remove(key)
+    }
+}
+
+/// Tokens kept only as long as the process, for tests and one-off runs that shouldn't leave
+/// credentials behind
+#[derive(Default)]
+pub struct MemoryStore {
+    tokens: std::sync::Mutex<HashMap<String, StoredToken>>,
+}
+
+impl MemoryStore {
+    fn tokens(&self) -> std::sync::MutexGuard<'_, HashMap<String, StoredToken>> {
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl TokenStore for MemoryStore {
+    fn load(&self, key: &TokenKey) -> Result<Option<StoredToken>> {
+        Ok(self.tokens().get(&key.account()).cloned())
+    }
+
+    fn save(&self, key: &TokenKey, token: &StoredToken) -> Result<()> {
+        self.tokens().insert(key.account(), token.clone());
+        Ok(())
+    }
+
+    fn remove(&self, key: &TokenKey) -> Result<()> {
+        self.tokens().remove(&key.account());
+        Ok(())
+    }
+}
+
+/// Move the credentials earlier versions saved per extension name into `store` under `key`.
+/// The old secret is removed only once they are saved, so a failed save keeps them.
+pub fn migrate_legacy_credentials(
+    store: &dyn TokenStore,
+    key: &TokenKey,
+    name: &str,
+) -> Option<StoredToken> {
+    #[derive(Deserialize)]
+    struct LegacyCredentials {
+        client_id: String,
+        token_response: Option<OAuthTokenResponse>,
+    }
+
+    let config = Config::global();
+    let secret = format!("oauth_creds_{name}");
+    let legacy: LegacyCredentials = config.get_secret(&secret).ok()?;
+    let stored = StoredToken {
+        client_id: legacy.client_id,
+        token_response: legacy.token_response?,
+        expires_at: None,
+        authorization_server: None,
+        scopes: Vec::new(),
+        identity: None,
+    };
+    match store.save(key, &stored) {
+        Ok(()) => {
+            if let Err(e) = config.delete_secret(&secret) {
+                warn!("Failed to remove old OAuth credentials for {}: {}", name, e);
+            }
+        }
+        Err(e) => warn!("Failed to move OAuth credentials for {}: {}", name, e),
+    }
+    Some(stored)
+}
+
+/// Where the key of an [`EncryptedFileStore`] comes from
+enum FileKey {
+    /// `GOOSE_OAUTH_TOKEN_KEY`
+    Env,
+    /// `GOOSE_OAUTH_TOKEN_KEY` when set, otherwise the keyring entry of this service, created
+    /// the first time tokens are written
+    Keyring(String),
+    Fixed([u8; 32]),
+}
+
+/// All tokens in one file, sealed with ChaCha20-Poly1305 under a key kept elsewhere
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    key: FileKey,
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32]> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("The OAuth token file key should be 32 bytes of base64"))
+}
+
+impl EncryptedFileStore {
+    /// The store in `dir`, keyed by `GOOSE_OAUTH_TOKEN_KEY`
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            path: dir.into().join(TOKENS_FILE),
+            key: FileKey::Env,
+        }
+    }
+
+    /// Keep the key in the keyring under `service`, unless `GOOSE_OAUTH_TOKEN_KEY` is set
+    pub fn with_keyring(mut self, service: &str) -> Self {
+        self.key = FileKey::Keyring(service.to_string());
+        self
+    }
+
+    /// Seal the file with `key`, e.g. one an embedding keeps itself
+    pub fn with_key(mut self, key: [u8; 32]) -> Self {
+        self.key = FileKey::Fixed(key);
+        self
+    }
+
+    /// The file's key, created in the keyring when `create` is set and there is none yet
+    fn key(&self, create: bool) -> Result<[u8; 32]> {
+        let service = match &self.key {
+            FileKey::Fixed(key) => return Ok(*key),
+            _ if std::env::var(TOKEN_FILE_KEY_ENV).is_ok() => {
+                return decode_key(&std::env::var(TOKEN_FILE_KEY_ENV)?);
+            }
+            FileKey::Env => bail!(
+                "No keyring for OAuth tokens; set {} to a base64 key to keep them in a file",
+                TOKEN_FILE_KEY_ENV
+            ),
+            FileKey::Keyring(service) => service,
+        };
+        let entry = Entry::new(service, FILE_KEY_ACCOUNT)?;
+        match entry.get_password() {
+            Ok(encoded) => decode_key(&encoded),
+            Err(keyring::Error::NoEntry) if create => {
+                let key: [u8; 32] = rand::random();
+                entry.set_password(&base64::engine::general_purpose::STANDARD.encode(key))?;
+                Ok(key)
+            }
+            Err(keyring::Error::NoEntry) => bail!("The key of the OAuth token file is missing"),
+            Err(e) => bail!(
+                "The keyring with the OAuth token file's key is unavailable ({}); set {} instead",
+                e,
+                TOKEN_FILE_KEY_ENV
+            ),
+        }
+    }
+
+    fn read_all(&self) -> Result<HashMap<String, StoredToken>> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let sealed = base64::engine::general_purpose::STANDARD.decode(contents.trim())?;
+        Ok(serde_json::from_slice(&open(&self.key(false)?, &sealed)?)?)
+    }
+
+    fn write_all(&self, tokens: &HashMap<String, StoredToken>) -> Result<()> {
+        let sealed = seal(&self.key(true)?, &serde_json::to_vec(tokens)?)?;
+        write_private(
+            &self.path,
+            base64::engine::general_purpose::STANDARD
+                .encode(sealed)
+                .as_bytes(),
+        )
+    }
+}
+
+impl TokenStore for EncryptedFileStore {
+    fn load(&self, key: &TokenKey) -> Result<Option<StoredToken>> {
+        let mut tokens = self.read_all()?;
+        Ok(tokens
+            .remove(&key.account())
+            .or_else(|| tokens.remove(&key.legacy_account())))
+    }
+
+    fn save(&self, key: &TokenKey, token: &StoredToken) -> Result<()> {
+        let mut tokens = self.read_all().unwrap_or_else(|e| {
+            warn!("Replacing unreadable OAuth token file: {}", e);
+            HashMap::new()
+        });
+        tokens.remove(&key.legacy_account());
+        tokens.insert(key.account(), token.clone());
+        self.write_all(&tokens)
+    }
+
+    fn remove(&self, key: &TokenKey) -> Result<()> {
+        let mut tokens = self.read_all()?;
+        let removed = tokens.remove(&key.account()).is_some();
+        if tokens.remove(&key.legacy_account()).is_some() || removed {
+            self.write_all(&tokens)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write `contents` to `path`, readable by its owner only. The contents go to a new file that
+/// is moved over `path`, so a file that was there before doesn't keep its permissions.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(format!(".{}.partial", &suffix[..8]));
+    let partial = PathBuf::from(partial);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options
+        .open(&partial)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, contents))
+        .and_then(|()| std::fs::rename(&partial, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    Ok(written?)
+}
+
+fn aead_key(key: &[u8; 32]) -> LessSafeKey {
+    LessSafeKey::new(
+        UnboundKey::new(&CHACHA20_POLY1305, key).expect("ChaCha20-Poly1305 keys are 32 bytes"),
+    )
+}
+
+/// `nonce || ciphertext || tag`, with a random nonce for every write
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let mut ciphertext = plaintext.to_vec();
+    aead_key(key)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(FILE_AAD),
+            &mut ciphertext,
+        )
+        .map_err(|_| anyhow!("Failed to encrypt the OAuth token file"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN + CHACHA20_POLY1305.tag_len() {
+        return Err(anyhow!("OAuth token file is truncated"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).expect("split at the nonce length");
+    let mut plaintext = ciphertext.to_vec();
+    let len = aead_key(key)
+        .open_in_place(nonce, Aad::from(FILE_AAD), &mut plaintext)
+        .map_err(|_| {
+            anyhow!("OAuth token file failed authentication, it was modified or the key changed")
+        })?
+        .len();
+    plaintext.truncate(len);
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oauth2::{RefreshToken, Scope};
+    use std::time::Duration;
+
+    fn token(access: &str, expires_in: Option<u64>) -> StoredToken {
+        let mut response = OAuthTokenResponse::new(
+            AccessToken::new(access.to_string()),
+            BasicTokenType::Bearer,
+            EmptyExtraTokenFields {},
+        );
+        response.set_refresh_token(Some(RefreshToken::new("refresh".to_string())));
+        response.set_expires_in(expires_in.map(Duration::from_secs).as_ref());
+        StoredToken::new("client".to_string(), response)
+    }
+
+    #[test]
+    fn test_token_key() {
+        let key = TokenKey::for_resource("https://mcp.example.com:8443/mcp/").unwrap();
+        assert_eq!(key.oauth_host, "mcp.example.com:8443");
+        assert_eq!(key.resource, "https://mcp.example.com:8443/mcp");
+        assert_eq!(
+            TokenKey::for_resource("https://mcp.example.com/mcp")
+                .unwrap()
+                .oauth_host,
+            "mcp.example.com"
+        );
+        assert!(TokenKey::for_resource("not a url").is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let default = TokenKey::for_resource("https://mcp.example.com/mcp").unwrap();
+        assert_eq!(default.account(), "mcp.example.com");
+        assert_eq!(
+            default.legacy_account(),
+            "mcp.example.com|https://mcp.example.com/mcp"
+        );
+        assert_eq!(default.profile_name(), DEFAULT_PROFILE);
+
+        let work = TokenKey::for_profile("https://mcp.example.com/mcp", Some("work")).unwrap();
+        assert_eq!(work.account(), "mcp.example.com|profile=work");
+        assert_eq!(
+            work.legacy_account(),
+            "mcp.example.com|https://mcp.example.com/mcp|work"
+        );
+        assert_eq!(
+            work.clone().with_client(Some("goose-cli")).account(),
+            "mcp.example.com|client=goose-cli|profile=work"
+        );
+        assert_ne!(work, default);
+        assert_eq!(
+            TokenKey::for_profile("https://mcp.example.com/mcp", Some(" Default ")).unwrap(),
+            default
+        );
+    }
+
+    #[test]
+    fn test_expiry() {
+        assert!(!token("a", Some(3600)).is_expired());
+        assert!(token("a", Some(30)).is_expired());
+        assert!(!token("a", None).is_expired());
+
+        // Renewed with the refresh token, no need to warn
+        assert_eq!(token("a", Some(30)).expiry(), None);
+        let mut without_refresh = token("a", Some(600));
+        without_refresh.token_response.set_refresh_token(None);
+        assert!(matches!(
+            without_refresh.expiry(),
+            Some(Expiry::ExpiresSoon(_))
+        ));
+        without_refresh.expires_at = Some(Utc::now() - chrono::Duration::minutes(5));
+        assert!(matches!(without_refresh.expiry(), Some(Expiry::Expired(_))));
+        without_refresh.expires_at = Some(Utc::now() + chrono::Duration::days(1));
+        assert_eq!(without_refresh.expiry(), None);
+    }
+
+    #[test]
+    fn test_granted_scopes() {
+        let scopes = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let requested = token("a", None).with_requested_scopes(&scopes(&["mcp:read"]));
+        assert!(requested.grants(&scopes(&["mcp:read"])));
+        assert!(!requested.grants(&scopes(&["mcp:read", "mcp:write"])));
+        assert!(!requested.grants(&scopes(&["mcp:read mcp:write"])));
+        assert!(!requested.grants(&scopes(&["mcp"])));
+        // Saved before scopes were tracked
+        assert!(token("a", None).grants(&scopes(&["mcp:read", "mcp:write"])));
+
+        let both = token("a", None).with_requested_scopes(&scopes(&["mcp:read mcp:write"]));
+        assert!(both.grants(&scopes(&["mcp:write", "mcp:read"])));
+
+        let mut response = token("a", None).token_response;
+        response.set_scopes(Some(vec![Scope::new("mcp:read".to_string())]));
+        // What the server says it granted wins over what was asked for
+        let granted = StoredToken::new("client".to_string(), response)
+            .with_requested_scopes(&scopes(&["mcp:read", "mcp:write"]));
+        assert_eq!(granted.scopes, scopes(&["mcp:read"]));
+        assert!(granted.grants(&[]));
+    }
+
+    #[test]
+    fn test_import() {
+        let exported = serde_json::to_string(&token("exported", Some(3600))).unwrap();
+        let imported = StoredToken::import(&exported, None).unwrap();
+        assert_eq!(imported.client_id, "client");
+        assert_eq!(imported.token_response.access_token().secret(), "exported");
+
+        let response = r#"{"access_token": "a", "token_type": "Bearer", "expires_in": 3600}"#;
+        let imported = StoredToken::import(response, None).unwrap();
+        assert_eq!(imported.client_id, IMPORTED_CLIENT_ID);
+        assert!(imported.expires_at.is_some());
+
+        let with_refresh = r#"{"access_token": "a", "token_type": "Bearer", "refresh_token": "r"}"#;
+        assert!(StoredToken::import(with_refresh, None).is_err());
+        let imported = StoredToken::import(with_refresh, Some("goose-desktop")).unwrap();
+        assert_eq!(imported.client_id, "goose-desktop");
+
+        let bare = StoredToken::import("gho_abc123\n", None).unwrap();
+        assert_eq!(bare.token_response.access_token().secret(), "gho_abc123");
+        assert_eq!(bare.expires_at, None);
+        assert!(StoredToken::import("two words", None).is_err());
+        assert!(StoredToken::import(r#"{"token": "a"}"#, None).is_err());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store: Arc<dyn TokenStore> = Arc::new(MemoryStore::default());
+        let work = TokenKey::for_profile("https://mcp.example.com/mcp", Some("work")).unwrap();
+        let default = TokenKey::for_resource("https://mcp.example.com/mcp").unwrap();
+
+        store.save(&work, &token("work", Some(3600))).unwrap();
+        assert!(store.load(&default).unwrap().is_none());
+        let shared = store.clone();
+        assert_eq!(
+            shared
+                .load(&work)
+                .unwrap()
+                .unwrap()
+                .token_response
+                .access_token()
+                .secret(),
+            "work"
+        );
+        store.remove(&work).unwrap();
+        assert!(shared.load(&work).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let key: [u8; 32] = rand::random();
+        let sealed = seal(&key, b"secret tokens").unwrap();
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(open(&key, &sealed).unwrap(), b"secret tokens");
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open(&key, &tampered).is_err());
+        assert!(open(&rand::random(), &sealed).is_err());
+        assert!(open(&key, &sealed[..10]).is_err());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let key: [u8; 32] = rand::random();
+        let store = EncryptedFileStore::in_dir(dir.path()).with_key(key);
+        let first = TokenKey::for_resource("https://a.example.com/mcp").unwrap();
+        let second = TokenKey::for_resource("https://b.example.com/mcp").unwrap();
+
+        assert!(store.load(&first).unwrap().is_none());
+        store.save(&first, &token("first", Some(3600))).unwrap();
+        store.save(&second, &token("second", None)).unwrap();
+
+        let loaded = store.load(&first).unwrap().unwrap();
+        assert_eq!(loaded.token_response.access_token().secret(), "first");
+        assert!(loaded.expires_at.is_some());
+        let contents = std::fs::read_to_string(dir.path().join(TOKENS_FILE)).unwrap();
+        assert!(!contents.contains("first"));
+
+        // Nothing but the token file is written next to it
+        let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        // Another key can't read it
+        let other = EncryptedFileStore::in_dir(dir.path()).with_key(rand::random());
+        assert!(other.load(&first).is_err());
+
+        store.remove(&first).unwrap();
+        assert!(store.load(&first).unwrap().is_none());
+        assert!(store.load(&second).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_servers_on_a_host_share_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedFileStore::in_dir(dir.path()).with_key(rand::random());
+        let docs = TokenKey::for_resource("https://mcp.example.com/docs").unwrap();
+        let tickets = TokenKey::for_resource("https://mcp.example.com/tickets").unwrap();
+
+        // Saved when each server had an entry of its own
+        let legacy = HashMap::from([(docs.legacy_account(), token("old", None))]);
+        store.write_all(&legacy).unwrap();
+        assert!(store.load(&docs).unwrap().is_some());
+        assert!(store.load(&tickets).unwrap().is_none());
+
+        store.save(&docs, &token("new", None)).unwrap();
+        let shared = store.load(&tickets).unwrap().unwrap();
+        assert_eq!(shared.token_response.access_token().secret(), "new");
+        assert_eq!(store.read_all().unwrap().len(), 1);
+        let registered = tickets.with_client(Some("goose-cli"));
+        assert!(store.load(&registered).unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TOKENS_FILE);
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+//! Waiting for the browser to hand back the authorization code.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use super::AuthError;
+use crate::config::Config;
+
+pub const OAUTH_TIMEOUT_KEY: &str = "GOOSE_OAUTH_TIMEOUT";
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// How long to wait for `service`'s sign-in
+pub fn timeout(service: &str) -> Duration {
+    let config = Config::global();
+    let secs = config
+        .get_param::<u64>(&format!(
+            "{}_OAUTH_TIMEOUT",
+            service.to_uppercase().replace(['-', ' '], "_")
+        ))
+        .or_else(|_| config.get_param::<u64>(OAUTH_TIMEOUT_KEY))
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs.max(1))
+}
+
+/// Wait for the callback server to send the code, until `timeout` runs out or Ctrl-C is pressed
+pub async fn wait_for_code<T>(
+    receiver: &mut oneshot::Receiver<T>,
+    timeout: Duration,
+) -> Result<T, AuthError> {
+    tokio::select! {
+        code = receiver => code.map_err(|_| {
+            AuthError::Other(anyhow::anyhow!("The sign-in callback server stopped"))
+        }),
+        _ = tokio::time::sleep(timeout) => Err(AuthError::Timeout(timeout)),
+        _ = tokio::signal::ctrl_c() => Err(AuthError::Cancelled),
+    }
+}
+
+fn wants_retry(answer: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+    answer.is_empty() || answer == "y" || answer == "yes"
+}
+
+/// Whether to wait for `name`'s sign-in again after `error`. Only asks, on the terminal, when
+/// the sign-in timed out or was cancelled; there is nobody to ask without a terminal.
+pub async fn offer_retry(name: &str, error: &AuthError) -> bool {
+    if !matches!(error, AuthError::Timeout(_) | AuthError::Cancelled)
+        || !std::io::stdin().is_terminal()
+    {
+        return false;
+    }
+    eprint!("{}. Try signing in to {} again? [Y/n] ", error, name);
+    if std::io::stderr().flush().is_err() {
+        return false;
+    }
+    let answer = tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line).map(|_| line)
+    })
+    .await;
+    matches!(answer, Ok(Ok(answer)) if wants_retry(&answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_code() {
+        let (sender, mut receiver) = oneshot::channel();
+        sender.send("code").unwrap();
+        assert_eq!(
+            wait_for_code(&mut receiver, Duration::from_secs(5))
+                .await
+                .unwrap(),
+            "code"
+        );
+
+        let (_sender, mut receiver) = oneshot::channel::<String>();
+        let error = wait_for_code(&mut receiver, Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthError::Timeout(_)));
+    }
+
+    #[test]
+    fn test_wants_retry() {
+        assert!(wants_retry("\n"));
+        assert!(wants_retry("Y\n"));
+        assert!(wants_retry("yes"));
+        assert!(!wants_retry("n\n"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Offline mode: `goose --offline`, or `GOOSE_OFFLINE: true` in the config.
+
+use std::net::IpAddr;
+
+use url::Url;
+
+use crate::config::Config;
+
+pub const OFFLINE_KEY: &str = "GOOSE_OFFLINE";
+
+/// Providers that can run against a local server, and the config key holding their host
+const LOCAL_CAPABLE_PROVIDERS: &[(&str, &str, &str)] = &[
+    (
+        "ollama",
+        "OLLAMA_HOST",
+        crate::providers::ollama::OLLAMA_HOST,
+    ),
+    ("openai", "OPENAI_HOST", "https://api.openai.com"),
+    ("litellm", "LITELLM_HOST", "http://localhost:4000"),
+];
+
+#[derive(Debug, thiserror::Error)]
+#[error("{what} needs the network, which is disabled in offline mode (run without --offline or unset GOOSE_OFFLINE to allow it)")]
+pub struct NetworkDisabled {
+    pub what: String,
+}
+
+pub fn is_offline() -> bool {
+    Config::global()
+        .get_param::<bool>(OFFLINE_KEY)
+        .unwrap_or(false)
+}
+
+/// Turn offline mode on for this process and the extensions it starts
+pub fn set_offline() {
+    std::env::set_var(OFFLINE_KEY, "true");
+}
+
+/// Fail with [`NetworkDisabled`] if goose is offline; call before doing `what` over the network
+pub fn require_network(what: impl Into<String>) -> Result<(), NetworkDisabled> {
+    if is_offline() {
+        Err(NetworkDisabled { what: what.into() })
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether `host` (a URL, `host:port` or a bare host) names this machine
+pub fn is_local_host(host: &str) -> bool {
+    let host = host.trim();
+    let parsed = if host.contains("://") {
+        Url::parse(host).ok()
+    } else {
+        Url::parse(&format!("http://{}", host)).ok()
+    };
+    let Some(name) = parsed.as_ref().and_then(|url| url.host_str()) else {
+        return false;
+    };
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    if name.eq_ignore_ascii_case("localhost") || name.ends_with(".localhost") {
+        return true;
+    }
+    name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Fail if `provider` would reach past this machine while offline
+pub fn check_provider(provider: &str) -> Result<(), NetworkDisabled> {
+    if !is_offline() {
+        return Ok(());
+    }
+    let local = LOCAL_CAPABLE_PROVIDERS
+        .iter()
+        .find(|(name, _, _)| *name == provider)
+        .is_some_and(|(_, key, default)| {
+            let host = Config::global()
+                .get_param::<String>(key)
+                .unwrap_or_else(|_| default.to_string());
+            is_local_host(&host)
+        });
+    if local {
+        Ok(())
+    } else {
+        Err(NetworkDisabled {
+            what: format!("The {} provider", provider),
+        })
+    }
+}
+
+/// Fail if `url` is not on this machine while offline; `what` says what would fetch it
+pub fn check_url(what: impl Into<String>, url: &str) -> Result<(), NetworkDisabled> {
+    if is_offline() && !is_local_host(url) {
+        Err(NetworkDisabled { what: what.into() })
+    } else {
+        Ok(())
+    }
+}
+
+/// Fail if the remote extension at `uri` is not on this machine while offline
+pub fn check_extension_uri(name: &str, uri: &str) -> Result<(), NetworkDisabled> {
+    check_url(format!("The {} extension ({})", name, uri), uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("localhost"));
+        assert!(is_local_host("localhost:11434"));
+        assert!(is_local_host("http://127.0.0.1:4000"));
+        assert!(is_local_host("http://[::1]:8080/mcp"));
+        assert!(is_local_host("http://api.localhost"));
+        assert!(!is_local_host("https://api.openai.com"));
+        assert!(!is_local_host("http://192.168.1.20:11434"));
+        assert!(!is_local_host(""));
+    }
+}
@@ -0,0 +1,269 @@
+//! Enforce the MCP annotations extensions attach to their tools.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rmcp::model::Tool;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::config::permission::PermissionLevel;
+use crate::config::{Config, PermissionManager};
+use crate::conversation::message::{Message, ToolRequest};
+use crate::tool_inspection::{InspectionAction, InspectionResult, ToolInspector};
+
+pub const TOOL_ANNOTATIONS_KEY: &str = "GOOSE_TOOL_ANNOTATIONS";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationTrust {
+    Trust,
+    #[default]
+    Ignore,
+}
+
+/// How much the annotations of each of the loaded `extensions` are trusted: as configured, or
+/// else trusted for the extensions in `bundled`, which ship with goose
+pub fn annotation_trust(
+    extensions: &[String],
+    bundled: &[String],
+) -> HashMap<String, AnnotationTrust> {
+    let configured = Config::global()
+        .get_param::<HashMap<String, AnnotationTrust>>(TOOL_ANNOTATIONS_KEY)
+        .unwrap_or_default();
+    extensions
+        .iter()
+        .map(|extension| {
+            let trust = configured.get(extension).copied().unwrap_or({
+                if bundled.contains(extension) {
+                    AnnotationTrust::Trust
+                } else {
+                    AnnotationTrust::Ignore
+                }
+            });
+            (extension.clone(), trust)
+        })
+        .collect()
+}
+
+/// Tools whose annotations are followed, split by what they declare
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnnotatedTools {
+    pub destructive: HashSet<String>,
+    /// The read-only tools of each trusted extension
+    pub read_only: HashMap<String, HashSet<String>>,
+}
+
+impl AnnotatedTools {
+    /// Collect the annotations of the extension tools in `tools`; `trust` has every loaded
+    /// extension, whose names prefix their tool names
+    pub fn from_tools(tools: &[Tool], trust: &HashMap<String, AnnotationTrust>) -> Self {
+        let mut annotated = Self::default();
+        for tool in tools {
+            let Some(annotations) = &tool.annotations else {
+                continue;
+            };
+            let Some((extension, trust)) = trust
+                .iter()
+                .find(|(ext, _)| tool.name.starts_with(&format!("{}__", ext)))
+            else {
+                continue;
+            };
+
+            if annotations.destructive_hint == Some(true)
+                && annotations.read_only_hint != Some(true)
+            {
+                annotated.destructive.insert(tool.name.to_string());
+            } else if annotations.read_only_hint == Some(true) && *trust == AnnotationTrust::Trust {
+                annotated
+                    .read_only
+                    .entry(extension.clone())
+                    .or_default()
+                    .insert(tool.name.to_string());
+            }
+        }
+        annotated
+    }
+}
+
+/// Inspector that requires approval for tools annotated as destructive
+pub struct AnnotationInspector {
+    tools: Mutex<AnnotatedTools>,
+    permission_manager: Arc<Mutex<PermissionManager>>,
+}
+
+impl AnnotationInspector {
+    pub fn new(permission_manager: Arc<Mutex<PermissionManager>>) -> Self {
+        Self {
+            tools: Mutex::new(AnnotatedTools::default()),
+            permission_manager,
+        }
+    }
+
+    pub async fn update_tools(&self, tools: AnnotatedTools) {
+        *self.tools.lock().await = tools;
+    }
+
+    pub async fn destructive_tools(&self) -> HashSet<String> {
+        self.tools.lock().await.destructive.clone()
+    }
+}
+
+#[async_trait]
+impl ToolInspector for AnnotationInspector {
+    fn name(&self) -> &'static str {
+        "annotations"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    async fn inspect(
+        &self,
+        tool_requests: &[ToolRequest],
+        _messages: &[Message],
+    ) -> Result<Vec<InspectionResult>> {
+        let tools = self.tools.lock().await;
+        let permission_manager = self.permission_manager.lock().await;
+
+        let results = tool_requests
+            .iter()
+            .filter_map(|request| {
+                let tool_call = request.tool_call.as_ref().ok()?;
+                let tool_name = tool_call.name.as_ref();
+                if !tools.destructive.contains(tool_name)
+                    || permission_manager.get_user_permission(tool_name)
+                        == Some(PermissionLevel::AlwaysAllow)
+                {
+                    return None;
+                }
+                Some(InspectionResult {
+                    tool_request_id: request.id.clone(),
+                    action: InspectionAction::RequireApproval(Some(format!(
+                        "{} is marked as destructive by its extension",
+                        tool_name
+                    ))),
+                    reason: "Tool annotated as destructive".to_string(),
+                    confidence: 1.0,
+                    inspector_name: self.name().to_string(),
+                    finding_id: None,
+                })
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permission::permission_judge::PermissionCheckResult;
+    use crate::tool_inspection::apply_inspection_results_to_permissions;
+    use rmcp::model::{CallToolRequestParam, ToolAnnotations};
+    use rmcp::object;
+
+    fn tool(name: &str, read_only: Option<bool>, destructive: Option<bool>) -> Tool {
+        Tool::new(name.to_string(), "test tool".to_string(), object!({})).annotate(
+            ToolAnnotations {
+                title: None,
+                read_only_hint: read_only,
+                destructive_hint: destructive,
+                idempotent_hint: None,
+                open_world_hint: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_annotated_tools() {
+        let tools = vec![
+            tool("developer__shell", Some(false), Some(true)),
+            tool("developer__list_windows", Some(true), Some(false)),
+            tool("untrusted__delete_everything", Some(false), Some(true)),
+            tool("untrusted__read", Some(true), None),
+            tool("unlisted__read", Some(true), None),
+            tool("platform__manage_schedule", Some(false), Some(true)),
+            Tool::new(
+                "developer__unannotated".to_string(),
+                "no annotations".to_string(),
+                object!({}),
+            ),
+        ];
+        let trust = HashMap::from([
+            ("developer".to_string(), AnnotationTrust::Trust),
+            ("untrusted".to_string(), AnnotationTrust::Ignore),
+            ("unlisted".to_string(), AnnotationTrust::default()),
+        ]);
+
+        let annotated = AnnotatedTools::from_tools(&tools, &trust);
+        // Asking for approval is safe whoever asks for it
+        assert_eq!(
+            annotated.destructive,
+            HashSet::from([
+                "developer__shell".to_string(),
+                "untrusted__delete_everything".to_string()
+            ])
+        );
+        // Skipping it is left to trusted extensions
+        assert_eq!(
+            annotated.read_only,
+            HashMap::from([(
+                "developer".to_string(),
+                HashSet::from(["developer__list_windows".to_string()])
+            )])
+        );
+    }
+
+    #[test]
+    fn test_only_bundled_extensions_are_trusted_by_default() {
+        let extensions = vec!["developer".to_string(), "community".to_string()];
+        let trust = annotation_trust(&extensions, &["developer".to_string()]);
+        assert_eq!(trust["developer"], AnnotationTrust::Trust);
+        assert_eq!(trust["community"], AnnotationTrust::Ignore);
+    }
+
+    #[tokio::test]
+    async fn test_destructive_tool_overrides_auto_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let permission_manager = PermissionManager::new(dir.path().join("permission.yaml"));
+        let inspector = AnnotationInspector::new(Arc::new(Mutex::new(permission_manager)));
+        inspector
+            .update_tools(AnnotatedTools {
+                destructive: HashSet::from(["developer__shell".to_string()]),
+                read_only: HashMap::new(),
+            })
+            .await;
+
+        let request = |id: &str, name: &str| ToolRequest {
+            id: id.to_string(),
+            tool_call: Ok(CallToolRequestParam {
+                name: name.to_string().into(),
+                arguments: Some(object!({})),
+            }),
+        };
+        let requests = vec![
+            request("req_1", "developer__shell"),
+            request("req_2", "developer__text_editor"),
+        ];
+
+        let results = inspector.inspect(&requests, &[]).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].tool_request_id, "req_1");
+
+        // In auto mode the permission inspector approves everything
+        let auto_approved = PermissionCheckResult {
+            approved: requests.clone(),
+            needs_approval: vec![],
+            denied: vec![],
+        };
+        let result = apply_inspection_results_to_permissions(auto_approved, &results);
+        assert_eq!(result.approved.len(), 1);
+        assert_eq!(result.approved[0].id, "req_2");
+        assert_eq!(result.needs_approval.len(), 1);
+        assert_eq!(result.needs_approval[0].id, "req_1");
+    }
+}
@@ -1,8 +1,10 @@
+pub mod annotation_inspector;
 pub mod permission_confirmation;
 pub mod permission_inspector;
 pub mod permission_judge;
 pub mod permission_store;
 
+pub use annotation_inspector::AnnotationInspector;
 pub use permission_confirmation::{Permission, PermissionConfirmation};
 pub use permission_inspector::PermissionInspector;
 pub use permission_judge::detect_read_only_tools;
@@ -1,3 +1,4 @@
+use rmcp::model::JsonObject;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -19,4 +20,7 @@ pub enum PrincipalType {
 pub struct PermissionConfirmation {
     pub principal_type: PrincipalType,
     pub permission: Permission,
+    /// Arguments the user edited before allowing the call, used instead of the requested ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<JsonObject>,
 }
@@ -6,7 +6,7 @@ use crate::permission::permission_judge::PermissionCheckResult;
 use crate::tool_inspection::{InspectionAction, InspectionResult, ToolInspector};
 use anyhow::Result;
 use async_trait::async_trait;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -14,6 +14,8 @@ use tokio::sync::Mutex;
 pub struct PermissionInspector {
     mode: Arc<Mutex<String>>,
     readonly_tools: HashSet<String>,
+    /// Tools each trusted extension annotates as read-only
+    annotated_readonly_tools: Mutex<HashMap<String, HashSet<String>>>,
     regular_tools: HashSet<String>,
     pub permission_manager: Arc<Mutex<PermissionManager>>,
 }
@@ -27,6 +29,7 @@ impl PermissionInspector {
         Self {
             mode: Arc::new(Mutex::new(mode)),
             readonly_tools,
+            annotated_readonly_tools: Mutex::new(HashMap::new()),
             regular_tools,
             permission_manager: Arc::new(Mutex::new(PermissionManager::default())),
         }
@@ -41,6 +44,7 @@ impl PermissionInspector {
         Self {
             mode: Arc::new(Mutex::new(mode)),
             readonly_tools,
+            annotated_readonly_tools: Mutex::new(HashMap::new()),
             regular_tools,
             permission_manager,
         }
@@ -52,6 +56,15 @@ impl PermissionInspector {
         *mode = new_mode;
     }
 
+    /// Replace the read-only tools annotated by each extension, leaving the ones this inspector
+    /// was created with
+    pub async fn update_annotated_readonly_tools(
+        &self,
+        readonly_tools: HashMap<String, HashSet<String>>,
+    ) {
+        *self.annotated_readonly_tools.lock().await = readonly_tools;
+    }
+
     /// Process inspection results into permission decisions
     /// This method takes all inspection results and converts them into a PermissionCheckResult
     /// that can be used by the agent to determine which tools to approve, deny, or ask for approval
@@ -134,6 +147,13 @@ impl ToolInspector for PermissionInspector {
         let mut results = Vec::new();
         let permission_manager = self.permission_manager.lock().await;
         let mode = self.mode.lock().await;
+        let annotated_readonly_tools = self.annotated_readonly_tools.lock().await;
+        let is_readonly = |tool_name: &str| {
+            self.readonly_tools.contains(tool_name)
+                || annotated_readonly_tools
+                    .values()
+                    .any(|tools| tools.contains(tool_name))
+        };
 
         for request in tool_requests {
             if let Ok(tool_call) = &request.tool_call {
@@ -158,7 +178,7 @@ impl ToolInspector for PermissionInspector {
                         }
                     }
                     // 2. Check if it's a readonly or regular tool (both pre-approved)
-                    else if self.readonly_tools.contains(tool_name.as_ref())
+                    else if is_readonly(tool_name.as_ref())
                         || self.regular_tools.contains(tool_name.as_ref())
                     {
                         InspectionAction::Allow
@@ -179,7 +199,7 @@ impl ToolInspector for PermissionInspector {
                     InspectionAction::Allow => {
                         if *mode == "auto" {
                             "Auto mode - all tools approved".to_string()
-                        } else if self.readonly_tools.contains(tool_name.as_ref()) {
+                        } else if is_readonly(tool_name.as_ref()) {
                             "Tool marked as read-only".to_string()
                         } else if self.regular_tools.contains(tool_name.as_ref()) {
                             "Tool pre-approved".to_string()
@@ -10,6 +10,8 @@ use std::fs::read_to_string;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use super::timeouts::RequestTimeouts;
+
 pub struct ApiClient {
     client: Client,
     host: String,
@@ -152,6 +154,8 @@ impl Default for TlsConfig {
 }
 
 pub struct OAuthConfig {
+    /// Names the service in `<SERVICE>_OAUTH_CALLBACK_PORT`
+    pub service: String,
     pub host: String,
     pub client_id: String,
     pub redirect_url: String,
@@ -199,11 +203,13 @@ pub struct ApiRequestBuilder<'a> {
 
 impl ApiClient {
     pub fn new(host: String, auth: AuthMethod) -> Result<Self> {
-        Self::with_timeout(host, auth, Duration::from_secs(600))
+        Self::with_timeout(host, auth, RequestTimeouts::from_config().total)
     }
 
     pub fn with_timeout(host: String, auth: AuthMethod, timeout: Duration) -> Result<Self> {
-        let mut client_builder = Client::builder().timeout(timeout);
+        let mut client_builder = Client::builder()
+            .timeout(timeout)
+            .connect_timeout(RequestTimeouts::from_config().connect);
 
         // Configure TLS if needed
         let tls_config = TlsConfig::from_config()?;
@@ -226,6 +232,7 @@ impl ApiClient {
     fn rebuild_client(&mut self) -> Result<()> {
         let mut client_builder = Client::builder()
             .timeout(self.timeout)
+            .connect_timeout(RequestTimeouts::from_config().connect)
             .default_headers(self.default_headers.clone());
 
         // Configure TLS if needed
@@ -312,6 +319,7 @@ impl ApiClient {
 
     async fn get_oauth_token(&self, config: &OAuthConfig) -> Result<String> {
         super::oauth::get_oauth_token_async(
+            &config.service,
             &config.host,
             &config.client_id,
             &config.redirect_url,
@@ -251,9 +251,17 @@ impl ProviderUsage {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Copy)]
 pub struct Usage {
+    /// All prompt tokens, including any served from the provider's prompt cache
     pub input_tokens: Option<i32>,
+    /// All generated tokens, including any reasoning tokens
     pub output_tokens: Option<i32>,
     pub total_tokens: Option<i32>,
+    /// The part of input_tokens read from the prompt cache, when the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input_tokens: Option<i32>,
+    /// The part of output_tokens spent on reasoning, when the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<i32>,
 }
 
 fn sum_optionals<T>(a: Option<T>, b: Option<T>) -> Option<T>
@@ -276,6 +284,8 @@ impl Add for Usage {
             input_tokens: sum_optionals(self.input_tokens, other.input_tokens),
             output_tokens: sum_optionals(self.output_tokens, other.output_tokens),
             total_tokens: sum_optionals(self.total_tokens, other.total_tokens),
+            cached_input_tokens: sum_optionals(self.cached_input_tokens, other.cached_input_tokens),
+            reasoning_tokens: sum_optionals(self.reasoning_tokens, other.reasoning_tokens),
         }
     }
 }
@@ -296,8 +306,32 @@ impl Usage {
             input_tokens,
             output_tokens,
             total_tokens,
+            cached_input_tokens: None,
+            reasoning_tokens: None,
         }
     }
+
+    pub fn with_cached_input_tokens(mut self, cached_input_tokens: Option<i32>) -> Self {
+        self.cached_input_tokens = cached_input_tokens;
+        self
+    }
+
+    pub fn with_reasoning_tokens(mut self, reasoning_tokens: Option<i32>) -> Self {
+        self.reasoning_tokens = reasoning_tokens;
+        self
+    }
+
+    /// Prompt tokens that were not served from the cache
+    pub fn fresh_input_tokens(&self) -> Option<i32> {
+        self.input_tokens
+            .map(|input| input - self.cached_input_tokens.unwrap_or(0).min(input))
+    }
+
+    /// Generated tokens that are part of the visible answer rather than reasoning
+    pub fn completion_tokens(&self) -> Option<i32> {
+        self.output_tokens
+            .map(|output| output - self.reasoning_tokens.unwrap_or(0).min(output))
+    }
 }
 
 use async_trait::async_trait;
@@ -309,11 +343,25 @@ pub trait LeadWorkerProviderTrait {
 
     /// Get the currently active model name
     fn get_active_model(&self) -> String;
+
+    /// Get the name of whichever of the lead and worker providers answers with `model`
+    fn get_provider_name_for(&self, model: &str) -> String;
+}
+
+/// The name of a provider's type, as in its metadata, for code that only has a `dyn Provider`
+pub trait ProviderName {
+    fn provider_name(&self) -> String;
+}
+
+impl<P: Provider> ProviderName for P {
+    fn provider_name(&self) -> String {
+        P::metadata().name
+    }
 }
 
 /// Base trait for AI providers (OpenAI, Anthropic, etc)
 #[async_trait]
-pub trait Provider: Send + Sync {
+pub trait Provider: ProviderName + Send + Sync {
     /// Get the metadata for this provider type
     fn metadata() -> ProviderMetadata
     where
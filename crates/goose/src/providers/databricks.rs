@@ -92,7 +92,10 @@ impl AuthProvider for DatabricksAuthProvider {
                 client_id,
                 redirect_url,
                 scopes,
-            } => oauth::get_oauth_token_async(host, client_id, redirect_url, scopes).await?,
+            } => {
+                oauth::get_oauth_token_async("databricks", host, client_id, redirect_url, scopes)
+                    .await?
+            }
         };
         Ok(("Authorization".to_string(), format!("Bearer {}", token)))
     }
@@ -22,6 +22,9 @@ pub enum ProviderError {
     #[error("Request failed: {0}")]
     RequestFailed(String),
 
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+
     #[error("Execution error: {0}")]
     ExecutionError(String),
 
@@ -35,6 +38,9 @@ pub enum ProviderError {
 impl From<anyhow::Error> for ProviderError {
     fn from(error: anyhow::Error) -> Self {
         if let Some(reqwest_err) = error.downcast_ref::<reqwest::Error>() {
+            if reqwest_err.is_timeout() {
+                return ProviderError::Timeout(reqwest_err.to_string());
+            }
             return ProviderError::RequestFailed(reqwest_err.to_string());
         }
         ProviderError::ExecutionError(error.to_string())
@@ -43,6 +49,9 @@ impl From<anyhow::Error> for ProviderError {
 
 impl From<reqwest::Error> for ProviderError {
     fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return ProviderError::Timeout(error.to_string());
+        }
         ProviderError::RequestFailed(error.to_string())
     }
 }
@@ -88,6 +88,7 @@ pub fn refresh_custom_providers() -> Result<()> {
 
 pub fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     let config = crate::config::Config::global();
+    crate::offline::check_provider(name)?;
 
     if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
         tracing::info!("Creating lead/worker provider from environment variables");
@@ -118,6 +119,8 @@ fn create_lead_worker_from_env(
         .get_param::<usize>("GOOSE_LEAD_FALLBACK_TURNS")
         .unwrap_or(DEFAULT_FALLBACK_TURNS);
 
+    crate::offline::check_provider(&lead_provider_name)?;
+
     let lead_model_config = ModelConfig::new_with_context_env(
         lead_model_name.to_string(),
         Some("GOOSE_LEAD_CONTEXT_LIMIT"),
@@ -316,7 +316,8 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
             Some(total_input_i32),
             Some(output_tokens_i32),
             Some(total_tokens_i32),
-        ))
+        )
+        .with_cached_input_tokens(Some(cache_read_tokens.min(i32::MAX as u64) as i32)))
     } else if data.as_object().is_some() {
         // Check if the data itself is the usage object (for message_delta events that might have usage at top level)
         let input_tokens = data
@@ -359,7 +360,8 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
                 Some(total_input_i32),
                 Some(output_tokens_i32),
                 Some(total_tokens_i32),
-            ))
+            )
+            .with_cached_input_tokens(Some(cache_read_tokens.min(i32::MAX as u64) as i32)))
         } else {
             tracing::debug!("🔍 Anthropic no token data found in object");
             Ok(Usage::new(None, None, None))
@@ -464,21 +466,26 @@ pub fn response_to_streaming_message<S>(
 where
     S: futures::Stream<Item = anyhow::Result<String>> + Unpin + Send + 'static,
 {
+    use super::stream_delta::{sse_data, SseEvent, SseTextDelta, ToolCallAccumulator};
     use async_stream::try_stream;
     use futures::StreamExt;
-    use serde::{Deserialize, Serialize};
+    use serde::Deserialize;
+
+    // Deltas are most of the stream, so they are parsed into borrowed views; the few other
+    // events are small enough to read as JSON values
+    #[derive(Deserialize, Debug)]
+    struct DeltaEvent<'a> {
+        #[serde(borrow)]
+        delta: SseTextDelta<'a>,
+    }
 
-    #[derive(Serialize, Deserialize, Debug)]
     struct StreamingEvent {
-        #[serde(rename = "type")]
         event_type: String,
-        #[serde(flatten)]
         data: Value,
     }
 
     try_stream! {
-        let mut accumulated_text = String::new();
-        let mut accumulated_tool_calls: std::collections::HashMap<String, (String, String)> = std::collections::HashMap::new();
+        let mut accumulated_tool_calls: ToolCallAccumulator<String> = ToolCallAccumulator::default();
         let mut current_tool_id: Option<String> = None;
         let mut final_usage: Option<crate::providers::base::ProviderUsage> = None;
         let mut message_id: Option<String> = None;
@@ -487,20 +494,53 @@ where
             let line = line_result?;
 
             // Skip empty lines and non-data lines
-            if line.trim().is_empty() || !line.starts_with("data: ") {
+            let Some(data_part) = sse_data(&line).filter(|data| !data.is_empty()) else {
                 continue;
-            }
-
-            let data_part = line.strip_prefix("data: ").unwrap_or(&line);
+            };
 
             // Handle end of stream
-            if data_part.trim() == "[DONE]" {
+            if data_part == "[DONE]" {
                 break;
             }
 
             // Parse the JSON event
-            let event: StreamingEvent = match serde_json::from_str(data_part) {
-                Ok(event) => event,
+            let event_type = match serde_json::from_str::<SseEvent>(data_part) {
+                Ok(event) => event.event_type,
+                Err(e) => {
+                    tracing::debug!("Failed to parse streaming event: {} - Line: {}", e, data_part);
+                    continue;
+                }
+            };
+
+            if event_type == "content_block_delta" {
+                let Ok(DeltaEvent { delta }) = serde_json::from_str::<DeltaEvent>(data_part) else {
+                    continue;
+                };
+                match (delta.delta_type.as_ref(), delta.text, delta.partial_json) {
+                    // Text content delta
+                    ("text_delta", Some(text), _) => {
+                        // Yield partial text message with the same ID from message_start
+                        let mut message = Message::new(
+                            Role::Assistant,
+                            chrono::Utc::now().timestamp(),
+                            vec![MessageContent::text(text)],
+                        );
+                        message.id = message_id.clone();
+                        yield (Some(message), None);
+                    }
+                    // Tool input delta
+                    ("input_json_delta", _, Some(partial_json)) => {
+                        if let Some(tool_id) = &current_tool_id {
+                            accumulated_tool_calls.append(tool_id, &partial_json);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            let event = match serde_json::from_str::<Value>(data_part) {
+                Ok(data) => StreamingEvent { event_type: event_type.into_owned(), data },
                 Err(e) => {
                     tracing::debug!("Failed to parse streaming event: {} - Line: {}", e, data_part);
                     continue;
@@ -538,36 +578,7 @@ where
                             if let Some(id) = content_block.get("id").and_then(|v| v.as_str()) {
                                 current_tool_id = Some(id.to_string());
                                 if let Some(name) = content_block.get("name").and_then(|v| v.as_str()) {
-                                    accumulated_tool_calls.insert(id.to_string(), (name.to_string(), String::new()));
-                                }
-                            }
-                        }
-                    }
-                    continue;
-                }
-                "content_block_delta" => {
-                    if let Some(delta) = event.data.get("delta") {
-                        if delta.get("type") == Some(&json!("text_delta")) {
-                            // Text content delta
-                            if let Some(text) = delta.get("text").and_then(|v| v.as_str()) {
-                                accumulated_text.push_str(text);
-
-                                // Yield partial text message with the same ID from message_start
-                                let mut message = Message::new(
-                                    Role::Assistant,
-                                    chrono::Utc::now().timestamp(),
-                                    vec![MessageContent::text(text)],
-                                );
-                                message.id = message_id.clone();
-                                yield (Some(message), None);
-                            }
-                        } else if delta.get("type") == Some(&json!("input_json_delta")) {
-                            // Tool input delta
-                            if let Some(tool_id) = &current_tool_id {
-                                if let Some(partial_json) = delta.get("partial_json").and_then(|v| v.as_str()) {
-                                    if let Some((_name, args)) = accumulated_tool_calls.get_mut(tool_id) {
-                                        args.push_str(partial_json);
-                                    }
+                                    accumulated_tool_calls.start(id.to_string(), id, name, "");
                                 }
                             }
                         }
@@ -578,32 +589,28 @@ where
                     // Content block finished
                     if let Some(tool_id) = current_tool_id.take() {
                         // Tool call finished, yield complete tool call
-                        if let Some((name, args)) = accumulated_tool_calls.remove(&tool_id) {
-                            let parsed_args = if args.is_empty() {
-                                json!({})
-                            } else {
-                                match serde_json::from_str::<Value>(&args) {
-                                    Ok(parsed) => parsed,
-                                    Err(_) => {
-                                        // If parsing fails, create an error tool request
-                                        let error = ErrorData::new(
-                                            ErrorCode::INVALID_PARAMS,
-                                            format!("Could not parse tool arguments: {}", args),
-                                            None,
-                                        );
-                                        let mut message = Message::new(
-                                            Role::Assistant,
-                                            chrono::Utc::now().timestamp(),
-                                            vec![MessageContent::tool_request(tool_id, Err(error))],
-                                        );
-                                        message.id = message_id.clone();
-                                        yield (Some(message), None);
-                                        continue;
-                                    }
+                        if let Some(call) = accumulated_tool_calls.finish(&tool_id) {
+                            let parsed_args = match call.parse_arguments() {
+                                Ok(parsed) => parsed,
+                                Err(_) => {
+                                    // If parsing fails, create an error tool request
+                                    let error = ErrorData::new(
+                                        ErrorCode::INVALID_PARAMS,
+                                        format!("Could not parse tool arguments: {}", call.arguments),
+                                        None,
+                                    );
+                                    let mut message = Message::new(
+                                        Role::Assistant,
+                                        chrono::Utc::now().timestamp(),
+                                        vec![MessageContent::tool_request(tool_id, Err(error))],
+                                    );
+                                    message.id = message_id.clone();
+                                    yield (Some(message), None);
+                                    continue;
                                 }
                             };
 
-                            let tool_call = CallToolRequestParam{ name: name.into(), arguments: Some(object(parsed_args)) };
+                            let tool_call = CallToolRequestParam{ name: call.name.into(), arguments: Some(object(parsed_args)) };
 
                             let mut message = Message::new(
                                 rmcp::model::Role::Assistant,
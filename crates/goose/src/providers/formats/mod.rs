@@ -5,3 +5,4 @@ pub mod gcpvertexai;
 pub mod google;
 pub mod openai;
 pub mod snowflake;
+pub mod stream_delta;
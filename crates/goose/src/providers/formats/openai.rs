@@ -1,6 +1,7 @@
 use crate::conversation::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::{ProviderUsage, Usage};
+use crate::providers::formats::stream_delta::{borrow_optional_str, sse_data, ToolCallAccumulator};
 use crate::providers::utils::{
     convert_image, detect_image_path, is_valid_function_name, load_image_file, safely_parse_json,
     sanitize_function_name, ImageFormat,
@@ -12,43 +13,56 @@ use rmcp::model::{
     object, AnnotateAble, CallToolRequestParam, Content, ErrorCode, ErrorData, RawContent,
     ResourceContents, Role, Tool,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::borrow::Cow;
 use std::ops::Deref;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DeltaToolCallFunction {
-    name: Option<String>,
-    arguments: String, // chunk of encoded JSON,
+// Streaming chunks borrow from the line they were parsed from, so the many small deltas of a
+// long response don't each allocate their strings
+#[derive(Deserialize, Debug)]
+struct DeltaToolCallFunction<'a> {
+    #[serde(default, borrow, deserialize_with = "borrow_optional_str")]
+    name: Option<Cow<'a, str>>,
+    #[serde(default, borrow)]
+    arguments: Cow<'a, str>, // chunk of encoded JSON,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DeltaToolCall {
-    id: Option<String>,
-    function: DeltaToolCallFunction,
+#[derive(Deserialize, Debug)]
+struct DeltaToolCall<'a> {
+    #[serde(default, borrow, deserialize_with = "borrow_optional_str")]
+    id: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    function: DeltaToolCallFunction<'a>,
     index: Option<i32>,
-    r#type: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Delta {
-    content: Option<String>,
-    role: Option<String>,
-    tool_calls: Option<Vec<DeltaToolCall>>,
+#[derive(Deserialize, Debug)]
+struct Delta<'a> {
+    #[serde(default, borrow, deserialize_with = "borrow_optional_str")]
+    content: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    tool_calls: Option<Vec<DeltaToolCall<'a>>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct StreamingChoice {
-    delta: Delta,
-    index: Option<i32>,
-    finish_reason: Option<String>,
+#[derive(Deserialize, Debug)]
+struct StreamingChoice<'a> {
+    #[serde(borrow)]
+    delta: Delta<'a>,
+    #[serde(default, borrow, deserialize_with = "borrow_optional_str")]
+    finish_reason: Option<Cow<'a, str>>,
+}
+
+impl StreamingChoice<'_> {
+    fn finishes_tool_calls(&self) -> bool {
+        self.finish_reason.as_deref() == Some("tool_calls")
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct StreamingChunk {
-    choices: Vec<StreamingChoice>,
-    created: Option<i64>,
+#[derive(Deserialize, Debug)]
+struct StreamingChunk<'a> {
+    #[serde(borrow)]
+    choices: Vec<StreamingChoice<'a>>,
     id: Option<String>,
     usage: Option<Value>,
     model: Option<String>,
@@ -380,7 +394,21 @@ pub fn get_usage(usage: &Value) -> Usage {
             _ => None,
         });
 
+    let cached_input_tokens = usage
+        .get("prompt_tokens_details")
+        .and_then(|d| d.get("cached_tokens"))
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+
+    let reasoning_tokens = usage
+        .get("completion_tokens_details")
+        .and_then(|d| d.get("reasoning_tokens"))
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+
     Usage::new(input_tokens, output_tokens, total_tokens)
+        .with_cached_input_tokens(cached_input_tokens)
+        .with_reasoning_tokens(reasoning_tokens)
 }
 
 /// Validates and fixes tool schemas to ensure they have proper parameter structure.
@@ -429,10 +457,6 @@ fn ensure_valid_json_schema(schema: &mut Value) {
     }
 }
 
-fn strip_data_prefix(line: &str) -> Option<&str> {
-    line.strip_prefix("data: ").map(|s| s.trim())
-}
-
 pub fn response_to_streaming_message<S>(
     mut stream: S,
 ) -> impl Stream<Item = anyhow::Result<(Option<Message>, Option<ProviderUsage>)>> + 'static
@@ -447,7 +471,7 @@ where
                 break 'outer;
             }
             let response_str = response?;
-            let line = strip_data_prefix(&response_str);
+            let line = sse_data(&response_str);
 
             if line.is_none() || line.is_some_and(|l| l.is_empty()) {
                 continue
@@ -469,86 +493,74 @@ where
             if chunk.choices.is_empty() {
                 yield (None, usage)
             } else if let Some(tool_calls) = &chunk.choices[0].delta.tool_calls {
-                let mut tool_call_data: std::collections::HashMap<i32, (String, String, String)> = std::collections::HashMap::new();
+                let mut tool_call_data: ToolCallAccumulator<i32> = ToolCallAccumulator::default();
 
                 for tool_call in tool_calls {
                     if let (Some(index), Some(id), Some(name)) = (tool_call.index, &tool_call.id, &tool_call.function.name) {
-                        tool_call_data.insert(index, (id.clone(), name.clone(), tool_call.function.arguments.clone()));
+                        tool_call_data.start(index, id, name, &tool_call.function.arguments);
                     }
                 }
 
                 // Check if this chunk already has finish_reason "tool_calls"
-                let is_complete = chunk.choices[0].finish_reason == Some("tool_calls".to_string());
-
-                if !is_complete {
-                    let mut done = false;
-                    while !done {
-                        if let Some(response_chunk) = stream.next().await {
-                            if response_chunk.as_ref().is_ok_and(|s| s == "data: [DONE]") {
-                                break 'outer;
-                            }
-                            let response_str = response_chunk?;
-                            if let Some(line) = strip_data_prefix(&response_str) {
-                                let tool_chunk: StreamingChunk = serde_json::from_str(line)
-                                    .map_err(|e| anyhow!("Failed to parse streaming chunk: {}: {:?}", e, &line))?;
-
-                                if let Some(delta_tool_calls) = &tool_chunk.choices[0].delta.tool_calls {
-                                    for delta_call in delta_tool_calls {
-                                        if let Some(index) = delta_call.index {
-                                            if let Some((_, _, ref mut args)) = tool_call_data.get_mut(&index) {
-                                                args.push_str(&delta_call.function.arguments);
-                                            } else if let (Some(id), Some(name)) = (&delta_call.id, &delta_call.function.name) {
-                                                tool_call_data.insert(index, (id.clone(), name.clone(), delta_call.function.arguments.clone()));
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    done = true;
-                                }
+                let mut done = chunk.choices[0].finishes_tool_calls();
+                while !done {
+                    let Some(response_chunk) = stream.next().await else {
+                        break;
+                    };
+                    if response_chunk.as_ref().is_ok_and(|s| s == "data: [DONE]") {
+                        break 'outer;
+                    }
+                    let response_str = response_chunk?;
+                    let Some(line) = sse_data(&response_str) else {
+                        continue;
+                    };
+                    let tool_chunk: StreamingChunk = serde_json::from_str(line)
+                        .map_err(|e| anyhow!("Failed to parse streaming chunk: {}: {:?}", e, &line))?;
+                    let Some(choice) = tool_chunk.choices.first() else {
+                        continue;
+                    };
 
-                                if tool_chunk.choices[0].finish_reason == Some("tool_calls".to_string()) {
-                                    done = true;
+                    if let Some(delta_tool_calls) = &choice.delta.tool_calls {
+                        for delta_call in delta_tool_calls {
+                            if let Some(index) = delta_call.index {
+                                if !tool_call_data.append(&index, &delta_call.function.arguments) {
+                                    if let (Some(id), Some(name)) = (&delta_call.id, &delta_call.function.name) {
+                                        tool_call_data.start(index, id, name, &delta_call.function.arguments);
+                                    }
                                 }
                             }
-                        } else {
-                            break;
                         }
+                    } else {
+                        done = true;
+                    }
+
+                    if choice.finishes_tool_calls() {
+                        done = true;
                     }
                 }
 
                 let mut contents = Vec::new();
-                let mut sorted_indices: Vec<_> = tool_call_data.keys().cloned().collect();
-                sorted_indices.sort();
-
-                for index in sorted_indices {
-                    if let Some((id, function_name, arguments)) = tool_call_data.get(&index) {
-                        let parsed = if arguments.is_empty() {
-                            Ok(json!({}))
-                        } else {
-                            serde_json::from_str::<Value>(arguments)
-                        };
-
-                        let content = match parsed {
-                            Ok(params) => {
-                                MessageContent::tool_request(
-                                    id.clone(),
-                                    Ok(CallToolRequestParam { name: function_name.clone().into(), arguments: Some(object(params)) }),
-                                )
-                            },
-                            Err(e) => {
-                                let error = ErrorData {
-                                    code: ErrorCode::INVALID_PARAMS,
-                                    message: Cow::from(format!(
-                                        "Could not interpret tool use parameters for id {}: {}",
-                                        id, e
-                                    )),
-                                    data: None,
-                                };
-                                MessageContent::tool_request(id.clone(), Err(error))
-                            }
-                        };
-                        contents.push(content);
-                    }
+                for call in tool_call_data.finish_all() {
+                    let content = match call.parse_arguments() {
+                        Ok(params) => {
+                            MessageContent::tool_request(
+                                call.id,
+                                Ok(CallToolRequestParam { name: call.name.into(), arguments: Some(object(params)) }),
+                            )
+                        },
+                        Err(e) => {
+                            let error = ErrorData {
+                                code: ErrorCode::INVALID_PARAMS,
+                                message: Cow::from(format!(
+                                    "Could not interpret tool use parameters for id {}: {}",
+                                    call.id, e
+                                )),
+                                data: None,
+                            };
+                            MessageContent::tool_request(call.id, Err(error))
+                        }
+                    };
+                    contents.push(content);
                 }
 
                 let mut msg = Message::new(
@@ -570,7 +582,7 @@ where
                 let mut msg = Message::new(
                     Role::Assistant,
                     chrono::Utc::now().timestamp(),
-                    vec![MessageContent::text(text)],
+                    vec![MessageContent::text(text.as_ref())],
                 );
 
                 // Add ID if present
@@ -699,6 +711,26 @@ mod tests {
     use tokio::pin;
     use tokio_stream::{self, StreamExt};
 
+    #[test]
+    fn test_get_usage_cached_and_reasoning() {
+        let usage = get_usage(&json!({
+            "prompt_tokens": 1200,
+            "completion_tokens": 300,
+            "total_tokens": 1500,
+            "prompt_tokens_details": {"cached_tokens": 1000},
+            "completion_tokens_details": {"reasoning_tokens": 200}
+        }));
+        assert_eq!(usage.cached_input_tokens, Some(1000));
+        assert_eq!(usage.reasoning_tokens, Some(200));
+        assert_eq!(usage.fresh_input_tokens(), Some(200));
+        assert_eq!(usage.completion_tokens(), Some(100));
+
+        let plain = get_usage(&json!({"prompt_tokens": 10, "completion_tokens": 5}));
+        assert_eq!(plain.cached_input_tokens, None);
+        assert_eq!(plain.fresh_input_tokens(), Some(10));
+        assert_eq!(plain.total_tokens, Some(15));
+    }
+
     #[test]
     fn test_validate_tool_schemas() {
         // Test case 1: Empty parameters object
@@ -0,0 +1,185 @@
+//! Shared pieces for parsing streamed provider responses without redoing work per chunk.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// The payload of an SSE `data:` line, if the line is one
+pub fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim)
+}
+
+/// Deserialize an optional string borrowing from the input when it has no escapes; serde only
+/// borrows a `Cow` that isn't wrapped in anything, so this unwraps the option itself
+pub fn borrow_optional_str<'de: 'a, 'a, D>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Ok(Option::<Borrowed>::deserialize(deserializer)?.map(|borrowed| borrowed.0))
+}
+
+/// Just the `type` of an event, so the rest is only parsed by the handler for that type
+#[derive(Debug, Deserialize)]
+pub struct SseEvent<'a> {
+    #[serde(rename = "type", borrow)]
+    pub event_type: Cow<'a, str>,
+}
+
+/// A delta carrying part of a text, thinking or tool input block
+#[derive(Debug, Deserialize)]
+pub struct SseTextDelta<'a> {
+    #[serde(rename = "type", borrow)]
+    pub delta_type: Cow<'a, str>,
+    #[serde(default, borrow, deserialize_with = "borrow_optional_str")]
+    pub text: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrow_optional_str")]
+    pub partial_json: Option<Cow<'a, str>>,
+}
+
+/// A tool call whose arguments are still arriving
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingToolCall {
+    pub id: String,
+    pub name: String,
+    /// The encoded JSON arguments received so far
+    pub arguments: String,
+}
+
+impl PendingToolCall {
+    /// The arguments as JSON; a call without any is called with an empty object
+    pub fn parse_arguments(&self) -> Result<Value, serde_json::Error> {
+        if self.arguments.trim().is_empty() {
+            Ok(Value::Object(Default::default()))
+        } else {
+            serde_json::from_str(&self.arguments)
+        }
+    }
+}
+
+/// Tool calls being streamed, by the key the provider identifies them with (an index or a
+/// content block id), kept in key order
+#[derive(Debug)]
+pub struct ToolCallAccumulator<K: Ord> {
+    calls: BTreeMap<K, PendingToolCall>,
+}
+
+impl<K: Ord> Default for ToolCallAccumulator<K> {
+    fn default() -> Self {
+        Self {
+            calls: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Ord> ToolCallAccumulator<K> {
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Start the call `key`, with the first fragment of its arguments if the start carries one
+    pub fn start(&mut self, key: K, id: &str, name: &str, arguments: &str) {
+        self.calls.insert(
+            key,
+            PendingToolCall {
+                id: id.to_string(),
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        );
+    }
+
+    /// Append a fragment to the arguments of `key`; false if no such call was started
+    pub fn append(&mut self, key: &K, fragment: &str) -> bool {
+        match self.calls.get_mut(key) {
+            Some(call) => {
+                call.arguments.push_str(fragment);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the finished call `key`
+    pub fn finish(&mut self, key: &K) -> Option<PendingToolCall> {
+        self.calls.remove(key)
+    }
+
+    /// Take every call, in key order
+    pub fn finish_all(&mut self) -> Vec<PendingToolCall> {
+        std::mem::take(&mut self.calls).into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_call_accumulator() {
+        let mut calls = ToolCallAccumulator::default();
+        calls.start(1, "call_b", "developer__shell", "");
+        calls.start(0, "call_a", "developer__text_editor", "{\"path\":");
+        assert!(calls.append(&0, " \"/tmp/a\"}"));
+        assert!(calls.append(&1, "{\"command\": \"ls\"}"));
+        assert!(!calls.append(&2, "{}"));
+
+        let finished = calls.finish_all();
+        assert!(calls.is_empty());
+        assert_eq!(
+            finished.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+            vec!["call_a", "call_b"]
+        );
+        assert_eq!(
+            finished[0].parse_arguments().unwrap(),
+            json!({"path": "/tmp/a"})
+        );
+        assert_eq!(
+            finished[1].parse_arguments().unwrap(),
+            json!({"command": "ls"})
+        );
+
+        calls.start(0, "call_c", "empty", "");
+        assert_eq!(
+            calls.finish(&0).unwrap().parse_arguments().unwrap(),
+            json!({})
+        );
+        calls.start(0, "call_d", "broken", "{\"a\":");
+        assert!(calls.finish(&0).unwrap().parse_arguments().is_err());
+    }
+
+    #[test]
+    fn test_borrowed_events() {
+        let line = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"fn main() {}"}}"#;
+        let data = sse_data(line).unwrap();
+        let event: SseEvent = serde_json::from_str(data).unwrap();
+        assert_eq!(event.event_type, "content_block_delta");
+        assert!(matches!(event.event_type, Cow::Borrowed(_)));
+
+        #[derive(Deserialize)]
+        struct Wrapper<'a> {
+            #[serde(borrow)]
+            delta: SseTextDelta<'a>,
+        }
+        let wrapper: Wrapper = serde_json::from_str(data).unwrap();
+        assert_eq!(wrapper.delta.delta_type, "text_delta");
+        assert_eq!(wrapper.delta.text.as_deref(), Some("fn main() {}"));
+        assert!(matches!(wrapper.delta.text, Some(Cow::Borrowed(_))));
+        assert!(wrapper.delta.partial_json.is_none());
+
+        // Escaped text can't be borrowed but still parses
+        let escaped: SseTextDelta =
+            serde_json::from_str(r#"{"type":"text_delta","text":"line\nnext"}"#).unwrap();
+        assert_eq!(escaped.text.as_deref(), Some("line\nnext"));
+        assert!(matches!(escaped.text, Some(Cow::Owned(_))));
+
+        assert_eq!(sse_data("event: ping"), None);
+    }
+}
@@ -4,7 +4,9 @@ use std::ops::Deref;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderName, ProviderUsage,
+};
 use super::errors::ProviderError;
 use crate::conversation::message::{Message, MessageContent};
 use crate::model::ModelConfig;
@@ -303,6 +305,15 @@ impl LeadWorkerProviderTrait for LeadWorkerProvider {
             self.lead_provider.get_model_config().model_name
         })
     }
+
+    /// Get the name of whichever of the lead and worker providers answers with `model`
+    fn get_provider_name_for(&self, model: &str) -> String {
+        if self.worker_provider.get_model_config().model_name == model {
+            self.worker_provider.provider_name()
+        } else {
+            self.lead_provider.provider_name()
+        }
+    }
 }
 
 #[async_trait]
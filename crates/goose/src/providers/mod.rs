@@ -30,6 +30,7 @@ pub mod sagemaker_tgi;
 pub mod snowflake;
 pub mod testprovider;
 pub mod tetrate;
+pub mod timeouts;
 pub mod toolshim;
 pub mod usage_estimator;
 pub mod utils;
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use axum::{extract::Query, response::Html, routing::get, Router};
+use axum::{extract::Query, routing::get, Router};
 use base64::Engine;
 use chrono::{DateTime, Utc};
 use etcetera::{choose_app_strategy, AppStrategy};
@@ -7,16 +7,167 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::Digest;
-use std::{collections::HashMap, fs, net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
 use tokio::sync::{oneshot, Mutex as TokioMutex};
 use url::Url;
 
+use crate::oauth::callback_page::CallbackPage;
+use crate::oauth::http::OAuthHttpSettings;
+use crate::oauth::loopback::CallbackAddress;
+use crate::oauth::manual::MANUAL_FLOW;
+use crate::oauth::token_store::TokenKey;
+use crate::oauth::wait;
+
 static OAUTH_MUTEX: Lazy<TokioMutex<()>> = Lazy::new(|| TokioMutex::new(()));
 
+/// How to sign in: `browser` (loopback redirect), `device` (RFC 8628 device code), `manual`
+/// (the code or redirect URL is pasted into the terminal, see [`crate::oauth::manual`]) or
+/// `auto`, which uses the device flow when there is no display to open a browser on
+pub const OAUTH_FLOW_KEY: &str = "GOOSE_OAUTH_FLOW";
+
+/// The port the redirect listener binds: a port, a `first-last` range tried in order, or `auto`
+/// for any free port. `<SERVICE>_OAUTH_CALLBACK_PORT` (e.g. `DATABRICKS_OAUTH_CALLBACK_PORT`)
+/// overrides it for one service. Anything but the redirect URL's own port only works if the
+/// server accepts the rewritten redirect URI.
+pub const OAUTH_CALLBACK_PORT_KEY: &str = "GOOSE_OAUTH_CALLBACK_PORT";
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+/// RFC 8628 section 3.2: wait five seconds between polls unless the server says otherwise
+const DEFAULT_DEVICE_POLL_INTERVAL_SECS: u64 = 5;
+
 #[derive(Debug, Clone)]
 struct OidcEndpoints {
     authorization_endpoint: String,
     token_endpoint: String,
+    device_authorization_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlowKind {
+    Browser,
+    Device,
+    Manual,
+}
+
+/// Whether a browser can be opened here: not over SSH, and on Linux only with a display
+fn has_browser(env: impl Fn(&str) -> Option<String>) -> bool {
+    let set = |name: &str| env(name).is_some_and(|value| !value.is_empty());
+    if set("SSH_CONNECTION") || set("SSH_TTY") {
+        return false;
+    }
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return true;
+    }
+    set("DISPLAY") || set("WAYLAND_DISPLAY")
+}
+
+/// The ports to try for the redirect listener, in order; 0 lets the OS pick one. Without a
+/// configured port only the redirect URL's port is used, unless its host is a loopback address
+/// literal: RFC 8252 (section 7.3) has servers accept any port for those, so a busy port falls
+/// back to a free one.
+fn callback_ports(configured: Option<&str>, redirect_url: &Url) -> Result<Vec<u16>> {
+    let parse = |port: &str| {
+        port.trim()
+            .parse::<u16>()
+            .map_err(|_| anyhow::anyhow!("Invalid OAuth callback port '{}'", port.trim()))
+    };
+    match configured.map(str::trim).filter(|spec| !spec.is_empty()) {
+        Some(spec) if spec.eq_ignore_ascii_case("auto") => Ok(vec![0]),
+        Some(spec) => match spec.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    anyhow::bail!("Invalid OAuth callback port range '{}'", spec);
+                }
+                Ok((first..=last).collect())
+            }
+            None => Ok(vec![parse(spec)?]),
+        },
+        None => {
+            let port = redirect_url.port_or_known_default().unwrap_or(80);
+            let loopback_literal = match redirect_url.host() {
+                Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+                Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+                _ => false,
+            };
+            Ok(if loopback_literal {
+                vec![port, 0]
+            } else {
+                vec![port]
+            })
+        }
+    }
+}
+
+/// The configured callback port for `service`, falling back to the one for all services
+fn configured_callback_port(service: &str) -> Option<String> {
+    let config = crate::config::Config::global();
+    config
+        .get_param::<String>(&format!(
+            "{}_OAUTH_CALLBACK_PORT",
+            service.to_uppercase().replace('-', "_")
+        ))
+        .or_else(|_| config.get_param::<String>(OAUTH_CALLBACK_PORT_KEY))
+        .ok()
+}
+
+/// Bind the redirect listener on the first free port of `ports`, and return it with the
+/// redirect URL rewritten to the port it got
+async fn bind_callback(
+    redirect_url: &str,
+    ports: &[u16],
+) -> Result<(tokio::net::TcpListener, String)> {
+    let (listener, rewritten) = CallbackAddress::parse(Some(redirect_url), None)?
+        .bind_first_free(ports)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "{:#}; set {} to a free port, a range such as 8020-8030, or auto",
+                e,
+                OAUTH_CALLBACK_PORT_KEY
+            )
+        })?;
+    let bound = listener.local_addr()?.port();
+    if Some(bound) == Url::parse(redirect_url)?.port_or_known_default() {
+        return Ok((listener, redirect_url.to_string()));
+    }
+    // Keep the URI as registered: `http://localhost:8020` has no trailing slash
+    let rewritten = match rewritten.strip_suffix('/') {
+        Some(trimmed) if !redirect_url.ends_with('/') && Url::parse(&rewritten)?.path() == "/" => {
+            trimmed.to_string()
+        }
+        _ => rewritten,
+    };
+    Ok((listener, rewritten))
+}
+
+fn choose_flow(
+    configured: Option<&str>,
+    endpoints: &OidcEndpoints,
+    env: impl Fn(&str) -> Option<String>,
+) -> FlowKind {
+    match configured.map(|flow| flow.trim().to_lowercase()).as_deref() {
+        Some("device") => FlowKind::Device,
+        Some("browser") => FlowKind::Browser,
+        Some(MANUAL_FLOW) => FlowKind::Manual,
+        _ if endpoints.device_authorization_endpoint.is_some() && !has_browser(&env) => {
+            FlowKind::Device
+        }
+        _ => FlowKind::Browser,
+    }
+}
+
+/// What the device authorization endpoint hands out (RFC 8628 section 3.2)
+#[derive(Debug, Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default)]
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -47,11 +198,17 @@ fn get_base_path() -> PathBuf {
 }
 
 impl TokenCache {
-    fn new(host: &str, client_id: &str, scopes: &[String]) -> Self {
+    /// `profile` is the named profile signing in, `None` for the default one, whose tokens keep
+    /// the path they had before profiles existed
+    fn new(host: &str, client_id: &str, scopes: &[String], profile: Option<&str>) -> Self {
         let mut hasher = sha2::Sha256::new();
         hasher.update(host.as_bytes());
         hasher.update(client_id.as_bytes());
         hasher.update(scopes.join(",").as_bytes());
+        if let Some(profile) = profile {
+            hasher.update(b"|");
+            hasher.update(profile.as_bytes());
+        }
         let hash = format!("{:x}", hasher.finalize());
 
         fs::create_dir_all(get_base_path()).unwrap();
@@ -92,13 +249,12 @@ impl TokenCache {
     }
 }
 
-async fn get_workspace_endpoints(host: &str) -> Result<OidcEndpoints> {
+async fn get_workspace_endpoints(host: &str, client: &reqwest::Client) -> Result<OidcEndpoints> {
     let base_url = Url::parse(host).expect("Invalid host URL");
     let oidc_url = base_url
         .join("oidc/.well-known/oauth-authorization-server")
         .expect("Invalid OIDC URL");
 
-    let client = reqwest::Client::new();
     let resp = client.get(oidc_url.clone()).send().await?;
 
     if !resp.status().is_success() {
@@ -122,9 +278,15 @@ async fn get_workspace_endpoints(host: &str) -> Result<OidcEndpoints> {
         .ok_or_else(|| anyhow::anyhow!("token_endpoint not found in OIDC configuration"))?
         .to_string();
 
+    let device_authorization_endpoint = oidc_config
+        .get("device_authorization_endpoint")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
     Ok(OidcEndpoints {
         authorization_endpoint,
         token_endpoint,
+        device_authorization_endpoint,
     })
 }
 
@@ -135,6 +297,9 @@ struct OAuthFlow {
     scopes: Vec<String>,
     state: String,
     verifier: String,
+    http: reqwest::Client,
+    /// Whether to ask the user which account to sign in with
+    select_account: bool,
 }
 
 impl OAuthFlow {
@@ -143,17 +308,27 @@ impl OAuthFlow {
         client_id: String,
         redirect_url: String,
         scopes: Vec<String>,
+        http: reqwest::Client,
     ) -> Self {
         Self {
             endpoints,
             client_id,
             redirect_url,
             scopes,
+            http,
             state: nanoid::nanoid!(16),
             verifier: nanoid::nanoid!(64),
+            select_account: false,
         }
     }
 
+    /// Have the sign-in page offer a choice of accounts, so a browser signed in to one doesn't
+    /// sign a profile in as it
+    fn with_account_choice(mut self, select_account: bool) -> Self {
+        self.select_account = select_account;
+        self
+    }
+
     /// Extracts token data from an OAuth 2.0 token response.
     ///
     /// This helper method consolidates the common logic for processing token responses
@@ -216,15 +391,19 @@ impl OAuthFlow {
             base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest)
         };
 
-        let params = [
+        let scope = self.scopes.join(" ");
+        let mut params = vec![
             ("response_type", "code"),
             ("client_id", &self.client_id),
             ("redirect_uri", &self.redirect_url),
-            ("scope", &self.scopes.join(" ")),
+            ("scope", &scope),
             ("state", &self.state),
             ("code_challenge", &challenge),
             ("code_challenge_method", "S256"),
         ];
+        if self.select_account {
+            params.push(("prompt", "select_account"));
+        }
 
         format!(
             "{}?{}",
@@ -242,8 +421,8 @@ impl OAuthFlow {
             ("client_id", &self.client_id),
         ];
 
-        let client = reqwest::Client::new();
-        let resp = client
+        let resp = self
+            .http
             .post(&self.endpoints.token_endpoint)
             .header("Content-Type", "application/x-www-form-urlencoded")
             .form(&params)
@@ -271,8 +450,8 @@ impl OAuthFlow {
 
         tracing::debug!("Refreshing token using refresh_token");
 
-        let client = reqwest::Client::new();
-        let resp = client
+        let resp = self
+            .http
             .post(&self.endpoints.token_endpoint)
             .header("Content-Type", "application/x-www-form-urlencoded")
             .form(&params)
@@ -288,20 +467,122 @@ impl OAuthFlow {
         self.extract_token_data(&token_response, Some(refresh_token))
     }
 
-    async fn execute(&self) -> Result<TokenData> {
+    /// Sign in with the device authorization grant (RFC 8628): show a code to enter on another
+    /// device, then poll the token endpoint until the user has approved it
+    async fn execute_device(&self) -> Result<TokenData> {
+        let device_endpoint = self
+            .endpoints
+            .device_authorization_endpoint
+            .as_deref()
+            .ok_or_else(|| {
+                anyhow::anyhow!("The server does not support the device authorization flow")
+            })?;
+
+        let client = &self.http;
+        let scope = self.scopes.join(" ");
+        let resp = client
+            .post(device_endpoint)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .form(&[("client_id", self.client_id.as_str()), ("scope", &scope)])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let err_text = resp.text().await?;
+            return Err(anyhow::anyhow!(
+                "Failed to start device authorization: {}",
+                err_text
+            ));
+        }
+        let device: DeviceAuthorization = resp.json().await?;
+
+        println!(
+            "To sign in, open {} on any device and enter the code {}",
+            device.verification_uri, device.user_code
+        );
+        if let Some(complete) = &device.verification_uri_complete {
+            println!("Or open this URL, which includes the code:\n{}", complete);
+        }
+
+        let mut interval = std::time::Duration::from_secs(
+            device.interval.unwrap_or(DEFAULT_DEVICE_POLL_INTERVAL_SECS),
+        );
+        let deadline =
+            tokio::time::Instant::now() + std::time::Duration::from_secs(device.expires_in);
+        loop {
+            tokio::time::sleep(interval).await;
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "The device code expired before sign-in finished"
+                ));
+            }
+
+            let resp = client
+                .post(&self.endpoints.token_endpoint)
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .form(&[
+                    ("grant_type", DEVICE_CODE_GRANT),
+                    ("device_code", device.device_code.as_str()),
+                    ("client_id", self.client_id.as_str()),
+                ])
+                .send()
+                .await?;
+            if resp.status().is_success() {
+                let token_response: Value = resp.json().await?;
+                return self.extract_token_data(&token_response, None);
+            }
+
+            let err_text = resp.text().await?;
+            let error = serde_json::from_str::<Value>(&err_text)
+                .ok()
+                .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string));
+            match error.as_deref() {
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += std::time::Duration::from_secs(5),
+                Some("access_denied") => {
+                    return Err(anyhow::anyhow!("Sign-in was denied"));
+                }
+                Some("expired_token") => {
+                    return Err(anyhow::anyhow!(
+                        "The device code expired before sign-in finished"
+                    ));
+                }
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Failed to get token with device code: {}",
+                        err_text
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Sign in with the code the user pastes, without listening for the redirect
+    async fn execute_manual(&self, service: &str) -> Result<TokenData> {
+        let authorization_url = self.get_authorization_url();
+        let code = crate::oauth::manual::read_code(service, &authorization_url).await?;
+        self.exchange_code_for_token(&code).await
+    }
+
+    /// Sign in through the browser, listening for the redirect on the first free port of `ports`
+    /// for as long as [`wait::timeout`] allows
+    async fn execute(&mut self, service: &str, ports: &[u16]) -> Result<TokenData> {
         // Create a channel that will send the auth code from the app process
-        let (tx, rx) = oneshot::channel();
+        let (tx, mut rx) = oneshot::channel();
         let state = self.state.clone();
         // Axum can theoretically spawn multiple threads, so we need this to be in an Arc even
         // though it will ultimately only get used once
         let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
 
         // Setup a server that will receive the redirect, capture the code, and display success/failure
+        let page = CallbackPage::from_config(service);
+        let name = service.to_string();
         let app = Router::new().route(
             "/",
             get(move |Query(params): Query<HashMap<String, String>>| {
                 let tx = Arc::clone(&tx);
                 let state = state.clone();
+                let page = page.clone();
+                let name = name.clone();
                 async move {
                     let code = params.get("code").cloned();
                     let received_state = params.get("state").cloned();
@@ -310,61 +591,68 @@ impl OAuthFlow {
                         if received_state == state {
                             if let Some(sender) = tx.lock().await.take() {
                                 if sender.send(code).is_ok() {
-                                    // Use the improved HTML response
-                                    return Html(
-                                        "<h2>Login Success</h2><p>You can close this window</p>",
-                                    );
+                                    return page.success(&name);
                                 }
                             }
-                            Html("<h2>Error</h2><p>Authentication already completed.</p>")
+                            page.error(&name, "Authentication already completed.")
                         } else {
-                            Html("<h2>Error</h2><p>State mismatch.</p>")
+                            page.error(&name, "State mismatch.")
                         }
                     } else {
-                        Html("<h2>Error</h2><p>Authentication failed.</p>")
+                        let message = params
+                            .get("error_description")
+                            .or_else(|| params.get("error"))
+                            .map_or("Authentication failed.", String::as_str);
+                        page.error(&name, message)
                     }
                 }
             }),
         );
 
-        // Start the server to accept the oauth code
-        let redirect_url = Url::parse(&self.redirect_url)?;
-        let port = redirect_url.port().unwrap_or(80);
-        let addr = SocketAddr::from(([127, 0, 0, 1], port));
-
-        let listener = tokio::net::TcpListener::bind(addr).await?;
+        // Start the server to accept the oauth code, and send the server the redirect URL it
+        // actually listens on
+        let (listener, redirect_url) = bind_callback(&self.redirect_url, ports).await?;
+        self.redirect_url = redirect_url;
 
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
         let server_handle = tokio::spawn(async move {
-            let server = axum::serve(listener, app);
-            server.await.unwrap();
+            let server = axum::serve(listener, app).with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+            if let Err(e) = server.await {
+                tracing::warn!("OAuth callback server error: {}", e);
+            }
         });
 
-        // Open the browser which will redirect with the code to the server
+        // Open the browser which will redirect with the code to the server, and open it again
+        // if the user wants another go after a timeout or Ctrl-C
         let authorization_url = self.get_authorization_url();
-        if webbrowser::open(&authorization_url).is_err() {
-            println!(
-                "Please open this URL in your browser:\n{}",
-                authorization_url
-            );
-        }
-
-        // Wait for the authorization code with a timeout
-        let code = tokio::time::timeout(
-            std::time::Duration::from_secs(60), // 1 minute timeout
-            rx,
-        )
-        .await
-        .map_err(|_| anyhow::anyhow!("Authentication timed out"))??;
+        let timeout = wait::timeout(service);
+        let code = loop {
+            if webbrowser::open(&authorization_url).is_err() {
+                println!(
+                    "Please open this URL in your browser:\n{}",
+                    authorization_url
+                );
+            }
+            match wait::wait_for_code(&mut rx, timeout).await {
+                Ok(code) => break Ok(code),
+                Err(e) if wait::offer_retry(service, &e).await => continue,
+                Err(e) => break Err(e),
+            }
+        };
 
-        // Stop the server
-        server_handle.abort();
+        // Stop the server, letting it finish the success page it may be sending
+        let _ = shutdown_tx.send(());
+        let _ = server_handle.await;
 
         // Exchange the code for a token
-        self.exchange_code_for_token(&code).await
+        self.exchange_code_for_token(&code?).await
     }
 }
 
 pub(crate) async fn get_oauth_token_async(
+    service: &str,
     host: &str,
     client_id: &str,
     redirect_url: &str,
@@ -373,7 +661,12 @@ pub(crate) async fn get_oauth_token_async(
     // Acquire the global mutex to ensure only one OAuth flow runs at a time
     let _guard = OAUTH_MUTEX.lock().await;
 
-    let token_cache = TokenCache::new(host, client_id, scopes);
+    // The workspace's default profile, which `goose auth switch` picks, has tokens of its own
+    let profile = TokenKey::for_profile(host, None)
+        .ok()
+        .and_then(|key| key.profile);
+    let token_cache = TokenCache::new(host, client_id, scopes, profile.as_deref());
+    let http = OAuthHttpSettings::from_config(service).client()?;
 
     // Try cache first
     if let Some(token) = token_cache.load_token() {
@@ -395,13 +688,14 @@ pub(crate) async fn get_oauth_token_async(
         // Token is expired or has no expiration, try to refresh if we have a refresh token
         if let Some(refresh_token) = token.refresh_token {
             // Get endpoints for token refresh
-            match get_workspace_endpoints(host).await {
+            match get_workspace_endpoints(host, &http).await {
                 Ok(endpoints) => {
                     let flow = OAuthFlow::new(
                         endpoints,
                         client_id.to_string(),
                         redirect_url.to_string(),
                         scopes.to_vec(),
+                        http.clone(),
                     );
 
                     // Try to refresh the token
@@ -419,7 +713,7 @@ pub(crate) async fn get_oauth_token_async(
                         Err(e) => {
                             tracing::warn!(
                                 "Failed to refresh token, will try new auth flow: {}",
-                                e
+                                crate::oauth::events::redact(&e.to_string())
                             );
                             // Continue to new auth flow
                         }
@@ -434,16 +728,33 @@ pub(crate) async fn get_oauth_token_async(
     }
 
     // Get endpoints and execute flow for a new token
-    let endpoints = get_workspace_endpoints(host).await?;
-    let flow = OAuthFlow::new(
+    let endpoints = get_workspace_endpoints(host, &http).await?;
+    let mut flow = OAuthFlow::new(
         endpoints,
         client_id.to_string(),
         redirect_url.to_string(),
         scopes.to_vec(),
-    );
+        http,
+    )
+    .with_account_choice(profile.is_some());
 
     // Execute the OAuth flow and get token
-    let token = flow.execute().await?;
+    let configured = crate::config::Config::global()
+        .get_param::<String>(OAUTH_FLOW_KEY)
+        .ok();
+    let token = match choose_flow(configured.as_deref(), &flow.endpoints, |name| {
+        std::env::var(name).ok()
+    }) {
+        FlowKind::Device => flow.execute_device().await?,
+        FlowKind::Manual => flow.execute_manual(service).await?,
+        FlowKind::Browser => {
+            let ports = callback_ports(
+                configured_callback_port(service).as_deref(),
+                &Url::parse(redirect_url)?,
+            )?;
+            flow.execute(service, &ports).await?
+        }
+    };
 
     // Cache and return
     token_cache.save_token(&token)?;
@@ -473,13 +784,135 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let endpoints = get_workspace_endpoints(&mock_server.uri()).await?;
+        let endpoints =
+            get_workspace_endpoints(&mock_server.uri(), &reqwest::Client::new()).await?;
 
         assert_eq!(
             endpoints.authorization_endpoint,
             "https://example.com/oauth2/authorize"
         );
         assert_eq!(endpoints.token_endpoint, "https://example.com/oauth2/token");
+        assert!(endpoints.device_authorization_endpoint.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_choose_flow() {
+        let mut endpoints = OidcEndpoints {
+            authorization_endpoint: "https://example.com/oauth2/authorize".to_string(),
+            token_endpoint: "https://example.com/oauth2/token".to_string(),
+            device_authorization_endpoint: Some("https://example.com/oauth2/device".to_string()),
+        };
+        let ssh = |name: &str| (name == "SSH_CONNECTION").then(|| "10.0.0.1 22".to_string());
+        let desktop = |name: &str| (name == "DISPLAY").then(|| ":0".to_string());
+
+        assert_eq!(choose_flow(None, &endpoints, ssh), FlowKind::Device);
+        assert_eq!(choose_flow(None, &endpoints, desktop), FlowKind::Browser);
+        assert_eq!(
+            choose_flow(Some("browser"), &endpoints, ssh),
+            FlowKind::Browser
+        );
+        assert_eq!(
+            choose_flow(Some("device"), &endpoints, desktop),
+            FlowKind::Device
+        );
+        assert_eq!(
+            choose_flow(Some("Manual"), &endpoints, desktop),
+            FlowKind::Manual
+        );
+
+        // Without a device endpoint, auto can only use the browser
+        endpoints.device_authorization_endpoint = None;
+        assert_eq!(
+            choose_flow(Some("auto"), &endpoints, ssh),
+            FlowKind::Browser
+        );
+    }
+
+    #[test]
+    fn test_callback_ports() -> Result<()> {
+        let localhost = Url::parse("http://localhost:8020")?;
+        assert_eq!(callback_ports(None, &localhost)?, vec![8020]);
+        assert_eq!(callback_ports(Some("9000"), &localhost)?, vec![9000]);
+        assert_eq!(
+            callback_ports(Some("8020-8023"), &localhost)?,
+            vec![8020, 8021, 8022, 8023]
+        );
+        assert_eq!(callback_ports(Some("auto"), &localhost)?, vec![0]);
+        assert!(callback_ports(Some("8030-8020"), &localhost).is_err());
+        assert!(callback_ports(Some("port"), &localhost).is_err());
+
+        let loopback = Url::parse("http://127.0.0.1:8020/callback")?;
+        assert_eq!(callback_ports(None, &loopback)?, vec![8020, 0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bind_callback_falls_back() -> Result<()> {
+        let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let taken_port = taken.local_addr()?.port();
+        let redirect = format!("http://localhost:{}", taken_port);
+
+        let (listener, rewritten) = bind_callback(&redirect, &[taken_port, 0]).await?;
+        let port = listener.local_addr()?.port();
+        assert_ne!(port, taken_port);
+        assert_eq!(rewritten, format!("http://localhost:{}", port));
+
+        assert!(bind_callback(&redirect, &[taken_port]).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_device_flow() -> Result<()> {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/oauth2/device"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "device_code": "device-123",
+                "user_code": "ABCD-EFGH",
+                "verification_uri": "https://example.com/device",
+                "expires_in": 600,
+                "interval": 0
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .set_body_json(serde_json::json!({"error": "authorization_pending"})),
+            )
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "device-access-token",
+                "refresh_token": "device-refresh-token",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let flow = OAuthFlow::new(
+            OidcEndpoints {
+                authorization_endpoint: format!("{}/oauth2/authorize", mock_server.uri()),
+                token_endpoint: format!("{}/oauth2/token", mock_server.uri()),
+                device_authorization_endpoint: Some(format!("{}/oauth2/device", mock_server.uri())),
+            },
+            "test-client".to_string(),
+            "http://localhost:8020".to_string(),
+            vec!["all-apis".to_string()],
+            reqwest::Client::new(),
+        );
+
+        let token = flow.execute_device().await?;
+        assert_eq!(token.access_token, "device-access-token");
+        assert_eq!(token.refresh_token.as_deref(), Some("device-refresh-token"));
 
         Ok(())
     }
@@ -490,7 +923,15 @@ mod tests {
             "https://example.com",
             "test-client",
             &["scope1".to_string()],
+            None,
+        );
+        let work = TokenCache::new(
+            "https://example.com",
+            "test-client",
+            &["scope1".to_string()],
+            Some("work"),
         );
+        assert_ne!(work.cache_path, cache.cache_path);
 
         // Test with expiration time
         let token_data = TokenData {
@@ -531,6 +972,7 @@ mod tests {
         let endpoints = OidcEndpoints {
             authorization_endpoint: "https://example.com/oauth2/authorize".to_string(),
             token_endpoint: "https://example.com/oauth2/token".to_string(),
+            device_authorization_endpoint: None,
         };
 
         let flow = OAuthFlow::new(
@@ -538,6 +980,7 @@ mod tests {
             "test-client".to_string(),
             "http://localhost:8020".to_string(),
             vec!["all-apis".to_string()],
+            reqwest::Client::new(),
         );
 
         // Test with expires_in (traditional OAuth)
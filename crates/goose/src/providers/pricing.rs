@@ -1,4 +1,5 @@
 use anyhow::Result;
+use regex::Regex;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -37,9 +38,25 @@ pub struct CachedPricingData {
 pub struct PricingInfo {
     pub input_cost: f64,  // Cost per token
     pub output_cost: f64, // Cost per token
+    /// Cost per token for input served from the prompt cache, when it is discounted
+    #[serde(default)]
+    pub cached_input_cost: Option<f64>,
     pub context_length: Option<u32>,
 }
 
+impl PricingInfo {
+    /// The cost of `input` prompt tokens, `cached_input` of them read from the prompt cache, and
+    /// `output` generated ones
+    pub fn cost(&self, input: usize, cached_input: usize, output: usize) -> f64 {
+        let cached = cached_input.min(input);
+        let fresh = input - cached;
+        let cached_rate = self.cached_input_cost.unwrap_or(self.input_cost);
+        self.input_cost * fresh as f64
+            + cached_rate * cached as f64
+            + self.output_cost * output as f64
+    }
+}
+
 /// Cache for OpenRouter pricing data with disk persistence
 pub struct PricingCache {
     /// In-memory cache
@@ -159,6 +176,11 @@ impl PricingCache {
                         PricingInfo {
                             input_cost,
                             output_cost,
+                            cached_input_cost: model
+                                .pricing
+                                .input_cache_read
+                                .as_deref()
+                                .and_then(convert_pricing),
                             context_length: model.context_length,
                         },
                     );
@@ -257,6 +279,8 @@ pub struct OpenRouterModel {
 pub struct OpenRouterPricing {
     pub prompt: String,     // Cost per token for input (in USD)
     pub completion: String, // Cost per token for output (in USD)
+    #[serde(default)]
+    pub input_cache_read: Option<String>, // Cost per token for cached input (in USD)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -274,6 +298,7 @@ pub struct OpenRouterModelsResponse {
 
 /// Internal function to fetch pricing data
 async fn fetch_openrouter_pricing_internal() -> Result<HashMap<String, OpenRouterModel>> {
+    crate::offline::require_network("Fetching model pricing")?;
     let client = create_http_client();
     let response = client
         .get("https://openrouter.ai/api/v1/models")
@@ -308,6 +333,46 @@ pub async fn get_model_pricing(provider: &str, model: &str) -> Option<PricingInf
     PRICING_CACHE.get_model_pricing(provider, model).await
 }
 
+/// Get pricing for a model as `provider` names it, looking up OpenRouter models under the
+/// provider behind them and dropping the version suffixes the pricing data leaves out
+pub async fn find_model_pricing(provider: &str, model: &str) -> Option<PricingInfo> {
+    let openrouter_data = if provider == "openrouter" {
+        parse_model_id(model)
+    } else {
+        None
+    };
+    let (provider, model) = match &openrouter_data {
+        Some((real_provider, real_model)) => (real_provider.as_str(), real_model.as_str()),
+        None => (provider, model),
+    };
+    get_model_pricing(provider, &normalize_model_name(model)).await
+}
+
+/// A model name as the pricing data has it: no `-latest` or date suffix, and versions like
+/// `3-7` written `3.7`
+pub fn normalize_model_name(model: &str) -> String {
+    let mut result = model.to_string();
+
+    // Remove "-latest" suffix
+    if result.ends_with("-latest") {
+        result = result.strip_suffix("-latest").unwrap().to_string();
+    }
+
+    // Remove date-like suffixes: -YYYYMMDD
+    let re_date = Regex::new(r"-\d{8}$").unwrap();
+    if re_date.is_match(&result) {
+        result = re_date.replace(&result, "").to_string();
+    }
+
+    // Convert version numbers like -3-7- to -3.7- (e.g., claude-3-7-sonnet -> claude-3.7-sonnet)
+    let re_version = Regex::new(r"-(\d+)-(\d+)-").unwrap();
+    if re_version.is_match(&result) {
+        result = re_version.replace(&result, "-$1.$2-").to_string();
+    }
+
+    result
+}
+
 /// Force refresh pricing data
 pub async fn refresh_pricing() -> Result<()> {
     PRICING_CACHE.refresh().await
@@ -370,6 +435,19 @@ pub fn convert_pricing(price_str: &str) -> Option<f64> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_model_name() {
+        assert_eq!(
+            normalize_model_name("claude-3-7-sonnet-20250219"),
+            "claude-3.7-sonnet"
+        );
+        assert_eq!(
+            normalize_model_name("claude-3-5-haiku-latest"),
+            "claude-3.5-haiku"
+        );
+        assert_eq!(normalize_model_name("gpt-4o"), "gpt-4o");
+    }
+
     #[test]
     fn test_parse_model_id() {
         assert_eq!(
@@ -303,11 +303,8 @@ impl Provider for SageMakerTgiProvider {
         let message = self.parse_tgi_response(response)?;
 
         // TGI doesn't provide usage statistics, so we estimate
-        let usage = Usage {
-            input_tokens: Some(0),  // Would need to tokenize input to get accurate count
-            output_tokens: Some(0), // Would need to tokenize output to get accurate count
-            total_tokens: Some(0),
-        };
+        // Would need to tokenize input and output to get accurate counts
+        let usage = Usage::new(Some(0), Some(0), Some(0));
 
         // Add debug trace
         let debug_payload = serde_json::json!({
@@ -0,0 +1,221 @@
+//! Time limits for each request to the model.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_stream::try_stream;
+use futures::StreamExt;
+use tokio::time::{timeout_at, Instant};
+
+use super::base::MessageStream;
+use super::errors::ProviderError;
+use crate::config::Config;
+
+pub const CONNECT_TIMEOUT_KEY: &str = "GOOSE_PROVIDER_CONNECT_TIMEOUT";
+pub const FIRST_TOKEN_TIMEOUT_KEY: &str = "GOOSE_PROVIDER_FIRST_TOKEN_TIMEOUT";
+pub const TOTAL_TIMEOUT_KEY: &str = "GOOSE_PROVIDER_TIMEOUT";
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+/// The limit requests had before there were separate ones
+const DEFAULT_TOTAL_TIMEOUT_SECS: u64 = 600;
+
+/// A request counts as close to its deadline once it has less than this share of its limit left
+const CLOSE_TO_DEADLINE: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeouts {
+    pub connect: Duration,
+    pub first_token: Duration,
+    pub total: Duration,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            first_token: Duration::from_secs(DEFAULT_TOTAL_TIMEOUT_SECS),
+            total: Duration::from_secs(DEFAULT_TOTAL_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let secs = |key: &str, default: Duration| {
+            config
+                .get_param::<u64>(key)
+                .map(|secs| Duration::from_secs(secs.max(1)))
+                .unwrap_or(default)
+        };
+        let defaults = Self::default();
+        let total = secs(TOTAL_TIMEOUT_KEY, defaults.total);
+        Self {
+            connect: secs(CONNECT_TIMEOUT_KEY, defaults.connect),
+            first_token: secs(FIRST_TOKEN_TIMEOUT_KEY, total),
+            total,
+        }
+    }
+}
+
+/// The deadline of one agent's request in flight and the limit it comes from, so the CLI can
+/// warn while a request is running out of time. Each agent has its own, so a subagent's
+/// request doesn't show up as its parent's.
+#[derive(Clone, Default)]
+pub struct RequestDeadline(Arc<Mutex<Option<(Instant, Duration)>>>);
+
+impl RequestDeadline {
+    fn set(&self, deadline: Option<(Instant, Duration)>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = deadline;
+    }
+
+    /// How long the request in flight has left, once that is little enough to mention
+    pub fn approaching(&self) -> Option<Duration> {
+        let (deadline, limit) = (*self.0.lock().unwrap_or_else(|e| e.into_inner()))?;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        close_to_deadline(remaining, limit).then_some(remaining)
+    }
+}
+
+/// Clears the request in flight when the request ends, however it ends
+struct InFlight(RequestDeadline);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.set(None);
+    }
+}
+
+fn close_to_deadline(remaining: Duration, limit: Duration) -> bool {
+    remaining.as_secs_f64() < limit.as_secs_f64() * CLOSE_TO_DEADLINE
+}
+
+fn timed_out(what: &str, limit: Duration) -> ProviderError {
+    ProviderError::Timeout(format!(
+        "no {} from the model within {}s",
+        what,
+        limit.as_secs()
+    ))
+}
+
+/// The stream `start` opens, failing when its first part takes longer than `first_token` or
+/// all of it longer than `total`. `deadline` follows the limit the request is up against.
+pub async fn with_timeouts<F>(
+    timeouts: RequestTimeouts,
+    deadline: &RequestDeadline,
+    start: F,
+) -> Result<MessageStream, ProviderError>
+where
+    F: Future<Output = Result<MessageStream, ProviderError>>,
+{
+    let started = Instant::now();
+    let first_token = timeouts.first_token.min(timeouts.total);
+    let first_deadline = started + first_token;
+    let total_deadline = started + timeouts.total;
+    deadline.set(Some((first_deadline, first_token)));
+    let in_flight = InFlight(deadline.clone());
+
+    let mut stream = match timeout_at(first_deadline, start).await {
+        Ok(stream) => stream?,
+        Err(_) => return Err(timed_out("response", first_token)),
+    };
+
+    Ok(Box::pin(try_stream! {
+        let in_flight = in_flight;
+        let mut received = false;
+        loop {
+            let deadline = if received { total_deadline } else { first_deadline };
+            match timeout_at(deadline, stream.next()).await {
+                Ok(Some(item)) => {
+                    if !received {
+                        received = true;
+                        in_flight.0.set(Some((total_deadline, timeouts.total)));
+                    }
+                    yield item?;
+                }
+                Ok(None) => break,
+                Err(_) if received => {
+                    Err::<(), _>(timed_out("complete response", timeouts.total))?
+                }
+                Err(_) => Err::<(), _>(timed_out("response", first_token))?,
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::Message;
+    use crate::providers::base::{stream_from_single_message, ProviderUsage, Usage};
+
+    fn timeouts(first_token_ms: u64, total_ms: u64) -> RequestTimeouts {
+        RequestTimeouts {
+            connect: Duration::from_secs(1),
+            first_token: Duration::from_millis(first_token_ms),
+            total: Duration::from_millis(total_ms),
+        }
+    }
+
+    fn single_message() -> MessageStream {
+        stream_from_single_message(
+            Message::assistant().with_text("done"),
+            ProviderUsage::new("model".to_string(), Usage::default()),
+        )
+    }
+
+    #[test]
+    fn test_close_to_deadline() {
+        let limit = Duration::from_secs(100);
+        assert!(!close_to_deadline(Duration::from_secs(50), limit));
+        assert!(close_to_deadline(Duration::from_secs(10), limit));
+
+        let deadline = RequestDeadline::default();
+        deadline.set(Some((Instant::now() + Duration::from_secs(10), limit)));
+        assert!(deadline.approaching().is_some());
+        // Each agent's request has a deadline of its own
+        assert!(RequestDeadline::default().approaching().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_with_timeouts() {
+        let deadline = RequestDeadline::default();
+        let mut stream = with_timeouts(timeouts(1000, 2000), &deadline, async {
+            Ok(single_message())
+        })
+        .await
+        .unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        assert!(stream.next().await.is_none());
+
+        let slow_start = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(single_message())
+        };
+        let error = with_timeouts(timeouts(20, 2000), &deadline, slow_start)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, ProviderError::Timeout(_)));
+
+        let silent: MessageStream = Box::pin(futures::stream::pending());
+        let mut stream = with_timeouts(timeouts(20, 2000), &deadline, async { Ok(silent) })
+            .await
+            .unwrap();
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(ProviderError::Timeout(_)))
+        ));
+
+        // A request given up on takes its deadline with it
+        let silent: MessageStream = Box::pin(futures::stream::pending());
+        let stream = with_timeouts(timeouts(2000, 2000), &deadline, async { Ok(silent) })
+            .await
+            .unwrap();
+        deadline.set(Some((Instant::now(), Duration::from_secs(100))));
+        assert!(deadline.approaching().is_some());
+        drop(stream);
+        assert!(deadline.approaching().is_none());
+    }
+}
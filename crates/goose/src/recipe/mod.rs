@@ -155,6 +155,121 @@ pub struct SubRecipe {
     pub sequential_when_repeated: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<TaskResources>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<TaskRetryPolicy>,
+    /// How long each of its tasks may run before it is stopped and fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    /// Which of the tasks waiting to run start first; `normal` unless given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
+    /// Files and directories, relative to the working directory, each of its tasks leaves
+    /// behind; they are copied into `artifacts/<task_id>/` once the task completes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+    /// Names of other sub-recipes of the recipe whose tasks must complete before its tasks start,
+    /// when they run together
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Whether its tasks may get the result a task like them completed with earlier, rather
+    /// than running again; only for tasks without effects that matter
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache: bool,
+}
+
+/// What the tasks of a sub-recipe need while they run, so parallel runs can be scheduled
+/// around it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct TaskResources {
+    /// Mostly busy on the CPU, like builds and test suites; no more of these run at once than
+    /// there are cores
+    #[serde(default)]
+    pub cpu_heavy: bool,
+    /// Mostly busy on the network, like downloads and crawls
+    #[serde(default)]
+    pub network_heavy: bool,
+    /// Named locks held for the whole task; tasks sharing a lock never run at the same time
+    #[serde(default)]
+    pub locks: Vec<String>,
+}
+
+/// How soon a task starts when more of them are waiting to run than may run at once; tasks of
+/// a higher priority start before any of a lower one
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl fmt::Display for TaskPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Normal => write!(f, "normal"),
+            Self::High => write!(f, "high"),
+        }
+    }
+}
+
+/// When a failed task of a sub-recipe is run again
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct TaskRetryPolicy {
+    /// Runs of the task in all, the first included
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Seconds to wait before the first retry; each retry after that waits twice as long
+    #[serde(default = "default_backoff_seconds")]
+    pub backoff_seconds: u64,
+    /// The longest wait between two runs, in seconds
+    #[serde(default = "default_max_backoff_seconds")]
+    pub max_backoff_seconds: u64,
+    /// Regular expressions matched against the error; only failures matching one of them are
+    /// retried. Every failure is when there are none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retry_on: Vec<String>,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_seconds() -> u64 {
+    5
+}
+
+fn default_max_backoff_seconds() -> u64 {
+    60
+}
+
+impl Default for TaskRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_seconds: default_backoff_seconds(),
+            max_backoff_seconds: default_max_backoff_seconds(),
+            retry_on: Vec::new(),
+        }
+    }
+}
+
+impl TaskRetryPolicy {
+    /// Checks that every `retry_on` pattern is a valid regular expression
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.retry_on {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(format!("retry_on pattern '{}' is invalid: {}", pattern, e));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 fn deserialize_value_map_as_string<'de, D>(
@@ -337,6 +452,30 @@ impl Recipe {
             }
         }
 
+        let sub_recipes = recipe.sub_recipes.as_deref().unwrap_or_default();
+        for sub_recipe in sub_recipes {
+            for dependency in &sub_recipe.depends_on {
+                if dependency == &sub_recipe.name
+                    || !sub_recipes.iter().any(|other| &other.name == dependency)
+                {
+                    return Err(anyhow::anyhow!(
+                        "Sub-recipe '{}' depends on '{}', which isn't another of its sub-recipes",
+                        sub_recipe.name,
+                        dependency
+                    ));
+                }
+            }
+            if let Some(ref retry_policy) = sub_recipe.retry {
+                if let Err(validation_error) = retry_policy.validate() {
+                    return Err(anyhow::anyhow!(
+                        "Invalid retry policy for sub-recipe '{}': {}",
+                        sub_recipe.name,
+                        validation_error
+                    ));
+                }
+            }
+        }
+
         Ok(recipe)
     }
 }
@@ -555,6 +694,137 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sub_recipe_resources() {
+        let content = r#"version: 1.0.0
+title: Test Recipe
+description: A test recipe
+instructions: Test instructions
+sub_recipes:
+  - name: build
+    path: build.yaml
+    resources:
+      cpu_heavy: true
+      locks: [target-dir]
+  - name: lint
+    path: lint.yaml
+"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+        let sub_recipes = recipe.sub_recipes.unwrap();
+        assert_eq!(
+            sub_recipes[0].resources,
+            Some(TaskResources {
+                cpu_heavy: true,
+                network_heavy: false,
+                locks: vec!["target-dir".to_string()],
+            })
+        );
+        assert_eq!(sub_recipes[1].resources, None);
+    }
+
+    #[test]
+    fn test_sub_recipe_depends_on() {
+        let content = r#"version: 1.0.0
+title: Test Recipe
+description: A test recipe
+instructions: Test instructions
+sub_recipes:
+  - name: fetch
+    path: fetch.yaml
+  - name: summarize
+    path: summarize.yaml
+    depends_on: ["fetch"]
+"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+        let sub_recipes = recipe.sub_recipes.unwrap();
+        assert!(sub_recipes[0].depends_on.is_empty());
+        assert_eq!(sub_recipes[1].depends_on, vec!["fetch".to_string()]);
+
+        for dependency in ["lint", "summarize"] {
+            let invalid = content.replace(r#"["fetch"]"#, &format!(r#"["{}"]"#, dependency));
+            let err = Recipe::from_content(&invalid).unwrap_err().to_string();
+            assert!(err.contains(&format!("depends on '{}'", dependency)));
+        }
+    }
+
+    #[test]
+    fn test_sub_recipe_retry() {
+        let content = r#"version: 1.0.0
+title: Test Recipe
+description: A test recipe
+instructions: Test instructions
+sub_recipes:
+  - name: fetch
+    path: fetch.yaml
+    retry:
+      max_attempts: 4
+      retry_on: ["timed out", "50[0-9]"]
+  - name: lint
+    path: lint.yaml
+"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+        let sub_recipes = recipe.sub_recipes.unwrap();
+        assert_eq!(
+            sub_recipes[0].retry,
+            Some(TaskRetryPolicy {
+                max_attempts: 4,
+                retry_on: vec!["timed out".to_string(), "50[0-9]".to_string()],
+                ..Default::default()
+            })
+        );
+        assert_eq!(sub_recipes[1].retry, None);
+
+        let invalid = content.replace(r#""50[0-9]""#, r#""50[0-9""#);
+        let err = Recipe::from_content(&invalid).unwrap_err().to_string();
+        assert!(err.contains("sub-recipe 'fetch'"));
+        assert!(err.contains("50[0-9"));
+    }
+
+    #[test]
+    fn test_sub_recipe_timeout() {
+        let content = r#"version: 1.0.0
+title: Test Recipe
+description: A test recipe
+instructions: Test instructions
+sub_recipes:
+  - name: crawl
+    path: crawl.yaml
+    timeout_seconds: 900
+  - name: lint
+    path: lint.yaml
+"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+        let sub_recipes = recipe.sub_recipes.unwrap();
+        assert_eq!(sub_recipes[0].timeout_seconds, Some(900));
+        assert_eq!(sub_recipes[1].timeout_seconds, None);
+    }
+
+    #[test]
+    fn test_sub_recipe_priority() {
+        let content = r#"version: 1.0.0
+title: Test Recipe
+description: A test recipe
+instructions: Test instructions
+sub_recipes:
+  - name: hotfix
+    path: hotfix.yaml
+    priority: high
+  - name: lint
+    path: lint.yaml
+"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+        let sub_recipes = recipe.sub_recipes.unwrap();
+        assert_eq!(sub_recipes[0].priority, Some(TaskPriority::High));
+        assert_eq!(sub_recipes[1].priority, None);
+        assert!(TaskPriority::High > TaskPriority::Normal);
+        assert!(TaskPriority::Normal > TaskPriority::Low);
+    }
+
     #[test]
     fn test_from_content_with_yaml() {
         let content = r#"version: 1.0.0
@@ -0,0 +1,272 @@
+//! Content-addressed storage for large attachments and tool artifacts.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use super::session_manager::ensure_session_dir;
+
+/// Blobs younger than this survive a collection even without references
+pub const DEFAULT_GC_GRACE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct GcReport {
+    pub removed_blobs: usize,
+    pub freed_bytes: u64,
+    pub kept_blobs: usize,
+    /// Reference files of sessions that no longer exist
+    pub removed_refs: usize,
+}
+
+impl BlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The store shared by all sessions, next to the session database
+    pub fn open() -> Result<Self> {
+        Ok(Self::new(ensure_session_dir()?.join("blobs")))
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    fn refs_dir(&self) -> PathBuf {
+        self.root.join("refs")
+    }
+
+    fn refs_file(&self, session_id: &str) -> PathBuf {
+        self.refs_dir().join(session_id)
+    }
+
+    pub fn path_for(&self, hash: &str) -> PathBuf {
+        self.objects_dir().join(&hash[..2]).join(hash)
+    }
+
+    /// Store `content` for `session_id` and return where it lives. Storing content that is
+    /// already there only adds the reference.
+    pub fn put(&self, session_id: &str, content: &[u8]) -> Result<PathBuf> {
+        let hash = format!("{:x}", Sha256::digest(content));
+        self.add_reference(session_id, &hash)?;
+
+        let path = self.path_for(&hash);
+        if path.exists() {
+            // Restart the grace period so a running collection leaves it alone
+            if let Ok(file) = OpenOptions::new().append(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Ok(path);
+        }
+
+        let dir = path.parent().expect("blob paths have a parent");
+        fs::create_dir_all(dir)?;
+        // Write under a temporary name so readers never see a partial blob
+        let partial = dir.join(format!(
+            "{}.{}.partial",
+            hash,
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ));
+        fs::write(&partial, content)?;
+        fs::rename(&partial, &path).with_context(|| format!("Failed to store blob {}", hash))?;
+        Ok(path)
+    }
+
+    fn add_reference(&self, session_id: &str, hash: &str) -> Result<()> {
+        if self.references_of(session_id).contains(hash) {
+            return Ok(());
+        }
+        fs::create_dir_all(self.refs_dir())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.refs_file(session_id))?;
+        writeln!(file, "{}", hash)?;
+        Ok(())
+    }
+
+    fn references_of(&self, session_id: &str) -> HashSet<String> {
+        fs::read_to_string(self.refs_file(session_id))
+            .map(|refs| refs.lines().map(|line| line.trim().to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// How many sessions reference each blob
+    pub fn reference_counts(&self) -> Result<HashMap<String, usize>> {
+        let mut counts = HashMap::new();
+        let Ok(entries) = fs::read_dir(self.refs_dir()) else {
+            return Ok(counts);
+        };
+        for entry in entries.flatten() {
+            let session_id = entry.file_name().to_string_lossy().into_owned();
+            for hash in self.references_of(&session_id) {
+                *counts.entry(hash).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    fn blobs(&self) -> Vec<PathBuf> {
+        let mut blobs = Vec::new();
+        let Ok(prefixes) = fs::read_dir(self.objects_dir()) else {
+            return blobs;
+        };
+        for prefix in prefixes.flatten() {
+            if let Ok(entries) = fs::read_dir(prefix.path()) {
+                blobs.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        }
+        blobs
+    }
+
+    /// Forget the references of sessions not in `live_sessions` and remove blobs that are left
+    /// unreferenced and older than `grace`. With `dry_run` nothing is deleted, only reported.
+    pub fn gc(
+        &self,
+        live_sessions: &HashSet<String>,
+        grace: Duration,
+        dry_run: bool,
+    ) -> Result<GcReport> {
+        let mut report = GcReport::default();
+
+        if let Ok(entries) = fs::read_dir(self.refs_dir()) {
+            for entry in entries.flatten() {
+                let session_id = entry.file_name().to_string_lossy().into_owned();
+                if !live_sessions.contains(&session_id) {
+                    report.removed_refs += 1;
+                    if !dry_run {
+                        fs::remove_file(entry.path())?;
+                    }
+                }
+            }
+        }
+
+        // A dry run left the stale reference files in place, so only count the live ones
+        let referenced: HashSet<String> = if dry_run {
+            live_sessions
+                .iter()
+                .flat_map(|id| self.references_of(id))
+                .collect()
+        } else {
+            self.reference_counts()?.into_keys().collect()
+        };
+        let cutoff = SystemTime::now().checked_sub(grace);
+
+        for blob in self.blobs() {
+            let Ok(metadata) = fs::metadata(&blob) else {
+                continue;
+            };
+            let name = blob
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // Leftovers of interrupted writes go once they are past the grace period
+            let hash = name.split('.').next().unwrap_or_default();
+            let recent = match (metadata.modified(), cutoff) {
+                (Ok(modified), Some(cutoff)) => modified > cutoff,
+                _ => true,
+            };
+            if recent || (referenced.contains(hash) && !name.ends_with(".partial")) {
+                report.kept_blobs += 1;
+                continue;
+            }
+            report.removed_blobs += 1;
+            report.freed_bytes += metadata.len();
+            if !dry_run {
+                fs::remove_file(&blob)?;
+            }
+        }
+
+        if !dry_run {
+            remove_empty_dirs(&self.objects_dir());
+        }
+        Ok(report)
+    }
+}
+
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            // Fails harmlessly when the directory still has blobs in it
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_old(path: &Path) {
+        OpenOptions::new()
+            .append(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(2 * 3600))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_put_deduplicates_across_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+
+        let first = store.put("session-1", b"big file").unwrap();
+        let second = store.put("session-2", b"big file").unwrap();
+        store.put("session-2", b"big file").unwrap();
+        let other = store.put("session-2", b"other file").unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(fs::read(&first).unwrap(), b"big file");
+        assert_eq!(store.blobs().len(), 2);
+
+        let counts = store.reference_counts().unwrap();
+        let hash = first.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(counts[&hash], 2);
+        assert_eq!(counts.values().sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn test_gc_removes_only_unreferenced_old_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+
+        let shared = store.put("kept", b"shared").unwrap();
+        store.put("deleted", b"shared").unwrap();
+        let orphan = store.put("deleted", b"orphan").unwrap();
+        let fresh = store.put("also-deleted", b"fresh").unwrap();
+        make_old(&shared);
+        make_old(&orphan);
+
+        let live = HashSet::from(["kept".to_string()]);
+        let dry = store.gc(&live, DEFAULT_GC_GRACE, true).unwrap();
+        assert_eq!(dry.removed_blobs, 1);
+        assert!(orphan.exists());
+
+        let report = store.gc(&live, DEFAULT_GC_GRACE, false).unwrap();
+        assert_eq!(
+            report,
+            GcReport {
+                removed_blobs: 1,
+                freed_bytes: 6,
+                kept_blobs: 2,
+                removed_refs: 2,
+            }
+        );
+        assert!(shared.exists());
+        assert!(!orphan.exists());
+        // Still within the grace period
+        assert!(fresh.exists());
+    }
+}
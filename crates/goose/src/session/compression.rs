@@ -0,0 +1,111 @@
+//! zstd compression of stored messages.
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+
+pub const COMPRESSION_KEY: &str = "GOOSE_SESSION_COMPRESSION";
+
+/// Smaller content isn't worth a frame of its own
+pub const MIN_COMPRESSED_BYTES: usize = 4 * 1024;
+
+const LEVEL: i32 = 3;
+
+/// Message content as it goes into the `messages` table: `content_json` holds the JSON unless
+/// it was compressed into `content_zstd`
+#[derive(Debug, PartialEq)]
+pub struct StoredContent {
+    pub content_json: String,
+    pub content_zstd: Option<Vec<u8>>,
+    /// Size of the JSON in bytes, compressed or not
+    pub content_size: i64,
+}
+
+pub fn is_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(COMPRESSION_KEY)
+        .unwrap_or(true)
+}
+
+/// Compress `json` if `compress` is set, it's big enough and compressing actually saves space
+pub fn encode(json: String, compress: bool) -> StoredContent {
+    let content_size = json.len() as i64;
+    if compress && json.len() >= MIN_COMPRESSED_BYTES {
+        match zstd::bulk::compress(json.as_bytes(), LEVEL) {
+            Ok(compressed) if compressed.len() < json.len() => {
+                return StoredContent {
+                    content_json: String::new(),
+                    content_zstd: Some(compressed),
+                    content_size,
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Storing a message uncompressed: {}", e),
+        }
+    }
+    StoredContent {
+        content_json: json,
+        content_zstd: None,
+        content_size,
+    }
+}
+
+/// The JSON of a stored message, whichever way it was stored
+pub fn decode(content_json: String, content_zstd: Option<Vec<u8>>) -> Result<String> {
+    match content_zstd {
+        Some(compressed) => {
+            let json = zstd::stream::decode_all(compressed.as_slice())
+                .context("Failed to decompress a stored message")?;
+            String::from_utf8(json).context("A stored message is not valid UTF-8")
+        }
+        None => Ok(content_json),
+    }
+}
+
+/// What compressing the messages already stored did
+#[derive(Debug, Default, PartialEq)]
+pub struct CompressionReport {
+    pub messages_compressed: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// How much room a session's messages take
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionSize {
+    /// As stored, compressed or not
+    pub stored_bytes: u64,
+    /// As JSON
+    pub content_bytes: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let big = serde_json::to_string(&vec!["tool output line"; 1000]).unwrap();
+        let stored = encode(big.clone(), true);
+        assert!(stored.content_json.is_empty());
+        assert!(stored.content_zstd.as_ref().unwrap().len() < big.len() / 10);
+        assert_eq!(stored.content_size, big.len() as i64);
+        assert_eq!(
+            decode(stored.content_json, stored.content_zstd).unwrap(),
+            big
+        );
+
+        let small = r#"[{"type":"text","text":"hi"}]"#.to_string();
+        let stored = encode(small.clone(), true);
+        assert_eq!(stored.content_json, small);
+        assert!(stored.content_zstd.is_none());
+
+        let uncompressed = encode(big.clone(), false);
+        assert_eq!(uncompressed.content_json, big);
+    }
+
+    #[test]
+    fn test_corrupt_frame() {
+        assert!(decode(String::new(), Some(b"not zstd".to_vec())).is_err());
+    }
+}
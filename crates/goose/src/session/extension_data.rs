@@ -2,6 +2,7 @@
 // Provides a simple way to store extension-specific data with versioned keys
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -95,6 +96,47 @@ impl TodoState {
     }
 }
 
+/// User reactions to assistant turns, recorded with /good and /bad
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedbackState {
+    pub entries: Vec<FeedbackEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackRating {
+    Good,
+    Bad,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEntry {
+    /// Index of the rated assistant message in the conversation
+    pub message_index: usize,
+    pub rating: FeedbackRating,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExtensionState for FeedbackState {
+    const EXTENSION_NAME: &'static str = "feedback";
+    const VERSION: &'static str = "v0";
+}
+
+impl FeedbackState {
+    /// Record a rating, replacing any earlier rating of the same message
+    pub fn record(&mut self, message_index: usize, rating: FeedbackRating, reason: Option<String>) {
+        self.entries.retain(|e| e.message_index != message_index);
+        self.entries.push(FeedbackEntry {
+            message_index,
+            rating,
+            reason,
+            created_at: Utc::now(),
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,6 +187,26 @@ mod tests {
         assert_eq!(retrieved.unwrap().content, "- Task 1\n- Task 2");
     }
 
+    #[test]
+    fn test_feedback_state_replaces_rating() {
+        let mut extension_data = ExtensionData::new();
+        let mut feedback = FeedbackState::default();
+        feedback.record(1, FeedbackRating::Good, None);
+        feedback.record(3, FeedbackRating::Good, None);
+        feedback.record(1, FeedbackRating::Bad, Some("wrong file".to_string()));
+        feedback.to_extension_data(&mut extension_data).unwrap();
+
+        let retrieved = FeedbackState::from_extension_data(&extension_data).unwrap();
+        assert_eq!(retrieved.entries.len(), 2);
+        let first = retrieved
+            .entries
+            .iter()
+            .find(|e| e.message_index == 1)
+            .unwrap();
+        assert_eq!(first.rating, FeedbackRating::Bad);
+        assert_eq!(first.reason.as_deref(), Some("wrong file"));
+    }
+
     #[test]
     fn test_extension_data_serialization() {
         let mut extension_data = ExtensionData::new();
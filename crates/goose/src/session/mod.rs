@@ -1,5 +1,8 @@
+pub mod blob_store;
+pub mod compression;
 pub mod extension_data;
 mod legacy;
+pub mod run_diff;
 pub mod session_manager;
 
 pub use session_manager::{Session, SessionInsights, SessionManager};
@@ -0,0 +1,349 @@
+//! Comparing two runs, for `goose run diff`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use rmcp::model::{CallToolRequestParam, Role};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::conversation::message::MessageContent;
+use crate::session::Session;
+
+/// Text editor commands that change the file they name
+const EDIT_COMMANDS: &[&str] = &["write", "str_replace", "insert", "undo_edit"];
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct RunSummary {
+    pub session_id: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub duration_secs: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_tokens: i64,
+    /// Estimated, when the price of the model is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    pub tool_calls: usize,
+    pub failed_tool_calls: usize,
+    pub tasks_completed: usize,
+    pub tasks_failed: usize,
+    pub task_errors: Vec<String>,
+    pub files_changed: BTreeSet<String>,
+    /// The text of the last assistant message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_output: Option<String>,
+}
+
+/// The file a text editor call changes, if it changes one
+fn edited_file(tool_call: &CallToolRequestParam) -> Option<String> {
+    if !tool_call.name.ends_with("text_editor") {
+        return None;
+    }
+    let arguments = tool_call.arguments.as_ref()?;
+    let command = arguments.get("command")?.as_str()?;
+    if !EDIT_COMMANDS.contains(&command) {
+        return None;
+    }
+    arguments
+        .get("path")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// The tasks in the response of the subagent task tool: whether each one completed, and why
+/// not if it didn't
+fn task_results(response: &Value) -> Vec<(bool, Option<String>)> {
+    response
+        .get("results")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|result| {
+            let status = result.get("status")?.as_str()?;
+            let error = result
+                .get("error")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            Some((status == "Completed", error))
+        })
+        .collect()
+}
+
+impl RunSummary {
+    /// Summarize `session`, which must have been loaded with its messages
+    pub fn from_session(session: &Session) -> Self {
+        let tokens = |accumulated: Option<i32>, last: Option<i32>| {
+            accumulated.or(last).unwrap_or_default() as i64
+        };
+        let mut summary = Self {
+            session_id: session.id.clone(),
+            description: session.description.clone(),
+            recipe: session.recipe.as_ref().map(|recipe| recipe.title.clone()),
+            schedule_id: session.schedule_id.clone(),
+            started_at: session.created_at,
+            duration_secs: (session.updated_at - session.created_at).num_seconds(),
+            input_tokens: tokens(session.accumulated_input_tokens, session.input_tokens),
+            output_tokens: tokens(session.accumulated_output_tokens, session.output_tokens),
+            total_tokens: tokens(session.accumulated_total_tokens, session.total_tokens),
+            ..Default::default()
+        };
+        let Some(conversation) = &session.conversation else {
+            return summary;
+        };
+
+        let mut edits: HashMap<&str, String> = HashMap::new();
+        for message in conversation.iter() {
+            for content in &message.content {
+                match content {
+                    MessageContent::ToolRequest(request) => {
+                        summary.tool_calls += 1;
+                        match &request.tool_call {
+                            Ok(tool_call) => {
+                                if let Some(path) = edited_file(tool_call) {
+                                    edits.insert(&request.id, path);
+                                }
+                            }
+                            Err(_) => summary.failed_tool_calls += 1,
+                        }
+                    }
+                    MessageContent::ToolResponse(response) => {
+                        let Ok(contents) = &response.tool_result else {
+                            summary.failed_tool_calls += 1;
+                            continue;
+                        };
+                        if let Some(path) = edits.remove(response.id.as_str()) {
+                            summary.files_changed.insert(path);
+                        }
+                        let results = contents
+                            .iter()
+                            .filter_map(|content| content.as_text())
+                            .filter_map(|text| serde_json::from_str::<Value>(&text.text).ok())
+                            .flat_map(|value| task_results(&value));
+                        for (completed, error) in results {
+                            if completed {
+                                summary.tasks_completed += 1;
+                            } else {
+                                summary.tasks_failed += 1;
+                                summary.task_errors.extend(error);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        summary.final_output = conversation
+            .iter()
+            .rev()
+            .find(|message| message.role == Role::Assistant && !message.as_concat_text().is_empty())
+            .map(|message| message.as_concat_text());
+        summary
+    }
+}
+
+/// One number both runs have
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MetricChange {
+    pub metric: &'static str,
+    pub a: f64,
+    pub b: f64,
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RunDiff {
+    pub a: RunSummary,
+    pub b: RunSummary,
+    pub metrics: Vec<MetricChange>,
+    pub files_only_in_a: Vec<String>,
+    pub files_only_in_b: Vec<String>,
+    /// Task errors of run b that run a didn't have
+    pub new_task_errors: Vec<String>,
+    pub final_output_changed: bool,
+}
+
+impl RunDiff {
+    pub fn new(a: RunSummary, b: RunSummary) -> Self {
+        let mut metrics = Vec::new();
+        let mut metric = |metric: &'static str, a: f64, b: f64| {
+            metrics.push(MetricChange {
+                metric,
+                a,
+                b,
+                change: b - a,
+            })
+        };
+        metric(
+            "tasks_completed",
+            a.tasks_completed as f64,
+            b.tasks_completed as f64,
+        );
+        metric("tasks_failed", a.tasks_failed as f64, b.tasks_failed as f64);
+        metric("tool_calls", a.tool_calls as f64, b.tool_calls as f64);
+        metric(
+            "failed_tool_calls",
+            a.failed_tool_calls as f64,
+            b.failed_tool_calls as f64,
+        );
+        metric(
+            "files_changed",
+            a.files_changed.len() as f64,
+            b.files_changed.len() as f64,
+        );
+        metric(
+            "duration_secs",
+            a.duration_secs as f64,
+            b.duration_secs as f64,
+        );
+        metric("input_tokens", a.input_tokens as f64, b.input_tokens as f64);
+        metric(
+            "output_tokens",
+            a.output_tokens as f64,
+            b.output_tokens as f64,
+        );
+        metric("total_tokens", a.total_tokens as f64, b.total_tokens as f64);
+        if let (Some(cost_a), Some(cost_b)) = (a.cost_usd, b.cost_usd) {
+            metric("cost_usd", cost_a, cost_b);
+        }
+
+        Self {
+            files_only_in_a: a
+                .files_changed
+                .difference(&b.files_changed)
+                .cloned()
+                .collect(),
+            files_only_in_b: b
+                .files_changed
+                .difference(&a.files_changed)
+                .cloned()
+                .collect(),
+            new_task_errors: b
+                .task_errors
+                .iter()
+                .filter(|error| !a.task_errors.contains(error))
+                .cloned()
+                .collect(),
+            final_output_changed: a.final_output != b.final_output,
+            metrics,
+            a,
+            b,
+        }
+    }
+
+    /// Whether run b did worse than run a: more failed tasks or tool calls, or new task errors
+    pub fn regressed(&self) -> bool {
+        self.b.tasks_failed > self.a.tasks_failed
+            || self.b.failed_tool_calls > self.a.failed_tool_calls
+            || !self.new_task_errors.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::Message;
+    use crate::conversation::Conversation;
+    use rmcp::model::{Content, ErrorCode, ErrorData};
+    use serde_json::json;
+
+    fn edit(path: &str) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: "developer__text_editor".into(),
+            arguments: json!({"command": "str_replace", "path": path})
+                .as_object()
+                .cloned(),
+        }
+    }
+
+    fn run(id: &str, messages: Vec<Message>) -> Session {
+        Session {
+            id: id.to_string(),
+            accumulated_total_tokens: Some(1000),
+            conversation: Some(Conversation::new_unvalidated(messages)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_session() {
+        let tasks = json!({
+            "status": "completed",
+            "results": [
+                {"task_id": "1", "status": "Completed"},
+                {"task_id": "2", "status": "Failed", "error": "tests failed"}
+            ]
+        });
+        let session = run(
+            "a",
+            vec![
+                Message::user().with_text("upgrade the dependencies"),
+                Message::assistant()
+                    .with_tool_request("1", Ok(edit("Cargo.toml")))
+                    .with_tool_request("2", Ok(edit("README.md"))),
+                Message::user()
+                    .with_tool_response("1", Ok(vec![Content::text("ok")]))
+                    .with_tool_response(
+                        "2",
+                        Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, "no match", None)),
+                    ),
+                Message::user().with_tool_response("3", Ok(vec![Content::text(tasks.to_string())])),
+                Message::assistant().with_text("Upgraded 3 crates"),
+            ],
+        );
+        let summary = RunSummary::from_session(&session);
+        assert_eq!(summary.tool_calls, 2);
+        assert_eq!(summary.failed_tool_calls, 1);
+        assert_eq!(summary.tasks_completed, 1);
+        assert_eq!(summary.tasks_failed, 1);
+        assert_eq!(summary.task_errors, vec!["tests failed".to_string()]);
+        assert_eq!(
+            summary.files_changed.into_iter().collect::<Vec<_>>(),
+            vec!["Cargo.toml".to_string()]
+        );
+        assert_eq!(summary.total_tokens, 1000);
+        assert_eq!(summary.final_output.as_deref(), Some("Upgraded 3 crates"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = RunSummary {
+            files_changed: BTreeSet::from(["Cargo.toml".to_string(), "Cargo.lock".to_string()]),
+            total_tokens: 1000,
+            final_output: Some("Upgraded 3 crates".to_string()),
+            ..Default::default()
+        };
+        let b = RunSummary {
+            files_changed: BTreeSet::from(["Cargo.toml".to_string()]),
+            total_tokens: 1500,
+            tasks_failed: 1,
+            task_errors: vec!["tests failed".to_string()],
+            final_output: Some("Could not upgrade serde".to_string()),
+            ..Default::default()
+        };
+        let diff = RunDiff::new(a, b);
+        assert_eq!(diff.files_only_in_a, vec!["Cargo.lock".to_string()]);
+        assert!(diff.files_only_in_b.is_empty());
+        assert_eq!(diff.new_task_errors, vec!["tests failed".to_string()]);
+        assert!(diff.final_output_changed);
+        assert!(diff.regressed());
+        let tokens = diff
+            .metrics
+            .iter()
+            .find(|metric| metric.metric == "total_tokens")
+            .unwrap();
+        assert_eq!(tokens.change, 500.0);
+        assert!(!diff
+            .metrics
+            .iter()
+            .any(|metric| metric.metric == "cost_usd"));
+
+        assert!(!RunDiff::new(RunSummary::default(), RunSummary::default()).regressed());
+    }
+}
@@ -3,6 +3,7 @@ use crate::conversation::message::Message;
 use crate::conversation::Conversation;
 use crate::providers::base::{Provider, MSG_COUNT_FOR_SESSION_NAME_GENERATION};
 use crate::recipe::Recipe;
+use crate::session::compression::{self, CompressionReport, SessionSize};
 use crate::session::extension_data::ExtensionData;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
@@ -11,6 +12,7 @@ use rmcp::model::Role;
 use serde::{Deserialize, Serialize};
 use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -18,7 +20,12 @@ use tokio::sync::OnceCell;
 use tracing::{info, warn};
 use utoipa::ToSchema;
 
-const CURRENT_SCHEMA_VERSION: i32 = 1;
+const CURRENT_SCHEMA_VERSION: i32 = 3;
+
+const INSERT_MESSAGE: &str = r#"
+    INSERT INTO messages (session_id, role, content_json, content_zstd, content_size, created_timestamp)
+    VALUES (?, ?, ?, ?, ?, ?)
+"#;
 
 static SESSION_STORAGE: OnceCell<Arc<SessionStorage>> = OnceCell::const_new();
 
@@ -34,9 +41,15 @@ pub struct Session {
     pub total_tokens: Option<i32>,
     pub input_tokens: Option<i32>,
     pub output_tokens: Option<i32>,
+    /// Part of input_tokens served from the provider's prompt cache
+    pub cached_input_tokens: Option<i32>,
+    /// Part of output_tokens spent on reasoning
+    pub reasoning_tokens: Option<i32>,
     pub accumulated_total_tokens: Option<i32>,
     pub accumulated_input_tokens: Option<i32>,
     pub accumulated_output_tokens: Option<i32>,
+    pub accumulated_cached_input_tokens: Option<i32>,
+    pub accumulated_reasoning_tokens: Option<i32>,
     pub schedule_id: Option<String>,
     pub recipe: Option<Recipe>,
     pub conversation: Option<Conversation>,
@@ -51,9 +64,13 @@ pub struct SessionUpdateBuilder {
     total_tokens: Option<Option<i32>>,
     input_tokens: Option<Option<i32>>,
     output_tokens: Option<Option<i32>>,
+    cached_input_tokens: Option<Option<i32>>,
+    reasoning_tokens: Option<Option<i32>>,
     accumulated_total_tokens: Option<Option<i32>>,
     accumulated_input_tokens: Option<Option<i32>>,
     accumulated_output_tokens: Option<Option<i32>>,
+    accumulated_cached_input_tokens: Option<Option<i32>>,
+    accumulated_reasoning_tokens: Option<Option<i32>>,
     schedule_id: Option<Option<String>>,
     recipe: Option<Option<Recipe>>,
 }
@@ -77,9 +94,13 @@ impl SessionUpdateBuilder {
             total_tokens: None,
             input_tokens: None,
             output_tokens: None,
+            cached_input_tokens: None,
+            reasoning_tokens: None,
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            accumulated_cached_input_tokens: None,
+            accumulated_reasoning_tokens: None,
             schedule_id: None,
             recipe: None,
         }
@@ -115,6 +136,16 @@ impl SessionUpdateBuilder {
         self
     }
 
+    pub fn cached_input_tokens(mut self, tokens: Option<i32>) -> Self {
+        self.cached_input_tokens = Some(tokens);
+        self
+    }
+
+    pub fn reasoning_tokens(mut self, tokens: Option<i32>) -> Self {
+        self.reasoning_tokens = Some(tokens);
+        self
+    }
+
     pub fn accumulated_total_tokens(mut self, tokens: Option<i32>) -> Self {
         self.accumulated_total_tokens = Some(tokens);
         self
@@ -130,6 +161,16 @@ impl SessionUpdateBuilder {
         self
     }
 
+    pub fn accumulated_cached_input_tokens(mut self, tokens: Option<i32>) -> Self {
+        self.accumulated_cached_input_tokens = Some(tokens);
+        self
+    }
+
+    pub fn accumulated_reasoning_tokens(mut self, tokens: Option<i32>) -> Self {
+        self.accumulated_reasoning_tokens = Some(tokens);
+        self
+    }
+
     pub fn schedule_id(mut self, schedule_id: Option<String>) -> Self {
         self.schedule_id = Some(schedule_id);
         self
@@ -200,6 +241,16 @@ impl SessionManager {
         Self::instance().await?.get_insights().await
     }
 
+    /// How much room each session's messages take, by session id
+    pub async fn session_sizes() -> Result<HashMap<String, SessionSize>> {
+        Self::instance().await?.session_sizes().await
+    }
+
+    /// Compress the messages stored before compression was on, and give the space back
+    pub async fn compress_stored_messages() -> Result<CompressionReport> {
+        Self::instance().await?.compress_stored_messages().await
+    }
+
     pub async fn maybe_update_description(id: &str, provider: Arc<dyn Provider>) -> Result<()> {
         let session = Self::get_session(id, true).await?;
         let conversation = session
@@ -260,9 +311,13 @@ impl Default for Session {
             total_tokens: None,
             input_tokens: None,
             output_tokens: None,
+            cached_input_tokens: None,
+            reasoning_tokens: None,
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            accumulated_cached_input_tokens: None,
+            accumulated_reasoning_tokens: None,
             schedule_id: None,
             recipe: None,
             conversation: None,
@@ -296,9 +351,13 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
             total_tokens: row.try_get("total_tokens")?,
             input_tokens: row.try_get("input_tokens")?,
             output_tokens: row.try_get("output_tokens")?,
+            cached_input_tokens: row.try_get("cached_input_tokens")?,
+            reasoning_tokens: row.try_get("reasoning_tokens")?,
             accumulated_total_tokens: row.try_get("accumulated_total_tokens")?,
             accumulated_input_tokens: row.try_get("accumulated_input_tokens")?,
             accumulated_output_tokens: row.try_get("accumulated_output_tokens")?,
+            accumulated_cached_input_tokens: row.try_get("accumulated_cached_input_tokens")?,
+            accumulated_reasoning_tokens: row.try_get("accumulated_reasoning_tokens")?,
             schedule_id: row.try_get("schedule_id")?,
             recipe,
             conversation: None,
@@ -382,9 +441,13 @@ impl SessionStorage {
                 total_tokens INTEGER,
                 input_tokens INTEGER,
                 output_tokens INTEGER,
+                cached_input_tokens INTEGER,
+                reasoning_tokens INTEGER,
                 accumulated_total_tokens INTEGER,
                 accumulated_input_tokens INTEGER,
                 accumulated_output_tokens INTEGER,
+                accumulated_cached_input_tokens INTEGER,
+                accumulated_reasoning_tokens INTEGER,
                 schedule_id TEXT,
                 recipe_json TEXT
             )
@@ -400,6 +463,8 @@ impl SessionStorage {
                 session_id TEXT NOT NULL REFERENCES sessions(id),
                 role TEXT NOT NULL,
                 content_json TEXT NOT NULL,
+                content_zstd BLOB,
+                content_size INTEGER,
                 created_timestamp INTEGER NOT NULL,
                 timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
                 tokens INTEGER
@@ -476,10 +541,11 @@ impl SessionStorage {
             r#"
         INSERT INTO sessions (
             id, description, working_dir, created_at, updated_at, extension_data,
-            total_tokens, input_tokens, output_tokens,
+            total_tokens, input_tokens, output_tokens, cached_input_tokens, reasoning_tokens,
             accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
+            accumulated_cached_input_tokens, accumulated_reasoning_tokens,
             schedule_id, recipe_json
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         )
         .bind(&session.id)
@@ -491,9 +557,13 @@ impl SessionStorage {
         .bind(session.total_tokens)
         .bind(session.input_tokens)
         .bind(session.output_tokens)
+        .bind(session.cached_input_tokens)
+        .bind(session.reasoning_tokens)
         .bind(session.accumulated_total_tokens)
         .bind(session.accumulated_input_tokens)
         .bind(session.accumulated_output_tokens)
+        .bind(session.accumulated_cached_input_tokens)
+        .bind(session.accumulated_reasoning_tokens)
         .bind(&session.schedule_id)
         .bind(recipe_json)
         .execute(&self.pool)
@@ -572,6 +642,30 @@ impl SessionStorage {
                 .execute(&self.pool)
                 .await?;
             }
+            2 => {
+                for column in [
+                    "cached_input_tokens",
+                    "reasoning_tokens",
+                    "accumulated_cached_input_tokens",
+                    "accumulated_reasoning_tokens",
+                ] {
+                    sqlx::query(&format!(
+                        "ALTER TABLE sessions ADD COLUMN {} INTEGER",
+                        column
+                    ))
+                    .execute(&self.pool)
+                    .await?;
+                }
+            }
+            3 => {
+                // Compressed content goes in content_zstd, leaving content_json empty
+                sqlx::query("ALTER TABLE messages ADD COLUMN content_zstd BLOB")
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query("ALTER TABLE messages ADD COLUMN content_size INTEGER")
+                    .execute(&self.pool)
+                    .await?;
+            }
             _ => {
                 anyhow::bail!("Unknown migration version: {}", version);
             }
@@ -610,8 +704,9 @@ impl SessionStorage {
         let mut session = sqlx::query_as::<_, Session>(
             r#"
         SELECT id, working_dir, description, created_at, updated_at, extension_data,
-               total_tokens, input_tokens, output_tokens,
+               total_tokens, input_tokens, output_tokens, cached_input_tokens, reasoning_tokens,
                accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
+               accumulated_cached_input_tokens, accumulated_reasoning_tokens,
                schedule_id, recipe_json
         FROM sessions
         WHERE id = ?
@@ -661,12 +756,22 @@ impl SessionStorage {
         add_update!(builder.total_tokens, "total_tokens");
         add_update!(builder.input_tokens, "input_tokens");
         add_update!(builder.output_tokens, "output_tokens");
+        add_update!(builder.cached_input_tokens, "cached_input_tokens");
+        add_update!(builder.reasoning_tokens, "reasoning_tokens");
         add_update!(builder.accumulated_total_tokens, "accumulated_total_tokens");
         add_update!(builder.accumulated_input_tokens, "accumulated_input_tokens");
         add_update!(
             builder.accumulated_output_tokens,
             "accumulated_output_tokens"
         );
+        add_update!(
+            builder.accumulated_cached_input_tokens,
+            "accumulated_cached_input_tokens"
+        );
+        add_update!(
+            builder.accumulated_reasoning_tokens,
+            "accumulated_reasoning_tokens"
+        );
         add_update!(builder.schedule_id, "schedule_id");
         add_update!(builder.recipe, "recipe_json");
 
@@ -699,6 +804,12 @@ impl SessionStorage {
         if let Some(ot) = builder.output_tokens {
             q = q.bind(ot);
         }
+        if let Some(cit) = builder.cached_input_tokens {
+            q = q.bind(cit);
+        }
+        if let Some(rt) = builder.reasoning_tokens {
+            q = q.bind(rt);
+        }
         if let Some(att) = builder.accumulated_total_tokens {
             q = q.bind(att);
         }
@@ -708,6 +819,12 @@ impl SessionStorage {
         if let Some(aot) = builder.accumulated_output_tokens {
             q = q.bind(aot);
         }
+        if let Some(acit) = builder.accumulated_cached_input_tokens {
+            q = q.bind(acit);
+        }
+        if let Some(art) = builder.accumulated_reasoning_tokens {
+            q = q.bind(art);
+        }
         if let Some(sid) = builder.schedule_id {
             q = q.bind(sid);
         }
@@ -723,21 +840,22 @@ impl SessionStorage {
     }
 
     async fn get_conversation(&self, session_id: &str) -> Result<Conversation> {
-        let rows = sqlx::query_as::<_, (String, String, i64)>(
-            "SELECT role, content_json, created_timestamp FROM messages WHERE session_id = ? ORDER BY timestamp",
+        let rows = sqlx::query_as::<_, (String, String, Option<Vec<u8>>, i64)>(
+            "SELECT role, content_json, content_zstd, created_timestamp FROM messages WHERE session_id = ? ORDER BY timestamp",
         )
             .bind(session_id)
             .fetch_all(&self.pool)
             .await?;
 
         let mut messages = Vec::new();
-        for (role_str, content_json, created_timestamp) in rows {
+        for (role_str, content_json, content_zstd, created_timestamp) in rows {
             let role = match role_str.as_str() {
                 "user" => Role::User,
                 "assistant" => Role::Assistant,
                 _ => continue,
             };
 
+            let content_json = compression::decode(content_json, content_zstd)?;
             let content = serde_json::from_str(&content_json)?;
             let message = Message::new(role, created_timestamp, content);
             messages.push(message);
@@ -747,18 +865,19 @@ impl SessionStorage {
     }
 
     async fn add_message(&self, session_id: &str, message: &Message) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO messages (session_id, role, content_json, created_timestamp)
-            VALUES (?, ?, ?, ?)
-        "#,
-        )
-        .bind(session_id)
-        .bind(role_to_string(&message.role))
-        .bind(serde_json::to_string(&message.content)?)
-        .bind(message.created)
-        .execute(&self.pool)
-        .await?;
+        let stored = compression::encode(
+            serde_json::to_string(&message.content)?,
+            compression::is_enabled(),
+        );
+        sqlx::query(INSERT_MESSAGE)
+            .bind(session_id)
+            .bind(role_to_string(&message.role))
+            .bind(stored.content_json)
+            .bind(stored.content_zstd)
+            .bind(stored.content_size)
+            .bind(message.created)
+            .execute(&self.pool)
+            .await?;
 
         sqlx::query("UPDATE sessions SET updated_at = datetime('now') WHERE id = ?")
             .bind(session_id)
@@ -780,19 +899,18 @@ impl SessionStorage {
             .execute(&mut *tx)
             .await?;
 
+        let compress = compression::is_enabled();
         for message in conversation.messages() {
-            sqlx::query(
-                r#"
-            INSERT INTO messages (session_id, role, content_json, created_timestamp)
-            VALUES (?, ?, ?, ?)
-        "#,
-            )
-            .bind(session_id)
-            .bind(role_to_string(&message.role))
-            .bind(serde_json::to_string(&message.content)?)
-            .bind(message.created)
-            .execute(&mut *tx)
-            .await?;
+            let stored = compression::encode(serde_json::to_string(&message.content)?, compress);
+            sqlx::query(INSERT_MESSAGE)
+                .bind(session_id)
+                .bind(role_to_string(&message.role))
+                .bind(stored.content_json)
+                .bind(stored.content_zstd)
+                .bind(stored.content_size)
+                .bind(message.created)
+                .execute(&mut *tx)
+                .await?;
         }
 
         tx.commit().await?;
@@ -804,7 +922,9 @@ impl SessionStorage {
             r#"
         SELECT s.id, s.working_dir, s.description, s.created_at, s.updated_at, s.extension_data,
                s.total_tokens, s.input_tokens, s.output_tokens,
+               s.cached_input_tokens, s.reasoning_tokens,
                s.accumulated_total_tokens, s.accumulated_input_tokens, s.accumulated_output_tokens,
+               s.accumulated_cached_input_tokens, s.accumulated_reasoning_tokens,
                s.schedule_id, s.recipe_json,
                COUNT(m.id) as message_count
         FROM sessions s
@@ -858,6 +978,68 @@ impl SessionStorage {
             total_tokens: row.1.unwrap_or(0),
         })
     }
+
+    async fn session_sizes(&self) -> Result<HashMap<String, SessionSize>> {
+        let rows = sqlx::query_as::<_, (String, i64, i64)>(
+            r#"
+            SELECT session_id,
+                   SUM(COALESCE(LENGTH(content_zstd), LENGTH(CAST(content_json AS BLOB)))),
+                   SUM(COALESCE(content_size, LENGTH(CAST(content_json AS BLOB))))
+            FROM messages
+            GROUP BY session_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(session_id, stored, content)| {
+                let size = SessionSize {
+                    stored_bytes: stored.max(0) as u64,
+                    content_bytes: content.max(0) as u64,
+                };
+                (session_id, size)
+            })
+            .collect())
+    }
+
+    async fn compress_stored_messages(&self) -> Result<CompressionReport> {
+        let rows = sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, content_json FROM messages WHERE content_zstd IS NULL AND LENGTH(CAST(content_json AS BLOB)) >= ?",
+        )
+        .bind(compression::MIN_COMPRESSED_BYTES as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut report = CompressionReport::default();
+        let mut tx = self.pool.begin().await?;
+        for (id, content_json) in rows {
+            let before = content_json.len() as u64;
+            let stored = compression::encode(content_json, true);
+            let Some(compressed) = stored.content_zstd else {
+                continue;
+            };
+            report.messages_compressed += 1;
+            report.bytes_before += before;
+            report.bytes_after += compressed.len() as u64;
+            sqlx::query(
+                "UPDATE messages SET content_json = '', content_zstd = ?, content_size = ? WHERE id = ?",
+            )
+            .bind(compressed)
+            .bind(stored.content_size)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        // Freed pages stay in the file until it is rebuilt
+        if report.messages_compressed > 0 {
+            sqlx::query("VACUUM").execute(&self.pool).await?;
+        }
+        Ok(report)
+    }
 }
 
 #[cfg(test)]
@@ -960,4 +1142,45 @@ mod tests {
         let expected_tokens = 100 * NUM_CONCURRENT_SESSIONS * (NUM_CONCURRENT_SESSIONS - 1) / 2;
         assert_eq!(insights.total_tokens, expected_tokens as i64);
     }
+
+    #[tokio::test]
+    async fn test_compressed_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = SessionStorage::create(&temp_dir.path().join("sessions.db"))
+            .await
+            .unwrap();
+        let session = storage
+            .create_session(PathBuf::from("/tmp"), "big output".to_string())
+            .await
+            .unwrap();
+        let big = Message::user().with_text("build log line\n".repeat(2000));
+        storage.add_message(&session.id, &big).await.unwrap();
+
+        // A message stored before compression, straight into the table
+        let plain = serde_json::to_string(&big.content).unwrap();
+        sqlx::query(INSERT_MESSAGE)
+            .bind(&session.id)
+            .bind("assistant")
+            .bind(&plain)
+            .bind(None::<Vec<u8>>)
+            .bind(None::<i64>)
+            .bind(big.created)
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+
+        let report = storage.compress_stored_messages().await.unwrap();
+        assert_eq!(report.messages_compressed, 1);
+        assert!(report.bytes_after < report.bytes_before);
+
+        let size = storage.session_sizes().await.unwrap()[&session.id];
+        assert_eq!(size.content_bytes, 2 * plain.len() as u64);
+        assert!(size.stored_bytes < size.content_bytes / 10);
+
+        let conversation = storage.get_conversation(&session.id).await.unwrap();
+        assert_eq!(conversation.messages().len(), 2);
+        for message in conversation.messages() {
+            assert_eq!(message.content, big.content);
+        }
+    }
 }
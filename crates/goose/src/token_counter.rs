@@ -29,6 +29,21 @@ pub struct TokenCounter {
     tokenizer: Arc<CoreBPE>,
 }
 
+/// Token usage of a request split by where the tokens come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextBreakdown {
+    pub system_prompt: usize,
+    pub tool_schemas: usize,
+    pub conversation: usize,
+    pub tool_results: usize,
+}
+
+impl ContextBreakdown {
+    pub fn total(&self) -> usize {
+        self.system_prompt + self.tool_schemas + self.conversation + self.tool_results
+    }
+}
+
 impl AsyncTokenCounter {
     pub async fn new() -> Result<Self, String> {
         let tokenizer = get_tokenizer().await?;
@@ -174,6 +189,46 @@ impl AsyncTokenCounter {
         num_tokens
     }
 
+    /// Same accounting as `count_chat_tokens`, split into categories. Per-message overhead and
+    /// the reply primer are attributed to the conversation.
+    pub fn count_chat_tokens_by_category(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> ContextBreakdown {
+        let tokens_per_message = 4;
+        let mut breakdown = ContextBreakdown::default();
+
+        if !system_prompt.is_empty() {
+            breakdown.system_prompt = self.count_tokens(system_prompt) + tokens_per_message;
+        }
+
+        for message in messages {
+            breakdown.conversation += tokens_per_message;
+            for content in &message.content {
+                if let Some(content_text) = content.as_text() {
+                    breakdown.conversation += self.count_tokens(content_text);
+                } else if let Some(tool_request) = content.as_tool_request() {
+                    if let Ok(tool_call) = tool_request.tool_call.as_ref() {
+                        let text = format!(
+                            "{}:{}:{:?}",
+                            tool_request.id, tool_call.name, tool_call.arguments
+                        );
+                        breakdown.conversation += self.count_tokens(&text);
+                    }
+                } else if let Some(tool_response_text) = content.as_tool_response_text() {
+                    breakdown.tool_results += self.count_tokens(&tool_response_text);
+                }
+            }
+        }
+
+        breakdown.tool_schemas = self.count_tokens_for_tools(tools);
+        breakdown.conversation += 3; // Reply primer
+
+        breakdown
+    }
+
     pub fn clear_cache(&self) {
         self.token_cache.clear();
     }
@@ -415,6 +470,37 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_count_chat_tokens_by_category() {
+        use crate::conversation::message::ToolResponse;
+        use rmcp::model::Content;
+
+        let counter = create_async_token_counter().await.unwrap();
+        let system_prompt = "You are a helpful assistant.";
+        let messages = vec![
+            Message::user().with_text("List the files here"),
+            Message::user().with_content(MessageContent::ToolResponse(ToolResponse {
+                id: "1".to_string(),
+                tool_result: Ok(vec![Content::text("Cargo.toml\nsrc\ntarget")]),
+            })),
+        ];
+        let tools = vec![Tool::new(
+            "list_files",
+            "List the files in a directory",
+            object!({"properties": {"path": {"type": "string"}}}),
+        )];
+
+        let breakdown = counter.count_chat_tokens_by_category(system_prompt, &messages, &tools);
+        assert!(breakdown.system_prompt > 0);
+        assert!(breakdown.tool_schemas > 0);
+        assert!(breakdown.conversation > 0);
+        assert!(breakdown.tool_results > 0);
+        assert_eq!(
+            breakdown.total(),
+            counter.count_chat_tokens(system_prompt, &messages, &tools)
+        );
+    }
+
     #[tokio::test]
     async fn test_async_token_caching() {
         let counter = create_async_token_counter().await.unwrap();
@@ -1,8 +1,9 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::conversation::message::{Message, ToolRequest};
+use crate::permission::annotation_inspector::{AnnotatedTools, AnnotationInspector};
 use crate::permission::permission_inspector::PermissionInspector;
 use crate::permission::permission_judge::PermissionCheckResult;
 
@@ -131,6 +132,34 @@ impl ToolInspectionManager {
         tracing::warn!("Permission inspector not found for mode update");
     }
 
+    /// Share the current tool annotations with the annotation and permission inspectors
+    pub async fn update_tool_annotations(&self, annotated: AnnotatedTools) {
+        for inspector in &self.inspectors {
+            let inspector = inspector.as_any();
+            if let Some(permission_inspector) = inspector.downcast_ref::<PermissionInspector>() {
+                permission_inspector
+                    .update_annotated_readonly_tools(annotated.read_only.clone())
+                    .await;
+            } else if let Some(annotation_inspector) =
+                inspector.downcast_ref::<AnnotationInspector>()
+            {
+                annotation_inspector.update_tools(annotated.clone()).await;
+            }
+        }
+    }
+
+    /// Tools that will require approval because they are annotated as destructive
+    pub async fn destructive_tools(&self) -> HashSet<String> {
+        for inspector in &self.inspectors {
+            if let Some(annotation_inspector) =
+                inspector.as_any().downcast_ref::<AnnotationInspector>()
+            {
+                return annotation_inspector.destructive_tools().await;
+            }
+        }
+        HashSet::new()
+    }
+
     /// Update the permission manager for a specific tool
     pub async fn update_permission_manager(
         &self,
@@ -1125,6 +1125,7 @@ mod max_turns_tests {
                             goose::permission::PermissionConfirmation {
                                 principal_type: goose::permission::permission_confirmation::PrincipalType::Tool,
                                 permission: goose::permission::Permission::AllowOnce,
+                                arguments: None,
                             }
                         ).await;
                     }
@@ -122,6 +122,63 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_create_tasks_with_dependencies() {
+        use goose::agents::subagent_execution_tool::tasks_manager::TasksManager;
+
+        let tasks_manager = TasksManager::new();
+        let params = json!({
+            "task_parameters": [
+                {"instructions": "Fetch the data"},
+                {"instructions": "Summarize the data", "depends_on": [0]}
+            ]
+        });
+
+        let result = create_dynamic_task(params, &tasks_manager, test_loaded_extensions()).await;
+        let contents = result.result.await.unwrap();
+        let text_content = contents.first().and_then(|c| c.as_text()).unwrap();
+        let task_payload: serde_json::Value = serde_json::from_str(&text_content.text).unwrap();
+        let task_ids: Vec<String> =
+            serde_json::from_value(task_payload["task_ids"].clone()).unwrap();
+
+        let summarize = tasks_manager.get_task(&task_ids[1]).await.unwrap();
+        assert_eq!(summarize.depends_on, vec![task_ids[0].clone()]);
+
+        let params = json!({
+            "task_parameters": [{"instructions": "Task 1", "depends_on": [0]}]
+        });
+        let result = create_dynamic_task(params, &tasks_manager, test_loaded_extensions()).await;
+        assert!(result.result.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_tasks_with_timeout() {
+        use goose::agents::subagent_execution_tool::task_types::OnTimeout;
+        use goose::agents::subagent_execution_tool::tasks_manager::TasksManager;
+
+        let tasks_manager = TasksManager::new();
+        let params = json!({
+            "task_parameters": [
+                {"instructions": "Crawl the docs", "timeout_seconds": 300, "on_timeout": "wrap_up"},
+                {"instructions": "Lint the code"}
+            ]
+        });
+
+        let result = create_dynamic_task(params, &tasks_manager, test_loaded_extensions()).await;
+        let contents = result.result.await.unwrap();
+        let text_content = contents.first().and_then(|c| c.as_text()).unwrap();
+        let task_payload: serde_json::Value = serde_json::from_str(&text_content.text).unwrap();
+        let task_ids: Vec<String> =
+            serde_json::from_value(task_payload["task_ids"].clone()).unwrap();
+
+        let crawl = tasks_manager.get_task(&task_ids[0]).await.unwrap();
+        assert_eq!(crawl.timeout_seconds, Some(300));
+        assert_eq!(crawl.on_timeout, OnTimeout::WrapUp);
+        let lint = tasks_manager.get_task(&task_ids[1]).await.unwrap();
+        assert_eq!(lint.timeout_seconds, None);
+        assert_eq!(lint.on_timeout, OnTimeout::Kill);
+    }
+
     #[test]
     fn test_return_last_only_flag() {
         let params_with_flag = json!({
@@ -1,4 +1,4 @@
-use goose::agents::subagent_execution_tool::task_types::{Task, TaskType};
+use goose::agents::subagent_execution_tool::task_types::{FailurePolicy, Task, TaskType};
 use serde_json::json;
 
 #[test]
@@ -33,6 +33,10 @@ fn test_task_serialization_with_enum() {
         id: "test-id".to_string(),
         task_type: TaskType::InlineRecipe,
         payload: json!({"recipe": "test"}),
+        depends_on: Vec::new(),
+        timeout_seconds: None,
+        on_timeout: Default::default(),
+        priority: Default::default(),
     };
 
     let serialized = serde_json::to_value(&task).unwrap();
@@ -71,9 +75,14 @@ fn test_task_methods_with_sub_recipe() {
                 "name": "test_recipe",
                 "recipe_path": "/path/to/recipe",
                 "command_parameters": {"key": "value"},
-                "sequential_when_repeated": true
+                "sequential_when_repeated": true,
+                "artifacts": ["report.md", "coverage"]
             }
         }),
+        depends_on: Vec::new(),
+        timeout_seconds: None,
+        on_timeout: Default::default(),
+        priority: Default::default(),
     };
 
     assert!(task.get_sub_recipe().is_some());
@@ -81,6 +90,7 @@ fn test_task_methods_with_sub_recipe() {
     assert_eq!(task.get_sub_recipe_path(), Some("/path/to/recipe"));
     assert!(task.get_command_parameters().is_some());
     assert!(task.get_sequential_when_repeated());
+    assert_eq!(task.get_artifacts(), ["report.md", "coverage"]);
 }
 
 #[test]
@@ -92,8 +102,13 @@ fn test_task_methods_with_inline_recipe() {
             "recipe": {
                 "instructions": "Test instructions"
             },
-            "return_last_only": true
+            "return_last_only": true,
+            "artifacts": null
         }),
+        depends_on: Vec::new(),
+        timeout_seconds: None,
+        on_timeout: Default::default(),
+        priority: Default::default(),
     };
 
     assert!(task.get_sub_recipe().is_none());
@@ -101,6 +116,7 @@ fn test_task_methods_with_inline_recipe() {
     assert!(task.get_sub_recipe_path().is_none());
     assert!(task.get_command_parameters().is_none());
     assert!(!task.get_sequential_when_repeated());
+    assert!(task.get_artifacts().is_empty());
 }
 
 #[test]
@@ -116,6 +132,10 @@ fn test_task_with_missing_fields() {
         id: "test-4".to_string(),
         task_type: TaskType::SubRecipe,
         payload: json!({}), // Missing sub_recipe field
+        depends_on: Vec::new(),
+        timeout_seconds: None,
+        on_timeout: Default::default(),
+        priority: Default::default(),
     };
 
     assert!(task.get_sub_recipe().is_none());
@@ -124,3 +144,33 @@ fn test_task_with_missing_fields() {
     assert!(task.get_command_parameters().is_none());
     assert!(!task.get_sequential_when_repeated());
 }
+
+#[test]
+fn test_failure_policy() {
+    assert_eq!(
+        FailurePolicy::parse("continue"),
+        Ok(FailurePolicy::Continue)
+    );
+    assert_eq!(
+        FailurePolicy::parse("fail_fast"),
+        Ok(FailurePolicy::FailFast)
+    );
+    assert_eq!(
+        FailurePolicy::parse("threshold:3"),
+        Ok(FailurePolicy::Threshold(3))
+    );
+    assert!(FailurePolicy::parse("threshold:0").is_err());
+    assert!(FailurePolicy::parse("stop").is_err());
+
+    assert!(!FailurePolicy::Continue.stops_after(10));
+    assert!(FailurePolicy::FailFast.stops_after(1));
+    assert!(!FailurePolicy::Threshold(3).stops_after(2));
+    assert!(FailurePolicy::Threshold(3).stops_after(3));
+
+    let policy = FailurePolicy::Threshold(2);
+    assert_eq!(serde_json::to_value(policy).unwrap(), json!("threshold:2"));
+    assert_eq!(
+        serde_json::from_value::<FailurePolicy>(json!("threshold:2")).unwrap(),
+        policy
+    );
+}
@@ -388,9 +388,13 @@ pub fn create_test_session_metadata(message_count: usize, working_dir: &str) ->
         total_tokens: Some(100),
         input_tokens: Some(50),
         output_tokens: Some(50),
+        cached_input_tokens: None,
+        reasoning_tokens: None,
         accumulated_total_tokens: Some(100),
         accumulated_input_tokens: Some(50),
         accumulated_output_tokens: Some(50),
+        accumulated_cached_input_tokens: None,
+        accumulated_reasoning_tokens: None,
         extension_data: Default::default(),
         updated_at: Default::default(),
         conversation: None,